@@ -3,7 +3,7 @@ use std::collections::HashSet;
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    to_json_binary, Binary, Deps, DepsMut, Env, Event, MessageInfo, Order, Reply, Response,
+    to_json_binary, Addr, Binary, Deps, DepsMut, Env, Event, MessageInfo, Order, Reply, Response,
     StdError, StdResult, SubMsg, SubMsgResponse, SubMsgResult, WasmMsg,
 };
 use cw2::set_contract_version;
@@ -14,16 +14,16 @@ use itertools::Itertools;
 use astroport::asset::{addr_opt_validate, AssetInfo, PairInfo};
 use astroport::common::{claim_ownership, drop_ownership_proposal, propose_new_owner};
 use astroport::factory::{
-    Config, ConfigResponse, ExecuteMsg, FeeInfoResponse, InstantiateMsg, PairConfig, PairType,
-    QueryMsg,
+    Config, ConfigResponse, ExecuteMsg, FeeInfoResponse, InstantiateMsg, PairConfig, PairFees,
+    PairType, PauseScope, PauseStatusResponse, QueryMsg,
 };
 use astroport::pair;
 use astroport::pair::InstantiateMsg as PairInstantiateMsg;
 
 use crate::error::ContractError;
 use crate::state::{
-    check_asset_infos, get_pairs_index, pair_key, CONFIG, DEFAULT_LIMIT, OWNERSHIP_PROPOSAL,
-    PAIR_CONFIGS,
+    check_asset_infos, get_pairs_index, pair_key, CONFIG, DEFAULT_LIMIT, GLOBAL_PAUSE,
+    OWNERSHIP_PROPOSAL, PAIR_CONFIGS, PAIR_FEES, PAIR_PAUSES,
 };
 
 /// Contract name that is used for migration.
@@ -51,6 +51,7 @@ pub fn instantiate(
         fee_address: None,
         incentives_address: None,
         coin_registry_address: deps.api.addr_validate(&msg.coin_registry_address)?,
+        guardian: None,
     };
 
     config.incentives_address = addr_opt_validate(deps.api, &msg.incentives_address)?;
@@ -95,19 +96,19 @@ pub struct UpdateConfig {
 ///
 /// ## Variants
 /// * **ExecuteMsg::UpdateConfig {
-///             token_code_id,
-///             fee_address,
-///             generator_address,
-///         }** Updates general contract parameters.
+///   token_code_id,
+///   fee_address,
+///   generator_address,
+///   }** Updates general contract parameters.
 ///
 /// * **ExecuteMsg::UpdatePairConfig { config }** Updates a pair type
 /// * configuration or creates a new pair type if a [`Custom`] name is used (which hasn't been used before).
 ///
 /// * **ExecuteMsg::CreatePair {
-///             pair_type,
-///             asset_infos,
-///             init_params,
-///         }** Creates a new pair with the specified input parameters.
+///   pair_type,
+///   asset_infos,
+///   init_params,
+///   }** Creates a new pair with the specified input parameters.
 ///
 /// * **ExecuteMsg::Deregister { asset_infos }** Removes an existing pair from the factory.
 /// * The asset information is for the assets that are traded in the pair.
@@ -117,6 +118,14 @@ pub struct UpdateConfig {
 /// * **ExecuteMsg::DropOwnershipProposal {}** Removes a request to change contract ownership.
 ///
 /// * **ExecuteMsg::ClaimOwnership {}** Claims contract ownership.
+///
+/// * **ExecuteMsg::UpdateGuardian { guardian }** Sets or removes the pause guardian.
+///
+/// * **ExecuteMsg::Pause { pair, scope }** Pauses swaps, provides or both for a pair or for all pairs.
+///
+/// * **ExecuteMsg::Unpause { pair }** Lifts the pause from a pair or the global pause.
+///
+/// * **ExecuteMsg::UpdatePairFees { pair_addr, total_fee_bps, maker_fee_bps }** Overrides the fees of a pair.
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
     deps: DepsMut,
@@ -177,6 +186,22 @@ pub fn execute(
             })
             .map_err(Into::into)
         }
+        ExecuteMsg::UpdateGuardian { guardian } => execute_update_guardian(deps, info, guardian),
+        ExecuteMsg::Pause { pair, scope } => execute_pause(deps, info, pair, scope),
+        ExecuteMsg::Unpause { pair } => execute_unpause(deps, info, pair),
+        ExecuteMsg::UpdatePairFees {
+            pair_addr,
+            total_fee_bps,
+            maker_fee_bps,
+        } => execute_update_pair_fees(
+            deps,
+            info,
+            pair_addr,
+            PairFees {
+                total_fee_bps,
+                maker_fee_bps,
+            },
+        ),
     }
 }
 
@@ -257,6 +282,164 @@ pub fn execute_update_pair_config(
     Ok(Response::new().add_event(event))
 }
 
+/// Sets or removes the guardian which is allowed to pause pairs.
+///
+/// ## Executor
+/// Only the owner can execute this.
+pub fn execute_update_guardian(
+    deps: DepsMut,
+    info: MessageInfo,
+    guardian: Option<String>,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+
+    // Permission check
+    if info.sender != config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    config.guardian = addr_opt_validate(deps.api, &guardian)?;
+    CONFIG.save(deps.storage, &config)?;
+
+    let event = Event::new("update_guardian")
+        .add_attribute("action", "update_guardian")
+        .add_attribute("guardian", guardian.unwrap_or_default());
+
+    Ok(Response::new().add_event(event))
+}
+
+/// Pauses the given scope for a pair or for all pairs if `pair` is `None`.
+/// A new pause replaces the previous one for the same target.
+///
+/// ## Executor
+/// Only the owner or the guardian can execute this.
+pub fn execute_pause(
+    deps: DepsMut,
+    info: MessageInfo,
+    pair: Option<String>,
+    scope: PauseScope,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    // Permission check
+    if info.sender != config.owner && Some(&info.sender) != config.guardian.as_ref() {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let target = match addr_opt_validate(deps.api, &pair)? {
+        Some(pair) => {
+            ensure_pair_registered(deps.as_ref(), &pair)?;
+            PAIR_PAUSES.save(deps.storage, &pair, &scope)?;
+            pair.to_string()
+        }
+        None => {
+            GLOBAL_PAUSE.save(deps.storage, &scope)?;
+            "all".to_string()
+        }
+    };
+
+    let event = Event::new("pause")
+        .add_attribute("action", "pause")
+        .add_attribute("pair", target)
+        .add_attribute("scope", format!("{scope:?}"));
+
+    Ok(Response::new().add_event(event))
+}
+
+/// Lifts the pause from a pair or the global pause if `pair` is `None`.
+///
+/// ## Executor
+/// Only the owner can execute this.
+pub fn execute_unpause(
+    deps: DepsMut,
+    info: MessageInfo,
+    pair: Option<String>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    // Permission check
+    if info.sender != config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let target = match addr_opt_validate(deps.api, &pair)? {
+        Some(pair) => {
+            PAIR_PAUSES.remove(deps.storage, &pair);
+            pair.to_string()
+        }
+        None => {
+            GLOBAL_PAUSE.remove(deps.storage);
+            "all".to_string()
+        }
+    };
+
+    let event = Event::new("unpause")
+        .add_attribute("action", "unpause")
+        .add_attribute("pair", target);
+
+    Ok(Response::new().add_event(event))
+}
+
+/// Overrides the fees of a pair. The override is removed if both fees are `None`.
+///
+/// ## Executor
+/// Only the owner can execute this.
+pub fn execute_update_pair_fees(
+    deps: DepsMut,
+    info: MessageInfo,
+    pair_addr: String,
+    pair_fees: PairFees,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    // Permission check
+    if info.sender != config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let pair_addr = deps.api.addr_validate(&pair_addr)?;
+    ensure_pair_registered(deps.as_ref(), &pair_addr)?;
+
+    // Validate total and maker fee bps
+    if !pair_fees.valid_fee_bps() {
+        return Err(ContractError::PairConfigInvalidFeeBps {});
+    }
+
+    if pair_fees == PairFees::default() {
+        PAIR_FEES.remove(deps.storage, &pair_addr);
+    } else {
+        PAIR_FEES.save(deps.storage, &pair_addr, &pair_fees)?;
+    }
+
+    let event = Event::new("update_pair_fees")
+        .add_attribute("action", "update_pair_fees")
+        .add_attribute("pair", pair_addr)
+        .add_attribute(
+            "total_fee_bps",
+            pair_fees
+                .total_fee_bps
+                .map(|bps| bps.to_string())
+                .unwrap_or_default(),
+        )
+        .add_attribute(
+            "maker_fee_bps",
+            pair_fees
+                .maker_fee_bps
+                .map(|bps| bps.to_string())
+                .unwrap_or_default(),
+        );
+
+    Ok(Response::new().add_event(event))
+}
+
+fn ensure_pair_registered(deps: Deps, pair: &Addr) -> Result<(), ContractError> {
+    if !get_pairs_index().has(deps.storage, pair.clone()) {
+        return Err(ContractError::PairNotRegistered(pair.to_string()));
+    }
+
+    Ok(())
+}
+
 /// Creates a new pair of `pair_type` with the assets specified in `asset_infos`.
 ///
 /// * **pair_type** is the pair type of the newly created pair.
@@ -300,6 +483,7 @@ pub fn execute_create_pair(
                 token_code_id: config.token_code_id,
                 factory_addr: env.contract.address.to_string(),
                 init_params,
+                native_lp_token: pair_config.native_lp_token,
             })?,
             funds: vec![],
             label: "Astroport pair".to_string(),
@@ -357,9 +541,12 @@ pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractE
 /// * **QueryMsg::Pairs { start_after, limit }** Returns an array that contains items of type [`PairInfo`].
 ///   This returns information about multiple Astroport pairs
 ///
-/// * **QueryMsg::FeeInfo { pair_type }** Returns the fee structure (total and maker fees) for a specific pair type.
+/// * **QueryMsg::FeeInfo { pair_type, pair_addr }** Returns the fee structure (total and maker fees) for a specific pair type
+///   taking fee overrides of the pair into account.
 ///
 /// * **QueryMsg::BlacklistedPairTypes {}** Returns a vector that contains blacklisted pair types (pair types that cannot get ASTRO emissions).
+///
+/// * **QueryMsg::PauseStatus { pair }** Returns the pause status of a pair using a [`PauseStatusResponse`] object.
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
@@ -373,8 +560,12 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
         QueryMsg::Pairs { start_after, limit } => {
             to_json_binary(&query_pairs(deps, start_after, limit)?)
         }
-        QueryMsg::FeeInfo { pair_type } => to_json_binary(&query_fee_info(deps, pair_type)?),
+        QueryMsg::FeeInfo {
+            pair_type,
+            pair_addr,
+        } => to_json_binary(&query_fee_info(deps, pair_type, pair_addr)?),
         QueryMsg::BlacklistedPairTypes {} => to_json_binary(&query_blacklisted_pair_types(deps)?),
+        QueryMsg::PauseStatus { pair } => to_json_binary(&query_pause_status(deps, pair)?),
     }
 }
 
@@ -408,11 +599,25 @@ pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
         fee_address: config.fee_address,
         incentives_address: config.incentives_address,
         coin_registry_address: config.coin_registry_address,
+        guardian: config.guardian,
     };
 
     Ok(resp)
 }
 
+/// Returns the global pause and the pause of the given pair using a [`PauseStatusResponse`] object.
+pub fn query_pause_status(deps: Deps, pair: Option<String>) -> StdResult<PauseStatusResponse> {
+    let pair = match addr_opt_validate(deps.api, &pair)? {
+        Some(pair) => PAIR_PAUSES.may_load(deps.storage, &pair)?,
+        None => None,
+    };
+
+    Ok(PauseStatusResponse {
+        global: GLOBAL_PAUSE.may_load(deps.storage)?,
+        pair,
+    })
+}
+
 /// Returns a vector with pair data that contains items of type [`PairInfo`].
 /// Querying starts at `start_after` and returns `limit` pairs.
 /// * **start_after** is a field which accepts an address [`String`].
@@ -466,13 +671,25 @@ pub fn query_pair_by_lp_token(deps: Deps, lp_token: String) -> StdResult<Binary>
 
 /// Returns the fee setup for a specific pair type using a [`FeeInfoResponse`] struct.
 /// * **pair_type** is a struct that represents the fee information (total and maker fees) for a specific pair type.
-pub fn query_fee_info(deps: Deps, pair_type: PairType) -> StdResult<FeeInfoResponse> {
+///
+/// * **pair_addr** is the pair which fee overrides take precedence over the pair type fees.
+pub fn query_fee_info(
+    deps: Deps,
+    pair_type: PairType,
+    pair_addr: Option<String>,
+) -> StdResult<FeeInfoResponse> {
     let config = CONFIG.load(deps.storage)?;
     let pair_config = PAIR_CONFIGS.load(deps.storage, pair_type.to_string())?;
+    let pair_fees = match addr_opt_validate(deps.api, &pair_addr)? {
+        Some(pair_addr) => PAIR_FEES
+            .may_load(deps.storage, &pair_addr)?
+            .unwrap_or_default(),
+        None => PairFees::default(),
+    };
 
     Ok(FeeInfoResponse {
         fee_address: config.fee_address,
-        total_fee_bps: pair_config.total_fee_bps,
-        maker_fee_bps: pair_config.maker_fee_bps,
+        total_fee_bps: pair_fees.total_fee_bps.unwrap_or(pair_config.total_fee_bps),
+        maker_fee_bps: pair_fees.maker_fee_bps.unwrap_or(pair_config.maker_fee_bps),
     })
 }
@@ -27,4 +27,7 @@ pub enum ContractError {
 
     #[error("Failed to parse or process reply message")]
     FailedToParseReply {},
+
+    #[error("Pair {0} is not registered in the factory")]
+    PairNotRegistered(String),
 }
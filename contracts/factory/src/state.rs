@@ -4,7 +4,7 @@ use itertools::Itertools;
 
 use astroport::asset::{AssetInfo, PairInfo};
 use astroport::common::OwnershipProposal;
-use astroport::factory::{Config, PairConfig};
+use astroport::factory::{Config, PairConfig, PairFees, PauseScope};
 
 use crate::error::ContractError;
 
@@ -61,3 +61,12 @@ pub(crate) fn check_asset_infos(
 
 /// Stores the latest contract ownership transfer proposal
 pub const OWNERSHIP_PROPOSAL: Item<OwnershipProposal> = Item::new("ownership_proposal");
+
+/// Stores the pause applied to all pairs
+pub const GLOBAL_PAUSE: Item<PauseScope> = Item::new("global_pause");
+
+/// Stores the pauses applied to specific pairs
+pub const PAIR_PAUSES: Map<&Addr, PauseScope> = Map::new("pair_pauses");
+
+/// Stores fee overrides of specific pairs
+pub const PAIR_FEES: Map<&Addr, PairFees> = Map::new("pair_fees");
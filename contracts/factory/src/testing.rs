@@ -34,7 +34,11 @@ fn mock_info(sender: &str, funds: &[Coin]) -> MessageInfo {
 #[test]
 fn pair_type_to_string() {
     assert_eq!(PairType::Xyk {}.to_string(), "xyk");
+    assert_eq!(PairType::Stable {}.to_string(), "stable");
     assert_eq!(PairType::Concentrated {}.to_string(), "concentrated");
+    assert_eq!(PairType::Weighted {}.to_string(), "weighted");
+    assert_eq!(PairType::Lbp {}.to_string(), "lbp");
+    assert_eq!(PairType::Clmm {}.to_string(), "clmm");
 }
 
 #[test]
@@ -53,6 +57,7 @@ fn proper_initialization() {
                 is_disabled: false,
                 is_generator_disabled: false,
                 permissioned: false,
+                native_lp_token: false,
             },
             PairConfig {
                 code_id: 325u64,
@@ -62,6 +67,7 @@ fn proper_initialization() {
                 is_disabled: false,
                 is_generator_disabled: false,
                 permissioned: false,
+                native_lp_token: false,
             },
         ],
         token_code_id: 123u64,
@@ -86,6 +92,7 @@ fn proper_initialization() {
             is_disabled: false,
             is_generator_disabled: false,
             permissioned: false,
+            native_lp_token: false,
         }],
         token_code_id: 123u64,
         fee_address: None,
@@ -112,6 +119,7 @@ fn proper_initialization() {
                 is_disabled: false,
                 is_generator_disabled: false,
                 permissioned: false,
+                native_lp_token: false,
             },
             PairConfig {
                 code_id: 123u64,
@@ -121,6 +129,7 @@ fn proper_initialization() {
                 is_disabled: false,
                 is_generator_disabled: false,
                 permissioned: false,
+                native_lp_token: false,
             },
         ],
         token_code_id: 123u64,
@@ -155,6 +164,7 @@ fn update_config() {
         is_disabled: false,
         is_generator_disabled: false,
         permissioned: false,
+        native_lp_token: false,
     }];
 
     let msg = InstantiateMsg {
@@ -303,6 +313,7 @@ fn update_pair_config() {
         is_disabled: false,
         is_generator_disabled: false,
         permissioned: false,
+        native_lp_token: false,
     }];
 
     let msg = InstantiateMsg {
@@ -334,6 +345,7 @@ fn update_pair_config() {
         is_disabled: false,
         is_generator_disabled: false,
         permissioned: false,
+        native_lp_token: false,
     };
 
     // Unauthorized err
@@ -358,6 +370,7 @@ fn update_pair_config() {
             is_disabled: false,
             is_generator_disabled: false,
             permissioned: false,
+            native_lp_token: false,
         },
     };
 
@@ -386,6 +399,7 @@ fn update_pair_config() {
         is_disabled: false,
         is_generator_disabled: false,
         permissioned: false,
+        native_lp_token: false,
     };
 
     let info = mock_info(owner, &[]);
@@ -416,6 +430,7 @@ fn create_pair() {
         is_disabled: false,
         is_generator_disabled: false,
         permissioned: false,
+        native_lp_token: false,
     };
 
     let msg = InstantiateMsg {
@@ -492,7 +507,8 @@ fn create_pair() {
                     factory_addr: String::from(MOCK_CONTRACT_ADDR),
                     asset_infos: asset_infos.clone(),
                     token_code_id: msg.token_code_id,
-                    init_params: None
+                    init_params: None,
+                    native_lp_token: false,
                 })
                 .unwrap(),
                 code_id: pair_config.code_id,
@@ -523,6 +539,7 @@ fn register() {
             is_disabled: false,
             is_generator_disabled: false,
             permissioned: false,
+            native_lp_token: false,
         }],
         token_code_id: 123u64,
         fee_address: None,
@@ -33,7 +33,7 @@ impl FactoryHelper {
             marketing: None,
         };
 
-        let astro_token = app
+        let _astro_token = app
             .instantiate_contract(
                 cw20_token_code_id,
                 owner.clone(),
@@ -76,6 +76,7 @@ impl FactoryHelper {
                     is_disabled: false,
                     is_generator_disabled: false,
                     permissioned: false,
+                    native_lp_token: false,
                 },
                 PairConfig {
                     code_id: pair_code_id,
@@ -85,6 +86,7 @@ impl FactoryHelper {
                     is_disabled: false,
                     is_generator_disabled: false,
                     permissioned: true,
+                    native_lp_token: false,
                 },
                 PairConfig {
                     code_id: pair_code_id,
@@ -94,6 +96,7 @@ impl FactoryHelper {
                     is_disabled: false,
                     is_generator_disabled: false,
                     permissioned: false,
+                    native_lp_token: false,
                 },
             ],
             token_code_id: cw20_token_code_id,
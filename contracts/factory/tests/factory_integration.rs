@@ -3,7 +3,8 @@ use cw_multi_test::{App, ContractWrapper, Executor};
 
 use astroport::asset::{AssetInfo, PairInfo};
 use astroport::factory::{
-    ConfigResponse, ExecuteMsg, FeeInfoResponse, InstantiateMsg, PairConfig, PairType, QueryMsg,
+    ConfigResponse, ExecuteMsg, FeeInfoResponse, InstantiateMsg, PairConfig, PairType, PauseScope,
+    PauseStatusResponse, QueryMsg,
 };
 use astroport_factory::error::ContractError;
 
@@ -40,6 +41,7 @@ fn proper_initialization() {
         is_disabled: false,
         is_generator_disabled: false,
         permissioned: false,
+        native_lp_token: false,
     }];
 
     let msg = InstantiateMsg {
@@ -156,6 +158,7 @@ fn test_create_pair() {
                 is_disabled: true,
                 is_generator_disabled: false,
                 permissioned: false,
+                native_lp_token: false,
             },
         },
         &[],
@@ -189,6 +192,7 @@ fn test_create_pair() {
             &helper.factory,
             &QueryMsg::FeeInfo {
                 pair_type: PairType::Custom("Custom".to_string()),
+                pair_addr: None,
             },
         )
         .unwrap();
@@ -414,3 +418,290 @@ fn test_indexed_queries() {
         .unwrap();
     assert_eq!(pair, pairs[0]);
 }
+
+#[test]
+fn test_pause() {
+    let mut app = App::default();
+    let owner = app.api().addr_make("owner");
+    let guardian = app.api().addr_make("guardian");
+    let someone = app.api().addr_make("someone");
+    let mut helper = FactoryHelper::init(&mut app, &owner);
+
+    let token1 = instantiate_token(
+        &mut app,
+        helper.cw20_token_code_id,
+        &owner,
+        "tokenX",
+        Some(18),
+    );
+    let token2 = instantiate_token(
+        &mut app,
+        helper.cw20_token_code_id,
+        &owner,
+        "tokenY",
+        Some(18),
+    );
+    helper
+        .create_pair(&mut app, &owner, PairType::Xyk {}, [&token1, &token2], None)
+        .unwrap();
+    let pairs: Vec<PairInfo> = app
+        .wrap()
+        .query_wasm_smart(
+            &helper.factory,
+            &QueryMsg::Pairs {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+    let pair = pairs[0].contract_addr.to_string();
+
+    let query_status = |app: &App, pair: Option<String>| -> PauseStatusResponse {
+        app.wrap()
+            .query_wasm_smart(&helper.factory, &QueryMsg::PauseStatus { pair })
+            .unwrap()
+    };
+
+    let pause_msg = ExecuteMsg::Pause {
+        pair: Some(pair.clone()),
+        scope: PauseScope::Swap,
+    };
+    let err = app
+        .execute_contract(guardian.clone(), helper.factory.clone(), &pause_msg, &[])
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::Unauthorized {}
+    );
+
+    // Only the owner can set the guardian
+    let update_guardian_msg = ExecuteMsg::UpdateGuardian {
+        guardian: Some(guardian.to_string()),
+    };
+    let err = app
+        .execute_contract(
+            someone.clone(),
+            helper.factory.clone(),
+            &update_guardian_msg,
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::Unauthorized {}
+    );
+    app.execute_contract(
+        owner.clone(),
+        helper.factory.clone(),
+        &update_guardian_msg,
+        &[],
+    )
+    .unwrap();
+    let config: ConfigResponse = app
+        .wrap()
+        .query_wasm_smart(&helper.factory, &QueryMsg::Config {})
+        .unwrap();
+    assert_eq!(config.guardian, Some(guardian.clone()));
+
+    app.execute_contract(guardian.clone(), helper.factory.clone(), &pause_msg, &[])
+        .unwrap();
+    let status = query_status(&app, Some(pair.clone()));
+    assert_eq!(
+        status,
+        PauseStatusResponse {
+            global: None,
+            pair: Some(PauseScope::Swap),
+        }
+    );
+    assert!(status.is_paused(PauseScope::Swap));
+    assert!(!status.is_paused(PauseScope::Provide));
+
+    // Only registered pairs can be paused
+    let err = app
+        .execute_contract(
+            guardian.clone(),
+            helper.factory.clone(),
+            &ExecuteMsg::Pause {
+                pair: Some(token1.to_string()),
+                scope: PauseScope::All,
+            },
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::PairNotRegistered(token1.to_string())
+    );
+
+    app.execute_contract(
+        owner.clone(),
+        helper.factory.clone(),
+        &ExecuteMsg::Pause {
+            pair: None,
+            scope: PauseScope::Provide,
+        },
+        &[],
+    )
+    .unwrap();
+    let status = query_status(&app, Some(pair.clone()));
+    assert!(status.is_paused(PauseScope::Swap));
+    assert!(status.is_paused(PauseScope::Provide));
+    assert_eq!(
+        query_status(&app, None),
+        PauseStatusResponse {
+            global: Some(PauseScope::Provide),
+            pair: None,
+        }
+    );
+
+    // The guardian can't lift pauses
+    let err = app
+        .execute_contract(
+            guardian.clone(),
+            helper.factory.clone(),
+            &ExecuteMsg::Unpause { pair: None },
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::Unauthorized {}
+    );
+
+    for pair in [None, Some(pair.clone())] {
+        app.execute_contract(
+            owner.clone(),
+            helper.factory.clone(),
+            &ExecuteMsg::Unpause { pair },
+            &[],
+        )
+        .unwrap();
+    }
+    assert_eq!(
+        query_status(&app, Some(pair)),
+        PauseStatusResponse::default()
+    );
+}
+
+#[test]
+fn test_update_pair_fees() {
+    let mut app = App::default();
+    let owner = app.api().addr_make("owner");
+    let someone = app.api().addr_make("someone");
+    let mut helper = FactoryHelper::init(&mut app, &owner);
+
+    let token1 = instantiate_token(
+        &mut app,
+        helper.cw20_token_code_id,
+        &owner,
+        "tokenX",
+        Some(18),
+    );
+    let token2 = instantiate_token(
+        &mut app,
+        helper.cw20_token_code_id,
+        &owner,
+        "tokenY",
+        Some(18),
+    );
+    helper
+        .create_pair(&mut app, &owner, PairType::Xyk {}, [&token1, &token2], None)
+        .unwrap();
+    let pairs: Vec<PairInfo> = app
+        .wrap()
+        .query_wasm_smart(
+            &helper.factory,
+            &QueryMsg::Pairs {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+    let pair = pairs[0].contract_addr.to_string();
+
+    let query_fee_info = |app: &App, pair_addr: Option<String>| -> FeeInfoResponse {
+        app.wrap()
+            .query_wasm_smart(
+                &helper.factory,
+                &QueryMsg::FeeInfo {
+                    pair_type: PairType::Xyk {},
+                    pair_addr,
+                },
+            )
+            .unwrap()
+    };
+    let update_fees_msg =
+        |pair_addr: &str, total_fee_bps, maker_fee_bps| ExecuteMsg::UpdatePairFees {
+            pair_addr: pair_addr.to_string(),
+            total_fee_bps,
+            maker_fee_bps,
+        };
+
+    let err = app
+        .execute_contract(
+            someone.clone(),
+            helper.factory.clone(),
+            &update_fees_msg(&pair, Some(300), None),
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::Unauthorized {}
+    );
+
+    let err = app
+        .execute_contract(
+            owner.clone(),
+            helper.factory.clone(),
+            &update_fees_msg(token1.as_str(), Some(300), None),
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::PairNotRegistered(token1.to_string())
+    );
+
+    let err = app
+        .execute_contract(
+            owner.clone(),
+            helper.factory.clone(),
+            &update_fees_msg(&pair, Some(10_001), None),
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::PairConfigInvalidFeeBps {}
+    );
+
+    // Only the total fee is overridden, the maker fee falls back to the pair type config
+    app.execute_contract(
+        owner.clone(),
+        helper.factory.clone(),
+        &update_fees_msg(&pair, Some(300), None),
+        &[],
+    )
+    .unwrap();
+    let fee_info = query_fee_info(&app, Some(pair.clone()));
+    assert_eq!(fee_info.total_fee_bps, 300);
+    assert_eq!(fee_info.maker_fee_bps, 10);
+
+    // Other pairs of the same type are not affected
+    let fee_info = query_fee_info(&app, None);
+    assert_eq!(fee_info.total_fee_bps, 100);
+    assert_eq!(fee_info.maker_fee_bps, 10);
+
+    // Removing the override restores the pair type fees
+    app.execute_contract(
+        owner.clone(),
+        helper.factory.clone(),
+        &update_fees_msg(&pair, None, None),
+        &[],
+    )
+    .unwrap();
+    let fee_info = query_fee_info(&app, Some(pair));
+    assert_eq!(fee_info.total_fee_bps, 100);
+    assert_eq!(fee_info.maker_fee_bps, 10);
+}
@@ -1,3 +1,5 @@
+use std::cmp::Ordering;
+use std::collections::HashSet;
 use std::convert::TryInto;
 use std::str::FromStr;
 use std::vec;
@@ -5,38 +7,60 @@ use std::vec;
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    attr, ensure_eq, from_json, to_json_binary, wasm_execute, wasm_instantiate, Addr, Binary,
-    CosmosMsg, CustomMsg, CustomQuery, Decimal, Decimal256, Deps, DepsMut, Empty, Env, Event,
-    Fraction, Isqrt, MessageInfo, QuerierWrapper, Reply, Response, StdError, StdResult, SubMsg,
-    SubMsgResponse, SubMsgResult, Uint128, Uint256, WasmMsg,
+    attr, coin, ensure_eq, from_json, to_json_binary, wasm_execute, wasm_instantiate, Addr, Api,
+    Attribute, Binary, CosmosMsg, CustomMsg, CustomQuery, Decimal, Decimal256, Deps, DepsMut,
+    Empty, Env, Event, Fraction, Isqrt, MessageInfo, Order, QuerierWrapper, Reply, Response,
+    StdError, StdResult, Storage, SubMsg, SubMsgResponse, SubMsgResult, Uint128, Uint256, Uint64,
+    WasmMsg,
 };
 use cw2::{get_contract_version, set_contract_version};
 use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg, MinterResponse};
-use cw_utils::parse_instantiate_response_data;
+use cw_storage_plus::Bound;
+use cw_utils::{must_pay, parse_instantiate_response_data};
 
 use astroport::asset::{
-    addr_opt_validate, check_swap_parameters, format_lp_token_name, Asset, AssetInfo, CoinsExt,
-    PairInfo, MINIMUM_LIQUIDITY_AMOUNT,
+    addr_opt_validate, check_swap_parameters, format_lp_token_name, Asset, AssetInfo, AssetInfoExt,
+    CoinsExt, PairInfo, MINIMUM_LIQUIDITY_AMOUNT,
 };
 use astroport::cosmwasm_ext::DecMul;
+use astroport::factory::PauseScope;
 use astroport::incentives;
+use astroport::observation::{query_observations, store_observation, Observation};
 use astroport::pair::{
-    ConfigResponse, FeeShareConfig, XYKPoolConfig, XYKPoolUpdateParams, DEFAULT_SLIPPAGE,
-    INSTANTIATE_TOKEN_REPLY_ID, MAX_ALLOWED_SLIPPAGE, MAX_FEE_SHARE_BPS,
+    calc_protocol_fee_share, is_deadline_exceeded, price_deviation, BatchOrder, ConfigResponse,
+    DynamicFeeParams, FeeShareConfig, FeeShareRecipient, PairHook, PairHookMsg, TraderAllowlist,
+    XYKPoolConfig, XYKPoolUpdateParams, DEFAULT_SLIPPAGE, DYNAMIC_FEE_WINDOW_LIMITS,
+    FLASH_SWAP_REPLY_ID, HOOK_GAS_LIMIT, HOOK_REPLY_ID, INSTANTIATE_TOKEN_REPLY_ID,
+    MAX_ALLOWED_SLIPPAGE, MAX_ALLOWLIST_ADDRESSES, MAX_BATCH_ORDERS, MAX_DYNAMIC_FEE_BPS,
+    MAX_FEE_SHARE_BPS, MAX_HOOKS, PRICE_DEVIATION_LIMITS,
 };
 use astroport::pair::{
     CumulativePricesResponse, Cw20HookMsg, ExecuteMsg, InstantiateMsg, PoolResponse, QueryMsg,
     ReverseSimulationResponse, SimulationResponse, TWAP_PRECISION,
 };
-use astroport::querier::{query_factory_config, query_fee_info, query_supply};
+use astroport::querier::{
+    ensure_not_paused, query_factory_config, query_fee_info, query_lp_supply, FeeInfo,
+};
+use astroport::stats::{epoch_at, AssetStats, StatsResponse};
+use astroport::token_factory::{
+    tf_burn_msg, tf_create_denom_msg, tf_denom, tf_mint_msg, LP_SUBDENOM,
+};
 
 use crate::error::ContractError;
-use crate::state::{Config, CONFIG};
+use crate::migration::migrate_config_from_v210;
+use crate::state::{
+    Config, BALANCES, BATCH_ORDERS, CONFIG, FLASH_SWAP, K_LAST, NEXT_BATCH_ORDER_ID, OBSERVATIONS,
+    RESERVES, STATS,
+};
 
 /// Contract name that is used for migration.
 const CONTRACT_NAME: &str = "astroport-pair";
 /// Contract version that is used for migration.
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+/// Default pagination limit
+const DEFAULT_LIMIT: u32 = 10;
+/// Max pagination limit
+const MAX_LIMIT: u32 = 30;
 
 /// Creates a new contract with the specified parameters in the [`InstantiateMsg`].
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -59,10 +83,16 @@ pub fn instantiate(
 
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
+    let liquidity_token = if msg.native_lp_token {
+        tf_denom(&env.contract.address, LP_SUBDENOM)
+    } else {
+        "".to_owned()
+    };
+
     let config = Config {
         pair_info: PairInfo {
             contract_addr: env.contract.address.clone(),
-            liquidity_token: "".to_owned(),
+            liquidity_token,
             asset_infos: msg.asset_infos.clone(),
             pair_type: msg.pair_type,
         },
@@ -71,9 +101,25 @@ pub fn instantiate(
         price0_cumulative_last: Uint128::zero(),
         price1_cumulative_last: Uint128::zero(),
         fee_share: None,
+        native_lp_token: msg.native_lp_token,
+        hooks: vec![],
+        dynamic_fee: None,
+        protocol_fee_accrual: false,
+        block_open_price: Decimal256::zero(),
+        max_price_deviation: None,
+        batch_auction: false,
+        trader_allowlist: None,
     };
 
     CONFIG.save(deps.storage, &config)?;
+    RESERVES.save(deps.storage, &vec![Uint128::zero(); 2])?;
+
+    // The LP denom is known upfront so there is no need to wait for a reply
+    if msg.native_lp_token {
+        return Ok(Response::new()
+            .add_message(tf_create_denom_msg(&env.contract.address, LP_SUBDENOM))
+            .add_attribute("liquidity_token", config.pair_info.liquidity_token));
+    }
 
     let sub_msg = SubMsg::reply_on_success(
         wasm_instantiate(
@@ -100,7 +146,7 @@ pub fn instantiate(
 
 /// The entry point to the contract for processing replies from submessages.
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
+pub fn reply(deps: DepsMut, env: Env, msg: Reply) -> Result<Response, ContractError> {
     match msg {
         #[allow(deprecated)]
         Reply {
@@ -120,6 +166,21 @@ pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractE
             Ok(Response::new()
                 .add_attribute("liquidity_token_addr", config.pair_info.liquidity_token))
         }
+        Reply {
+            id: FLASH_SWAP_REPLY_ID,
+            ..
+        } => {
+            let ask_asset = FLASH_SWAP.load(deps.storage)?;
+            FLASH_SWAP.remove(deps.storage);
+
+            verify_flash_swap(deps, env, ask_asset)
+        }
+        // Failures of non-blocking hooks are ignored
+        Reply {
+            id: HOOK_REPLY_ID,
+            result: SubMsgResult::Err(err),
+            ..
+        } => Ok(Response::new().add_attribute("hook_error", err)),
         _ => Err(StdError::generic_err("Invalid reply".to_string()).into()),
     }
 }
@@ -127,7 +188,8 @@ pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractE
 /// Exposes all the execute functions available in the contract.
 ///
 /// ## Variants
-/// * **ExecuteMsg::UpdateConfig { params: Binary }** Not supported.
+/// * **ExecuteMsg::UpdateConfig { params: Binary }** Updates fee sharing, the dynamic fee, hooks,
+/// the protocol fee accrual or the intra-block price guard of the pair.
 ///
 /// * **ExecuteMsg::Receive(msg)** Receives a message of type [`Cw20ReceiveMsg`] and processes
 /// it depending on the received template.
@@ -138,6 +200,7 @@ pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractE
 ///             auto_stake,
 ///             receiver,
 ///            min_lp_to_receive,
+///            deadline,
 ///         }** Provides liquidity in the pair with the specified input parameters.
 ///
 /// * **ExecuteMsg::Swap {
@@ -145,11 +208,35 @@ pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractE
 ///             belief_price,
 ///             max_spread,
 ///             to,
+///             deadline,
 ///         }** Performs a swap operation with the specified parameters.
-/// * **ExecuteMsg::WithdrawLiquidity {
-///            assets,
-///           min_assets_to_receive,
-///       }** Withdraws liquidity from the pool.
+///
+/// * **ExecuteMsg::SwapExactOut {
+///             ask_asset,
+///             max_offer_amount,
+///             to,
+///         }** Swaps the offer asset for the exact amount of the ask asset refunding unused funds.
+///
+/// * **ExecuteMsg::FlashSwap { ask_asset, callback_msg }** Lends the ask asset to the sender
+/// and executes the callback on the sender. The borrowed amount plus fees must be returned
+/// by the end of the callback.
+///
+/// * **ExecuteMsg::WithdrawLiquidity { min_assets_to_receive, deadline }** Withdraws liquidity from the pool
+/// by burning the LP token denom sent along with the message.
+///
+/// Swap and liquidity messages are rejected with [`ContractError::DeadlineExceeded`]
+/// once the block time is past their optional deadline.
+///
+/// * **ExecuteMsg::Skim { to }** Sends the pair balances exceeding the tracked reserves to the recipient.
+///
+/// * **ExecuteMsg::Sync {}** Updates the tracked reserves to match the pair balances.
+///
+/// * **ExecuteMsg::CollectProtocolFees {}** Mints the accrued protocol fee as LP tokens to the fee address.
+///
+/// * **ExecuteMsg::SettleBatch {}** Executes the swap orders queued before the current block
+/// at a uniform price.
+///
+/// * **ExecuteMsg::CancelBatchOrder { order_id }** Cancels a queued swap order of the sender.
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
     deps: DepsMut,
@@ -157,6 +244,15 @@ pub fn execute(
     info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
+    // The pair is locked until the flash swap is settled
+    if FLASH_SWAP.exists(deps.storage) {
+        return Err(ContractError::FlashSwapInProgress {});
+    }
+
+    if is_deadline_exceeded(&env.block, msg.deadline()) {
+        return Err(ContractError::DeadlineExceeded {});
+    }
+
     match msg {
         ExecuteMsg::Receive(msg) => receive_cw20(deps, env, info, msg),
         ExecuteMsg::ProvideLiquidity {
@@ -165,6 +261,7 @@ pub fn execute(
             auto_stake,
             receiver,
             min_lp_to_receive,
+            ..
         } => provide_liquidity(
             deps,
             env,
@@ -200,7 +297,66 @@ pub fn execute(
                 to_addr,
             )
         }
-        ExecuteMsg::UpdateConfig { params } => update_config(deps, info, params),
+        ExecuteMsg::SwapExactOut {
+            ask_asset,
+            max_offer_amount,
+            to,
+            ..
+        } => {
+            ask_asset.info.check(deps.api)?;
+            let config = CONFIG.load(deps.storage)?;
+            let offer_asset_info = config
+                .pair_info
+                .asset_infos
+                .iter()
+                .find(|info| !info.equal(&ask_asset.info))
+                .cloned()
+                .ok_or(ContractError::AssetMismatch {})?;
+            if !offer_asset_info.is_native_token() {
+                return Err(ContractError::Cw20DirectSwap {});
+            }
+
+            let offer_asset = offer_asset_info.with_balance(max_offer_amount);
+            offer_asset.assert_sent_native_token_balance(&info)?;
+
+            let to_addr = addr_opt_validate(deps.api, &to)?;
+
+            swap_exact_out(deps, env, info.sender, offer_asset, ask_asset, to_addr)
+        }
+        ExecuteMsg::FlashSwap {
+            ask_asset,
+            callback_msg,
+            ..
+        } => {
+            ask_asset.info.check(deps.api)?;
+            flash_swap(deps, info.sender, ask_asset, callback_msg)
+        }
+        ExecuteMsg::WithdrawLiquidity {
+            min_assets_to_receive,
+            asset,
+            ..
+        } => {
+            let config = CONFIG.load(deps.storage)?;
+            if !config.native_lp_token || asset.is_some() {
+                return Err(ContractError::NonSupported {});
+            }
+
+            let amount = must_pay(&info, &config.pair_info.liquidity_token)?;
+            let sender = info.sender.clone();
+
+            withdraw_liquidity(deps, env, info, min_assets_to_receive, sender, amount)
+        }
+        ExecuteMsg::Skim { to } => {
+            let to_addr = addr_opt_validate(deps.api, &to)?.unwrap_or(info.sender);
+            skim(deps, env, to_addr)
+        }
+        ExecuteMsg::Sync {} => sync(deps, env),
+        ExecuteMsg::CollectProtocolFees {} => collect_protocol_fees(deps, env),
+        ExecuteMsg::SettleBatch {} => settle_batch(deps, env),
+        ExecuteMsg::CancelBatchOrder { order_id } => {
+            cancel_batch_order(deps, info.sender, order_id)
+        }
+        ExecuteMsg::UpdateConfig { params } => update_config(deps, env, info, params),
         _ => Err(ContractError::NonSupported {}),
     }
 }
@@ -214,7 +370,12 @@ pub fn receive_cw20(
     info: MessageInfo,
     cw20_msg: Cw20ReceiveMsg,
 ) -> Result<Response, ContractError> {
-    match from_json(&cw20_msg.msg)? {
+    let hook_msg: Cw20HookMsg = from_json(&cw20_msg.msg)?;
+    if is_deadline_exceeded(&env.block, hook_msg.deadline()) {
+        return Err(ContractError::DeadlineExceeded {});
+    }
+
+    match hook_msg {
         Cw20HookMsg::Swap {
             belief_price,
             max_spread,
@@ -254,22 +415,60 @@ pub fn receive_cw20(
                 to_addr,
             )
         }
+        Cw20HookMsg::SwapExactOut { ask_asset, to, .. } => {
+            // Only asset contract can execute this message
+            let config = CONFIG.load(deps.storage)?;
+            let offer_asset_info = AssetInfo::Token {
+                contract_addr: info.sender.clone(),
+            };
+            if !config.pair_info.asset_infos.contains(&offer_asset_info) {
+                return Err(ContractError::Unauthorized {});
+            }
+
+            let to_addr = addr_opt_validate(deps.api, &to)?;
+
+            swap_exact_out(
+                deps,
+                env,
+                Addr::unchecked(cw20_msg.sender),
+                offer_asset_info.with_balance(cw20_msg.amount),
+                ask_asset,
+                to_addr,
+            )
+        }
         Cw20HookMsg::WithdrawLiquidity {
             min_assets_to_receive,
-        } => withdraw_liquidity(
-            deps,
-            env,
-            info,
-            min_assets_to_receive,
-            Addr::unchecked(cw20_msg.sender),
-            cw20_msg.amount,
-        ),
+            asset,
+            ..
+        } => {
+            let config = CONFIG.load(deps.storage)?;
+            ensure_eq!(
+                info.sender.to_string(),
+                config.pair_info.liquidity_token,
+                ContractError::Unauthorized {}
+            );
+            if asset.is_some() {
+                return Err(ContractError::NonSupported {});
+            }
+
+            withdraw_liquidity(
+                deps,
+                env,
+                info,
+                min_assets_to_receive,
+                Addr::unchecked(cw20_msg.sender),
+                cw20_msg.amount,
+            )
+        }
     }
 }
 
 /// Provides liquidity in the pair with the specified input parameters.
 ///
-/// * **assets** is an array with assets available in the pool.
+/// Deposits which don't match the pool ratio are balanced with an internal swap, see [`compute_provide`].
+///
+/// * **assets** is an array with assets available in the pool. A single asset can be provided
+///   if the pool is not empty.
 ///
 /// * **slippage_tolerance** is an optional parameter which is used to specify how much
 ///   the pool price can move until the provide liquidity transaction goes through.
@@ -294,10 +493,18 @@ pub fn provide_liquidity(
     min_lp_to_receive: Option<Uint128>,
 ) -> Result<Response, ContractError> {
     let mut config = CONFIG.load(deps.storage)?;
+    ensure_not_paused(
+        &deps.querier,
+        &config.factory_addr,
+        &config.pair_info.contract_addr,
+        PauseScope::Provide,
+        ContractError::Paused,
+    )?;
 
-    let mut pools = config
-        .pair_info
-        .query_pools(&deps.querier, &config.pair_info.contract_addr)?;
+    let receiver = addr_opt_validate(deps.api, &receiver)?.unwrap_or_else(|| info.sender.clone());
+    ensure_traders_allowed(deps.querier, &config, &[&info.sender, &receiver])?;
+
+    let pools = query_reserves(deps.storage, &config)?;
 
     let deposits = get_deposits_from_assets(deps.as_ref(), &assets, &pools)?;
 
@@ -307,9 +514,12 @@ pub fn provide_liquidity(
     let auto_stake = auto_stake.unwrap_or(false);
 
     let mut messages = vec![];
-    for (i, pool) in pools.iter_mut().enumerate() {
+    for (i, pool) in pools.iter().enumerate() {
         // If the asset is a token contract, then we need to execute a TransferFrom msg to receive assets
         if let AssetInfo::Token { contract_addr, .. } = &pool.info {
+            if deposits[i].is_zero() {
+                continue;
+            }
             messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
                 contract_addr: contract_addr.to_string(),
                 msg: to_json_binary(&Cw20ExecuteMsg::TransferFrom {
@@ -319,15 +529,36 @@ pub fn provide_liquidity(
                 })?,
                 funds: vec![],
             }));
-        } else {
-            // If the asset is native token, the pool balance is already increased
-            // To calculate the total amount of deposits properly, we should subtract the user deposit from the pool
-            pool.amount = pool.amount.checked_sub(deposits[i])?;
         }
     }
 
-    let total_share = query_supply(&deps.querier, &config.pair_info.liquidity_token)?;
-    let share = calculate_shares(&deposits, &pools, total_share, slippage_tolerance)?;
+    let total_share = query_lp_supply(
+        &deps.querier,
+        &config.pair_info.liquidity_token,
+        config.native_lp_token,
+    )?;
+
+    let (protocol_fee_msgs, protocol_share) =
+        mint_protocol_fee(deps.as_ref(), &env, &config, &pools, total_share)?;
+    messages.extend(protocol_fee_msgs);
+    let total_share = total_share + protocol_share;
+
+    let ProvideOutcome {
+        share,
+        new_pools,
+        messages: fee_messages,
+        attributes: swap_attrs,
+        stats,
+    } = compute_provide(
+        deps.as_ref(),
+        &env,
+        &config,
+        deposits,
+        &pools,
+        total_share,
+        slippage_tolerance,
+    )?;
+    messages.extend(fee_messages);
 
     if total_share.is_zero() {
         messages.extend(mint_liquidity_token_message(
@@ -350,7 +581,6 @@ pub fn provide_liquidity(
     }
 
     // Mint LP tokens for the sender or for the receiver (if set)
-    let receiver = addr_opt_validate(deps.api, &receiver)?.unwrap_or_else(|| info.sender.clone());
     messages.extend(mint_liquidity_token_message(
         deps.querier,
         &config,
@@ -360,6 +590,15 @@ pub fn provide_liquidity(
         auto_stake,
     )?);
 
+    save_reserves(deps.storage, &new_pools, env.block.height)?;
+    save_k_last(deps.storage, &config, &new_pools)?;
+    STATS.record(
+        deps.storage,
+        &config.pair_info.asset_infos,
+        env.block.time.seconds(),
+        &stats,
+    )?;
+
     // Accumulate prices for the assets in the pool
     if let Some((price0_cumulative_new, price1_cumulative_new, block_time)) =
         accumulate_prices(env, &config, pools[0].amount, pools[1].amount)?
@@ -367,20 +606,44 @@ pub fn provide_liquidity(
         config.price0_cumulative_last = price0_cumulative_new;
         config.price1_cumulative_last = price1_cumulative_new;
         config.block_time_last = block_time;
+        config.block_open_price = spot_price(&pools);
         CONFIG.save(deps.storage, &config)?;
+        save_observation(deps.storage, &config)?;
     }
 
+    // The balancing swap of an imbalanced deposit moves the price
+    ensure_price_deviation(&config, &new_pools)?;
+
     let attrs = vec![
         attr("action", "provide_liquidity"),
         attr("sender", info.sender.to_string()),
         attr("receiver", receiver.to_string()),
-        attr("assets", format!("{}, {}", assets[0], assets[1])),
+        attr(
+            "assets",
+            assets
+                .iter()
+                .map(|asset| asset.to_string())
+                .collect::<Vec<_>>()
+                .join(", "),
+        ),
         attr("share", share),
     ];
 
-    let event = Event::new("provide_liquidity").add_attributes(attrs);
+    let event = Event::new("provide_liquidity")
+        .add_attributes(attrs)
+        .add_attributes(swap_attrs);
 
-    Ok(Response::new().add_messages(messages).add_event(event))
+    let hook_msgs = PairHookMsg::AfterProvide {
+        assets,
+        share,
+        receiver,
+    }
+    .into_submsgs(&config.hooks)?;
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_submessages(hook_msgs)
+        .add_event(event))
 }
 
 /// Mint LP tokens for a beneficiary and auto stake the tokens in the Incentive contract (if auto staking is specified).
@@ -403,6 +666,17 @@ where
     C: CustomQuery,
     T: CustomMsg,
 {
+    if config.native_lp_token {
+        return mint_native_liquidity_token_message(
+            querier,
+            config,
+            contract_address,
+            recipient,
+            amount,
+            auto_stake,
+        );
+    }
+
     // If no auto-stake - just mint to recipient
     if !auto_stake {
         return Ok(vec![wasm_execute(
@@ -448,6 +722,45 @@ where
     }
 }
 
+/// Mint LP shares as a token factory denom and auto stake them in the Incentives contract
+/// (if auto staking is specified).
+fn mint_native_liquidity_token_message<T, C>(
+    querier: QuerierWrapper<C>,
+    config: &Config,
+    contract_address: &Addr,
+    recipient: &Addr,
+    amount: Uint128,
+    auto_stake: bool,
+) -> Result<Vec<CosmosMsg<T>>, ContractError>
+where
+    C: CustomQuery,
+    T: CustomMsg,
+{
+    let coin = coin(amount.into(), &config.pair_info.liquidity_token);
+
+    if !auto_stake {
+        return Ok(tf_mint_msg(contract_address, coin, recipient));
+    }
+
+    let incentives_addr = query_factory_config(&querier, &config.factory_addr)?
+        .incentives_address
+        .ok_or(ContractError::AutoStakeError {})?;
+
+    let mut messages = tf_mint_msg(contract_address, coin.clone(), contract_address);
+    messages.push(
+        wasm_execute(
+            incentives_addr,
+            &incentives::ExecuteMsg::Deposit {
+                recipient: Some(recipient.to_string()),
+            },
+            vec![coin],
+        )?
+        .into(),
+    );
+
+    Ok(messages)
+}
+
 /// Withdraw liquidity from the pool.
 pub fn withdraw_liquidity(
     deps: DepsMut,
@@ -459,18 +772,28 @@ pub fn withdraw_liquidity(
 ) -> Result<Response, ContractError> {
     let mut config = CONFIG.load(deps.storage).unwrap();
 
-    ensure_eq!(
-        info.sender.to_string(),
-        config.pair_info.liquidity_token,
-        ContractError::Unauthorized {}
-    );
+    let (pools, total_share) = pool_info(deps.as_ref(), &config)?;
 
-    let (pools, total_share) = pool_info(deps.querier, &config)?;
+    let (protocol_fee_msgs, protocol_share) =
+        mint_protocol_fee(deps.as_ref(), &env, &config, &pools, total_share)?;
+    let total_share = total_share + protocol_share;
 
     let refund_assets = get_share_in_assets(&pools, amount, total_share);
 
     ensure_min_assets_to_receive(&config, refund_assets.clone(), min_assets_to_receive)?;
 
+    let new_pools = pools
+        .iter()
+        .zip(refund_assets.iter())
+        .map(|(pool, refund_asset)| {
+            Ok(pool
+                .info
+                .with_balance(pool.amount.checked_sub(refund_asset.amount)?))
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    save_reserves(deps.storage, &new_pools, env.block.height)?;
+    save_k_last(deps.storage, &config, &new_pools)?;
+
     // Accumulate prices for the pair assets
     if let Some((price0_cumulative_new, price1_cumulative_new, block_time)) =
         accumulate_prices(env.clone(), &config, pools[0].amount, pools[1].amount)?
@@ -478,7 +801,9 @@ pub fn withdraw_liquidity(
         config.price0_cumulative_last = price0_cumulative_new;
         config.price1_cumulative_last = price1_cumulative_new;
         config.block_time_last = block_time;
+        config.block_open_price = spot_price(&pools);
         CONFIG.save(deps.storage, &config)?;
+        save_observation(deps.storage, &config)?;
     }
 
     // Update the pool info
@@ -487,15 +812,23 @@ pub fn withdraw_liquidity(
         .into_iter()
         .map(|asset| asset.into_msg(&receiver))
         .collect::<StdResult<Vec<_>>>()?;
+    messages.extend(protocol_fee_msgs);
 
-    messages.push(
-        wasm_execute(
-            &config.pair_info.liquidity_token,
-            &Cw20ExecuteMsg::Burn { amount },
-            vec![],
-        )?
-        .into(),
-    );
+    if config.native_lp_token {
+        messages.push(tf_burn_msg(
+            &env.contract.address,
+            coin(amount.into(), &config.pair_info.liquidity_token),
+        ));
+    } else {
+        messages.push(
+            wasm_execute(
+                &config.pair_info.liquidity_token,
+                &Cw20ExecuteMsg::Burn { amount },
+                vec![],
+            )?
+            .into(),
+        );
+    }
 
     let attrs = vec![
         attr("action", "withdraw_liquidity"),
@@ -510,7 +843,17 @@ pub fn withdraw_liquidity(
 
     let event = Event::new("withdraw_liquidity").add_attributes(attrs);
 
-    Ok(Response::new().add_messages(messages).add_event(event))
+    let hook_msgs = PairHookMsg::AfterWithdraw {
+        assets: refund_assets,
+        share: amount,
+        receiver,
+    }
+    .into_submsgs(&config.hooks)?;
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_submessages(hook_msgs)
+        .add_event(event))
 }
 
 /// Returns the amount of pool assets that correspond to an amount of LP tokens.
@@ -563,19 +906,20 @@ pub fn swap(
     offer_asset.assert_sent_native_token_balance(&info)?;
 
     let mut config = CONFIG.load(deps.storage)?;
+    ensure_not_paused(
+        &deps.querier,
+        &config.factory_addr,
+        &config.pair_info.contract_addr,
+        PauseScope::Swap,
+        ContractError::Paused,
+    )?;
+    ensure_traders_allowed(
+        deps.querier,
+        &config,
+        &[&sender, to.as_ref().unwrap_or(&sender)],
+    )?;
 
-    // If the asset balance is already increased, we should subtract the user deposit from the pool amount
-    let pools = config
-        .pair_info
-        .query_pools(&deps.querier, &config.pair_info.contract_addr)?
-        .into_iter()
-        .map(|mut p| {
-            if p.info.equal(&offer_asset.info) {
-                p.amount = p.amount.checked_sub(offer_asset.amount)?;
-            }
-            Ok(p)
-        })
-        .collect::<StdResult<Vec<_>>>()?;
+    let pools = query_reserves(deps.storage, &config)?;
 
     let offer_pool: Asset;
     let ask_pool: Asset;
@@ -590,12 +934,21 @@ pub fn swap(
         return Err(ContractError::AssetMismatch {});
     }
 
+    if config.batch_auction {
+        return queue_batch_order(
+            deps.storage,
+            &env,
+            sender,
+            to,
+            offer_asset,
+            ask_pool.info,
+            belief_price,
+            max_spread,
+        );
+    }
+
     // Get fee info from the factory
-    let fee_info = query_fee_info(
-        &deps.querier,
-        &config.factory_addr,
-        config.pair_info.pair_type.clone(),
-    )?;
+    let fee_info = query_swap_fee_info(deps.as_ref(), &env, &config, &pools)?;
 
     let offer_amount = offer_asset.amount;
 
@@ -623,7 +976,7 @@ pub fn swap(
     let receiver = to.unwrap_or_else(|| sender.clone());
     let mut messages = vec![];
     if !return_amount.is_zero() {
-        messages.push(return_asset.into_msg(receiver.clone())?)
+        messages.push(return_asset.clone().into_msg(receiver.clone())?)
     }
 
     // If this pool is configured to share fees, calculate the amount to send
@@ -634,20 +987,15 @@ pub fn swap(
     let mut fee_share_amount = Uint128::zero();
     if let Some(fee_share) = config.fee_share.clone() {
         // Calculate the fee share amount from the full commission amount
-        let share_fee_rate = Decimal::from_ratio(fee_share.bps, 10000u16);
+        let share_fee_rate = Decimal::from_ratio(fee_share.total_bps(), 10000u16);
         fee_share_amount = fees_commission_amount.dec_mul(share_fee_rate);
 
         if !fee_share_amount.is_zero() {
             // Subtract the fee share amount from the commission
             fees_commission_amount = fees_commission_amount.saturating_sub(fee_share_amount);
 
-            // Build send message for the shared amount
-            let fee_share_msg = Asset {
-                info: ask_pool.info.clone(),
-                amount: fee_share_amount,
-            }
-            .into_msg(fee_share.recipient)?;
-            messages.push(fee_share_msg);
+            // Build send messages for the shared amount
+            messages.extend(fee_share.into_msgs(&ask_pool.info, fee_share_amount)?);
         }
     }
 
@@ -664,6 +1012,35 @@ pub fn swap(
         }
     }
 
+    let new_pools = pools
+        .iter()
+        .map(|pool| {
+            let amount = if pool.info.equal(&offer_pool.info) {
+                pool.amount.checked_add(offer_amount)?
+            } else {
+                pool.amount
+                    .checked_sub(return_amount + fee_share_amount + maker_fee_amount)?
+            };
+            Ok(pool.info.with_balance(amount))
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    save_reserves(deps.storage, &new_pools, env.block.height)?;
+
+    let protocol_fee_amount = maker_fee_amount
+        + accrued_maker_fee(&config, fee_info.maker_fee_rate, fees_commission_amount);
+    STATS.record(
+        deps.storage,
+        &config.pair_info.asset_infos,
+        env.block.time.seconds(),
+        &AssetStats::swap(
+            &offer_asset,
+            &return_asset,
+            fees_commission_amount.checked_sub(protocol_fee_amount)?,
+            protocol_fee_amount,
+            fee_share_amount,
+        ),
+    )?;
+
     // Accumulate prices for the assets in the pool
     if let Some((price0_cumulative_new, price1_cumulative_new, block_time)) =
         accumulate_prices(env, &config, pools[0].amount, pools[1].amount)?
@@ -671,12 +1048,16 @@ pub fn swap(
         config.price0_cumulative_last = price0_cumulative_new;
         config.price1_cumulative_last = price1_cumulative_new;
         config.block_time_last = block_time;
+        config.block_open_price = spot_price(&pools);
         CONFIG.save(deps.storage, &config)?;
+        save_observation(deps.storage, &config)?;
     }
 
+    ensure_price_deviation(&config, &new_pools)?;
+
     let attrs = vec![
         attr("action", "swap"),
-        attr("sender", sender),
+        attr("sender", &sender),
         attr("receiver", receiver),
         attr("offer_asset", offer_asset.info.to_string()),
         attr("ask_asset", ask_pool.info.to_string()),
@@ -690,53 +1071,817 @@ pub fn swap(
 
     let event = Event::new("swap").add_attributes(attrs);
 
-    Ok(Response::new().add_messages(messages).add_event(event))
+    let hook_msgs = PairHookMsg::AfterSwap {
+        offer: offer_asset.info.with_balance(offer_amount),
+        ask: ask_pool.info.with_balance(return_amount),
+        trader: sender,
+    }
+    .into_submsgs(&config.hooks)?;
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_submessages(hook_msgs)
+        .add_event(event))
 }
 
-/// Updates the pool configuration with the specified parameters in the `params` variable.
+/// Performs a swap of the offer asset for the exact amount of the ask asset.
+/// The required offer amount is calculated with [`compute_offer_amount`],
+/// the rest of the offer asset is refunded to the sender.
 ///
-/// * **params** new parameter values.
-pub fn update_config(
+/// * **sender** is the sender of the swap operation.
+///
+/// * **offer_asset** is the offer asset sent along with the message.
+///   Its amount is the maximum amount that can be spent on the swap.
+///
+/// * **ask_asset** is the asset and the exact amount to receive.
+///
+/// * **to** sets the recipient of the swap operation.
+pub fn swap_exact_out(
     deps: DepsMut,
-    info: MessageInfo,
-    params: Binary,
+    env: Env,
+    sender: Addr,
+    offer_asset: Asset,
+    ask_asset: Asset,
+    to: Option<Addr>,
 ) -> Result<Response, ContractError> {
     let mut config = CONFIG.load(deps.storage)?;
-    let factory_config = query_factory_config(&deps.querier, &config.factory_addr)?;
-
-    if info.sender != factory_config.owner {
-        return Err(ContractError::Unauthorized {});
+    ensure_not_paused(
+        &deps.querier,
+        &config.factory_addr,
+        &config.pair_info.contract_addr,
+        PauseScope::Swap,
+        ContractError::Paused,
+    )?;
+    if config.batch_auction {
+        return Err(ContractError::BatchAuctionEnabled {});
     }
+    ensure_traders_allowed(
+        deps.querier,
+        &config,
+        &[&sender, to.as_ref().unwrap_or(&sender)],
+    )?;
 
-    let mut event = Event::new("update_config");
+    let pools = query_reserves(deps.storage, &config)?;
 
-    match from_json::<XYKPoolUpdateParams>(&params)? {
-        XYKPoolUpdateParams::EnableFeeShare {
-            fee_share_bps,
-            fee_share_address,
-        } => {
-            // Enable fee sharing for this contract
-            // If fee sharing is already enabled, we should be able to overwrite
-            // the values currently set
+    let offer_pool: Asset;
+    let ask_pool: Asset;
 
-            // Ensure the fee share isn't 0 and doesn't exceed the maximum allowed value
-            if fee_share_bps == 0 || fee_share_bps > MAX_FEE_SHARE_BPS {
-                return Err(ContractError::FeeShareOutOfBounds {});
-            }
+    if ask_asset.info.equal(&pools[0].info) && offer_asset.info.equal(&pools[1].info) {
+        ask_pool = pools[0].clone();
+        offer_pool = pools[1].clone();
+    } else if ask_asset.info.equal(&pools[1].info) && offer_asset.info.equal(&pools[0].info) {
+        ask_pool = pools[1].clone();
+        offer_pool = pools[0].clone();
+    } else {
+        return Err(ContractError::AssetMismatch {});
+    }
 
-            // Set sharing config
-            config.fee_share = Some(FeeShareConfig {
-                bps: fee_share_bps,
-                recipient: deps.api.addr_validate(&fee_share_address)?,
-            });
+    // Get fee info from the factory
+    let fee_info = query_swap_fee_info(deps.as_ref(), &env, &config, &pools)?;
 
-            CONFIG.save(deps.storage, &config)?;
+    let (offer_amount, spread_amount, commission_amount) = compute_offer_amount(
+        offer_pool.amount,
+        ask_pool.amount,
+        ask_asset.amount,
+        fee_info.total_fee_rate,
+    )?;
+
+    if offer_amount.is_zero() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+    if offer_amount > offer_asset.amount {
+        return Err(ContractError::MaxOfferAmountAssertion(
+            offer_amount,
+            offer_asset.amount,
+        ));
+    }
+
+    let receiver = to.unwrap_or_else(|| sender.clone());
+    let mut messages = vec![ask_asset.clone().into_msg(receiver.clone())?];
+
+    // Refund the unused offer funds
+    let refund_amount = offer_asset.amount - offer_amount;
+    if !refund_amount.is_zero() {
+        messages.push(
+            offer_asset
+                .info
+                .with_balance(refund_amount)
+                .into_msg(sender.clone())?,
+        );
+    }
+
+    // If this pool is configured to share fees, calculate the amount to send
+    // to the receiver and add the transfer message
+    let mut fees_commission_amount = commission_amount;
+    let mut fee_share_amount = Uint128::zero();
+    if let Some(fee_share) = config.fee_share.clone() {
+        let share_fee_rate = Decimal::from_ratio(fee_share.total_bps(), 10000u16);
+        fee_share_amount = fees_commission_amount.dec_mul(share_fee_rate);
+
+        if !fee_share_amount.is_zero() {
+            fees_commission_amount = fees_commission_amount.saturating_sub(fee_share_amount);
+
+            messages.extend(fee_share.into_msgs(&ask_pool.info, fee_share_amount)?);
+        }
+    }
+
+    // Compute the Maker fee
+    let mut maker_fee_amount = Uint128::zero();
+    if let Some(fee_address) = fee_info.fee_address {
+        if let Some(f) = calculate_maker_fee(
+            &ask_pool.info,
+            fees_commission_amount,
+            fee_info.maker_fee_rate,
+        ) {
+            maker_fee_amount = f.amount;
+            messages.push(f.into_msg(fee_address)?);
+        }
+    }
+
+    let new_pools = pools
+        .iter()
+        .map(|pool| {
+            let amount = if pool.info.equal(&offer_pool.info) {
+                pool.amount.checked_add(offer_amount)?
+            } else {
+                pool.amount
+                    .checked_sub(ask_asset.amount + fee_share_amount + maker_fee_amount)?
+            };
+            Ok(pool.info.with_balance(amount))
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    save_reserves(deps.storage, &new_pools, env.block.height)?;
+
+    let protocol_fee_amount = maker_fee_amount
+        + accrued_maker_fee(&config, fee_info.maker_fee_rate, fees_commission_amount);
+    STATS.record(
+        deps.storage,
+        &config.pair_info.asset_infos,
+        env.block.time.seconds(),
+        &AssetStats::swap(
+            &offer_asset.info.with_balance(offer_amount),
+            &ask_asset,
+            fees_commission_amount.checked_sub(protocol_fee_amount)?,
+            protocol_fee_amount,
+            fee_share_amount,
+        ),
+    )?;
+
+    // Accumulate prices for the assets in the pool
+    if let Some((price0_cumulative_new, price1_cumulative_new, block_time)) =
+        accumulate_prices(env, &config, pools[0].amount, pools[1].amount)?
+    {
+        config.price0_cumulative_last = price0_cumulative_new;
+        config.price1_cumulative_last = price1_cumulative_new;
+        config.block_time_last = block_time;
+        config.block_open_price = spot_price(&pools);
+        CONFIG.save(deps.storage, &config)?;
+        save_observation(deps.storage, &config)?;
+    }
+
+    ensure_price_deviation(&config, &new_pools)?;
+
+    let attrs = vec![
+        attr("action", "swap_exact_out"),
+        attr("sender", &sender),
+        attr("receiver", receiver),
+        attr("offer_asset", offer_asset.info.to_string()),
+        attr("ask_asset", ask_pool.info.to_string()),
+        attr("offer_amount", offer_amount),
+        attr("return_amount", ask_asset.amount),
+        attr("refund_amount", refund_amount),
+        attr("spread_amount", spread_amount),
+        attr("commission_amount", commission_amount),
+        attr("maker_fee_amount", maker_fee_amount),
+        attr("fee_share_amount", fee_share_amount),
+    ];
+
+    let event = Event::new("swap").add_attributes(attrs);
+
+    let hook_msgs = PairHookMsg::AfterSwap {
+        offer: offer_asset.info.with_balance(offer_amount),
+        ask: ask_asset,
+        trader: sender,
+    }
+    .into_submsgs(&config.hooks)?;
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_submessages(hook_msgs)
+        .add_event(event))
+}
+
+/// Sends the requested asset to the sender and executes the callback message on the sender.
+/// The pool invariant is verified in the reply once the callback is executed.
+///
+/// * **sender** is the borrower which receives the asset and the callback.
+///
+/// * **ask_asset** is the asset and the amount to borrow.
+///
+/// * **callback_msg** is the message executed on the sender after the asset is sent.
+pub fn flash_swap(
+    deps: DepsMut,
+    sender: Addr,
+    ask_asset: Asset,
+    callback_msg: Binary,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    ensure_not_paused(
+        &deps.querier,
+        &config.factory_addr,
+        &config.pair_info.contract_addr,
+        PauseScope::Swap,
+        ContractError::Paused,
+    )?;
+    if config.batch_auction {
+        return Err(ContractError::BatchAuctionEnabled {});
+    }
+    ensure_traders_allowed(deps.querier, &config, &[&sender])?;
+
+    let pools = query_reserves(deps.storage, &config)?;
+    let ask_pool = pools
+        .iter()
+        .find(|pool| pool.info.equal(&ask_asset.info))
+        .ok_or(ContractError::AssetMismatch {})?;
+    if ask_asset.amount.is_zero() || ask_asset.amount >= ask_pool.amount {
+        return Err(ContractError::InvalidFlashSwapAmount {});
+    }
+
+    FLASH_SWAP.save(deps.storage, &ask_asset)?;
+
+    let callback = SubMsg::reply_on_success(
+        WasmMsg::Execute {
+            contract_addr: sender.to_string(),
+            msg: callback_msg,
+            funds: vec![],
+        },
+        FLASH_SWAP_REPLY_ID,
+    );
+
+    let event = Event::new("flash_swap").add_attributes(vec![
+        attr("action", "flash_swap"),
+        attr("sender", sender.as_str()),
+        attr("ask_asset", ask_asset.info.to_string()),
+        attr("ask_amount", ask_asset.amount),
+    ]);
+
+    Ok(Response::new()
+        .add_message(ask_asset.into_msg(&sender)?)
+        .add_submessage(callback)
+        .add_event(event))
+}
+
+/// Verifies that the flash swap borrower returned the borrowed asset plus fees.
+/// The swap fee is charged on all the amounts returned to the pair, then the constant product
+/// of the fee adjusted balances must not be less than the one before the flash swap.
+fn verify_flash_swap(deps: DepsMut, env: Env, ask_asset: Asset) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+
+    let reserves = query_reserves(deps.storage, &config)?;
+    let balances = query_pool_balances(deps.as_ref(), &env, &config)?;
+
+    let fee_info = query_swap_fee_info(deps.as_ref(), &env, &config, &reserves)?;
+
+    let mut stats = vec![];
+    let adjusted_balances = reserves
+        .iter()
+        .zip(balances.iter())
+        .map(|(reserve, balance)| {
+            let mut expected = reserve.amount;
+            let mut volume_out = Uint128::zero();
+            if reserve.info.equal(&ask_asset.info) {
+                expected = expected.checked_sub(ask_asset.amount)?;
+                volume_out = ask_asset.amount;
+            }
+            let amount_in = balance.amount.saturating_sub(expected);
+            let fee = amount_in.mul_ceil(fee_info.total_fee_rate);
+            stats.push(AssetStats {
+                volume_in: amount_in,
+                volume_out,
+                lp_fee: fee,
+                ..AssetStats::new(reserve.info.clone())
+            });
+            Ok(balance.amount.checked_sub(fee)?)
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    if adjusted_balances[0].full_mul(adjusted_balances[1])
+        < reserves[0].amount.full_mul(reserves[1].amount)
+    {
+        return Err(ContractError::FlashSwapInvariantViolation {});
+    }
+
+    save_reserves(deps.storage, &balances, env.block.height)?;
+    STATS.record(
+        deps.storage,
+        &config.pair_info.asset_infos,
+        env.block.time.seconds(),
+        &stats,
+    )?;
+
+    // Accumulate prices up to this moment using the previous reserves
+    if let Some((price0_cumulative_new, price1_cumulative_new, block_time)) =
+        accumulate_prices(env, &config, reserves[0].amount, reserves[1].amount)?
+    {
+        config.price0_cumulative_last = price0_cumulative_new;
+        config.price1_cumulative_last = price1_cumulative_new;
+        config.block_time_last = block_time;
+        config.block_open_price = spot_price(&reserves);
+        CONFIG.save(deps.storage, &config)?;
+        save_observation(deps.storage, &config)?;
+    }
+
+    ensure_price_deviation(&config, &balances)?;
+
+    let event = Event::new("flash_swap_repay").add_attributes(vec![
+        attr("action", "flash_swap_repay"),
+        attr("reserves", format!("{}, {}", balances[0], balances[1])),
+    ]);
+
+    Ok(Response::new().add_event(event))
+}
+
+/// Sends the pair balances exceeding the tracked reserves (e.g. direct token transfers)
+/// to the specified recipient.
+///
+/// * **to** is the recipient of the excess balances.
+pub fn skim(deps: DepsMut, env: Env, to: Addr) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    let reserves = query_reserves(deps.storage, &config)?;
+    let balances = query_pool_balances(deps.as_ref(), &env, &config)?;
+
+    let excess_assets = balances
+        .into_iter()
+        .zip(reserves)
+        .map(|(balance, reserve)| {
+            balance
+                .info
+                .with_balance(balance.amount.saturating_sub(reserve.amount))
+        })
+        .filter(|asset| !asset.amount.is_zero())
+        .collect::<Vec<_>>();
+
+    let messages = excess_assets
+        .iter()
+        .map(|asset| asset.clone().into_msg(&to))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let attrs = vec![
+        attr("action", "skim"),
+        attr("receiver", to),
+        attr(
+            "assets",
+            excess_assets
+                .iter()
+                .map(|asset| asset.to_string())
+                .collect::<Vec<_>>()
+                .join(", "),
+        ),
+    ];
+
+    let event = Event::new("skim").add_attributes(attrs);
+
+    Ok(Response::new().add_messages(messages).add_event(event))
+}
+
+/// Updates the tracked reserves to match the current pair balances.
+pub fn sync(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+
+    let reserves = query_reserves(deps.storage, &config)?;
+    let balances = query_pool_balances(deps.as_ref(), &env, &config)?;
+
+    save_reserves(deps.storage, &balances, env.block.height)?;
+
+    // Accumulate prices up to this moment using the previous reserves
+    if let Some((price0_cumulative_new, price1_cumulative_new, block_time)) =
+        accumulate_prices(env, &config, reserves[0].amount, reserves[1].amount)?
+    {
+        config.price0_cumulative_last = price0_cumulative_new;
+        config.price1_cumulative_last = price1_cumulative_new;
+        config.block_time_last = block_time;
+        config.block_open_price = spot_price(&reserves);
+        CONFIG.save(deps.storage, &config)?;
+        save_observation(deps.storage, &config)?;
+    }
+
+    let attrs = vec![
+        attr("action", "sync"),
+        attr("reserves", format!("{}, {}", balances[0], balances[1])),
+    ];
+
+    let event = Event::new("sync").add_attributes(attrs);
+
+    Ok(Response::new().add_event(event))
+}
+
+/// Mints the protocol fee accrued since the last liquidity event as LP tokens to the fee address.
+pub fn collect_protocol_fees(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if !config.protocol_fee_accrual {
+        return Err(ContractError::ProtocolFeeAccrualDisabled {});
+    }
+
+    let (pools, total_share) = pool_info(deps.as_ref(), &config)?;
+    let (messages, protocol_share) =
+        mint_protocol_fee(deps.as_ref(), &env, &config, &pools, total_share)?;
+    save_k_last(deps.storage, &config, &pools)?;
+
+    let event = Event::new("collect_protocol_fees").add_attributes(vec![
+        attr("action", "collect_protocol_fees"),
+        attr("protocol_fee_share", protocol_share),
+    ]);
+
+    Ok(Response::new().add_messages(messages).add_event(event))
+}
+
+/// Queues a swap order in the batch auction mode. The offer asset is held by the pair
+/// until the order is settled or cancelled.
+#[allow(clippy::too_many_arguments)]
+fn queue_batch_order(
+    storage: &mut dyn Storage,
+    env: &Env,
+    sender: Addr,
+    to: Option<Addr>,
+    offer_asset: Asset,
+    ask_asset_info: AssetInfo,
+    belief_price: Option<Decimal>,
+    max_spread: Option<Decimal>,
+) -> Result<Response, ContractError> {
+    if offer_asset.amount.is_zero() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+    if max_spread.unwrap_or_default() > Decimal::from_str(MAX_ALLOWED_SLIPPAGE)? {
+        return Err(ContractError::AllowedSpreadAssertion {});
+    }
+    if BATCH_ORDERS
+        .keys(storage, None, None, Order::Ascending)
+        .count()
+        >= MAX_BATCH_ORDERS
+    {
+        return Err(ContractError::BatchOrdersLimitReached {});
+    }
+
+    let order_id = NEXT_BATCH_ORDER_ID.may_load(storage)?.unwrap_or_default();
+    NEXT_BATCH_ORDER_ID.save(storage, &(order_id + 1))?;
+
+    let order = BatchOrder {
+        order_id,
+        receiver: to.unwrap_or_else(|| sender.clone()),
+        trader: sender,
+        offer_asset,
+        ask_asset_info,
+        belief_price,
+        max_spread,
+        block_height: env.block.height,
+    };
+    BATCH_ORDERS.save(storage, order_id, &order)?;
+
+    let event = Event::new("queue_batch_order").add_attributes(vec![
+        attr("action", "queue_batch_order"),
+        attr("order_id", order_id.to_string()),
+        attr("sender", order.trader),
+        attr("receiver", order.receiver),
+        attr("offer_asset", order.offer_asset.info.to_string()),
+        attr("ask_asset", order.ask_asset_info.to_string()),
+        attr("offer_amount", order.offer_asset.amount),
+    ]);
+
+    Ok(Response::new().add_event(event))
+}
+
+/// Executes all swap orders queued before the current block at a single uniform price,
+/// see [`clear_batch`]. Orders violating their spread limits at the clearing price are refunded
+/// and the price is cleared again without them. The same applies to the latest orders pushing
+/// the price beyond the price guard limit and to orders of traders which are no longer allowed.
+pub fn settle_batch(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    ensure_not_paused(
+        &deps.querier,
+        &config.factory_addr,
+        &config.pair_info.contract_addr,
+        PauseScope::Swap,
+        ContractError::Paused,
+    )?;
+
+    // Orders placed in the current block are settled in the next one
+    let mut orders = BATCH_ORDERS
+        .range(deps.storage, None, None, Order::Ascending)
+        .filter_map(|item| match item {
+            Ok((_, order)) if order.block_height >= env.block.height => None,
+            item => Some(item.map(|(_, order)| order)),
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    if orders.is_empty() {
+        return Err(ContractError::NoBatchOrdersToSettle {});
+    }
+    for order in &orders {
+        BATCH_ORDERS.remove(deps.storage, order.order_id);
+    }
+
+    let pools = query_reserves(deps.storage, &config)?;
+    let fee_info = query_swap_fee_info(deps.as_ref(), &env, &config, &pools)?;
+
+    // Accumulate prices for the assets in the pool
+    if let Some((price0_cumulative_new, price1_cumulative_new, block_time)) =
+        accumulate_prices(env.clone(), &config, pools[0].amount, pools[1].amount)?
+    {
+        config.price0_cumulative_last = price0_cumulative_new;
+        config.price1_cumulative_last = price1_cumulative_new;
+        config.block_time_last = block_time;
+        config.block_open_price = spot_price(&pools);
+        CONFIG.save(deps.storage, &config)?;
+        save_observation(deps.storage, &config)?;
+    }
+
+    // The allowlist might have changed since the orders were placed
+    let mut rejected = vec![];
+    if let Some(allowlist) = &config.trader_allowlist {
+        let mut allowed = vec![];
+        for order in orders {
+            if allowlist.is_allowed(&deps.querier, &order.trader)?
+                && allowlist.is_allowed(&deps.querier, &order.receiver)?
+            {
+                allowed.push(order);
+            } else {
+                rejected.push(order);
+            }
+        }
+        orders = allowed;
+    }
+
+    let returns = loop {
+        let returns = clear_batch(&pools, &orders)?;
+
+        let (filled, failed): (Vec<_>, Vec<_>) =
+            orders
+                .into_iter()
+                .zip(returns)
+                .partition(|(order, return_amount)| {
+                    let (offer_pool, ask_pool) = if order.offer_asset.info.equal(&pools[0].info) {
+                        (pools[0].amount, pools[1].amount)
+                    } else {
+                        (pools[1].amount, pools[0].amount)
+                    };
+                    let spread_amount = order
+                        .offer_asset
+                        .amount
+                        .multiply_ratio(ask_pool, offer_pool)
+                        .saturating_sub(*return_amount);
+
+                    assert_max_spread(
+                        order.belief_price,
+                        order.max_spread,
+                        order.offer_asset.amount,
+                        *return_amount,
+                        spread_amount,
+                    )
+                    .is_ok()
+                });
+
+        if !failed.is_empty() {
+            rejected.extend(failed.into_iter().map(|(order, _)| order));
+            orders = filled.into_iter().map(|(order, _)| order).collect();
+            continue;
+        }
+
+        let Some(order_id) = find_price_guard_violation(&config, &pools, &filled) else {
+            break filled;
+        };
+        let (failed, filled): (Vec<_>, Vec<_>) = filled
+            .into_iter()
+            .map(|(order, _)| order)
+            .partition(|order| order.order_id == order_id);
+        rejected.extend(failed);
+        orders = filled;
+    };
+
+    let mut messages = rejected
+        .iter()
+        .map(|order| order.offer_asset.clone().into_msg(&order.trader))
+        .collect::<StdResult<Vec<_>>>()?;
+    let mut hook_msgs = vec![];
+    let mut events = vec![];
+    let mut stats = vec![];
+    let mut offered = [Uint128::zero(); 2];
+    let mut paid_out = [Uint128::zero(); 2];
+    let mut fee_shares = [Uint128::zero(); 2];
+    let mut maker_fees = [Uint128::zero(); 2];
+
+    for (order, amount) in &returns {
+        let (offer_ind, ask_ind) = if order.offer_asset.info.equal(&pools[0].info) {
+            (0, 1)
+        } else {
+            (1, 0)
+        };
+
+        let commission_amount = amount.dec_mul(fee_info.total_fee_rate);
+        let return_amount = amount.checked_sub(commission_amount)?;
+        let return_asset = order.ask_asset_info.with_balance(return_amount);
+        if !return_amount.is_zero() {
+            messages.push(return_asset.clone().into_msg(&order.receiver)?);
+        }
+
+        let mut fees_commission_amount = commission_amount;
+        let mut fee_share_amount = Uint128::zero();
+        if let Some(fee_share) = &config.fee_share {
+            let share_fee_rate = Decimal::from_ratio(fee_share.total_bps(), 10000u16);
+            fee_share_amount = fees_commission_amount.dec_mul(share_fee_rate);
+            fees_commission_amount = fees_commission_amount.saturating_sub(fee_share_amount);
+        }
+
+        let mut maker_fee_amount = Uint128::zero();
+        if fee_info.fee_address.is_some() {
+            if let Some(f) = calculate_maker_fee(
+                &order.ask_asset_info,
+                fees_commission_amount,
+                fee_info.maker_fee_rate,
+            ) {
+                maker_fee_amount = f.amount;
+            }
+        }
+
+        offered[offer_ind] += order.offer_asset.amount;
+        paid_out[ask_ind] += return_amount + fee_share_amount + maker_fee_amount;
+        fee_shares[ask_ind] += fee_share_amount;
+        maker_fees[ask_ind] += maker_fee_amount;
+        let protocol_fee_amount = maker_fee_amount
+            + accrued_maker_fee(&config, fee_info.maker_fee_rate, fees_commission_amount);
+        stats.extend(AssetStats::swap(
+            &order.offer_asset,
+            &return_asset,
+            fees_commission_amount.checked_sub(protocol_fee_amount)?,
+            protocol_fee_amount,
+            fee_share_amount,
+        ));
+
+        events.push(Event::new("swap").add_attributes(vec![
+            attr("action", "swap"),
+            attr("order_id", order.order_id.to_string()),
+            attr("sender", &order.trader),
+            attr("receiver", &order.receiver),
+            attr("offer_asset", order.offer_asset.info.to_string()),
+            attr("ask_asset", order.ask_asset_info.to_string()),
+            attr("offer_amount", order.offer_asset.amount),
+            attr("return_amount", return_amount),
+            attr("commission_amount", commission_amount),
+            attr("maker_fee_amount", maker_fee_amount),
+            attr("fee_share_amount", fee_share_amount),
+        ]));
+
+        hook_msgs.extend(
+            PairHookMsg::AfterSwap {
+                offer: order.offer_asset.clone(),
+                ask: return_asset,
+                trader: order.trader.clone(),
+            }
+            .into_submsgs(&config.hooks)?,
+        );
+    }
+
+    for (ind, pool) in pools.iter().enumerate() {
+        if let Some(fee_share) = &config.fee_share {
+            if !fee_shares[ind].is_zero() {
+                messages.extend(fee_share.into_msgs(&pool.info, fee_shares[ind])?);
+            }
+        }
+        if let Some(fee_address) = &fee_info.fee_address {
+            if !maker_fees[ind].is_zero() {
+                messages.push(
+                    pool.info
+                        .with_balance(maker_fees[ind])
+                        .into_msg(fee_address)?,
+                );
+            }
+        }
+    }
+
+    // The pool receives all filled offers and pays out returns, fee shares and maker fees
+    let new_pools = pools
+        .iter()
+        .enumerate()
+        .map(|(ind, pool)| {
+            let amount = pool
+                .amount
+                .checked_add(offered[ind])?
+                .checked_sub(paid_out[ind])?;
+            Ok(pool.info.with_balance(amount))
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    save_reserves(deps.storage, &new_pools, env.block.height)?;
+
+    STATS.record(
+        deps.storage,
+        &config.pair_info.asset_infos,
+        env.block.time.seconds(),
+        &stats,
+    )?;
+
+    let event = Event::new("settle_batch").add_attributes(vec![
+        attr("action", "settle_batch"),
+        attr("filled_orders", returns.len().to_string()),
+        attr("refunded_orders", rejected.len().to_string()),
+    ]);
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_submessages(hook_msgs)
+        .add_event(event)
+        .add_events(events))
+}
+
+/// Cancels a queued swap order and refunds the offer asset to the trader.
+/// Only the trader which placed the order can cancel it.
+pub fn cancel_batch_order(
+    deps: DepsMut,
+    sender: Addr,
+    order_id: u64,
+) -> Result<Response, ContractError> {
+    let order = BATCH_ORDERS.load(deps.storage, order_id)?;
+    if order.trader != sender {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    BATCH_ORDERS.remove(deps.storage, order_id);
+
+    let event = Event::new("cancel_batch_order").add_attributes(vec![
+        attr("action", "cancel_batch_order"),
+        attr("order_id", order_id.to_string()),
+        attr("sender", &order.trader),
+        attr("refund_asset", order.offer_asset.to_string()),
+    ]);
+
+    Ok(Response::new()
+        .add_message(order.offer_asset.into_msg(&order.trader)?)
+        .add_event(event))
+}
+
+/// Updates the pool configuration with the specified parameters in the `params` variable.
+///
+/// * **params** new parameter values.
+pub fn update_config(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    params: Binary,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    let factory_config = query_factory_config(&deps.querier, &config.factory_addr)?;
+
+    if info.sender != factory_config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let mut event = Event::new("update_config");
+    let mut messages: Vec<CosmosMsg> = vec![];
+
+    match from_json::<XYKPoolUpdateParams>(&params)? {
+        XYKPoolUpdateParams::EnableFeeShare {
+            fee_share_bps,
+            fee_share_address,
+        } => {
+            // Enable fee sharing for this contract
+            // If fee sharing is already enabled, we should be able to overwrite
+            // the values currently set
+
+            // Ensure the fee share isn't 0 and doesn't exceed the maximum allowed value
+            if fee_share_bps == 0 || fee_share_bps > MAX_FEE_SHARE_BPS {
+                return Err(ContractError::FeeShareOutOfBounds {});
+            }
+
+            // Set sharing config
+            config.fee_share = Some(FeeShareConfig::single(
+                deps.api.addr_validate(&fee_share_address)?,
+                fee_share_bps,
+            ));
+
+            CONFIG.save(deps.storage, &config)?;
 
             event = event
                 .add_attribute("action", "enable_fee_share")
                 .add_attribute("fee_share_bps", fee_share_bps.to_string())
                 .add_attribute("fee_share_address", fee_share_address);
         }
+        XYKPoolUpdateParams::EnableFeeShareRecipients { recipients } => {
+            let fee_share = check_fee_share_recipients(deps.api, recipients)?;
+
+            event = event
+                .add_attribute("action", "enable_fee_share")
+                .add_attribute("fee_share_bps", fee_share.total_bps().to_string())
+                .add_attribute(
+                    "fee_share_recipients",
+                    fee_share
+                        .recipients
+                        .iter()
+                        .map(|recipient| format!("{}:{}", recipient.recipient, recipient.bps))
+                        .collect::<Vec<_>>()
+                        .join(","),
+                );
+
+            config.fee_share = Some(fee_share);
+            CONFIG.save(deps.storage, &config)?;
+        }
         XYKPoolUpdateParams::DisableFeeShare => {
             // Disable fee sharing for this contract by setting bps and
             // address back to None
@@ -745,9 +1890,242 @@ pub fn update_config(
 
             event = event.add_attribute("action", "disable_fee_share");
         }
+        XYKPoolUpdateParams::EnableDynamicFee(dynamic_fee) => {
+            if dynamic_fee.min_fee_bps > dynamic_fee.max_fee_bps
+                || dynamic_fee.max_fee_bps > MAX_DYNAMIC_FEE_BPS
+                || !DYNAMIC_FEE_WINDOW_LIMITS.contains(&dynamic_fee.window)
+            {
+                return Err(ContractError::InvalidDynamicFeeParams {});
+            }
+
+            event = event
+                .add_attribute("action", "enable_dynamic_fee")
+                .add_attribute("min_fee_bps", dynamic_fee.min_fee_bps.to_string())
+                .add_attribute("max_fee_bps", dynamic_fee.max_fee_bps.to_string())
+                .add_attribute("window", dynamic_fee.window.to_string())
+                .add_attribute(
+                    "volatility_multiplier",
+                    dynamic_fee.volatility_multiplier.to_string(),
+                );
+
+            config.dynamic_fee = Some(dynamic_fee);
+            CONFIG.save(deps.storage, &config)?;
+        }
+        XYKPoolUpdateParams::DisableDynamicFee => {
+            config.dynamic_fee = None;
+            CONFIG.save(deps.storage, &config)?;
+
+            event = event.add_attribute("action", "disable_dynamic_fee");
+        }
+        XYKPoolUpdateParams::EnableProtocolFeeAccrual => {
+            config.protocol_fee_accrual = true;
+            CONFIG.save(deps.storage, &config)?;
+
+            // Only the growth from now on is charged
+            let pools = query_reserves(deps.storage, &config)?;
+            save_k_last(deps.storage, &config, &pools)?;
+
+            event = event.add_attribute("action", "enable_protocol_fee_accrual");
+        }
+        XYKPoolUpdateParams::DisableProtocolFeeAccrual => {
+            let (pools, total_share) = pool_info(deps.as_ref(), &config)?;
+            let (mint_msgs, protocol_share) =
+                mint_protocol_fee(deps.as_ref(), &env, &config, &pools, total_share)?;
+            messages.extend(mint_msgs);
+
+            config.protocol_fee_accrual = false;
+            CONFIG.save(deps.storage, &config)?;
+            save_k_last(deps.storage, &config, &pools)?;
+
+            event = event
+                .add_attribute("action", "disable_protocol_fee_accrual")
+                .add_attribute("protocol_fee_share", protocol_share);
+        }
+        XYKPoolUpdateParams::EnablePriceGuard { max_deviation } => {
+            if !PRICE_DEVIATION_LIMITS.contains(&max_deviation) {
+                return Err(ContractError::InvalidPriceDeviation {});
+            }
+
+            config.max_price_deviation = Some(max_deviation);
+            CONFIG.save(deps.storage, &config)?;
+
+            event = event
+                .add_attribute("action", "enable_price_guard")
+                .add_attribute("max_deviation", max_deviation.to_string());
+        }
+        XYKPoolUpdateParams::DisablePriceGuard => {
+            config.max_price_deviation = None;
+            CONFIG.save(deps.storage, &config)?;
+
+            event = event.add_attribute("action", "disable_price_guard");
+        }
+        XYKPoolUpdateParams::EnableBatchAuction => {
+            config.batch_auction = true;
+            CONFIG.save(deps.storage, &config)?;
+
+            event = event.add_attribute("action", "enable_batch_auction");
+        }
+        XYKPoolUpdateParams::DisableBatchAuction => {
+            config.batch_auction = false;
+            CONFIG.save(deps.storage, &config)?;
+
+            event = event.add_attribute("action", "disable_batch_auction");
+        }
+        XYKPoolUpdateParams::SetTraderAllowlist(allowlist) => {
+            let allowlist = check_trader_allowlist(deps.api, allowlist)?;
+
+            event = event.add_attribute("action", "set_trader_allowlist");
+            event = match &allowlist {
+                TraderAllowlist::Addresses(addresses) => event.add_attribute(
+                    "addresses",
+                    addresses
+                        .iter()
+                        .map(Addr::as_str)
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                ),
+                TraderAllowlist::Cw4Group(group_addr) => {
+                    event.add_attribute("cw4_group", group_addr)
+                }
+            };
+
+            config.trader_allowlist = Some(allowlist);
+            CONFIG.save(deps.storage, &config)?;
+        }
+        XYKPoolUpdateParams::RemoveTraderAllowlist => {
+            config.trader_allowlist = None;
+            CONFIG.save(deps.storage, &config)?;
+
+            event = event.add_attribute("action", "remove_trader_allowlist");
+        }
+        XYKPoolUpdateParams::SetHooks { hooks } => {
+            config.hooks = check_hooks(deps.api, hooks)?;
+            CONFIG.save(deps.storage, &config)?;
+
+            event = event.add_attribute("action", "set_hooks").add_attribute(
+                "hooks",
+                config
+                    .hooks
+                    .iter()
+                    .map(|hook| hook.contract_addr.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            );
+        }
     }
 
-    Ok(Response::new().add_event(event))
+    Ok(Response::new().add_messages(messages).add_event(event))
+}
+
+/// Validates fee share recipients: they must be unique, every share must be positive
+/// and the total share must not exceed [`MAX_FEE_SHARE_BPS`].
+fn check_fee_share_recipients(
+    api: &dyn Api,
+    recipients: Vec<(String, u16)>,
+) -> Result<FeeShareConfig, ContractError> {
+    if recipients.iter().any(|(_, bps)| *bps == 0)
+        || !(1..=MAX_FEE_SHARE_BPS as u32)
+            .contains(&recipients.iter().map(|(_, bps)| *bps as u32).sum())
+    {
+        return Err(ContractError::FeeShareOutOfBounds {});
+    }
+
+    let recipients = recipients
+        .into_iter()
+        .map(|(recipient, bps)| {
+            Ok(FeeShareRecipient {
+                recipient: api.addr_validate(&recipient)?,
+                bps,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    if recipients
+        .iter()
+        .map(|recipient| &recipient.recipient)
+        .collect::<HashSet<_>>()
+        .len()
+        != recipients.len()
+    {
+        return Err(ContractError::DuplicateFeeShareRecipients {});
+    }
+
+    Ok(FeeShareConfig { recipients })
+}
+
+/// Validates hooks: they must be unique, their number must not exceed [`MAX_HOOKS`]
+/// and their gas limits must be within [`HOOK_GAS_LIMIT`].
+fn check_hooks(api: &dyn Api, hooks: Vec<PairHook>) -> Result<Vec<PairHook>, ContractError> {
+    if hooks.len() > MAX_HOOKS
+        || hooks
+            .iter()
+            .map(|hook| &hook.contract_addr)
+            .collect::<HashSet<_>>()
+            .len()
+            != hooks.len()
+        || hooks
+            .iter()
+            .any(|hook| !HOOK_GAS_LIMIT.contains(&hook.gas_limit))
+    {
+        return Err(ContractError::InvalidHooks {});
+    }
+
+    hooks
+        .into_iter()
+        .map(|hook| {
+            Ok(PairHook {
+                contract_addr: api.addr_validate(hook.contract_addr.as_str())?,
+                ..hook
+            })
+        })
+        .collect()
+}
+
+/// Validates the trader allowlist: it must contain from 1 to [`MAX_ALLOWLIST_ADDRESSES`]
+/// unique addresses.
+fn check_trader_allowlist(
+    api: &dyn Api,
+    allowlist: TraderAllowlist,
+) -> Result<TraderAllowlist, ContractError> {
+    match allowlist {
+        TraderAllowlist::Addresses(addresses) => {
+            if addresses.is_empty()
+                || addresses.len() > MAX_ALLOWLIST_ADDRESSES
+                || addresses.iter().collect::<HashSet<_>>().len() != addresses.len()
+            {
+                return Err(ContractError::InvalidTraderAllowlist {});
+            }
+
+            Ok(TraderAllowlist::Addresses(
+                addresses
+                    .iter()
+                    .map(|addr| api.addr_validate(addr.as_str()))
+                    .collect::<StdResult<_>>()?,
+            ))
+        }
+        TraderAllowlist::Cw4Group(group_addr) => Ok(TraderAllowlist::Cw4Group(
+            api.addr_validate(group_addr.as_str())?,
+        )),
+    }
+}
+
+/// Returns an error if the pair has a trader allowlist and any of the addresses isn't on it.
+fn ensure_traders_allowed(
+    querier: QuerierWrapper,
+    config: &Config,
+    addrs: &[&Addr],
+) -> Result<(), ContractError> {
+    if let Some(allowlist) = &config.trader_allowlist {
+        for addr in addrs {
+            if !allowlist.is_allowed(&querier, addr)? {
+                return Err(ContractError::TraderNotAllowed {
+                    addr: (*addr).clone(),
+                });
+            }
+        }
+    }
+
+    Ok(())
 }
 
 /// Accumulate token prices for the assets in the pool.
@@ -791,6 +2169,292 @@ pub fn accumulate_prices(
     Ok(Some((pcl0, pcl1, block_time)))
 }
 
+/// Returns the price of asset 0 in asset 1 or zero if the pool is empty.
+fn spot_price(pools: &[Asset]) -> Decimal256 {
+    Decimal256::checked_from_ratio(pools[1].amount, pools[0].amount).unwrap_or_default()
+}
+
+/// Ensures the pool price after a swap stays within the allowed deviation
+/// from the price at the start of the block.
+///
+/// * **pools** are the pool reserves after the swap.
+fn ensure_price_deviation(config: &Config, pools: &[Asset]) -> Result<(), ContractError> {
+    let Some(max_deviation) = config.max_price_deviation else {
+        return Ok(());
+    };
+    if config.block_open_price.is_zero() {
+        return Ok(());
+    }
+
+    let deviation = price_deviation(config.block_open_price, spot_price(pools));
+    if deviation > Decimal256::from(max_deviation) {
+        return Err(ContractError::PriceDeviationExceeded {
+            deviation,
+            max_deviation,
+        });
+    }
+
+    Ok(())
+}
+
+/// Returns the identifier of the latest order pushing the pool price beyond the allowed deviation
+/// from the block open price if the batch is settled with the given returns.
+/// Fees staying in the pool are ignored thus the price move is estimated conservatively.
+///
+/// * **filled** are the orders together with their returns at the clearing price.
+fn find_price_guard_violation(
+    config: &Config,
+    pools: &[Asset],
+    filled: &[(BatchOrder, Uint128)],
+) -> Option<u64> {
+    let mut new_pools = pools.to_vec();
+    for (order, return_amount) in filled {
+        let (offer_ind, ask_ind) = if order.offer_asset.info.equal(&pools[0].info) {
+            (0, 1)
+        } else {
+            (1, 0)
+        };
+        new_pools[offer_ind].amount += order.offer_asset.amount;
+        new_pools[ask_ind].amount = new_pools[ask_ind].amount.saturating_sub(*return_amount);
+    }
+    ensure_price_deviation(config, &new_pools).err()?;
+
+    // The price of asset 0 goes down if it is offered in excess
+    let excess_ind = if spot_price(&new_pools) < config.block_open_price {
+        0
+    } else {
+        1
+    };
+    filled
+        .iter()
+        .filter(|(order, _)| order.offer_asset.info.equal(&pools[excess_ind].info))
+        .map(|(order, _)| order.order_id)
+        .max()
+}
+
+/// Records the latest cumulative prices from the config in the observations buffer.
+fn save_observation(storage: &mut dyn Storage, config: &Config) -> StdResult<()> {
+    let asset_infos = &config.pair_info.asset_infos;
+    let observation = Observation {
+        timestamp: config.block_time_last,
+        cumulative_prices: vec![
+            (
+                asset_infos[0].clone(),
+                asset_infos[1].clone(),
+                config.price0_cumulative_last,
+            ),
+            (
+                asset_infos[1].clone(),
+                asset_infos[0].clone(),
+                config.price1_cumulative_last,
+            ),
+        ],
+    };
+
+    store_observation(storage, &OBSERVATIONS, &observation)
+}
+
+/// Returns the fee info from the factory. If the dynamic fee is enabled, the total fee rate
+/// is adjusted according to the recent price volatility.
+///
+/// * **pools** are the pool reserves before the swap.
+fn query_swap_fee_info(
+    deps: Deps,
+    env: &Env,
+    config: &Config,
+    pools: &[Asset],
+) -> StdResult<FeeInfo> {
+    let mut fee_info = query_fee_info(
+        &deps.querier,
+        &config.factory_addr,
+        config.pair_info.pair_type.clone(),
+        &config.pair_info.contract_addr,
+    )?;
+
+    if let Some(dynamic_fee) = &config.dynamic_fee {
+        fee_info.total_fee_rate = compute_dynamic_fee_rate(
+            deps.storage,
+            env,
+            config,
+            pools,
+            dynamic_fee,
+            fee_info.total_fee_rate,
+        )?;
+    }
+
+    // The maker fee stays in the pool and is minted later as LP tokens.
+    // Nothing is minted if the factory has no fee address
+    if config.protocol_fee_accrual && fee_info.fee_address.take().is_none() {
+        fee_info.maker_fee_rate = Decimal::zero();
+    }
+
+    Ok(fee_info)
+}
+
+/// Builds messages minting LP tokens to the fee address for the maker share of the pool growth
+/// since the last liquidity event if the protocol fee accrual is enabled.
+/// Returns the messages and the amount of LP tokens to mint.
+///
+/// * **pools** are the current pool reserves.
+///
+/// * **total_share** is the LP token supply before minting.
+fn mint_protocol_fee(
+    deps: Deps,
+    env: &Env,
+    config: &Config,
+    pools: &[Asset],
+    total_share: Uint128,
+) -> Result<(Vec<CosmosMsg>, Uint128), ContractError> {
+    let k_last = K_LAST.may_load(deps.storage)?.unwrap_or_default();
+    mint_protocol_fee_since(deps, env, config, pools, k_last, total_share)
+}
+
+/// Same as [`mint_protocol_fee`], but measures the pool growth from the given **k_last**.
+fn mint_protocol_fee_since(
+    deps: Deps,
+    env: &Env,
+    config: &Config,
+    pools: &[Asset],
+    k_last: Uint256,
+    total_share: Uint128,
+) -> Result<(Vec<CosmosMsg>, Uint128), ContractError> {
+    if !config.protocol_fee_accrual || k_last.is_zero() || total_share.is_zero() {
+        return Ok((vec![], Uint128::zero()));
+    }
+
+    let fee_info = query_fee_info(
+        &deps.querier,
+        &config.factory_addr,
+        config.pair_info.pair_type.clone(),
+        &config.pair_info.contract_addr,
+    )?;
+    let Some(fee_address) = fee_info.fee_address else {
+        return Ok((vec![], Uint128::zero()));
+    };
+
+    let protocol_share = calc_protocol_fee_share(
+        total_share,
+        Decimal256::from_ratio(pools_k(pools).isqrt(), 1u8),
+        Decimal256::from_ratio(k_last.isqrt(), 1u8),
+        fee_info.maker_fee_rate,
+    )?;
+    if protocol_share.is_zero() {
+        return Ok((vec![], protocol_share));
+    }
+
+    let messages = mint_liquidity_token_message(
+        deps.querier,
+        config,
+        &env.contract.address,
+        &fee_address,
+        protocol_share,
+        false,
+    )?;
+
+    Ok((messages, protocol_share))
+}
+
+/// Returns the maker fee which stays in the pool if the protocol fee accrual is enabled.
+/// The fee is minted later as LP tokens to the fee address instead of being transferred.
+///
+/// * **commission_amount** is the swap fee left after the fee share.
+fn accrued_maker_fee(
+    config: &Config,
+    maker_fee_rate: Decimal,
+    commission_amount: Uint128,
+) -> Uint128 {
+    if config.protocol_fee_accrual {
+        commission_amount.dec_mul(maker_fee_rate)
+    } else {
+        Uint128::zero()
+    }
+}
+
+/// Records the product of the pool reserves after a liquidity event
+/// if the protocol fee accrual is enabled.
+fn save_k_last(storage: &mut dyn Storage, config: &Config, pools: &[Asset]) -> StdResult<()> {
+    if config.protocol_fee_accrual {
+        K_LAST.save(storage, &pools_k(pools))
+    } else {
+        K_LAST.remove(storage);
+        Ok(())
+    }
+}
+
+fn pools_k(pools: &[Asset]) -> Uint256 {
+    pools[0].amount.full_mul(pools[1].amount)
+}
+
+/// Computes the swap fee rate from the relative deviation of the spot price from the TWAP
+/// over the last `window` seconds: `base_fee_rate + volatility_multiplier * |spot - twap| / twap`.
+/// The result is clamped to the configured bounds. The base fee rate is used
+/// if the observations don't cover the window yet.
+///
+/// * **base_fee_rate** is the fee rate configured in the factory.
+pub fn compute_dynamic_fee_rate(
+    storage: &dyn Storage,
+    env: &Env,
+    config: &Config,
+    pools: &[Asset],
+    dynamic_fee: &DynamicFeeParams,
+    base_fee_rate: Decimal,
+) -> StdResult<Decimal> {
+    let min_fee_rate = Decimal::from_ratio(dynamic_fee.min_fee_bps, 10000u16);
+    let max_fee_rate = Decimal::from_ratio(dynamic_fee.max_fee_bps, 10000u16);
+
+    let (x, y) = (pools[0].amount, pools[1].amount);
+    if x.is_zero() || y.is_zero() {
+        return Ok(base_fee_rate.clamp(min_fee_rate, max_fee_rate));
+    }
+
+    let (price0_cumulative, price1_cumulative, timestamp) =
+        accumulate_prices(env.clone(), config, x, y)?.unwrap_or((
+            config.price0_cumulative_last,
+            config.price1_cumulative_last,
+            config.block_time_last,
+        ));
+    let asset_infos = &config.pair_info.asset_infos;
+    let current = Observation {
+        timestamp,
+        cumulative_prices: vec![
+            (
+                asset_infos[0].clone(),
+                asset_infos[1].clone(),
+                price0_cumulative,
+            ),
+            (
+                asset_infos[1].clone(),
+                asset_infos[0].clone(),
+                price1_cumulative,
+            ),
+        ],
+    };
+
+    // Not enough price history to measure volatility
+    let Ok(past) = query_observations(storage, &OBSERVATIONS, &current, vec![dynamic_fee.window])
+    else {
+        return Ok(base_fee_rate.clamp(min_fee_rate, max_fee_rate));
+    };
+
+    let twap = Decimal::from_ratio(
+        price0_cumulative.wrapping_sub(past[0].cumulative_prices[0].2),
+        Uint128::from(dynamic_fee.window) * Uint128::from(10u128.pow(TWAP_PRECISION.into())),
+    );
+    if twap.is_zero() {
+        return Ok(base_fee_rate.clamp(min_fee_rate, max_fee_rate));
+    }
+
+    let spot = Decimal::from_ratio(y, x);
+    let deviation = spot
+        .abs_diff(twap)
+        .checked_div(twap)
+        .unwrap_or(Decimal::MAX);
+
+    Ok(base_fee_rate
+        .saturating_add(deviation.saturating_mul(dynamic_fee.volatility_multiplier))
+        .clamp(min_fee_rate, max_fee_rate))
+}
+
 /// Calculates the amount of fees the Maker contract gets according to specified pair parameters.
 /// Returns a [`None`] if the Maker fee is zero, otherwise returns a [`Asset`] struct with the specified attributes.
 ///
@@ -837,10 +2501,22 @@ pub fn calculate_maker_fee(
 /// * **QueryMsg::Config {}** Returns the configuration for the pair contract using a [`ConfigResponse`] object.
 ///
 /// * **QueryMsg::AssetBalanceAt { asset_info, block_height }** Returns the balance of the specified asset that was in the pool
-/// just preceeding the moment of the specified block height creation.
-/// * **QueryMsg::SimulateWithdraw { lp_amount }** Returns the amount of assets that could be withdrawn from the pool
+/// just preceding the moment of the specified block height creation.
+///
+/// * **QueryMsg::SimulateWithdraw { lp_amount, .. }** Returns the amount of assets that could be withdrawn from the pool
 /// using a specific amount of LP tokens. The result is returned in a vector that contains objects of type [`Asset`].
+/// Single asset withdrawals are not supported.
 /// * **QueryMsg::SimulateProvide { msg }** Simulates the liquidity provision in the pair contract.
+///
+/// * **QueryMsg::Observe { seconds_ago }** Returns cumulative prices at each moment `seconds_ago`
+/// from the current block time using a vector of [`Observation`] objects.
+///
+/// * **QueryMsg::Stats { epoch }** Returns swap volumes and fees accumulated during the epoch
+/// and over the pair lifetime in a [`StatsResponse`] object.
+///
+/// * **QueryMsg::BatchOrder { order_id }** Returns a queued swap order in a [`BatchOrder`] object.
+///
+/// * **QueryMsg::BatchOrders { trader, start_after, limit }** Returns queued swap orders.
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
@@ -848,18 +2524,47 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
         QueryMsg::Pool {} => to_json_binary(&query_pool(deps)?),
         QueryMsg::Share { amount } => to_json_binary(&query_share(deps, amount)?),
         QueryMsg::Simulation { offer_asset, .. } => {
-            to_json_binary(&query_simulation(deps, offer_asset)?)
+            to_json_binary(&query_simulation(deps, env, offer_asset)?)
         }
         QueryMsg::ReverseSimulation { ask_asset, .. } => {
-            to_json_binary(&query_reverse_simulation(deps, ask_asset)?)
+            to_json_binary(&query_reverse_simulation(deps, env, ask_asset)?)
         }
         QueryMsg::CumulativePrices {} => to_json_binary(&query_cumulative_prices(deps, env)?),
         QueryMsg::Config {} => to_json_binary(&query_config(deps)?),
-        QueryMsg::SimulateWithdraw { lp_amount } => to_json_binary(&query_share(deps, lp_amount)?),
+        QueryMsg::SimulateWithdraw { lp_amount, asset } => {
+            if asset.is_some() {
+                return Err(StdError::generic_err(
+                    ContractError::NonSupported {}.to_string(),
+                ));
+            }
+
+            to_json_binary(&query_share(deps, lp_amount)?)
+        }
         QueryMsg::SimulateProvide {
             assets,
             slippage_tolerance,
-        } => to_json_binary(&query_simulate_provide(deps, assets, slippage_tolerance)?),
+        } => to_json_binary(&query_simulate_provide(
+            deps,
+            env,
+            assets,
+            slippage_tolerance,
+        )?),
+        QueryMsg::AssetBalanceAt {
+            asset_info,
+            block_height,
+        } => to_json_binary(&query_asset_balance_at(deps, asset_info, block_height)?),
+        QueryMsg::Observe { seconds_ago } => {
+            to_json_binary(&query_observe(deps, env, seconds_ago)?)
+        }
+        QueryMsg::Stats { epoch } => to_json_binary(&query_stats(deps, env, epoch)?),
+        QueryMsg::BatchOrder { order_id } => {
+            to_json_binary(&BATCH_ORDERS.load(deps.storage, order_id)?)
+        }
+        QueryMsg::BatchOrders {
+            trader,
+            start_after,
+            limit,
+        } => to_json_binary(&query_batch_orders(deps, trader, start_after, limit)?),
         _ => Err(StdError::generic_err("Query is not supported")),
     }
 }
@@ -868,7 +2573,7 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
 /// tokens currently minted in an object of type [`PoolResponse`].
 pub fn query_pool(deps: Deps) -> StdResult<PoolResponse> {
     let config = CONFIG.load(deps.storage)?;
-    let (assets, total_share) = pool_info(deps.querier, &config)?;
+    let (assets, total_share) = pool_info(deps, &config)?;
 
     let resp = PoolResponse {
         assets,
@@ -884,7 +2589,7 @@ pub fn query_pool(deps: Deps) -> StdResult<PoolResponse> {
 /// * **amount** is the amount of LP tokens for which we calculate associated amounts of assets.
 pub fn query_share(deps: Deps, amount: Uint128) -> StdResult<Vec<Asset>> {
     let config = CONFIG.load(deps.storage)?;
-    let (pools, total_share) = pool_info(deps.querier, &config)?;
+    let (pools, total_share) = pool_info(deps, &config)?;
     let refund_assets = get_share_in_assets(&pools, amount, total_share);
 
     Ok(refund_assets)
@@ -893,12 +2598,10 @@ pub fn query_share(deps: Deps, amount: Uint128) -> StdResult<Vec<Asset>> {
 /// Returns information about a swap simulation in a [`SimulationResponse`] object.
 ///
 /// * **offer_asset** is the asset to swap as well as an amount of the said asset.
-pub fn query_simulation(deps: Deps, offer_asset: Asset) -> StdResult<SimulationResponse> {
+pub fn query_simulation(deps: Deps, env: Env, offer_asset: Asset) -> StdResult<SimulationResponse> {
     let config = CONFIG.load(deps.storage)?;
 
-    let pools = config
-        .pair_info
-        .query_pools(&deps.querier, &config.pair_info.contract_addr)?;
+    let pools = query_reserves(deps.storage, &config)?;
 
     let offer_pool: Asset;
     let ask_pool: Asset;
@@ -915,11 +2618,7 @@ pub fn query_simulation(deps: Deps, offer_asset: Asset) -> StdResult<SimulationR
     }
 
     // Get fee info from the factory contract
-    let fee_info = query_fee_info(
-        &deps.querier,
-        config.factory_addr,
-        config.pair_info.pair_type,
-    )?;
+    let fee_info = query_swap_fee_info(deps, &env, &config, &pools)?;
 
     let (return_amount, spread_amount, commission_amount) = compute_swap(
         offer_pool.amount,
@@ -941,13 +2640,12 @@ pub fn query_simulation(deps: Deps, offer_asset: Asset) -> StdResult<SimulationR
 ///   assets to receive from the swap.
 pub fn query_reverse_simulation(
     deps: Deps,
+    env: Env,
     ask_asset: Asset,
 ) -> StdResult<ReverseSimulationResponse> {
     let config = CONFIG.load(deps.storage)?;
 
-    let pools = config
-        .pair_info
-        .query_pools(&deps.querier, &config.pair_info.contract_addr)?;
+    let pools = query_reserves(deps.storage, &config)?;
 
     let offer_pool: Asset;
     let ask_pool: Asset;
@@ -964,11 +2662,7 @@ pub fn query_reverse_simulation(
     }
 
     // Get fee info from factory
-    let fee_info = query_fee_info(
-        &deps.querier,
-        config.factory_addr,
-        config.pair_info.pair_type,
-    )?;
+    let fee_info = query_swap_fee_info(deps, &env, &config, &pools)?;
 
     let (offer_amount, spread_amount, commission_amount) = compute_offer_amount(
         offer_pool.amount,
@@ -984,10 +2678,21 @@ pub fn query_reverse_simulation(
     })
 }
 
+/// Returns the balance of the specified asset that was in the pool just preceding the moment
+/// of the specified block height creation. Returns `None` if the pool balance had never been
+/// recorded before the given block height.
+pub fn query_asset_balance_at(
+    deps: Deps,
+    asset_info: AssetInfo,
+    block_height: Uint64,
+) -> StdResult<Option<Uint128>> {
+    BALANCES.may_load_at_height(deps.storage, &asset_info, block_height.u64())
+}
+
 /// Returns information about cumulative prices for the assets in the pool using a [`CumulativePricesResponse`] object.
 pub fn query_cumulative_prices(deps: Deps, env: Env) -> StdResult<CumulativePricesResponse> {
     let config = CONFIG.load(deps.storage)?;
-    let (assets, total_share) = pool_info(deps.querier, &config)?;
+    let (assets, total_share) = pool_info(deps, &config)?;
 
     let mut price0_cumulative_last = config.price0_cumulative_last;
     let mut price1_cumulative_last = config.price1_cumulative_last;
@@ -1021,6 +2726,54 @@ pub fn query_cumulative_prices(deps: Deps, env: Env) -> StdResult<CumulativePric
     Ok(resp)
 }
 
+/// Returns cumulative prices interpolated from the recorded observations at each moment
+/// `seconds_ago` from the current block time.
+pub fn query_observe(deps: Deps, env: Env, seconds_ago: Vec<u64>) -> StdResult<Vec<Observation>> {
+    let current = Observation {
+        timestamp: env.block.time.seconds(),
+        cumulative_prices: query_cumulative_prices(deps, env)?.cumulative_prices,
+    };
+
+    query_observations(deps.storage, &OBSERVATIONS, &current, seconds_ago)
+}
+
+/// Returns swap volumes and fees accumulated during the given **epoch** (the current one
+/// by default) and over the pair lifetime in a [`StatsResponse`] object.
+pub fn query_stats(deps: Deps, env: Env, epoch: Option<u64>) -> StdResult<StatsResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let epoch = epoch.unwrap_or_else(|| epoch_at(env.block.time.seconds()));
+
+    STATS.query(deps.storage, &config.pair_info.asset_infos, epoch)
+}
+
+/// Returns queued batch orders in ascending order of their identifiers.
+///
+/// * **trader** if set, only the orders placed by this address are returned.
+pub fn query_batch_orders(
+    deps: Deps,
+    trader: Option<String>,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<Vec<BatchOrder>> {
+    let trader = addr_opt_validate(deps.api, &trader)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+    BATCH_ORDERS
+        .range(
+            deps.storage,
+            start_after.map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .filter(|item| match (item, &trader) {
+            (Ok((_, order)), Some(trader)) => order.trader == trader,
+            _ => true,
+        })
+        .take(limit)
+        .map(|item| item.map(|(_, order)| order))
+        .collect()
+}
+
 /// Returns the pair contract configuration in a [`ConfigResponse`] object.
 pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
     let config: Config = CONFIG.load(deps.storage)?;
@@ -1031,6 +2784,12 @@ pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
         block_time_last: config.block_time_last,
         params: Some(to_json_binary(&XYKPoolConfig {
             fee_share: config.fee_share,
+            hooks: config.hooks,
+            dynamic_fee: config.dynamic_fee,
+            protocol_fee_accrual: config.protocol_fee_accrual,
+            max_price_deviation: config.max_price_deviation,
+            batch_auction: config.batch_auction,
+            trader_allowlist: config.trader_allowlist,
         })?),
         owner: factory_config.owner,
         factory_addr: config.factory_addr,
@@ -1046,23 +2805,34 @@ pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
 ///
 fn query_simulate_provide(
     deps: Deps,
+    env: Env,
     assets: Vec<Asset>,
     slippage_tolerance: Option<Decimal>,
 ) -> StdResult<Uint128> {
     let config = CONFIG.load(deps.storage)?;
 
-    let pools = config
-        .pair_info
-        .query_pools(&deps.querier, &config.pair_info.contract_addr)?;
+    let pools = query_reserves(deps.storage, &config)?;
 
     let deposits = get_deposits_from_assets(deps, &assets, &pools)
         .map_err(|e| StdError::generic_err(e.to_string()))?;
 
-    let total_share = query_supply(&deps.querier, &config.pair_info.liquidity_token)?;
-    let share = calculate_shares(&deposits, &pools, total_share, slippage_tolerance)
-        .map_err(|e| StdError::generic_err(e.to_string()))?;
+    let total_share = query_lp_supply(
+        &deps.querier,
+        &config.pair_info.liquidity_token,
+        config.native_lp_token,
+    )?;
+    let outcome = compute_provide(
+        deps,
+        &env,
+        &config,
+        deposits,
+        &pools,
+        total_share,
+        slippage_tolerance,
+    )
+    .map_err(|e| StdError::generic_err(e.to_string()))?;
 
-    Ok(share)
+    Ok(outcome.share)
 }
 
 /// Returns the result of a swap.
@@ -1110,6 +2880,72 @@ pub fn compute_swap(
     ))
 }
 
+/// Returns the amounts of the ask asset each order receives before fees when the batch is
+/// cleared at a single uniform price.
+///
+/// The orders of the side offering more than the other side can take at the pool price
+/// are matched with the other side and the excess is swapped through the pool with
+/// [`compute_swap`]. The excess is chosen so that the average price of this swap equals
+/// the price the matched orders trade at, i.e. `(ask_pool + ask_offered) / (offer_pool + offer_offered)`.
+///
+/// * **pools** are the pool reserves before the settlement.
+///
+/// * **orders** are the orders to settle.
+pub fn clear_batch(pools: &[Asset], orders: &[BatchOrder]) -> StdResult<Vec<Uint128>> {
+    if pools.iter().any(|pool| pool.amount.is_zero()) {
+        return Err(StdError::generic_err("One of the pools is empty"));
+    }
+
+    let offered = pools
+        .iter()
+        .map(|pool| {
+            orders
+                .iter()
+                .filter(|order| order.offer_asset.info.equal(&pool.info))
+                .map(|order| order.offer_asset.amount)
+                .sum::<Uint128>()
+        })
+        .collect::<Vec<_>>();
+
+    let (offer_ind, ask_ind) =
+        if offered[0].full_mul(pools[1].amount) >= offered[1].full_mul(pools[0].amount) {
+            (0, 1)
+        } else {
+            (1, 0)
+        };
+    let (offer_pool, ask_pool) = (pools[offer_ind].amount, pools[ask_ind].amount);
+
+    let excess_amount: Uint128 = ((offered[offer_ind].full_mul(ask_pool)
+        - offered[ask_ind].full_mul(offer_pool))
+        / (Uint256::from(ask_pool) + Uint256::from(offered[ask_ind])))
+    .try_into()?;
+    let swap_return = if excess_amount.is_zero() {
+        Uint128::zero()
+    } else {
+        compute_swap(offer_pool, ask_pool, excess_amount, Decimal::zero())?.0
+    };
+
+    // The total amounts each side receives
+    let mut cleared = [Uint128::zero(); 2];
+    cleared[offer_ind] = offered[ask_ind] + swap_return;
+    cleared[ask_ind] = offered[offer_ind] - excess_amount;
+
+    Ok(orders
+        .iter()
+        .map(|order| {
+            let ind = if order.offer_asset.info.equal(&pools[0].info) {
+                0
+            } else {
+                1
+            };
+            order
+                .offer_asset
+                .amount
+                .multiply_ratio(cleared[ind], offered[ind])
+        })
+        .collect())
+}
+
 /// Returns an amount of offer assets for a specified amount of ask assets.
 ///
 /// * **offer_pool** total amount of offer assets in the pool.
@@ -1133,13 +2969,14 @@ pub fn compute_offer_amount(
     let one_minus_commission = Decimal256::one() - Decimal256::from(commission_rate);
     let inv_one_minus_commission = Decimal256::one() / one_minus_commission;
 
+    // The offer amount is rounded up so that the pool doesn't lose on rounding
     let offer_amount: Uint128 = cp
-        .multiply_ratio(
+        .mul_ceil((
             Uint256::one(),
             Uint256::from(ask_pool.checked_sub(
                 (Uint256::from(ask_amount).dec_mul(inv_one_minus_commission)).try_into()?,
             )?),
-        )
+        ))
         .checked_sub(offer_pool.into())?
         .try_into()?;
 
@@ -1153,7 +2990,24 @@ pub fn compute_offer_amount(
     Ok((offer_amount, spread_amount, commission_amount))
 }
 
-/// Returns shares for the provided deposits.
+/// Result of a liquidity provision computed by [`compute_provide`].
+struct ProvideOutcome {
+    /// Amount of LP tokens to mint
+    share: Uint128,
+    /// Pool reserves after the provision
+    new_pools: Vec<Asset>,
+    /// Fee transfers of the internal swap
+    messages: Vec<CosmosMsg>,
+    /// Attributes describing the internal swap
+    attributes: Vec<Attribute>,
+    /// Statistics deltas of the internal swap
+    stats: Vec<AssetStats>,
+}
+
+/// Computes LP tokens minted for the deposits and the resulting pool reserves.
+/// If the deposits don't match the pool ratio, the excess part is swapped internally
+/// (charging the usual swap fees) so that the remaining deposits match the new pool ratio.
+/// This allows providing a single asset to a non-empty pool.
 ///
 /// * **deposits** is an array with asset amounts
 ///
@@ -1163,11 +3017,227 @@ pub fn compute_offer_amount(
 ///
 /// * **slippage_tolerance** is an optional parameter which is used to specify how much
 ///   the pool price can move until the provide liquidity transaction goes through.
+fn compute_provide(
+    deps: Deps,
+    env: &Env,
+    config: &Config,
+    mut deposits: [Uint128; 2],
+    pools: &[Asset],
+    total_share: Uint128,
+    slippage_tolerance: Option<Decimal>,
+) -> Result<ProvideOutcome, ContractError> {
+    let mut new_pools = pools.to_vec();
+    let mut messages = vec![];
+    let mut attributes = vec![];
+    let mut stats = vec![];
+    let mut total_share = total_share;
+
+    if !total_share.is_zero() {
+        if !deposits[0].is_zero() && !deposits[1].is_zero() {
+            assert_slippage_tolerance(slippage_tolerance, &deposits, pools)?;
+        }
+
+        // Find the asset provided in excess of the pool ratio
+        let lhs = deposits[0].full_mul(pools[1].amount);
+        let rhs = deposits[1].full_mul(pools[0].amount);
+        let offer_ind = match lhs.cmp(&rhs) {
+            Ordering::Greater => Some(0),
+            Ordering::Less => Some(1),
+            Ordering::Equal => None,
+        };
+
+        if let Some(offer_ind) = offer_ind {
+            let ask_ind = 1 - offer_ind;
+            let offer_pool = pools[offer_ind].clone();
+            let ask_pool = pools[ask_ind].clone();
+
+            let fee_info = query_swap_fee_info(deps, env, config, pools)?;
+
+            let offer_amount = balancing_swap_amount(
+                deposits[offer_ind],
+                deposits[ask_ind],
+                offer_pool.amount,
+                ask_pool.amount,
+                fee_info.total_fee_rate,
+            )?;
+
+            if !offer_amount.is_zero() {
+                // The balancing swap would bypass the batch auction
+                if config.batch_auction {
+                    return Err(ContractError::ImbalancedProvideInBatchMode {});
+                }
+
+                let (return_amount, spread_amount, commission_amount) = compute_swap(
+                    offer_pool.amount,
+                    ask_pool.amount,
+                    offer_amount,
+                    fee_info.total_fee_rate,
+                )?;
+
+                assert_max_spread(
+                    None,
+                    slippage_tolerance,
+                    offer_amount,
+                    return_amount + commission_amount,
+                    spread_amount,
+                )?;
+
+                let mut fees_commission_amount = commission_amount;
+                let mut fee_share_amount = Uint128::zero();
+                if let Some(fee_share) = config.fee_share.clone() {
+                    let share_fee_rate = Decimal::from_ratio(fee_share.total_bps(), 10000u16);
+                    fee_share_amount = fees_commission_amount.dec_mul(share_fee_rate);
+
+                    if !fee_share_amount.is_zero() {
+                        fees_commission_amount =
+                            fees_commission_amount.saturating_sub(fee_share_amount);
+
+                        messages.extend(fee_share.into_msgs(&ask_pool.info, fee_share_amount)?);
+                    }
+                }
+
+                let mut maker_fee_amount = Uint128::zero();
+                if let Some(fee_address) = fee_info.fee_address {
+                    if let Some(f) = calculate_maker_fee(
+                        &ask_pool.info,
+                        fees_commission_amount,
+                        fee_info.maker_fee_rate,
+                    ) {
+                        maker_fee_amount = f.amount;
+                        messages.push(f.into_msg(fee_address)?);
+                    }
+                }
+
+                // The swapped amounts stay in the pool as a part of the deposits
+                new_pools[offer_ind].amount = offer_pool.amount.checked_add(offer_amount)?;
+                new_pools[ask_ind].amount = ask_pool
+                    .amount
+                    .checked_sub(return_amount + fee_share_amount + maker_fee_amount)?;
+                deposits[offer_ind] = deposits[offer_ind].checked_sub(offer_amount)?;
+                deposits[ask_ind] = deposits[ask_ind].checked_add(return_amount)?;
+
+                // The maker share of the swap fee accrued in the pool is minted
+                // before the deposits are added, as they reset the recorded k
+                let (mint_msgs, protocol_share) = mint_protocol_fee_since(
+                    deps,
+                    env,
+                    config,
+                    &new_pools,
+                    pools_k(pools),
+                    total_share,
+                )?;
+                messages.extend(mint_msgs);
+                total_share += protocol_share;
+
+                let protocol_fee_amount = maker_fee_amount
+                    + accrued_maker_fee(config, fee_info.maker_fee_rate, fees_commission_amount);
+                stats.extend(AssetStats::swap(
+                    &offer_pool.info.with_balance(offer_amount),
+                    &ask_pool.info.with_balance(return_amount),
+                    fees_commission_amount.checked_sub(protocol_fee_amount)?,
+                    protocol_fee_amount,
+                    fee_share_amount,
+                ));
+
+                attributes.extend([
+                    attr("offer_asset", offer_pool.info.to_string()),
+                    attr("ask_asset", ask_pool.info.to_string()),
+                    attr("offer_amount", offer_amount),
+                    attr("return_amount", return_amount),
+                    attr("spread_amount", spread_amount),
+                    attr("commission_amount", commission_amount),
+                    attr("maker_fee_amount", maker_fee_amount),
+                    attr("fee_share_amount", fee_share_amount),
+                ]);
+            }
+        }
+    }
+
+    let share = calculate_shares(&deposits, &new_pools, total_share)?;
+
+    for (pool, deposit) in new_pools.iter_mut().zip(deposits) {
+        pool.amount = pool.amount.checked_add(deposit)?;
+    }
+
+    Ok(ProvideOutcome {
+        share,
+        new_pools,
+        messages,
+        attributes,
+        stats,
+    })
+}
+
+/// Returns the amount of the excess deposit asset which has to be swapped so that the rest of
+/// the deposits matches the pool ratio after the swap.
+///
+/// The amount is `t * offer_pool` where `t` is the positive root of
+/// `(1 + v) * t^2 + (2 * (1 + v) - f * (1 + u)) * t - (u - v) = 0`,
+/// `u` and `v` are the offer and ask deposits relative to their pools
+/// and `f` is the commission rate charged from the ask asset.
+///
+/// * **offer_deposit** deposit of the asset provided in excess.
+///
+/// * **ask_deposit** deposit of the other asset.
+///
+/// * **offer_pool** total amount of offer assets in the pool.
+///
+/// * **ask_pool** total amount of ask assets in the pool.
+///
+/// * **commission_rate** total amount of fees charged for the swap.
+pub fn balancing_swap_amount(
+    offer_deposit: Uint128,
+    ask_deposit: Uint128,
+    offer_pool: Uint128,
+    ask_pool: Uint128,
+    commission_rate: Decimal,
+) -> StdResult<Uint128> {
+    let one = Decimal256::one();
+    let two = Decimal256::from_ratio(2u8, 1u8);
+    let u = Decimal256::from_ratio(offer_deposit, offer_pool);
+    let v = Decimal256::from_ratio(ask_deposit, ask_pool);
+    let f = Decimal256::from(commission_rate);
+
+    let a = one.checked_add(v)?;
+    let c = u.checked_sub(v)?;
+    let b_pos = two.checked_mul(a)?;
+    let b_neg = f.checked_mul(one.checked_add(u)?)?;
+    // The discriminant part which doesn't depend on the sign of the linear coefficient
+    let d = two.checked_mul(two)?.checked_mul(a)?.checked_mul(c)?;
+
+    let t = if b_pos >= b_neg {
+        // 2c / (b + sqrt(b^2 + 4ac)) avoids the cancellation of close values
+        let b = b_pos - b_neg;
+        let sqrt_d = b.checked_mul(b)?.checked_add(d)?.sqrt();
+        two.checked_mul(c)?
+            .checked_div(b.checked_add(sqrt_d)?)
+            .map_err(|e| StdError::generic_err(e.to_string()))?
+    } else {
+        let b = b_neg - b_pos;
+        let sqrt_d = b.checked_mul(b)?.checked_add(d)?.sqrt();
+        sqrt_d
+            .checked_add(b)?
+            .checked_div(two.checked_mul(a)?)
+            .map_err(|e| StdError::generic_err(e.to_string()))?
+    };
+
+    let amount: Uint128 = Uint256::from(offer_pool).dec_mul(t).try_into()?;
+
+    Ok(amount.min(offer_deposit))
+}
+
+/// Returns shares for the provided deposits.
+/// The deposits are expected to match the pool ratio which is ensured by [`compute_provide`].
+///
+/// * **deposits** is an array with asset amounts
+///
+/// * **pools** is an array with total amount of assets in the pool
+///
+/// * **total_share** is the total amount of LP tokens currently minted
 pub fn calculate_shares(
     deposits: &[Uint128; 2],
     pools: &[Asset],
     total_share: Uint128,
-    slippage_tolerance: Option<Decimal>,
 ) -> Result<Uint128, ContractError> {
     let share = if total_share.is_zero() {
         // Initial share = collateral amount
@@ -1184,9 +3254,6 @@ pub fn calculate_shares(
 
         share
     } else {
-        // Assert slippage tolerance
-        assert_slippage_tolerance(slippage_tolerance, deposits, pools)?;
-
         // min(1, 2)
         // 1. sqrt(deposit_0 * exchange_rate_0_to_1 * deposit_0) * (total_share / sqrt(pool_0 * pool_0))
         // == deposit_0 * total_share / pool_0
@@ -1201,6 +3268,7 @@ pub fn calculate_shares(
 }
 
 /// Verify assets provided and returns deposit amounts.
+/// A single asset can be provided only if the pool is not empty.
 ///
 /// * **assets** is an array with assets available in the pool.
 ///
@@ -1210,26 +3278,33 @@ pub fn get_deposits_from_assets(
     assets: &[Asset],
     pools: &[Asset],
 ) -> Result<[Uint128; 2], ContractError> {
-    if assets.len() != 2 {
-        return Err(StdError::generic_err("asset_infos must contain exactly two elements").into());
+    if assets.is_empty() || assets.len() > 2 {
+        return Err(StdError::generic_err("assets must contain one or two elements").into());
+    }
+    for asset in assets {
+        asset.info.check(deps.api)?;
+        if !pools.iter().any(|pool| pool.info.equal(&asset.info)) {
+            return Err(ContractError::AssetMismatch {});
+        }
     }
-    assets[0].info.check(deps.api)?;
-    assets[1].info.check(deps.api)?;
 
     let deposits = [
         assets
             .iter()
             .find(|a| a.info.equal(&pools[0].info))
             .map(|a| a.amount)
-            .expect("Wrong asset info is given"),
+            .unwrap_or_default(),
         assets
             .iter()
             .find(|a| a.info.equal(&pools[1].info))
             .map(|a| a.amount)
-            .expect("Wrong asset info is given"),
+            .unwrap_or_default(),
     ];
 
-    if deposits[0].is_zero() || deposits[1].is_zero() {
+    let pool_is_empty = pools.iter().any(|pool| pool.amount.is_zero());
+    if (deposits[0].is_zero() || deposits[1].is_zero())
+        && (pool_is_empty || deposits == [Uint128::zero(); 2])
+    {
         return Err(ContractError::InvalidZeroAmount {});
     }
 
@@ -1323,17 +3398,26 @@ pub fn assert_slippage_tolerance(
 /// Manages the contract migration.
 #[cfg(not(tarpaulin_include))]
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(deps: DepsMut, _env: Env, _msg: Empty) -> Result<Response, ContractError> {
+pub fn migrate(deps: DepsMut, env: Env, _msg: Empty) -> Result<Response, ContractError> {
     let version = get_contract_version(deps.storage)?;
 
     match version.contract.as_ref() {
         CONTRACT_NAME => match version.version.as_ref() {
-            "2.1.0" => {}
+            "2.1.0" | "2.1.1" => migrate_config_from_v210(deps.storage)?,
             _ => return Err(ContractError::MigrationError {}),
         },
         _ => return Err(ContractError::MigrationError {}),
     }
 
+    // Start tracking reserves from the current pair balances
+    if !RESERVES.exists(deps.storage) {
+        let config = CONFIG.load(deps.storage)?;
+        let balances = config
+            .pair_info
+            .query_pools(&deps.querier, &env.contract.address)?;
+        save_reserves(deps.storage, &balances, env.block.height)?;
+    }
+
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
     Ok(Response::new()
@@ -1344,15 +3428,58 @@ pub fn migrate(deps: DepsMut, _env: Env, _msg: Empty) -> Result<Response, Contra
 }
 
 /// Returns the total amount of assets in the pool as well as the total amount of LP tokens currently minted.
-pub fn pool_info(querier: QuerierWrapper, config: &Config) -> StdResult<(Vec<Asset>, Uint128)> {
-    let pools = config
-        .pair_info
-        .query_pools(&querier, &config.pair_info.contract_addr)?;
-    let total_share = query_supply(&querier, &config.pair_info.liquidity_token)?;
+pub fn pool_info(deps: Deps, config: &Config) -> StdResult<(Vec<Asset>, Uint128)> {
+    let pools = query_reserves(deps.storage, config)?;
+    let total_share = query_lp_supply(
+        &deps.querier,
+        &config.pair_info.liquidity_token,
+        config.native_lp_token,
+    )?;
 
     Ok((pools, total_share))
 }
 
+/// Returns the pool reserves tracked by the contract.
+/// Unlike the pair balances they can't be affected by direct token transfers.
+pub fn query_reserves(storage: &dyn Storage, config: &Config) -> StdResult<Vec<Asset>> {
+    let reserves = RESERVES.load(storage)?;
+
+    Ok(config
+        .pair_info
+        .asset_infos
+        .iter()
+        .zip(reserves)
+        .map(|(info, amount)| info.with_balance(amount))
+        .collect())
+}
+
+/// Returns the pair balances excluding the offer assets of queued batch orders.
+fn query_pool_balances(deps: Deps, env: &Env, config: &Config) -> StdResult<Vec<Asset>> {
+    let mut balances = config
+        .pair_info
+        .query_pools(&deps.querier, &env.contract.address)?;
+
+    for item in BATCH_ORDERS.range(deps.storage, None, None, Order::Ascending) {
+        let (_, order) = item?;
+        for balance in balances.iter_mut() {
+            if balance.info.equal(&order.offer_asset.info) {
+                balance.amount = balance.amount.checked_sub(order.offer_asset.amount)?;
+            }
+        }
+    }
+
+    Ok(balances)
+}
+
+/// Saves the new pool reserves and records them in the asset balance snapshots.
+fn save_reserves(storage: &mut dyn Storage, pools: &[Asset], block_height: u64) -> StdResult<()> {
+    for pool in pools {
+        BALANCES.save(storage, &pool.info, &pool.amount, block_height)?;
+    }
+
+    RESERVES.save(storage, &pools.iter().map(|pool| pool.amount).collect())
+}
+
 fn ensure_min_assets_to_receive(
     config: &Config,
     mut refund_assets: Vec<Asset>,
@@ -1,8 +1,15 @@
-use cosmwasm_std::{ConversionOverflowError, OverflowError, StdError, Uint128};
+use cosmwasm_std::{
+    Addr, ConversionOverflowError, Decimal, Decimal256, OverflowError, StdError, Uint128,
+};
 use cw_utils::{ParseReplyError, PaymentError};
 use thiserror::Error;
 
-use astroport::{asset::MINIMUM_LIQUIDITY_AMOUNT, pair::MAX_FEE_SHARE_BPS};
+use astroport::asset::MINIMUM_LIQUIDITY_AMOUNT;
+use astroport::factory::PauseScope;
+use astroport::pair::{
+    DYNAMIC_FEE_WINDOW_LIMITS, HOOK_GAS_LIMIT, MAX_ALLOWLIST_ADDRESSES, MAX_BATCH_ORDERS,
+    MAX_DYNAMIC_FEE_BPS, MAX_FEE_SHARE_BPS, MAX_HOOKS, PRICE_DEVIATION_LIMITS,
+};
 
 /// This enum describes pair contract errors
 #[derive(Error, Debug, PartialEq)]
@@ -37,6 +44,24 @@ pub enum ContractError {
     #[error("Operation exceeds max spread limit")]
     MaxSpreadAssertion {},
 
+    #[error("{0:?} is paused")]
+    Paused(PauseScope),
+
+    #[error("Transaction deadline exceeded")]
+    DeadlineExceeded {},
+
+    #[error("Flash swap is in progress")]
+    FlashSwapInProgress {},
+
+    #[error("Flash swap amount must be positive and less than the pool reserve")]
+    InvalidFlashSwapAmount {},
+
+    #[error("Flash swap invariant violated: borrowed assets plus fees were not returned")]
+    FlashSwapInvariantViolation {},
+
+    #[error("Required offer amount {0} exceeds the maximum offer amount {1}")]
+    MaxOfferAmountAssertion(Uint128, Uint128),
+
     #[error("Provided spread amount exceeds allowed limit")]
     AllowedSpreadAssertion {},
 
@@ -82,4 +107,54 @@ pub enum ContractError {
         MAX_FEE_SHARE_BPS
     )]
     FeeShareOutOfBounds {},
+
+    #[error("Fee share recipients must be unique")]
+    DuplicateFeeShareRecipients {},
+
+    #[error(
+        "Hooks must be unique, there can be at most {} hooks and their gas limits must be within {:?}",
+        MAX_HOOKS,
+        HOOK_GAS_LIMIT
+    )]
+    InvalidHooks {},
+
+    #[error(
+        "Dynamic fee bounds must satisfy min <= max <= {} bps and the window must be within {:?}",
+        MAX_DYNAMIC_FEE_BPS,
+        DYNAMIC_FEE_WINDOW_LIMITS
+    )]
+    InvalidDynamicFeeParams {},
+
+    #[error("Protocol fee accrual is disabled")]
+    ProtocolFeeAccrualDisabled {},
+
+    #[error("Max price deviation must be within {:?}", PRICE_DEVIATION_LIMITS)]
+    InvalidPriceDeviation {},
+
+    #[error("Swap moves the price by {deviation} from the block open price, max allowed {max_deviation}")]
+    PriceDeviationExceeded {
+        deviation: Decimal256,
+        max_deviation: Decimal,
+    },
+
+    #[error("Instant swaps are disabled in the batch auction mode")]
+    BatchAuctionEnabled {},
+
+    #[error("Only deposits in the current pool ratio are allowed in the batch auction mode")]
+    ImbalancedProvideInBatchMode {},
+
+    #[error("There can be at most {} queued batch orders", MAX_BATCH_ORDERS)]
+    BatchOrdersLimitReached {},
+
+    #[error("No batch orders placed before the current block")]
+    NoBatchOrdersToSettle {},
+
+    #[error(
+        "Trader allowlist must contain from 1 to {} unique addresses",
+        MAX_ALLOWLIST_ADDRESSES
+    )]
+    InvalidTraderAllowlist {},
+
+    #[error("{addr} is not allowed to trade in this pair")]
+    TraderNotAllowed { addr: Addr },
 }
@@ -2,6 +2,7 @@ pub mod contract;
 pub mod state;
 
 pub mod error;
+pub mod migration;
 
 #[cfg(test)]
 mod testing;
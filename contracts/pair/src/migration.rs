@@ -0,0 +1,47 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, Decimal256, StdResult, Storage, Uint128};
+use cw_storage_plus::Item;
+
+use astroport::asset::PairInfo;
+use astroport::pair::LegacyFeeShareConfig;
+
+use crate::state::{Config, CONFIG};
+
+/// This structure describes the config stored by the pair contract v2.1.x
+#[cw_serde]
+pub struct ConfigV210 {
+    pub pair_info: PairInfo,
+    pub factory_addr: Addr,
+    pub block_time_last: u64,
+    pub price0_cumulative_last: Uint128,
+    pub price1_cumulative_last: Uint128,
+    pub fee_share: Option<LegacyFeeShareConfig>,
+}
+
+pub const CONFIG_V210: Item<ConfigV210> = Item::new("config");
+
+/// Converts the v2.1.x config with a single fee share recipient to the current one.
+/// All features added after v2.1.x are disabled.
+pub fn migrate_config_from_v210(storage: &mut dyn Storage) -> StdResult<()> {
+    let config = CONFIG_V210.load(storage)?;
+
+    CONFIG.save(
+        storage,
+        &Config {
+            pair_info: config.pair_info,
+            factory_addr: config.factory_addr,
+            block_time_last: config.block_time_last,
+            price0_cumulative_last: config.price0_cumulative_last,
+            price1_cumulative_last: config.price1_cumulative_last,
+            fee_share: config.fee_share.map(Into::into),
+            native_lp_token: false,
+            hooks: vec![],
+            dynamic_fee: None,
+            protocol_fee_accrual: false,
+            block_open_price: Decimal256::zero(),
+            max_price_deviation: None,
+            batch_auction: false,
+            trader_allowlist: None,
+        },
+    )
+}
@@ -7,8 +7,8 @@ use cosmwasm_std::{
 };
 use cw20::{BalanceResponse, Cw20QueryMsg, TokenInfoResponse};
 
-use astroport::factory::FeeInfoResponse;
-use astroport::factory::QueryMsg::FeeInfo;
+use astroport::factory::QueryMsg::{FeeInfo, PauseStatus};
+use astroport::factory::{FeeInfoResponse, PauseStatusResponse};
 use astroport_test::legacy_mock_api::LegacyMockApi;
 
 /// mock_dependencies is a drop-in replacement for cosmwasm_std::testing::mock_dependencies.
@@ -91,6 +91,9 @@ impl WasmMockQuerier {
                             })
                             .into(),
                         ),
+                        PauseStatus { .. } => {
+                            SystemResult::Ok(to_json_binary(&PauseStatusResponse::default()).into())
+                        }
                         _ => panic!("DO NOT ENTER HERE"),
                     }
                 } else {
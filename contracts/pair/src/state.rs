@@ -1,8 +1,11 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Addr, Uint128};
-use cw_storage_plus::Item;
+use cosmwasm_std::{Addr, Decimal, Decimal256, Uint128, Uint256};
+use cw_storage_plus::{Deque, Item, Map, SnapshotMap, Strategy};
 
-use astroport::{asset::PairInfo, pair::FeeShareConfig};
+use astroport::asset::{Asset, AssetInfo, PairInfo};
+use astroport::observation::Observation;
+use astroport::pair::{BatchOrder, DynamicFeeParams, FeeShareConfig, PairHook, TraderAllowlist};
+use astroport::stats::PairStats;
 
 /// This structure stores the main config parameters for a constant product pair contract.
 #[cw_serde]
@@ -19,7 +22,61 @@ pub struct Config {
     pub price1_cumulative_last: Uint128,
     /// The config for swap fee sharing
     pub fee_share: Option<FeeShareConfig>,
+    /// Whether LP shares are issued as a token factory denom instead of a CW20 token
+    #[serde(default)]
+    pub native_lp_token: bool,
+    /// Contracts notified after swaps and liquidity events
+    #[serde(default)]
+    pub hooks: Vec<PairHook>,
+    /// The config for the volatility-based swap fee
+    #[serde(default)]
+    pub dynamic_fee: Option<DynamicFeeParams>,
+    /// Whether the maker fee accrues in the pool and is minted as LP tokens to the fee address
+    #[serde(default)]
+    pub protocol_fee_accrual: bool,
+    /// The price of asset 0 in asset 1 at the start of the block at `block_time_last`
+    #[serde(default)]
+    pub block_open_price: Decimal256,
+    /// The maximum relative deviation of the pool price from the block open price allowed after a swap
+    #[serde(default)]
+    pub max_price_deviation: Option<Decimal>,
+    /// Whether swaps are queued and settled in batches at a uniform price
+    #[serde(default)]
+    pub batch_auction: bool,
+    /// The addresses allowed to swap and provide liquidity. Everyone is allowed if not set
+    #[serde(default)]
+    pub trader_allowlist: Option<TraderAllowlist>,
 }
 
 /// Stores the config struct at the given key
 pub const CONFIG: Item<Config> = Item::new("config");
+
+/// Stores the pool reserves in the same order as [`PairInfo::asset_infos`]
+pub const RESERVES: Item<Vec<Uint128>> = Item::new("reserves");
+
+/// Stores the product of the pool reserves after the last liquidity event.
+/// Used to measure the growth from the accrued protocol fee
+pub const K_LAST: Item<Uint256> = Item::new("k_last");
+
+/// Stores the asset borrowed by an ongoing flash swap
+pub const FLASH_SWAP: Item<Asset> = Item::new("flash_swap");
+
+/// Stores a bounded ring buffer of cumulative price observations
+pub const OBSERVATIONS: Deque<Observation> = Deque::new("observations");
+
+/// Stores the pool balance of each asset at every block height it changed
+pub const BALANCES: SnapshotMap<&AssetInfo, Uint128> = SnapshotMap::new(
+    "balances",
+    "balances_check",
+    "balances_change",
+    Strategy::EveryBlock,
+);
+
+/// Stores the swap orders queued in the batch auction mode by their identifiers
+pub const BATCH_ORDERS: Map<u64, BatchOrder> = Map::new("batch_orders");
+
+/// Stores the identifier of the next batch order
+pub const NEXT_BATCH_ORDER_ID: Item<u64> = Item::new("next_batch_order_id");
+
+/// Stores the lifetime and per-epoch swap volumes and fees
+pub const STATS: PairStats = PairStats::new("stats", "epoch_stats");
@@ -1,30 +1,33 @@
+#![allow(clippy::inconsistent_digit_grouping)]
+
 use cosmwasm_std::testing::{message_info, mock_env, MOCK_CONTRACT_ADDR};
 use cosmwasm_std::{
-    attr, to_json_binary, Addr, BankMsg, BlockInfo, Coin, CosmosMsg, Decimal, DepsMut, Env,
-    MessageInfo, Reply, ReplyOn, Response, StdError, SubMsg, SubMsgResponse, SubMsgResult,
-    Timestamp, Uint128, WasmMsg,
+    attr, to_json_binary, Addr, BankMsg, BlockInfo, Coin, CosmosMsg, Decimal, Decimal256, DepsMut,
+    Empty, Env, MessageInfo, Reply, ReplyOn, Response, StdError, SubMsg, SubMsgResponse,
+    SubMsgResult, Timestamp, Uint128, WasmMsg,
 };
 use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg, MinterResponse};
 use cw20_base::msg::InstantiateMsg as TokenInstantiateMsg;
 use proptest::prelude::*;
 use prost::Message;
 
-use astroport::asset::{Asset, AssetInfo, PairInfo};
+use astroport::asset::{Asset, AssetInfo, AssetInfoExt, PairInfo};
 use astroport::factory::PairType;
 use astroport::pair::{
-    Cw20HookMsg, ExecuteMsg, InstantiateMsg, PoolResponse, ReverseSimulationResponse,
-    SimulationResponse, TWAP_PRECISION,
+    BatchOrder, Cw20HookMsg, ExecuteMsg, FeeShareConfig, InstantiateMsg, LegacyFeeShareConfig,
+    PoolResponse, ReverseSimulationResponse, SimulationResponse, TWAP_PRECISION,
 };
 
 use crate::contract::compute_offer_amount;
 use crate::contract::reply;
 use crate::contract::{
-    accumulate_prices, assert_max_spread, compute_swap, execute, instantiate, query_pool,
-    query_reverse_simulation, query_share, query_simulation,
+    accumulate_prices, assert_max_spread, clear_batch, compute_swap, execute, instantiate, migrate,
+    query_pool, query_reverse_simulation, query_share, query_simulation,
 };
 use crate::error::ContractError;
+use crate::migration::{ConfigV210, CONFIG_V210};
 use crate::mock_querier::mock_dependencies;
-use crate::state::{Config, CONFIG};
+use crate::state::{Config, CONFIG, RESERVES};
 
 #[derive(Clone, PartialEq, Message)]
 struct MsgInstantiateContractResponse {
@@ -38,6 +41,12 @@ fn mock_info(sender: &str, funds: &[Coin]) -> MessageInfo {
     message_info(&Addr::unchecked(sender), funds)
 }
 
+fn store_reserves(deps: DepsMut, reserves: [u128; 2]) {
+    RESERVES
+        .save(deps.storage, &reserves.map(Uint128::new).to_vec())
+        .unwrap();
+}
+
 fn store_liquidity_token(deps: DepsMut, msg_id: u64, contract_addr: String) {
     let instantiate_reply = MsgInstantiateContractResponse {
         contract_address: contract_addr,
@@ -86,6 +95,7 @@ fn proper_initialization() {
         ],
         token_code_id: 10u64,
         init_params: None,
+        native_lp_token: false,
     };
 
     let sender = "addr0000";
@@ -172,6 +182,7 @@ fn provide_liquidity() {
         token_code_id: 10u64,
         factory_addr: String::from("factory"),
         init_params: None,
+        native_lp_token: false,
     };
 
     let env = mock_env();
@@ -202,6 +213,7 @@ fn provide_liquidity() {
         auto_stake: None,
         receiver: None,
         min_lp_to_receive: None,
+        deadline: None,
     };
 
     let env = mock_env();
@@ -276,7 +288,7 @@ fn provide_liquidity() {
     );
 
     // Provide more liquidity 1:2, which is not propotional to 1:1,
-    // It must accept 1:1 and treat the leftover amount as a donation
+    // The excess uusd is swapped internally and the rest is provided with the new pool ratio
     deps.querier.with_balance(&[(
         &String::from(MOCK_CONTRACT_ADDR),
         &[Coin {
@@ -284,6 +296,10 @@ fn provide_liquidity() {
             amount: Uint128::new(200_000000000000000000 + 200_000000000000000000 /* user deposit must be pre-applied */),
         }],
     )]);
+    store_reserves(
+        deps.as_mut(),
+        [200_000000000000000000, 200_000000000000000000],
+    );
 
     deps.querier.with_token_balances(&[
         (
@@ -321,6 +337,7 @@ fn provide_liquidity() {
         auto_stake: None,
         receiver: None,
         min_lp_to_receive: None,
+        deadline: None,
     };
 
     let env = mock_env_with_block_time(env.block.time.seconds() + 1000);
@@ -332,10 +349,10 @@ fn provide_liquidity() {
         }],
     );
 
-    // Only accept 100, then 50 share will be generated with 100 * (100 / 200)
     let res: Response = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
     let transfer_from_msg = res.messages.first().expect("no message");
-    let mint_msg = res.messages.get(1).expect("no message");
+    let maker_fee_msg = res.messages.get(1).expect("no message");
+    let mint_msg = res.messages.get(2).expect("no message");
     assert_eq!(
         transfer_from_msg,
         &SubMsg {
@@ -356,6 +373,26 @@ fn provide_liquidity() {
             payload: Default::default(),
         }
     );
+    assert_eq!(
+        maker_fee_msg,
+        &SubMsg {
+            msg: WasmMsg::Execute {
+                contract_addr: String::from("asset0000"),
+                msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: String::from("fee_address"),
+                    amount: Uint128::from(13363913323731800u128),
+                })
+                .unwrap(),
+                funds: vec![],
+            }
+            .into(),
+            id: 0,
+            gas_limit: None,
+            reply_on: ReplyOn::Never,
+            payload: Default::default(),
+        }
+    );
+    // Roughly 100 * (sqrt(300 * 400) / sqrt(200 * 200) - 1) share is minted
     assert_eq!(
         mint_msg,
         &SubMsg {
@@ -363,7 +400,7 @@ fn provide_liquidity() {
                 contract_addr: String::from("liquidity0000"),
                 msg: to_json_binary(&Cw20ExecuteMsg::Mint {
                     recipient: String::from("addr0000"),
-                    amount: Uint128::from(50_000000000000000000u128),
+                    amount: Uint128::from(73_164832683269475928u128),
                 })
                 .unwrap(),
                 funds: vec![],
@@ -396,6 +433,7 @@ fn provide_liquidity() {
         auto_stake: None,
         receiver: None,
         min_lp_to_receive: None,
+        deadline: None,
     };
 
     let env = mock_env();
@@ -422,6 +460,10 @@ fn provide_liquidity() {
             amount: Uint128::new(100_000000000000000000 + 100_000000000000000000 /* user deposit must be pre-applied */),
         }],
     )]);
+    store_reserves(
+        deps.as_mut(),
+        [100_000000000000000000, 100_000000000000000000],
+    );
 
     deps.querier.with_token_balances(&[
         (
@@ -460,6 +502,7 @@ fn provide_liquidity() {
         auto_stake: None,
         receiver: None,
         min_lp_to_receive: None,
+        deadline: None,
     };
 
     let env = mock_env_with_block_time(env.block.time.seconds() + 1000);
@@ -481,6 +524,10 @@ fn provide_liquidity() {
             amount: Uint128::new(100_000000000000000000 + 98_000000000000000000 /* user deposit must be pre-applied */),
         }],
     )]);
+    store_reserves(
+        deps.as_mut(),
+        [100_000000000000000000, 100_000000000000000000],
+    );
 
     // Failed because the price is under slippage_tolerance
     let msg = ExecuteMsg::ProvideLiquidity {
@@ -502,6 +549,7 @@ fn provide_liquidity() {
         auto_stake: None,
         receiver: None,
         min_lp_to_receive: None,
+        deadline: None,
     };
 
     let env = mock_env_with_block_time(env.block.time.seconds() + 1000);
@@ -523,6 +571,10 @@ fn provide_liquidity() {
             amount: Uint128::new(100_000000000000000000 + 100_000000000000000000 /* user deposit must be pre-applied */),
         }],
     )]);
+    store_reserves(
+        deps.as_mut(),
+        [100_000000000000000000, 100_000000000000000000],
+    );
 
     // Successfully provides liquidity
     let msg = ExecuteMsg::ProvideLiquidity {
@@ -544,6 +596,7 @@ fn provide_liquidity() {
         auto_stake: None,
         receiver: None,
         min_lp_to_receive: None,
+        deadline: None,
     };
 
     let env = mock_env_with_block_time(env.block.time.seconds() + 1000);
@@ -564,6 +617,10 @@ fn provide_liquidity() {
             amount: Uint128::new(100_000000000000000000 + 99_000000000000000000 /* user deposit must be pre-applied */),
         }],
     )]);
+    store_reserves(
+        deps.as_mut(),
+        [100_000000000000000000, 100_000000000000000000],
+    );
 
     // Successfully provides liquidity
     let msg = ExecuteMsg::ProvideLiquidity {
@@ -585,6 +642,7 @@ fn provide_liquidity() {
         auto_stake: None,
         receiver: None,
         min_lp_to_receive: None,
+        deadline: None,
     };
 
     let env = mock_env_with_block_time(env.block.time.seconds() + 1000);
@@ -616,6 +674,7 @@ fn provide_liquidity() {
         auto_stake: None,
         receiver: None,
         min_lp_to_receive: None,
+        deadline: None,
     };
     let info = mock_info(
         "addr0001",
@@ -624,7 +683,32 @@ fn provide_liquidity() {
             amount: Uint128::from(99_000000000000000000u128),
         }],
     );
+    // Half of the single-sided deposit is swapped which moves the price beyond the tolerance
     let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+    assert_eq!(err, ContractError::MaxSpreadAssertion {});
+
+    let msg = ExecuteMsg::ProvideLiquidity {
+        assets: vec![
+            Asset {
+                info: AssetInfo::Token {
+                    contract_addr: Addr::unchecked("asset0000"),
+                },
+                amount: Uint128::zero(),
+            },
+            Asset {
+                info: AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+                amount: Uint128::zero(),
+            },
+        ],
+        slippage_tolerance: None,
+        auto_stake: None,
+        receiver: None,
+        min_lp_to_receive: None,
+        deadline: None,
+    };
+    let err = execute(deps.as_mut(), mock_env(), mock_info("addr0001", &[]), msg).unwrap_err();
     assert_eq!(err, ContractError::InvalidZeroAmount {});
 
     let msg = ExecuteMsg::ProvideLiquidity {
@@ -646,6 +730,7 @@ fn provide_liquidity() {
         auto_stake: None,
         receiver: None,
         min_lp_to_receive: None,
+        deadline: None,
     };
     let info = mock_info(
         "addr0001",
@@ -690,6 +775,7 @@ fn withdraw_liquidity() {
 
         factory_addr: String::from("factory"),
         init_params: None,
+        native_lp_token: false,
     };
 
     let env = mock_env();
@@ -699,12 +785,15 @@ fn withdraw_liquidity() {
 
     // Store liquidity token
     store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+    store_reserves(deps.as_mut(), [100, 100]);
 
     // Withdraw liquidity
     let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
         sender: String::from("addr0000"),
         msg: to_json_binary(&Cw20HookMsg::WithdrawLiquidity {
             min_assets_to_receive: None,
+            asset: None,
+            deadline: None,
         })
         .unwrap(),
         amount: Uint128::new(100u128),
@@ -823,6 +912,7 @@ fn try_native_to_token() {
         token_code_id: 10u64,
         factory_addr: String::from("factory"),
         init_params: None,
+        native_lp_token: false,
     };
 
     let env = mock_env();
@@ -832,6 +922,10 @@ fn try_native_to_token() {
 
     // Store liquidity token
     store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+    store_reserves(
+        deps.as_mut(),
+        [collateral_pool_amount.u128(), asset_pool_amount.u128()],
+    );
 
     // Normal swap
     let msg = ExecuteMsg::Swap {
@@ -845,6 +939,7 @@ fn try_native_to_token() {
         belief_price: None,
         max_spread: Some(Decimal::percent(50)),
         to: None,
+        deadline: None,
     };
     let env = mock_env_with_block_time(1000);
     let info = mock_info(
@@ -873,6 +968,10 @@ fn try_native_to_token() {
         .unwrap();
 
     // Check simulation result
+    store_reserves(
+        deps.as_mut(),
+        [collateral_pool_amount.u128(), asset_pool_amount.u128()],
+    );
     deps.querier.with_balance(&[(
         &String::from(MOCK_CONTRACT_ADDR),
         &[Coin {
@@ -883,6 +982,7 @@ fn try_native_to_token() {
 
     let err = query_simulation(
         deps.as_ref(),
+        mock_env(),
         Asset {
             info: AssetInfo::NativeToken {
                 denom: "cny".to_string(),
@@ -898,6 +998,7 @@ fn try_native_to_token() {
 
     let simulation_res: SimulationResponse = query_simulation(
         deps.as_ref(),
+        mock_env(),
         Asset {
             info: AssetInfo::NativeToken {
                 denom: "uusd".to_string(),
@@ -913,6 +1014,7 @@ fn try_native_to_token() {
     // Check reverse simulation result
     let err = query_reverse_simulation(
         deps.as_ref(),
+        mock_env(),
         Asset {
             info: AssetInfo::NativeToken {
                 denom: "cny".to_string(),
@@ -928,6 +1030,7 @@ fn try_native_to_token() {
 
     let reverse_simulation_res: ReverseSimulationResponse = query_reverse_simulation(
         deps.as_ref(),
+        mock_env(),
         Asset {
             info: AssetInfo::Token {
                 contract_addr: Addr::unchecked("asset0000"),
@@ -1033,6 +1136,7 @@ fn try_token_to_native() {
         token_code_id: 10u64,
         factory_addr: String::from("factory"),
         init_params: None,
+        native_lp_token: false,
     };
 
     let env = mock_env();
@@ -1042,6 +1146,10 @@ fn try_token_to_native() {
 
     // Store liquidity token
     store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+    store_reserves(
+        deps.as_mut(),
+        [collateral_pool_amount.u128(), asset_pool_amount.u128()],
+    );
 
     // Unauthorized access; can not execute swap directly for token swap
     let msg = ExecuteMsg::Swap {
@@ -1055,6 +1163,7 @@ fn try_token_to_native() {
         belief_price: None,
         max_spread: None,
         to: None,
+        deadline: None,
     };
     let env = mock_env_with_block_time(1000);
     let info = mock_info("addr0000", &[]);
@@ -1070,6 +1179,7 @@ fn try_token_to_native() {
             belief_price: None,
             max_spread: Some(Decimal::percent(50)),
             to: None,
+            deadline: None,
         })
         .unwrap(),
     });
@@ -1093,6 +1203,10 @@ fn try_token_to_native() {
         .unwrap();
 
     // Check simulation res
+    store_reserves(
+        deps.as_mut(),
+        [collateral_pool_amount.u128(), asset_pool_amount.u128()],
+    );
     // Return asset token balance as normal
     deps.querier.with_token_balances(&[
         (
@@ -1107,6 +1221,7 @@ fn try_token_to_native() {
 
     let simulation_res: SimulationResponse = query_simulation(
         deps.as_ref(),
+        mock_env(),
         Asset {
             amount: offer_amount,
             info: AssetInfo::Token {
@@ -1122,6 +1237,7 @@ fn try_token_to_native() {
     // Check reverse simulation result
     let reverse_simulation_res: ReverseSimulationResponse = query_reverse_simulation(
         deps.as_ref(),
+        mock_env(),
         Asset {
             amount: expected_return_amount,
             info: AssetInfo::NativeToken {
@@ -1194,6 +1310,7 @@ fn try_token_to_native() {
             belief_price: None,
             max_spread: None,
             to: None,
+            deadline: None,
         })
         .unwrap(),
     });
@@ -1285,6 +1402,7 @@ fn test_query_pool() {
         token_code_id: 10u64,
         factory_addr: String::from("factory"),
         init_params: None,
+        native_lp_token: false,
     };
 
     let env = mock_env();
@@ -1294,6 +1412,10 @@ fn test_query_pool() {
 
     // Store liquidity token
     store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+    store_reserves(
+        deps.as_mut(),
+        [asset_0_amount.u128(), asset_1_amount.u128()],
+    );
 
     let res: PoolResponse = query_pool(deps.as_ref()).unwrap();
 
@@ -1351,6 +1473,7 @@ fn test_query_share() {
         token_code_id: 10u64,
         factory_addr: String::from("factory"),
         init_params: None,
+        native_lp_token: false,
     };
 
     let env = mock_env();
@@ -1360,6 +1483,10 @@ fn test_query_share() {
 
     // Store liquidity token
     store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+    store_reserves(
+        deps.as_mut(),
+        [asset_0_amount.u128(), asset_1_amount.u128()],
+    );
 
     let res = query_share(deps.as_ref(), Uint128::new(250)).unwrap();
 
@@ -1464,6 +1591,14 @@ fn test_accumulate_prices() {
                 price0_cumulative_last: Uint128::new(case.last0),
                 price1_cumulative_last: Uint128::new(case.last1),
                 fee_share: None,
+                native_lp_token: false,
+                hooks: vec![],
+                dynamic_fee: None,
+                protocol_fee_accrual: false,
+                block_open_price: Decimal256::zero(),
+                max_price_deviation: None,
+                batch_auction: false,
+                trader_allowlist: None,
             },
             Uint128::new(case.x_amount),
             Uint128::new(case.y_amount),
@@ -1511,6 +1646,73 @@ fn compute_swap_rounding() {
     );
 }
 
+#[test]
+fn clear_batch_uniform_price() {
+    let uusd = AssetInfo::NativeToken {
+        denom: "uusd".to_string(),
+    };
+    let uluna = AssetInfo::NativeToken {
+        denom: "uluna".to_string(),
+    };
+    let pools = vec![
+        uusd.with_balance(1_000_000u128),
+        uluna.with_balance(1_000_000u128),
+    ];
+    let order = |order_id: u64, offer_asset: Asset, ask_asset_info: &AssetInfo| BatchOrder {
+        order_id,
+        trader: Addr::unchecked("trader"),
+        receiver: Addr::unchecked("trader"),
+        offer_asset,
+        ask_asset_info: ask_asset_info.clone(),
+        belief_price: None,
+        max_spread: None,
+        block_height: 1,
+    };
+
+    // A single order is a regular swap
+    let returns = clear_batch(&pools, &[order(0, uusd.with_balance(100_000u128), &uluna)]).unwrap();
+    let (swap_return, _, _) = compute_swap(
+        pools[0].amount,
+        pools[1].amount,
+        Uint128::new(100_000),
+        Decimal::zero(),
+    )
+    .unwrap();
+    assert_eq!(returns, vec![swap_return]);
+
+    // Orders matching the pool price don't move it
+    let returns = clear_batch(
+        &pools,
+        &[
+            order(0, uusd.with_balance(1_000u128), &uluna),
+            order(1, uluna.with_balance(1_000u128), &uusd),
+        ],
+    )
+    .unwrap();
+    assert_eq!(returns, vec![Uint128::new(1_000), Uint128::new(1_000)]);
+
+    let orders = [
+        order(0, uusd.with_balance(100_000u128), &uluna),
+        order(1, uusd.with_balance(50_000u128), &uluna),
+        order(2, uluna.with_balance(60_000u128), &uusd),
+    ];
+    let returns = clear_batch(&pools, &orders).unwrap();
+    // The clearing price is (1_000_000 + 60_000) / (1_000_000 + 150_000) uluna per uusd
+    assert_eq!(
+        returns,
+        vec![
+            Uint128::new(92_173),
+            Uint128::new(46_086),
+            Uint128::new(65_095)
+        ]
+    );
+
+    // The pool executes the excess swap along the constant product curve
+    let new_x = pools[0].amount + Uint128::new(150_000) - returns[2];
+    let new_y = pools[1].amount + Uint128::new(60_000) - returns[0] - returns[1];
+    assert!(new_x.full_mul(new_y) >= pools[0].amount.full_mul(pools[1].amount));
+}
+
 proptest! {
     #[test]
     fn compute_swap_overflow_test(
@@ -1604,3 +1806,66 @@ fn ensure_useful_error_messages_are_given_on_swaps() {
     );
     compute_offer_amount(OFFER, ASK, AMOUNT, DZERO).unwrap();
 }
+
+#[test]
+fn migrate_legacy_fee_share() {
+    let mut deps = mock_dependencies(&[
+        Coin::new(1_000_000u128, "uusd"),
+        Coin::new(2_000_000u128, "uluna"),
+    ]);
+
+    let pair_info = PairInfo {
+        asset_infos: vec![
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::NativeToken {
+                denom: "uluna".to_string(),
+            },
+        ],
+        contract_addr: Addr::unchecked(MOCK_CONTRACT_ADDR),
+        liquidity_token: "liquidity0000".to_string(),
+        pair_type: PairType::Xyk {},
+    };
+    CONFIG_V210
+        .save(
+            deps.as_mut().storage,
+            &ConfigV210 {
+                pair_info: pair_info.clone(),
+                factory_addr: Addr::unchecked("factory"),
+                block_time_last: 100,
+                price0_cumulative_last: Uint128::new(10),
+                price1_cumulative_last: Uint128::new(20),
+                fee_share: Some(LegacyFeeShareConfig {
+                    bps: 500,
+                    recipient: Addr::unchecked("recipient"),
+                }),
+            },
+        )
+        .unwrap();
+    cw2::set_contract_version(deps.as_mut().storage, "astroport-pair", "2.1.0").unwrap();
+
+    // The legacy fee share can't be read as the current config
+    CONFIG.load(deps.as_ref().storage).unwrap_err();
+
+    migrate(deps.as_mut(), mock_env(), Empty {}).unwrap();
+
+    let config = CONFIG.load(deps.as_ref().storage).unwrap();
+    assert_eq!(config.pair_info, pair_info);
+    assert_eq!(config.block_time_last, 100);
+    assert_eq!(config.price1_cumulative_last, Uint128::new(20));
+    assert_eq!(
+        config.fee_share,
+        Some(FeeShareConfig::single(Addr::unchecked("recipient"), 500))
+    );
+    assert_eq!(
+        RESERVES.load(deps.as_ref().storage).unwrap(),
+        vec![Uint128::new(1_000_000), Uint128::new(2_000_000)]
+    );
+    assert_eq!(
+        cw2::get_contract_version(deps.as_ref().storage)
+            .unwrap()
+            .version,
+        env!("CARGO_PKG_VERSION")
+    );
+}
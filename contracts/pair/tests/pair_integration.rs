@@ -1,21 +1,33 @@
+#![allow(clippy::inconsistent_digit_grouping)]
+use cosmwasm_schema::cw_serde;
 use cosmwasm_std::testing::MockApi;
-use cosmwasm_std::{attr, to_json_binary, Addr, Coin, Decimal, Uint128};
+use cosmwasm_std::{
+    attr, coin, from_json, to_json_binary, wasm_execute, Addr, Binary, Coin, Decimal, Empty, Event,
+    Response, StdError, StdResult, Uint128, WasmMsg,
+};
 use cw20::{BalanceResponse, Cw20Coin, Cw20ExecuteMsg, Cw20QueryMsg, MinterResponse};
 use cw20_base::msg::InstantiateMsg as TokenInstantiateMsg;
-use cw_multi_test::{App, ContractWrapper, Executor};
+use cw_multi_test::{App, AppBuilder, ContractWrapper, Executor};
+use cw_storage_plus::Item;
+use cw_utils::PaymentError;
 
-use astroport::asset::{native_asset_info, Asset, AssetInfo, PairInfo};
+use astroport::asset::{native_asset_info, Asset, AssetInfo, AssetInfoExt, PairInfo};
 use astroport::cosmwasm_ext::DecMul;
 use astroport::factory::{
     ExecuteMsg as FactoryExecuteMsg, InstantiateMsg as FactoryInstantiateMsg, PairConfig, PairType,
-    QueryMsg as FactoryQueryMsg,
+    PauseScope, QueryMsg as FactoryQueryMsg,
 };
+use astroport::observation::Observation;
 use astroport::pair::{
-    ConfigResponse, CumulativePricesResponse, Cw20HookMsg, ExecuteMsg, FeeShareConfig,
-    InstantiateMsg, PoolResponse, QueryMsg, XYKPoolConfig, XYKPoolUpdateParams, MAX_FEE_SHARE_BPS,
+    BatchOrder, ConfigResponse, CumulativePricesResponse, Cw20HookMsg, DynamicFeeParams,
+    ExecuteMsg, FeeShareConfig, FeeShareRecipient, InstantiateMsg, PairHook, PairHookExecuteMsg,
+    PairHookMsg, PoolResponse, QueryMsg, ReverseSimulationResponse, SimulationResponse,
+    TraderAllowlist, XYKPoolConfig, XYKPoolUpdateParams, MAX_DYNAMIC_FEE_BPS, MAX_FEE_SHARE_BPS,
     TWAP_PRECISION,
 };
+use astroport::stats::{StatsResponse, STATS_EPOCH_LENGTH};
 use astroport_pair::error::ContractError;
+use astroport_test::modules::stargate::MockStargate;
 
 fn mock_app(owner: Addr, coins: Vec<Coin>) -> App {
     App::new(|router, _, storage| {
@@ -60,7 +72,7 @@ fn store_factory_code(app: &mut App) -> u64 {
     app.store_code(factory_contract)
 }
 
-fn instantiate_pair(mut app: &mut App, owner: &Addr) -> Addr {
+fn instantiate_pair(app: &mut App, owner: &Addr) -> Addr {
     let token_contract_code_id = store_token_code(app);
 
     let pair_contract_code_id = store_pair_code(app);
@@ -76,6 +88,7 @@ fn instantiate_pair(mut app: &mut App, owner: &Addr) -> Addr {
             is_disabled: false,
             is_generator_disabled: false,
             permissioned: false,
+            native_lp_token: false,
         }],
         token_code_id: token_contract_code_id,
         incentives_address: Some(app.api().addr_make("incentives").to_string()),
@@ -107,6 +120,7 @@ fn instantiate_pair(mut app: &mut App, owner: &Addr) -> Addr {
         token_code_id: token_contract_code_id,
         factory_addr: factory_instance.to_string(),
         init_params: None,
+        native_lp_token: false,
     };
 
     app.instantiate_contract(
@@ -296,9 +310,9 @@ fn test_provide_and_withdraw_liquidity() {
         Uint128::new(100),
         None,
         None,
-        Some(double_amount_to_receive.clone()),
+        Some(double_amount_to_receive),
     );
-    let res = router
+    let _res = router
         .execute_contract(alice_address.clone(), pair_instance.clone(), &msg, &coins)
         .unwrap_err();
 
@@ -330,6 +344,8 @@ fn test_provide_and_withdraw_liquidity() {
         amount: Uint128::from(50u8),
         msg: to_json_binary(&Cw20HookMsg::WithdrawLiquidity {
             min_assets_to_receive: None,
+            asset: None,
+            deadline: None,
         })
         .unwrap(),
     };
@@ -358,6 +374,7 @@ fn test_provide_and_withdraw_liquidity() {
                 belief_price: None,
                 max_spread: None,
                 to: None,
+                deadline: None,
             },
             &[Coin {
                 denom: "cny".to_string(),
@@ -379,7 +396,18 @@ fn test_provide_and_withdraw_liquidity() {
         config.clone(),
         ConfigResponse {
             block_time_last: router.block_info().time.seconds(),
-            params: Some(to_json_binary(&XYKPoolConfig { fee_share: None }).unwrap()),
+            params: Some(
+                to_json_binary(&XYKPoolConfig {
+                    fee_share: None,
+                    hooks: vec![],
+                    dynamic_fee: None,
+                    protocol_fee_accrual: false,
+                    max_price_deviation: None,
+                    batch_auction: false,
+                    trader_allowlist: None,
+                })
+                .unwrap()
+            ),
             owner,
             factory_addr: config.factory_addr
         }
@@ -408,10 +436,11 @@ fn provide_liquidity_msg(
                 amount: uluna_amount,
             },
         ],
-        slippage_tolerance: slippage_tolerance,
+        slippage_tolerance,
         auto_stake: None,
         receiver,
         min_lp_to_receive,
+        deadline: None,
     };
 
     let coins = [
@@ -523,6 +552,7 @@ fn test_compatibility_of_tokens_with_different_precision() {
             is_disabled: false,
             is_generator_disabled: false,
             permissioned: false,
+            native_lp_token: false,
         }],
         token_code_id,
         incentives_address: Some(app.api().addr_make("incentives").to_string()),
@@ -604,6 +634,7 @@ fn test_compatibility_of_tokens_with_different_precision() {
             belief_price: None,
             max_spread: None,
             to: Some(user.to_string()),
+            deadline: None,
         })
         .unwrap(),
         amount: x_offer,
@@ -636,6 +667,7 @@ fn test_compatibility_of_tokens_with_different_precision() {
         auto_stake: None,
         receiver: None,
         min_lp_to_receive: None,
+        deadline: None,
     };
 
     app.execute_contract(owner.clone(), pair_instance.clone(), &msg, &[])
@@ -650,6 +682,7 @@ fn test_compatibility_of_tokens_with_different_precision() {
             belief_price: None,
             max_spread: None,
             to: Some(user.to_string()),
+            deadline: None,
         })
         .unwrap(),
         amount: x_offer,
@@ -804,6 +837,7 @@ fn create_pair_with_same_assets() {
         token_code_id: token_contract_code_id,
         factory_addr: String::from("factory"),
         init_params: None,
+        native_lp_token: false,
     };
 
     let resp = router
@@ -839,6 +873,7 @@ fn wrong_number_of_assets() {
         token_code_id: 123,
         factory_addr: String::from("factory"),
         init_params: None,
+        native_lp_token: false,
     };
 
     let err = router
@@ -867,6 +902,7 @@ fn wrong_number_of_assets() {
         token_code_id: 123,
         factory_addr: String::from("factory"),
         init_params: None,
+        native_lp_token: false,
     };
 
     let err = router
@@ -942,6 +978,7 @@ fn update_pair_config() {
         token_code_id: token_contract_code_id,
         factory_addr: factory_instance.to_string(),
         init_params: None,
+        native_lp_token: false,
     };
 
     let pair = router
@@ -964,7 +1001,18 @@ fn update_pair_config() {
         res,
         ConfigResponse {
             block_time_last: 0,
-            params: Some(to_json_binary(&XYKPoolConfig { fee_share: None }).unwrap()),
+            params: Some(
+                to_json_binary(&XYKPoolConfig {
+                    fee_share: None,
+                    hooks: vec![],
+                    dynamic_fee: None,
+                    protocol_fee_accrual: false,
+                    max_price_deviation: None,
+                    batch_auction: false,
+                    trader_allowlist: None,
+                })
+                .unwrap()
+            ),
             owner: owner.clone(),
             factory_addr: factory_instance.clone()
         }
@@ -1027,6 +1075,7 @@ fn enable_disable_fee_sharing() {
         token_code_id: token_contract_code_id,
         factory_addr: factory_instance.to_string(),
         init_params: None,
+        native_lp_token: false,
     };
 
     let pair = router
@@ -1047,7 +1096,18 @@ fn enable_disable_fee_sharing() {
 
     assert_eq!(
         res.params,
-        Some(to_json_binary(&XYKPoolConfig { fee_share: None }).unwrap())
+        Some(
+            to_json_binary(&XYKPoolConfig {
+                fee_share: None,
+                hooks: vec![],
+                dynamic_fee: None,
+                protocol_fee_accrual: false,
+                max_price_deviation: None,
+                batch_auction: false,
+                trader_allowlist: None,
+            })
+            .unwrap()
+        )
     );
 
     // Attemt to set fee sharing higher than maximum
@@ -1107,10 +1167,16 @@ fn enable_disable_fee_sharing() {
         res.params,
         Some(
             to_json_binary(&XYKPoolConfig {
-                fee_share: Some(FeeShareConfig {
-                    bps: fee_share_bps,
-                    recipient: fee_share_contract.clone(),
-                }),
+                fee_share: Some(FeeShareConfig::single(
+                    fee_share_contract.clone(),
+                    fee_share_bps
+                )),
+                hooks: vec![],
+                dynamic_fee: None,
+                protocol_fee_accrual: false,
+                max_price_deviation: None,
+                batch_auction: false,
+                trader_allowlist: None,
             })
             .unwrap()
         )
@@ -1131,7 +1197,18 @@ fn enable_disable_fee_sharing() {
         .unwrap();
     assert_eq!(
         res.params,
-        Some(to_json_binary(&XYKPoolConfig { fee_share: None }).unwrap())
+        Some(
+            to_json_binary(&XYKPoolConfig {
+                fee_share: None,
+                hooks: vec![],
+                dynamic_fee: None,
+                protocol_fee_accrual: false,
+                max_price_deviation: None,
+                batch_auction: false,
+                trader_allowlist: None,
+            })
+            .unwrap()
+        )
     );
 }
 
@@ -1355,6 +1432,7 @@ fn test_fee_share(
             is_disabled: false,
             is_generator_disabled: false,
             permissioned: false,
+            native_lp_token: false,
         }],
         token_code_id,
         incentives_address: Some(app.api().addr_make("incentives").to_string()),
@@ -1448,6 +1526,7 @@ fn test_fee_share(
         auto_stake: None,
         receiver: None,
         min_lp_to_receive: None,
+        deadline: None,
     };
 
     app.execute_contract(owner.clone(), pair_instance.clone(), &msg, &[])
@@ -1473,6 +1552,7 @@ fn test_fee_share(
             belief_price: None,
             max_spread: None,
             to: Some(user.to_string()),
+            deadline: None,
         })
         .unwrap(),
         amount: x_offer,
@@ -1539,93 +1619,3013 @@ fn test_fee_share(
 }
 
 #[test]
-fn test_provide_liquidity_without_funds() {
-    let api = MockApi::default();
-    let owner = api.addr_make("owner");
-    let alice_address = api.addr_make("alice");
-    let mut router = mock_app(
+fn test_fee_share_recipients() {
+    let owner = MockApi::default().addr_make("owner");
+    let mut app = mock_app(
         owner.clone(),
         vec![
-            Coin {
-                denom: "uusd".to_string(),
-                amount: Uint128::new(100_000_000_000u128),
-            },
-            Coin {
-                denom: "uluna".to_string(),
-                amount: Uint128::new(100_000_000_000u128),
-            },
-            Coin {
-                denom: "cny".to_string(),
-                amount: Uint128::new(100_000_000_000u128),
-            },
+            coin(1_000_000_000_000, "uusd"),
+            coin(1_000_000_000_000, "uluna"),
         ],
     );
 
-    // Set Alice's balances
-    router
-        .send_tokens(
+    let pair_instance = instantiate_pair(&mut app, &owner);
+    let config: ConfigResponse = app
+        .wrap()
+        .query_wasm_smart(&pair_instance, &QueryMsg::Config {})
+        .unwrap();
+    app.execute_contract(
+        owner.clone(),
+        config.factory_addr,
+        &FactoryExecuteMsg::UpdatePairConfig {
+            config: PairConfig {
+                code_id: 0,
+                maker_fee_bps: 0,
+                pair_type: PairType::Xyk {},
+                total_fee_bps: 30,
+                is_disabled: false,
+                is_generator_disabled: false,
+                permissioned: false,
+                native_lp_token: false,
+            },
+        },
+        &[],
+    )
+    .unwrap();
+
+    let frontend = app.api().addr_make("frontend");
+    let project = app.api().addr_make("project");
+    let dao = app.api().addr_make("dao");
+    let enable_msg = |recipients: Vec<(&Addr, u16)>| ExecuteMsg::UpdateConfig {
+        params: to_json_binary(&XYKPoolUpdateParams::EnableFeeShareRecipients {
+            recipients: recipients
+                .into_iter()
+                .map(|(recipient, bps)| (recipient.to_string(), bps))
+                .collect(),
+        })
+        .unwrap(),
+    };
+
+    for (recipients, expected_err) in [
+        (vec![], ContractError::FeeShareOutOfBounds {}),
+        (
+            vec![(&frontend, 600), (&dao, MAX_FEE_SHARE_BPS - 500)],
+            ContractError::FeeShareOutOfBounds {},
+        ),
+        (
+            vec![(&frontend, 500), (&frontend, 500)],
+            ContractError::DuplicateFeeShareRecipients {},
+        ),
+    ] {
+        let err = app
+            .execute_contract(
+                owner.clone(),
+                pair_instance.clone(),
+                &enable_msg(recipients),
+                &[],
+            )
+            .unwrap_err();
+        assert_eq!(err.downcast::<ContractError>().unwrap(), expected_err);
+    }
+
+    app.execute_contract(
+        owner.clone(),
+        pair_instance.clone(),
+        &enable_msg(vec![(&frontend, 500), (&project, 300), (&dao, 200)]),
+        &[],
+    )
+    .unwrap();
+
+    let config: ConfigResponse = app
+        .wrap()
+        .query_wasm_smart(&pair_instance, &QueryMsg::Config {})
+        .unwrap();
+    assert_eq!(
+        config.params,
+        Some(
+            to_json_binary(&XYKPoolConfig {
+                fee_share: Some(FeeShareConfig {
+                    recipients: vec![
+                        FeeShareRecipient {
+                            recipient: frontend.clone(),
+                            bps: 500
+                        },
+                        FeeShareRecipient {
+                            recipient: project.clone(),
+                            bps: 300
+                        },
+                        FeeShareRecipient {
+                            recipient: dao.clone(),
+                            bps: 200
+                        },
+                    ]
+                }),
+                hooks: vec![],
+                dynamic_fee: None,
+                protocol_fee_accrual: false,
+                max_price_deviation: None,
+                batch_auction: false,
+                trader_allowlist: None,
+            })
+            .unwrap()
+        )
+    );
+
+    let (msg, coins) = provide_liquidity_msg(
+        Uint128::new(1_000_000_000),
+        Uint128::new(1_000_000_000),
+        None,
+        None,
+        None,
+    );
+    app.execute_contract(owner.clone(), pair_instance.clone(), &msg, &coins)
+        .unwrap();
+
+    let resp = app
+        .execute_contract(
             owner.clone(),
-            alice_address.clone(),
-            &[
-                Coin {
-                    denom: "uusd".to_string(),
-                    amount: Uint128::new(233_000_000u128),
-                },
-                Coin {
-                    denom: "uluna".to_string(),
-                    amount: Uint128::new(2_00_000_000u128),
-                },
-                Coin {
-                    denom: "cny".to_string(),
-                    amount: Uint128::from(100_000_000u128),
-                },
-            ],
+            pair_instance.clone(),
+            &ExecuteMsg::Swap {
+                offer_asset: native_asset_info("uusd".to_string()).with_balance(10_000_000u128),
+                ask_asset_info: None,
+                belief_price: None,
+                max_spread: Some(Decimal::percent(5)),
+                to: None,
+                deadline: None,
+            },
+            &[coin(10_000_000, "uusd")],
         )
         .unwrap();
 
-    // Init pair
-    let pair_instance = instantiate_pair(&mut router, &owner);
+    // 9900990 uluna are returned before the 0.3% fee, 10% of the fee is shared
+    let fee_share_amount = resp
+        .events
+        .iter()
+        .flat_map(|event| &event.attributes)
+        .find(|attr| attr.key == "fee_share_amount")
+        .unwrap()
+        .value
+        .clone();
+    assert_eq!(fee_share_amount, "2970");
+
+    let balance = |addr: &Addr| app.wrap().query_balance(addr, "uluna").unwrap().amount;
+    assert_eq!(balance(&frontend).u128(), 1485);
+    assert_eq!(balance(&project).u128(), 891);
+    assert_eq!(balance(&dao).u128(), 594);
+}
 
-    let res: PairInfo = router
+#[test]
+fn test_dynamic_fee() {
+    let owner = MockApi::default().addr_make("owner");
+    let mut app = mock_app(
+        owner.clone(),
+        vec![
+            coin(1_000_000_000_000, "uusd"),
+            coin(1_000_000_000_000, "uluna"),
+        ],
+    );
+
+    // The factory fee is zero so the whole commission comes from the dynamic fee
+    let pair_instance = instantiate_pair(&mut app, &owner);
+
+    let dynamic_fee = DynamicFeeParams {
+        min_fee_bps: 10,
+        max_fee_bps: 500,
+        window: 600,
+        volatility_multiplier: Decimal::percent(10),
+    };
+    let update_msg = |params: XYKPoolUpdateParams| ExecuteMsg::UpdateConfig {
+        params: to_json_binary(&params).unwrap(),
+    };
+
+    for invalid in [
+        DynamicFeeParams {
+            min_fee_bps: 600,
+            ..dynamic_fee.clone()
+        },
+        DynamicFeeParams {
+            max_fee_bps: MAX_DYNAMIC_FEE_BPS + 1,
+            ..dynamic_fee.clone()
+        },
+        DynamicFeeParams {
+            window: 10,
+            ..dynamic_fee.clone()
+        },
+    ] {
+        let err = app
+            .execute_contract(
+                owner.clone(),
+                pair_instance.clone(),
+                &update_msg(XYKPoolUpdateParams::EnableDynamicFee(invalid)),
+                &[],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.downcast::<ContractError>().unwrap(),
+            ContractError::InvalidDynamicFeeParams {}
+        );
+    }
+
+    app.execute_contract(
+        owner.clone(),
+        pair_instance.clone(),
+        &update_msg(XYKPoolUpdateParams::EnableDynamicFee(dynamic_fee.clone())),
+        &[],
+    )
+    .unwrap();
+    let config: ConfigResponse = app
         .wrap()
-        .query_wasm_smart(pair_instance.to_string(), &QueryMsg::Pair {})
+        .query_wasm_smart(&pair_instance, &QueryMsg::Config {})
+        .unwrap();
+    assert_eq!(
+        config.params,
+        Some(
+            to_json_binary(&XYKPoolConfig {
+                fee_share: None,
+                hooks: vec![],
+                dynamic_fee: Some(dynamic_fee),
+                protocol_fee_accrual: false,
+                max_price_deviation: None,
+                batch_auction: false,
+                trader_allowlist: None,
+            })
+            .unwrap()
+        )
+    );
+
+    let (msg, coins) = provide_liquidity_msg(
+        Uint128::new(1_000_000_000),
+        Uint128::new(1_000_000_000),
+        None,
+        None,
+        None,
+    );
+    app.execute_contract(owner.clone(), pair_instance.clone(), &msg, &coins)
         .unwrap();
+    app.update_block(|block| block.time = block.time.plus_seconds(1000));
+
+    let simulate = |app: &App| -> SimulationResponse {
+        app.wrap()
+            .query_wasm_smart(
+                &pair_instance,
+                &QueryMsg::Simulation {
+                    offer_asset: native_asset_info("uluna".to_string()).with_balance(1_000_000u128),
+                    ask_asset_info: None,
+                },
+            )
+            .unwrap()
+    };
+    let fee_rate = |sim: &SimulationResponse| {
+        Decimal::from_ratio(
+            sim.commission_amount,
+            sim.return_amount + sim.commission_amount,
+        )
+    };
+
+    // The price has been stable, the minimum fee is charged
+    let sim = simulate(&app);
+    assert_eq!(sim.commission_amount.u128(), 999);
+
+    // A large swap moves the price away from the TWAP
+    app.execute_contract(
+        owner.clone(),
+        pair_instance.clone(),
+        &ExecuteMsg::Swap {
+            offer_asset: native_asset_info("uusd".to_string()).with_balance(100_000_000u128),
+            ask_asset_info: None,
+            belief_price: None,
+            max_spread: Some(Decimal::percent(50)),
+            to: None,
+            deadline: None,
+        },
+        &[coin(100_000_000, "uusd")],
+    )
+    .unwrap();
 
+    let pool: PoolResponse = app
+        .wrap()
+        .query_wasm_smart(&pair_instance, &QueryMsg::Pool {})
+        .unwrap();
+    let spot = Decimal::from_ratio(pool.assets[1].amount, pool.assets[0].amount);
+    let expected_fee_rate = (Decimal::one() - spot) * Decimal::percent(10);
+    let sim = simulate(&app);
+    assert!(fee_rate(&sim).abs_diff(expected_fee_rate) < Decimal::from_ratio(1u8, 10000u16));
+    assert!(fee_rate(&sim) > Decimal::percent(1));
+
+    // The simulation matches the actual swap
+    let user = app.api().addr_make("user");
+    app.send_tokens(owner.clone(), user.clone(), &[coin(1_000_000, "uluna")])
+        .unwrap();
+    app.execute_contract(
+        user.clone(),
+        pair_instance.clone(),
+        &ExecuteMsg::Swap {
+            offer_asset: native_asset_info("uluna".to_string()).with_balance(1_000_000u128),
+            ask_asset_info: None,
+            belief_price: None,
+            max_spread: Some(Decimal::percent(50)),
+            to: None,
+            deadline: None,
+        },
+        &[coin(1_000_000, "uluna")],
+    )
+    .unwrap();
     assert_eq!(
-        res.asset_infos,
-        [
-            AssetInfo::NativeToken {
-                denom: "uusd".to_string(),
-            },
-            AssetInfo::NativeToken {
-                denom: "uluna".to_string(),
-            },
+        app.wrap().query_balance(&user, "uusd").unwrap().amount,
+        sim.return_amount
+    );
+
+    // Once the TWAP catches up with the new price, the fee returns to the minimum
+    app.update_block(|block| block.time = block.time.plus_seconds(1000));
+    let sim = simulate(&app);
+    assert!(fee_rate(&sim).abs_diff(Decimal::bps(10)) < Decimal::from_ratio(1u8, 10000u16));
+
+    app.execute_contract(
+        owner.clone(),
+        pair_instance.clone(),
+        &update_msg(XYKPoolUpdateParams::DisableDynamicFee),
+        &[],
+    )
+    .unwrap();
+    assert!(simulate(&app).commission_amount.is_zero());
+}
+
+#[test]
+fn test_stats() {
+    let owner = MockApi::default().addr_make("owner");
+    let mut app = mock_app(
+        owner.clone(),
+        vec![
+            coin(1_000_000_000_000, "uusd"),
+            coin(1_000_000_000_000, "uluna"),
         ],
     );
 
-    // provide some liquidity to assume contract have funds (to prevent underflow err)
+    let pair_instance = instantiate_pair(&mut app, &owner);
+    let config: ConfigResponse = app
+        .wrap()
+        .query_wasm_smart(&pair_instance, &QueryMsg::Config {})
+        .unwrap();
+
+    let maker = app.api().addr_make("maker");
+    app.execute_contract(
+        owner.clone(),
+        config.factory_addr.clone(),
+        &FactoryExecuteMsg::UpdateConfig {
+            token_code_id: None,
+            fee_address: Some(maker.to_string()),
+            incentives_address: None,
+            coin_registry_address: None,
+        },
+        &[],
+    )
+    .unwrap();
+    app.execute_contract(
+        owner.clone(),
+        config.factory_addr,
+        &FactoryExecuteMsg::UpdatePairConfig {
+            config: PairConfig {
+                code_id: 0,
+                maker_fee_bps: 5000,
+                pair_type: PairType::Xyk {},
+                total_fee_bps: 30,
+                is_disabled: false,
+                is_generator_disabled: false,
+                permissioned: false,
+                native_lp_token: false,
+            },
+        },
+        &[],
+    )
+    .unwrap();
+
+    let project = app.api().addr_make("project");
+    app.execute_contract(
+        owner.clone(),
+        pair_instance.clone(),
+        &ExecuteMsg::UpdateConfig {
+            params: to_json_binary(&XYKPoolUpdateParams::EnableFeeShare {
+                fee_share_bps: 1000,
+                fee_share_address: project.to_string(),
+            })
+            .unwrap(),
+        },
+        &[],
+    )
+    .unwrap();
+
     let (msg, coins) = provide_liquidity_msg(
-        Uint128::new(100_000_000),
-        Uint128::new(100_000_000),
+        Uint128::new(1_000_000_000),
+        Uint128::new(1_000_000_000),
         None,
         None,
         None,
     );
-
-    router
-        .execute_contract(alice_address.clone(), pair_instance.clone(), &msg, &coins)
+    app.execute_contract(owner.clone(), pair_instance.clone(), &msg, &coins)
         .unwrap();
 
-    router
-        .execute_contract(alice_address.clone(), pair_instance.clone(), &msg, &coins)
+    let swap = |app: &mut App, offer_asset: Asset| -> SimulationResponse {
+        let sim: SimulationResponse = app
+            .wrap()
+            .query_wasm_smart(
+                &pair_instance,
+                &QueryMsg::Simulation {
+                    offer_asset: offer_asset.clone(),
+                    ask_asset_info: None,
+                },
+            )
+            .unwrap();
+        let funds = [offer_asset.as_coin().unwrap()];
+        app.execute_contract(
+            owner.clone(),
+            pair_instance.clone(),
+            &ExecuteMsg::Swap {
+                offer_asset,
+                ask_asset_info: None,
+                belief_price: None,
+                max_spread: None,
+                to: None,
+                deadline: None,
+            },
+            &funds,
+        )
         .unwrap();
+        sim
+    };
+    let query_stats = |app: &App, epoch: Option<u64>| -> StatsResponse {
+        app.wrap()
+            .query_wasm_smart(&pair_instance, &QueryMsg::Stats { epoch })
+            .unwrap()
+    };
 
-    // provide liquidity without funds
-    let err = router
-        .execute_contract(alice_address.clone(), pair_instance.clone(), &msg, &[])
-        .unwrap_err();
+    let sim = swap(
+        &mut app,
+        native_asset_info("uusd".to_string()).with_balance(1_000_000u128),
+    );
+    let stats = query_stats(&app, None);
+    let epoch = app.block_info().time.seconds() / STATS_EPOCH_LENGTH;
+    assert_eq!(stats.epoch, epoch);
+    assert_eq!(stats.epoch_start, epoch * STATS_EPOCH_LENGTH);
+    assert_eq!(stats.epoch_stats, stats.lifetime_stats);
+
+    let [uusd_stats, uluna_stats] = &stats.epoch_stats[..] else {
+        panic!("Expected stats for two assets");
+    };
+    assert_eq!(uusd_stats.volume_in.u128(), 1_000_000);
+    assert!(uusd_stats.volume_out.is_zero());
+    assert!(uusd_stats.lp_fee.is_zero());
+    assert_eq!(uluna_stats.volume_out, sim.return_amount);
     assert_eq!(
-        err.root_cause().to_string(),
-        "Generic error: Native token balance mismatch between the argument (100000000uusd) and the transferred (0uusd)"
+        uluna_stats.fee_share,
+        app.wrap().query_balance(&project, "uluna").unwrap().amount
+    );
+    assert_eq!(
+        uluna_stats.maker_fee,
+        app.wrap().query_balance(&maker, "uluna").unwrap().amount
+    );
+    assert!(!uluna_stats.lp_fee.is_zero());
+    assert_eq!(
+        uluna_stats.lp_fee + uluna_stats.maker_fee + uluna_stats.fee_share,
+        sim.commission_amount
+    );
+
+    // Swaps in the next epoch are accounted separately
+    app.update_block(|block| block.time = block.time.plus_seconds(STATS_EPOCH_LENGTH));
+    let reverse_sim = swap(
+        &mut app,
+        native_asset_info("uluna".to_string()).with_balance(2_000_000u128),
+    );
+
+    let stats = query_stats(&app, None);
+    assert_eq!(stats.epoch, epoch + 1);
+    assert_eq!(stats.epoch_stats[1].volume_in.u128(), 2_000_000);
+    assert_eq!(stats.epoch_stats[0].volume_out, reverse_sim.return_amount);
+    assert!(stats.epoch_stats[0].volume_in.is_zero());
+    assert!(stats.epoch_stats[1].lp_fee.is_zero());
+    assert_eq!(stats.lifetime_stats[0].volume_in.u128(), 1_000_000);
+    assert_eq!(stats.lifetime_stats[1].volume_in.u128(), 2_000_000);
+    assert_eq!(stats.lifetime_stats[1].lp_fee, uluna_stats.lp_fee);
+
+    // Past epochs are still available
+    assert_eq!(query_stats(&app, Some(epoch)).epoch_stats[1], *uluna_stats);
+}
+
+#[test]
+fn test_protocol_fee_accrual() {
+    let owner = MockApi::default().addr_make("owner");
+    let mut app = mock_app(
+        owner.clone(),
+        vec![
+            coin(1_000_000_000_000, "uusd"),
+            coin(1_000_000_000_000, "uluna"),
+        ],
     );
+
+    let pair_instance = instantiate_pair(&mut app, &owner);
+    let config: ConfigResponse = app
+        .wrap()
+        .query_wasm_smart(&pair_instance, &QueryMsg::Config {})
+        .unwrap();
+    let pair_info: PairInfo = app
+        .wrap()
+        .query_wasm_smart(&pair_instance, &QueryMsg::Pair {})
+        .unwrap();
+
+    let maker = app.api().addr_make("maker");
+    app.execute_contract(
+        owner.clone(),
+        config.factory_addr.clone(),
+        &FactoryExecuteMsg::UpdateConfig {
+            token_code_id: None,
+            fee_address: Some(maker.to_string()),
+            incentives_address: None,
+            coin_registry_address: None,
+        },
+        &[],
+    )
+    .unwrap();
+    app.execute_contract(
+        owner.clone(),
+        config.factory_addr,
+        &FactoryExecuteMsg::UpdatePairConfig {
+            config: PairConfig {
+                code_id: 0,
+                maker_fee_bps: 5000,
+                pair_type: PairType::Xyk {},
+                total_fee_bps: 30,
+                is_disabled: false,
+                is_generator_disabled: false,
+                permissioned: false,
+                native_lp_token: false,
+            },
+        },
+        &[],
+    )
+    .unwrap();
+
+    let update_msg = |params: XYKPoolUpdateParams| ExecuteMsg::UpdateConfig {
+        params: to_json_binary(&params).unwrap(),
+    };
+    let provide = |app: &mut App| {
+        let (msg, coins) = provide_liquidity_msg(
+            Uint128::new(1_000_000_000),
+            Uint128::new(1_000_000_000),
+            None,
+            Some(Decimal::percent(5)),
+            None,
+        );
+        app.execute_contract(owner.clone(), pair_instance.clone(), &msg, &coins)
+            .unwrap();
+    };
+    let swap = |app: &mut App| -> Uint128 {
+        let offer_asset = native_asset_info("uusd".to_string()).with_balance(10_000_000u128);
+        let sim: SimulationResponse = app
+            .wrap()
+            .query_wasm_smart(
+                &pair_instance,
+                &QueryMsg::Simulation {
+                    offer_asset: offer_asset.clone(),
+                    ask_asset_info: None,
+                },
+            )
+            .unwrap();
+        app.execute_contract(
+            owner.clone(),
+            pair_instance.clone(),
+            &ExecuteMsg::Swap {
+                offer_asset,
+                ask_asset_info: None,
+                belief_price: None,
+                max_spread: Some(Decimal::percent(5)),
+                to: None,
+                deadline: None,
+            },
+            &[coin(10_000_000, "uusd")],
+        )
+        .unwrap();
+        sim.commission_amount
+    };
+    let maker_lp_balance = |app: &App| -> Uint128 {
+        app.wrap()
+            .query_wasm_smart::<BalanceResponse>(
+                &pair_info.liquidity_token,
+                &Cw20QueryMsg::Balance {
+                    address: maker.to_string(),
+                },
+            )
+            .unwrap()
+            .balance
+    };
+    let collect = |app: &mut App| {
+        app.execute_contract(
+            owner.clone(),
+            pair_instance.clone(),
+            &ExecuteMsg::CollectProtocolFees {},
+            &[],
+        )
+    };
+
+    let err = collect(&mut app).unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::ProtocolFeeAccrualDisabled {}
+    );
+
+    provide(&mut app);
+    app.execute_contract(
+        owner.clone(),
+        pair_instance.clone(),
+        &update_msg(XYKPoolUpdateParams::EnableProtocolFeeAccrual),
+        &[],
+    )
+    .unwrap();
+    let config: ConfigResponse = app
+        .wrap()
+        .query_wasm_smart(&pair_instance, &QueryMsg::Config {})
+        .unwrap();
+    let params: XYKPoolConfig = from_json(config.params.unwrap()).unwrap();
+    assert!(params.protocol_fee_accrual);
+
+    // The maker fee stays in the pool
+    let commission = swap(&mut app);
+    assert!(app
+        .wrap()
+        .query_balance(&maker, "uluna")
+        .unwrap()
+        .amount
+        .is_zero());
+    // but it is still accounted as the maker fee
+    let stats: StatsResponse = app
+        .wrap()
+        .query_wasm_smart(&pair_instance, &QueryMsg::Stats { epoch: None })
+        .unwrap();
+    assert_eq!(
+        stats.lifetime_stats[1].maker_fee,
+        commission.multiply_ratio(1u8, 2u8)
+    );
+    assert_eq!(
+        stats.lifetime_stats[1].lp_fee + stats.lifetime_stats[1].maker_fee,
+        commission
+    );
+
+    // The maker gets LP tokens worth roughly half of the commission
+    collect(&mut app).unwrap();
+    let maker_share = maker_lp_balance(&app);
+    let share_value: Vec<Asset> = app
+        .wrap()
+        .query_wasm_smart(
+            &pair_instance,
+            &QueryMsg::Share {
+                amount: maker_share,
+            },
+        )
+        .unwrap();
+    let value_in_uluna = share_value[0].amount + share_value[1].amount;
+    let expected = commission.u128() / 2;
+    assert!(
+        value_in_uluna.u128().abs_diff(expected) * 100 < expected,
+        "{value_in_uluna} != {expected}"
+    );
+
+    // Nothing accrued since the last collection
+    collect(&mut app).unwrap();
+    assert_eq!(maker_lp_balance(&app), maker_share);
+
+    // The maker share of the balancing swap fee of a single-sided provision is minted right away
+    let query_stats = |app: &App| -> StatsResponse {
+        app.wrap()
+            .query_wasm_smart(&pair_instance, &QueryMsg::Stats { epoch: None })
+            .unwrap()
+    };
+    let stats_before = query_stats(&app);
+    app.execute_contract(
+        owner.clone(),
+        pair_instance.clone(),
+        &ExecuteMsg::ProvideLiquidity {
+            assets: vec![native_asset_info("uluna".to_string()).with_balance(20_000_000u128)],
+            slippage_tolerance: Some(Decimal::percent(5)),
+            auto_stake: None,
+            receiver: None,
+            min_lp_to_receive: None,
+            deadline: None,
+        },
+        &[coin(20_000_000, "uluna")],
+    )
+    .unwrap();
+    let maker_share_after_balancing = maker_lp_balance(&app);
+    assert!(maker_share_after_balancing > maker_share);
+    let stats = query_stats(&app);
+    let maker_fee = stats.lifetime_stats[0].maker_fee - stats_before.lifetime_stats[0].maker_fee;
+    let lp_fee = stats.lifetime_stats[0].lp_fee - stats_before.lifetime_stats[0].lp_fee;
+    assert!(!maker_fee.is_zero());
+    assert!(maker_fee.u128().abs_diff(lp_fee.u128()) <= 1);
+
+    collect(&mut app).unwrap();
+    assert_eq!(maker_lp_balance(&app), maker_share_after_balancing);
+    let maker_share = maker_share_after_balancing;
+
+    // The accrued fee is minted on the next provision
+    swap(&mut app);
+    provide(&mut app);
+    let maker_share_after_provide = maker_lp_balance(&app);
+    assert!(maker_share_after_provide > maker_share);
+
+    // Disabling the accrual collects the pending fee
+    swap(&mut app);
+    app.execute_contract(
+        owner.clone(),
+        pair_instance.clone(),
+        &update_msg(XYKPoolUpdateParams::DisableProtocolFeeAccrual),
+        &[],
+    )
+    .unwrap();
+    assert!(maker_lp_balance(&app) > maker_share_after_provide);
+
+    // The maker fee is sent on every swap again
+    swap(&mut app);
+    assert!(!app
+        .wrap()
+        .query_balance(&maker, "uluna")
+        .unwrap()
+        .amount
+        .is_zero());
+}
+
+#[test]
+fn test_deadline() {
+    let owner = MockApi::default().addr_make("owner");
+    let mut app = mock_app(
+        owner.clone(),
+        vec![
+            coin(1_000_000_000_000, "uusd"),
+            coin(1_000_000_000_000, "uluna"),
+        ],
+    );
+
+    let pair_instance = instantiate_pair(&mut app, &owner);
+    let pair_info: PairInfo = app
+        .wrap()
+        .query_wasm_smart(&pair_instance, &QueryMsg::Pair {})
+        .unwrap();
+    let now = app.block_info().time.seconds();
+
+    let with_deadline = |mut msg: ExecuteMsg, new_deadline: u64| {
+        if let ExecuteMsg::ProvideLiquidity { deadline, .. } = &mut msg {
+            *deadline = Some(new_deadline);
+        }
+        msg
+    };
+
+    let (msg, coins) = provide_liquidity_msg(
+        Uint128::new(1_000_000_000),
+        Uint128::new(1_000_000_000),
+        None,
+        None,
+        None,
+    );
+    let err = app
+        .execute_contract(
+            owner.clone(),
+            pair_instance.clone(),
+            &with_deadline(msg.clone(), now - 1),
+            &coins,
+        )
+        .unwrap_err();
+    assert_eq!(ContractError::DeadlineExceeded {}, err.downcast().unwrap());
+
+    // The deadline is inclusive
+    app.execute_contract(
+        owner.clone(),
+        pair_instance.clone(),
+        &with_deadline(msg, now),
+        &coins,
+    )
+    .unwrap();
+
+    let swap_msg = |deadline: Option<u64>| ExecuteMsg::Swap {
+        offer_asset: native_asset_info("uusd".to_string()).with_balance(1_000u128),
+        ask_asset_info: None,
+        belief_price: None,
+        max_spread: None,
+        to: None,
+        deadline,
+    };
+    app.update_block(|b| b.time = b.time.plus_seconds(60));
+    let err = app
+        .execute_contract(
+            owner.clone(),
+            pair_instance.clone(),
+            &swap_msg(Some(now + 59)),
+            &[coin(1_000, "uusd")],
+        )
+        .unwrap_err();
+    assert_eq!(ContractError::DeadlineExceeded {}, err.downcast().unwrap());
+    app.execute_contract(
+        owner.clone(),
+        pair_instance.clone(),
+        &swap_msg(Some(now + 60)),
+        &[coin(1_000, "uusd")],
+    )
+    .unwrap();
+    app.execute_contract(
+        owner.clone(),
+        pair_instance.clone(),
+        &swap_msg(None),
+        &[coin(1_000, "uusd")],
+    )
+    .unwrap();
+
+    let swap_exact_out_msg = |deadline: u64| ExecuteMsg::SwapExactOut {
+        ask_asset: native_asset_info("uluna".to_string()).with_balance(1_000u128),
+        max_offer_amount: Uint128::new(2_000),
+        to: None,
+        deadline: Some(deadline),
+    };
+    let err = app
+        .execute_contract(
+            owner.clone(),
+            pair_instance.clone(),
+            &swap_exact_out_msg(now + 59),
+            &[coin(2_000, "uusd")],
+        )
+        .unwrap_err();
+    assert_eq!(ContractError::DeadlineExceeded {}, err.downcast().unwrap());
+    app.execute_contract(
+        owner.clone(),
+        pair_instance.clone(),
+        &swap_exact_out_msg(now + 60),
+        &[coin(2_000, "uusd")],
+    )
+    .unwrap();
+
+    // CW20 hook messages are checked as well
+    let withdraw_msg = |deadline: u64| Cw20ExecuteMsg::Send {
+        contract: pair_instance.to_string(),
+        amount: Uint128::new(1_000),
+        msg: to_json_binary(&Cw20HookMsg::WithdrawLiquidity {
+            min_assets_to_receive: None,
+            asset: None,
+            deadline: Some(deadline),
+        })
+        .unwrap(),
+    };
+    let lp_token = Addr::unchecked(&pair_info.liquidity_token);
+    let err = app
+        .execute_contract(owner.clone(), lp_token.clone(), &withdraw_msg(now), &[])
+        .unwrap_err();
+    assert_eq!(ContractError::DeadlineExceeded {}, err.downcast().unwrap());
+    app.execute_contract(owner.clone(), lp_token, &withdraw_msg(now + 60), &[])
+        .unwrap();
+}
+
+#[test]
+fn test_price_guard() {
+    let owner = MockApi::default().addr_make("owner");
+    let mut app = mock_app(
+        owner.clone(),
+        vec![
+            coin(1_000_000_000_000, "uusd"),
+            coin(1_000_000_000_000, "uluna"),
+        ],
+    );
+
+    let pair_instance = instantiate_pair(&mut app, &owner);
+    let (msg, coins) = provide_liquidity_msg(
+        Uint128::new(1_000_000_000),
+        Uint128::new(1_000_000_000),
+        None,
+        None,
+        None,
+    );
+    app.execute_contract(owner.clone(), pair_instance.clone(), &msg, &coins)
+        .unwrap();
+
+    let update_msg = |params: XYKPoolUpdateParams| ExecuteMsg::UpdateConfig {
+        params: to_json_binary(&params).unwrap(),
+    };
+    let err = app
+        .execute_contract(
+            owner.clone(),
+            pair_instance.clone(),
+            &update_msg(XYKPoolUpdateParams::EnablePriceGuard {
+                max_deviation: Decimal::percent(51),
+            }),
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(
+        ContractError::InvalidPriceDeviation {},
+        err.downcast().unwrap()
+    );
+
+    app.execute_contract(
+        owner.clone(),
+        pair_instance.clone(),
+        &update_msg(XYKPoolUpdateParams::EnablePriceGuard {
+            max_deviation: Decimal::percent(5),
+        }),
+        &[],
+    )
+    .unwrap();
+    let config: ConfigResponse = app
+        .wrap()
+        .query_wasm_smart(&pair_instance, &QueryMsg::Config {})
+        .unwrap();
+    let params: XYKPoolConfig = from_json(config.params.unwrap()).unwrap();
+    assert_eq!(params.max_price_deviation, Some(Decimal::percent(5)));
+
+    let swap = |app: &mut App, amount: u128| {
+        app.execute_contract(
+            owner.clone(),
+            pair_instance.clone(),
+            &ExecuteMsg::Swap {
+                offer_asset: native_asset_info("uusd".to_string()).with_balance(amount),
+                ask_asset_info: None,
+                belief_price: None,
+                max_spread: Some(Decimal::percent(10)),
+                to: None,
+                deadline: None,
+            },
+            &[coin(amount, "uusd")],
+        )
+    };
+
+    // Moves the price by ~2%
+    app.update_block(|b| b.time = b.time.plus_seconds(5));
+    swap(&mut app, 10_000_000).unwrap();
+
+    // Another ~4% within the same block exceeds the limit
+    let err = swap(&mut app, 20_000_000).unwrap_err();
+    assert!(matches!(
+        err.downcast().unwrap(),
+        ContractError::PriceDeviationExceeded { max_deviation, .. } if max_deviation == Decimal::percent(5)
+    ));
+
+    // The next block opens at the new price
+    app.update_block(|b| b.time = b.time.plus_seconds(5));
+    swap(&mut app, 20_000_000).unwrap();
+
+    // The balancing swap of a single-sided deposit is guarded as well
+    app.update_block(|b| b.time = b.time.plus_seconds(5));
+    let err = app
+        .execute_contract(
+            owner.clone(),
+            pair_instance.clone(),
+            &ExecuteMsg::ProvideLiquidity {
+                assets: vec![native_asset_info("uusd".to_string()).with_balance(200_000_000u128)],
+                slippage_tolerance: Some(Decimal::percent(50)),
+                auto_stake: None,
+                receiver: None,
+                min_lp_to_receive: None,
+                deadline: None,
+            },
+            &[coin(200_000_000, "uusd")],
+        )
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast().unwrap(),
+        ContractError::PriceDeviationExceeded { max_deviation, .. } if max_deviation == Decimal::percent(5)
+    ));
+
+    app.execute_contract(
+        owner.clone(),
+        pair_instance.clone(),
+        &update_msg(XYKPoolUpdateParams::DisablePriceGuard),
+        &[],
+    )
+    .unwrap();
+    swap(&mut app, 50_000_000).unwrap();
+}
+
+#[test]
+fn test_batch_auction() {
+    let owner = MockApi::default().addr_make("owner");
+    let mut app = mock_app(
+        owner.clone(),
+        vec![
+            coin(1_000_000_000_000, "uusd"),
+            coin(1_000_000_000_000, "uluna"),
+        ],
+    );
+
+    let pair_instance = instantiate_pair(&mut app, &owner);
+    let (msg, coins) = provide_liquidity_msg(
+        Uint128::new(1_000_000_000),
+        Uint128::new(1_000_000_000),
+        None,
+        None,
+        None,
+    );
+    app.execute_contract(owner.clone(), pair_instance.clone(), &msg, &coins)
+        .unwrap();
+
+    let alice = app.api().addr_make("alice");
+    let bob = app.api().addr_make("bob");
+    let carol = app.api().addr_make("carol");
+    app.send_tokens(owner.clone(), alice.clone(), &[coin(20_000_000, "uusd")])
+        .unwrap();
+    app.send_tokens(owner.clone(), bob.clone(), &[coin(5_000_000, "uluna")])
+        .unwrap();
+    app.send_tokens(owner.clone(), carol.clone(), &[coin(1_000_000, "uusd")])
+        .unwrap();
+
+    app.execute_contract(
+        owner.clone(),
+        pair_instance.clone(),
+        &ExecuteMsg::UpdateConfig {
+            params: to_json_binary(&XYKPoolUpdateParams::EnableBatchAuction).unwrap(),
+        },
+        &[],
+    )
+    .unwrap();
+    let config: ConfigResponse = app
+        .wrap()
+        .query_wasm_smart(&pair_instance, &QueryMsg::Config {})
+        .unwrap();
+    let params: XYKPoolConfig = from_json(config.params.unwrap()).unwrap();
+    assert!(params.batch_auction);
+
+    let swap_msg = |denom: &str, amount: u128, belief_price: Option<Decimal>| ExecuteMsg::Swap {
+        offer_asset: native_asset_info(denom.to_string()).with_balance(amount),
+        ask_asset_info: None,
+        belief_price,
+        max_spread: Some(Decimal::percent(5)),
+        to: None,
+        deadline: None,
+    };
+    let balance = |app: &App, addr: &Addr, denom: &str| {
+        app.wrap().query_balance(addr, denom).unwrap().amount.u128()
+    };
+
+    // Instant swaps are disabled
+    let err = app
+        .execute_contract(
+            alice.clone(),
+            pair_instance.clone(),
+            &ExecuteMsg::SwapExactOut {
+                ask_asset: native_asset_info("uluna".to_string()).with_balance(1_000u128),
+                max_offer_amount: Uint128::new(2_000),
+                to: None,
+                deadline: None,
+            },
+            &[coin(2_000, "uusd")],
+        )
+        .unwrap_err();
+    assert_eq!(
+        ContractError::BatchAuctionEnabled {},
+        err.downcast().unwrap()
+    );
+
+    app.execute_contract(
+        alice.clone(),
+        pair_instance.clone(),
+        &swap_msg("uusd", 10_000_000, None),
+        &[coin(10_000_000, "uusd")],
+    )
+    .unwrap();
+    app.execute_contract(
+        bob.clone(),
+        pair_instance.clone(),
+        &swap_msg("uluna", 5_000_000, None),
+        &[coin(5_000_000, "uluna")],
+    )
+    .unwrap();
+    // The belief price can't be met at the clearing price
+    app.execute_contract(
+        carol.clone(),
+        pair_instance.clone(),
+        &swap_msg("uusd", 1_000_000, Some(Decimal::percent(50))),
+        &[coin(1_000_000, "uusd")],
+    )
+    .unwrap();
+    assert_eq!(balance(&app, &alice, "uluna"), 0);
+
+    let orders: Vec<BatchOrder> = app
+        .wrap()
+        .query_wasm_smart(
+            &pair_instance,
+            &QueryMsg::BatchOrders {
+                trader: Some(alice.to_string()),
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(orders.len(), 1);
+    assert_eq!(orders[0].offer_asset.amount.u128(), 10_000_000);
+    let order: BatchOrder = app
+        .wrap()
+        .query_wasm_smart(&pair_instance, &QueryMsg::BatchOrder { order_id: 1 })
+        .unwrap();
+    assert_eq!(order.trader, bob);
+
+    // Queued funds are not part of the pool
+    let pool_balance = balance(&app, &pair_instance, "uusd");
+    app.execute_contract(
+        owner.clone(),
+        pair_instance.clone(),
+        &ExecuteMsg::Skim { to: None },
+        &[],
+    )
+    .unwrap();
+    assert_eq!(balance(&app, &pair_instance, "uusd"), pool_balance);
+
+    // Only the trader can cancel an order
+    app.execute_contract(
+        alice.clone(),
+        pair_instance.clone(),
+        &swap_msg("uusd", 10_000_000, None),
+        &[coin(10_000_000, "uusd")],
+    )
+    .unwrap();
+    let err = app
+        .execute_contract(
+            bob.clone(),
+            pair_instance.clone(),
+            &ExecuteMsg::CancelBatchOrder { order_id: 3 },
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+    app.execute_contract(
+        alice.clone(),
+        pair_instance.clone(),
+        &ExecuteMsg::CancelBatchOrder { order_id: 3 },
+        &[],
+    )
+    .unwrap();
+    assert_eq!(balance(&app, &alice, "uusd"), 10_000_000);
+
+    // Orders are settled in the next block
+    let keeper = app.api().addr_make("keeper");
+    let err = app
+        .execute_contract(
+            keeper.clone(),
+            pair_instance.clone(),
+            &ExecuteMsg::SettleBatch {},
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(
+        ContractError::NoBatchOrdersToSettle {},
+        err.downcast().unwrap()
+    );
+
+    app.update_block(|b| {
+        b.height += 1;
+        b.time = b.time.plus_seconds(5);
+    });
+    let resp = app
+        .execute_contract(
+            keeper.clone(),
+            pair_instance.clone(),
+            &ExecuteMsg::SettleBatch {},
+            &[],
+        )
+        .unwrap();
+    assert!(resp.has_event(
+        &Event::new("wasm-settle_batch")
+            .add_attribute("filled_orders", "2")
+            .add_attribute("refunded_orders", "1")
+    ));
+
+    // Both sides trade at the same price
+    let alice_return = balance(&app, &alice, "uluna");
+    let bob_return = balance(&app, &bob, "uusd");
+    assert_eq!(alice_return, 9_950_494);
+    assert_eq!(bob_return, 5_024_876);
+    assert!(
+        Decimal::from_ratio(alice_return, 10_000_000u128)
+            .abs_diff(Decimal::from_ratio(5_000_000u128, bob_return))
+            < Decimal::from_ratio(1u8, 1_000_000u32)
+    );
+    assert_eq!(balance(&app, &carol, "uusd"), 1_000_000);
+
+    let orders: Vec<BatchOrder> = app
+        .wrap()
+        .query_wasm_smart(
+            &pair_instance,
+            &QueryMsg::BatchOrders {
+                trader: None,
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+    assert!(orders.is_empty());
+
+    let pool: PoolResponse = app
+        .wrap()
+        .query_wasm_smart(&pair_instance, &QueryMsg::Pool {})
+        .unwrap();
+    assert_eq!(
+        pool.assets[0].amount.u128(),
+        1_000_000_000 + 10_000_000 - bob_return
+    );
+    assert_eq!(
+        pool.assets[1].amount.u128(),
+        1_000_000_000 + 5_000_000 - alice_return
+    );
+
+    // Swaps execute immediately after the batch auction is disabled
+    app.execute_contract(
+        owner.clone(),
+        pair_instance.clone(),
+        &ExecuteMsg::UpdateConfig {
+            params: to_json_binary(&XYKPoolUpdateParams::DisableBatchAuction).unwrap(),
+        },
+        &[],
+    )
+    .unwrap();
+    app.execute_contract(
+        alice.clone(),
+        pair_instance.clone(),
+        &swap_msg("uusd", 1_000_000, None),
+        &[coin(1_000_000, "uusd")],
+    )
+    .unwrap();
+    assert!(balance(&app, &alice, "uluna") > alice_return);
+}
+
+#[test]
+fn test_batch_auction_guards() {
+    let owner = MockApi::default().addr_make("owner");
+    let mut app = mock_app(
+        owner.clone(),
+        vec![
+            coin(1_000_000_000_000, "uusd"),
+            coin(1_000_000_000_000, "uluna"),
+        ],
+    );
+
+    let pair_instance = instantiate_pair(&mut app, &owner);
+    let (msg, coins) = provide_liquidity_msg(
+        Uint128::new(1_000_000_000),
+        Uint128::new(1_000_000_000),
+        None,
+        None,
+        None,
+    );
+    app.execute_contract(owner.clone(), pair_instance.clone(), &msg, &coins)
+        .unwrap();
+
+    let alice = app.api().addr_make("alice");
+    let bob = app.api().addr_make("bob");
+    let carol = app.api().addr_make("carol");
+    for (user, amount) in [
+        (&alice, 1_000_000),
+        (&bob, 100_000_000),
+        (&carol, 1_000_000),
+    ] {
+        app.send_tokens(owner.clone(), user.clone(), &[coin(amount, "uusd")])
+            .unwrap();
+    }
+
+    for params in [
+        XYKPoolUpdateParams::EnableBatchAuction,
+        XYKPoolUpdateParams::EnablePriceGuard {
+            max_deviation: Decimal::percent(1),
+        },
+        XYKPoolUpdateParams::SetTraderAllowlist(TraderAllowlist::Addresses(vec![
+            alice.clone(),
+            bob.clone(),
+            carol.clone(),
+        ])),
+    ] {
+        app.execute_contract(
+            owner.clone(),
+            pair_instance.clone(),
+            &ExecuteMsg::UpdateConfig {
+                params: to_json_binary(&params).unwrap(),
+            },
+            &[],
+        )
+        .unwrap();
+    }
+
+    for (user, amount) in [
+        (&alice, 1_000_000),
+        (&bob, 100_000_000),
+        (&carol, 1_000_000),
+    ] {
+        app.execute_contract(
+            user.clone(),
+            pair_instance.clone(),
+            &ExecuteMsg::Swap {
+                offer_asset: native_asset_info("uusd".to_string()).with_balance(amount),
+                ask_asset_info: None,
+                belief_price: None,
+                max_spread: Some(Decimal::percent(50)),
+                to: None,
+                deadline: None,
+            },
+            &[coin(amount, "uusd")],
+        )
+        .unwrap();
+    }
+
+    // Carol is removed from the allowlist after placing the order
+    app.execute_contract(
+        owner.clone(),
+        pair_instance.clone(),
+        &ExecuteMsg::UpdateConfig {
+            params: to_json_binary(&XYKPoolUpdateParams::SetTraderAllowlist(
+                TraderAllowlist::Addresses(vec![alice.clone(), bob.clone()]),
+            ))
+            .unwrap(),
+        },
+        &[],
+    )
+    .unwrap();
+
+    app.update_block(|b| {
+        b.height += 1;
+        b.time = b.time.plus_seconds(5);
+    });
+    let resp = app
+        .execute_contract(
+            owner.clone(),
+            pair_instance.clone(),
+            &ExecuteMsg::SettleBatch {},
+            &[],
+        )
+        .unwrap();
+    assert!(resp.has_event(
+        &Event::new("wasm-settle_batch")
+            .add_attribute("filled_orders", "1")
+            .add_attribute("refunded_orders", "2")
+    ));
+
+    // Bob's order moves the price by more than 1% thus it is refunded
+    let balance = |app: &App, addr: &Addr, denom: &str| {
+        app.wrap().query_balance(addr, denom).unwrap().amount.u128()
+    };
+    assert_eq!(balance(&app, &bob, "uusd"), 100_000_000);
+    assert_eq!(balance(&app, &carol, "uusd"), 1_000_000);
+    assert_eq!(balance(&app, &alice, "uusd"), 0);
+    assert_eq!(balance(&app, &alice, "uluna"), 999_000);
+}
+
+#[test]
+fn test_batch_auction_provide() {
+    let owner = MockApi::default().addr_make("owner");
+    let mut app = mock_app(
+        owner.clone(),
+        vec![
+            coin(1_000_000_000_000, "uusd"),
+            coin(1_000_000_000_000, "uluna"),
+        ],
+    );
+
+    let pair_instance = instantiate_pair(&mut app, &owner);
+    let (msg, coins) = provide_liquidity_msg(
+        Uint128::new(1_000_000_000),
+        Uint128::new(2_000_000_000),
+        None,
+        None,
+        None,
+    );
+    app.execute_contract(owner.clone(), pair_instance.clone(), &msg, &coins)
+        .unwrap();
+
+    app.execute_contract(
+        owner.clone(),
+        pair_instance.clone(),
+        &ExecuteMsg::UpdateConfig {
+            params: to_json_binary(&XYKPoolUpdateParams::EnableBatchAuction).unwrap(),
+        },
+        &[],
+    )
+    .unwrap();
+
+    // A single-sided deposit would be balanced with an instant swap
+    let err = app
+        .execute_contract(
+            owner.clone(),
+            pair_instance.clone(),
+            &ExecuteMsg::ProvideLiquidity {
+                assets: vec![native_asset_info("uusd".to_string()).with_balance(1_000_000u128)],
+                slippage_tolerance: None,
+                auto_stake: None,
+                receiver: None,
+                min_lp_to_receive: None,
+                deadline: None,
+            },
+            &[coin(1_000_000, "uusd")],
+        )
+        .unwrap_err();
+    assert_eq!(
+        ContractError::ImbalancedProvideInBatchMode {},
+        err.downcast().unwrap()
+    );
+
+    // Deposits in the pool ratio are accepted
+    let (msg, coins) = provide_liquidity_msg(
+        Uint128::new(1_000_000),
+        Uint128::new(2_000_000),
+        None,
+        None,
+        None,
+    );
+    app.execute_contract(owner.clone(), pair_instance.clone(), &msg, &coins)
+        .unwrap();
+    let pool: PoolResponse = app
+        .wrap()
+        .query_wasm_smart(&pair_instance, &QueryMsg::Pool {})
+        .unwrap();
+    assert_eq!(pool.assets[0].amount.u128(), 1_001_000_000);
+    assert_eq!(pool.assets[1].amount.u128(), 2_002_000_000);
+}
+
+#[test]
+fn test_provide_liquidity_without_funds() {
+    let api = MockApi::default();
+    let owner = api.addr_make("owner");
+    let alice_address = api.addr_make("alice");
+    let mut router = mock_app(
+        owner.clone(),
+        vec![
+            Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::new(100_000_000_000u128),
+            },
+            Coin {
+                denom: "uluna".to_string(),
+                amount: Uint128::new(100_000_000_000u128),
+            },
+            Coin {
+                denom: "cny".to_string(),
+                amount: Uint128::new(100_000_000_000u128),
+            },
+        ],
+    );
+
+    // Set Alice's balances
+    router
+        .send_tokens(
+            owner.clone(),
+            alice_address.clone(),
+            &[
+                Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::new(233_000_000u128),
+                },
+                Coin {
+                    denom: "uluna".to_string(),
+                    amount: Uint128::new(2_00_000_000u128),
+                },
+                Coin {
+                    denom: "cny".to_string(),
+                    amount: Uint128::from(100_000_000u128),
+                },
+            ],
+        )
+        .unwrap();
+
+    // Init pair
+    let pair_instance = instantiate_pair(&mut router, &owner);
+
+    let res: PairInfo = router
+        .wrap()
+        .query_wasm_smart(pair_instance.to_string(), &QueryMsg::Pair {})
+        .unwrap();
+
+    assert_eq!(
+        res.asset_infos,
+        [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::NativeToken {
+                denom: "uluna".to_string(),
+            },
+        ],
+    );
+
+    // provide some liquidity to assume contract have funds (to prevent underflow err)
+    let (msg, coins) = provide_liquidity_msg(
+        Uint128::new(100_000_000),
+        Uint128::new(100_000_000),
+        None,
+        None,
+        None,
+    );
+
+    router
+        .execute_contract(alice_address.clone(), pair_instance.clone(), &msg, &coins)
+        .unwrap();
+
+    router
+        .execute_contract(alice_address.clone(), pair_instance.clone(), &msg, &coins)
+        .unwrap();
+
+    // provide liquidity without funds
+    let err = router
+        .execute_contract(alice_address.clone(), pair_instance.clone(), &msg, &[])
+        .unwrap_err();
+    assert_eq!(
+        err.root_cause().to_string(),
+        "Generic error: Native token balance mismatch between the argument (100000000uusd) and the transferred (0uusd)"
+    );
+}
+
+#[test]
+fn test_provide_and_withdraw_with_native_lp_token() {
+    let api = MockApi::default();
+    let owner = api.addr_make("owner");
+    let alice_address = api.addr_make("alice");
+    let mut app = AppBuilder::new()
+        .with_stargate(MockStargate)
+        .build(|router, _, storage| {
+            router
+                .bank
+                .init_balance(
+                    storage,
+                    &alice_address,
+                    vec![
+                        coin(100_000_000_000, "uusd"),
+                        coin(100_000_000_000, "uluna"),
+                    ],
+                )
+                .unwrap()
+        });
+
+    let pair_code_id = app.store_code(Box::new(
+        ContractWrapper::new_with_empty(
+            astroport_pair::contract::execute,
+            astroport_pair::contract::instantiate,
+            astroport_pair::contract::query,
+        )
+        .with_reply_empty(astroport_pair::contract::reply),
+    ));
+    let factory_code_id = app.store_code(Box::new(
+        ContractWrapper::new_with_empty(
+            astroport_factory::contract::execute,
+            astroport_factory::contract::instantiate,
+            astroport_factory::contract::query,
+        )
+        .with_reply_empty(astroport_factory::contract::reply),
+    ));
+
+    let factory = app
+        .instantiate_contract(
+            factory_code_id,
+            owner.clone(),
+            &FactoryInstantiateMsg {
+                fee_address: None,
+                pair_configs: vec![PairConfig {
+                    code_id: pair_code_id,
+                    maker_fee_bps: 0,
+                    pair_type: PairType::Xyk {},
+                    total_fee_bps: 0,
+                    is_disabled: false,
+                    is_generator_disabled: false,
+                    permissioned: false,
+                    native_lp_token: true,
+                }],
+                token_code_id: 0,
+                incentives_address: None,
+                owner: owner.to_string(),
+                coin_registry_address: api.addr_make("coin_registry").to_string(),
+            },
+            &[],
+            "FACTORY",
+            None,
+        )
+        .unwrap();
+
+    app.execute_contract(
+        owner.clone(),
+        factory.clone(),
+        &FactoryExecuteMsg::CreatePair {
+            pair_type: PairType::Xyk {},
+            asset_infos: vec![
+                native_asset_info("uusd".to_string()),
+                native_asset_info("uluna".to_string()),
+            ],
+            init_params: None,
+        },
+        &[],
+    )
+    .unwrap();
+
+    let pair_info: Vec<PairInfo> = app
+        .wrap()
+        .query_wasm_smart(
+            &factory,
+            &FactoryQueryMsg::PairsByAssetInfos {
+                asset_infos: vec![
+                    native_asset_info("uusd".to_string()),
+                    native_asset_info("uluna".to_string()),
+                ],
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+    let pair_info = pair_info[0].clone();
+    let lp_denom = pair_info.liquidity_token.clone();
+    assert_eq!(
+        lp_denom,
+        format!("factory/{}/astroport/share", pair_info.contract_addr)
+    );
+
+    let res: PairInfo = app
+        .wrap()
+        .query_wasm_smart(
+            &factory,
+            &FactoryQueryMsg::PairByLpToken {
+                lp_token: lp_denom.clone(),
+            },
+        )
+        .unwrap();
+    assert_eq!(res, pair_info);
+
+    let (msg, coins) = provide_liquidity_msg(
+        Uint128::new(100_000_000),
+        Uint128::new(100_000_000),
+        None,
+        None,
+        None,
+    );
+    app.execute_contract(
+        alice_address.clone(),
+        pair_info.contract_addr.clone(),
+        &msg,
+        &coins,
+    )
+    .unwrap();
+
+    let lp_balance = app.wrap().query_balance(&alice_address, &lp_denom).unwrap();
+    assert_eq!(lp_balance.amount.u128(), 100_000_000 - 1000);
+    let pool: PoolResponse = app
+        .wrap()
+        .query_wasm_smart(&pair_info.contract_addr, &QueryMsg::Pool {})
+        .unwrap();
+    assert_eq!(pool.total_share.u128(), 100_000_000);
+
+    // Withdrawing requires LP tokens to be sent as funds
+    let withdraw_msg = ExecuteMsg::WithdrawLiquidity {
+        min_assets_to_receive: None,
+        asset: None,
+        deadline: None,
+    };
+    let err = app
+        .execute_contract(
+            alice_address.clone(),
+            pair_info.contract_addr.clone(),
+            &withdraw_msg,
+            &[coin(1000, "uusd")],
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::PaymentError(PaymentError::MissingDenom(lp_denom.clone()))
+    );
+
+    // Single asset withdrawals are not supported by XYK pairs
+    let err = app
+        .execute_contract(
+            alice_address.clone(),
+            pair_info.contract_addr.clone(),
+            &ExecuteMsg::WithdrawLiquidity {
+                min_assets_to_receive: None,
+                asset: Some(native_asset_info("uusd".to_string())),
+                deadline: None,
+            },
+            &[coin(50_000_000, &lp_denom)],
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::NonSupported {}
+    );
+
+    app.execute_contract(
+        alice_address.clone(),
+        pair_info.contract_addr.clone(),
+        &withdraw_msg,
+        &[coin(50_000_000, &lp_denom)],
+    )
+    .unwrap();
+
+    let lp_balance = app.wrap().query_balance(&alice_address, &lp_denom).unwrap();
+    assert_eq!(lp_balance.amount.u128(), 50_000_000 - 1000);
+    assert_eq!(
+        app.wrap().query_supply(&lp_denom).unwrap().amount.u128(),
+        50_000_000
+    );
+    assert_eq!(
+        app.wrap()
+            .query_balance(&alice_address, "uusd")
+            .unwrap()
+            .amount
+            .u128(),
+        100_000_000_000 - 50_000_000
+    );
+}
+
+#[test]
+fn test_asset_balances_tracking() {
+    let owner = MockApi::default().addr_make("owner");
+    let user = MockApi::default().addr_make("user");
+    let mut app = mock_app(
+        owner.clone(),
+        vec![
+            coin(1_000_000_000_000, "uusd"),
+            coin(1_000_000_000_000, "uluna"),
+        ],
+    );
+    app.send_tokens(
+        owner.clone(),
+        user.clone(),
+        &[coin(10_000_000, "uusd"), coin(10_000_000, "uluna")],
+    )
+    .unwrap();
+
+    let pair_instance = instantiate_pair(&mut app, &owner);
+    let lp_token = app
+        .wrap()
+        .query_wasm_smart::<PairInfo>(&pair_instance, &QueryMsg::Pair {})
+        .unwrap()
+        .liquidity_token;
+
+    let query_balances_at = |app: &App, height: u64| -> Vec<Option<Uint128>> {
+        ["uusd", "uluna"]
+            .into_iter()
+            .map(|denom| {
+                app.wrap()
+                    .query_wasm_smart(
+                        &pair_instance,
+                        &QueryMsg::AssetBalanceAt {
+                            asset_info: native_asset_info(denom.to_string()),
+                            block_height: height.into(),
+                        },
+                    )
+                    .unwrap()
+            })
+            .collect()
+    };
+    let query_pool_balances = |app: &App| -> Vec<Option<Uint128>> {
+        app.wrap()
+            .query_wasm_smart::<PoolResponse>(&pair_instance, &QueryMsg::Pool {})
+            .unwrap()
+            .assets
+            .into_iter()
+            .map(|asset| Some(asset.amount))
+            .collect()
+    };
+
+    // Balances are not recorded before the first provide
+    let provide_height = app.block_info().height;
+    assert_eq!(query_balances_at(&app, provide_height), vec![None, None]);
+
+    let (msg, coins) = provide_liquidity_msg(
+        Uint128::new(100_000_000),
+        Uint128::new(100_000_000),
+        None,
+        None,
+        None,
+    );
+    app.execute_contract(owner.clone(), pair_instance.clone(), &msg, &coins)
+        .unwrap();
+
+    // The snapshot taken at some height is visible starting from the next block
+    assert_eq!(query_balances_at(&app, provide_height), vec![None, None]);
+    app.update_block(|b| b.height += 1);
+    assert_eq!(
+        query_balances_at(&app, provide_height + 1),
+        vec![Some(Uint128::new(100_000_000)); 2]
+    );
+
+    let swap_height = app.block_info().height;
+    app.execute_contract(
+        user.clone(),
+        pair_instance.clone(),
+        &ExecuteMsg::Swap {
+            offer_asset: native_asset_info("uusd".to_string()).with_balance(1_000_000u128),
+            ask_asset_info: None,
+            belief_price: None,
+            max_spread: Some(Decimal::percent(50)),
+            to: None,
+            deadline: None,
+        },
+        &[coin(1_000_000, "uusd")],
+    )
+    .unwrap();
+    let balances_after_swap = query_pool_balances(&app);
+    app.update_block(|b| b.height += 1);
+    assert_eq!(
+        query_balances_at(&app, swap_height + 1),
+        balances_after_swap
+    );
+
+    let withdraw_height = app.block_info().height;
+    app.execute_contract(
+        owner.clone(),
+        Addr::unchecked(&lp_token),
+        &Cw20ExecuteMsg::Send {
+            contract: pair_instance.to_string(),
+            amount: Uint128::new(50_000_000),
+            msg: to_json_binary(&Cw20HookMsg::WithdrawLiquidity {
+                min_assets_to_receive: None,
+                asset: None,
+                deadline: None,
+            })
+            .unwrap(),
+        },
+        &[],
+    )
+    .unwrap();
+    let balances_after_withdraw = query_pool_balances(&app);
+    app.update_block(|b| b.height += 1);
+    assert_eq!(
+        query_balances_at(&app, withdraw_height + 1),
+        balances_after_withdraw
+    );
+
+    // Historical balances are preserved
+    assert_eq!(
+        query_balances_at(&app, provide_height + 1),
+        vec![Some(Uint128::new(100_000_000)); 2]
+    );
+    assert_eq!(
+        query_balances_at(&app, swap_height + 1),
+        balances_after_swap
+    );
+    assert_eq!(
+        query_balances_at(&app, withdraw_height + 100),
+        balances_after_withdraw
+    );
+}
+
+#[test]
+fn test_skim_and_sync() {
+    let owner = MockApi::default().addr_make("owner");
+    let skimmer = MockApi::default().addr_make("skimmer");
+    let mut app = mock_app(
+        owner.clone(),
+        vec![
+            coin(1_000_000_000_000, "uusd"),
+            coin(1_000_000_000_000, "uluna"),
+        ],
+    );
+
+    let pair_instance = instantiate_pair(&mut app, &owner);
+
+    let query_pool_balances = |app: &App| -> Vec<Uint128> {
+        app.wrap()
+            .query_wasm_smart::<PoolResponse>(&pair_instance, &QueryMsg::Pool {})
+            .unwrap()
+            .assets
+            .into_iter()
+            .map(|asset| asset.amount)
+            .collect()
+    };
+
+    let (msg, coins) = provide_liquidity_msg(
+        Uint128::new(100_000_000),
+        Uint128::new(100_000_000),
+        None,
+        None,
+        None,
+    );
+    app.execute_contract(owner.clone(), pair_instance.clone(), &msg, &coins)
+        .unwrap();
+
+    // Direct transfers are not accounted in the pool reserves
+    app.send_tokens(
+        owner.clone(),
+        pair_instance.clone(),
+        &[coin(1_000_000, "uusd"), coin(2_000_000, "uluna")],
+    )
+    .unwrap();
+    assert_eq!(
+        query_pool_balances(&app),
+        vec![Uint128::new(100_000_000); 2]
+    );
+
+    app.execute_contract(
+        owner.clone(),
+        pair_instance.clone(),
+        &ExecuteMsg::Skim {
+            to: Some(skimmer.to_string()),
+        },
+        &[],
+    )
+    .unwrap();
+    assert_eq!(
+        [
+            app.wrap().query_balance(&skimmer, "uusd").unwrap(),
+            app.wrap().query_balance(&skimmer, "uluna").unwrap()
+        ],
+        [coin(1_000_000, "uusd"), coin(2_000_000, "uluna")]
+    );
+    assert_eq!(
+        query_pool_balances(&app),
+        vec![Uint128::new(100_000_000); 2]
+    );
+
+    app.send_tokens(
+        owner.clone(),
+        pair_instance.clone(),
+        &[coin(1_000_000, "uusd")],
+    )
+    .unwrap();
+    app.execute_contract(
+        owner.clone(),
+        pair_instance.clone(),
+        &ExecuteMsg::Sync {},
+        &[],
+    )
+    .unwrap();
+    assert_eq!(
+        query_pool_balances(&app),
+        vec![Uint128::new(101_000_000), Uint128::new(100_000_000)]
+    );
+
+    // Nothing left to skim after sync
+    let res = app
+        .execute_contract(
+            owner.clone(),
+            pair_instance.clone(),
+            &ExecuteMsg::Skim { to: None },
+            &[],
+        )
+        .unwrap();
+    assert!(!res.events.iter().any(|event| event.ty == "transfer"));
+}
+
+#[test]
+fn test_swap_exact_out() {
+    let owner = MockApi::default().addr_make("owner");
+    let user = MockApi::default().addr_make("user");
+    let merchant = MockApi::default().addr_make("merchant");
+    let mut app = mock_app(
+        owner.clone(),
+        vec![
+            coin(1_000_000_000_000, "uusd"),
+            coin(1_000_000_000_000, "uluna"),
+        ],
+    );
+    app.send_tokens(owner.clone(), user.clone(), &[coin(10_000_000, "uluna")])
+        .unwrap();
+
+    let pair_instance = instantiate_pair(&mut app, &owner);
+
+    let (msg, coins) = provide_liquidity_msg(
+        Uint128::new(100_000_000),
+        Uint128::new(100_000_000),
+        None,
+        None,
+        None,
+    );
+    app.execute_contract(owner.clone(), pair_instance.clone(), &msg, &coins)
+        .unwrap();
+
+    let ask_asset = native_asset_info("uusd".to_string()).with_balance(1_000_000u128);
+    let swap_exact_out_msg = |max_offer_amount: u128| ExecuteMsg::SwapExactOut {
+        ask_asset: ask_asset.clone(),
+        max_offer_amount: max_offer_amount.into(),
+        to: Some(merchant.to_string()),
+        deadline: None,
+    };
+
+    let expected_offer_amount = app
+        .wrap()
+        .query_wasm_smart::<ReverseSimulationResponse>(
+            &pair_instance,
+            &QueryMsg::ReverseSimulation {
+                offer_asset_info: None,
+                ask_asset: ask_asset.clone(),
+            },
+        )
+        .unwrap()
+        .offer_amount;
+    assert_eq!(expected_offer_amount.u128(), 1_010_102);
+
+    // Sent funds must match the max offer amount
+    let err = app
+        .execute_contract(
+            user.clone(),
+            pair_instance.clone(),
+            &swap_exact_out_msg(2_000_000),
+            &[coin(1_000_000, "uluna")],
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.root_cause().to_string(),
+        "Generic error: Native token balance mismatch between the argument and the transferred"
+    );
+
+    let err = app
+        .execute_contract(
+            user.clone(),
+            pair_instance.clone(),
+            &swap_exact_out_msg(1_000_000),
+            &[coin(1_000_000, "uluna")],
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::MaxOfferAmountAssertion(expected_offer_amount, Uint128::new(1_000_000))
+    );
+
+    app.execute_contract(
+        user.clone(),
+        pair_instance.clone(),
+        &swap_exact_out_msg(2_000_000),
+        &[coin(2_000_000, "uluna")],
+    )
+    .unwrap();
+
+    // The merchant receives the exact amount and unused funds are refunded
+    assert_eq!(
+        app.wrap().query_balance(&merchant, "uusd").unwrap().amount,
+        Uint128::new(1_000_000)
+    );
+    assert_eq!(
+        app.wrap().query_balance(&user, "uluna").unwrap().amount,
+        Uint128::new(10_000_000) - expected_offer_amount
+    );
+
+    let pool_assets = app
+        .wrap()
+        .query_wasm_smart::<PoolResponse>(&pair_instance, &QueryMsg::Pool {})
+        .unwrap()
+        .assets;
+    assert_eq!(
+        pool_assets
+            .iter()
+            .map(|asset| asset.amount.u128())
+            .collect::<Vec<_>>(),
+        vec![99_000_000, 101_010_102]
+    );
+}
+
+/// Messages of a mock contract borrowing assets via flash swaps
+#[cw_serde]
+enum BorrowerMsg {
+    Borrow {
+        pair: String,
+        ask_asset: Asset,
+        callback_msg: Binary,
+    },
+    Repay {
+        pair: String,
+        assets: Vec<Asset>,
+    },
+    Call {
+        contract: String,
+        msg: Binary,
+    },
+}
+
+fn store_borrower_code(app: &mut App) -> u64 {
+    app.store_code(Box::new(ContractWrapper::new_with_empty(
+        |_, _, _, msg: BorrowerMsg| -> StdResult<Response> {
+            match msg {
+                BorrowerMsg::Borrow {
+                    pair,
+                    ask_asset,
+                    callback_msg,
+                } => Ok(Response::new().add_message(wasm_execute(
+                    pair,
+                    &ExecuteMsg::FlashSwap {
+                        ask_asset,
+                        callback_msg,
+                        deadline: None,
+                    },
+                    vec![],
+                )?)),
+                BorrowerMsg::Repay { pair, assets } => Ok(Response::new().add_messages(
+                    assets
+                        .into_iter()
+                        .map(|asset| asset.into_msg(&pair))
+                        .collect::<StdResult<Vec<_>>>()?,
+                )),
+                BorrowerMsg::Call { contract, msg } => {
+                    Ok(Response::new().add_message(WasmMsg::Execute {
+                        contract_addr: contract,
+                        msg,
+                        funds: vec![],
+                    }))
+                }
+            }
+        },
+        |_, _, _, _: Empty| -> StdResult<Response> { Ok(Response::new()) },
+        |_, _, _: Empty| -> StdResult<Binary> { unimplemented!() },
+    )))
+}
+
+#[test]
+fn test_flash_swap() {
+    let owner = MockApi::default().addr_make("owner");
+    let mut app = mock_app(
+        owner.clone(),
+        vec![
+            coin(1_000_000_000_000, "uusd"),
+            coin(1_000_000_000_000, "uluna"),
+        ],
+    );
+
+    let pair_instance = instantiate_pair(&mut app, &owner);
+
+    let (msg, coins) = provide_liquidity_msg(
+        Uint128::new(100_000_000),
+        Uint128::new(100_000_000),
+        None,
+        None,
+        None,
+    );
+    app.execute_contract(owner.clone(), pair_instance.clone(), &msg, &coins)
+        .unwrap();
+
+    let borrower_code_id = store_borrower_code(&mut app);
+    let borrower = app
+        .instantiate_contract(
+            borrower_code_id,
+            owner.clone(),
+            &Empty {},
+            &[],
+            "Borrower",
+            None,
+        )
+        .unwrap();
+    app.send_tokens(
+        owner.clone(),
+        borrower.clone(),
+        &[coin(10_000_000, "uusd"), coin(10_000_000, "uluna")],
+    )
+    .unwrap();
+
+    let ask_asset = native_asset_info("uusd".to_string()).with_balance(1_000_000u128);
+    let flash_swap = |app: &mut App, callback: BorrowerMsg| {
+        app.execute_contract(
+            owner.clone(),
+            borrower.clone(),
+            &BorrowerMsg::Borrow {
+                pair: pair_instance.to_string(),
+                ask_asset: ask_asset.clone(),
+                callback_msg: to_json_binary(&callback).unwrap(),
+            },
+            &[],
+        )
+    };
+    let repay_msg = |denom: &str, amount: u128| BorrowerMsg::Repay {
+        pair: pair_instance.to_string(),
+        assets: vec![native_asset_info(denom.to_string()).with_balance(amount)],
+    };
+    let query_pool_balances = |app: &App| -> Vec<u128> {
+        app.wrap()
+            .query_wasm_smart::<PoolResponse>(&pair_instance, &QueryMsg::Pool {})
+            .unwrap()
+            .assets
+            .into_iter()
+            .map(|asset| asset.amount.u128())
+            .collect()
+    };
+
+    let err = flash_swap(&mut app, repay_msg("uusd", 999_999)).unwrap_err();
+    assert_eq!(
+        err.root_cause().to_string(),
+        ContractError::FlashSwapInvariantViolation {}.to_string()
+    );
+
+    // The pair is locked during the callback
+    let err = flash_swap(
+        &mut app,
+        BorrowerMsg::Call {
+            contract: pair_instance.to_string(),
+            msg: to_json_binary(&ExecuteMsg::Sync {}).unwrap(),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.root_cause().to_string(),
+        ContractError::FlashSwapInProgress {}.to_string()
+    );
+
+    flash_swap(&mut app, repay_msg("uusd", 1_000_000)).unwrap();
+    assert_eq!(query_pool_balances(&app), vec![100_000_000, 100_000_000]);
+
+    // Repaying in the other asset must keep the constant product
+    let err = flash_swap(&mut app, repay_msg("uluna", 1_010_101)).unwrap_err();
+    assert_eq!(
+        err.root_cause().to_string(),
+        ContractError::FlashSwapInvariantViolation {}.to_string()
+    );
+    flash_swap(&mut app, repay_msg("uluna", 1_010_102)).unwrap();
+    assert_eq!(query_pool_balances(&app), vec![99_000_000, 101_010_102]);
+    assert_eq!(
+        app.wrap().query_balance(&borrower, "uusd").unwrap().amount,
+        Uint128::new(11_000_000)
+    );
+}
+
+#[test]
+fn test_observe() {
+    let owner = MockApi::default().addr_make("owner");
+    let mut app = mock_app(
+        owner.clone(),
+        vec![
+            coin(1_000_000_000_000, "uusd"),
+            coin(1_000_000_000_000, "uluna"),
+        ],
+    );
+
+    let pair_instance = instantiate_pair(&mut app, &owner);
+    let start_time = app.block_info().time.seconds();
+
+    let (msg, coins) = provide_liquidity_msg(
+        Uint128::new(100_000_000),
+        Uint128::new(100_000_000),
+        None,
+        None,
+        None,
+    );
+    app.execute_contract(owner.clone(), pair_instance.clone(), &msg, &coins)
+        .unwrap();
+
+    app.update_block(|b| {
+        b.height += 100;
+        b.time = b.time.plus_seconds(1000);
+    });
+
+    // Change the pool price to uusd:uluna = 3:2
+    let (msg, coins) = provide_liquidity_msg(
+        Uint128::new(200_000_000),
+        Uint128::new(100_000_000),
+        None,
+        Some(Decimal::percent(50)),
+        None,
+    );
+    app.execute_contract(owner.clone(), pair_instance.clone(), &msg, &coins)
+        .unwrap();
+
+    app.update_block(|b| {
+        b.height += 100;
+        b.time = b.time.plus_seconds(1000);
+    });
+
+    let observations: Vec<Observation> = app
+        .wrap()
+        .query_wasm_smart(
+            &pair_instance,
+            &QueryMsg::Observe {
+                seconds_ago: vec![0, 500, 1000, 1500, 2000],
+            },
+        )
+        .unwrap();
+    let values = observations
+        .iter()
+        .map(|obs| {
+            (
+                obs.timestamp - start_time,
+                obs.cumulative_prices[0].2.u128(),
+                obs.cumulative_prices[1].2.u128(),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        values,
+        vec![
+            (2000, 1_666_666_666, 2_500_000_000),
+            (1500, 1_333_333_333, 1_750_000_000),
+            (1000, 1_000_000_000, 1_000_000_000),
+            (500, 500_000_000, 500_000_000),
+            (0, 0, 0),
+        ]
+    );
+
+    // The current observation matches the cumulative prices
+    let cpr: CumulativePricesResponse = app
+        .wrap()
+        .query_wasm_smart(&pair_instance, &QueryMsg::CumulativePrices {})
+        .unwrap();
+    assert_eq!(observations[0].cumulative_prices, cpr.cumulative_prices);
+
+    // TWAP of uusd denominated in uluna for the last 1000 seconds
+    let twap = (observations[0].cumulative_prices[0].2 - observations[2].cumulative_prices[0].2)
+        / Uint128::new(1000);
+    assert_eq!(twap, Uint128::new(666_666));
+
+    let err = app
+        .wrap()
+        .query_wasm_smart::<Vec<Observation>>(
+            &pair_instance,
+            &QueryMsg::Observe {
+                seconds_ago: vec![2001],
+            },
+        )
+        .unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("is older than the oldest recorded one"));
+}
+
+#[test]
+fn test_pause() {
+    let owner = MockApi::default().addr_make("owner");
+    let mut app = mock_app(
+        owner.clone(),
+        vec![
+            coin(1_000_000_000_000, "uusd"),
+            coin(1_000_000_000_000, "uluna"),
+        ],
+    );
+
+    let pair_instance = instantiate_pair(&mut app, &owner);
+    let config: ConfigResponse = app
+        .wrap()
+        .query_wasm_smart(&pair_instance, &QueryMsg::Config {})
+        .unwrap();
+    let factory = config.factory_addr;
+
+    let (provide_msg, provide_coins) = provide_liquidity_msg(
+        Uint128::new(100_000_000),
+        Uint128::new(100_000_000),
+        None,
+        None,
+        None,
+    );
+    app.execute_contract(
+        owner.clone(),
+        pair_instance.clone(),
+        &provide_msg,
+        &provide_coins,
+    )
+    .unwrap();
+
+    let swap_msg = ExecuteMsg::Swap {
+        offer_asset: native_asset_info("uusd".to_string()).with_balance(1_000u128),
+        ask_asset_info: None,
+        belief_price: None,
+        max_spread: None,
+        to: None,
+        deadline: None,
+    };
+    let swap_coins = [coin(1_000, "uusd")];
+
+    app.execute_contract(
+        owner.clone(),
+        factory.clone(),
+        &FactoryExecuteMsg::Pause {
+            pair: None,
+            scope: PauseScope::Provide,
+        },
+        &[],
+    )
+    .unwrap();
+
+    let err = app
+        .execute_contract(
+            owner.clone(),
+            pair_instance.clone(),
+            &provide_msg,
+            &provide_coins,
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::Paused(PauseScope::Provide)
+    );
+    app.execute_contract(owner.clone(), pair_instance.clone(), &swap_msg, &swap_coins)
+        .unwrap();
+
+    app.execute_contract(
+        owner.clone(),
+        factory.clone(),
+        &FactoryExecuteMsg::Pause {
+            pair: None,
+            scope: PauseScope::All,
+        },
+        &[],
+    )
+    .unwrap();
+
+    let err = app
+        .execute_contract(owner.clone(), pair_instance.clone(), &swap_msg, &swap_coins)
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::Paused(PauseScope::Swap)
+    );
+
+    // Withdrawals stay open during a pause
+    let pair_info: PairInfo = app
+        .wrap()
+        .query_wasm_smart(&pair_instance, &QueryMsg::Pair {})
+        .unwrap();
+    app.execute_contract(
+        owner.clone(),
+        Addr::unchecked(pair_info.liquidity_token),
+        &Cw20ExecuteMsg::Send {
+            contract: pair_instance.to_string(),
+            amount: Uint128::new(1_000_000),
+            msg: to_json_binary(&Cw20HookMsg::WithdrawLiquidity {
+                min_assets_to_receive: None,
+                asset: None,
+                deadline: None,
+            })
+            .unwrap(),
+        },
+        &[],
+    )
+    .unwrap();
+
+    app.execute_contract(
+        owner.clone(),
+        factory,
+        &FactoryExecuteMsg::Unpause { pair: None },
+        &[],
+    )
+    .unwrap();
+    app.execute_contract(owner.clone(), pair_instance.clone(), &swap_msg, &swap_coins)
+        .unwrap();
+}
+
+#[test]
+fn test_single_sided_provide() {
+    let owner = MockApi::default().addr_make("owner");
+    let mut app = mock_app(
+        owner.clone(),
+        vec![
+            coin(1_000_000_000_000, "uusd"),
+            coin(1_000_000_000_000, "uluna"),
+        ],
+    );
+
+    let pair_instance = instantiate_pair(&mut app, &owner);
+    let pair_info: PairInfo = app
+        .wrap()
+        .query_wasm_smart(&pair_instance, &QueryMsg::Pair {})
+        .unwrap();
+    let lp_balance = |app: &App| {
+        app.wrap()
+            .query_wasm_smart::<BalanceResponse>(
+                &pair_info.liquidity_token,
+                &Cw20QueryMsg::Balance {
+                    address: owner.to_string(),
+                },
+            )
+            .unwrap()
+            .balance
+    };
+
+    // Single-sided provide is not allowed in an empty pool
+    let single_asset = vec![native_asset_info("uusd".to_string()).with_balance(10_000_000u128)];
+    let single_provide_msg = ExecuteMsg::ProvideLiquidity {
+        assets: single_asset.clone(),
+        slippage_tolerance: Some(Decimal::percent(10)),
+        auto_stake: None,
+        receiver: None,
+        min_lp_to_receive: None,
+        deadline: None,
+    };
+    let err = app
+        .execute_contract(
+            owner.clone(),
+            pair_instance.clone(),
+            &single_provide_msg,
+            &[coin(10_000_000, "uusd")],
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::InvalidZeroAmount {}
+    );
+
+    let (msg, coins) = provide_liquidity_msg(
+        Uint128::new(100_000_000),
+        Uint128::new(100_000_000),
+        None,
+        None,
+        None,
+    );
+    app.execute_contract(owner.clone(), pair_instance.clone(), &msg, &coins)
+        .unwrap();
+
+    // Swapping half of the deposit exceeds the default slippage tolerance
+    let err = app
+        .execute_contract(
+            owner.clone(),
+            pair_instance.clone(),
+            &ExecuteMsg::ProvideLiquidity {
+                assets: single_asset.clone(),
+                slippage_tolerance: None,
+                auto_stake: None,
+                receiver: None,
+                min_lp_to_receive: None,
+                deadline: None,
+            },
+            &[coin(10_000_000, "uusd")],
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::MaxSpreadAssertion {}
+    );
+
+    let simulated: Uint128 = app
+        .wrap()
+        .query_wasm_smart(
+            &pair_instance,
+            &QueryMsg::SimulateProvide {
+                assets: single_asset.clone(),
+                slippage_tolerance: Some(Decimal::percent(10)),
+            },
+        )
+        .unwrap();
+
+    let balance_before = lp_balance(&app);
+    app.execute_contract(
+        owner.clone(),
+        pair_instance.clone(),
+        &single_provide_msg,
+        &[coin(10_000_000, "uusd")],
+    )
+    .unwrap();
+    let minted = lp_balance(&app) - balance_before;
+    assert_eq!(minted, simulated);
+    // Roughly 100_000_000 * (sqrt(110 / 100) - 1)
+    assert_eq!(minted.u128(), 4_880_883);
+
+    let pool: PoolResponse = app
+        .wrap()
+        .query_wasm_smart(&pair_instance, &QueryMsg::Pool {})
+        .unwrap();
+    assert_eq!(
+        pool.assets,
+        vec![
+            native_asset_info("uusd".to_string()).with_balance(110_000_000u128),
+            native_asset_info("uluna".to_string()).with_balance(100_000_000u128),
+        ]
+    );
+
+    // Imbalanced deposits are balanced with an internal swap as well
+    let (msg, coins) = provide_liquidity_msg(
+        Uint128::new(3_000_000),
+        Uint128::new(4_000_000),
+        None,
+        Some(Decimal::percent(50)),
+        None,
+    );
+    let ExecuteMsg::ProvideLiquidity { assets, .. } = &msg else {
+        unreachable!()
+    };
+    let simulated: Uint128 = app
+        .wrap()
+        .query_wasm_smart(
+            &pair_instance,
+            &QueryMsg::SimulateProvide {
+                assets: assets.clone(),
+                slippage_tolerance: Some(Decimal::percent(50)),
+            },
+        )
+        .unwrap();
+    let balance_before = lp_balance(&app);
+    app.execute_contract(owner.clone(), pair_instance.clone(), &msg, &coins)
+        .unwrap();
+    assert_eq!(lp_balance(&app) - balance_before, simulated);
+}
+
+const HOOK_CALLS: Item<Vec<PairHookMsg>> = Item::new("calls");
+const HOOK_FAILS: Item<bool> = Item::new("fails");
+
+/// Stores a hook which records all notifications or always fails depending on the instantiate message.
+fn store_hook_code(app: &mut App) -> u64 {
+    app.store_code(Box::new(ContractWrapper::new_with_empty(
+        |deps, _, _, msg: PairHookExecuteMsg| -> StdResult<Response> {
+            if HOOK_FAILS.load(deps.storage)? {
+                return Err(StdError::generic_err("Hook failed"));
+            }
+
+            let PairHookExecuteMsg::PairHook(msg) = msg;
+            let mut calls = HOOK_CALLS.load(deps.storage)?;
+            calls.push(msg);
+            HOOK_CALLS.save(deps.storage, &calls)?;
+
+            Ok(Response::new())
+        },
+        |deps, _, _, fails: bool| -> StdResult<Response> {
+            HOOK_FAILS.save(deps.storage, &fails)?;
+            HOOK_CALLS.save(deps.storage, &vec![])?;
+            Ok(Response::new())
+        },
+        |deps, _, _: Empty| -> StdResult<Binary> {
+            to_json_binary(&HOOK_CALLS.load(deps.storage)?)
+        },
+    )))
+}
+
+#[test]
+fn test_hooks() {
+    let owner = MockApi::default().addr_make("owner");
+    let mut app = mock_app(
+        owner.clone(),
+        vec![
+            coin(1_000_000_000_000, "uusd"),
+            coin(1_000_000_000_000, "uluna"),
+        ],
+    );
+
+    let pair_instance = instantiate_pair(&mut app, &owner);
+    let pair_info: PairInfo = app
+        .wrap()
+        .query_wasm_smart(&pair_instance, &QueryMsg::Pair {})
+        .unwrap();
+
+    let hook_code_id = store_hook_code(&mut app);
+    let mut instantiate_hook = |fails: bool| {
+        app.instantiate_contract(hook_code_id, owner.clone(), &fails, &[], "Hook", None)
+            .unwrap()
+    };
+    let recorder = instantiate_hook(false);
+    let failing = instantiate_hook(true);
+
+    let hook = |contract_addr: &Addr, blocking: bool| PairHook {
+        contract_addr: contract_addr.clone(),
+        gas_limit: 500_000,
+        blocking,
+    };
+    let set_hooks_msg = |hooks: Vec<PairHook>| ExecuteMsg::UpdateConfig {
+        params: to_json_binary(&XYKPoolUpdateParams::SetHooks { hooks }).unwrap(),
+    };
+
+    // Only the factory owner can set hooks
+    let err = app
+        .execute_contract(
+            app.api().addr_make("random"),
+            pair_instance.clone(),
+            &set_hooks_msg(vec![hook(&recorder, true)]),
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::Unauthorized {}
+    );
+
+    for hooks in [
+        vec![hook(&recorder, true), hook(&recorder, false)],
+        vec![PairHook {
+            gas_limit: 10_000_000,
+            ..hook(&recorder, true)
+        }],
+    ] {
+        let err = app
+            .execute_contract(
+                owner.clone(),
+                pair_instance.clone(),
+                &set_hooks_msg(hooks),
+                &[],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.downcast::<ContractError>().unwrap(),
+            ContractError::InvalidHooks {}
+        );
+    }
+
+    let hooks = vec![hook(&recorder, true), hook(&failing, false)];
+    app.execute_contract(
+        owner.clone(),
+        pair_instance.clone(),
+        &set_hooks_msg(hooks.clone()),
+        &[],
+    )
+    .unwrap();
+    let config: ConfigResponse = app
+        .wrap()
+        .query_wasm_smart(&pair_instance, &QueryMsg::Config {})
+        .unwrap();
+    assert_eq!(
+        config.params,
+        Some(
+            to_json_binary(&XYKPoolConfig {
+                fee_share: None,
+                hooks,
+                dynamic_fee: None,
+                protocol_fee_accrual: false,
+                max_price_deviation: None,
+                batch_auction: false,
+                trader_allowlist: None,
+            })
+            .unwrap()
+        )
+    );
+
+    let hook_calls = |app: &App| -> Vec<PairHookMsg> {
+        app.wrap().query_wasm_smart(&recorder, &Empty {}).unwrap()
+    };
+
+    // The failing hook is non-blocking so its error is ignored
+    let (msg, coins) = provide_liquidity_msg(
+        Uint128::new(100_000_000),
+        Uint128::new(100_000_000),
+        None,
+        None,
+        None,
+    );
+    let resp = app
+        .execute_contract(owner.clone(), pair_instance.clone(), &msg, &coins)
+        .unwrap();
+    assert!(resp
+        .events
+        .iter()
+        .any(|event| event.attributes.iter().any(|attr| attr.key == "hook_error")));
+    let PairHookMsg::AfterProvide {
+        assets,
+        share,
+        receiver,
+    } = hook_calls(&app)[0].clone()
+    else {
+        panic!("Expected AfterProvide");
+    };
+    assert_eq!(assets.len(), 2);
+    assert_eq!(share.u128(), 100_000_000 - 1000);
+    assert_eq!(receiver, owner);
+
+    let offer_asset = native_asset_info("uusd".to_string()).with_balance(1_000_000u128);
+    let user = app.api().addr_make("user");
+    app.send_tokens(owner.clone(), user.clone(), &[coin(1_000_000, "uusd")])
+        .unwrap();
+    app.execute_contract(
+        user.clone(),
+        pair_instance.clone(),
+        &ExecuteMsg::Swap {
+            offer_asset: offer_asset.clone(),
+            ask_asset_info: None,
+            belief_price: None,
+            max_spread: Some(Decimal::percent(5)),
+            to: None,
+            deadline: None,
+        },
+        &[coin(1_000_000, "uusd")],
+    )
+    .unwrap();
+    let received = app.wrap().query_balance(&user, "uluna").unwrap().amount;
+    assert_eq!(
+        hook_calls(&app)[1],
+        PairHookMsg::AfterSwap {
+            offer: offer_asset,
+            ask: native_asset_info("uluna".to_string()).with_balance(received),
+            trader: user.clone(),
+        }
+    );
+
+    app.execute_contract(
+        owner.clone(),
+        Addr::unchecked(&pair_info.liquidity_token),
+        &Cw20ExecuteMsg::Send {
+            contract: pair_instance.to_string(),
+            amount: Uint128::new(1_000_000),
+            msg: to_json_binary(&Cw20HookMsg::WithdrawLiquidity {
+                min_assets_to_receive: None,
+                asset: None,
+                deadline: None,
+            })
+            .unwrap(),
+        },
+        &[],
+    )
+    .unwrap();
+    let PairHookMsg::AfterWithdraw {
+        assets,
+        share,
+        receiver,
+    } = hook_calls(&app)[2].clone()
+    else {
+        panic!("Expected AfterWithdraw");
+    };
+    assert_eq!(assets.len(), 2);
+    assert_eq!(share.u128(), 1_000_000);
+    assert_eq!(receiver, owner);
+
+    // A failing blocking hook reverts the transaction
+    app.execute_contract(
+        owner.clone(),
+        pair_instance.clone(),
+        &set_hooks_msg(vec![hook(&failing, true)]),
+        &[],
+    )
+    .unwrap();
+    let err = app
+        .execute_contract(
+            owner.clone(),
+            pair_instance.clone(),
+            &ExecuteMsg::Swap {
+                offer_asset: native_asset_info("uusd".to_string()).with_balance(1_000u128),
+                ask_asset_info: None,
+                belief_price: None,
+                max_spread: Some(Decimal::percent(5)),
+                to: None,
+                deadline: None,
+            },
+            &[coin(1_000, "uusd")],
+        )
+        .unwrap_err();
+    assert_eq!(err.root_cause().to_string(), "Generic error: Hook failed");
+}
+
+#[cw_serde]
+enum Cw4QueryMsg {
+    Member {
+        addr: String,
+        at_height: Option<u64>,
+    },
+}
+
+#[cw_serde]
+struct Cw4MemberResponse {
+    weight: Option<u64>,
+}
+
+const GROUP_MEMBERS: Item<Vec<String>> = Item::new("members");
+
+/// Stores a cw4 group mock answering membership queries for the members from the instantiate message.
+fn store_cw4_group_code(app: &mut App) -> u64 {
+    app.store_code(Box::new(ContractWrapper::new_with_empty(
+        |_, _, _, _: Empty| -> StdResult<Response> { Ok(Response::new()) },
+        |deps, _, _, members: Vec<String>| -> StdResult<Response> {
+            GROUP_MEMBERS.save(deps.storage, &members)?;
+            Ok(Response::new())
+        },
+        |deps, _, msg: Cw4QueryMsg| -> StdResult<Binary> {
+            let Cw4QueryMsg::Member { addr, .. } = msg;
+            let members = GROUP_MEMBERS.load(deps.storage)?;
+            to_json_binary(&Cw4MemberResponse {
+                weight: members.contains(&addr).then_some(1),
+            })
+        },
+    )))
+}
+
+#[test]
+fn test_trader_allowlist() {
+    let owner = MockApi::default().addr_make("owner");
+    let mut app = mock_app(
+        owner.clone(),
+        vec![
+            coin(1_000_000_000_000, "uusd"),
+            coin(1_000_000_000_000, "uluna"),
+        ],
+    );
+
+    let pair_instance = instantiate_pair(&mut app, &owner);
+    let (msg, coins) = provide_liquidity_msg(
+        Uint128::new(1_000_000_000),
+        Uint128::new(1_000_000_000),
+        None,
+        None,
+        None,
+    );
+    app.execute_contract(owner.clone(), pair_instance.clone(), &msg, &coins)
+        .unwrap();
+
+    let alice = app.api().addr_make("alice");
+    let bob = app.api().addr_make("bob");
+    for user in [&alice, &bob] {
+        app.send_tokens(
+            owner.clone(),
+            user.clone(),
+            &[coin(10_000_000, "uusd"), coin(10_000_000, "uluna")],
+        )
+        .unwrap();
+    }
+
+    let update_msg = |params: XYKPoolUpdateParams| ExecuteMsg::UpdateConfig {
+        params: to_json_binary(&params).unwrap(),
+    };
+    let swap = |app: &mut App, sender: &Addr, to: Option<&Addr>| {
+        app.execute_contract(
+            sender.clone(),
+            pair_instance.clone(),
+            &ExecuteMsg::Swap {
+                offer_asset: native_asset_info("uusd".to_string()).with_balance(1_000u128),
+                ask_asset_info: None,
+                belief_price: None,
+                max_spread: Some(Decimal::percent(5)),
+                to: to.map(Addr::to_string),
+                deadline: None,
+            },
+            &[coin(1_000, "uusd")],
+        )
+    };
+
+    let err = app
+        .execute_contract(
+            owner.clone(),
+            pair_instance.clone(),
+            &update_msg(XYKPoolUpdateParams::SetTraderAllowlist(
+                TraderAllowlist::Addresses(vec![]),
+            )),
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(
+        ContractError::InvalidTraderAllowlist {},
+        err.downcast().unwrap()
+    );
+
+    let err = app
+        .execute_contract(
+            alice.clone(),
+            pair_instance.clone(),
+            &update_msg(XYKPoolUpdateParams::SetTraderAllowlist(
+                TraderAllowlist::Addresses(vec![alice.clone()]),
+            )),
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+
+    app.execute_contract(
+        owner.clone(),
+        pair_instance.clone(),
+        &update_msg(XYKPoolUpdateParams::SetTraderAllowlist(
+            TraderAllowlist::Addresses(vec![alice.clone()]),
+        )),
+        &[],
+    )
+    .unwrap();
+    let config: ConfigResponse = app
+        .wrap()
+        .query_wasm_smart(&pair_instance, &QueryMsg::Config {})
+        .unwrap();
+    let params: XYKPoolConfig = from_json(config.params.unwrap()).unwrap();
+    assert_eq!(
+        params.trader_allowlist,
+        Some(TraderAllowlist::Addresses(vec![alice.clone()]))
+    );
+
+    swap(&mut app, &alice, None).unwrap();
+    let err = swap(&mut app, &bob, None).unwrap_err();
+    assert_eq!(
+        ContractError::TraderNotAllowed { addr: bob.clone() },
+        err.downcast().unwrap()
+    );
+    // The receiver must be allowed as well
+    let err = swap(&mut app, &alice, Some(&bob)).unwrap_err();
+    assert_eq!(
+        ContractError::TraderNotAllowed { addr: bob.clone() },
+        err.downcast().unwrap()
+    );
+
+    let (msg, coins) = provide_liquidity_msg(
+        Uint128::new(1_000_000),
+        Uint128::new(1_000_000),
+        None,
+        None,
+        None,
+    );
+    let err = app
+        .execute_contract(bob.clone(), pair_instance.clone(), &msg, &coins)
+        .unwrap_err();
+    assert_eq!(
+        ContractError::TraderNotAllowed { addr: bob.clone() },
+        err.downcast().unwrap()
+    );
+    app.execute_contract(alice.clone(), pair_instance.clone(), &msg, &coins)
+        .unwrap();
+
+    // Only the group members are allowed
+    let group_code_id = store_cw4_group_code(&mut app);
+    let group = app
+        .instantiate_contract(
+            group_code_id,
+            owner.clone(),
+            &vec![bob.to_string()],
+            &[],
+            "Group",
+            None,
+        )
+        .unwrap();
+    app.execute_contract(
+        owner.clone(),
+        pair_instance.clone(),
+        &update_msg(XYKPoolUpdateParams::SetTraderAllowlist(
+            TraderAllowlist::Cw4Group(group),
+        )),
+        &[],
+    )
+    .unwrap();
+    swap(&mut app, &bob, None).unwrap();
+    let err = swap(&mut app, &alice, None).unwrap_err();
+    assert_eq!(
+        ContractError::TraderNotAllowed {
+            addr: alice.clone()
+        },
+        err.downcast().unwrap()
+    );
+
+    app.execute_contract(
+        owner.clone(),
+        pair_instance.clone(),
+        &update_msg(XYKPoolUpdateParams::RemoveTraderAllowlist),
+        &[],
+    )
+    .unwrap();
+    swap(&mut app, &alice, None).unwrap();
 }
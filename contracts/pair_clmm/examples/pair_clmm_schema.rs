@@ -0,0 +1,11 @@
+use astroport::pair::InstantiateMsg;
+use astroport::pair_clmm::{ExecuteMsg, QueryMsg};
+use cosmwasm_schema::write_api;
+
+fn main() {
+    write_api! {
+        instantiate: InstantiateMsg,
+        query: QueryMsg,
+        execute: ExecuteMsg,
+    }
+}
@@ -0,0 +1,1115 @@
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+use cosmwasm_std::{
+    attr, ensure, ensure_eq, from_json, to_json_binary, wasm_execute, Addr, Binary, CosmosMsg,
+    Decimal, Decimal256, Deps, DepsMut, Empty, Env, Event, MessageInfo, Order, Response, StdError,
+    StdResult, Uint128, Uint256,
+};
+use cw2::{get_contract_version, set_contract_version};
+use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
+use cw_storage_plus::Bound;
+use itertools::Itertools;
+
+use astroport::asset::{addr_opt_validate, Asset, AssetInfo, AssetInfoExt, CoinsExt, PairInfo};
+use astroport::factory::PauseScope;
+use astroport::pair::{
+    is_deadline_exceeded, ConfigResponse, Cw20HookMsg, InstantiateMsg, PoolResponse,
+    ReverseSimulationResponse, SimulationResponse,
+};
+use astroport::pair_clmm::{
+    ClmmConfig, ClmmParams, Cw721ReceiveMsg, ExecuteMsg, NumTokensResponse, OwnerOfResponse,
+    PoolStateResponse, PositionResponse, QueryMsg, TickResponse, TokensResponse,
+};
+use astroport::querier::{ensure_not_paused, query_factory_config, query_fee_info};
+
+use crate::error::ContractError;
+use crate::math::{
+    amounts_for_liquidity, liquidity_for_amounts, sqrt_price_at_tick, tick_at_sqrt_price, MAX_TICK,
+};
+use crate::pool::{
+    apply_crossed_ticks, compute_swap, fee_growth_inside, pending_fees, update_position, SwapResult,
+};
+use crate::state::{
+    tokens, Config, PoolState, Position, PositionToken, CONFIG, LAST_TOKEN_ID, NUM_TOKENS,
+    POOL_STATE, POSITIONS, TICKS,
+};
+use crate::utils::{
+    assert_max_spread, ensure_min_assets_to_receive, get_amounts_from_assets, select_pools,
+};
+
+/// Contract name that is used for migration.
+const CONTRACT_NAME: &str = env!("CARGO_PKG_NAME");
+/// Contract version that is used for migration.
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+/// Default pagination limit
+const DEFAULT_LIMIT: u32 = 10;
+/// Max pagination limit
+const MAX_LIMIT: u32 = 30;
+
+/// Creates a new contract with the specified parameters in the [`InstantiateMsg`].
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn instantiate(
+    deps: DepsMut,
+    env: Env,
+    _info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
+    if msg.asset_infos.len() != 2 || msg.asset_infos[0] == msg.asset_infos[1] {
+        return Err(ContractError::InvalidNumberOfAssets {});
+    }
+    msg.asset_infos
+        .iter()
+        .try_for_each(|asset_info| asset_info.check(deps.api))?;
+
+    let params: ClmmParams = from_json(
+        msg.init_params
+            .ok_or(ContractError::InitParamsNotFound {})?,
+    )?;
+
+    if params.tick_spacing == 0 || params.tick_spacing > MAX_TICK as u32 {
+        return Err(ContractError::InvalidTickSpacing {});
+    }
+
+    let sqrt_price = Decimal256::from(params.initial_price).sqrt();
+    let tick = tick_at_sqrt_price(sqrt_price)?;
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    // Positions are NFTs issued by the pair itself, so the pair address identifies them
+    let config = Config {
+        pair_info: PairInfo {
+            contract_addr: env.contract.address.clone(),
+            liquidity_token: env.contract.address.to_string(),
+            asset_infos: msg.asset_infos,
+            pair_type: msg.pair_type,
+        },
+        factory_addr: deps.api.addr_validate(&msg.factory_addr)?,
+        block_time_last: 0,
+        tick_spacing: params.tick_spacing as i32,
+    };
+
+    CONFIG.save(deps.storage, &config)?;
+    POOL_STATE.save(
+        deps.storage,
+        &PoolState {
+            sqrt_price,
+            tick,
+            liquidity: Uint128::zero(),
+            fee_growth_global: [Uint256::zero(); 2],
+        },
+    )?;
+    LAST_TOKEN_ID.save(deps.storage, &0)?;
+    NUM_TOKENS.save(deps.storage, &0)?;
+
+    Ok(Response::new().add_attribute("tick", tick.to_string()))
+}
+
+/// Exposes all the execute functions available in the contract.
+///
+/// ## Variants
+/// * **ExecuteMsg::Receive(msg)** Receives a message of type [`Cw20ReceiveMsg`] and processes
+///   it depending on the received template.
+///
+/// * **ExecuteMsg::Swap { offer_asset, ask_asset_info, belief_price, max_spread, to }** Performs
+///   a swap operation with the specified parameters.
+///
+/// * **ExecuteMsg::AddLiquidity { tick_lower, tick_upper, assets, min_liquidity, receiver }** Adds
+///   liquidity to the price range and mints a position NFT for the receiver.
+///
+/// * **ExecuteMsg::RemoveLiquidity { token_id, liquidity, min_assets_to_receive, receiver }** Removes
+///   liquidity from the position along with the accrued fees.
+///
+/// * **ExecuteMsg::CollectFees { token_id, receiver }** Sends the fees accrued by the position.
+///
+/// * **ExecuteMsg::TransferNft { recipient, token_id }** Transfers the position NFT.
+///
+/// * **ExecuteMsg::SendNft { contract, token_id, msg }** Transfers the position NFT to a contract
+///   and calls its `receive_nft` endpoint.
+///
+/// Swap and liquidity messages are rejected with [`ContractError::DeadlineExceeded`]
+/// once the block time is past their optional deadline.
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    if is_deadline_exceeded(&env.block, msg.deadline()) {
+        return Err(ContractError::DeadlineExceeded {});
+    }
+
+    match msg {
+        ExecuteMsg::Receive(msg) => receive_cw20(deps, env, info, msg),
+        ExecuteMsg::Swap {
+            offer_asset,
+            ask_asset_info,
+            belief_price,
+            max_spread,
+            to,
+            ..
+        } => {
+            offer_asset.info.check(deps.api)?;
+            if !offer_asset.info.is_native_token() {
+                return Err(ContractError::Cw20DirectSwap {});
+            }
+
+            let to_addr = addr_opt_validate(deps.api, &to)?;
+
+            swap(
+                deps,
+                env,
+                info.clone(),
+                info.sender,
+                offer_asset,
+                ask_asset_info,
+                belief_price,
+                max_spread,
+                to_addr,
+            )
+        }
+        ExecuteMsg::AddLiquidity {
+            tick_lower,
+            tick_upper,
+            assets,
+            min_liquidity,
+            receiver,
+            ..
+        } => add_liquidity(
+            deps,
+            env,
+            info,
+            tick_lower,
+            tick_upper,
+            assets,
+            min_liquidity,
+            receiver,
+        ),
+        ExecuteMsg::RemoveLiquidity {
+            token_id,
+            liquidity,
+            min_assets_to_receive,
+            receiver,
+            ..
+        } => remove_liquidity(
+            deps,
+            info,
+            token_id,
+            liquidity,
+            min_assets_to_receive,
+            receiver,
+        ),
+        ExecuteMsg::CollectFees { token_id, receiver } => {
+            collect_fees(deps, info, token_id, receiver)
+        }
+        ExecuteMsg::TransferNft {
+            recipient,
+            token_id,
+        } => {
+            let recipient = deps.api.addr_validate(&recipient)?;
+            transfer_nft(deps, &info.sender, &recipient, &token_id)?;
+
+            let event = Event::new("transfer_nft").add_attributes([
+                attr("action", "transfer_nft"),
+                attr("sender", info.sender),
+                attr("recipient", recipient),
+                attr("token_id", token_id),
+            ]);
+
+            Ok(Response::new().add_event(event))
+        }
+        ExecuteMsg::SendNft {
+            contract,
+            token_id,
+            msg,
+        } => {
+            let contract = deps.api.addr_validate(&contract)?;
+            transfer_nft(deps, &info.sender, &contract, &token_id)?;
+
+            let receive_msg = Cw721ReceiveMsg {
+                sender: info.sender.to_string(),
+                token_id: token_id.clone(),
+                msg,
+            }
+            .into_cosmos_msg(&contract)?;
+
+            let event = Event::new("send_nft").add_attributes([
+                attr("action", "send_nft"),
+                attr("sender", info.sender),
+                attr("recipient", contract),
+                attr("token_id", token_id),
+            ]);
+
+            Ok(Response::new().add_message(receive_msg).add_event(event))
+        }
+    }
+}
+
+/// Receives a message of type [`Cw20ReceiveMsg`] and processes it depending on the received template.
+///
+/// * **cw20_msg** is the CW20 message that has to be processed.
+pub fn receive_cw20(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    cw20_msg: Cw20ReceiveMsg,
+) -> Result<Response, ContractError> {
+    let hook_msg: Cw20HookMsg = from_json(&cw20_msg.msg)?;
+    if is_deadline_exceeded(&env.block, hook_msg.deadline()) {
+        return Err(ContractError::DeadlineExceeded {});
+    }
+
+    match hook_msg {
+        Cw20HookMsg::Swap {
+            ask_asset_info,
+            belief_price,
+            max_spread,
+            to,
+            ..
+        } => {
+            // Only asset contract can execute this message
+            let config = CONFIG.load(deps.storage)?;
+            let offer_asset_info = AssetInfo::Token {
+                contract_addr: info.sender.clone(),
+            };
+            if !config.pair_info.asset_infos.contains(&offer_asset_info) {
+                return Err(ContractError::Unauthorized {});
+            }
+
+            let to_addr = addr_opt_validate(deps.api, &to)?;
+
+            swap(
+                deps,
+                env,
+                info,
+                Addr::unchecked(cw20_msg.sender),
+                offer_asset_info.with_balance(cw20_msg.amount),
+                ask_asset_info,
+                belief_price,
+                max_spread,
+                to_addr,
+            )
+        }
+        Cw20HookMsg::SwapExactOut { .. } | Cw20HookMsg::WithdrawLiquidity { .. } => {
+            Err(ContractError::NonSupported {})
+        }
+    }
+}
+
+/// Adds liquidity to the price range between `tick_lower` and `tick_upper`. The liquidity is
+/// computed from the maximum amounts of assets to deposit, the unused native tokens are refunded.
+/// One unit of each asset is reserved to cover rounding.
+///
+/// * **assets** are the maximum amounts of assets to deposit. Assets not needed for the range may be omitted.
+///
+/// * **min_liquidity** is the minimum amount of liquidity to add.
+///
+/// * **receiver** is the owner of the position. If the receiver already has a position in the same
+///   range, its liquidity is increased, otherwise a new position NFT is minted.
+///   NOTE - the address that wants to provide liquidity should approve the pair contract to pull its relevant tokens.
+#[allow(clippy::too_many_arguments)]
+pub fn add_liquidity(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    tick_lower: i32,
+    tick_upper: i32,
+    assets: Vec<Asset>,
+    min_liquidity: Option<Uint128>,
+    receiver: Option<String>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    ensure_not_paused(
+        &deps.querier,
+        &config.factory_addr,
+        &config.pair_info.contract_addr,
+        PauseScope::Provide,
+        ContractError::Paused,
+    )?;
+
+    if tick_lower >= tick_upper
+        || tick_lower % config.tick_spacing != 0
+        || tick_upper % config.tick_spacing != 0
+    {
+        return Err(ContractError::InvalidTickRange {
+            tick_spacing: config.tick_spacing,
+        });
+    }
+    let sqrt_lower = sqrt_price_at_tick(tick_lower)?;
+    let sqrt_upper = sqrt_price_at_tick(tick_upper)?;
+
+    let max_amounts = get_amounts_from_assets(&config, &assets)?;
+    info.funds
+        .assert_coins_properly_sent(&assets, &config.pair_info.asset_infos)?;
+
+    let mut state = POOL_STATE.load(deps.storage)?;
+    let liquidity = liquidity_for_amounts(
+        state.sqrt_price,
+        sqrt_lower,
+        sqrt_upper,
+        max_amounts[0].saturating_sub(Uint128::one()),
+        max_amounts[1].saturating_sub(Uint128::one()),
+    )?;
+    if liquidity.is_zero() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+
+    let min_liquidity = min_liquidity.unwrap_or_default();
+    ensure!(
+        liquidity >= min_liquidity,
+        ContractError::ProvideSlippageViolation(liquidity, min_liquidity)
+    );
+
+    let (amount0, amount1) =
+        amounts_for_liquidity(state.sqrt_price, sqrt_lower, sqrt_upper, liquidity, true)?;
+    let amounts: [Uint128; 2] = [amount0.try_into()?, amount1.try_into()?];
+    ensure!(
+        amounts[0] <= max_amounts[0] && amounts[1] <= max_amounts[1],
+        ContractError::InsufficientLiquidity {}
+    );
+
+    let receiver = addr_opt_validate(deps.api, &receiver)?.unwrap_or_else(|| info.sender.clone());
+    let key = (&receiver, tick_lower, tick_upper);
+    let mut position = match POSITIONS.may_load(deps.storage, key)? {
+        Some(position) => position,
+        None => {
+            let token_id = LAST_TOKEN_ID.load(deps.storage)? + 1;
+            LAST_TOKEN_ID.save(deps.storage, &token_id)?;
+            NUM_TOKENS.update::<_, StdError>(deps.storage, |num| Ok(num + 1))?;
+            tokens().save(
+                deps.storage,
+                token_id,
+                &PositionToken {
+                    owner: receiver.clone(),
+                    tick_lower,
+                    tick_upper,
+                },
+            )?;
+
+            Position {
+                token_id,
+                liquidity: Uint128::zero(),
+                fee_growth_inside_last: [Uint256::zero(); 2],
+                fees_owed: [Uint128::zero(); 2],
+            }
+        }
+    };
+
+    update_position(
+        deps.storage,
+        &mut state,
+        &mut position,
+        tick_lower,
+        tick_upper,
+        i128::try_from(liquidity.u128()).map_err(|_| ContractError::LiquidityOverflow {})?,
+    )?;
+    POSITIONS.save(deps.storage, key, &position)?;
+    POOL_STATE.save(deps.storage, &state)?;
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    for ((asset_info, amount), max_amount) in config
+        .pair_info
+        .asset_infos
+        .iter()
+        .zip(amounts)
+        .zip(max_amounts)
+    {
+        match asset_info {
+            AssetInfo::Token { contract_addr } if !amount.is_zero() => {
+                messages.push(
+                    wasm_execute(
+                        contract_addr,
+                        &Cw20ExecuteMsg::TransferFrom {
+                            owner: info.sender.to_string(),
+                            recipient: env.contract.address.to_string(),
+                            amount,
+                        },
+                        vec![],
+                    )?
+                    .into(),
+                );
+            }
+            AssetInfo::NativeToken { .. } if max_amount > amount => {
+                messages.push(
+                    asset_info
+                        .with_balance(max_amount - amount)
+                        .into_msg(&info.sender)?,
+                );
+            }
+            _ => {}
+        }
+    }
+
+    let deposits = config
+        .pair_info
+        .asset_infos
+        .iter()
+        .zip(amounts)
+        .map(|(asset_info, amount)| asset_info.with_balance(amount))
+        .collect_vec();
+
+    let event = Event::new("add_liquidity").add_attributes([
+        attr("action", "add_liquidity"),
+        attr("sender", info.sender),
+        attr("receiver", receiver),
+        attr("token_id", position.token_id.to_string()),
+        attr("tick_lower", tick_lower.to_string()),
+        attr("tick_upper", tick_upper.to_string()),
+        attr("liquidity", liquidity),
+        attr("assets", deposits.iter().join(", ")),
+    ]);
+
+    Ok(Response::new().add_messages(messages).add_event(event))
+}
+
+/// Removes liquidity from the position and sends the withdrawn assets together with the accrued fees.
+/// The position NFT is burned once its liquidity drops to zero. Only the position owner can remove liquidity.
+///
+/// * **liquidity** is the amount of liquidity to remove. Defaults to the whole position.
+///
+/// * **min_assets_to_receive** are the minimum amounts of assets to receive including the fees.
+///
+/// * **receiver** is the receiver of the assets. Defaults to the sender.
+pub fn remove_liquidity(
+    deps: DepsMut,
+    info: MessageInfo,
+    token_id: String,
+    liquidity: Option<Uint128>,
+    min_assets_to_receive: Option<Vec<Asset>>,
+    receiver: Option<String>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let (id, token) = load_owned_token(deps.as_ref(), &info.sender, &token_id)?;
+    let key = (&token.owner, token.tick_lower, token.tick_upper);
+    let mut position = POSITIONS.load(deps.storage, key)?;
+
+    let liquidity = liquidity.unwrap_or(position.liquidity);
+    if liquidity.is_zero() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+
+    let mut state = POOL_STATE.load(deps.storage)?;
+    update_position(
+        deps.storage,
+        &mut state,
+        &mut position,
+        token.tick_lower,
+        token.tick_upper,
+        -i128::try_from(liquidity.u128()).map_err(|_| ContractError::LiquidityOverflow {})?,
+    )?;
+    POOL_STATE.save(deps.storage, &state)?;
+
+    let (amount0, amount1) = amounts_for_liquidity(
+        state.sqrt_price,
+        sqrt_price_at_tick(token.tick_lower)?,
+        sqrt_price_at_tick(token.tick_upper)?,
+        liquidity,
+        false,
+    )?;
+    let withdrawn_assets = [amount0, amount1]
+        .into_iter()
+        .zip(position.fees_owed)
+        .zip(&config.pair_info.asset_infos)
+        .map(|((amount, fee), asset_info)| {
+            Ok(asset_info.with_balance(Uint128::try_from(amount)?.checked_add(fee)?))
+        })
+        .collect::<Result<Vec<_>, ContractError>>()?;
+    ensure_min_assets_to_receive(&withdrawn_assets, min_assets_to_receive)?;
+
+    position.fees_owed = [Uint128::zero(); 2];
+    if position.liquidity.is_zero() {
+        POSITIONS.remove(deps.storage, key);
+        tokens().remove(deps.storage, id)?;
+        NUM_TOKENS.update::<_, StdError>(deps.storage, |num| Ok(num - 1))?;
+    } else {
+        POSITIONS.save(deps.storage, key, &position)?;
+    }
+
+    let receiver = addr_opt_validate(deps.api, &receiver)?.unwrap_or_else(|| info.sender.clone());
+    let messages = withdrawn_assets
+        .iter()
+        .filter(|asset| !asset.amount.is_zero())
+        .map(|asset| asset.clone().into_msg(&receiver))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let event = Event::new("remove_liquidity").add_attributes([
+        attr("action", "remove_liquidity"),
+        attr("sender", info.sender),
+        attr("receiver", receiver),
+        attr("token_id", token_id),
+        attr("liquidity", liquidity),
+        attr("assets", withdrawn_assets.iter().join(", ")),
+    ]);
+
+    Ok(Response::new().add_messages(messages).add_event(event))
+}
+
+/// Sends the fees accrued by the position. Only the position owner can collect fees.
+///
+/// * **receiver** is the receiver of the fees. Defaults to the sender.
+pub fn collect_fees(
+    deps: DepsMut,
+    info: MessageInfo,
+    token_id: String,
+    receiver: Option<String>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let (_, token) = load_owned_token(deps.as_ref(), &info.sender, &token_id)?;
+    let key = (&token.owner, token.tick_lower, token.tick_upper);
+    let mut position = POSITIONS.load(deps.storage, key)?;
+
+    let mut state = POOL_STATE.load(deps.storage)?;
+    update_position(
+        deps.storage,
+        &mut state,
+        &mut position,
+        token.tick_lower,
+        token.tick_upper,
+        0,
+    )?;
+
+    let fees = config
+        .pair_info
+        .asset_infos
+        .iter()
+        .zip(position.fees_owed)
+        .map(|(asset_info, fee)| asset_info.with_balance(fee))
+        .collect_vec();
+    position.fees_owed = [Uint128::zero(); 2];
+    POSITIONS.save(deps.storage, key, &position)?;
+
+    let receiver = addr_opt_validate(deps.api, &receiver)?.unwrap_or_else(|| info.sender.clone());
+    let messages = fees
+        .iter()
+        .filter(|asset| !asset.amount.is_zero())
+        .map(|asset| asset.clone().into_msg(&receiver))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let event = Event::new("collect_fees").add_attributes([
+        attr("action", "collect_fees"),
+        attr("sender", info.sender),
+        attr("receiver", receiver),
+        attr("token_id", token_id),
+        attr("fees", fees.iter().join(", ")),
+    ]);
+
+    Ok(Response::new().add_messages(messages).add_event(event))
+}
+
+/// Moves the position from the sender to the recipient. Only the position owner can transfer it.
+/// A recipient can't hold two positions in the same range.
+fn transfer_nft(
+    deps: DepsMut,
+    sender: &Addr,
+    recipient: &Addr,
+    token_id: &str,
+) -> Result<(), ContractError> {
+    let (id, mut token) = load_owned_token(deps.as_ref(), sender, token_id)?;
+    let new_key = (recipient, token.tick_lower, token.tick_upper);
+    if POSITIONS.has(deps.storage, new_key) {
+        return Err(ContractError::PositionExists {});
+    }
+
+    let old_key = (sender, token.tick_lower, token.tick_upper);
+    let position = POSITIONS.load(deps.storage, old_key)?;
+    POSITIONS.remove(deps.storage, old_key);
+    POSITIONS.save(deps.storage, new_key, &position)?;
+
+    token.owner = recipient.clone();
+    tokens().save(deps.storage, id, &token)?;
+
+    Ok(())
+}
+
+/// Loads the position NFT and verifies that it belongs to the sender.
+fn load_owned_token(
+    deps: Deps,
+    sender: &Addr,
+    token_id: &str,
+) -> Result<(u64, PositionToken), ContractError> {
+    let (id, token) = load_token(deps, token_id)?;
+    ensure_eq!(token.owner, *sender, ContractError::Unauthorized {});
+
+    Ok((id, token))
+}
+
+/// Loads the position NFT by its ID.
+fn load_token(deps: Deps, token_id: &str) -> Result<(u64, PositionToken), ContractError> {
+    let not_found = || ContractError::PositionNotFound(token_id.to_string());
+    let id: u64 = token_id.parse().map_err(|_| not_found())?;
+    let token = tokens().may_load(deps.storage, id)?.ok_or_else(not_found)?;
+
+    Ok((id, token))
+}
+
+/// Performs an swap operation with the specified parameters. The swap walks through the initialized
+/// ticks until the whole offer amount is used. The trader must approve the pool contract to transfer
+/// offer assets from their wallet.
+///
+/// * **sender** is the sender of the swap operation.
+///
+/// * **offer_asset** proposed asset for swapping.
+///
+/// * **ask_asset_info** is the asset to swap to.
+///
+/// * **belief_price** is used to calculate the maximum swap spread.
+///
+/// * **max_spread** sets the maximum spread of the swap operation.
+///
+/// * **to** sets the recipient of the swap operation.
+#[allow(clippy::too_many_arguments)]
+pub fn swap(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    sender: Addr,
+    offer_asset: Asset,
+    ask_asset_info: Option<AssetInfo>,
+    belief_price: Option<Decimal>,
+    max_spread: Option<Decimal>,
+    to: Option<Addr>,
+) -> Result<Response, ContractError> {
+    offer_asset.assert_sent_native_token_balance(&info)?;
+
+    let mut config = CONFIG.load(deps.storage)?;
+    ensure_not_paused(
+        &deps.querier,
+        &config.factory_addr,
+        &config.pair_info.contract_addr,
+        PauseScope::Swap,
+        ContractError::Paused,
+    )?;
+
+    let (offer_ind, ask_ind) =
+        select_pools(&config, Some(&offer_asset.info), ask_asset_info.as_ref())?;
+    if offer_asset.amount.is_zero() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+
+    let fee_info = query_fee_info(
+        &deps.querier,
+        &config.factory_addr,
+        config.pair_info.pair_type.clone(),
+        &config.pair_info.contract_addr,
+    )?;
+    let protocol_fee_rate = if fee_info.fee_address.is_some() {
+        fee_info.maker_fee_rate.into()
+    } else {
+        Decimal256::zero()
+    };
+
+    let state = POOL_STATE.load(deps.storage)?;
+    let result = compute_swap(
+        deps.storage,
+        &state,
+        offer_ind == 0,
+        offer_asset.amount,
+        true,
+        fee_info.total_fee_rate.into(),
+        protocol_fee_rate,
+    )?;
+
+    // The spread is the difference from the output at the price before the swap
+    let price = swap_price(&state, offer_ind == 0)?;
+    let return_amount = result.amount_out;
+    let spread_amount = mul_price(result.amount_in, price)?.saturating_sub(return_amount);
+    let commission_amount = result.fee_amount;
+
+    // Check the max spread limit (if it was specified)
+    assert_max_spread(
+        belief_price,
+        max_spread,
+        offer_asset.amount,
+        return_amount + mul_price(commission_amount, price)?,
+        spread_amount,
+    )?;
+
+    apply_crossed_ticks(deps.storage, &result.crossed_ticks)?;
+    POOL_STATE.save(deps.storage, &result.state)?;
+    config.block_time_last = env.block.time.seconds();
+    CONFIG.save(deps.storage, &config)?;
+
+    let ask_asset_info = config.pair_info.asset_infos[ask_ind].clone();
+    let receiver = to.unwrap_or_else(|| sender.clone());
+    let mut messages = vec![];
+    if !return_amount.is_zero() {
+        messages.push(
+            ask_asset_info
+                .with_balance(return_amount)
+                .into_msg(&receiver)?,
+        );
+    }
+
+    // The Maker fee is charged in the offer asset
+    let maker_fee_amount = result.protocol_fee;
+    if let Some(fee_address) = fee_info.fee_address {
+        if !maker_fee_amount.is_zero() {
+            messages.push(
+                offer_asset
+                    .info
+                    .with_balance(maker_fee_amount)
+                    .into_msg(fee_address)?,
+            );
+        }
+    }
+
+    let event = Event::new("swap").add_attributes(vec![
+        attr("action", "swap"),
+        attr("sender", sender),
+        attr("receiver", receiver),
+        attr("offer_asset", offer_asset.info.to_string()),
+        attr("ask_asset", ask_asset_info.to_string()),
+        attr("offer_amount", offer_asset.amount),
+        attr("return_amount", return_amount),
+        attr("spread_amount", spread_amount),
+        attr("commission_amount", commission_amount),
+        attr("maker_fee_amount", maker_fee_amount),
+        attr("fee_share_amount", Uint128::zero()),
+    ]);
+
+    Ok(Response::new().add_messages(messages).add_event(event))
+}
+
+/// Returns the price of the offer asset denominated in the ask asset.
+fn swap_price(state: &PoolState, zero_for_one: bool) -> Result<Decimal256, ContractError> {
+    let price = state.sqrt_price.checked_mul(state.sqrt_price)?;
+    if zero_for_one {
+        Ok(price)
+    } else {
+        Ok(Decimal256::one().checked_div(price)?)
+    }
+}
+
+/// Converts the amount of the offer asset into the ask asset at the given price.
+fn mul_price(amount: Uint128, price: Decimal256) -> Result<Uint128, ContractError> {
+    Ok(Uint256::from(amount).mul_floor(price).try_into()?)
+}
+
+/// Exposes all the queries available in the contract.
+///
+/// ## Queries
+/// * **QueryMsg::Pair {}** Returns information about the pair in an object of type [`PairInfo`].
+///
+/// * **QueryMsg::Pool {}** Returns the pair balances and the liquidity in the current price range
+///   using a [`PoolResponse`] object.
+///
+/// * **QueryMsg::Config {}** Returns the configuration for the pair contract using a [`ConfigResponse`] object.
+///
+/// * **QueryMsg::Simulation { offer_asset, ask_asset_info }** Returns the result of a swap simulation
+///   using a [`SimulationResponse`] object. The commission is denominated in the offer asset.
+///
+/// * **QueryMsg::ReverseSimulation { offer_asset_info, ask_asset }** Returns the result of a reverse
+///   swap simulation using a [`ReverseSimulationResponse`] object.
+///
+/// * **QueryMsg::PoolState {}** Returns the current price, tick and liquidity using a [`PoolStateResponse`] object.
+///
+/// * **QueryMsg::Ticks { start_after, limit }** Returns initialized ticks using [`TickResponse`] objects.
+///
+/// * **QueryMsg::Position { token_id }** Returns the position using a [`PositionResponse`] object.
+///
+/// * **QueryMsg::OwnerOf { token_id }**, **QueryMsg::Tokens { owner, start_after, limit }**,
+///   **QueryMsg::AllTokens { start_after, limit }** and **QueryMsg::NumTokens {}** are CW721 queries
+///   for position NFTs.
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::Pair {} => to_json_binary(&CONFIG.load(deps.storage)?.pair_info),
+        QueryMsg::Pool {} => to_json_binary(&query_pool(deps, env)?),
+        QueryMsg::Config {} => to_json_binary(&query_config(deps)?),
+        QueryMsg::Simulation {
+            offer_asset,
+            ask_asset_info,
+        } => to_json_binary(
+            &query_simulation(deps, offer_asset, ask_asset_info)
+                .map_err(|err| StdError::generic_err(err.to_string()))?,
+        ),
+        QueryMsg::ReverseSimulation {
+            offer_asset_info,
+            ask_asset,
+        } => to_json_binary(
+            &query_reverse_simulation(deps, ask_asset, offer_asset_info)
+                .map_err(|err| StdError::generic_err(err.to_string()))?,
+        ),
+        QueryMsg::PoolState {} => to_json_binary(&query_pool_state(deps)?),
+        QueryMsg::Ticks { start_after, limit } => {
+            to_json_binary(&query_ticks(deps, start_after, limit)?)
+        }
+        QueryMsg::Position { token_id } => to_json_binary(
+            &query_position(deps, token_id)
+                .map_err(|err| StdError::generic_err(err.to_string()))?,
+        ),
+        QueryMsg::OwnerOf { token_id } => {
+            let (_, token) = load_token(deps, &token_id)
+                .map_err(|err| StdError::generic_err(err.to_string()))?;
+            to_json_binary(&OwnerOfResponse {
+                owner: token.owner.to_string(),
+                approvals: vec![],
+            })
+        }
+        QueryMsg::Tokens {
+            owner,
+            start_after,
+            limit,
+        } => to_json_binary(&query_tokens(deps, Some(owner), start_after, limit)?),
+        QueryMsg::AllTokens { start_after, limit } => {
+            to_json_binary(&query_tokens(deps, None, start_after, limit)?)
+        }
+        QueryMsg::NumTokens {} => to_json_binary(&NumTokensResponse {
+            count: NUM_TOKENS.load(deps.storage)?,
+        }),
+    }
+}
+
+/// Returns the pair balances and the liquidity in the current price range in an object of type [`PoolResponse`].
+pub fn query_pool(deps: Deps, env: Env) -> StdResult<PoolResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let state = POOL_STATE.load(deps.storage)?;
+
+    Ok(PoolResponse {
+        assets: config
+            .pair_info
+            .query_pools(&deps.querier, &env.contract.address)?,
+        total_share: state.liquidity,
+    })
+}
+
+/// Returns the pair contract configuration in a [`ConfigResponse`] object.
+pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let factory_config = query_factory_config(&deps.querier, &config.factory_addr)?;
+
+    Ok(ConfigResponse {
+        block_time_last: config.block_time_last,
+        params: Some(to_json_binary(&ClmmConfig {
+            tick_spacing: config.tick_spacing as u32,
+        })?),
+        owner: factory_config.owner,
+        factory_addr: config.factory_addr,
+    })
+}
+
+/// Simulates a swap in the given direction using the fee configured in the factory.
+fn simulate_swap(
+    deps: Deps,
+    config: &Config,
+    zero_for_one: bool,
+    amount: Uint128,
+    exact_in: bool,
+) -> Result<(PoolState, SwapResult), ContractError> {
+    if amount.is_zero() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+
+    let fee_info = query_fee_info(
+        &deps.querier,
+        &config.factory_addr,
+        config.pair_info.pair_type.clone(),
+        &config.pair_info.contract_addr,
+    )?;
+
+    let state = POOL_STATE.load(deps.storage)?;
+    let result = compute_swap(
+        deps.storage,
+        &state,
+        zero_for_one,
+        amount,
+        exact_in,
+        fee_info.total_fee_rate.into(),
+        Decimal256::zero(),
+    )?;
+
+    Ok((state, result))
+}
+
+/// Returns information about a swap simulation in a [`SimulationResponse`] object.
+///
+/// * **offer_asset** is the asset to swap as well as an amount of the said asset.
+///
+/// * **ask_asset_info** is the asset to swap to.
+pub fn query_simulation(
+    deps: Deps,
+    offer_asset: Asset,
+    ask_asset_info: Option<AssetInfo>,
+) -> Result<SimulationResponse, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let (offer_ind, _) = select_pools(&config, Some(&offer_asset.info), ask_asset_info.as_ref())?;
+
+    let (state, result) = simulate_swap(deps, &config, offer_ind == 0, offer_asset.amount, true)?;
+    let price = swap_price(&state, offer_ind == 0)?;
+
+    Ok(SimulationResponse {
+        return_amount: result.amount_out,
+        spread_amount: mul_price(result.amount_in, price)?.saturating_sub(result.amount_out),
+        commission_amount: result.fee_amount,
+    })
+}
+
+/// Returns information about a reverse swap simulation in a [`ReverseSimulationResponse`] object.
+///
+/// * **ask_asset** is the asset to swap to as well as the desired amount of ask
+///   assets to receive from the swap.
+///
+/// * **offer_asset_info** is the asset to swap from.
+pub fn query_reverse_simulation(
+    deps: Deps,
+    ask_asset: Asset,
+    offer_asset_info: Option<AssetInfo>,
+) -> Result<ReverseSimulationResponse, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let (offer_ind, _) = select_pools(&config, offer_asset_info.as_ref(), Some(&ask_asset.info))?;
+
+    let (state, result) = simulate_swap(deps, &config, offer_ind == 0, ask_asset.amount, false)?;
+    let price = swap_price(&state, offer_ind == 0)?;
+
+    Ok(ReverseSimulationResponse {
+        offer_amount: result.amount_in + result.fee_amount,
+        spread_amount: result
+            .amount_in
+            .saturating_sub(mul_price(result.amount_out, Decimal256::one() / price)?),
+        commission_amount: result.fee_amount,
+    })
+}
+
+/// Returns the current pool state in a [`PoolStateResponse`] object.
+pub fn query_pool_state(deps: Deps) -> StdResult<PoolStateResponse> {
+    let state = POOL_STATE.load(deps.storage)?;
+
+    Ok(PoolStateResponse {
+        sqrt_price: state.sqrt_price,
+        price: state.sqrt_price * state.sqrt_price,
+        tick: state.tick,
+        liquidity: state.liquidity,
+    })
+}
+
+/// Returns initialized ticks in ascending order.
+pub fn query_ticks(
+    deps: Deps,
+    start_after: Option<i32>,
+    limit: Option<u32>,
+) -> StdResult<Vec<TickResponse>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+    TICKS
+        .range(
+            deps.storage,
+            start_after.map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .map(|item| {
+            let (tick, info) = item?;
+            Ok(TickResponse {
+                tick,
+                liquidity_gross: info.liquidity_gross,
+                liquidity_net: info.liquidity_net,
+            })
+        })
+        .collect()
+}
+
+/// Returns the position with the assets it can be withdrawn for and the accrued fees
+/// in a [`PositionResponse`] object.
+pub fn query_position(deps: Deps, token_id: String) -> Result<PositionResponse, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let (_, token) = load_token(deps, &token_id)?;
+    let position = POSITIONS.load(
+        deps.storage,
+        (&token.owner, token.tick_lower, token.tick_upper),
+    )?;
+    let state = POOL_STATE.load(deps.storage)?;
+
+    let (amount0, amount1) = amounts_for_liquidity(
+        state.sqrt_price,
+        sqrt_price_at_tick(token.tick_lower)?,
+        sqrt_price_at_tick(token.tick_upper)?,
+        position.liquidity,
+        false,
+    )?;
+    let inside = fee_growth_inside(deps.storage, &state, token.tick_lower, token.tick_upper)?;
+    let pending = pending_fees(&position, &inside)?;
+
+    let asset_infos = &config.pair_info.asset_infos;
+    let fees = asset_infos
+        .iter()
+        .zip(position.fees_owed)
+        .zip(pending)
+        .map(
+            |((asset_info, owed), pending)| Ok(asset_info.with_balance(owed.checked_add(pending)?)),
+        )
+        .collect::<Result<Vec<_>, ContractError>>()?;
+
+    Ok(PositionResponse {
+        token_id,
+        owner: token.owner,
+        tick_lower: token.tick_lower,
+        tick_upper: token.tick_upper,
+        liquidity: position.liquidity,
+        assets: vec![
+            asset_infos[0].with_balance(Uint128::try_from(amount0)?),
+            asset_infos[1].with_balance(Uint128::try_from(amount1)?),
+        ],
+        fees,
+    })
+}
+
+/// Returns position NFT IDs in ascending order, optionally filtered by the owner.
+pub fn query_tokens(
+    deps: Deps,
+    owner: Option<String>,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<TokensResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after
+        .map(|token_id| {
+            token_id
+                .parse::<u64>()
+                .map_err(|_| StdError::generic_err(format!("Invalid token id {token_id}")))
+        })
+        .transpose()?
+        .map(Bound::exclusive);
+
+    let tokens = match owner {
+        Some(owner) => {
+            let owner = deps.api.addr_validate(&owner)?;
+            tokens()
+                .idx
+                .owner
+                .prefix(owner)
+                .keys(deps.storage, start, None, Order::Ascending)
+                .take(limit)
+                .map(|id| id.map(|id| id.to_string()))
+                .collect::<StdResult<Vec<_>>>()?
+        }
+        None => tokens()
+            .keys(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .map(|id| id.map(|id| id.to_string()))
+            .collect::<StdResult<Vec<_>>>()?,
+    };
+
+    Ok(TokensResponse { tokens })
+}
+
+/// Manages the contract migration.
+#[cfg(not(tarpaulin_include))]
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, _env: Env, _msg: Empty) -> Result<Response, ContractError> {
+    let version = get_contract_version(deps.storage)?;
+
+    ensure_eq!(
+        version.contract,
+        CONTRACT_NAME,
+        ContractError::MigrationError {}
+    );
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Response::new()
+        .add_attribute("previous_contract_name", &version.contract)
+        .add_attribute("previous_contract_version", &version.version)
+        .add_attribute("new_contract_name", CONTRACT_NAME)
+        .add_attribute("new_contract_version", CONTRACT_VERSION))
+}
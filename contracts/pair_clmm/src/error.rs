@@ -0,0 +1,108 @@
+use cosmwasm_std::{
+    CheckedFromRatioError, CheckedMultiplyRatioError, ConversionOverflowError, OverflowError,
+    StdError, Uint128,
+};
+use cw_utils::PaymentError;
+use thiserror::Error;
+
+use astroport::factory::PauseScope;
+
+use crate::math::{MAX_TICK, MIN_TICK};
+
+/// This enum describes tick-based concentrated liquidity pair contract errors
+#[derive(Error, Debug, PartialEq)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("{0}")]
+    ConversionOverflowError(#[from] ConversionOverflowError),
+
+    #[error("{0}")]
+    CheckedMultiplyRatioError(#[from] CheckedMultiplyRatioError),
+
+    #[error("{0}")]
+    CheckedFromRatioError(#[from] CheckedFromRatioError),
+
+    #[error("{0}")]
+    OverflowError(#[from] OverflowError),
+
+    #[error("{0}")]
+    PaymentError(#[from] PaymentError),
+
+    #[error("Unauthorized")]
+    Unauthorized {},
+
+    #[error("Transaction deadline exceeded")]
+    DeadlineExceeded {},
+
+    #[error("CW20 tokens can be swapped via Cw20::Send message only")]
+    Cw20DirectSwap {},
+
+    #[error("Operation non supported")]
+    NonSupported {},
+
+    #[error("You need to provide init params")]
+    InitParamsNotFound {},
+
+    #[error("The pair must contain exactly two distinct assets")]
+    InvalidNumberOfAssets {},
+
+    #[error("Tick spacing must be from 1 to {}", MAX_TICK)]
+    InvalidTickSpacing {},
+
+    #[error("The price must be from 1e-12 to 1e12")]
+    PriceOutOfRange {},
+
+    #[error("Tick {0} is out of range [{min}, {max}]", min = MIN_TICK, max = MAX_TICK)]
+    InvalidTick(i32),
+
+    #[error("Invalid tick range: the lower tick must be less than the upper one and both must be divisible by the tick spacing {tick_spacing}")]
+    InvalidTickRange { tick_spacing: i32 },
+
+    #[error("The asset {0} does not belong to the pair")]
+    InvalidAsset(String),
+
+    #[error("Doubling assets in asset infos")]
+    DoublingAssets {},
+
+    #[error("Source and target assets are the same")]
+    SameAssets {},
+
+    #[error("Event of zero transfer")]
+    InvalidZeroAmount {},
+
+    #[error("Not enough liquidity in the pool to fill the swap")]
+    InsufficientLiquidity {},
+
+    #[error("Position liquidity exceeds the maximum of {}", i128::MAX)]
+    LiquidityOverflow {},
+
+    #[error("Position {0} not found")]
+    PositionNotFound(String),
+
+    #[error("The recipient already has a position in the same range")]
+    PositionExists {},
+
+    #[error("{0:?} is paused")]
+    Paused(PauseScope),
+
+    #[error("Operation exceeds max spread limit")]
+    MaxSpreadAssertion {},
+
+    #[error("Provided spread amount exceeds allowed limit")]
+    AllowedSpreadAssertion {},
+
+    #[error("Slippage is more than expected: received {0}, expected {1} liquidity")]
+    ProvideSlippageViolation(Uint128, Uint128),
+
+    #[error("Received {received} {asset_name} but expected {expected}")]
+    WithdrawSlippageViolation {
+        asset_name: String,
+        received: Uint128,
+        expected: Uint128,
+    },
+
+    #[error("Failed to migrate the contract")]
+    MigrationError {},
+}
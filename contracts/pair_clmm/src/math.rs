@@ -0,0 +1,368 @@
+use cosmwasm_std::{Decimal256, Uint128, Uint256};
+
+use crate::error::ContractError;
+
+/// The minimum tick. The price at this tick is about 1e-12
+pub const MIN_TICK: i32 = -276324;
+/// The maximum tick. The price at this tick is about 1e12
+pub const MAX_TICK: i32 = -MIN_TICK;
+/// The square root of the price ratio between two adjacent ticks: sqrt(1.0001)
+const SQRT_TICK_BASE: Decimal256 = Decimal256::raw(1_000049998750062496);
+
+/// Returns sqrt(1.0001^tick).
+pub fn sqrt_price_at_tick(tick: i32) -> Result<Decimal256, ContractError> {
+    if !(MIN_TICK..=MAX_TICK).contains(&tick) {
+        return Err(ContractError::InvalidTick(tick));
+    }
+
+    let sqrt_price = SQRT_TICK_BASE.checked_pow(tick.unsigned_abs())?;
+    if tick < 0 {
+        Ok(Decimal256::one().checked_div(sqrt_price)?)
+    } else {
+        Ok(sqrt_price)
+    }
+}
+
+/// Returns the greatest tick whose square root price does not exceed the given one.
+pub fn tick_at_sqrt_price(sqrt_price: Decimal256) -> Result<i32, ContractError> {
+    let (mut low, mut high) = (MIN_TICK, MAX_TICK);
+    if sqrt_price < sqrt_price_at_tick(low)? || sqrt_price > sqrt_price_at_tick(high)? {
+        return Err(ContractError::PriceOutOfRange {});
+    }
+
+    while low < high {
+        let mid = low + (high - low + 1) / 2;
+        if sqrt_price_at_tick(mid)? <= sqrt_price {
+            low = mid;
+        } else {
+            high = mid - 1;
+        }
+    }
+
+    Ok(low)
+}
+
+/// Converts a decimal amount into an integer rounding it in the requested direction.
+/// Rounding up adds an extra unit to cover the truncation errors of [`Decimal256`] operations.
+fn to_amount(amount: Decimal256, round_up: bool) -> Uint256 {
+    if round_up && !amount.is_zero() {
+        amount.to_uint_floor() + Uint256::one()
+    } else {
+        amount.to_uint_floor()
+    }
+}
+
+/// Returns the amount of the first asset between two prices: L * (sqrt_b - sqrt_a) / (sqrt_a * sqrt_b).
+pub fn amount0_delta(
+    sqrt_a: Decimal256,
+    sqrt_b: Decimal256,
+    liquidity: Uint128,
+    round_up: bool,
+) -> Result<Uint256, ContractError> {
+    let (sqrt_a, sqrt_b) = if sqrt_a > sqrt_b {
+        (sqrt_b, sqrt_a)
+    } else {
+        (sqrt_a, sqrt_b)
+    };
+
+    let amount = Decimal256::from_ratio(liquidity, 1u8)
+        .checked_mul(sqrt_b - sqrt_a)?
+        .checked_div(sqrt_b)?
+        .checked_div(sqrt_a)?;
+
+    Ok(to_amount(amount, round_up))
+}
+
+/// Returns the amount of the second asset between two prices: L * (sqrt_b - sqrt_a).
+pub fn amount1_delta(
+    sqrt_a: Decimal256,
+    sqrt_b: Decimal256,
+    liquidity: Uint128,
+    round_up: bool,
+) -> Result<Uint256, ContractError> {
+    let diff = sqrt_a.abs_diff(sqrt_b);
+    let amount = Decimal256::from_ratio(liquidity, 1u8).checked_mul(diff)?;
+
+    Ok(to_amount(amount, round_up))
+}
+
+/// Returns the maximum liquidity the given amounts can provide in the range between
+/// `sqrt_a` and `sqrt_b` at the current price.
+pub fn liquidity_for_amounts(
+    sqrt_price: Decimal256,
+    sqrt_a: Decimal256,
+    sqrt_b: Decimal256,
+    amount0: Uint128,
+    amount1: Uint128,
+) -> Result<Uint128, ContractError> {
+    // L = amount0 * sqrt_a * sqrt_b / (sqrt_b - sqrt_a)
+    let liquidity0 = |sqrt_a: Decimal256| -> Result<Decimal256, ContractError> {
+        Ok(Decimal256::from_ratio(amount0, 1u8)
+            .checked_mul(sqrt_a)?
+            .checked_mul(sqrt_b)?
+            .checked_div(sqrt_b - sqrt_a)?)
+    };
+    // L = amount1 / (sqrt_b - sqrt_a)
+    let liquidity1 = |sqrt_b: Decimal256| -> Result<Decimal256, ContractError> {
+        Ok(Decimal256::from_ratio(amount1, 1u8).checked_div(sqrt_b - sqrt_a)?)
+    };
+
+    let liquidity = if sqrt_price <= sqrt_a {
+        liquidity0(sqrt_a)?
+    } else if sqrt_price >= sqrt_b {
+        liquidity1(sqrt_b)?
+    } else {
+        liquidity0(sqrt_price)?.min(liquidity1(sqrt_price)?)
+    };
+
+    Ok(liquidity.to_uint_floor().try_into()?)
+}
+
+/// Returns the amounts of assets corresponding to the liquidity in the range between
+/// `sqrt_a` and `sqrt_b` at the current price.
+pub fn amounts_for_liquidity(
+    sqrt_price: Decimal256,
+    sqrt_a: Decimal256,
+    sqrt_b: Decimal256,
+    liquidity: Uint128,
+    round_up: bool,
+) -> Result<(Uint256, Uint256), ContractError> {
+    let sqrt_price = sqrt_price.clamp(sqrt_a, sqrt_b);
+
+    Ok((
+        amount0_delta(sqrt_price, sqrt_b, liquidity, round_up)?,
+        amount1_delta(sqrt_a, sqrt_price, liquidity, round_up)?,
+    ))
+}
+
+/// Returns the next square root price after the given amount of input is added to the pool.
+/// The result is rounded so that the pool never gives away more than it should.
+fn next_sqrt_price_from_input(
+    sqrt_price: Decimal256,
+    liquidity: Uint128,
+    amount_in: Uint256,
+    zero_for_one: bool,
+) -> Result<Decimal256, ContractError> {
+    if amount_in.is_zero() {
+        return Ok(sqrt_price);
+    }
+
+    let liquidity = Decimal256::from_ratio(liquidity, 1u8);
+    let amount_in = Decimal256::from_ratio(amount_in, 1u8);
+    if zero_for_one {
+        // sqrt_p' = L * sqrt_p / (L + amount_in * sqrt_p), rounded up
+        let numerator = liquidity.checked_mul(sqrt_price)?;
+        let denominator = liquidity.checked_add(amount_in.checked_mul(sqrt_price)?)?;
+        Ok(numerator.checked_div(denominator)? + Decimal256::raw(1))
+    } else {
+        // sqrt_p' = sqrt_p + amount_in / L, rounded down
+        Ok(sqrt_price.checked_add(amount_in.checked_div(liquidity)?)?)
+    }
+}
+
+/// Returns the next square root price after the given amount of output is taken from the pool.
+/// The result is rounded so that the pool never gives away more than it should.
+fn next_sqrt_price_from_output(
+    sqrt_price: Decimal256,
+    liquidity: Uint128,
+    amount_out: Uint256,
+    zero_for_one: bool,
+) -> Result<Decimal256, ContractError> {
+    if amount_out.is_zero() {
+        return Ok(sqrt_price);
+    }
+
+    let liquidity = Decimal256::from_ratio(liquidity, 1u8);
+    let amount_out = Decimal256::from_ratio(amount_out, 1u8);
+    if zero_for_one {
+        // sqrt_p' = sqrt_p - amount_out / L, rounded down
+        let delta = amount_out.checked_div(liquidity)? + Decimal256::raw(1);
+        sqrt_price
+            .checked_sub(delta)
+            .map_err(|_| ContractError::InsufficientLiquidity {})
+    } else {
+        // sqrt_p' = L * sqrt_p / (L - amount_out * sqrt_p), rounded up
+        let numerator = liquidity.checked_mul(sqrt_price)?;
+        let denominator = liquidity
+            .checked_sub(amount_out.checked_mul(sqrt_price)?)
+            .map_err(|_| ContractError::InsufficientLiquidity {})?;
+        if denominator.is_zero() {
+            return Err(ContractError::InsufficientLiquidity {});
+        }
+        Ok(numerator.checked_div(denominator)? + Decimal256::raw(1))
+    }
+}
+
+/// The result of a swap within a single price range.
+#[derive(Debug, PartialEq)]
+pub struct SwapStep {
+    /// The square root price after the step
+    pub sqrt_price_next: Decimal256,
+    /// The amount of input excluding the fee
+    pub amount_in: Uint256,
+    pub amount_out: Uint256,
+    /// The fee charged in the input asset
+    pub fee_amount: Uint256,
+}
+
+/// Swaps within a single price range with constant liquidity until either the target price is
+/// reached or the remaining amount is exhausted.
+///
+/// * **amount_remaining** is the remaining input amount (including the fee) for exact input swaps
+///   or the remaining output amount for exact output swaps.
+///
+/// * **fee_rate** is the fee charged on top of the input amount.
+pub fn compute_swap_step(
+    sqrt_price: Decimal256,
+    sqrt_target: Decimal256,
+    liquidity: Uint128,
+    amount_remaining: Uint256,
+    fee_rate: Decimal256,
+    exact_in: bool,
+) -> Result<SwapStep, ContractError> {
+    let zero_for_one = sqrt_price >= sqrt_target;
+    let amount_in_for = |sqrt_next: Decimal256| {
+        if zero_for_one {
+            amount0_delta(sqrt_next, sqrt_price, liquidity, true)
+        } else {
+            amount1_delta(sqrt_price, sqrt_next, liquidity, true)
+        }
+    };
+    let amount_out_for = |sqrt_next: Decimal256| {
+        if zero_for_one {
+            amount1_delta(sqrt_next, sqrt_price, liquidity, false)
+        } else {
+            amount0_delta(sqrt_price, sqrt_next, liquidity, false)
+        }
+    };
+
+    let sqrt_price_next = if exact_in {
+        let amount_less_fee = amount_remaining.mul_floor(Decimal256::one() - fee_rate);
+        if amount_less_fee >= amount_in_for(sqrt_target)? {
+            sqrt_target
+        } else {
+            let next =
+                next_sqrt_price_from_input(sqrt_price, liquidity, amount_less_fee, zero_for_one)?;
+            // Rounding must not move the price past the target
+            if zero_for_one {
+                next.max(sqrt_target)
+            } else {
+                next.min(sqrt_target)
+            }
+        }
+    } else if amount_remaining >= amount_out_for(sqrt_target)? {
+        sqrt_target
+    } else {
+        let next =
+            next_sqrt_price_from_output(sqrt_price, liquidity, amount_remaining, zero_for_one)?;
+        if zero_for_one {
+            next.max(sqrt_target)
+        } else {
+            next.min(sqrt_target)
+        }
+    };
+
+    let amount_in = amount_in_for(sqrt_price_next)?;
+    let mut amount_out = amount_out_for(sqrt_price_next)?;
+    if !exact_in {
+        // The price was moved to fill the whole remaining output
+        amount_out = if sqrt_price_next == sqrt_target {
+            amount_out.min(amount_remaining)
+        } else {
+            amount_remaining
+        };
+    }
+
+    let fee_amount = if exact_in && sqrt_price_next != sqrt_target {
+        // The remainder is taken as a fee as it can't move the price any further
+        amount_remaining.saturating_sub(amount_in)
+    } else {
+        amount_in.mul_ceil(fee_rate / (Decimal256::one() - fee_rate))
+    };
+
+    Ok(SwapStep {
+        sqrt_price_next,
+        amount_in,
+        amount_out,
+        fee_amount,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    #[test]
+    fn tick_price_conversions() {
+        assert_eq!(sqrt_price_at_tick(0).unwrap(), Decimal256::one());
+        let price = sqrt_price_at_tick(2).unwrap();
+        assert!(price.abs_diff(Decimal256::from_str("1.0001").unwrap()) < Decimal256::raw(10));
+        assert_eq!(
+            sqrt_price_at_tick(MAX_TICK + 1).unwrap_err(),
+            ContractError::InvalidTick(MAX_TICK + 1)
+        );
+
+        for tick in [MIN_TICK, -50000, -1, 0, 1, 12345, MAX_TICK] {
+            let sqrt_price = sqrt_price_at_tick(tick).unwrap();
+            assert_eq!(tick_at_sqrt_price(sqrt_price).unwrap(), tick);
+            if tick < MAX_TICK {
+                let next = sqrt_price_at_tick(tick + 1).unwrap();
+                assert!(next > sqrt_price);
+                assert_eq!(tick_at_sqrt_price(next - Decimal256::raw(1)).unwrap(), tick);
+            }
+        }
+    }
+
+    #[test]
+    fn swap_step_exact_in_and_out() {
+        let fee_rate = Decimal256::permille(3);
+        let liquidity = Uint128::new(1_000_000_000);
+        let sqrt_price = Decimal256::one();
+        let sqrt_target = sqrt_price_at_tick(-1000).unwrap();
+
+        // The step stops before the target if the input is small
+        let step = compute_swap_step(
+            sqrt_price,
+            sqrt_target,
+            liquidity,
+            Uint256::from(1_000_000u128),
+            fee_rate,
+            true,
+        )
+        .unwrap();
+        assert!(step.sqrt_price_next > sqrt_target);
+        assert_eq!(
+            step.amount_in + step.fee_amount,
+            Uint256::from(1_000_000u128)
+        );
+        assert!(step.amount_out < step.amount_in);
+
+        // Reverse computation returns the same output for about the same input
+        let reverse = compute_swap_step(
+            sqrt_price,
+            sqrt_target,
+            liquidity,
+            step.amount_out,
+            fee_rate,
+            false,
+        )
+        .unwrap();
+        assert_eq!(reverse.amount_out, step.amount_out);
+        assert!(reverse.amount_in.abs_diff(step.amount_in) <= Uint256::from(2u8));
+
+        // A large input is capped at the target price
+        let step = compute_swap_step(
+            sqrt_price,
+            sqrt_target,
+            liquidity,
+            Uint256::from(1_000_000_000u128),
+            fee_rate,
+            true,
+        )
+        .unwrap();
+        assert_eq!(step.sqrt_price_next, sqrt_target);
+        assert!(step.amount_in + step.fee_amount < Uint256::from(1_000_000_000u128));
+    }
+}
@@ -0,0 +1,296 @@
+use cosmwasm_std::{Decimal256, Int128, Order, StdError, StdResult, Storage, Uint128, Uint256};
+use cw_storage_plus::Bound;
+
+use crate::error::ContractError;
+use crate::math::{compute_swap_step, sqrt_price_at_tick, tick_at_sqrt_price, MAX_TICK, MIN_TICK};
+use crate::state::{PoolState, Position, TickInfo, TICKS};
+
+/// Fee growth values are multiplied by 2^128 to keep the precision.
+fn q128() -> Uint256 {
+    Uint256::one() << 128
+}
+
+/// Adds a signed liquidity delta to the liquidity amount.
+pub fn add_delta(liquidity: Uint128, delta: i128) -> Result<Uint128, ContractError> {
+    let abs = Uint128::new(delta.unsigned_abs());
+    if delta >= 0 {
+        Ok(liquidity.checked_add(abs)?)
+    } else {
+        Ok(liquidity.checked_sub(abs)?)
+    }
+}
+
+/// Updates the tick liquidity when a position using the tick as a bound changes.
+/// A newly initialized tick assumes all the fees so far were earned below it.
+fn update_tick(
+    storage: &mut dyn Storage,
+    state: &PoolState,
+    tick: i32,
+    delta: i128,
+    upper: bool,
+) -> Result<(), ContractError> {
+    let mut info = TICKS.may_load(storage, tick)?.unwrap_or(TickInfo {
+        liquidity_gross: Uint128::zero(),
+        liquidity_net: Int128::zero(),
+        fee_growth_outside: [Uint256::zero(); 2],
+    });
+
+    if info.liquidity_gross.is_zero() && tick <= state.tick {
+        info.fee_growth_outside = state.fee_growth_global;
+    }
+
+    info.liquidity_gross = add_delta(info.liquidity_gross, delta)?;
+    let delta = Int128::new(delta);
+    info.liquidity_net = if upper {
+        info.liquidity_net.checked_sub(delta)?
+    } else {
+        info.liquidity_net.checked_add(delta)?
+    };
+
+    TICKS.save(storage, tick, &info)?;
+
+    Ok(())
+}
+
+/// Returns the fee growth per unit of liquidity inside the tick range.
+pub fn fee_growth_inside(
+    storage: &dyn Storage,
+    state: &PoolState,
+    tick_lower: i32,
+    tick_upper: i32,
+) -> StdResult<[Uint256; 2]> {
+    let lower = TICKS.load(storage, tick_lower)?;
+    let upper = TICKS.load(storage, tick_upper)?;
+
+    Ok([0, 1].map(|i| {
+        let global = state.fee_growth_global[i];
+        let below = if state.tick >= tick_lower {
+            lower.fee_growth_outside[i]
+        } else {
+            global.wrapping_sub(lower.fee_growth_outside[i])
+        };
+        let above = if state.tick < tick_upper {
+            upper.fee_growth_outside[i]
+        } else {
+            global.wrapping_sub(upper.fee_growth_outside[i])
+        };
+
+        global.wrapping_sub(below).wrapping_sub(above)
+    }))
+}
+
+/// Returns the fees earned by the position since its last update.
+pub fn pending_fees(
+    position: &Position,
+    fee_growth_inside: &[Uint256; 2],
+) -> Result<[Uint128; 2], ContractError> {
+    let mut fees = [Uint128::zero(); 2];
+    for (i, fee) in fees.iter_mut().enumerate() {
+        *fee = fee_growth_inside[i]
+            .wrapping_sub(position.fee_growth_inside_last[i])
+            .checked_multiply_ratio(position.liquidity, q128())?
+            .try_into()?;
+    }
+
+    Ok(fees)
+}
+
+/// Changes the position liquidity by the signed delta. Updates the bounding ticks and the active
+/// liquidity and accrues the fees earned by the position so far.
+pub fn update_position(
+    storage: &mut dyn Storage,
+    state: &mut PoolState,
+    position: &mut Position,
+    tick_lower: i32,
+    tick_upper: i32,
+    delta: i128,
+) -> Result<(), ContractError> {
+    if delta != 0 {
+        update_tick(storage, state, tick_lower, delta, false)?;
+        update_tick(storage, state, tick_upper, delta, true)?;
+    }
+
+    let inside = fee_growth_inside(storage, state, tick_lower, tick_upper)?;
+    let fees = pending_fees(position, &inside)?;
+    for (owed, fee) in position.fees_owed.iter_mut().zip(fees) {
+        *owed = owed.checked_add(fee)?;
+    }
+    position.fee_growth_inside_last = inside;
+    position.liquidity = add_delta(position.liquidity, delta)?;
+
+    // Ticks that are not used by any position anymore are removed
+    if delta < 0 {
+        for tick in [tick_lower, tick_upper] {
+            if TICKS.load(storage, tick)?.liquidity_gross.is_zero() {
+                TICKS.remove(storage, tick);
+            }
+        }
+    }
+
+    if (tick_lower..tick_upper).contains(&state.tick) {
+        state.liquidity = add_delta(state.liquidity, delta)?;
+    }
+
+    Ok(())
+}
+
+/// Returns the closest initialized tick in the swap direction. The current tick is included
+/// when moving to the left.
+fn next_initialized_tick(
+    storage: &dyn Storage,
+    tick: i32,
+    zero_for_one: bool,
+) -> StdResult<Option<i32>> {
+    let next = if zero_for_one {
+        TICKS
+            .keys(
+                storage,
+                None,
+                Some(Bound::inclusive(tick)),
+                Order::Descending,
+            )
+            .next()
+    } else {
+        TICKS
+            .keys(
+                storage,
+                Some(Bound::exclusive(tick)),
+                None,
+                Order::Ascending,
+            )
+            .next()
+    };
+
+    next.transpose()
+}
+
+/// The result of a swap simulation.
+#[derive(Debug)]
+pub struct SwapResult {
+    /// The input amount excluding the fee
+    pub amount_in: Uint128,
+    pub amount_out: Uint128,
+    /// The total fee charged in the input asset
+    pub fee_amount: Uint128,
+    /// The part of the fee sent to the Maker contract
+    pub protocol_fee: Uint128,
+    /// The pool state after the swap
+    pub state: PoolState,
+    /// Crossed ticks along with the global fee growth at the moment of crossing
+    pub crossed_ticks: Vec<(i32, [Uint256; 2])>,
+}
+
+/// Simulates a swap walking through the initialized ticks without changing the storage.
+/// The result must be applied with [`apply_crossed_ticks`].
+///
+/// * **zero_for_one** is true if the first asset is swapped for the second one.
+///
+/// * **amount** is the input amount for exact input swaps or the output amount otherwise.
+///
+/// * **protocol_fee_rate** is the share of the fee that is not distributed to liquidity providers.
+pub fn compute_swap(
+    storage: &dyn Storage,
+    state: &PoolState,
+    zero_for_one: bool,
+    amount: Uint128,
+    exact_in: bool,
+    fee_rate: Decimal256,
+    protocol_fee_rate: Decimal256,
+) -> Result<SwapResult, ContractError> {
+    let mut state = state.clone();
+    let fee_ind = if zero_for_one { 0 } else { 1 };
+    let limit_tick = if zero_for_one { MIN_TICK } else { MAX_TICK };
+    let sqrt_price_limit = sqrt_price_at_tick(limit_tick)?;
+
+    let mut remaining = Uint256::from(amount);
+    let (mut amount_in, mut amount_out) = (Uint256::zero(), Uint256::zero());
+    let (mut fee_amount, mut protocol_fee) = (Uint256::zero(), Uint256::zero());
+    let mut crossed_ticks = vec![];
+
+    while !remaining.is_zero() {
+        if state.sqrt_price == sqrt_price_limit {
+            return Err(ContractError::InsufficientLiquidity {});
+        }
+
+        let next_tick = next_initialized_tick(storage, state.tick, zero_for_one)?;
+        let target_tick = next_tick.unwrap_or(limit_tick);
+        let sqrt_target = sqrt_price_at_tick(target_tick)?;
+
+        let step = compute_swap_step(
+            state.sqrt_price,
+            sqrt_target,
+            state.liquidity,
+            remaining,
+            fee_rate,
+            exact_in,
+        )?;
+
+        remaining = if exact_in {
+            remaining.saturating_sub(step.amount_in + step.fee_amount)
+        } else {
+            remaining.saturating_sub(step.amount_out)
+        };
+        amount_in += step.amount_in;
+        amount_out += step.amount_out;
+        fee_amount += step.fee_amount;
+
+        let step_protocol_fee = step.fee_amount.mul_floor(protocol_fee_rate);
+        protocol_fee += step_protocol_fee;
+        if !state.liquidity.is_zero() {
+            let growth = (step.fee_amount - step_protocol_fee)
+                .checked_multiply_ratio(q128(), state.liquidity)?;
+            state.fee_growth_global[fee_ind] =
+                state.fee_growth_global[fee_ind].wrapping_add(growth);
+        }
+
+        if step.sqrt_price_next == sqrt_target {
+            if let Some(tick) = next_tick {
+                crossed_ticks.push((tick, state.fee_growth_global));
+                let liquidity_net = TICKS.load(storage, tick)?.liquidity_net.i128();
+                state.liquidity = add_delta(
+                    state.liquidity,
+                    if zero_for_one {
+                        -liquidity_net
+                    } else {
+                        liquidity_net
+                    },
+                )?;
+            }
+            state.tick = if zero_for_one {
+                target_tick - 1
+            } else {
+                target_tick
+            };
+        } else if step.sqrt_price_next != state.sqrt_price {
+            state.tick = tick_at_sqrt_price(step.sqrt_price_next)?;
+        }
+        state.sqrt_price = step.sqrt_price_next;
+    }
+
+    Ok(SwapResult {
+        amount_in: amount_in.try_into()?,
+        amount_out: amount_out.try_into()?,
+        fee_amount: fee_amount.try_into()?,
+        protocol_fee: protocol_fee.try_into()?,
+        state,
+        crossed_ticks,
+    })
+}
+
+/// Flips the fee growth outside of the ticks crossed during a swap.
+pub fn apply_crossed_ticks(
+    storage: &mut dyn Storage,
+    crossed_ticks: &[(i32, [Uint256; 2])],
+) -> StdResult<()> {
+    for (tick, fee_growth_global) in crossed_ticks {
+        TICKS.update(storage, *tick, |info| -> StdResult<_> {
+            let mut info = info.ok_or_else(|| StdError::not_found("tick"))?;
+            for (outside, global) in info.fee_growth_outside.iter_mut().zip(fee_growth_global) {
+                *outside = global.wrapping_sub(*outside);
+            }
+            Ok(info)
+        })?;
+    }
+
+    Ok(())
+}
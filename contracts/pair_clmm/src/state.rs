@@ -0,0 +1,94 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, Decimal256, Int128, Uint128, Uint256};
+use cw_storage_plus::{index_list, IndexedMap, Item, Map, MultiIndex};
+
+use astroport::asset::PairInfo;
+
+/// This structure stores the main tick-based concentrated liquidity pair parameters.
+#[cw_serde]
+pub struct Config {
+    /// The pair information stored in a [`PairInfo`] struct
+    pub pair_info: PairInfo,
+    /// The factory contract address
+    pub factory_addr: Addr,
+    /// The last timestamp when the pool price changed
+    pub block_time_last: u64,
+    /// Only ticks divisible by the tick spacing can be used as position bounds
+    pub tick_spacing: i32,
+}
+
+/// This structure stores the current state of the pool.
+#[cw_serde]
+pub struct PoolState {
+    /// The square root of the current price of the first asset denominated in the second one
+    pub sqrt_price: Decimal256,
+    /// The greatest tick whose price does not exceed the current price
+    pub tick: i32,
+    /// The liquidity of the positions whose range contains the current price
+    pub liquidity: Uint128,
+    /// The total fees earned per unit of liquidity for each asset, multiplied by 2^128.
+    /// Fee growth values wrap around on overflow, only their differences are meaningful
+    pub fee_growth_global: [Uint256; 2],
+}
+
+/// This structure stores an initialized tick.
+#[cw_serde]
+pub struct TickInfo {
+    /// The total liquidity of positions using the tick as a bound
+    pub liquidity_gross: Uint128,
+    /// The liquidity added when the price crosses the tick from left to right
+    pub liquidity_net: Int128,
+    /// The fee growth on the other side of the tick relative to the current tick
+    pub fee_growth_outside: [Uint256; 2],
+}
+
+/// This structure stores a liquidity position.
+#[cw_serde]
+pub struct Position {
+    /// The ID of the NFT representing the position
+    pub token_id: u64,
+    pub liquidity: Uint128,
+    /// The fee growth inside the position range as of the last position update
+    pub fee_growth_inside_last: [Uint256; 2],
+    /// The fees accrued by the position as of the last position update
+    pub fees_owed: [Uint128; 2],
+}
+
+/// This structure stores a position NFT.
+#[cw_serde]
+pub struct PositionToken {
+    pub owner: Addr,
+    pub tick_lower: i32,
+    pub tick_upper: i32,
+}
+
+/// Stores the config struct at the given key
+pub const CONFIG: Item<Config> = Item::new("config");
+
+/// Stores the current pool state
+pub const POOL_STATE: Item<PoolState> = Item::new("pool_state");
+
+/// Stores initialized ticks
+pub const TICKS: Map<i32, TickInfo> = Map::new("ticks");
+
+/// Stores positions keyed by the owner and the tick range
+pub const POSITIONS: Map<(&Addr, i32, i32), Position> = Map::new("positions");
+
+/// Stores the ID of the last minted position NFT
+pub const LAST_TOKEN_ID: Item<u64> = Item::new("last_token_id");
+
+/// Stores the number of existing position NFTs
+pub const NUM_TOKENS: Item<u64> = Item::new("num_tokens");
+
+#[index_list(PositionToken)]
+pub struct TokenIndexes<'a> {
+    pub owner: MultiIndex<'a, Addr, PositionToken, u64>,
+}
+
+/// Returns the position NFTs indexed by their owner.
+pub fn tokens<'a>() -> IndexedMap<u64, PositionToken, TokenIndexes<'a>> {
+    let indexes = TokenIndexes {
+        owner: MultiIndex::new(|_, token| token.owner.clone(), "tokens", "to"),
+    };
+    IndexedMap::new("tokens", indexes)
+}
@@ -0,0 +1,138 @@
+use std::str::FromStr;
+
+use cosmwasm_std::{Decimal, Fraction, StdError, Uint128};
+use itertools::Itertools;
+
+use astroport::asset::{Asset, AssetInfo};
+use astroport::cosmwasm_ext::DecMul;
+use astroport::pair::{DEFAULT_SLIPPAGE, MAX_ALLOWED_SLIPPAGE};
+
+use crate::error::ContractError;
+use crate::state::Config;
+
+/// Returns the position of the given asset in the pool.
+pub fn asset_position(config: &Config, asset_info: &AssetInfo) -> Result<usize, ContractError> {
+    config
+        .pair_info
+        .asset_infos
+        .iter()
+        .position(|info| info.equal(asset_info))
+        .ok_or_else(|| ContractError::InvalidAsset(asset_info.to_string()))
+}
+
+/// Resolves positions of the offer and the ask assets in the pool.
+pub fn select_pools(
+    config: &Config,
+    offer_asset_info: Option<&AssetInfo>,
+    ask_asset_info: Option<&AssetInfo>,
+) -> Result<(usize, usize), ContractError> {
+    let (offer_ind, ask_ind) = match (offer_asset_info, ask_asset_info) {
+        (Some(offer), Some(ask)) => (asset_position(config, offer)?, asset_position(config, ask)?),
+        (Some(offer), None) => {
+            let offer_ind = asset_position(config, offer)?;
+            (offer_ind, 1 - offer_ind)
+        }
+        (None, Some(ask)) => {
+            let ask_ind = asset_position(config, ask)?;
+            (1 - ask_ind, ask_ind)
+        }
+        (None, None) => unreachable!("offer or ask asset is always specified"),
+    };
+
+    if offer_ind == ask_ind {
+        return Err(ContractError::SameAssets {});
+    }
+
+    Ok((offer_ind, ask_ind))
+}
+
+/// Verifies provided assets and returns their amounts ordered the same way as the pool assets.
+pub fn get_amounts_from_assets(
+    config: &Config,
+    assets: &[Asset],
+) -> Result<[Uint128; 2], ContractError> {
+    if !assets.iter().map(|asset| &asset.info).all_unique() {
+        return Err(ContractError::DoublingAssets {});
+    }
+
+    let mut amounts = [Uint128::zero(); 2];
+    for asset in assets {
+        amounts[asset_position(config, &asset.info)?] = asset.amount;
+    }
+
+    if amounts.iter().all(Uint128::is_zero) {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+
+    Ok(amounts)
+}
+
+/// Returns an error if any of the withdrawn assets is less than the specified minimum.
+pub fn ensure_min_assets_to_receive(
+    withdrawn_assets: &[Asset],
+    min_assets_to_receive: Option<Vec<Asset>>,
+) -> Result<(), ContractError> {
+    for min_asset in min_assets_to_receive.unwrap_or_default() {
+        let withdrawn = withdrawn_assets
+            .iter()
+            .find(|asset| asset.info.equal(&min_asset.info))
+            .ok_or_else(|| ContractError::InvalidAsset(min_asset.info.to_string()))?;
+
+        if withdrawn.amount < min_asset.amount {
+            return Err(ContractError::WithdrawSlippageViolation {
+                asset_name: withdrawn.info.to_string(),
+                received: withdrawn.amount,
+                expected: min_asset.amount,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// If `belief_price` and `max_spread` are both specified, we compute a new spread,
+/// otherwise we just use the swap spread to check `max_spread`.
+///
+/// * **belief_price** belief price used in the swap.
+///
+/// * **max_spread** max spread allowed so that the swap can be executed successfuly.
+///
+/// * **offer_amount** amount of assets to swap.
+///
+/// * **return_amount** amount of assets to receive from the swap.
+///
+/// * **spread_amount** spread used in the swap.
+pub fn assert_max_spread(
+    belief_price: Option<Decimal>,
+    max_spread: Option<Decimal>,
+    offer_amount: Uint128,
+    return_amount: Uint128,
+    spread_amount: Uint128,
+) -> Result<(), ContractError> {
+    let default_spread = Decimal::from_str(DEFAULT_SLIPPAGE)?;
+    let max_allowed_spread = Decimal::from_str(MAX_ALLOWED_SLIPPAGE)?;
+
+    let max_spread = max_spread.unwrap_or(default_spread);
+    if max_spread.gt(&max_allowed_spread) {
+        return Err(ContractError::AllowedSpreadAssertion {});
+    }
+
+    if let Some(belief_price) = belief_price {
+        let expected_return = offer_amount.dec_mul(
+            belief_price
+                .inv()
+                .ok_or_else(|| StdError::generic_err("Belief price must not be zero!"))?,
+        );
+        let spread_amount = expected_return.saturating_sub(return_amount);
+
+        if return_amount < expected_return
+            && Decimal::from_ratio(spread_amount, expected_return) > max_spread
+        {
+            return Err(ContractError::MaxSpreadAssertion {});
+        }
+    } else if Decimal::from_ratio(spread_amount, return_amount + spread_amount) > max_spread {
+        return Err(ContractError::MaxSpreadAssertion {});
+    }
+
+    Ok(())
+}
@@ -0,0 +1,557 @@
+#![allow(clippy::inconsistent_digit_grouping)]
+
+use cosmwasm_std::{to_json_binary, Decimal, Decimal256, Int128, StdError, Uint128};
+use cw_multi_test::Executor;
+
+use astroport::asset::{native_asset_info, AssetInfoExt};
+use astroport::factory::PauseScope;
+use astroport::router::SwapOperation;
+use astroport_pair_clmm::error::ContractError;
+use astroport_test::coins::TestCoin;
+
+use crate::helper::{clmm_params, Helper};
+
+mod helper;
+
+/// Creates a LUNA/USD pair with the price of 1 LUNA = 1 USD and the tick spacing of 10.
+fn setup(test_coins: &[TestCoin]) -> Helper {
+    Helper::new(test_coins.to_vec(), clmm_params(10, Decimal::one())).unwrap()
+}
+
+#[test]
+fn check_wrong_initialization() {
+    let test_coins = vec![TestCoin::native("uluna"), TestCoin::native("uusd")];
+
+    let err = Helper::new(test_coins.clone(), clmm_params(0, Decimal::one())).unwrap_err();
+    assert_eq!(
+        ContractError::InvalidTickSpacing {},
+        err.downcast().unwrap()
+    );
+
+    let err = Helper::new(
+        test_coins.clone(),
+        clmm_params(10, Decimal::from_ratio(1u8, 10u128.pow(13))),
+    )
+    .unwrap_err();
+    assert_eq!(ContractError::PriceOutOfRange {}, err.downcast().unwrap());
+
+    let err = Helper::new(
+        vec![
+            TestCoin::native("uluna"),
+            TestCoin::native("uusd"),
+            TestCoin::native("uusdc"),
+        ],
+        clmm_params(10, Decimal::one()),
+    )
+    .unwrap_err();
+    assert_eq!(
+        ContractError::InvalidNumberOfAssets {},
+        err.downcast().unwrap()
+    );
+
+    let helper = setup(&test_coins);
+    let state = helper.query_pool_state().unwrap();
+    assert_eq!(state.sqrt_price, Decimal256::one());
+    assert_eq!(state.tick, 0);
+    assert_eq!(state.liquidity, Uint128::zero());
+
+    // Positions are issued by the pair itself
+    let pair_info: astroport::asset::PairInfo = helper
+        .app
+        .wrap()
+        .query_wasm_smart(&helper.pair_addr, &astroport::pair_clmm::QueryMsg::Pair {})
+        .unwrap();
+    assert_eq!(pair_info.liquidity_token, helper.pair_addr.to_string());
+}
+
+#[test]
+fn check_positions() {
+    let test_coins = vec![TestCoin::native("uluna"), TestCoin::native("uusd")];
+    let mut helper = setup(&test_coins);
+
+    let owner = helper.owner.clone();
+    let assets = vec![
+        helper.assets[&test_coins[0]].with_balance(1_000_000000u128),
+        helper.assets[&test_coins[1]].with_balance(1_000_000000u128),
+    ];
+
+    // Ticks must be divisible by the tick spacing
+    let err = helper
+        .add_liquidity(&owner, -1005, 1000, &assets)
+        .unwrap_err();
+    assert_eq!(
+        ContractError::InvalidTickRange { tick_spacing: 10 },
+        err.downcast().unwrap()
+    );
+    let err = helper
+        .add_liquidity(&owner, 1000, 1000, &assets)
+        .unwrap_err();
+    assert_eq!(
+        ContractError::InvalidTickRange { tick_spacing: 10 },
+        err.downcast().unwrap()
+    );
+
+    let luna_before = helper.coin_balance(&test_coins[0], &owner);
+    helper.add_liquidity(&owner, -1000, 1000, &assets).unwrap();
+    assert_eq!(helper.query_tokens(&owner).unwrap(), vec!["1".to_string()]);
+
+    // The price is in the middle of the range so both assets are deposited in equal amounts
+    let position = helper.query_position("1").unwrap();
+    assert_eq!(position.owner, owner);
+    assert_eq!((position.tick_lower, position.tick_upper), (-1000, 1000));
+    for asset in &position.assets {
+        assert!(
+            asset.amount.u128().abs_diff(1_000_000000) <= 2,
+            "{}",
+            asset.amount
+        );
+    }
+    assert!(luna_before - helper.coin_balance(&test_coins[0], &owner) <= 1_000_000000);
+
+    let ticks = helper.query_ticks().unwrap();
+    assert_eq!(ticks.len(), 2);
+    let liquidity = position.liquidity.u128() as i128;
+    assert_eq!(ticks[0].tick, -1000);
+    assert_eq!(ticks[0].liquidity_net, Int128::new(liquidity));
+    assert_eq!(ticks[1].tick, 1000);
+    assert_eq!(ticks[1].liquidity_net, Int128::new(-liquidity));
+    assert_eq!(
+        helper.query_pool_state().unwrap().liquidity,
+        position.liquidity
+    );
+
+    // Adding liquidity to the same range tops up the existing position
+    helper.add_liquidity(&owner, -1000, 1000, &assets).unwrap();
+    assert_eq!(helper.query_num_tokens().unwrap(), 1);
+    let topped_up = helper.query_position("1").unwrap();
+    assert!(
+        topped_up
+            .liquidity
+            .u128()
+            .abs_diff(2 * position.liquidity.u128())
+            <= 1
+    );
+
+    // A range above the current price holds only the first asset
+    helper
+        .add_liquidity(
+            &owner,
+            100,
+            200,
+            &[helper.assets[&test_coins[0]].with_balance(1_000000u128)],
+        )
+        .unwrap();
+    let position = helper.query_position("2").unwrap();
+    assert_eq!(position.assets[1].amount, Uint128::zero());
+    assert!(position.assets[0].amount.u128().abs_diff(1_000000) <= 2);
+    // The pool liquidity doesn't change as the price is out of the range
+    assert_eq!(
+        helper.query_pool_state().unwrap().liquidity,
+        topped_up.liquidity
+    );
+
+    let err = helper
+        .add_liquidity_full(
+            &owner,
+            100,
+            200,
+            &[helper.assets[&test_coins[0]].with_balance(1_000000u128)],
+            Some(Uint128::MAX),
+            None,
+        )
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast().unwrap(),
+        ContractError::ProvideSlippageViolation(_, _)
+    ));
+
+    // Liquidity above i128::MAX can't be tracked in tick liquidity deltas
+    let err = helper
+        .add_liquidity(
+            &owner,
+            10,
+            20,
+            &[helper.assets[&test_coins[0]].with_balance(10u128.pow(35))],
+        )
+        .unwrap_err();
+    assert_eq!(ContractError::LiquidityOverflow {}, err.downcast().unwrap());
+
+    // Only the owner can manage a position
+    let user = helper.app.api().addr_make("user");
+    let err = helper.remove_liquidity(&user, "1", None).unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+    let err = helper.collect_fees(&user, "1").unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+
+    // Partial removal
+    let half = topped_up.liquidity.u128() / 2;
+    let usd_before = helper.coin_balance(&test_coins[1], &owner);
+    helper.remove_liquidity(&owner, "1", Some(half)).unwrap();
+    let usd_received = helper.coin_balance(&test_coins[1], &owner) - usd_before;
+    assert!(usd_received.abs_diff(1_000_000000) <= 2, "{usd_received}");
+    assert_eq!(
+        helper.query_position("1").unwrap().liquidity.u128(),
+        topped_up.liquidity.u128() - half
+    );
+
+    // Full removal burns the NFT and clears the ticks
+    helper.remove_liquidity(&owner, "1", None).unwrap();
+    let err = helper.query_position("1").unwrap_err();
+    assert_eq!(
+        StdError::generic_err("Querier contract error: Generic error: Position 1 not found"),
+        err
+    );
+    helper.remove_liquidity(&owner, "2", None).unwrap();
+    assert_eq!(helper.query_num_tokens().unwrap(), 0);
+    assert!(helper.query_ticks().unwrap().is_empty());
+    assert_eq!(
+        helper.query_pool_state().unwrap().liquidity,
+        Uint128::zero()
+    );
+}
+
+#[test]
+fn check_position_transfer() {
+    let test_coins = vec![TestCoin::native("uluna"), TestCoin::native("uusd")];
+    let mut helper = setup(&test_coins);
+
+    let owner = helper.owner.clone();
+    let user = helper.app.api().addr_make("user");
+    let assets = vec![
+        helper.assets[&test_coins[0]].with_balance(1_000_000000u128),
+        helper.assets[&test_coins[1]].with_balance(1_000_000000u128),
+    ];
+    helper.add_liquidity(&owner, -1000, 1000, &assets).unwrap();
+
+    let err = helper.transfer_nft(&user, &user, "1").unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+
+    helper.transfer_nft(&owner, &user, "1").unwrap();
+    assert_eq!(helper.query_owner_of("1").unwrap().owner, user.to_string());
+    assert_eq!(helper.query_tokens(&user).unwrap(), vec!["1".to_string()]);
+    assert!(helper.query_tokens(&owner).unwrap().is_empty());
+
+    // The previous owner can't manage the position anymore
+    let err = helper.remove_liquidity(&owner, "1", None).unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+
+    // The recipient can't hold two positions in the same range
+    helper.add_liquidity(&owner, -1000, 1000, &assets).unwrap();
+    let err = helper.transfer_nft(&owner, &user, "2").unwrap_err();
+    assert_eq!(ContractError::PositionExists {}, err.downcast().unwrap());
+
+    let luna_before = helper.coin_balance(&test_coins[0], &user);
+    helper.remove_liquidity(&user, "1", None).unwrap();
+    assert!(helper.coin_balance(&test_coins[0], &user) - luna_before > 999_000000);
+}
+
+#[test]
+fn check_swaps_and_fees() {
+    let test_coins = vec![TestCoin::native("uluna"), TestCoin::native("uusd")];
+    let mut helper = setup(&test_coins);
+
+    let owner = helper.owner.clone();
+    let lp = helper.app.api().addr_make("lp");
+    let assets = vec![
+        helper.assets[&test_coins[0]].with_balance(1_000_000000u128),
+        helper.assets[&test_coins[1]].with_balance(1_000_000000u128),
+    ];
+    helper.add_liquidity(&owner, -1000, 1000, &assets).unwrap();
+    helper.give_me_money(&assets, &lp);
+    helper.add_liquidity(&lp, -100, 100, &assets).unwrap();
+
+    let wide = helper.query_position("1").unwrap();
+    let narrow = helper.query_position("2").unwrap();
+    // The narrow position has about 10 times more liquidity for the same deposit
+    assert!(narrow.liquidity > wide.liquidity * Uint128::new(9));
+
+    let user = helper.app.api().addr_make("user");
+    let offer_asset = helper.assets[&test_coins[1]].with_balance(10_000000u128);
+    helper.give_me_money(std::slice::from_ref(&offer_asset), &user);
+
+    let sim = helper.simulate_swap(&offer_asset, None).unwrap();
+    // 0.3% fee and a small price impact
+    assert_eq!(sim.commission_amount.u128(), 30000);
+    assert!(sim.return_amount.u128() > 9_960000, "{}", sim.return_amount);
+    assert!(sim.return_amount.u128() < 9_970000, "{}", sim.return_amount);
+
+    let resp = helper.swap(&user, &offer_asset, None).unwrap();
+    assert_eq!(
+        helper.coin_balance(&test_coins[0], &user),
+        sim.return_amount.u128()
+    );
+    let event = resp
+        .events
+        .iter()
+        .find(|event| event.ty == "wasm-swap")
+        .unwrap();
+    let maker_fee = event
+        .attributes
+        .iter()
+        .find(|attr| attr.key == "maker_fee_amount")
+        .unwrap()
+        .value
+        .parse::<u128>()
+        .unwrap();
+    // Half of the fee is sent to the Maker in the offer asset
+    assert_eq!(maker_fee, 15000);
+    assert_eq!(
+        helper.coin_balance(&test_coins[1], &helper.fake_maker.clone()),
+        15000
+    );
+
+    // The price has moved up by less than a tick
+    let state = helper.query_pool_state().unwrap();
+    assert!(state.price > Decimal256::one());
+    assert_eq!(state.tick, 0);
+
+    // LPs share the rest of the fee proportionally to their liquidity
+    let wide = helper.query_position("1").unwrap();
+    let narrow = helper.query_position("2").unwrap();
+    assert_eq!(wide.fees[0].amount, Uint128::zero());
+    assert_eq!(narrow.fees[0].amount, Uint128::zero());
+    let total_fees = wide.fees[1].amount + narrow.fees[1].amount;
+    assert!(total_fees.u128().abs_diff(15000) <= 2, "{total_fees}");
+    assert_eq!(
+        wide.fees[1].amount.u128(),
+        15000 * wide.liquidity.u128() / (wide.liquidity + narrow.liquidity).u128()
+    );
+
+    let usd_before = helper.coin_balance(&test_coins[1], &lp);
+    helper.collect_fees(&lp, "2").unwrap();
+    assert_eq!(
+        helper.coin_balance(&test_coins[1], &lp) - usd_before,
+        narrow.fees[1].amount.u128()
+    );
+    assert_eq!(
+        helper.query_position("2").unwrap().fees[1].amount,
+        Uint128::zero()
+    );
+
+    // Swaps are paused by the factory as in any other pair
+    helper.pause(PauseScope::Swap).unwrap();
+    helper.give_me_money(std::slice::from_ref(&offer_asset), &user);
+    let err = helper.swap(&user, &offer_asset, None).unwrap_err();
+    assert_eq!(
+        ContractError::Paused(PauseScope::Swap),
+        err.downcast().unwrap()
+    );
+}
+
+#[test]
+fn check_crossing_ticks() {
+    let test_coins = vec![TestCoin::native("uluna"), TestCoin::native("uusd")];
+    let mut helper = setup(&test_coins);
+
+    let owner = helper.owner.clone();
+    let assets = vec![
+        helper.assets[&test_coins[0]].with_balance(1_000_000000u128),
+        helper.assets[&test_coins[1]].with_balance(1_000_000000u128),
+    ];
+    helper.add_liquidity(&owner, -100, 100, &assets).unwrap();
+    // Only LUNA is deposited above the current price
+    helper
+        .add_liquidity(&owner, 100, 1000, &assets[..1])
+        .unwrap();
+    let inner = helper.query_position("1").unwrap();
+    let outer = helper.query_position("2").unwrap();
+
+    // Buy all LUNA from the inner range and some more
+    let user = helper.app.api().addr_make("user");
+    let offer_asset = helper.assets[&test_coins[1]].with_balance(1_100_000000u128);
+    helper.give_me_money(std::slice::from_ref(&offer_asset), &user);
+    helper
+        .swap(&user, &offer_asset, Some(Decimal::percent(50)))
+        .unwrap();
+
+    let state = helper.query_pool_state().unwrap();
+    assert!(state.tick > 100 && state.tick < 1000, "{}", state.tick);
+    assert_eq!(state.liquidity, outer.liquidity);
+
+    // The inner position holds USD only, the outer one holds both assets
+    let inner_now = helper.query_position("1").unwrap();
+    assert_eq!(inner_now.assets[0].amount, Uint128::zero());
+    assert!(inner_now.assets[1].amount > inner.assets[1].amount);
+    let outer_now = helper.query_position("2").unwrap();
+    assert!(outer_now.assets[0].amount < outer.assets[0].amount);
+    assert!(!outer_now.assets[1].amount.is_zero());
+
+    // The inner position earned fees only while the price was in its range
+    assert!(!inner_now.fees[1].amount.is_zero());
+    assert!(!outer_now.fees[1].amount.is_zero());
+
+    // Swapping back crosses the tick in the opposite direction
+    let offer_asset = helper.assets[&test_coins[0]].with_balance(1_500_000000u128);
+    helper.give_me_money(std::slice::from_ref(&offer_asset), &user);
+    helper
+        .swap(&user, &offer_asset, Some(Decimal::percent(50)))
+        .unwrap();
+    let state = helper.query_pool_state().unwrap();
+    assert!(state.tick < 100, "{}", state.tick);
+    assert_eq!(state.liquidity, inner.liquidity);
+
+    // There is not enough liquidity to fill a swap beyond all positions
+    let offer_asset = helper.assets[&test_coins[0]].with_balance(10_000_000000u128);
+    let err = helper.simulate_swap(&offer_asset, None).unwrap_err();
+    assert_eq!(
+        StdError::generic_err(format!(
+            "Querier contract error: Generic error: {}",
+            ContractError::InsufficientLiquidity {}
+        )),
+        err
+    );
+
+    // All positions can be withdrawn after the swaps
+    helper.remove_liquidity(&owner, "1", None).unwrap();
+    helper.remove_liquidity(&owner, "2", None).unwrap();
+    let pool = helper.query_pool().unwrap();
+    // Only rounding dust stays in the pool
+    for asset in pool.assets {
+        assert!(asset.amount.u128() < 10, "{asset}");
+    }
+}
+
+#[test]
+fn check_reverse_simulation() {
+    let test_coins = vec![TestCoin::native("uluna"), TestCoin::native("uusd")];
+    let mut helper = setup(&test_coins);
+
+    let owner = helper.owner.clone();
+    let assets = vec![
+        helper.assets[&test_coins[0]].with_balance(1_000_000000u128),
+        helper.assets[&test_coins[1]].with_balance(1_000_000000u128),
+    ];
+    helper.add_liquidity(&owner, -1000, 1000, &assets).unwrap();
+
+    let ask_asset = helper.assets[&test_coins[1]].with_balance(50_000000u128);
+    let reverse = helper.simulate_reverse_swap(&ask_asset, None).unwrap();
+    assert!(reverse.offer_amount > ask_asset.amount);
+
+    let offer_asset = helper.assets[&test_coins[0]].with_balance(reverse.offer_amount);
+    let sim = helper.simulate_swap(&offer_asset, None).unwrap();
+    assert!(sim.return_amount >= ask_asset.amount);
+    assert!(sim.return_amount.u128() - ask_asset.amount.u128() <= 2);
+}
+
+#[test]
+fn check_cw20_asset() {
+    let test_coins = vec![TestCoin::cw20("tokenx"), TestCoin::native("uusd")];
+    let mut helper = setup(&test_coins);
+
+    let owner = helper.owner.clone();
+    let assets = vec![
+        helper.assets[&test_coins[0]].with_balance(1_000_000000u128),
+        helper.assets[&test_coins[1]].with_balance(1_000_000000u128),
+    ];
+    helper.add_liquidity(&owner, -1000, 1000, &assets).unwrap();
+    let position = helper.query_position("1").unwrap();
+    assert_eq!(
+        helper.coin_balance(&test_coins[0], &helper.pair_addr.clone()),
+        position.assets[0].amount.u128() + 1
+    );
+
+    let user = helper.app.api().addr_make("user");
+    let offer_asset = helper.assets[&test_coins[0]].with_balance(1_000000u128);
+    helper.give_me_money(std::slice::from_ref(&offer_asset), &user);
+    let sim = helper.simulate_swap(&offer_asset, None).unwrap();
+    helper.swap(&user, &offer_asset, None).unwrap();
+    assert_eq!(
+        helper.coin_balance(&test_coins[1], &user),
+        sim.return_amount.u128()
+    );
+
+    // CW20 tokens can't be swapped directly
+    let err = helper
+        .app
+        .execute_contract(
+            user.clone(),
+            helper.pair_addr.clone(),
+            &astroport::pair_clmm::ExecuteMsg::Swap {
+                offer_asset,
+                ask_asset_info: None,
+                belief_price: None,
+                max_spread: None,
+                to: None,
+                deadline: None,
+            },
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(ContractError::Cw20DirectSwap {}, err.downcast().unwrap());
+}
+
+#[test]
+fn check_router_swap() {
+    let test_coins = vec![TestCoin::native("uluna"), TestCoin::native("uusd")];
+    let mut helper = setup(&test_coins);
+
+    let owner = helper.owner.clone();
+    let assets = vec![
+        helper.assets[&test_coins[0]].with_balance(1_000_000000u128),
+        helper.assets[&test_coins[1]].with_balance(1_000_000000u128),
+    ];
+    helper.add_liquidity(&owner, -1000, 1000, &assets).unwrap();
+
+    let user = helper.app.api().addr_make("user");
+    let offer_asset = helper.assets[&test_coins[0]].with_balance(1_000000u128);
+    helper.give_me_money(std::slice::from_ref(&offer_asset), &user);
+    let sim = helper.simulate_swap(&offer_asset, None).unwrap();
+
+    let operations = vec![SwapOperation {
+        pair_address: helper.pair_addr.to_string(),
+        offer_asset_info: native_asset_info("uluna".to_string()),
+        ask_asset_info: native_asset_info("uusd".to_string()),
+    }];
+    let simulated: astroport::router::SimulateSwapOperationsResponse = helper
+        .app
+        .wrap()
+        .query_wasm_smart(
+            &helper.router,
+            &astroport::router::QueryMsg::SimulateSwapOperations {
+                offer_amount: offer_asset.amount,
+                operations: operations.clone(),
+            },
+        )
+        .unwrap();
+    assert_eq!(simulated.amount, sim.return_amount);
+
+    helper
+        .app
+        .execute_contract(
+            user.clone(),
+            helper.router.clone(),
+            &astroport::router::ExecuteMsg::ExecuteSwapOperations {
+                operations,
+                minimum_receive: Some(sim.return_amount),
+                to: None,
+                max_spread: None,
+                deadline: None,
+            },
+            &[cosmwasm_std::coin(1_000000, "uluna")],
+        )
+        .unwrap();
+    assert_eq!(
+        helper.coin_balance(&test_coins[1], &user),
+        sim.return_amount.u128()
+    );
+
+    // The pair is also registered in the factory
+    let pair_info: astroport::asset::PairInfo = helper
+        .app
+        .wrap()
+        .query_wasm_smart(
+            &helper.factory,
+            &astroport::factory::QueryMsg::PairsByAssetInfos {
+                asset_infos: vec![
+                    native_asset_info("uluna".to_string()),
+                    native_asset_info("uusd".to_string()),
+                ],
+                start_after: None,
+                limit: None,
+            },
+        )
+        .map(|pairs: Vec<astroport::asset::PairInfo>| pairs[0].clone())
+        .unwrap();
+    assert_eq!(pair_info.pair_type, astroport::factory::PairType::Clmm {});
+    let _ = to_json_binary(&pair_info).unwrap();
+}
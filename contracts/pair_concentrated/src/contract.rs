@@ -3,13 +3,13 @@ use std::vec;
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    attr, ensure, ensure_eq, from_json, wasm_execute, wasm_instantiate, Addr, Binary, CosmosMsg,
-    Decimal, Decimal256, DepsMut, Empty, Env, Event, MessageInfo, Reply, Response, StdError,
-    StdResult, SubMsg, SubMsgResponse, SubMsgResult, Uint128,
+    attr, coin, ensure, ensure_eq, from_json, wasm_execute, wasm_instantiate, Addr, Binary,
+    CosmosMsg, Decimal, Decimal256, DepsMut, Empty, Env, Event, MessageInfo, Reply, Response,
+    StdError, StdResult, SubMsg, SubMsgResponse, SubMsgResult, Uint128, WasmMsg,
 };
 use cw2::{get_contract_version, set_contract_version};
 use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg, MinterResponse};
-use cw_utils::parse_instantiate_response_data;
+use cw_utils::{must_pay, parse_instantiate_response_data};
 use itertools::Itertools;
 
 use astroport::asset::{
@@ -18,27 +18,40 @@ use astroport::asset::{
 use astroport::asset::{format_lp_token_name, AssetInfoExt};
 use astroport::common::{claim_ownership, drop_ownership_proposal, propose_new_owner};
 use astroport::cosmwasm_ext::{DecimalToInteger, IntegerToDecimal};
+use astroport::factory::PauseScope;
 use astroport::pair::{
-    Cw20HookMsg, ExecuteMsg, FeeShareConfig, InstantiateMsg, INSTANTIATE_TOKEN_REPLY_ID,
-    MAX_FEE_SHARE_BPS, MIN_TRADE_SIZE,
+    is_deadline_exceeded, Cw20HookMsg, ExecuteMsg, FeeShareConfig, InstantiateMsg, PairHookMsg,
+    TraderAllowlist, FLASH_SWAP_REPLY_ID, HOOK_REPLY_ID, INSTANTIATE_TOKEN_REPLY_ID,
+    MAX_FEE_SHARE_BPS, MIN_TRADE_SIZE, PRICE_DEVIATION_LIMITS,
 };
 use astroport::pair_concentrated::{
     ConcentratedPoolParams, ConcentratedPoolUpdateParams, UpdatePoolParams,
 };
-use astroport::querier::{query_factory_config, query_fee_info, query_supply};
+use astroport::querier::{
+    ensure_not_paused, query_factory_config, query_fee_info, query_lp_supply,
+};
+use astroport::stats::AssetStats;
+use astroport::token_factory::{tf_burn_msg, tf_create_denom_msg, tf_denom, LP_SUBDENOM};
+use astroport_pcl_common::error::PclError;
 use astroport_pcl_common::state::{
     AmpGamma, Config, PoolParams, PoolState, Precisions, PriceState,
 };
 use astroport_pcl_common::utils::{
     accumulate_prices, assert_max_spread, before_swap_check, calc_last_prices, check_asset_infos,
-    check_cw20_in_pool, compute_swap, get_share_in_assets, mint_liquidity_token_message,
+    check_cw20_in_pool, compute_offer_amount, compute_swap, get_share_in_assets,
+    mint_liquidity_token_message,
 };
 use astroport_pcl_common::{calc_d, get_xcp};
 
 use crate::error::ContractError;
-use crate::state::{CONFIG, OWNERSHIP_PROPOSAL};
+use crate::migration::migrate_config_from_v410;
+use crate::state::{CONFIG, FLASH_SWAP, OWNERSHIP_PROPOSAL, RESERVES, STATS};
 use crate::utils::{
-    calculate_shares, ensure_min_assets_to_receive, get_assets_with_precision, query_pools,
+    accrued_maker_fee, calc_withdraw_one_asset, calculate_shares, check_fee_share_recipients,
+    check_hooks, check_trader_allowlist, ensure_min_assets_to_receive, ensure_price_deviation,
+    ensure_traders_allowed, get_assets_with_precision, mint_protocol_fee, query_pools,
+    query_reserves, save_observation, save_reserves, save_xcp_profit_last, select_pools,
+    to_internal_repr,
 };
 
 /// Contract name that is used for migration.
@@ -56,10 +69,6 @@ pub fn instantiate(
     _info: MessageInfo,
     msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
-    if msg.asset_infos.len() != 2 {
-        return Err(StdError::generic_err("asset_infos must contain exactly two elements").into());
-    }
-
     check_asset_infos(deps.api, &msg.asset_infos)?;
 
     let params: ConcentratedPoolParams = from_json(
@@ -67,7 +76,18 @@ pub fn instantiate(
             .ok_or(ContractError::InitParamsNotFound {})?,
     )?;
 
-    if params.price_scale.is_zero() {
+    if params.extra_price_scales.len() != msg.asset_infos.len() - 2 {
+        return Err(StdError::generic_err(
+            "extra_price_scales must contain a price scale for each asset after the second one",
+        )
+        .into());
+    }
+
+    let price_scales = std::iter::once(params.price_scale)
+        .chain(params.extra_price_scales.iter().copied())
+        .map(Decimal256::from)
+        .collect_vec();
+    if price_scales.iter().any(Decimal256::is_zero) {
         return Err(StdError::generic_err("Initial price scale can not be zero").into());
     }
 
@@ -77,19 +97,14 @@ pub fn instantiate(
 
     Precisions::store_precisions(deps.branch(), &msg.asset_infos, &factory_addr)?;
 
-    // Initializing cumulative prices
-    let cumulative_prices = vec![
-        (
-            msg.asset_infos[0].clone(),
-            msg.asset_infos[1].clone(),
-            Uint128::zero(),
-        ),
-        (
-            msg.asset_infos[1].clone(),
-            msg.asset_infos[0].clone(),
-            Uint128::zero(),
-        ),
-    ];
+    // Initializing cumulative prices for each pair of assets
+    let cumulative_prices = msg
+        .asset_infos
+        .iter()
+        .cartesian_product(&msg.asset_infos)
+        .filter(|(from, to)| from != to)
+        .map(|(from, to)| (from.clone(), to.clone(), Uint128::zero()))
+        .collect();
 
     let mut pool_params = PoolParams::default();
     pool_params.update_params(UpdatePoolParams {
@@ -106,20 +121,19 @@ pub fn instantiate(
         future: AmpGamma::new(params.amp, params.gamma)?,
         future_time: env.block.time.seconds(),
         initial_time: 0,
-        price_state: PriceState {
-            oracle_price: params.price_scale.into(),
-            last_price: params.price_scale.into(),
-            price_scale: params.price_scale.into(),
-            last_price_update: env.block.time.seconds(),
-            xcp_profit: Decimal256::zero(),
-            xcp_profit_real: Decimal256::zero(),
-        },
+        price_state: PriceState::new(&price_scales, env.block.time.seconds()),
+    };
+
+    let liquidity_token = if msg.native_lp_token {
+        tf_denom(&env.contract.address, LP_SUBDENOM)
+    } else {
+        "".to_owned()
     };
 
     let config = Config {
         pair_info: PairInfo {
             contract_addr: env.contract.address.clone(),
-            liquidity_token: "".to_owned(),
+            liquidity_token,
             asset_infos: msg.asset_infos.clone(),
             pair_type: msg.pair_type,
         },
@@ -130,9 +144,30 @@ pub fn instantiate(
         pool_state,
         owner: None,
         fee_share: None,
+        native_lp_token: msg.native_lp_token,
+        protocol_fee_accrual: false,
+        hooks: vec![],
+        block_open_prices: vec![],
+        max_price_deviation: None,
+        trader_allowlist: None,
     };
 
     CONFIG.save(deps.storage, &config)?;
+    RESERVES.save(
+        deps.storage,
+        &vec![Uint128::zero(); config.pair_info.asset_infos.len()],
+    )?;
+
+    // The LP denom is known upfront so there is no need to wait for a reply
+    if msg.native_lp_token {
+        let event = Event::new("instantiate")
+            .add_attribute("action", "instantiate")
+            .add_attribute("liquidity_token", config.pair_info.liquidity_token);
+
+        return Ok(Response::new()
+            .add_message(tf_create_denom_msg(&env.contract.address, LP_SUBDENOM))
+            .add_event(event));
+    }
 
     let sub_msg = SubMsg::reply_on_success(
         wasm_instantiate(
@@ -161,7 +196,7 @@ pub fn instantiate(
 
 /// The entry point to the contract for processing replies from submessages.
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
+pub fn reply(deps: DepsMut, env: Env, msg: Reply) -> Result<Response, ContractError> {
     match msg {
         #[allow(deprecated)]
         Reply {
@@ -183,6 +218,21 @@ pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractE
 
             Ok(Response::new().add_event(event))
         }
+        Reply {
+            id: FLASH_SWAP_REPLY_ID,
+            ..
+        } => {
+            let ask_asset = FLASH_SWAP.load(deps.storage)?;
+            FLASH_SWAP.remove(deps.storage);
+
+            verify_flash_swap(deps, env, ask_asset)
+        }
+        // Failures of non-blocking hooks are ignored
+        Reply {
+            id: HOOK_REPLY_ID,
+            result: SubMsgResult::Err(err),
+            ..
+        } => Ok(Response::new().add_attribute("hook_error", err)),
         _ => Err(StdError::generic_err("Invalid reply".to_string()).into()),
     }
 }
@@ -200,6 +250,7 @@ pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractE
 ///             slippage_tolerance,
 ///             auto_stake,
 ///             receiver,
+///             deadline,
 ///         }** Provides liquidity in the pair with the specified input parameters.
 ///
 /// * **ExecuteMsg::Swap {
@@ -207,7 +258,31 @@ pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractE
 ///             belief_price,
 ///             max_spread,
 ///             to,
+///             deadline,
 ///         }** Performs a swap operation with the specified parameters.
+///
+/// * **ExecuteMsg::SwapExactOut {
+///             ask_asset,
+///             max_offer_amount,
+///             to,
+///         }** Swaps the offer asset for the exact amount of the ask asset refunding unused funds.
+///
+/// * **ExecuteMsg::FlashSwap { ask_asset, callback_msg }** Lends the ask asset to the sender
+/// and executes the callback on the sender. The borrowed amount plus fees must be returned
+/// by the end of the callback.
+///
+/// * **ExecuteMsg::WithdrawLiquidity { min_assets_to_receive, asset }** Withdraws liquidity from the pool
+/// by burning the LP token denom sent along with the message. If **asset** is specified,
+/// all liquidity is withdrawn in that asset.
+///
+/// Swap and liquidity messages are rejected with [`ContractError::DeadlineExceeded`]
+/// once the block time is past their optional deadline.
+///
+/// * **ExecuteMsg::Skim { to }** Sends the pair balances exceeding the tracked reserves to the recipient.
+///
+/// * **ExecuteMsg::Sync {}** Updates the tracked reserves to match the pair balances.
+///
+/// * **ExecuteMsg::CollectProtocolFees {}** Mints the accrued protocol fee as LP tokens to the fee address.
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
     deps: DepsMut,
@@ -215,6 +290,16 @@ pub fn execute(
     info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
+    // The pair is locked until the flash swap is settled
+    ensure!(
+        !FLASH_SWAP.exists(deps.storage),
+        ContractError::FlashSwapInProgress {}
+    );
+    ensure!(
+        !is_deadline_exceeded(&env.block, msg.deadline()),
+        ContractError::DeadlineExceeded {}
+    );
+
     let config = CONFIG.load(deps.storage)?;
 
     match msg {
@@ -225,6 +310,7 @@ pub fn execute(
             auto_stake,
             receiver,
             min_lp_to_receive,
+            ..
         } => provide_liquidity(
             deps,
             env,
@@ -237,6 +323,7 @@ pub fn execute(
         ),
         ExecuteMsg::Swap {
             offer_asset,
+            ask_asset_info,
             belief_price,
             max_spread,
             to,
@@ -259,11 +346,79 @@ pub fn execute(
                 env,
                 info.sender,
                 offer_asset,
+                ask_asset_info,
                 belief_price,
                 max_spread,
                 to_addr,
             )
         }
+        ExecuteMsg::SwapExactOut {
+            ask_asset,
+            max_offer_amount,
+            to,
+            ..
+        } => {
+            ask_asset.info.check(deps.api)?;
+            let offer_asset_info = if config.pair_info.asset_infos.len() == 2 {
+                let (offer_ind, _) = select_pools(&config, None, Some(&ask_asset.info))?;
+                config.pair_info.asset_infos[offer_ind].clone()
+            } else {
+                // The offer asset can't be derived from the ask one thus it is taken from the sent funds
+                match info.funds.as_slice() {
+                    [coin] => AssetInfo::NativeToken {
+                        denom: coin.denom.clone(),
+                    },
+                    _ => return Err(ContractError::VariableAssetMissed {}),
+                }
+            };
+            if !offer_asset_info.is_native_token() {
+                return Err(ContractError::Cw20DirectSwap {});
+            }
+
+            let offer_asset = offer_asset_info.with_balance(max_offer_amount);
+            offer_asset.assert_sent_native_token_balance(&info)?;
+
+            let to_addr = addr_opt_validate(deps.api, &to)?;
+
+            swap_exact_out(deps, env, info.sender, offer_asset, ask_asset, to_addr)
+        }
+        ExecuteMsg::FlashSwap {
+            ask_asset,
+            callback_msg,
+            ..
+        } => {
+            ask_asset.info.check(deps.api)?;
+            flash_swap(deps, info.sender, ask_asset, callback_msg)
+        }
+        ExecuteMsg::WithdrawLiquidity {
+            min_assets_to_receive,
+            asset,
+            ..
+        } => {
+            ensure!(config.native_lp_token, ContractError::NonSupported {});
+
+            let amount = must_pay(&info, &config.pair_info.liquidity_token)?;
+            let sender = info.sender.clone();
+
+            withdraw_liquidity(
+                deps,
+                env,
+                info,
+                min_assets_to_receive,
+                asset,
+                sender,
+                amount,
+            )
+        }
+        ExecuteMsg::Skim { to } => {
+            let to_addr = addr_opt_validate(deps.api, &to)?.unwrap_or(info.sender);
+            skim(deps, env, to_addr)
+        }
+        ExecuteMsg::Sync {} => sync(deps, env),
+        ExecuteMsg::CollectProtocolFees {} => collect_protocol_fees(deps, env),
+        ExecuteMsg::SettleBatch {} | ExecuteMsg::CancelBatchOrder { .. } => {
+            Err(ContractError::NonSupported {})
+        }
         ExecuteMsg::UpdateConfig { params } => update_config(deps, env, info, params),
         ExecuteMsg::ProposeNewOwner { owner, expires_in } => {
             let factory_config = query_factory_config(&deps.querier, config.factory_addr)?;
@@ -313,8 +468,15 @@ fn receive_cw20(
     info: MessageInfo,
     cw20_msg: Cw20ReceiveMsg,
 ) -> Result<Response, ContractError> {
-    match from_json(&cw20_msg.msg)? {
+    let hook_msg: Cw20HookMsg = from_json(&cw20_msg.msg)?;
+    ensure!(
+        !is_deadline_exceeded(&env.block, hook_msg.deadline()),
+        ContractError::DeadlineExceeded {}
+    );
+
+    match hook_msg {
         Cw20HookMsg::Swap {
+            ask_asset_info,
             belief_price,
             max_spread,
             to,
@@ -331,21 +493,50 @@ fn receive_cw20(
                 env,
                 Addr::unchecked(cw20_msg.sender),
                 token_asset(info.sender, cw20_msg.amount),
+                ask_asset_info,
                 belief_price,
                 max_spread,
                 to_addr,
             )
         }
+        Cw20HookMsg::SwapExactOut { ask_asset, to, .. } => {
+            let config = CONFIG.load(deps.storage)?;
+
+            // Only asset contract can execute this message
+            check_cw20_in_pool(&config, &info.sender)?;
+
+            let to_addr = addr_opt_validate(deps.api, &to)?;
+            swap_exact_out(
+                deps,
+                env,
+                Addr::unchecked(cw20_msg.sender),
+                token_asset(info.sender, cw20_msg.amount),
+                ask_asset,
+                to_addr,
+            )
+        }
         Cw20HookMsg::WithdrawLiquidity {
             min_assets_to_receive,
-        } => withdraw_liquidity(
-            deps,
-            env,
-            info,
-            min_assets_to_receive,
-            Addr::unchecked(cw20_msg.sender),
-            cw20_msg.amount,
-        ),
+            asset,
+            ..
+        } => {
+            let config = CONFIG.load(deps.storage)?;
+            ensure_eq!(
+                info.sender.to_string(),
+                config.pair_info.liquidity_token,
+                ContractError::Unauthorized {}
+            );
+
+            withdraw_liquidity(
+                deps,
+                env,
+                info,
+                min_assets_to_receive,
+                asset,
+                Addr::unchecked(cw20_msg.sender),
+                cw20_msg.amount,
+            )
+        }
     }
 }
 
@@ -375,15 +566,31 @@ pub fn provide_liquidity(
     min_lp_to_receive: Option<Uint128>,
 ) -> Result<Response, ContractError> {
     let mut config = CONFIG.load(deps.storage)?;
+    ensure_not_paused(
+        &deps.querier,
+        &config.factory_addr,
+        &config.pair_info.contract_addr,
+        PauseScope::Provide,
+        ContractError::Paused,
+    )?;
+
+    let receiver = addr_opt_validate(deps.api, &receiver)?.unwrap_or_else(|| info.sender.clone());
+    ensure_traders_allowed(deps.querier, &config, &[&info.sender, &receiver])?;
 
-    let total_share = query_supply(&deps.querier, &config.pair_info.liquidity_token)?
-        .to_decimal256(LP_TOKEN_PRECISION);
+    let total_share = query_lp_supply(
+        &deps.querier,
+        &config.pair_info.liquidity_token,
+        config.native_lp_token,
+    )?;
+    let (mut messages, protocol_share) =
+        mint_protocol_fee(deps.as_ref(), &env, &mut config, total_share)?;
+    let total_share = (total_share + protocol_share).to_decimal256(LP_TOKEN_PRECISION);
 
     let precisions = Precisions::new(deps.storage)?;
 
-    let mut pools = query_pools(deps.querier, &env.contract.address, &config, &precisions)?;
+    let pools = query_pools(deps.storage, &config, &precisions)?;
 
-    let old_real_price = config.pool_state.price_state.last_price;
+    let old_real_prices = config.pool_state.price_state.last_prices();
 
     let deposits = get_assets_with_precision(
         deps.as_ref(),
@@ -396,28 +603,19 @@ pub fn provide_liquidity(
     info.funds
         .assert_coins_properly_sent(&assets, &config.pair_info.asset_infos)?;
 
-    let mut messages = vec![];
-    for (i, pool) in pools.iter_mut().enumerate() {
+    for (i, pool) in pools.iter().enumerate() {
         // If the asset is a token contract, then we need to execute a TransferFrom msg to receive assets
-        match &pool.info {
-            AssetInfo::Token { contract_addr } => {
-                if !deposits[i].is_zero() {
-                    messages.push(CosmosMsg::Wasm(wasm_execute(
-                        contract_addr,
-                        &Cw20ExecuteMsg::TransferFrom {
-                            owner: info.sender.to_string(),
-                            recipient: env.contract.address.to_string(),
-                            amount: deposits[i]
-                                .to_uint(precisions.get_precision(&assets[i].info)?)?,
-                        },
-                        vec![],
-                    )?))
-                }
-            }
-            AssetInfo::NativeToken { .. } => {
-                // If the asset is native token, the pool balance is already increased
-                // To calculate the total amount of deposits properly, we should subtract the user deposit from the pool
-                pool.amount = pool.amount.checked_sub(deposits[i])?;
+        if let AssetInfo::Token { contract_addr } = &pool.info {
+            if !deposits[i].is_zero() {
+                messages.push(CosmosMsg::Wasm(wasm_execute(
+                    contract_addr,
+                    &Cw20ExecuteMsg::TransferFrom {
+                        owner: info.sender.to_string(),
+                        recipient: env.contract.address.to_string(),
+                        amount: deposits[i].to_uint(precisions.get_precision(&assets[i].info)?)?,
+                    },
+                    vec![],
+                )?))
             }
         }
     }
@@ -425,7 +623,7 @@ pub fn provide_liquidity(
     let (share_uint128, slippage) = calculate_shares(
         &env,
         &mut config,
-        &mut pools,
+        &pools,
         total_share,
         deposits.clone(),
         slippage_tolerance,
@@ -449,7 +647,6 @@ pub fn provide_liquidity(
     );
 
     // Mint LP tokens for the sender or for the receiver (if set)
-    let receiver = addr_opt_validate(deps.api, &receiver)?.unwrap_or_else(|| info.sender.clone());
     let auto_stake = auto_stake.unwrap_or(false);
     messages.extend(mint_liquidity_token_message(
         deps.querier,
@@ -460,20 +657,52 @@ pub fn provide_liquidity(
         auto_stake,
     )?);
 
-    accumulate_prices(&env, &mut config, old_real_price);
+    let new_xs = pools
+        .iter()
+        .zip(deposits)
+        .map(|(pool, deposit)| pool.amount + deposit)
+        .collect_vec();
+    let new_pools = pools
+        .iter()
+        .zip(&new_xs)
+        .map(|(pool, x)| {
+            let prec = precisions.get_precision(&pool.info)?;
+            Ok(pool.info.with_balance(x.to_uint(prec)?))
+        })
+        .collect::<Result<Vec<_>, ContractError>>()?;
+    save_reserves(deps.storage, &new_pools, env.block.height)?;
+
+    accumulate_prices(&env, &mut config, &old_real_prices);
 
     CONFIG.save(deps.storage, &config)?;
+    save_observation(deps.storage, &config)?;
+    save_xcp_profit_last(deps.storage, &config)?;
+
+    // An imbalanced deposit moves the price of all assets against the first one
+    if !total_share.is_zero() {
+        ensure_price_deviation(&env, &config, &new_xs, None, 0)?;
+    }
 
     let event = Event::new("provide_liquidity").add_attributes(vec![
         attr("action", "provide_liquidity"),
         attr("sender", info.sender),
-        attr("receiver", receiver),
-        attr("assets", format!("{}, {}", &assets[0], &assets[1])),
+        attr("receiver", &receiver),
+        attr("assets", assets.iter().join(", ")),
         attr("share", share_uint128),
         attr("slippage", slippage.to_string()),
     ]);
 
-    Ok(Response::new().add_messages(messages).add_event(event))
+    let hook_msgs = PairHookMsg::AfterProvide {
+        assets,
+        share: share_uint128,
+        receiver,
+    }
+    .into_submsgs(&config.hooks)?;
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_submessages(hook_msgs)
+        .add_event(event))
 }
 
 /// Withdraw liquidity from the pool.
@@ -481,62 +710,134 @@ pub fn provide_liquidity(
 /// * **sender** address that will receive assets back from the pair contract
 ///
 /// * **assets** defines number of coins a user wants to withdraw per each asset.
+///
+/// * **asset** is an optional asset to withdraw all liquidity in.
+///   The withdrawal is charged with the imbalance fee.
 pub fn withdraw_liquidity(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
     min_assets_to_receive: Option<Vec<Asset>>,
+    asset: Option<AssetInfo>,
     receiver: Addr,
     amount: Uint128,
 ) -> Result<Response, ContractError> {
     let mut config = CONFIG.load(deps.storage)?;
 
-    ensure_eq!(
-        info.sender.to_string(),
-        config.pair_info.liquidity_token,
-        ContractError::Unauthorized {}
-    );
-
     let precisions = Precisions::new(deps.storage)?;
-    let pools = query_pools(
-        deps.querier,
-        &config.pair_info.contract_addr,
-        &config,
-        &precisions,
+    let pools = query_pools(deps.storage, &config, &precisions)?;
+
+    let total_share = query_lp_supply(
+        &deps.querier,
+        &config.pair_info.liquidity_token,
+        config.native_lp_token,
     )?;
+    let (mut messages, protocol_share) =
+        mint_protocol_fee(deps.as_ref(), &env, &mut config, total_share)?;
+    let total_share = total_share + protocol_share;
 
-    let total_share = query_supply(&deps.querier, &config.pair_info.liquidity_token)?;
-    let mut messages = vec![];
+    let mut attrs = vec![];
+    let refund_assets = if let Some(asset_info) = asset {
+        let ask_ind = pools
+            .iter()
+            .position(|pool| pool.info == asset_info)
+            .ok_or_else(|| ContractError::InvalidAsset(asset_info.to_string()))?;
 
-    let refund_assets =
-        get_share_in_assets(&pools, amount.saturating_sub(Uint128::one()), total_share);
+        let old_real_prices = config.pool_state.price_state.last_prices();
+        let withdraw_result = calc_withdraw_one_asset(
+            &env,
+            &config,
+            &pools,
+            total_share.to_decimal256(LP_TOKEN_PRECISION),
+            amount
+                .saturating_sub(Uint128::one())
+                .to_decimal256(LP_TOKEN_PRECISION),
+            ask_ind,
+        )?;
+
+        let total_lp = (total_share - amount).to_decimal256(LP_TOKEN_PRECISION);
+        if let Some(last_prices) = withdraw_result.last_prices {
+            config.pool_state.update_price(
+                &config.pool_params,
+                &env,
+                total_lp,
+                &withdraw_result.new_xp,
+                &last_prices,
+            )?;
+        } else {
+            let amp_gamma = config.pool_state.get_amp_gamma(&env);
+            let d = calc_d(&withdraw_result.new_xp, &amp_gamma)?;
+            config.pool_state.price_state.xcp_profit_real =
+                get_xcp(d, &config.pool_state.price_state.price_scales()) / total_lp;
+        }
 
-    // decrease XCP
-    let mut xs = pools.iter().map(|a| a.amount).collect_vec();
+        accumulate_prices(&env, &mut config, &old_real_prices);
 
-    xs[0] -= refund_assets[0].amount;
-    xs[1] -= refund_assets[1].amount;
-    xs[1] *= config.pool_state.price_state.price_scale;
-    let amp_gamma = config.pool_state.get_amp_gamma(&env);
-    let d = calc_d(&xs, &amp_gamma)?;
-    config.pool_state.price_state.xcp_profit_real =
-        get_xcp(d, config.pool_state.price_state.price_scale)
-            / (total_share - amount).to_decimal256(LP_TOKEN_PRECISION);
-
-    let refund_assets = refund_assets
-        .into_iter()
-        .map(|asset| {
-            let prec = precisions.get_precision(&asset.info).unwrap();
+        let new_xs = pools
+            .iter()
+            .enumerate()
+            .map(|(ind, pool)| {
+                if ind == ask_ind {
+                    pool.amount - withdraw_result.amount
+                } else {
+                    pool.amount
+                }
+            })
+            .collect_vec();
+        ensure_price_deviation(&env, &config, &new_xs, None, ask_ind)?;
+
+        let prec = precisions.get_precision(&asset_info)?;
+        attrs.push(attr("fee", withdraw_result.fee.to_uint(prec)?));
+        vec![asset_info.with_balance(withdraw_result.amount.to_uint(prec)?)]
+    } else {
+        let refund_assets =
+            get_share_in_assets(&pools, amount.saturating_sub(Uint128::one()), total_share);
+
+        // decrease XCP
+        let price_scales = config.pool_state.price_state.price_scales();
+        let xs = pools
+            .iter()
+            .zip(&refund_assets)
+            .zip(&price_scales)
+            .map(|((pool, refund_asset), price_scale)| {
+                (pool.amount - refund_asset.amount) * price_scale
+            })
+            .collect_vec();
+        let amp_gamma = config.pool_state.get_amp_gamma(&env);
+        let d = calc_d(&xs, &amp_gamma)?;
+        config.pool_state.price_state.xcp_profit_real =
+            get_xcp(d, &price_scales) / (total_share - amount).to_decimal256(LP_TOKEN_PRECISION);
 
-            Ok(Asset {
-                info: asset.info,
-                amount: asset.amount.to_uint(prec)?,
+        refund_assets
+            .into_iter()
+            .map(|asset| {
+                let prec = precisions.get_precision(&asset.info).unwrap();
+
+                Ok(Asset {
+                    info: asset.info,
+                    amount: asset.amount.to_uint(prec)?,
+                })
             })
-        })
-        .collect::<StdResult<Vec<_>>>()?;
+            .collect::<StdResult<Vec<_>>>()?
+    };
 
     ensure_min_assets_to_receive(&config, refund_assets.clone(), min_assets_to_receive)?;
 
+    let new_pools = pools
+        .iter()
+        .map(|pool| {
+            let prec = precisions.get_precision(&pool.info)?;
+            let refund_amount = refund_assets
+                .iter()
+                .find(|refund_asset| refund_asset.info == pool.info)
+                .map(|refund_asset| refund_asset.amount)
+                .unwrap_or_default();
+            let amount = pool.amount.to_uint(prec)?.checked_sub(refund_amount)?;
+            Ok(pool.info.with_balance(amount))
+        })
+        .collect::<Result<Vec<_>, ContractError>>()?;
+    save_reserves(deps.storage, &new_pools, env.block.height)?;
+
     messages.extend(
         refund_assets
             .iter()
@@ -545,25 +846,49 @@ pub fn withdraw_liquidity(
             .collect::<StdResult<Vec<_>>>()?,
     );
 
-    messages.push(
-        wasm_execute(
-            &config.pair_info.liquidity_token,
-            &Cw20ExecuteMsg::Burn { amount },
-            vec![],
-        )?
-        .into(),
-    );
+    if config.native_lp_token {
+        messages.push(tf_burn_msg(
+            &env.contract.address,
+            coin(amount.into(), &config.pair_info.liquidity_token),
+        ));
+    } else {
+        messages.push(
+            wasm_execute(
+                &config.pair_info.liquidity_token,
+                &Cw20ExecuteMsg::Burn { amount },
+                vec![],
+            )?
+            .into(),
+        );
+    }
 
     CONFIG.save(deps.storage, &config)?;
+    if !attrs.is_empty() {
+        save_observation(deps.storage, &config)?;
+    }
+    save_xcp_profit_last(deps.storage, &config)?;
+
+    let event = Event::new("withdraw_liquidity")
+        .add_attributes(vec![
+            attr("action", "withdraw_liquidity"),
+            attr("sender", info.sender),
+            attr("withdrawn_share", amount),
+            attr("refund_assets", refund_assets.iter().join(", ")),
+            attr("receiver", receiver.to_string()),
+        ])
+        .add_attributes(attrs);
+
+    let hook_msgs = PairHookMsg::AfterWithdraw {
+        assets: refund_assets,
+        share: amount,
+        receiver,
+    }
+    .into_submsgs(&config.hooks)?;
 
-    let event = Event::new("withdraw_liquidity").add_attributes(vec![
-        attr("action", "withdraw_liquidity"),
-        attr("sender", info.sender),
-        attr("withdrawn_share", amount),
-        attr("refund_assets", refund_assets.iter().join(", ")),
-        attr("receiver", receiver.to_string()),
-    ]);
-    Ok(Response::new().add_messages(messages).add_event(event))
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_submessages(hook_msgs)
+        .add_event(event))
 }
 
 /// Performs an swap operation with the specified parameters. The trader must approve the
@@ -573,16 +898,20 @@ pub fn withdraw_liquidity(
 ///
 /// * **offer_asset** proposed asset for swapping.
 ///
+/// * **ask_asset_info** is the asset to swap to. Must be specified if the pool holds more than two assets.
+///
 /// * **belief_price** is used to calculate the maximum swap spread.
 ///
 /// * **max_spread** sets the maximum spread of the swap operation.
 ///
 /// * **to** sets the recipient of the swap operation.
+#[allow(clippy::too_many_arguments)]
 fn swap(
     deps: DepsMut,
     env: Env,
     sender: Addr,
     offer_asset: Asset,
+    ask_asset_info: Option<AssetInfo>,
     belief_price: Option<Decimal>,
     max_spread: Option<Decimal>,
     to: Option<Addr>,
@@ -591,42 +920,55 @@ fn swap(
     let offer_asset_prec = precisions.get_precision(&offer_asset.info)?;
     let offer_asset_dec = offer_asset.to_decimal_asset(offer_asset_prec);
     let mut config = CONFIG.load(deps.storage)?;
+    ensure_not_paused(
+        &deps.querier,
+        &config.factory_addr,
+        &config.pair_info.contract_addr,
+        PauseScope::Swap,
+        ContractError::Paused,
+    )?;
+    ensure_traders_allowed(
+        deps.querier,
+        &config,
+        &[&sender, to.as_ref().unwrap_or(&sender)],
+    )?;
 
-    let mut pools = query_pools(deps.querier, &env.contract.address, &config, &precisions)?;
+    let pools = query_pools(deps.storage, &config, &precisions)?;
 
-    let (offer_ind, _) = pools
-        .iter()
-        .find_position(|asset| asset.info == offer_asset_dec.info)
-        .ok_or_else(|| ContractError::InvalidAsset(offer_asset_dec.info.to_string()))?;
-    let ask_ind = 1 ^ offer_ind;
+    let (offer_ind, ask_ind) = select_pools(
+        &config,
+        Some(&offer_asset_dec.info),
+        ask_asset_info.as_ref(),
+    )?;
     let ask_asset_prec = precisions.get_precision(&pools[ask_ind].info)?;
 
-    pools[offer_ind].amount -= offer_asset_dec.amount;
-
     before_swap_check(&pools, offer_asset_dec.amount)?;
 
     let mut xs = pools.iter().map(|asset| asset.amount).collect_vec();
-    let old_real_price = calc_last_prices(&xs, &config, &env)?;
+    let old_real_prices = calc_last_prices(&xs, &config, &env)?;
 
     // Get fee info from the factory
     let fee_info = query_fee_info(
         &deps.querier,
         &config.factory_addr,
         config.pair_info.pair_type.clone(),
+        &config.pair_info.contract_addr,
     )?;
+    // The maker fee stays in the pool if it is minted later as LP tokens
     let mut maker_fee_share = Decimal256::zero();
-    if fee_info.fee_address.is_some() {
+    if fee_info.fee_address.is_some() && !config.protocol_fee_accrual {
         maker_fee_share = fee_info.maker_fee_rate.into();
     }
     // If this pool is configured to share fees
     let mut share_fee_share = Decimal256::zero();
     if let Some(fee_share) = config.fee_share.clone() {
-        share_fee_share = Decimal256::from_ratio(fee_share.bps, 10000u16);
+        share_fee_share = Decimal256::from_ratio(fee_share.total_bps(), 10000u16);
     }
 
     let swap_result = compute_swap(
         &xs,
         offer_asset_dec.amount,
+        offer_ind,
         ask_ind,
         &config,
         &env,
@@ -646,21 +988,34 @@ fn swap(
         spread_amount,
     )?;
 
-    let total_share = query_supply(&deps.querier, &config.pair_info.liquidity_token)?
-        .to_decimal256(LP_TOKEN_PRECISION);
+    let total_share = query_lp_supply(
+        &deps.querier,
+        &config.pair_info.liquidity_token,
+        config.native_lp_token,
+    )?
+    .to_decimal256(LP_TOKEN_PRECISION);
 
     // Skip very small trade sizes which could significantly mess up the price due to rounding errors,
     // especially if token precisions are 18.
     if (swap_result.dy + swap_result.maker_fee + swap_result.share_fee) >= MIN_TRADE_SIZE
         && offer_asset_dec.amount >= MIN_TRADE_SIZE
     {
-        let last_price = swap_result.calc_last_price(offer_asset_dec.amount, offer_ind);
+        let last_prices = swap_result.calc_last_prices(
+            offer_asset_dec.amount,
+            offer_ind,
+            ask_ind,
+            &config.pool_state.price_state.last_prices(),
+        );
 
         // update_price() works only with internal representation
-        xs[1] *= config.pool_state.price_state.price_scale;
-        config
-            .pool_state
-            .update_price(&config.pool_params, &env, total_share, &xs, last_price)?;
+        let ixs = to_internal_repr(&xs, &config);
+        config.pool_state.update_price(
+            &config.pool_params,
+            &env,
+            total_share,
+            &ixs,
+            &last_prices,
+        )?;
     }
 
     let receiver = to.unwrap_or_else(|| sender.clone());
@@ -676,11 +1031,17 @@ fn swap(
     if let Some(fee_share) = config.fee_share.clone() {
         fee_share_amount = swap_result.share_fee.to_uint(ask_asset_prec)?;
         if !fee_share_amount.is_zero() {
-            let fee = pools[ask_ind].info.with_balance(fee_share_amount);
-            messages.push(fee.into_msg(fee_share.recipient)?);
+            messages.extend(fee_share.into_msgs(&pools[ask_ind].info, fee_share_amount)?);
         }
     }
 
+    let accrued_fee = accrued_maker_fee(
+        &config,
+        &fee_info,
+        swap_result.total_fee - swap_result.share_fee,
+    )
+    .to_uint(ask_asset_prec)?;
+
     // Send the maker fee
     let mut maker_fee = Uint128::zero();
     if let Some(fee_address) = fee_info.fee_address {
@@ -691,9 +1052,39 @@ fn swap(
         }
     }
 
-    accumulate_prices(&env, &mut config, old_real_price);
+    let mut new_pools = pools
+        .iter()
+        .map(|pool| {
+            let prec = precisions.get_precision(&pool.info)?;
+            Ok(pool.info.with_balance(pool.amount.to_uint(prec)?))
+        })
+        .collect::<Result<Vec<_>, ContractError>>()?;
+    new_pools[offer_ind].amount = new_pools[offer_ind]
+        .amount
+        .checked_add(offer_asset.amount)?;
+    new_pools[ask_ind].amount = new_pools[ask_ind]
+        .amount
+        .checked_sub(return_amount + fee_share_amount + maker_fee)?;
+    save_reserves(deps.storage, &new_pools, env.block.height)?;
+    let commission_amount = swap_result.total_fee.to_uint(ask_asset_prec)?;
+    STATS.record(
+        deps.storage,
+        &config.pair_info.asset_infos,
+        env.block.time.seconds(),
+        &AssetStats::swap(
+            &offer_asset,
+            &pools[ask_ind].info.with_balance(return_amount),
+            commission_amount.checked_sub(maker_fee + accrued_fee + fee_share_amount)?,
+            maker_fee + accrued_fee,
+            fee_share_amount,
+        ),
+    )?;
+
+    accumulate_prices(&env, &mut config, &old_real_prices);
 
     CONFIG.save(deps.storage, &config)?;
+    save_observation(deps.storage, &config)?;
+    ensure_price_deviation(&env, &config, &xs, Some(offer_ind), ask_ind)?;
 
     let event = Event::new("swap")
         .add_attribute("action", "swap")
@@ -704,13 +1095,453 @@ fn swap(
         .add_attribute("offer_amount", offer_asset.amount.to_string())
         .add_attribute("return_amount", return_amount.to_string())
         .add_attribute("spread_amount", spread_amount.to_string())
+        .add_attribute("commission_amount", commission_amount.to_string())
+        .add_attribute("maker_fee_amount", maker_fee.to_string())
+        .add_attribute("fee_share_amount", fee_share_amount.to_string());
+
+    let hook_msgs = PairHookMsg::AfterSwap {
+        offer: offer_asset,
+        ask: pools[ask_ind].info.with_balance(return_amount),
+        trader: sender,
+    }
+    .into_submsgs(&config.hooks)?;
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_submessages(hook_msgs)
+        .add_event(event))
+}
+
+/// Performs a swap of the offer asset for the exact amount of the ask asset.
+/// The required offer amount is calculated with [`compute_offer_amount`] which assumes
+/// the maximum fee rate. Thus the swap might return more than requested.
+/// The surplus stays in the pool while unused offer funds are refunded to the sender.
+///
+/// * **sender** is the sender of the swap operation.
+///
+/// * **offer_asset** is the offer asset sent along with the message.
+///   Its amount is the maximum amount that can be spent on the swap.
+///
+/// * **ask_asset** is the asset and the exact amount to receive.
+///
+/// * **to** sets the recipient of the swap operation.
+fn swap_exact_out(
+    deps: DepsMut,
+    env: Env,
+    sender: Addr,
+    offer_asset: Asset,
+    ask_asset: Asset,
+    to: Option<Addr>,
+) -> Result<Response, ContractError> {
+    let precisions = Precisions::new(deps.storage)?;
+    let ask_asset_prec = precisions.get_precision(&ask_asset.info)?;
+    let ask_asset_dec = ask_asset.to_decimal_asset(ask_asset_prec);
+    let mut config = CONFIG.load(deps.storage)?;
+    ensure_not_paused(
+        &deps.querier,
+        &config.factory_addr,
+        &config.pair_info.contract_addr,
+        PauseScope::Swap,
+        ContractError::Paused,
+    )?;
+    ensure_traders_allowed(
+        deps.querier,
+        &config,
+        &[&sender, to.as_ref().unwrap_or(&sender)],
+    )?;
+
+    let pools = query_pools(deps.storage, &config, &precisions)?;
+
+    let (offer_ind, ask_ind) =
+        select_pools(&config, Some(&offer_asset.info), Some(&ask_asset.info))?;
+    let offer_asset_prec = precisions.get_precision(&offer_asset.info)?;
+
+    let mut xs = pools.iter().map(|asset| asset.amount).collect_vec();
+
+    let (offer_amount_dec, ..) =
+        compute_offer_amount(&xs, ask_asset_dec.amount, offer_ind, ask_ind, &config, &env)?;
+    // Round the offer amount up so the pool doesn't lose on rounding
+    let mut offer_amount = offer_amount_dec.to_uint(offer_asset_prec)?;
+    if offer_amount.to_decimal256(offer_asset_prec) < offer_amount_dec {
+        offer_amount += Uint128::one();
+    }
+    ensure!(
+        offer_amount <= offer_asset.amount,
+        ContractError::MaxOfferAmountAssertion(offer_amount, offer_asset.amount)
+    );
+    let offer_amount_dec = offer_amount.to_decimal256(offer_asset_prec);
+
+    before_swap_check(&pools, offer_amount_dec)?;
+
+    let old_real_prices = calc_last_prices(&xs, &config, &env)?;
+
+    // Get fee info from the factory
+    let fee_info = query_fee_info(
+        &deps.querier,
+        &config.factory_addr,
+        config.pair_info.pair_type.clone(),
+        &config.pair_info.contract_addr,
+    )?;
+    // The maker fee stays in the pool if it is minted later as LP tokens
+    let mut maker_fee_share = Decimal256::zero();
+    if fee_info.fee_address.is_some() && !config.protocol_fee_accrual {
+        maker_fee_share = fee_info.maker_fee_rate.into();
+    }
+    // If this pool is configured to share fees
+    let mut share_fee_share = Decimal256::zero();
+    if let Some(fee_share) = config.fee_share.clone() {
+        share_fee_share = Decimal256::from_ratio(fee_share.total_bps(), 10000u16);
+    }
+
+    let swap_result = compute_swap(
+        &xs,
+        offer_amount_dec,
+        offer_ind,
+        ask_ind,
+        &config,
+        &env,
+        maker_fee_share,
+        share_fee_share,
+    )?;
+    ensure!(
+        swap_result.dy >= ask_asset_dec.amount,
+        PclError::MaxSpreadAssertion {}
+    );
+    xs[offer_ind] += offer_amount_dec;
+    xs[ask_ind] -= ask_asset_dec.amount + swap_result.maker_fee + swap_result.share_fee;
+
+    let total_share = query_lp_supply(
+        &deps.querier,
+        &config.pair_info.liquidity_token,
+        config.native_lp_token,
+    )?
+    .to_decimal256(LP_TOKEN_PRECISION);
+
+    // Skip very small trade sizes which could significantly mess up the price due to rounding errors,
+    // especially if token precisions are 18.
+    if (swap_result.dy + swap_result.maker_fee + swap_result.share_fee) >= MIN_TRADE_SIZE
+        && offer_amount_dec >= MIN_TRADE_SIZE
+    {
+        let last_prices = swap_result.calc_last_prices(
+            offer_amount_dec,
+            offer_ind,
+            ask_ind,
+            &config.pool_state.price_state.last_prices(),
+        );
+
+        // update_price() works only with internal representation
+        let ixs = to_internal_repr(&xs, &config);
+        config.pool_state.update_price(
+            &config.pool_params,
+            &env,
+            total_share,
+            &ixs,
+            &last_prices,
+        )?;
+    }
+
+    let receiver = to.unwrap_or_else(|| sender.clone());
+
+    let mut messages = vec![ask_asset.clone().into_msg(&receiver)?];
+
+    // Refund the unused offer funds
+    let refund_amount = offer_asset.amount - offer_amount;
+    if !refund_amount.is_zero() {
+        messages.push(
+            offer_asset
+                .info
+                .with_balance(refund_amount)
+                .into_msg(&sender)?,
+        );
+    }
+
+    // Send the shared fee
+    let mut fee_share_amount = Uint128::zero();
+    if let Some(fee_share) = config.fee_share.clone() {
+        fee_share_amount = swap_result.share_fee.to_uint(ask_asset_prec)?;
+        if !fee_share_amount.is_zero() {
+            messages.extend(fee_share.into_msgs(&pools[ask_ind].info, fee_share_amount)?);
+        }
+    }
+
+    let accrued_fee = accrued_maker_fee(
+        &config,
+        &fee_info,
+        swap_result.total_fee - swap_result.share_fee,
+    )
+    .to_uint(ask_asset_prec)?;
+
+    // Send the maker fee
+    let mut maker_fee = Uint128::zero();
+    if let Some(fee_address) = fee_info.fee_address {
+        maker_fee = swap_result.maker_fee.to_uint(ask_asset_prec)?;
+        if !maker_fee.is_zero() {
+            let fee = pools[ask_ind].info.with_balance(maker_fee);
+            messages.push(fee.into_msg(fee_address)?);
+        }
+    }
+
+    let mut new_pools = pools
+        .iter()
+        .map(|pool| {
+            let prec = precisions.get_precision(&pool.info)?;
+            Ok(pool.info.with_balance(pool.amount.to_uint(prec)?))
+        })
+        .collect::<Result<Vec<_>, ContractError>>()?;
+    new_pools[offer_ind].amount = new_pools[offer_ind].amount.checked_add(offer_amount)?;
+    new_pools[ask_ind].amount = new_pools[ask_ind]
+        .amount
+        .checked_sub(ask_asset.amount + fee_share_amount + maker_fee)?;
+    save_reserves(deps.storage, &new_pools, env.block.height)?;
+    let commission_amount = swap_result.total_fee.to_uint(ask_asset_prec)?;
+    STATS.record(
+        deps.storage,
+        &config.pair_info.asset_infos,
+        env.block.time.seconds(),
+        &AssetStats::swap(
+            &offer_asset.info.with_balance(offer_amount),
+            &ask_asset,
+            commission_amount.checked_sub(maker_fee + accrued_fee + fee_share_amount)?,
+            maker_fee + accrued_fee,
+            fee_share_amount,
+        ),
+    )?;
+
+    accumulate_prices(&env, &mut config, &old_real_prices);
+
+    CONFIG.save(deps.storage, &config)?;
+    save_observation(deps.storage, &config)?;
+    ensure_price_deviation(&env, &config, &xs, Some(offer_ind), ask_ind)?;
+
+    let event = Event::new("swap")
+        .add_attribute("action", "swap_exact_out")
+        .add_attribute("sender", sender.to_string())
+        .add_attribute("receiver", receiver.to_string())
+        .add_attribute("offer_asset", offer_asset.info.to_string())
+        .add_attribute("ask_asset", ask_asset.info.to_string())
+        .add_attribute("offer_amount", offer_amount.to_string())
+        .add_attribute("return_amount", ask_asset.amount.to_string())
+        .add_attribute("refund_amount", refund_amount.to_string())
         .add_attribute(
-            "commission_amount",
-            swap_result.total_fee.to_uint(ask_asset_prec)?.to_string(),
+            "spread_amount",
+            swap_result.spread_fee.to_uint(ask_asset_prec)?.to_string(),
         )
+        .add_attribute("commission_amount", commission_amount.to_string())
         .add_attribute("maker_fee_amount", maker_fee.to_string())
         .add_attribute("fee_share_amount", fee_share_amount.to_string());
 
+    let hook_msgs = PairHookMsg::AfterSwap {
+        offer: offer_asset.info.with_balance(offer_amount),
+        ask: ask_asset,
+        trader: sender,
+    }
+    .into_submsgs(&config.hooks)?;
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_submessages(hook_msgs)
+        .add_event(event))
+}
+
+/// Sends the requested asset to the sender and executes the callback message on the sender.
+/// The pool invariant is verified in the reply once the callback is executed.
+///
+/// * **sender** is the borrower which receives the asset and the callback.
+///
+/// * **ask_asset** is the asset and the amount to borrow.
+///
+/// * **callback_msg** is the message executed on the sender after the asset is sent.
+fn flash_swap(
+    deps: DepsMut,
+    sender: Addr,
+    ask_asset: Asset,
+    callback_msg: Binary,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    ensure_not_paused(
+        &deps.querier,
+        &config.factory_addr,
+        &config.pair_info.contract_addr,
+        PauseScope::Swap,
+        ContractError::Paused,
+    )?;
+    ensure_traders_allowed(deps.querier, &config, &[&sender])?;
+
+    let pools = query_reserves(deps.storage, &config)?;
+    let ask_pool = pools
+        .iter()
+        .find(|pool| pool.info == ask_asset.info)
+        .ok_or_else(|| ContractError::InvalidAsset(ask_asset.info.to_string()))?;
+    ensure!(
+        !ask_asset.amount.is_zero() && ask_asset.amount < ask_pool.amount,
+        ContractError::InvalidFlashSwapAmount {}
+    );
+
+    FLASH_SWAP.save(deps.storage, &ask_asset)?;
+
+    let callback = SubMsg::reply_on_success(
+        WasmMsg::Execute {
+            contract_addr: sender.to_string(),
+            msg: callback_msg,
+            funds: vec![],
+        },
+        FLASH_SWAP_REPLY_ID,
+    );
+
+    let event = Event::new("flash_swap")
+        .add_attribute("action", "flash_swap")
+        .add_attribute("sender", sender.to_string())
+        .add_attribute("ask_asset", ask_asset.info.to_string())
+        .add_attribute("ask_amount", ask_asset.amount.to_string());
+
+    Ok(Response::new()
+        .add_message(ask_asset.into_msg(&sender)?)
+        .add_submessage(callback)
+        .add_event(event))
+}
+
+/// Verifies that the flash swap borrower returned the borrowed asset plus fees.
+/// The maximum fee rate is charged on all the amounts returned to the pair,
+/// then the invariant D of the fee adjusted balances must not be less than the one before the flash swap.
+fn verify_flash_swap(deps: DepsMut, env: Env, ask_asset: Asset) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    let precisions = Precisions::new(deps.storage)?;
+    let ask_asset_dec = ask_asset.to_decimal_asset(precisions.get_precision(&ask_asset.info)?);
+
+    let pools = query_pools(deps.storage, &config, &precisions)?;
+    let balances = config
+        .pair_info
+        .query_pools(&deps.querier, &env.contract.address)?;
+
+    let fee_rate = Decimal256::from(config.pool_params.out_fee);
+    let xs = pools.iter().map(|asset| asset.amount).collect_vec();
+    let new_xs = pools
+        .iter()
+        .zip(balances.iter())
+        .map(|(pool, balance)| {
+            let balance = balance
+                .amount
+                .to_decimal256(precisions.get_precision(&balance.info)?);
+            let mut expected = pool.amount;
+            if pool.info == ask_asset_dec.info {
+                expected = expected.checked_sub(ask_asset_dec.amount)?;
+            }
+            let amount_in = balance.saturating_sub(expected);
+            Ok(balance.checked_sub(amount_in * fee_rate)?)
+        })
+        .collect::<Result<Vec<_>, ContractError>>()?;
+
+    let amp_gamma = config.pool_state.get_amp_gamma(&env);
+    let old_d = calc_d(&to_internal_repr(&xs, &config), &amp_gamma)?;
+    let new_d = calc_d(&to_internal_repr(&new_xs, &config), &amp_gamma)?;
+    ensure!(
+        new_d >= old_d,
+        ContractError::FlashSwapInvariantViolation {}
+    );
+
+    save_reserves(deps.storage, &balances, env.block.height)?;
+
+    // Accumulate prices up to this moment using the previous reserves
+    let old_real_prices = calc_last_prices(&xs, &config, &env)?;
+    accumulate_prices(&env, &mut config, &old_real_prices);
+    CONFIG.save(deps.storage, &config)?;
+    save_observation(deps.storage, &config)?;
+
+    let ask_ind = pools
+        .iter()
+        .position(|pool| pool.info == ask_asset_dec.info)
+        .ok_or_else(|| ContractError::InvalidAsset(ask_asset_dec.info.to_string()))?;
+    let new_xs = query_pools(deps.storage, &config, &precisions)?
+        .into_iter()
+        .map(|pool| pool.amount)
+        .collect_vec();
+    ensure_price_deviation(&env, &config, &new_xs, None, ask_ind)?;
+
+    let event = Event::new("flash_swap_repay")
+        .add_attribute("action", "flash_swap_repay")
+        .add_attribute("reserves", balances.iter().join(", "));
+
+    Ok(Response::new().add_event(event))
+}
+
+/// Sends the pair balances exceeding the tracked reserves (e.g. direct token transfers)
+/// to the specified recipient.
+///
+/// * **to** is the recipient of the excess balances.
+fn skim(deps: DepsMut, env: Env, to: Addr) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    let reserves = query_reserves(deps.storage, &config)?;
+    let balances = config
+        .pair_info
+        .query_pools(&deps.querier, &env.contract.address)?;
+
+    let excess_assets = balances
+        .into_iter()
+        .zip(reserves)
+        .map(|(balance, reserve)| {
+            balance
+                .info
+                .with_balance(balance.amount.saturating_sub(reserve.amount))
+        })
+        .filter(|asset| !asset.amount.is_zero())
+        .collect_vec();
+
+    let messages = excess_assets
+        .iter()
+        .map(|asset| asset.clone().into_msg(&to))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let event = Event::new("skim").add_attributes(vec![
+        attr("action", "skim"),
+        attr("receiver", to),
+        attr("assets", excess_assets.iter().join(", ")),
+    ]);
+
+    Ok(Response::new().add_messages(messages).add_event(event))
+}
+
+/// Updates the tracked reserves to match the current pair balances.
+fn sync(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    let balances = config
+        .pair_info
+        .query_pools(&deps.querier, &env.contract.address)?;
+
+    save_reserves(deps.storage, &balances, env.block.height)?;
+
+    let event = Event::new("sync").add_attributes(vec![
+        attr("action", "sync"),
+        attr("reserves", balances.iter().join(", ")),
+    ]);
+
+    Ok(Response::new().add_event(event))
+}
+
+/// Mints the protocol fee accrued since the last liquidity event as LP tokens to the fee address.
+fn collect_protocol_fees(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    ensure!(
+        config.protocol_fee_accrual,
+        ContractError::ProtocolFeeAccrualDisabled {}
+    );
+
+    let total_share = query_lp_supply(
+        &deps.querier,
+        &config.pair_info.liquidity_token,
+        config.native_lp_token,
+    )?;
+    let (messages, protocol_share) =
+        mint_protocol_fee(deps.as_ref(), &env, &mut config, total_share)?;
+    CONFIG.save(deps.storage, &config)?;
+    save_xcp_profit_last(deps.storage, &config)?;
+
+    let event = Event::new("collect_protocol_fees").add_attributes(vec![
+        attr("action", "collect_protocol_fees"),
+        attr("protocol_fee_share", protocol_share),
+    ]);
+
     Ok(Response::new().add_messages(messages).add_event(event))
 }
 
@@ -763,10 +1594,10 @@ fn update_config(
             }
 
             // Set sharing config
-            config.fee_share = Some(FeeShareConfig {
-                bps: fee_share_bps,
-                recipient: deps.api.addr_validate(&fee_share_address)?,
-            });
+            config.fee_share = Some(FeeShareConfig::single(
+                deps.api.addr_validate(&fee_share_address)?,
+                fee_share_bps,
+            ));
 
             response.attributes.extend(vec![
                 attr("action", "enable_fee_share"),
@@ -774,6 +1605,24 @@ fn update_config(
                 attr("fee_share_address", fee_share_address),
             ]);
         }
+        ConcentratedPoolUpdateParams::EnableFeeShareRecipients { recipients } => {
+            let fee_share = check_fee_share_recipients(deps.api, recipients)?;
+
+            response.attributes.extend(vec![
+                attr("action", "enable_fee_share"),
+                attr("fee_share_bps", fee_share.total_bps().to_string()),
+                attr(
+                    "fee_share_recipients",
+                    fee_share
+                        .recipients
+                        .iter()
+                        .map(|recipient| format!("{}:{}", recipient.recipient, recipient.bps))
+                        .join(","),
+                ),
+            ]);
+
+            config.fee_share = Some(fee_share);
+        }
         ConcentratedPoolUpdateParams::DisableFeeShare => {
             // Disable fee sharing for this contract by setting bps and
             // address back to None
@@ -782,6 +1631,86 @@ fn update_config(
                 .attributes
                 .push(attr("action", "disable_fee_share"));
         }
+        ConcentratedPoolUpdateParams::SetHooks { hooks } => {
+            config.hooks = check_hooks(deps.api, hooks)?;
+
+            response.attributes.extend(vec![
+                attr("action", "set_hooks"),
+                attr(
+                    "hooks",
+                    config
+                        .hooks
+                        .iter()
+                        .map(|hook| hook.contract_addr.as_str())
+                        .join(", "),
+                ),
+            ]);
+        }
+        ConcentratedPoolUpdateParams::EnablePriceGuard { max_deviation } => {
+            if !PRICE_DEVIATION_LIMITS.contains(&max_deviation) {
+                return Err(ContractError::InvalidPriceDeviation {});
+            }
+
+            config.max_price_deviation = Some(max_deviation);
+            response.attributes.extend(vec![
+                attr("action", "enable_price_guard"),
+                attr("max_deviation", max_deviation.to_string()),
+            ]);
+        }
+        ConcentratedPoolUpdateParams::DisablePriceGuard => {
+            config.max_price_deviation = None;
+            response
+                .attributes
+                .push(attr("action", "disable_price_guard"));
+        }
+        ConcentratedPoolUpdateParams::SetTraderAllowlist(allowlist) => {
+            let allowlist = check_trader_allowlist(deps.api, allowlist)?;
+
+            response
+                .attributes
+                .push(attr("action", "set_trader_allowlist"));
+            response.attributes.push(match &allowlist {
+                TraderAllowlist::Addresses(addresses) => {
+                    attr("addresses", addresses.iter().map(Addr::as_str).join(", "))
+                }
+                TraderAllowlist::Cw4Group(group_addr) => attr("cw4_group", group_addr),
+            });
+
+            config.trader_allowlist = Some(allowlist);
+        }
+        ConcentratedPoolUpdateParams::RemoveTraderAllowlist => {
+            config.trader_allowlist = None;
+            response
+                .attributes
+                .push(attr("action", "remove_trader_allowlist"));
+        }
+        ConcentratedPoolUpdateParams::EnableProtocolFeeAccrual => {
+            config.protocol_fee_accrual = true;
+            // Only the growth from now on is charged
+            save_xcp_profit_last(deps.storage, &config)?;
+
+            response
+                .attributes
+                .push(attr("action", "enable_protocol_fee_accrual"));
+        }
+        ConcentratedPoolUpdateParams::DisableProtocolFeeAccrual => {
+            let total_share = query_lp_supply(
+                &deps.querier,
+                &config.pair_info.liquidity_token,
+                config.native_lp_token,
+            )?;
+            let (messages, protocol_share) =
+                mint_protocol_fee(deps.as_ref(), &env, &mut config, total_share)?;
+
+            config.protocol_fee_accrual = false;
+            save_xcp_profit_last(deps.storage, &config)?;
+
+            response = response.add_messages(messages);
+            response.attributes.extend(vec![
+                attr("action", "disable_protocol_fee_accrual"),
+                attr("protocol_fee_share", protocol_share),
+            ]);
+        }
     };
     CONFIG.save(deps.storage, &config)?;
 
@@ -791,17 +1720,26 @@ fn update_config(
 /// Manages the contract migration.
 #[cfg(not(tarpaulin_include))]
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(deps: DepsMut, _env: Env, _msg: Empty) -> Result<Response, ContractError> {
+pub fn migrate(deps: DepsMut, env: Env, _msg: Empty) -> Result<Response, ContractError> {
     let version = get_contract_version(deps.storage)?;
 
     match version.contract.as_ref() {
         CONTRACT_NAME => match version.version.as_ref() {
-            "4.1.0" => {}
+            "4.1.0" | "4.1.1" => migrate_config_from_v410(deps.storage)?,
             _ => return Err(ContractError::MigrationError {}),
         },
         _ => return Err(ContractError::MigrationError {}),
     }
 
+    // Start tracking reserves from the current pair balances
+    if !RESERVES.exists(deps.storage) {
+        let config = CONFIG.load(deps.storage)?;
+        let balances = config
+            .pair_info
+            .query_pools(&deps.querier, &env.contract.address)?;
+        save_reserves(deps.storage, &balances, env.block.height)?;
+    }
+
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
     Ok(Response::new()
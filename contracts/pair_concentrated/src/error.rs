@@ -1,8 +1,14 @@
-use cosmwasm_std::{ConversionOverflowError, OverflowError, StdError, Uint128};
+use cosmwasm_std::{
+    Addr, ConversionOverflowError, Decimal, Decimal256, OverflowError, StdError, Uint128,
+};
 use cw_utils::{ParseReplyError, PaymentError};
 use thiserror::Error;
 
-use astroport::{asset::MINIMUM_LIQUIDITY_AMOUNT, pair::MAX_FEE_SHARE_BPS};
+use astroport::asset::MINIMUM_LIQUIDITY_AMOUNT;
+use astroport::factory::PauseScope;
+use astroport::pair::{
+    HOOK_GAS_LIMIT, MAX_ALLOWLIST_ADDRESSES, MAX_FEE_SHARE_BPS, MAX_HOOKS, PRICE_DEVIATION_LIMITS,
+};
 use astroport_pcl_common::error::PclError;
 
 /// This enum describes pair contract errors
@@ -29,9 +35,15 @@ pub enum ContractError {
     #[error("Unauthorized")]
     Unauthorized {},
 
+    #[error("Transaction deadline exceeded")]
+    DeadlineExceeded {},
+
     #[error("CW20 tokens can be swapped via Cw20::Send message only")]
     Cw20DirectSwap {},
 
+    #[error("Operation non supported")]
+    NonSupported {},
+
     #[error("You need to provide init params")]
     InitParamsNotFound {},
 
@@ -53,6 +65,12 @@ pub enum ContractError {
     #[error("The asset {0} does not belong to the pair")]
     InvalidAsset(String),
 
+    #[error("Ask or offer asset is missed")]
+    VariableAssetMissed {},
+
+    #[error("Source and target assets are the same")]
+    SameAssets {},
+
     #[error("Contract can't be migrated!")]
     MigrationError {},
 
@@ -62,6 +80,24 @@ pub enum ContractError {
     )]
     FeeShareOutOfBounds {},
 
+    #[error("Fee share recipients must be unique")]
+    DuplicateFeeShareRecipients {},
+
+    #[error("{0:?} is paused")]
+    Paused(PauseScope),
+
+    #[error("Flash swap is in progress")]
+    FlashSwapInProgress {},
+
+    #[error("Flash swap amount must be positive and less than the pool reserve")]
+    InvalidFlashSwapAmount {},
+
+    #[error("Flash swap invariant violated: borrowed assets plus fees were not returned")]
+    FlashSwapInvariantViolation {},
+
+    #[error("Required offer amount {0} exceeds the maximum offer amount {1}")]
+    MaxOfferAmountAssertion(Uint128, Uint128),
+
     #[error("Slippage is more than expected: received {0}, expected {1} LP tokens")]
     ProvideSlippageViolation(Uint128, Uint128),
 
@@ -77,4 +113,32 @@ pub enum ContractError {
 
     #[error("Asset mismatch between the requested and the stored asset in contract")]
     AssetMismatch {},
+
+    #[error("Protocol fee accrual is disabled")]
+    ProtocolFeeAccrualDisabled {},
+
+    #[error(
+        "Hooks must be unique, there can be at most {} hooks and their gas limits must be within {:?}",
+        MAX_HOOKS,
+        HOOK_GAS_LIMIT
+    )]
+    InvalidHooks {},
+
+    #[error("Max price deviation must be within {:?}", PRICE_DEVIATION_LIMITS)]
+    InvalidPriceDeviation {},
+
+    #[error("Swap moves the price by {deviation} from the block open price, max allowed {max_deviation}")]
+    PriceDeviationExceeded {
+        deviation: Decimal256,
+        max_deviation: Decimal,
+    },
+
+    #[error(
+        "Trader allowlist must contain from 1 to {} unique addresses",
+        MAX_ALLOWLIST_ADDRESSES
+    )]
+    InvalidTraderAllowlist {},
+
+    #[error("{addr} is not allowed to trade in this pair")]
+    TraderNotAllowed { addr: Addr },
 }
@@ -2,5 +2,6 @@ pub mod contract;
 pub mod state;
 
 pub mod error;
+pub mod migration;
 pub mod queries;
 pub mod utils;
@@ -0,0 +1,50 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, StdResult, Storage, Uint128};
+use cw_storage_plus::Item;
+
+use astroport::asset::{AssetInfo, PairInfo};
+use astroport::pair::LegacyFeeShareConfig;
+use astroport_pcl_common::state::{Config, PoolParams, PoolState};
+
+use crate::state::CONFIG;
+
+/// This structure describes the config stored by the concentrated pair contract v4.1.x
+#[cw_serde]
+pub struct ConfigV410 {
+    pub pair_info: PairInfo,
+    pub factory_addr: Addr,
+    pub block_time_last: u64,
+    pub cumulative_prices: Vec<(AssetInfo, AssetInfo, Uint128)>,
+    pub pool_params: PoolParams,
+    pub pool_state: PoolState,
+    pub owner: Option<Addr>,
+    pub fee_share: Option<LegacyFeeShareConfig>,
+}
+
+pub const CONFIG_V410: Item<ConfigV410> = Item::new("config");
+
+/// Converts the v4.1.x config with a single fee share recipient to the current one.
+/// All features added after v4.1.x are disabled.
+pub fn migrate_config_from_v410(storage: &mut dyn Storage) -> StdResult<()> {
+    let config = CONFIG_V410.load(storage)?;
+
+    CONFIG.save(
+        storage,
+        &Config {
+            pair_info: config.pair_info,
+            factory_addr: config.factory_addr,
+            block_time_last: config.block_time_last,
+            cumulative_prices: config.cumulative_prices,
+            pool_params: config.pool_params,
+            pool_state: config.pool_state,
+            owner: config.owner,
+            fee_share: config.fee_share.map(Into::into),
+            native_lp_token: false,
+            protocol_fee_accrual: false,
+            hooks: vec![],
+            block_open_prices: vec![],
+            max_price_deviation: None,
+            trader_allowlist: None,
+        },
+    )
+}
@@ -1,18 +1,20 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    to_json_binary, Binary, Decimal, Decimal256, Deps, Env, StdError, StdResult, Uint128,
+    to_json_binary, Binary, Decimal, Decimal256, Deps, Env, StdError, StdResult, Uint128, Uint64,
 };
 use itertools::Itertools;
 
-use astroport::asset::Asset;
+use astroport::asset::{Asset, AssetInfo, AssetInfoExt};
 use astroport::cosmwasm_ext::{DecimalToInteger, IntegerToDecimal};
+use astroport::observation::{query_observations, Observation};
 use astroport::pair::{
     ConfigResponse, CumulativePricesResponse, PoolResponse, ReverseSimulationResponse,
     SimulationResponse,
 };
 use astroport::pair_concentrated::{ConcentratedPoolConfig, QueryMsg};
-use astroport::querier::{query_factory_config, query_fee_info, query_supply};
+use astroport::querier::{query_factory_config, query_fee_info, query_lp_supply};
+use astroport::stats::{epoch_at, StatsResponse};
 use astroport_pcl_common::state::Precisions;
 use astroport_pcl_common::utils::{
     accumulate_prices, before_swap_check, calc_last_prices, compute_offer_amount, compute_swap,
@@ -22,8 +24,11 @@ use astroport_pcl_common::{calc_d, get_xcp};
 
 use crate::contract::LP_TOKEN_PRECISION;
 use crate::error::ContractError;
-use crate::state::CONFIG;
-use crate::utils::{calculate_shares, get_assets_with_precision, pool_info, query_pools};
+use crate::state::{BALANCES, CONFIG, OBSERVATIONS, STATS};
+use crate::utils::{
+    calc_withdraw_one_asset, calculate_shares, get_assets_with_precision, pool_info, query_pools,
+    select_pools, to_internal_repr,
+};
 
 /// Exposes all the queries available in the contract.
 ///
@@ -36,9 +41,9 @@ use crate::utils::{calculate_shares, get_assets_with_precision, pool_info, query
 /// * **QueryMsg::Share { amount }** Returns the amount of assets that could be withdrawn from the pool
 /// using a specific amount of LP tokens. The result is returned in a vector that contains objects of type [`Asset`].
 ///
-/// * **QueryMsg::Simulation { offer_asset }** Returns the result of a swap simulation using a [`SimulationResponse`] object.
+/// * **QueryMsg::Simulation { offer_asset, ask_asset_info }** Returns the result of a swap simulation using a [`SimulationResponse`] object.
 ///
-/// * **QueryMsg::ReverseSimulation { ask_asset }** Returns the result of a reverse swap simulation  using
+/// * **QueryMsg::ReverseSimulation { offer_asset_info, ask_asset }** Returns the result of a reverse swap simulation  using
 /// a [`ReverseSimulationResponse`] object.
 ///
 /// * **QueryMsg::CumulativePrices {}** Returns information about cumulative prices for the assets in the
@@ -48,6 +53,12 @@ use crate::utils::{calculate_shares, get_assets_with_precision, pool_info, query
 ///
 /// * **QueryMsg::AssetBalanceAt { asset_info, block_height }** Returns the balance of the specified
 /// asset that was in the pool just preceding the moment of the specified block height creation.
+///
+/// * **QueryMsg::Observe { seconds_ago }** Returns cumulative prices at each moment `seconds_ago`
+/// from the current block time using a vector of [`Observation`] objects.
+///
+/// * **QueryMsg::Stats { epoch }** Returns swap volumes and fees accumulated during the epoch
+/// and over the pair lifetime in a [`StatsResponse`] object.
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
@@ -56,12 +67,18 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
         QueryMsg::Share { amount } => to_json_binary(
             &query_share(deps, amount).map_err(|err| StdError::generic_err(err.to_string()))?,
         ),
-        QueryMsg::Simulation { offer_asset, .. } => to_json_binary(
-            &query_simulation(deps, env, offer_asset)
+        QueryMsg::Simulation {
+            offer_asset,
+            ask_asset_info,
+        } => to_json_binary(
+            &query_simulation(deps, env, offer_asset, ask_asset_info)
                 .map_err(|err| StdError::generic_err(format!("{err}")))?,
         ),
-        QueryMsg::ReverseSimulation { ask_asset, .. } => to_json_binary(
-            &query_reverse_simulation(deps, env, ask_asset)
+        QueryMsg::ReverseSimulation {
+            offer_asset_info,
+            ask_asset,
+        } => to_json_binary(
+            &query_reverse_simulation(deps, env, offer_asset_info, ask_asset)
                 .map_err(|err| StdError::generic_err(format!("{err}")))?,
         ),
         QueryMsg::CumulativePrices {} => to_json_binary(
@@ -80,9 +97,19 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
             assets,
             slippage_tolerance,
         )?),
-        QueryMsg::SimulateWithdraw { lp_amount } => to_json_binary(
-            &query_share(deps, lp_amount).map_err(|err| StdError::generic_err(err.to_string()))?,
+        QueryMsg::SimulateWithdraw { lp_amount, asset } => to_json_binary(
+            &query_simulate_withdraw(deps, env, lp_amount, asset)
+                .map_err(|err| StdError::generic_err(err.to_string()))?,
         ),
+        QueryMsg::AssetBalanceAt {
+            asset_info,
+            block_height,
+        } => to_json_binary(&query_asset_balance_at(deps, asset_info, block_height)?),
+        QueryMsg::Observe { seconds_ago } => to_json_binary(
+            &query_observe(deps, env, seconds_ago)
+                .map_err(|err| StdError::generic_err(format!("{err}")))?,
+        ),
+        QueryMsg::Stats { epoch } => to_json_binary(&query_stats(deps, env, epoch)?),
     }
 }
 
@@ -90,7 +117,7 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
 /// tokens currently minted in an object of type [`PoolResponse`].
 fn query_pool(deps: Deps) -> StdResult<PoolResponse> {
     let config = CONFIG.load(deps.storage)?;
-    let (assets, total_share) = pool_info(deps.querier, &config)?;
+    let (assets, total_share) = pool_info(deps, &config)?;
 
     let resp = PoolResponse {
         assets,
@@ -107,13 +134,12 @@ fn query_pool(deps: Deps) -> StdResult<PoolResponse> {
 fn query_share(deps: Deps, amount: Uint128) -> Result<Vec<Asset>, ContractError> {
     let config = CONFIG.load(deps.storage)?;
     let precisions = Precisions::new(deps.storage)?;
-    let pools = query_pools(
-        deps.querier,
-        &config.pair_info.contract_addr,
-        &config,
-        &precisions,
+    let pools = query_pools(deps.storage, &config, &precisions)?;
+    let total_share = query_lp_supply(
+        &deps.querier,
+        &config.pair_info.liquidity_token,
+        config.native_lp_token,
     )?;
-    let total_share = query_supply(&deps.querier, config.pair_info.liquidity_token.to_string())?;
     let refund_assets =
         get_share_in_assets(&pools, amount.saturating_sub(Uint128::one()), total_share);
 
@@ -132,24 +158,71 @@ fn query_share(deps: Deps, amount: Uint128) -> Result<Vec<Asset>, ContractError>
     Ok(refund_assets)
 }
 
+/// Returns the amount of assets that could be withdrawn from the pool using a specific amount of LP tokens.
+/// If **asset** is specified, returns the amount of the asset received for withdrawing all liquidity in it.
+///
+/// * **lp_amount** is the amount of LP tokens to withdraw.
+///
+/// * **asset** is an optional asset to withdraw all liquidity in.
+fn query_simulate_withdraw(
+    deps: Deps,
+    env: Env,
+    lp_amount: Uint128,
+    asset: Option<AssetInfo>,
+) -> Result<Vec<Asset>, ContractError> {
+    let Some(asset_info) = asset else {
+        return query_share(deps, lp_amount);
+    };
+
+    let config = CONFIG.load(deps.storage)?;
+    let precisions = Precisions::new(deps.storage)?;
+    let pools = query_pools(deps.storage, &config, &precisions)?;
+    let total_share = query_lp_supply(
+        &deps.querier,
+        &config.pair_info.liquidity_token,
+        config.native_lp_token,
+    )?;
+
+    let ask_ind = pools
+        .iter()
+        .position(|pool| pool.info == asset_info)
+        .ok_or_else(|| ContractError::InvalidAsset(asset_info.to_string()))?;
+
+    let withdraw_result = calc_withdraw_one_asset(
+        &env,
+        &config,
+        &pools,
+        total_share.to_decimal256(LP_TOKEN_PRECISION),
+        lp_amount
+            .saturating_sub(Uint128::one())
+            .to_decimal256(LP_TOKEN_PRECISION),
+        ask_ind,
+    )?;
+    let prec = precisions.get_precision(&asset_info)?;
+
+    Ok(vec![
+        asset_info.with_balance(withdraw_result.amount.to_uint(prec)?)
+    ])
+}
+
 /// Returns information about a swap simulation.
+///
+/// * **ask_asset_info** is the asset to swap to. Must be specified if the pool holds more than two assets.
 pub fn query_simulation(
     deps: Deps,
     env: Env,
     offer_asset: Asset,
+    ask_asset_info: Option<AssetInfo>,
 ) -> Result<SimulationResponse, ContractError> {
     let config = CONFIG.load(deps.storage)?;
     let precisions = Precisions::new(deps.storage)?;
     let offer_asset_prec = precisions.get_precision(&offer_asset.info)?;
     let offer_asset_dec = offer_asset.to_decimal_asset(offer_asset_prec);
 
-    let pools = query_pools(deps.querier, &env.contract.address, &config, &precisions)?;
+    let pools = query_pools(deps.storage, &config, &precisions)?;
 
-    let (offer_ind, _) = pools
-        .iter()
-        .find_position(|asset| asset.info == offer_asset.info)
-        .ok_or_else(|| ContractError::InvalidAsset(offer_asset_dec.info.to_string()))?;
-    let ask_ind = 1 - offer_ind;
+    let (offer_ind, ask_ind) =
+        select_pools(&config, Some(&offer_asset.info), ask_asset_info.as_ref())?;
     let ask_asset_prec = precisions.get_precision(&pools[ask_ind].info)?;
 
     before_swap_check(&pools, offer_asset_dec.amount)?;
@@ -161,6 +234,7 @@ pub fn query_simulation(
         &deps.querier,
         &config.factory_addr,
         config.pair_info.pair_type.clone(),
+        &config.pair_info.contract_addr,
     )?;
     let mut maker_fee_share = Decimal256::zero();
     if fee_info.fee_address.is_some() {
@@ -169,12 +243,13 @@ pub fn query_simulation(
     // If this pool is configured to share fees
     let mut share_fee_share = Decimal256::zero();
     if let Some(fee_share) = config.fee_share.clone() {
-        share_fee_share = Decimal256::from_ratio(fee_share.bps, 10000u16);
+        share_fee_share = Decimal256::from_ratio(fee_share.total_bps(), 10000u16);
     }
 
     let swap_result = compute_swap(
         &xs,
         offer_asset_dec.amount,
+        offer_ind,
         ask_ind,
         &config,
         &env,
@@ -190,9 +265,12 @@ pub fn query_simulation(
 }
 
 /// Returns information about a reverse swap simulation.
+///
+/// * **offer_asset_info** is the asset to swap from. Must be specified if the pool holds more than two assets.
 pub fn query_reverse_simulation(
     deps: Deps,
     env: Env,
+    offer_asset_info: Option<AssetInfo>,
     ask_asset: Asset,
 ) -> Result<ReverseSimulationResponse, ContractError> {
     let config = CONFIG.load(deps.storage)?;
@@ -200,18 +278,15 @@ pub fn query_reverse_simulation(
     let ask_asset_prec = precisions.get_precision(&ask_asset.info)?;
     let ask_asset_dec = ask_asset.to_decimal_asset(ask_asset_prec);
 
-    let pools = query_pools(deps.querier, &env.contract.address, &config, &precisions)?;
+    let pools = query_pools(deps.storage, &config, &precisions)?;
 
-    let (ask_ind, _) = pools
-        .iter()
-        .find_position(|asset| asset.info == ask_asset.info)
-        .ok_or_else(|| ContractError::InvalidAsset(ask_asset.info.to_string()))?;
-    let offer_ind = 1 - ask_ind;
+    let (offer_ind, ask_ind) =
+        select_pools(&config, offer_asset_info.as_ref(), Some(&ask_asset.info))?;
     let offer_asset_prec = precisions.get_precision(&pools[offer_ind].info)?;
 
     let xs = pools.iter().map(|asset| asset.amount).collect_vec();
     let (offer_amount, spread_amount, commission_amount) =
-        compute_offer_amount(&xs, ask_asset_dec.amount, ask_ind, &config, &env)?;
+        compute_offer_amount(&xs, ask_asset_dec.amount, offer_ind, ask_ind, &config, &env)?;
 
     Ok(ReverseSimulationResponse {
         offer_amount: offer_amount.to_uint(offer_asset_prec)?,
@@ -227,14 +302,14 @@ fn query_cumulative_prices(
 ) -> Result<CumulativePricesResponse, ContractError> {
     let mut config = CONFIG.load(deps.storage)?;
     let precisions = Precisions::new(deps.storage)?;
-    let pools = query_pools(deps.querier, &env.contract.address, &config, &precisions)?;
+    let pools = query_pools(deps.storage, &config, &precisions)?;
 
     let xs = pools.iter().map(|asset| asset.amount).collect_vec();
-    let last_real_price = calc_last_prices(&xs, &config, &env)?;
+    let last_real_prices = calc_last_prices(&xs, &config, &env)?;
 
-    accumulate_prices(&env, &mut config, last_real_price);
+    accumulate_prices(&env, &mut config, &last_real_prices);
 
-    let (assets, total_share) = pool_info(deps.querier, &config)?;
+    let (assets, total_share) = pool_info(deps, &config)?;
 
     Ok(CumulativePricesResponse {
         assets,
@@ -243,22 +318,50 @@ fn query_cumulative_prices(
     })
 }
 
+/// Returns cumulative prices interpolated from the recorded observations at each moment
+/// `seconds_ago` from the current block time.
+fn query_observe(
+    deps: Deps,
+    env: Env,
+    seconds_ago: Vec<u64>,
+) -> Result<Vec<Observation>, ContractError> {
+    let current = Observation {
+        timestamp: env.block.time.seconds(),
+        cumulative_prices: query_cumulative_prices(deps, env)?.cumulative_prices,
+    };
+
+    query_observations(deps.storage, &OBSERVATIONS, &current, seconds_ago).map_err(Into::into)
+}
+
+/// Returns swap volumes and fees accumulated during the given **epoch** (the current one
+/// by default) and over the pair lifetime in a [`StatsResponse`] object.
+fn query_stats(deps: Deps, env: Env, epoch: Option<u64>) -> StdResult<StatsResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let epoch = epoch.unwrap_or_else(|| epoch_at(env.block.time.seconds()));
+
+    STATS.query(deps.storage, &config.pair_info.asset_infos, epoch)
+}
+
 /// Compute the current LP token virtual price.
 pub fn query_lp_price(deps: Deps, env: Env) -> StdResult<Decimal256> {
     let config = CONFIG.load(deps.storage)?;
-    let total_lp = query_supply(&deps.querier, &config.pair_info.liquidity_token)?
-        .to_decimal256(LP_TOKEN_PRECISION);
+    let total_lp = query_lp_supply(
+        &deps.querier,
+        &config.pair_info.liquidity_token,
+        config.native_lp_token,
+    )?
+    .to_decimal256(LP_TOKEN_PRECISION);
     if !total_lp.is_zero() {
         let precisions = Precisions::new(deps.storage)?;
-        let mut ixs = query_pools(deps.querier, &env.contract.address, &config, &precisions)
+        let xs = query_pools(deps.storage, &config, &precisions)
             .map_err(|err| StdError::generic_err(err.to_string()))?
             .into_iter()
             .map(|asset| asset.amount)
             .collect_vec();
-        ixs[1] *= config.pool_state.price_state.price_scale;
+        let ixs = to_internal_repr(&xs, &config);
         let amp_gamma = config.pool_state.get_amp_gamma(&env);
         let d = calc_d(&ixs, &amp_gamma)?;
-        let xcp = get_xcp(d, config.pool_state.price_state.price_scale);
+        let xcp = get_xcp(d, &config.pool_state.price_state.price_scales());
 
         Ok(xcp / total_lp)
     } else {
@@ -270,12 +373,21 @@ pub fn query_lp_price(deps: Deps, env: Env) -> StdResult<Decimal256> {
 pub fn query_config(deps: Deps, env: Env) -> StdResult<ConfigResponse> {
     let config = CONFIG.load(deps.storage)?;
     let amp_gamma = config.pool_state.get_amp_gamma(&env);
-    let dec256_price_scale = config.pool_state.price_state.price_scale;
-    let price_scale = Decimal::from_atomics(
-        Uint128::try_from(dec256_price_scale.atomics())?,
-        dec256_price_scale.decimal_places(),
-    )
-    .map_err(|e| StdError::generic_err(format!("{e}")))?;
+    let to_decimal = |dec256_price_scale: Decimal256| -> StdResult<Decimal> {
+        Decimal::from_atomics(
+            Uint128::try_from(dec256_price_scale.atomics())?,
+            dec256_price_scale.decimal_places(),
+        )
+        .map_err(|e| StdError::generic_err(format!("{e}")))
+    };
+    let price_scale = to_decimal(config.pool_state.price_state.price_scale)?;
+    let extra_price_scales = config
+        .pool_state
+        .price_state
+        .extra_assets
+        .iter()
+        .map(|asset| to_decimal(asset.price_scale))
+        .collect::<StdResult<Vec<_>>>()?;
 
     let factory_config = query_factory_config(&deps.querier, &config.factory_addr)?;
 
@@ -292,31 +404,45 @@ pub fn query_config(deps: Deps, env: Env) -> StdResult<ConfigResponse> {
             price_scale,
             ma_half_time: config.pool_params.ma_half_time,
             fee_share: config.fee_share,
+            extra_price_scales,
+            protocol_fee_accrual: config.protocol_fee_accrual,
+            hooks: config.hooks,
+            max_price_deviation: config.max_price_deviation,
+            trader_allowlist: config.trader_allowlist,
         })?),
         owner: config.owner.unwrap_or(factory_config.owner),
         factory_addr: config.factory_addr,
     })
 }
 
+/// Returns the balance of the specified asset that was in the pool just preceding the moment
+/// of the specified block height creation. Returns `None` if the pool balance had never been
+/// recorded before the given block height.
+pub fn query_asset_balance_at(
+    deps: Deps,
+    asset_info: AssetInfo,
+    block_height: Uint64,
+) -> StdResult<Option<Uint128>> {
+    BALANCES.may_load_at_height(deps.storage, &asset_info, block_height.u64())
+}
+
 /// Compute the current pool D value.
 pub fn query_compute_d(deps: Deps, env: Env) -> StdResult<Decimal256> {
     let config = CONFIG.load(deps.storage)?;
     let precisions = Precisions::new(deps.storage)?;
 
-    let mut xs = query_pools(deps.querier, &env.contract.address, &config, &precisions)
+    let xs = query_pools(deps.storage, &config, &precisions)
         .map_err(|e| StdError::generic_err(e.to_string()))?
         .into_iter()
         .map(|a| a.amount)
         .collect_vec();
 
-    if xs[0].is_zero() || xs[1].is_zero() {
+    if xs.iter().any(|x| x.is_zero()) {
         return Err(StdError::generic_err("Pools are empty"));
     }
 
-    xs[1] *= config.pool_state.price_state.price_scale;
-
     let amp_gamma = config.pool_state.get_amp_gamma(&env);
-    calc_d(&xs, &amp_gamma)
+    calc_d(&to_internal_repr(&xs, &config), &amp_gamma)
 }
 
 pub fn query_simulate_provide(
@@ -327,12 +453,16 @@ pub fn query_simulate_provide(
 ) -> StdResult<Uint128> {
     let mut config = CONFIG.load(deps.storage)?;
 
-    let total_share = query_supply(&deps.querier, &config.pair_info.liquidity_token)?
-        .to_decimal256(LP_TOKEN_PRECISION);
+    let total_share = query_lp_supply(
+        &deps.querier,
+        &config.pair_info.liquidity_token,
+        config.native_lp_token,
+    )?
+    .to_decimal256(LP_TOKEN_PRECISION);
 
     let precisions = Precisions::new(deps.storage)?;
 
-    let mut pools = query_pools(deps.querier, &env.contract.address, &config, &precisions)
+    let pools = query_pools(deps.storage, &config, &precisions)
         .map_err(|e| StdError::generic_err(e.to_string()))?;
 
     let deposits =
@@ -342,7 +472,7 @@ pub fn query_simulate_provide(
     let (share_uint128, _) = calculate_shares(
         &env,
         &mut config,
-        &mut pools,
+        &pools,
         total_share,
         deposits.clone(),
         slippage_tolerance,
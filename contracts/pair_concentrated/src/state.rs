@@ -1,6 +1,10 @@
-use cw_storage_plus::Item;
+use cosmwasm_std::{Decimal256, Uint128};
+use cw_storage_plus::{Deque, Item, SnapshotMap, Strategy};
 
+use astroport::asset::{Asset, AssetInfo};
 use astroport::common::OwnershipProposal;
+use astroport::observation::Observation;
+use astroport::stats::PairStats;
 use astroport_pcl_common::state::Config;
 
 /// Stores pool parameters and state.
@@ -8,3 +12,27 @@ pub const CONFIG: Item<Config> = Item::new("config");
 
 /// Stores the latest contract ownership transfer proposal
 pub const OWNERSHIP_PROPOSAL: Item<OwnershipProposal> = Item::new("ownership_proposal");
+
+/// Stores the pool reserves in the same order as [`astroport::asset::PairInfo::asset_infos`]
+pub const RESERVES: Item<Vec<Uint128>> = Item::new("reserves");
+
+/// Stores the LP token virtual price (`xcp_profit_real`) after the last liquidity event.
+/// Used to measure the growth from the accrued protocol fee
+pub const XCP_PROFIT_LAST: Item<Decimal256> = Item::new("xcp_profit_last");
+
+/// Stores the asset borrowed by an ongoing flash swap
+pub const FLASH_SWAP: Item<Asset> = Item::new("flash_swap");
+
+/// Stores the lifetime and per-epoch swap statistics
+pub const STATS: PairStats = PairStats::new("stats", "epoch_stats");
+
+/// Stores a bounded ring buffer of cumulative price observations
+pub const OBSERVATIONS: Deque<Observation> = Deque::new("observations");
+
+/// Stores the pool balance of each asset at every block height it changed
+pub const BALANCES: SnapshotMap<&AssetInfo, Uint128> = SnapshotMap::new(
+    "balances",
+    "balances_check",
+    "balances_change",
+    Strategy::EveryBlock,
+);
@@ -1,48 +1,373 @@
 use cosmwasm_std::{
-    Addr, Decimal, Decimal256, Deps, Env, QuerierWrapper, StdError, StdResult, Uint128,
+    Addr, Api, CosmosMsg, Decimal, Decimal256, Deps, Env, QuerierWrapper, StdError, StdResult,
+    Storage, Uint128,
 };
 use itertools::Itertools;
 
-use astroport::asset::{Asset, DecimalAsset, MINIMUM_LIQUIDITY_AMOUNT};
+use astroport::asset::{Asset, AssetInfo, AssetInfoExt, DecimalAsset, MINIMUM_LIQUIDITY_AMOUNT};
 use astroport::cosmwasm_ext::{DecimalToInteger, IntegerToDecimal};
-use astroport::pair::MIN_TRADE_SIZE;
-use astroport::querier::query_supply;
+use astroport::observation::{store_observation, Observation};
+use astroport::pair::{
+    calc_protocol_fee_share, price_deviation, FeeShareConfig, FeeShareRecipient, PairHook,
+    TraderAllowlist, HOOK_GAS_LIMIT, MAX_ALLOWLIST_ADDRESSES, MAX_FEE_SHARE_BPS, MAX_HOOKS,
+    MIN_TRADE_SIZE,
+};
+use astroport::querier::{query_fee_info, query_lp_supply, FeeInfo};
 use astroport_pcl_common::state::{Config, Precisions};
-use astroport_pcl_common::utils::{assert_slippage_tolerance, calc_provide_fee, check_assets};
-use astroport_pcl_common::{calc_d, get_xcp};
+use astroport_pcl_common::utils::{
+    assert_slippage_tolerance, calc_last_prices, calc_provide_fee, check_assets,
+    mint_liquidity_token_message,
+};
+use astroport_pcl_common::{calc_d, calc_y, get_xcp};
 
 use crate::contract::LP_TOKEN_PRECISION;
 use crate::error::ContractError;
+use crate::state::{BALANCES, OBSERVATIONS, RESERVES, XCP_PROFIT_LAST};
 
-/// Returns the total amount of assets in the pool as well as the total amount of LP tokens currently minted.
-pub(crate) fn pool_info(
+/// Validates fee share recipients: they must be unique, every share must be positive
+/// and the total share must not exceed [`MAX_FEE_SHARE_BPS`].
+pub(crate) fn check_fee_share_recipients(
+    api: &dyn Api,
+    recipients: Vec<(String, u16)>,
+) -> Result<FeeShareConfig, ContractError> {
+    if recipients.iter().any(|(_, bps)| *bps == 0)
+        || !(1..=MAX_FEE_SHARE_BPS as u32)
+            .contains(&recipients.iter().map(|(_, bps)| *bps as u32).sum())
+    {
+        return Err(ContractError::FeeShareOutOfBounds {});
+    }
+
+    let recipients = recipients
+        .into_iter()
+        .map(|(recipient, bps)| {
+            Ok(FeeShareRecipient {
+                recipient: api.addr_validate(&recipient)?,
+                bps,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    if !recipients
+        .iter()
+        .map(|recipient| &recipient.recipient)
+        .all_unique()
+    {
+        return Err(ContractError::DuplicateFeeShareRecipients {});
+    }
+
+    Ok(FeeShareConfig { recipients })
+}
+
+/// Validates hooks: they must be unique, their number must not exceed [`MAX_HOOKS`]
+/// and their gas limits must be within [`HOOK_GAS_LIMIT`].
+pub(crate) fn check_hooks(
+    api: &dyn Api,
+    hooks: Vec<PairHook>,
+) -> Result<Vec<PairHook>, ContractError> {
+    if hooks.len() > MAX_HOOKS
+        || !hooks.iter().map(|hook| &hook.contract_addr).all_unique()
+        || hooks
+            .iter()
+            .any(|hook| !HOOK_GAS_LIMIT.contains(&hook.gas_limit))
+    {
+        return Err(ContractError::InvalidHooks {});
+    }
+
+    hooks
+        .into_iter()
+        .map(|hook| {
+            Ok(PairHook {
+                contract_addr: api.addr_validate(hook.contract_addr.as_str())?,
+                ..hook
+            })
+        })
+        .collect()
+}
+
+/// Validates the trader allowlist: it must contain from 1 to [`MAX_ALLOWLIST_ADDRESSES`]
+/// unique addresses.
+pub(crate) fn check_trader_allowlist(
+    api: &dyn Api,
+    allowlist: TraderAllowlist,
+) -> Result<TraderAllowlist, ContractError> {
+    match allowlist {
+        TraderAllowlist::Addresses(addresses) => {
+            if addresses.is_empty()
+                || addresses.len() > MAX_ALLOWLIST_ADDRESSES
+                || !addresses.iter().all_unique()
+            {
+                return Err(ContractError::InvalidTraderAllowlist {});
+            }
+
+            Ok(TraderAllowlist::Addresses(
+                addresses
+                    .iter()
+                    .map(|addr| api.addr_validate(addr.as_str()))
+                    .collect::<StdResult<_>>()?,
+            ))
+        }
+        TraderAllowlist::Cw4Group(group_addr) => Ok(TraderAllowlist::Cw4Group(
+            api.addr_validate(group_addr.as_str())?,
+        )),
+    }
+}
+
+/// Returns an error if the pair has a trader allowlist and any of the addresses isn't on it.
+pub(crate) fn ensure_traders_allowed(
     querier: QuerierWrapper,
     config: &Config,
-) -> StdResult<(Vec<Asset>, Uint128)> {
-    let pools = config
-        .pair_info
-        .query_pools(&querier, &config.pair_info.contract_addr)?;
+    addrs: &[&Addr],
+) -> Result<(), ContractError> {
+    if let Some(allowlist) = &config.trader_allowlist {
+        for addr in addrs {
+            if !allowlist.is_allowed(&querier, addr)? {
+                return Err(ContractError::TraderNotAllowed {
+                    addr: (*addr).clone(),
+                });
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Ensures the price of the swapped assets stays within the allowed deviation
+/// from their price at the start of the block.
+/// If **offer_ind** is not specified, the price of the ask asset is checked against
+/// all other pool assets.
+///
+/// * **xs** are the pool balances after the swap.
+pub(crate) fn ensure_price_deviation(
+    env: &Env,
+    config: &Config,
+    xs: &[Decimal256],
+    offer_ind: Option<usize>,
+    ask_ind: usize,
+) -> Result<(), ContractError> {
+    let Some(max_deviation) = config.max_price_deviation else {
+        return Ok(());
+    };
+    let open_prices = &config.block_open_prices;
+    if open_prices.len() != xs.len() {
+        return Ok(());
+    }
+
+    let prices = calc_last_prices(xs, config, env)?;
+    let offer_inds = match offer_ind {
+        Some(offer_ind) => vec![offer_ind],
+        None => (0..xs.len()).filter(|&ind| ind != ask_ind).collect(),
+    };
+    for offer_ind in offer_inds {
+        // Amount of the ask asset for one offer asset
+        let open_price = open_prices[offer_ind] / open_prices[ask_ind];
+        let price = prices[offer_ind] / prices[ask_ind];
+
+        let deviation = price_deviation(open_price, price);
+        if deviation > Decimal256::from(max_deviation) {
+            return Err(ContractError::PriceDeviationExceeded {
+                deviation,
+                max_deviation,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the maker fee which stays in the pool if the protocol fee accrual is enabled.
+/// The fee is minted later as LP tokens to the fee address instead of being transferred.
+///
+/// * **commission** is the swap fee left after the fee share.
+pub(crate) fn accrued_maker_fee(
+    config: &Config,
+    fee_info: &FeeInfo,
+    commission: Decimal256,
+) -> Decimal256 {
+    if config.protocol_fee_accrual && fee_info.fee_address.is_some() {
+        commission * Decimal256::from(fee_info.maker_fee_rate)
+    } else {
+        Decimal256::zero()
+    }
+}
+
+/// Builds messages minting LP tokens to the fee address for the maker share of the LP token
+/// virtual price growth since the last liquidity event if the protocol fee accrual is enabled.
+/// The virtual price in the pool state is reduced by the minted share.
+/// Returns the messages and the amount of LP tokens to mint.
+///
+/// * **total_share** is the LP token supply before minting.
+pub(crate) fn mint_protocol_fee(
+    deps: Deps,
+    env: &Env,
+    config: &mut Config,
+    total_share: Uint128,
+) -> Result<(Vec<CosmosMsg>, Uint128), ContractError> {
+    let xcp_profit_last = XCP_PROFIT_LAST.may_load(deps.storage)?.unwrap_or_default();
+    if !config.protocol_fee_accrual || xcp_profit_last.is_zero() || total_share.is_zero() {
+        return Ok((vec![], Uint128::zero()));
+    }
+
+    let fee_info = query_fee_info(
+        &deps.querier,
+        &config.factory_addr,
+        config.pair_info.pair_type.clone(),
+        &config.pair_info.contract_addr,
+    )?;
+    let Some(fee_address) = fee_info.fee_address else {
+        return Ok((vec![], Uint128::zero()));
+    };
 
-    let total_share = query_supply(&querier, config.pair_info.liquidity_token.to_string())?;
+    let price_state = &mut config.pool_state.price_state;
+    let protocol_share = calc_protocol_fee_share(
+        total_share,
+        price_state.xcp_profit_real,
+        xcp_profit_last,
+        fee_info.maker_fee_rate,
+    )?;
+    if protocol_share.is_zero() {
+        return Ok((vec![], protocol_share));
+    }
+
+    // XCP is spread over more LP tokens now
+    let ratio = Decimal256::from_ratio(total_share, total_share + protocol_share);
+    price_state.xcp_profit *= ratio;
+    price_state.xcp_profit_real *= ratio;
+
+    let messages = mint_liquidity_token_message(
+        deps.querier,
+        config,
+        &env.contract.address,
+        &fee_address,
+        protocol_share,
+        false,
+    )?;
+
+    Ok((messages, protocol_share))
+}
+
+/// Records the LP token virtual price after a liquidity event
+/// if the protocol fee accrual is enabled.
+pub(crate) fn save_xcp_profit_last(storage: &mut dyn Storage, config: &Config) -> StdResult<()> {
+    if config.protocol_fee_accrual {
+        XCP_PROFIT_LAST.save(storage, &config.pool_state.price_state.xcp_profit_real)
+    } else {
+        XCP_PROFIT_LAST.remove(storage);
+        Ok(())
+    }
+}
+
+/// Returns the total amount of assets in the pool as well as the total amount of LP tokens currently minted.
+pub(crate) fn pool_info(deps: Deps, config: &Config) -> StdResult<(Vec<Asset>, Uint128)> {
+    let pools = query_reserves(deps.storage, config)?;
+
+    let total_share = query_lp_supply(
+        &deps.querier,
+        &config.pair_info.liquidity_token,
+        config.native_lp_token,
+    )?;
 
     Ok((pools, total_share))
 }
 
+/// Returns the pool reserves tracked by the contract.
+/// Unlike the pair balances they can't be affected by direct token transfers.
+pub(crate) fn query_reserves(storage: &dyn Storage, config: &Config) -> StdResult<Vec<Asset>> {
+    let reserves = RESERVES.load(storage)?;
+
+    Ok(config
+        .pair_info
+        .asset_infos
+        .iter()
+        .zip(reserves)
+        .map(|(info, amount)| info.with_balance(amount))
+        .collect())
+}
+
+/// Saves the new pool reserves and records them in the asset balance snapshots.
+pub(crate) fn save_reserves(
+    storage: &mut dyn Storage,
+    pools: &[Asset],
+    block_height: u64,
+) -> StdResult<()> {
+    for pool in pools {
+        BALANCES.save(storage, &pool.info, &pool.amount, block_height)?;
+    }
+
+    RESERVES.save(storage, &pools.iter().map(|pool| pool.amount).collect())
+}
+
+/// Records the latest cumulative prices from the config in the observations buffer.
+pub(crate) fn save_observation(storage: &mut dyn Storage, config: &Config) -> StdResult<()> {
+    let observation = Observation {
+        timestamp: config.block_time_last,
+        cumulative_prices: config.cumulative_prices.clone(),
+    };
+
+    store_observation(storage, &OBSERVATIONS, &observation)
+}
+
 /// Returns current pool's volumes where amount is in [`Decimal256`] form.
 pub(crate) fn query_pools(
-    querier: QuerierWrapper,
-    addr: &Addr,
+    storage: &dyn Storage,
     config: &Config,
     precisions: &Precisions,
 ) -> Result<Vec<DecimalAsset>, ContractError> {
-    config
-        .pair_info
-        .query_pools(&querier, addr)?
+    query_reserves(storage, config)?
         .into_iter()
         .map(|asset| Ok(asset.to_decimal_asset(precisions.get_precision(&asset.info)?)))
         .collect()
 }
 
+/// Converts pool volumes into the internal representation by applying the price scales.
+pub(crate) fn to_internal_repr(xs: &[Decimal256], config: &Config) -> Vec<Decimal256> {
+    xs.iter()
+        .zip(config.pool_state.price_state.price_scales())
+        .map(|(x, price_scale)| *x * price_scale)
+        .collect()
+}
+
+/// Returns the position of the given asset in the pool.
+pub(crate) fn asset_position(
+    config: &Config,
+    asset_info: &AssetInfo,
+) -> Result<usize, ContractError> {
+    config
+        .pair_info
+        .asset_infos
+        .iter()
+        .position(|info| info.equal(asset_info))
+        .ok_or_else(|| ContractError::InvalidAsset(asset_info.to_string()))
+}
+
+/// Resolves positions of the offer and the ask assets in the pool.
+/// The ask or the offer asset may be omitted only in a pool with two assets.
+pub(crate) fn select_pools(
+    config: &Config,
+    offer_asset_info: Option<&AssetInfo>,
+    ask_asset_info: Option<&AssetInfo>,
+) -> Result<(usize, usize), ContractError> {
+    let asset_infos = &config.pair_info.asset_infos;
+
+    let (offer_ind, ask_ind) = match (offer_asset_info, ask_asset_info) {
+        (Some(offer), Some(ask)) => (asset_position(config, offer)?, asset_position(config, ask)?),
+        (Some(offer), None) if asset_infos.len() == 2 => {
+            let offer_ind = asset_position(config, offer)?;
+            (offer_ind, 1 ^ offer_ind)
+        }
+        (None, Some(ask)) if asset_infos.len() == 2 => {
+            let ask_ind = asset_position(config, ask)?;
+            (1 ^ ask_ind, ask_ind)
+        }
+        _ => return Err(ContractError::VariableAssetMissed {}),
+    };
+
+    if offer_ind == ask_ind {
+        return Err(ContractError::SameAssets {});
+    }
+
+    Ok((offer_ind, ask_ind))
+}
+
 pub(crate) fn get_assets_with_precision(
     deps: Deps,
     config: &Config,
@@ -50,73 +375,102 @@ pub(crate) fn get_assets_with_precision(
     pools: Vec<DecimalAsset>,
     precisions: &Precisions,
 ) -> Result<Vec<Decimal256>, ContractError> {
-    match assets.len() {
-        0 => {
-            return Err(StdError::generic_err("Nothing to provide").into());
-        }
-        1 => {
-            // Append omitted asset with explicit zero amount
-            let (given_ind, _) = config
-                .pair_info
-                .asset_infos
-                .iter()
-                .find_position(|pool| pool.equal(&assets[0].info))
-                .ok_or_else(|| ContractError::InvalidAsset(assets[0].info.to_string()))?;
-            assets.push(Asset {
-                info: config.pair_info.asset_infos[1 ^ given_ind].clone(),
-                amount: Uint128::zero(),
-            });
+    let asset_infos = &config.pair_info.asset_infos;
+
+    if assets.is_empty() {
+        return Err(StdError::generic_err("Nothing to provide").into());
+    }
+    if assets.len() > asset_infos.len() {
+        return Err(ContractError::InvalidNumberOfAssets(asset_infos.len()));
+    }
+
+    // Append omitted assets with explicit zero amounts
+    if assets.len() < asset_infos.len() {
+        for asset in assets.iter() {
+            asset_position(config, &asset.info)?;
         }
-        2 => {}
-        _ => {
-            return Err(ContractError::InvalidNumberOfAssets(
-                config.pair_info.asset_infos.len(),
-            ))
+        for asset_info in asset_infos {
+            if !assets.iter().any(|asset| asset.info.equal(asset_info)) {
+                assets.push(Asset {
+                    info: asset_info.clone(),
+                    amount: Uint128::zero(),
+                });
+            }
         }
     }
 
     check_assets(deps.api, assets)?;
 
-    if pools[0].info.equal(&assets[1].info) {
-        assets.swap(0, 1);
-    }
+    // Sort assets in the pool order. Assets which don't belong to the pool are moved to the end
+    assets.sort_by_key(|asset| {
+        pools
+            .iter()
+            .position(|pool| pool.info.equal(&asset.info))
+            .unwrap_or(pools.len())
+    });
 
     // precisions.get_precision() also validates that the asset belongs to the pool
-    Ok(vec![
-        assets[0]
-            .amount
-            .to_decimal256(precisions.get_precision(&assets[0].info)?),
-        assets[1]
-            .amount
-            .to_decimal256(precisions.get_precision(&assets[1].info)?),
-    ])
+    assets
+        .iter()
+        .map(|asset| {
+            Ok(asset
+                .amount
+                .to_decimal256(precisions.get_precision(&asset.info)?))
+        })
+        .collect()
+}
+
+/// Returns prices of all assets quoted in the 1st asset implied by an imbalanced provide or withdrawal.
+/// In a 2-asset pool the action is a balanced one followed by a swap,
+/// thus the price is the ratio of the amounts diverged from the balanced share.
+/// With more assets the implied trades are ambiguous, so the spot prices of the new pool volumes are used.
+///
+/// * **assets_diff** - amounts diverged from the balanced share.
+/// * **new_xs** - pool volumes after the action.
+fn calc_imbalance_prices(
+    env: &Env,
+    config: &Config,
+    assets_diff: &[Decimal256],
+    new_xs: &[Decimal256],
+) -> StdResult<Vec<Decimal256>> {
+    if let [diff_0, diff_1] = assets_diff {
+        Ok(vec![Decimal256::one(), *diff_0 / *diff_1])
+    } else {
+        calc_last_prices(new_xs, config, env)
+    }
 }
 
 pub(crate) fn calculate_shares(
     env: &Env,
     config: &mut Config,
-    pools: &mut [DecimalAsset],
+    pools: &[DecimalAsset],
     total_share: Decimal256,
     deposits: Vec<Decimal256>,
     slippage_tolerance: Option<Decimal>,
 ) -> Result<(Uint128, Decimal256), ContractError> {
     // Initial provide can not be one-sided
-    if total_share.is_zero() && (deposits[0].is_zero() || deposits[1].is_zero()) {
+    if total_share.is_zero() && deposits.iter().any(|deposit| deposit.is_zero()) {
         return Err(ContractError::InvalidZeroAmount {});
     }
 
-    let mut new_xp = pools
+    let price_scales = config.pool_state.price_state.price_scales();
+
+    let new_xs = pools
+        .iter()
+        .zip(&deposits)
+        .map(|(pool, deposit)| pool.amount + deposit)
+        .collect_vec();
+    let new_xp = new_xs
         .iter()
-        .enumerate()
-        .map(|(ind, pool)| pool.amount + deposits[ind])
+        .zip(&price_scales)
+        .map(|(x, price_scale)| *x * price_scale)
         .collect_vec();
-    new_xp[1] *= config.pool_state.price_state.price_scale;
 
     let amp_gamma = config.pool_state.get_amp_gamma(env);
     let new_d = calc_d(&new_xp, &amp_gamma)?;
 
     let share = if total_share.is_zero() {
-        let xcp = get_xcp(new_d, config.pool_state.price_state.price_scale);
+        let xcp = get_xcp(new_d, &price_scales);
         let mint_amount = xcp
             .checked_sub(MINIMUM_LIQUIDITY_AMOUNT.to_decimal256(LP_TOKEN_PRECISION))
             .map_err(|_| ContractError::MinimumLiquidityAmountError {})?;
@@ -131,32 +485,35 @@ pub(crate) fn calculate_shares(
 
         mint_amount
     } else {
-        let mut old_xp = pools.iter().map(|a| a.amount).collect_vec();
-        old_xp[1] *= config.pool_state.price_state.price_scale;
+        let old_xp = pools
+            .iter()
+            .zip(&price_scales)
+            .map(|(pool, price_scale)| pool.amount * price_scale)
+            .collect_vec();
         let old_d = calc_d(&old_xp, &amp_gamma)?;
         let share = (total_share * new_d / old_d).saturating_sub(total_share);
 
-        let mut ideposits = deposits.clone();
-        ideposits[1] *= config.pool_state.price_state.price_scale;
+        let ideposits = deposits
+            .iter()
+            .zip(&price_scales)
+            .map(|(deposit, price_scale)| *deposit * price_scale)
+            .collect_vec();
 
         share * (Decimal256::one() - calc_provide_fee(&ideposits, &new_xp, &config.pool_params))
     };
 
     // calculate accrued share
     let share_ratio = share / (total_share + share);
-    let balanced_share = [
-        new_xp[0] * share_ratio,
-        new_xp[1] * share_ratio / config.pool_state.price_state.price_scale,
-    ];
-    let assets_diff = [
-        deposits[0].abs_diff(balanced_share[0]),
-        deposits[1].abs_diff(balanced_share[1]),
-    ];
+    let assets_diff = deposits
+        .iter()
+        .zip(new_xp.iter().zip(&price_scales))
+        .map(|(deposit, (xp, price_scale))| deposit.abs_diff(*xp * share_ratio / price_scale))
+        .collect_vec();
 
     let mut slippage = Decimal256::zero();
 
     // If deposit doesn't diverge too much from the balanced share, we don't update the price
-    if assets_diff[0] >= MIN_TRADE_SIZE && assets_diff[1] >= MIN_TRADE_SIZE {
+    if assets_diff.iter().all(|diff| *diff >= MIN_TRADE_SIZE) {
         slippage = assert_slippage_tolerance(
             &deposits,
             share,
@@ -164,13 +521,13 @@ pub(crate) fn calculate_shares(
             slippage_tolerance,
         )?;
 
-        let last_price = assets_diff[0] / assets_diff[1];
+        let last_prices = calc_imbalance_prices(env, config, &assets_diff, &new_xs)?;
         config.pool_state.update_price(
             &config.pool_params,
             env,
             total_share + share,
             &new_xp,
-            last_price,
+            &last_prices,
         )?;
     }
 
@@ -179,7 +536,7 @@ pub(crate) fn calculate_shares(
 
 pub fn ensure_min_assets_to_receive(
     config: &Config,
-    mut refund_assets: Vec<Asset>,
+    refund_assets: Vec<Asset>,
     min_assets_to_receive: Option<Vec<Asset>>,
 ) -> Result<(), ContractError> {
     if let Some(min_assets_to_receive) = min_assets_to_receive {
@@ -196,26 +553,97 @@ pub fn ensure_min_assets_to_receive(
             }
         }
 
-        if refund_assets[0].info.ne(&min_assets_to_receive[0].info) {
-            refund_assets.swap(0, 1)
-        }
-
-        if refund_assets[0].amount < min_assets_to_receive[0].amount {
-            return Err(ContractError::WithdrawSlippageViolation {
-                asset_name: refund_assets[0].info.to_string(),
-                received: refund_assets[0].amount,
-                expected: min_assets_to_receive[0].amount,
-            });
-        }
+        for min_asset in &min_assets_to_receive {
+            let received = refund_assets
+                .iter()
+                .find(|asset| asset.info == min_asset.info)
+                .map(|asset| asset.amount)
+                .unwrap_or_default();
 
-        if refund_assets[1].amount < min_assets_to_receive[1].amount {
-            return Err(ContractError::WithdrawSlippageViolation {
-                asset_name: refund_assets[1].info.to_string(),
-                received: refund_assets[1].amount,
-                expected: min_assets_to_receive[1].amount,
-            });
+            if received < min_asset.amount {
+                return Err(ContractError::WithdrawSlippageViolation {
+                    asset_name: min_asset.info.to_string(),
+                    received,
+                    expected: min_asset.amount,
+                });
+            }
         }
     }
 
     Ok(())
 }
+
+/// Result of a single asset withdrawal calculated by [`calc_withdraw_one_asset`].
+pub(crate) struct WithdrawOneAssetResult {
+    /// Amount of the asset to withdraw
+    pub amount: Decimal256,
+    /// Imbalance fee which stays in the pool
+    pub fee: Decimal256,
+    /// Internal representation of pool volumes after the withdrawal
+    pub new_xp: Vec<Decimal256>,
+    /// Prices of all assets implied by the withdrawal.
+    /// None if the withdrawal doesn't diverge much from the balanced share
+    pub last_prices: Option<Vec<Decimal256>>,
+}
+
+/// Calculates the amount of the asset at **ask_ind** received for burning **amount** LP tokens.
+/// D invariant is decreased pro-rata to the burnt LP tokens and the new asset volume is found
+/// with [`calc_y`]. The withdrawal is charged with the same imbalance fee as a one-sided provide.
+pub(crate) fn calc_withdraw_one_asset(
+    env: &Env,
+    config: &Config,
+    pools: &[DecimalAsset],
+    total_share: Decimal256,
+    amount: Decimal256,
+    ask_ind: usize,
+) -> Result<WithdrawOneAssetResult, ContractError> {
+    let price_scales = config.pool_state.price_state.price_scales();
+
+    let mut xp = pools
+        .iter()
+        .zip(&price_scales)
+        .map(|(pool, price_scale)| pool.amount * price_scale)
+        .collect_vec();
+
+    let amp_gamma = config.pool_state.get_amp_gamma(env);
+    let d = calc_d(&xp, &amp_gamma)?;
+    let new_d = d - d * amount / total_share;
+    let new_y = calc_y(&xp, new_d, &amp_gamma, ask_ind)?;
+
+    let mut withdrawn = vec![Decimal256::zero(); xp.len()];
+    withdrawn[ask_ind] = xp[ask_ind].saturating_sub(new_y);
+    xp[ask_ind] = new_y;
+
+    let mut fee = Decimal256::zero();
+    if !withdrawn[ask_ind].is_zero() {
+        fee = withdrawn[ask_ind] * calc_provide_fee(&withdrawn, &xp, &config.pool_params);
+    }
+    // The fee stays in the pool
+    xp[ask_ind] += fee;
+
+    let withdraw_amount = (withdrawn[ask_ind] - fee) / price_scales[ask_ind];
+    let fee = fee / price_scales[ask_ind];
+
+    // The withdrawal is a balanced one followed by swaps of the other assets
+    let share_ratio = amount / total_share;
+    let mut assets_diff = pools
+        .iter()
+        .map(|pool| pool.amount * share_ratio)
+        .collect_vec();
+    assets_diff[ask_ind] = withdraw_amount.abs_diff(assets_diff[ask_ind]);
+
+    let last_prices = if assets_diff.iter().all(|diff| *diff >= MIN_TRADE_SIZE) {
+        let mut new_xs = pools.iter().map(|pool| pool.amount).collect_vec();
+        new_xs[ask_ind] = new_xs[ask_ind].saturating_sub(withdraw_amount);
+        Some(calc_imbalance_prices(env, config, &assets_diff, &new_xs)?)
+    } else {
+        None
+    };
+
+    Ok(WithdrawOneAssetResult {
+        amount: withdraw_amount,
+        fee,
+        new_xp: xp,
+        last_prices,
+    })
+}
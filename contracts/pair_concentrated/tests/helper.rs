@@ -6,28 +6,32 @@ use anyhow::Result as AnyResult;
 use cosmwasm_schema::cw_serde;
 use cosmwasm_std::testing::MockApi;
 use cosmwasm_std::{
-    coin, from_json, to_json_binary, Addr, Coin, Decimal, Decimal256, Empty, StdError, StdResult,
-    Uint128,
+    coin, from_json, to_json_binary, wasm_execute, Addr, Binary, Coin, Decimal, Decimal256, Empty,
+    Response, StdError, StdResult, Uint128, WasmMsg,
 };
 use cw20::{BalanceResponse, Cw20Coin, Cw20ExecuteMsg, Cw20QueryMsg};
-use cw_multi_test::{App, AppBuilder, AppResponse, Contract, ContractWrapper, Executor};
+use cw_multi_test::{AppBuilder, AppResponse, Contract, ContractWrapper, Executor};
+use cw_storage_plus::Item;
 use derivative::Derivative;
 use itertools::Itertools;
 
 use astroport::asset::{native_asset_info, token_asset_info, Asset, AssetInfo, PairInfo};
 use astroport::factory::{PairConfig, PairType};
+use astroport::observation::Observation;
 use astroport::pair::{
-    ConfigResponse, CumulativePricesResponse, Cw20HookMsg, ExecuteMsg, PoolResponse,
-    ReverseSimulationResponse, SimulationResponse,
+    ConfigResponse, CumulativePricesResponse, Cw20HookMsg, ExecuteMsg, PairHookExecuteMsg,
+    PairHookMsg, PoolResponse, ReverseSimulationResponse, SimulationResponse,
 };
 use astroport::pair_concentrated::{
     ConcentratedPoolConfig, ConcentratedPoolParams, ConcentratedPoolUpdateParams, QueryMsg,
 };
+use astroport::stats::StatsResponse;
 use astroport_pair_concentrated::contract::{execute, instantiate, reply};
 use astroport_pair_concentrated::queries::query;
 use astroport_pcl_common::state::Config;
 use astroport_test::coins::TestCoin;
 use astroport_test::convert::f64_to_dec;
+use astroport_test::modules::stargate::{MockStargate, StargateApp};
 
 const INIT_BALANCE: u128 = u128::MAX;
 
@@ -43,6 +47,7 @@ pub fn common_pcl_params() -> ConcentratedPoolParams {
         price_scale: Decimal::one(),
         ma_half_time: 600,
         fee_share: None,
+        extra_price_scales: vec![],
     }
 }
 
@@ -106,30 +111,134 @@ fn generator() -> Box<dyn Contract<Empty>> {
     ))
 }
 
+/// Messages of a mock contract borrowing assets via flash swaps
+#[cw_serde]
+pub enum BorrowerMsg {
+    Borrow {
+        pair: String,
+        ask_asset: Asset,
+        callback_msg: Binary,
+    },
+    Repay {
+        pair: String,
+        assets: Vec<Asset>,
+    },
+    Call {
+        contract: String,
+        msg: Binary,
+    },
+}
+
+fn borrower_contract() -> Box<dyn Contract<Empty>> {
+    Box::new(ContractWrapper::new_with_empty(
+        |_, _, _, msg: BorrowerMsg| -> StdResult<Response> {
+            match msg {
+                BorrowerMsg::Borrow {
+                    pair,
+                    ask_asset,
+                    callback_msg,
+                } => Ok(Response::new().add_message(wasm_execute(
+                    pair,
+                    &ExecuteMsg::FlashSwap {
+                        ask_asset,
+                        callback_msg,
+                        deadline: None,
+                    },
+                    vec![],
+                )?)),
+                BorrowerMsg::Repay { pair, assets } => Ok(Response::new().add_messages(
+                    assets
+                        .into_iter()
+                        .map(|asset| asset.into_msg(&pair))
+                        .collect::<StdResult<Vec<_>>>()?,
+                )),
+                BorrowerMsg::Call { contract, msg } => {
+                    Ok(Response::new().add_message(WasmMsg::Execute {
+                        contract_addr: contract,
+                        msg,
+                        funds: vec![],
+                    }))
+                }
+            }
+        },
+        |_, _, _, _: Empty| -> StdResult<Response> { Ok(Response::new()) },
+        |_, _, _: Empty| -> StdResult<Binary> { unimplemented!() },
+    ))
+}
+
+const HOOK_CALLS: Item<Vec<PairHookMsg>> = Item::new("calls");
+const HOOK_FAILS: Item<bool> = Item::new("fails");
+
+/// A hook which records all notifications or always fails depending on the instantiate message
+fn hook_contract() -> Box<dyn Contract<Empty>> {
+    Box::new(ContractWrapper::new_with_empty(
+        |deps, _, _, msg: PairHookExecuteMsg| -> StdResult<Response> {
+            if HOOK_FAILS.load(deps.storage)? {
+                return Err(StdError::generic_err("Hook failed"));
+            }
+
+            let PairHookExecuteMsg::PairHook(msg) = msg;
+            let mut calls = HOOK_CALLS.load(deps.storage)?;
+            calls.push(msg);
+            HOOK_CALLS.save(deps.storage, &calls)?;
+
+            Ok(Response::new())
+        },
+        |deps, _, _, fails: bool| -> StdResult<Response> {
+            HOOK_FAILS.save(deps.storage, &fails)?;
+            HOOK_CALLS.save(deps.storage, &vec![])?;
+            Ok(Response::new())
+        },
+        |deps, _, _: Empty| -> StdResult<Binary> {
+            to_json_binary(&HOOK_CALLS.load(deps.storage)?)
+        },
+    ))
+}
+
 #[derive(Derivative)]
 #[derivative(Debug)]
 pub struct Helper {
     #[derivative(Debug = "ignore")]
-    pub app: App,
+    pub app: StargateApp,
     pub owner: Addr,
     pub assets: HashMap<TestCoin, AssetInfo>,
     pub factory: Addr,
     pub pair_addr: Addr,
     pub lp_token: String,
+    pub native_lp_token: bool,
     pub fake_maker: Addr,
     pub generator: Addr,
 }
 
 impl Helper {
     pub fn new(test_coins: Vec<TestCoin>, params: ConcentratedPoolParams) -> AnyResult<Self> {
+        Self::init(test_coins, params, false)
+    }
+
+    /// Creates a pair which issues LP shares as a token factory denom.
+    pub fn new_native_lp(
+        test_coins: Vec<TestCoin>,
+        params: ConcentratedPoolParams,
+    ) -> AnyResult<Self> {
+        Self::init(test_coins, params, true)
+    }
+
+    fn init(
+        test_coins: Vec<TestCoin>,
+        params: ConcentratedPoolParams,
+        native_lp_token: bool,
+    ) -> AnyResult<Self> {
         let api = MockApi::default();
         let owner = api.addr_make("owner");
-        let mut app = AppBuilder::new_custom().build(|router, _, storage| {
-            router
-                .bank
-                .init_balance(storage, &owner, init_native_coins(&test_coins))
-                .unwrap()
-        });
+        let mut app =
+            AppBuilder::new_custom()
+                .with_stargate(MockStargate)
+                .build(|router, _, storage| {
+                    router
+                        .bank
+                        .init_balance(storage, &owner, init_native_coins(&test_coins))
+                        .unwrap()
+                });
 
         let token_code_id = app.store_code(token_contract());
 
@@ -199,6 +308,7 @@ impl Helper {
                 is_disabled: false,
                 is_generator_disabled: false,
                 permissioned: false,
+                native_lp_token,
             }],
             token_code_id,
             incentives_address: None,
@@ -278,6 +388,7 @@ impl Helper {
             generator: generator_address,
             pair_addr: resp[0].contract_addr.clone(),
             lp_token: resp[0].liquidity_token.clone(),
+            native_lp_token,
             fake_maker,
         })
     }
@@ -305,6 +416,7 @@ impl Helper {
             auto_stake: Some(true),
             receiver: None,
             min_lp_to_receive: None,
+            deadline: None,
         };
 
         self.app
@@ -326,6 +438,7 @@ impl Helper {
             auto_stake: None,
             receiver: None,
             min_lp_to_receive: None,
+            deadline: None,
         };
 
         self.app
@@ -350,6 +463,7 @@ impl Helper {
             auto_stake,
             receiver,
             min_lp_to_receive,
+            deadline: None,
         };
 
         self.app
@@ -357,6 +471,29 @@ impl Helper {
     }
 
     pub fn withdraw_liquidity(&mut self, sender: &Addr, amount: u128) -> AnyResult<AppResponse> {
+        self.withdraw_liquidity_full(sender, amount, None, None)
+    }
+
+    pub fn withdraw_liquidity_full(
+        &mut self,
+        sender: &Addr,
+        amount: u128,
+        min_assets_to_receive: Option<Vec<Asset>>,
+        asset: Option<AssetInfo>,
+    ) -> AnyResult<AppResponse> {
+        if self.native_lp_token {
+            return self.app.execute_contract(
+                sender.clone(),
+                self.pair_addr.clone(),
+                &ExecuteMsg::WithdrawLiquidity {
+                    min_assets_to_receive,
+                    asset,
+                    deadline: None,
+                },
+                &[coin(amount, &self.lp_token)],
+            );
+        }
+
         self.app.execute_contract(
             sender.clone(),
             Addr::unchecked(&self.lp_token),
@@ -364,7 +501,9 @@ impl Helper {
                 contract: self.pair_addr.to_string(),
                 amount: amount.into(),
                 msg: to_json_binary(&Cw20HookMsg::WithdrawLiquidity {
-                    min_assets_to_receive: None,
+                    min_assets_to_receive,
+                    asset,
+                    deadline: None,
                 })
                 .unwrap(),
             },
@@ -372,6 +511,24 @@ impl Helper {
         )
     }
 
+    pub fn skim(&mut self, sender: &Addr, to: Option<String>) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            sender.clone(),
+            self.pair_addr.clone(),
+            &ExecuteMsg::Skim { to },
+            &[],
+        )
+    }
+
+    pub fn sync(&mut self, sender: &Addr) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            sender.clone(),
+            self.pair_addr.clone(),
+            &ExecuteMsg::Sync {},
+            &[],
+        )
+    }
+
     pub fn swap(
         &mut self,
         sender: &Addr,
@@ -387,6 +544,17 @@ impl Helper {
         offer_asset: &Asset,
         max_spread: Option<Decimal>,
         belief_price: Option<Decimal>,
+    ) -> AnyResult<AppResponse> {
+        self.swap_to(sender, offer_asset, None, max_spread, belief_price)
+    }
+
+    pub fn swap_to(
+        &mut self,
+        sender: &Addr,
+        offer_asset: &Asset,
+        ask_asset_info: Option<AssetInfo>,
+        max_spread: Option<Decimal>,
+        belief_price: Option<Decimal>,
     ) -> AnyResult<AppResponse> {
         match &offer_asset.info {
             AssetInfo::Token { contract_addr } => {
@@ -394,10 +562,11 @@ impl Helper {
                     contract: self.pair_addr.to_string(),
                     amount: offer_asset.amount,
                     msg: to_json_binary(&Cw20HookMsg::Swap {
-                        ask_asset_info: None,
+                        ask_asset_info,
                         belief_price,
                         max_spread,
                         to: None,
+                        deadline: None,
                     })
                     .unwrap(),
                 };
@@ -415,10 +584,11 @@ impl Helper {
 
                 let msg = ExecuteMsg::Swap {
                     offer_asset: offer_asset.clone(),
-                    ask_asset_info: None,
+                    ask_asset_info,
                     belief_price,
                     max_spread,
                     to: None,
+                    deadline: None,
                 };
 
                 self.app
@@ -427,6 +597,93 @@ impl Helper {
         }
     }
 
+    pub fn swap_exact_out(
+        &mut self,
+        sender: &Addr,
+        max_offer_asset: &Asset,
+        ask_asset: &Asset,
+        to: Option<String>,
+    ) -> AnyResult<AppResponse> {
+        match &max_offer_asset.info {
+            AssetInfo::Token { contract_addr } => {
+                let msg = Cw20ExecuteMsg::Send {
+                    contract: self.pair_addr.to_string(),
+                    amount: max_offer_asset.amount,
+                    msg: to_json_binary(&Cw20HookMsg::SwapExactOut {
+                        ask_asset: ask_asset.clone(),
+                        to,
+                        deadline: None,
+                    })
+                    .unwrap(),
+                };
+
+                self.app
+                    .execute_contract(sender.clone(), contract_addr.clone(), &msg, &[])
+            }
+            AssetInfo::NativeToken { .. } => {
+                let funds = max_offer_asset.mock_coin_sent(
+                    &mut self.app,
+                    sender,
+                    &self.pair_addr,
+                    SendType::None,
+                );
+
+                let msg = ExecuteMsg::SwapExactOut {
+                    ask_asset: ask_asset.clone(),
+                    max_offer_amount: max_offer_asset.amount,
+                    to,
+                    deadline: None,
+                };
+
+                self.app
+                    .execute_contract(sender.clone(), self.pair_addr.clone(), &msg, &funds)
+            }
+        }
+    }
+
+    pub fn init_borrower(&mut self) -> Addr {
+        let code_id = self.app.store_code(borrower_contract());
+        self.app
+            .instantiate_contract(
+                code_id,
+                self.owner.clone(),
+                &Empty {},
+                &[],
+                "Borrower",
+                None,
+            )
+            .unwrap()
+    }
+
+    pub fn init_hook(&mut self, fails: bool) -> Addr {
+        let code_id = self.app.store_code(hook_contract());
+        self.app
+            .instantiate_contract(code_id, self.owner.clone(), &fails, &[], "Hook", None)
+            .unwrap()
+    }
+
+    pub fn query_hook_calls(&self, hook: &Addr) -> StdResult<Vec<PairHookMsg>> {
+        self.app.wrap().query_wasm_smart(hook, &Empty {})
+    }
+
+    pub fn flash_swap(
+        &mut self,
+        borrower: &Addr,
+        ask_asset: &Asset,
+        callback: &BorrowerMsg,
+    ) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            self.owner.clone(),
+            borrower.clone(),
+            &BorrowerMsg::Borrow {
+                pair: self.pair_addr.to_string(),
+                ask_asset: ask_asset.clone(),
+                callback_msg: to_json_binary(callback).unwrap(),
+            },
+            &[],
+        )
+    }
+
     pub fn query_incentives_deposit(&self, denom: impl Into<String>, user: &Addr) -> Uint128 {
         self.app
             .wrap()
@@ -475,7 +732,7 @@ impl Helper {
     }
 
     fn init_token(
-        app: &mut App,
+        app: &mut StargateApp,
         token_code: u64,
         name: String,
         decimals: u8,
@@ -612,6 +869,46 @@ impl Helper {
             },
         )
     }
+
+    pub fn query_simulate_withdraw(
+        &self,
+        lp_amount: impl Into<Uint128>,
+        asset: Option<AssetInfo>,
+    ) -> StdResult<Vec<Asset>> {
+        self.app.wrap().query_wasm_smart::<Vec<Asset>>(
+            &self.pair_addr,
+            &QueryMsg::SimulateWithdraw {
+                lp_amount: lp_amount.into(),
+                asset,
+            },
+        )
+    }
+
+    pub fn query_observe(&self, seconds_ago: Vec<u64>) -> StdResult<Vec<Observation>> {
+        self.app
+            .wrap()
+            .query_wasm_smart(&self.pair_addr, &QueryMsg::Observe { seconds_ago })
+    }
+
+    pub fn query_stats(&self, epoch: Option<u64>) -> StdResult<StatsResponse> {
+        self.app
+            .wrap()
+            .query_wasm_smart(&self.pair_addr, &QueryMsg::Stats { epoch })
+    }
+
+    pub fn query_asset_balance_at(
+        &self,
+        asset_info: &AssetInfo,
+        block_height: u64,
+    ) -> StdResult<Option<Uint128>> {
+        self.app.wrap().query_wasm_smart(
+            &self.pair_addr,
+            &QueryMsg::AssetBalanceAt {
+                asset_info: asset_info.clone(),
+                block_height: block_height.into(),
+            },
+        )
+    }
 }
 
 #[derive(Clone, Copy)]
@@ -624,7 +921,7 @@ pub enum SendType {
 pub trait AssetExt {
     fn mock_coin_sent(
         &self,
-        app: &mut App,
+        app: &mut StargateApp,
         user: &Addr,
         spender: &Addr,
         typ: SendType,
@@ -634,7 +931,7 @@ pub trait AssetExt {
 impl AssetExt for Asset {
     fn mock_coin_sent(
         &self,
-        app: &mut App,
+        app: &mut StargateApp,
         user: &Addr,
         spender: &Addr,
         typ: SendType,
@@ -670,7 +967,7 @@ impl AssetExt for Asset {
 pub trait AssetsExt {
     fn mock_coins_sent(
         &self,
-        app: &mut App,
+        app: &mut StargateApp,
         user: &Addr,
         spender: &Addr,
         typ: SendType,
@@ -680,7 +977,7 @@ pub trait AssetsExt {
 impl AssetsExt for &[Asset] {
     fn mock_coins_sent(
         &self,
-        app: &mut App,
+        app: &mut StargateApp,
         user: &Addr,
         spender: &Addr,
         typ: SendType,
@@ -697,7 +994,7 @@ pub trait AppExtension {
     fn next_block(&mut self, time: u64);
 }
 
-impl AppExtension for App {
+impl AppExtension for StargateApp {
     fn next_block(&mut self, time: u64) {
         self.update_block(|block| {
             block.time = block.time.plus_seconds(time);
@@ -1,22 +1,36 @@
+#![allow(clippy::inconsistent_digit_grouping)]
 use std::str::FromStr;
 
-use cosmwasm_std::{Decimal, Decimal256, StdError, Uint128};
+use cosmwasm_std::testing::{mock_dependencies, mock_env, MOCK_CONTRACT_ADDR};
+use cosmwasm_std::{to_json_binary, Addr, Decimal, Decimal256, Empty, StdError, Uint128};
 use cw_multi_test::Executor;
+use cw_utils::PaymentError;
 use itertools::{max, Itertools};
 
-use astroport::asset::{native_asset_info, AssetInfoExt, MINIMUM_LIQUIDITY_AMOUNT};
+use astroport::asset::{native_asset_info, AssetInfoExt, PairInfo, MINIMUM_LIQUIDITY_AMOUNT};
 use astroport::cosmwasm_ext::IntegerToDecimal;
-use astroport::pair::{ExecuteMsg, PoolResponse, MAX_FEE_SHARE_BPS};
+use astroport::factory::{
+    ExecuteMsg as FactoryExecuteMsg, PairType, PauseScope, QueryMsg as FactoryQueryMsg,
+};
+use astroport::pair::{
+    ExecuteMsg, FeeShareConfig, FeeShareRecipient, LegacyFeeShareConfig, PairHook, PairHookMsg,
+    PoolResponse, TraderAllowlist, MAX_FEE_SHARE_BPS,
+};
 use astroport::pair_concentrated::{
     ConcentratedPoolParams, ConcentratedPoolUpdateParams, PromoteParams, QueryMsg, UpdatePoolParams,
 };
+use astroport::stats::STATS_EPOCH_LENGTH;
+use astroport_pair_concentrated::contract::migrate;
 use astroport_pair_concentrated::error::ContractError;
+use astroport_pair_concentrated::migration::{ConfigV410, CONFIG_V410};
+use astroport_pair_concentrated::state::{CONFIG, RESERVES};
 use astroport_pcl_common::consts::{AMP_MAX, AMP_MIN, MA_HALF_TIME_LIMITS};
 use astroport_pcl_common::error::PclError;
+use astroport_pcl_common::state::{AmpGamma, PoolParams, PoolState, PriceState};
 use astroport_test::coins::TestCoin;
 use astroport_test::convert::{dec_to_f64, f64_to_dec};
 
-use crate::helper::{common_pcl_params, AppExtension, Helper};
+use crate::helper::{common_pcl_params, AppExtension, BorrowerMsg, Helper};
 
 mod helper;
 
@@ -31,7 +45,7 @@ fn check_wrong_initialization() {
 
     assert_eq!(
         err.root_cause().to_string(),
-        "Generic error: asset_infos must contain exactly two elements",
+        "Invalid number of assets. This pair supports from 2 to 3 assets",
     );
 
     let mut wrong_params = params.clone();
@@ -148,7 +162,7 @@ fn provide_and_withdraw() {
 
     // Provide just one asset which does not belong to the pair
     let err = helper
-        .provide_liquidity(&user1, &[random_coin.clone()])
+        .provide_liquidity(&user1, std::slice::from_ref(&random_coin))
         .unwrap_err();
     assert_eq!(
         "The asset random-coin does not belong to the pair",
@@ -422,7 +436,7 @@ fn swap_different_precisions() {
     assert_eq!(reverse_sim_resp.commission_amount.u128(), 45084);
     assert_eq!(reverse_sim_resp.spread_amount.u128(), 125);
 
-    helper.give_me_money(&[offer_asset.clone()], &user);
+    helper.give_me_money(std::slice::from_ref(&offer_asset), &user);
     helper.swap(&user, &offer_asset, None).unwrap();
 
     assert_eq!(0, helper.coin_balance(&test_coins[0], &user));
@@ -533,7 +547,7 @@ fn check_swaps_simple() {
 
     let user = helper.app.api().addr_make("user");
     let offer_asset = helper.assets[&test_coins[0]].with_balance(100_000000u128);
-    helper.give_me_money(&[offer_asset.clone()], &user);
+    helper.give_me_money(std::slice::from_ref(&offer_asset), &user);
 
     // Check swap does not work if pool is empty
     let err = helper.swap(&user, &offer_asset, None).unwrap_err();
@@ -545,7 +559,7 @@ fn check_swaps_simple() {
     // Try to swap a wrong asset
     let wrong_coin = native_asset_info("random-coin".to_string());
     let wrong_asset = wrong_coin.with_balance(100_000000u128);
-    helper.give_me_money(&[wrong_asset.clone()], &user);
+    helper.give_me_money(std::slice::from_ref(&wrong_asset), &user);
     let err = helper.swap(&user, &wrong_asset, None).unwrap_err();
     assert_eq!(
         ContractError::InvalidAsset(wrong_coin.to_string()),
@@ -570,6 +584,7 @@ fn check_swaps_simple() {
                 belief_price: None,
                 max_spread: None,
                 to: None,
+                deadline: None,
             },
             &[],
         )
@@ -585,7 +600,7 @@ fn check_swaps_simple() {
     assert_eq!(99_737929, helper.coin_balance(&test_coins[1], &user));
 
     let offer_asset = helper.assets[&test_coins[0]].with_balance(90_000_000000u128);
-    helper.give_me_money(&[offer_asset.clone()], &user);
+    helper.give_me_money(std::slice::from_ref(&offer_asset), &user);
     let err = helper.swap(&user, &offer_asset, None).unwrap_err();
     assert_eq!(
         ContractError::PclError(PclError::MaxSpreadAssertion {}),
@@ -594,7 +609,7 @@ fn check_swaps_simple() {
 
     let user2 = helper.app.api().addr_make("user2");
     let offer_asset = helper.assets[&test_coins[1]].with_balance(100_000000u128);
-    helper.give_me_money(&[offer_asset.clone()], &user2);
+    helper.give_me_money(std::slice::from_ref(&offer_asset), &user2);
     helper.swap(&user2, &offer_asset, None).unwrap();
     assert_eq!(0, helper.coin_balance(&test_coins[1], &user2));
     assert_eq!(99_741246, helper.coin_balance(&test_coins[0], &user2));
@@ -627,7 +642,7 @@ fn check_swaps_with_price_update() {
     let mut prev_vlp_price = helper.query_lp_price().unwrap();
 
     for i in 0..4 {
-        helper.give_me_money(&[offer_asset.clone()], &user1);
+        helper.give_me_money(std::slice::from_ref(&offer_asset), &user1);
         helper.swap(&user1, &offer_asset, Some(half)).unwrap();
         let new_vlp_price = helper.query_lp_price().unwrap();
         assert!(
@@ -640,7 +655,7 @@ fn check_swaps_with_price_update() {
 
     let offer_asset = helper.assets[&test_coins[0]].with_balance(10_000_000000u128);
     for _i in 0..4 {
-        helper.give_me_money(&[offer_asset.clone()], &user1);
+        helper.give_me_money(std::slice::from_ref(&offer_asset), &user1);
         helper.swap(&user1, &offer_asset, Some(half)).unwrap();
         helper.app.next_block(1000);
     }
@@ -665,7 +680,7 @@ fn provides_and_swaps() {
 
     let user = helper.app.api().addr_make("user");
     let offer_asset = helper.assets[&test_coins[0]].with_balance(100_000000u128);
-    helper.give_me_money(&[offer_asset.clone()], &user);
+    helper.give_me_money(std::slice::from_ref(&offer_asset), &user);
     helper.swap(&user, &offer_asset, None).unwrap();
 
     let provider = helper.app.api().addr_make("provider");
@@ -677,13 +692,13 @@ fn provides_and_swaps() {
     helper.provide_liquidity(&provider, &assets).unwrap();
 
     let offer_asset = helper.assets[&test_coins[1]].with_balance(100_000000u128);
-    helper.give_me_money(&[offer_asset.clone()], &user);
+    helper.give_me_money(std::slice::from_ref(&offer_asset), &user);
     helper.swap(&user, &offer_asset, None).unwrap();
 
     helper.withdraw_liquidity(&provider, 999_999354).unwrap();
 
     let offer_asset = helper.assets[&test_coins[0]].with_balance(100_000000u128);
-    helper.give_me_money(&[offer_asset.clone()], &user);
+    helper.give_me_money(std::slice::from_ref(&offer_asset), &user);
     helper.swap(&user, &offer_asset, None).unwrap();
 }
 
@@ -815,7 +830,7 @@ fn check_prices() {
 
     let user1 = helper.app.api().addr_make("user1");
     let offer_asset = helper.assets[&test_coins[0]].with_balance(1000_000000u128);
-    helper.give_me_money(&[offer_asset.clone()], &user1);
+    helper.give_me_money(std::slice::from_ref(&offer_asset), &user1);
 
     helper.swap(&user1, &offer_asset, None).unwrap();
     check_prices(&helper);
@@ -834,7 +849,7 @@ fn check_prices() {
     helper.app.next_block(14 * 86400);
 
     let offer_asset = helper.assets[&test_coins[1]].with_balance(10_000_000000u128);
-    helper.give_me_money(&[offer_asset.clone()], &user1);
+    helper.give_me_money(std::slice::from_ref(&offer_asset), &user1);
     helper.swap(&user1, &offer_asset, None).unwrap();
     check_prices(&helper);
 }
@@ -979,23 +994,23 @@ fn provides_and_swaps_and_withdraw() {
     // swap uluna
     let user = helper.app.api().addr_make("user");
     let offer_asset = helper.assets[&test_coins[0]].with_balance(1000_000000u128);
-    helper.give_me_money(&[offer_asset.clone()], &user);
+    helper.give_me_money(std::slice::from_ref(&offer_asset), &user);
     helper.swap(&user, &offer_asset, Some(half)).unwrap();
 
     helper.app.next_block(1000);
 
     // swap usdc
     let offer_asset = helper.assets[&test_coins[1]].with_balance(1000_000000u128);
-    helper.give_me_money(&[offer_asset.clone()], &user);
+    helper.give_me_money(std::slice::from_ref(&offer_asset), &user);
     helper.swap(&user, &offer_asset, Some(half)).unwrap();
 
     let offer_asset = helper.assets[&test_coins[1]].with_balance(100_000000u128);
-    helper.give_me_money(&[offer_asset.clone()], &user);
+    helper.give_me_money(std::slice::from_ref(&offer_asset), &user);
     helper.swap(&user, &offer_asset, Some(half)).unwrap();
 
     // swap uluna
     let offer_asset = helper.assets[&test_coins[0]].with_balance(100_000000u128);
-    helper.give_me_money(&[offer_asset.clone()], &user);
+    helper.give_me_money(std::slice::from_ref(&offer_asset), &user);
     helper.swap(&user, &offer_asset, Some(half)).unwrap();
     let res: PoolResponse = helper
         .app
@@ -1040,6 +1055,120 @@ fn provide_liquidity_with_autostaking_to_generator() {
     assert_eq!(amount, Uint128::new(99003));
 }
 
+#[test]
+fn provide_and_withdraw_with_native_lp_token() {
+    let test_coins = vec![TestCoin::native("uluna"), TestCoin::native("uusdc")];
+
+    let params = ConcentratedPoolParams {
+        price_scale: Decimal::from_ratio(1u8, 2u8),
+        ..common_pcl_params()
+    };
+    let mut helper = Helper::new_native_lp(test_coins.clone(), params).unwrap();
+    let owner = helper.owner.clone();
+
+    assert_eq!(
+        helper.lp_token,
+        format!("factory/{}/astroport/share", helper.pair_addr)
+    );
+    let pair_info: PairInfo = helper
+        .app
+        .wrap()
+        .query_wasm_smart(
+            &helper.factory,
+            &FactoryQueryMsg::PairByLpToken {
+                lp_token: helper.lp_token.clone(),
+            },
+        )
+        .unwrap();
+    assert_eq!(pair_info.contract_addr, helper.pair_addr);
+
+    let assets = vec![
+        helper.assets[&test_coins[0]].with_balance(100_000_000000u128),
+        helper.assets[&test_coins[1]].with_balance(50_000_000000u128),
+    ];
+    helper.provide_liquidity(&owner, &assets).unwrap();
+
+    let lp_amount = helper.native_balance(&helper.lp_token, &owner);
+    assert_eq!(lp_amount, 71039_654698);
+    assert_eq!(
+        helper.native_balance(&helper.lp_token, &helper.pair_addr),
+        MINIMUM_LIQUIDITY_AMOUNT.u128()
+    );
+    assert_eq!(
+        helper.query_pool().unwrap().total_share.u128(),
+        lp_amount + MINIMUM_LIQUIDITY_AMOUNT.u128()
+    );
+
+    // LP tokens must be sent along with the withdrawal message
+    let err = helper
+        .app
+        .execute_contract(
+            owner.clone(),
+            helper.pair_addr.clone(),
+            &ExecuteMsg::WithdrawLiquidity {
+                min_assets_to_receive: None,
+                asset: None,
+                deadline: None,
+            },
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::PaymentError(PaymentError::NoFunds {})
+    );
+
+    helper.withdraw_liquidity(&owner, lp_amount / 2).unwrap();
+
+    let lp_left = lp_amount - lp_amount / 2;
+    assert_eq!(helper.native_balance(&helper.lp_token, &owner), lp_left);
+    assert_eq!(
+        helper.query_pool().unwrap().total_share.u128(),
+        lp_left + MINIMUM_LIQUIDITY_AMOUNT.u128()
+    );
+
+    // LP tokens are staked in the incentives contract by their native denom
+    helper
+        .provide_liquidity_with_auto_staking(&owner, &assets, None)
+        .unwrap();
+    let amount = helper.query_incentives_deposit(&helper.lp_token, &owner);
+    assert_eq!(
+        helper.query_pool().unwrap().total_share.u128(),
+        lp_left + amount.u128() + MINIMUM_LIQUIDITY_AMOUNT.u128()
+    );
+    assert_eq!(helper.native_balance(&helper.lp_token, &owner), lp_left);
+}
+
+#[test]
+fn withdraw_with_funds_requires_native_lp_token() {
+    let test_coins = vec![TestCoin::native("uluna"), TestCoin::native("uusdc")];
+
+    let params = ConcentratedPoolParams {
+        price_scale: Decimal::from_ratio(1u8, 2u8),
+        ..common_pcl_params()
+    };
+    let mut helper = Helper::new(test_coins.clone(), params).unwrap();
+    let owner = helper.owner.clone();
+
+    let err = helper
+        .app
+        .execute_contract(
+            owner,
+            helper.pair_addr.clone(),
+            &ExecuteMsg::WithdrawLiquidity {
+                min_assets_to_receive: None,
+                asset: None,
+                deadline: None,
+            },
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::NonSupported {}
+    );
+}
+
 #[test]
 fn provide_withdraw_provide() {
     let test_coins = vec![TestCoin::native("uusd"), TestCoin::native("uluna")];
@@ -1190,50 +1319,40 @@ fn test_frontrun_before_initial_provide() {
         helper.assets[&test_coins[1]].with_balance(1_000000u128),
     ];
     helper.provide_liquidity(&owner, &assets).unwrap();
-    // Now pool became imbalanced with value (10010, 1)  (or in internal representation (10010, 10))
-    // while price scale stays 10
+
+    // The donation doesn't affect the pool as reserves are tracked internally
+    let pool = helper.query_pool().unwrap();
+    assert_eq!(pool.assets[0].amount.u128(), 10_000000);
+    assert_eq!(pool.assets[1].amount.u128(), 1_000000);
 
     let arber = helper.app.api().addr_make("arber");
     let offer_asset_luna = helper.assets[&test_coins[1]].with_balance(1_000000u128);
-    // Arber spinning pool back to balanced state
-    loop {
-        helper.app.next_block(10);
-        helper.give_me_money(&[offer_asset_luna.clone()], &arber);
-        // swapping until price satisfies an arber
-        if helper
-            .swap_full_params(
-                &arber,
-                &offer_asset_luna,
-                Some(f64_to_dec(0.02)),
-                Some(f64_to_dec(0.1)), // imagine market price is 10 -> i.e. inverted price is 1/10
-            )
-            .is_err()
-        {
-            break;
-        }
-    }
-
-    // price scale changed, however it isn't equal to 10 because of repegging
-    // But next swaps will align price back to the market value
-    let config = helper.query_config().unwrap();
-    let price_scale = config.pool_state.price_state.price_scale;
-    assert!(
-        dec_to_f64(price_scale) - 77.255853 < 1e-5,
-        "price_scale: {price_scale} is far from expected price",
+    helper.give_me_money(std::slice::from_ref(&offer_asset_luna), &arber);
+    // Swapping luna at the market price is not profitable anymore
+    let err = helper
+        .swap_full_params(
+            &arber,
+            &offer_asset_luna,
+            Some(f64_to_dec(0.02)),
+            Some(f64_to_dec(0.1)), // imagine market price is 10 -> i.e. inverted price is 1/10
+        )
+        .unwrap_err();
+    assert_eq!(
+        ContractError::PclError(PclError::MaxSpreadAssertion {}),
+        err.downcast().unwrap(),
     );
 
-    // Arber collected significant profit (denominated in uusd)
-    // Essentially 10_000 - fees (which settled in the pool)
-    let arber_balance = helper.coin_balance(&test_coins[0], &arber);
-    assert_eq!(arber_balance, 9667_528248);
-
-    // Pool's TVL increased from (10, 1) i.e. 20 to (320, 32) i.e. 640 considering market price is 10.0
-    let pools = config
-        .pair_info
-        .query_pools(&helper.app.wrap(), &helper.pair_addr)
-        .unwrap();
-    assert_eq!(pools[0].amount.u128(), 320_624088);
-    assert_eq!(pools[1].amount.u128(), 32_000000);
+    // Anyone can send the donation to themselves
+    let uusd_before = helper.coin_balance(&test_coins[0], &arber);
+    helper.skim(&arber, None).unwrap();
+    assert_eq!(
+        helper.coin_balance(&test_coins[0], &arber) - uusd_before,
+        10_000_000000
+    );
+    assert_eq!(
+        helper.coin_balance(&test_coins[0], &helper.pair_addr),
+        10_000000
+    );
 }
 
 #[test]
@@ -1277,8 +1396,10 @@ fn check_correct_fee_share() {
 
     let config = helper.query_config().unwrap();
     let fee_share = config.fee_share.unwrap();
-    assert_eq!(fee_share.bps, 1000u16);
-    assert_eq!(fee_share.recipient, share_recipient);
+    assert_eq!(
+        fee_share,
+        FeeShareConfig::single(share_recipient.clone(), 1000)
+    );
 
     helper.app.next_block(1000);
 
@@ -1292,7 +1413,7 @@ fn check_correct_fee_share() {
 
     let user = helper.app.api().addr_make("user");
     let offer_asset = helper.assets[&test_coins[0]].with_balance(100_000000u128);
-    helper.give_me_money(&[offer_asset.clone()], &user);
+    helper.give_me_money(std::slice::from_ref(&offer_asset), &user);
     helper.swap(&user, &offer_asset, None).unwrap();
 
     let last_price = helper
@@ -1320,7 +1441,7 @@ fn check_correct_fee_share() {
     helper.provide_liquidity(&provider, &assets).unwrap();
 
     let offer_asset = helper.assets[&test_coins[1]].with_balance(100_000000u128);
-    helper.give_me_money(&[offer_asset.clone()], &user);
+    helper.give_me_money(std::slice::from_ref(&offer_asset), &user);
     helper.swap(&user, &offer_asset, None).unwrap();
 
     let last_price = helper
@@ -1337,7 +1458,7 @@ fn check_correct_fee_share() {
     helper.withdraw_liquidity(&provider, 999_999354).unwrap();
 
     let offer_asset = helper.assets[&test_coins[0]].with_balance(100_000000u128);
-    helper.give_me_money(&[offer_asset.clone()], &user);
+    helper.give_me_money(std::slice::from_ref(&offer_asset), &user);
     helper.swap(&user, &offer_asset, None).unwrap();
 
     let last_price = helper
@@ -1359,6 +1480,93 @@ fn check_correct_fee_share() {
     assert!(config.fee_share.is_none());
 }
 
+#[test]
+fn check_fee_share_recipients() {
+    let test_coins = vec![TestCoin::native("uluna"), TestCoin::native("uusdc")];
+
+    let mut helper = Helper::new(test_coins.clone(), common_pcl_params()).unwrap();
+    let owner = helper.owner.clone();
+
+    let frontend = helper.app.api().addr_make("frontend");
+    let project = helper.app.api().addr_make("project");
+    let dao = helper.app.api().addr_make("dao");
+
+    for (recipients, expected_err) in [
+        (vec![], ContractError::FeeShareOutOfBounds {}),
+        (
+            vec![(frontend.to_string(), 500), (dao.to_string(), 0)],
+            ContractError::FeeShareOutOfBounds {},
+        ),
+        (
+            vec![
+                (frontend.to_string(), 500),
+                (dao.to_string(), MAX_FEE_SHARE_BPS),
+            ],
+            ContractError::FeeShareOutOfBounds {},
+        ),
+        (
+            vec![(frontend.to_string(), 500), (frontend.to_string(), 500)],
+            ContractError::DuplicateFeeShareRecipients {},
+        ),
+    ] {
+        let action = ConcentratedPoolUpdateParams::EnableFeeShareRecipients { recipients };
+        let err = helper.update_config(&owner, &action).unwrap_err();
+        assert_eq!(err.downcast::<ContractError>().unwrap(), expected_err);
+    }
+
+    helper.app.next_block(1000);
+
+    let action = ConcentratedPoolUpdateParams::EnableFeeShareRecipients {
+        recipients: vec![
+            (frontend.to_string(), 500),
+            (project.to_string(), 300),
+            (dao.to_string(), 200),
+        ],
+    };
+    helper.update_config(&owner, &action).unwrap();
+
+    let config = helper.query_config().unwrap();
+    assert_eq!(
+        config.fee_share.unwrap(),
+        FeeShareConfig {
+            recipients: vec![
+                FeeShareRecipient {
+                    recipient: frontend.clone(),
+                    bps: 500
+                },
+                FeeShareRecipient {
+                    recipient: project.clone(),
+                    bps: 300
+                },
+                FeeShareRecipient {
+                    recipient: dao.clone(),
+                    bps: 200
+                },
+            ]
+        }
+    );
+
+    helper.app.next_block(1000);
+
+    let assets = vec![
+        helper.assets[&test_coins[0]].with_balance(100_000_000000u128),
+        helper.assets[&test_coins[1]].with_balance(100_000_000000u128),
+    ];
+    helper.provide_liquidity(&owner, &assets).unwrap();
+
+    helper.app.next_block(1000);
+
+    let user = helper.app.api().addr_make("user");
+    let offer_asset = helper.assets[&test_coins[0]].with_balance(100_000000u128);
+    helper.give_me_money(std::slice::from_ref(&offer_asset), &user);
+    helper.swap(&user, &offer_asset, None).unwrap();
+
+    // The total share is the same as with a single 10% recipient in check_correct_fee_share
+    assert_eq!(helper.coin_balance(&test_coins[1], &frontend), 13040);
+    assert_eq!(helper.coin_balance(&test_coins[1], &project), 7824);
+    assert_eq!(helper.coin_balance(&test_coins[1], &dao), 5217);
+}
+
 #[test]
 fn check_small_trades() {
     let test_coins = vec![TestCoin::native("uusd"), TestCoin::native("uluna")];
@@ -1534,6 +1742,7 @@ fn check_lsd_swaps_with_price_update() {
         price_scale: f64_to_dec(price_scale),
         ma_half_time: 600,
         fee_share: None,
+        extra_price_scales: vec![],
     };
     let mut helper = Helper::new(test_coins.clone(), params).unwrap();
     let owner = helper.owner.clone();
@@ -1569,19 +1778,200 @@ fn check_lsd_swaps_with_price_update() {
     let offer_asset = helper.assets[&test_coins[0]].with_balance(1e16 as u128);
 
     for _ in 0..10 {
-        helper.give_me_money(&[offer_asset.clone()], &user1);
+        helper.give_me_money(std::slice::from_ref(&offer_asset), &user1);
         helper.swap(&user1, &offer_asset, Some(half)).unwrap();
         helper.app.next_block(1000);
     }
 
     let offer_asset = helper.assets[&test_coins[1]].with_balance(1e16 as u128);
     for _ in 0..10 {
-        helper.give_me_money(&[offer_asset.clone()], &user1);
+        helper.give_me_money(std::slice::from_ref(&offer_asset), &user1);
         helper.swap(&user1, &offer_asset, Some(half)).unwrap();
         helper.app.next_block(1000);
     }
 }
 
+#[test]
+fn check_protocol_fee_accrual() {
+    let test_coins = vec![TestCoin::native("uluna"), TestCoin::native("uusdc")];
+
+    let mut helper = Helper::new(test_coins.clone(), common_pcl_params()).unwrap();
+    let owner = helper.owner.clone();
+    let maker = helper.fake_maker.clone();
+
+    let err = helper
+        .app
+        .execute_contract(
+            owner.clone(),
+            helper.pair_addr.clone(),
+            &ExecuteMsg::CollectProtocolFees {},
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::ProtocolFeeAccrualDisabled {}
+    );
+
+    let assets = vec![
+        helper.assets[&test_coins[0]].with_balance(100_000_000000u128),
+        helper.assets[&test_coins[1]].with_balance(100_000_000000u128),
+    ];
+    helper.provide_liquidity(&owner, &assets).unwrap();
+
+    helper
+        .update_config(
+            &owner,
+            &ConcentratedPoolUpdateParams::EnableProtocolFeeAccrual,
+        )
+        .unwrap();
+    assert!(helper.query_config().unwrap().protocol_fee_accrual);
+
+    // Swaps no longer send the maker fee directly
+    let user = helper.app.api().addr_make("user");
+    for _ in 0..5 {
+        helper.app.next_block(1000);
+        let offer_asset = helper.assets[&test_coins[0]].with_balance(1_000_000000u128);
+        helper.give_me_money(std::slice::from_ref(&offer_asset), &user);
+        helper.swap(&user, &offer_asset, None).unwrap();
+        helper.app.next_block(1000);
+        let offer_asset = helper.assets[&test_coins[1]].with_balance(1_000_000000u128);
+        helper.give_me_money(std::slice::from_ref(&offer_asset), &user);
+        helper.swap(&user, &offer_asset, None).unwrap();
+    }
+    assert_eq!(helper.coin_balance(&test_coins[0], &maker), 0);
+    assert_eq!(helper.coin_balance(&test_coins[1], &maker), 0);
+    assert_eq!(helper.token_balance(&helper.lp_token, &maker), 0);
+
+    // The accrued protocol fee is minted as LP tokens to the maker
+    helper
+        .app
+        .execute_contract(
+            user.clone(),
+            helper.pair_addr.clone(),
+            &ExecuteMsg::CollectProtocolFees {},
+            &[],
+        )
+        .unwrap();
+    let maker_lp = helper.token_balance(&helper.lp_token, &maker);
+    assert!(maker_lp > 0);
+
+    // Nothing else accrued since the last collection
+    helper
+        .app
+        .execute_contract(
+            user.clone(),
+            helper.pair_addr.clone(),
+            &ExecuteMsg::CollectProtocolFees {},
+            &[],
+        )
+        .unwrap();
+    assert_eq!(helper.token_balance(&helper.lp_token, &maker), maker_lp);
+
+    // The maker can redeem its share for pool assets
+    helper.withdraw_liquidity(&maker, maker_lp).unwrap();
+    assert!(helper.coin_balance(&test_coins[0], &maker) > 0);
+    assert!(helper.coin_balance(&test_coins[1], &maker) > 0);
+
+    // Once disabled, the maker fee is sent on every swap again
+    helper
+        .update_config(
+            &owner,
+            &ConcentratedPoolUpdateParams::DisableProtocolFeeAccrual,
+        )
+        .unwrap();
+    assert!(!helper.query_config().unwrap().protocol_fee_accrual);
+
+    let maker_usdc = helper.coin_balance(&test_coins[1], &maker);
+    helper.app.next_block(1000);
+    let offer_asset = helper.assets[&test_coins[0]].with_balance(1_000_000000u128);
+    helper.give_me_money(std::slice::from_ref(&offer_asset), &user);
+    helper.swap(&user, &offer_asset, None).unwrap();
+    assert!(helper.coin_balance(&test_coins[1], &maker) > maker_usdc);
+}
+
+#[test]
+fn check_deadline() {
+    let test_coins = vec![TestCoin::native("uluna"), TestCoin::native("uusdc")];
+
+    let mut helper = Helper::new(test_coins.clone(), common_pcl_params()).unwrap();
+    let owner = helper.owner.clone();
+
+    let assets = vec![
+        helper.assets[&test_coins[0]].with_balance(100_000_000000u128),
+        helper.assets[&test_coins[1]].with_balance(100_000_000000u128),
+    ];
+    helper.provide_liquidity(&owner, &assets).unwrap();
+
+    let now = helper.app.block_info().time.seconds();
+    let user = helper.app.api().addr_make("user");
+    let offer_asset = helper.assets[&test_coins[0]].with_balance(100_000000u128);
+    helper.give_me_money(std::slice::from_ref(&offer_asset), &user);
+
+    let swap_msg = |deadline| ExecuteMsg::Swap {
+        offer_asset: offer_asset.clone(),
+        ask_asset_info: None,
+        belief_price: None,
+        max_spread: None,
+        to: None,
+        deadline: Some(deadline),
+    };
+    let funds = [offer_asset.as_coin().unwrap()];
+
+    let err = helper
+        .app
+        .execute_contract(
+            user.clone(),
+            helper.pair_addr.clone(),
+            &swap_msg(now - 1),
+            &funds,
+        )
+        .unwrap_err();
+    assert_eq!(ContractError::DeadlineExceeded {}, err.downcast().unwrap());
+
+    helper
+        .app
+        .execute_contract(
+            user.clone(),
+            helper.pair_addr.clone(),
+            &swap_msg(now),
+            &funds,
+        )
+        .unwrap();
+
+    let max_offer_asset = helper.assets[&test_coins[0]].with_balance(2_000000u128);
+    let ask_asset = helper.assets[&test_coins[1]].with_balance(1_000000u128);
+    helper.give_me_money(std::slice::from_ref(&max_offer_asset), &user);
+    let swap_exact_out_msg = |deadline| ExecuteMsg::SwapExactOut {
+        ask_asset: ask_asset.clone(),
+        max_offer_amount: max_offer_asset.amount,
+        to: None,
+        deadline: Some(deadline),
+    };
+    let funds = [max_offer_asset.as_coin().unwrap()];
+
+    let err = helper
+        .app
+        .execute_contract(
+            user.clone(),
+            helper.pair_addr.clone(),
+            &swap_exact_out_msg(now - 1),
+            &funds,
+        )
+        .unwrap_err();
+    assert_eq!(ContractError::DeadlineExceeded {}, err.downcast().unwrap());
+
+    helper
+        .app
+        .execute_contract(
+            user.clone(),
+            helper.pair_addr.clone(),
+            &swap_exact_out_msg(now),
+            &funds,
+        )
+        .unwrap();
+}
+
 #[test]
 fn test_provide_liquidity_without_funds() {
     let test_coins = vec![TestCoin::native("uluna"), TestCoin::native("uusdc")];
@@ -1612,6 +2002,7 @@ fn test_provide_liquidity_without_funds() {
         auto_stake: None,
         receiver: None,
         min_lp_to_receive: None,
+        deadline: None,
     };
 
     let err = helper
@@ -1624,3 +2015,1169 @@ fn test_provide_liquidity_without_funds() {
         "Generic error: Native token balance mismatch between the argument (100000000000uluna) and the transferred (0uluna)"
     )
 }
+
+#[test]
+fn check_asset_balances_tracking() {
+    let test_coins = vec![TestCoin::native("uluna"), TestCoin::cw20("USDC")];
+
+    let mut helper = Helper::new(test_coins.clone(), common_pcl_params()).unwrap();
+    let owner = helper.owner.clone();
+    let asset_infos = test_coins
+        .iter()
+        .map(|coin| helper.assets[coin].clone())
+        .collect_vec();
+
+    let query_balances_at = |helper: &Helper, height: u64| {
+        asset_infos
+            .iter()
+            .map(|info| helper.query_asset_balance_at(info, height).unwrap())
+            .collect_vec()
+    };
+    let query_pool_balances = |helper: &Helper| {
+        helper
+            .query_pool()
+            .unwrap()
+            .assets
+            .into_iter()
+            .map(|asset| Some(asset.amount))
+            .collect_vec()
+    };
+
+    let provide_height = helper.app.block_info().height;
+    assert_eq!(query_balances_at(&helper, provide_height), vec![None, None]);
+
+    let assets = vec![
+        helper.assets[&test_coins[0]].with_balance(100_000_000000u128),
+        helper.assets[&test_coins[1]].with_balance(100_000_000000u128),
+    ];
+    helper.provide_liquidity(&owner, &assets).unwrap();
+
+    // The snapshot taken at some height is visible starting from the next block
+    assert_eq!(query_balances_at(&helper, provide_height), vec![None, None]);
+    helper.app.next_block(1000);
+    assert_eq!(
+        query_balances_at(&helper, provide_height + 1),
+        vec![
+            Some(Uint128::new(100_000_000000)),
+            Some(Uint128::new(100_000_000000))
+        ]
+    );
+
+    let swap_height = helper.app.block_info().height;
+    let offer_asset = helper.assets[&test_coins[0]].with_balance(1_000_000000u128);
+    helper.give_me_money(std::slice::from_ref(&offer_asset), &owner);
+    helper.swap(&owner, &offer_asset, None).unwrap();
+    let balances_after_swap = query_pool_balances(&helper);
+    helper.app.next_block(1000);
+    assert_eq!(
+        query_balances_at(&helper, swap_height + 1),
+        balances_after_swap
+    );
+
+    let withdraw_height = helper.app.block_info().height;
+    helper.withdraw_liquidity(&owner, 10_000_000000).unwrap();
+    let balances_after_withdraw = query_pool_balances(&helper);
+    helper.app.next_block(1000);
+    assert_eq!(
+        query_balances_at(&helper, withdraw_height + 1),
+        balances_after_withdraw
+    );
+
+    // Historical balances are preserved
+    assert_eq!(
+        query_balances_at(&helper, swap_height + 1),
+        balances_after_swap
+    );
+    assert_eq!(
+        query_balances_at(&helper, withdraw_height + 100),
+        balances_after_withdraw
+    );
+}
+
+#[test]
+fn check_sync_reserves() {
+    let test_coins = vec![TestCoin::native("uluna"), TestCoin::cw20("USDC")];
+
+    let mut helper = Helper::new(test_coins.clone(), common_pcl_params()).unwrap();
+    let owner = helper.owner.clone();
+
+    let assets = vec![
+        helper.assets[&test_coins[0]].with_balance(100_000_000000u128),
+        helper.assets[&test_coins[1]].with_balance(100_000_000000u128),
+    ];
+    helper.provide_liquidity(&owner, &assets).unwrap();
+
+    // Direct transfers are ignored until someone syncs the reserves
+    let donation = vec![
+        helper.assets[&test_coins[0]].with_balance(1_000000u128),
+        helper.assets[&test_coins[1]].with_balance(2_000000u128),
+    ];
+    helper.give_me_money(&donation, &helper.pair_addr.clone());
+    let pool = helper.query_pool().unwrap();
+    assert_eq!(pool.assets[0].amount.u128(), 100_000_000000);
+    assert_eq!(pool.assets[1].amount.u128(), 100_000_000000);
+
+    let user = helper.app.api().addr_make("user");
+    helper.sync(&user).unwrap();
+    let pool = helper.query_pool().unwrap();
+    assert_eq!(pool.assets[0].amount.u128(), 100_001_000000);
+    assert_eq!(pool.assets[1].amount.u128(), 100_002_000000);
+
+    // Nothing left to skim after sync
+    helper.skim(&user, None).unwrap();
+    assert_eq!(helper.coin_balance(&test_coins[0], &user), 0);
+    assert_eq!(helper.coin_balance(&test_coins[1], &user), 0);
+}
+
+#[test]
+fn check_swap_exact_out() {
+    let test_coins = vec![TestCoin::native("uluna"), TestCoin::cw20("USDC")];
+
+    let mut helper = Helper::new(test_coins.clone(), common_pcl_params()).unwrap();
+    let owner = helper.owner.clone();
+
+    let assets = vec![
+        helper.assets[&test_coins[0]].with_balance(100_000_000000u128),
+        helper.assets[&test_coins[1]].with_balance(100_000_000000u128),
+    ];
+    helper.provide_liquidity(&owner, &assets).unwrap();
+
+    let merchant = helper.app.api().addr_make("merchant");
+
+    for (offer_ind, ask_ind) in [(0, 1), (1, 0)] {
+        let user = helper.app.api().addr_make(&format!("user{offer_ind}"));
+        let ask_asset = helper.assets[&test_coins[ask_ind]].with_balance(1_000_000000u128);
+        let max_offer_asset = helper.assets[&test_coins[offer_ind]].with_balance(1_100_000000u128);
+        helper.give_me_money(std::slice::from_ref(&max_offer_asset), &user);
+
+        let expected_offer_amount = helper
+            .simulate_reverse_swap(&ask_asset, None)
+            .unwrap()
+            .offer_amount;
+
+        let err = helper
+            .swap_exact_out(
+                &user,
+                &helper.assets[&test_coins[offer_ind]].with_balance(1_000_000000u128),
+                &ask_asset,
+                Some(merchant.to_string()),
+            )
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast::<ContractError>().unwrap(),
+            ContractError::MaxOfferAmountAssertion(..)
+        ));
+
+        let merchant_balance = helper.coin_balance(&test_coins[ask_ind], &merchant);
+        let pool_before = helper.query_pool().unwrap();
+        helper
+            .swap_exact_out(
+                &user,
+                &max_offer_asset,
+                &ask_asset,
+                Some(merchant.to_string()),
+            )
+            .unwrap();
+
+        // The merchant receives the exact amount
+        assert_eq!(
+            helper.coin_balance(&test_coins[ask_ind], &merchant),
+            merchant_balance + 1_000_000000
+        );
+
+        // Unused funds are refunded to the sender
+        let spent = 1_100_000000 - helper.coin_balance(&test_coins[offer_ind], &user);
+        assert_eq!(spent, expected_offer_amount.u128() + 1);
+
+        let pool_after = helper.query_pool().unwrap();
+        assert_eq!(
+            pool_after.assets[offer_ind].amount,
+            pool_before.assets[offer_ind].amount + Uint128::new(spent)
+        );
+        // The surplus caused by the fee estimation stays in the pool
+        assert!(
+            pool_after.assets[ask_ind].amount
+                >= pool_before.assets[ask_ind].amount
+                    - Uint128::new(1_000_000000)
+                    - Uint128::new(10_000000)
+        );
+    }
+}
+
+#[test]
+fn check_flash_swap() {
+    let test_coins = vec![TestCoin::native("uluna"), TestCoin::cw20("USDC")];
+
+    let mut helper = Helper::new(test_coins.clone(), common_pcl_params()).unwrap();
+    let owner = helper.owner.clone();
+
+    let assets = vec![
+        helper.assets[&test_coins[0]].with_balance(100_000_000000u128),
+        helper.assets[&test_coins[1]].with_balance(100_000_000000u128),
+    ];
+    helper.provide_liquidity(&owner, &assets).unwrap();
+
+    let borrower = helper.init_borrower();
+    let funds = vec![
+        helper.assets[&test_coins[0]].with_balance(100_000000u128),
+        helper.assets[&test_coins[1]].with_balance(100_000000u128),
+    ];
+    helper.give_me_money(&funds, &borrower);
+
+    let pair = helper.pair_addr.to_string();
+    let ask_asset = helper.assets[&test_coins[1]].with_balance(1_000_000000u128);
+
+    // Returning the borrowed amount without fees is not enough
+    let err = helper
+        .flash_swap(
+            &borrower,
+            &ask_asset,
+            &BorrowerMsg::Repay {
+                pair: pair.clone(),
+                assets: vec![ask_asset.clone()],
+            },
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.root_cause().to_string(),
+        ContractError::FlashSwapInvariantViolation {}.to_string()
+    );
+
+    // The pair is locked during the callback
+    let err = helper
+        .flash_swap(
+            &borrower,
+            &ask_asset,
+            &BorrowerMsg::Call {
+                contract: pair.clone(),
+                msg: to_json_binary(&ExecuteMsg::Sync {}).unwrap(),
+            },
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.root_cause().to_string(),
+        ContractError::FlashSwapInProgress {}.to_string()
+    );
+
+    // Repay in the borrowed asset
+    helper
+        .flash_swap(
+            &borrower,
+            &ask_asset,
+            &BorrowerMsg::Repay {
+                pair: pair.clone(),
+                assets: vec![helper.assets[&test_coins[1]].with_balance(1_005_000000u128)],
+            },
+        )
+        .unwrap();
+    let pool = helper.query_pool().unwrap();
+    assert_eq!(pool.assets[0].amount.u128(), 100_000_000000);
+    assert_eq!(pool.assets[1].amount.u128(), 100_005_000000);
+    assert_eq!(helper.coin_balance(&test_coins[1], &borrower), 95_000000);
+
+    // Repay in the other asset
+    helper.give_me_money(
+        &[helper.assets[&test_coins[0]].with_balance(1_000_000000u128)],
+        &borrower,
+    );
+    helper
+        .flash_swap(
+            &borrower,
+            &ask_asset,
+            &BorrowerMsg::Repay {
+                pair,
+                assets: vec![helper.assets[&test_coins[0]].with_balance(1_010_000000u128)],
+            },
+        )
+        .unwrap();
+    let pool = helper.query_pool().unwrap();
+    assert_eq!(pool.assets[0].amount.u128(), 101_010_000000);
+    assert_eq!(pool.assets[1].amount.u128(), 99_005_000000);
+}
+
+#[test]
+fn check_observe() {
+    let test_coins = vec![TestCoin::native("uluna"), TestCoin::cw20("USDC")];
+
+    let mut helper = Helper::new(test_coins.clone(), common_pcl_params()).unwrap();
+    let owner = helper.owner.clone();
+
+    let assets = vec![
+        helper.assets[&test_coins[0]].with_balance(100_000_000000u128),
+        helper.assets[&test_coins[1]].with_balance(100_000_000000u128),
+    ];
+    helper.provide_liquidity(&owner, &assets).unwrap();
+    let prices_after_provide = helper.query_prices().unwrap().cumulative_prices;
+
+    helper.app.next_block(1000);
+
+    let offer_asset = helper.assets[&test_coins[0]].with_balance(1_000_000000u128);
+    helper.give_me_money(std::slice::from_ref(&offer_asset), &owner);
+    helper.swap(&owner, &offer_asset, None).unwrap();
+    let prices_after_swap = helper.query_prices().unwrap().cumulative_prices;
+
+    helper.app.next_block(1000);
+
+    let observations = helper.query_observe(vec![0, 1000, 2000]).unwrap();
+    assert_eq!(
+        observations[0].cumulative_prices,
+        helper.query_prices().unwrap().cumulative_prices
+    );
+    assert_eq!(observations[1].cumulative_prices, prices_after_swap);
+    assert_eq!(observations[2].cumulative_prices, prices_after_provide);
+    assert_eq!(observations[0].timestamp - observations[2].timestamp, 2000);
+
+    // Halfway between the recorded observations
+    let observation = helper.query_observe(vec![1500]).unwrap().remove(0);
+    for ((.., value), ((.., before), (.., after))) in observation
+        .cumulative_prices
+        .iter()
+        .zip(prices_after_provide.iter().zip(&prices_after_swap))
+    {
+        assert_eq!(*value, before + (after - before) / Uint128::new(2));
+    }
+
+    let err = helper.query_observe(vec![2001]).unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("is older than the oldest recorded one"));
+}
+
+#[test]
+fn check_pause() {
+    let test_coins = vec![TestCoin::native("uluna"), TestCoin::cw20("USDC")];
+
+    let mut helper = Helper::new(test_coins.clone(), common_pcl_params()).unwrap();
+    let owner = helper.owner.clone();
+
+    let assets = vec![
+        helper.assets[&test_coins[0]].with_balance(100_000_000000u128),
+        helper.assets[&test_coins[1]].with_balance(100_000_000000u128),
+    ];
+    helper.provide_liquidity(&owner, &assets).unwrap();
+
+    helper
+        .app
+        .execute_contract(
+            owner.clone(),
+            helper.factory.clone(),
+            &FactoryExecuteMsg::Pause {
+                pair: Some(helper.pair_addr.to_string()),
+                scope: PauseScope::Swap,
+            },
+            &[],
+        )
+        .unwrap();
+
+    let offer_asset = helper.assets[&test_coins[0]].with_balance(1_000000u128);
+    helper.give_me_money(std::slice::from_ref(&offer_asset), &owner);
+    let err = helper.swap(&owner, &offer_asset, None).unwrap_err();
+    assert_eq!(
+        ContractError::Paused(PauseScope::Swap),
+        err.downcast().unwrap()
+    );
+
+    // Liquidity can still be provided and withdrawn
+    helper.give_me_money(&assets, &owner);
+    helper.provide_liquidity(&owner, &assets).unwrap();
+    helper.withdraw_liquidity(&owner, 1_000000).unwrap();
+
+    helper
+        .app
+        .execute_contract(
+            owner.clone(),
+            helper.factory.clone(),
+            &FactoryExecuteMsg::Unpause {
+                pair: Some(helper.pair_addr.to_string()),
+            },
+            &[],
+        )
+        .unwrap();
+    helper.swap(&owner, &offer_asset, None).unwrap();
+}
+
+#[test]
+fn check_withdraw_one_asset() {
+    let test_coins = vec![TestCoin::native("uluna"), TestCoin::cw20("USDC")];
+
+    let mut helper = Helper::new(test_coins.clone(), common_pcl_params()).unwrap();
+    let owner = helper.owner.clone();
+
+    let assets = vec![
+        helper.assets[&test_coins[0]].with_balance(100_000_000000u128),
+        helper.assets[&test_coins[1]].with_balance(100_000_000000u128),
+    ];
+    helper.provide_liquidity(&owner, &assets).unwrap();
+
+    let user = helper.app.api().addr_make("user");
+    let assets = vec![
+        helper.assets[&test_coins[0]].with_balance(1_000_000000u128),
+        helper.assets[&test_coins[1]].with_balance(1_000_000000u128),
+    ];
+    helper.give_me_money(&assets, &user);
+    helper.provide_liquidity(&user, &assets).unwrap();
+    let lp_amount = helper.token_balance(&helper.lp_token, &user);
+
+    helper.app.next_block(1000);
+
+    let err = helper
+        .query_simulate_withdraw(lp_amount, Some(native_asset_info("random".to_string())))
+        .unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("The asset random does not belong to the pair"));
+
+    let balanced = helper.query_share(lp_amount).unwrap();
+    let simulated = helper
+        .query_simulate_withdraw(lp_amount, Some(helper.assets[&test_coins[0]].clone()))
+        .unwrap();
+    assert_eq!(simulated.len(), 1);
+    let withdraw_amount = simulated[0].amount.u128();
+    // The imbalance fee and the price impact are charged from the withdrawal
+    let balanced_value = balanced[0].amount.u128() + balanced[1].amount.u128();
+    assert!(withdraw_amount < balanced_value);
+    assert!(withdraw_amount > balanced_value * 99 / 100);
+
+    let min_assets = vec![helper.assets[&test_coins[0]].with_balance(withdraw_amount + 1)];
+    let err = helper
+        .withdraw_liquidity_full(
+            &user,
+            lp_amount,
+            Some(min_assets),
+            Some(helper.assets[&test_coins[0]].clone()),
+        )
+        .unwrap_err();
+    assert_eq!(
+        ContractError::WithdrawSlippageViolation {
+            asset_name: helper.assets[&test_coins[0]].to_string(),
+            received: withdraw_amount.into(),
+            expected: (withdraw_amount + 1).into(),
+        },
+        err.downcast().unwrap()
+    );
+
+    let lp_price_before = helper.query_lp_price().unwrap();
+    let min_assets = vec![helper.assets[&test_coins[0]].with_balance(withdraw_amount)];
+    helper
+        .withdraw_liquidity_full(
+            &user,
+            lp_amount,
+            Some(min_assets),
+            Some(helper.assets[&test_coins[0]].clone()),
+        )
+        .unwrap();
+
+    assert_eq!(helper.coin_balance(&test_coins[0], &user), withdraw_amount);
+    assert_eq!(helper.coin_balance(&test_coins[1], &user), 0);
+    assert_eq!(helper.token_balance(&helper.lp_token, &user), 0);
+
+    // The fee stays in the pool thus remaining LPs don't lose
+    assert!(helper.query_lp_price().unwrap() >= lp_price_before);
+    let pool = helper.query_pool().unwrap();
+    assert_eq!(
+        pool.assets[0].amount.u128(),
+        101_000_000000 - withdraw_amount
+    );
+    assert_eq!(pool.assets[1].amount.u128(), 101_000_000000);
+
+    // The pool is still functional
+    let offer_asset = helper.assets[&test_coins[1]].with_balance(1_000000u128);
+    helper.give_me_money(std::slice::from_ref(&offer_asset), &owner);
+    helper.swap(&owner, &offer_asset, None).unwrap();
+}
+
+#[test]
+fn check_three_asset_pool() {
+    let test_coins = vec![
+        TestCoin::native("uluna"),
+        TestCoin::native("uusd"),
+        TestCoin::cw20("FOO"),
+    ];
+
+    let params = ConcentratedPoolParams {
+        price_scale: Decimal::from_ratio(2u8, 1u8),
+        extra_price_scales: vec![],
+        ..common_pcl_params()
+    };
+    let err = Helper::new(test_coins.clone(), params.clone()).unwrap_err();
+    assert_eq!(
+        err.root_cause().to_string(),
+        "Generic error: extra_price_scales must contain a price scale for each asset after the second one"
+    );
+
+    let params = ConcentratedPoolParams {
+        extra_price_scales: vec![Decimal::from_ratio(3u8, 1u8)],
+        ..params
+    };
+    let mut helper = Helper::new(test_coins.clone(), params).unwrap();
+    let owner = helper.owner.clone();
+
+    let config = helper.query_config().unwrap();
+    assert_eq!(config.pool_state.price_state.extra_assets.len(), 1);
+    assert_eq!(helper.query_prices().unwrap().cumulative_prices.len(), 6);
+
+    // Deposit equal value of each asset
+    let assets = vec![
+        helper.assets[&test_coins[0]].with_balance(600_000_000000u128),
+        helper.assets[&test_coins[1]].with_balance(300_000_000000u128),
+        helper.assets[&test_coins[2]].with_balance(200_000_000000u128),
+    ];
+    helper.provide_liquidity(&owner, &assets).unwrap();
+    let d = helper.query_d().unwrap();
+    assert_eq!(dec_to_f64(d), 1_800_000.0);
+    assert!((dec_to_f64(helper.query_lp_price().unwrap()) - 1.0).abs() < 1e-9);
+
+    // The ask asset is ambiguous in a 3-asset pool
+    let offer_asset = helper.assets[&test_coins[0]].with_balance(10_000_000000u128);
+    let err = helper.simulate_swap(&offer_asset, None).unwrap_err();
+    assert!(err.to_string().contains("Ask or offer asset is missed"));
+    let err = helper
+        .simulate_swap(&offer_asset, Some(helper.assets[&test_coins[0]].clone()))
+        .unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("Source and target assets are the same"));
+
+    let user = helper.app.api().addr_make("user");
+    helper.give_me_money(std::slice::from_ref(&offer_asset), &user);
+    let sim = helper
+        .simulate_swap(&offer_asset, Some(helper.assets[&test_coins[2]].clone()))
+        .unwrap();
+    helper
+        .swap_to(
+            &user,
+            &offer_asset,
+            Some(helper.assets[&test_coins[2]].clone()),
+            None,
+            None,
+        )
+        .unwrap();
+    assert_eq!(helper.coin_balance(&test_coins[0], &user), 0);
+    assert_eq!(helper.coin_balance(&test_coins[1], &user), 0);
+    assert_eq!(
+        helper.coin_balance(&test_coins[2], &user),
+        sim.return_amount.u128()
+    );
+    // 10k uluna are worth ~3333 FOO
+    assert!(sim.return_amount.u128() < 3333_333333);
+    assert!(sim.return_amount.u128() > 3300_000000);
+
+    // Swap between two non-quote assets
+    let offer_asset = helper.assets[&test_coins[2]].with_balance(sim.return_amount);
+    let sim = helper
+        .simulate_swap(&offer_asset, Some(helper.assets[&test_coins[1]].clone()))
+        .unwrap();
+    helper
+        .swap_to(
+            &user,
+            &offer_asset,
+            Some(helper.assets[&test_coins[1]].clone()),
+            None,
+            None,
+        )
+        .unwrap();
+    assert_eq!(helper.coin_balance(&test_coins[2], &user), 0);
+    assert_eq!(
+        helper.coin_balance(&test_coins[1], &user),
+        sim.return_amount.u128()
+    );
+    assert!(sim.return_amount.u128() < 5000_000000);
+    assert!(sim.return_amount.u128() > 4900_000000);
+
+    let rev_sim = helper
+        .simulate_reverse_swap(
+            &helper.assets[&test_coins[2]].with_balance(1_000000u128),
+            Some(helper.assets[&test_coins[1]].clone()),
+        )
+        .unwrap();
+    assert!(rev_sim.offer_amount.u128() > 1_500000);
+    assert!(rev_sim.offer_amount.u128() < 1_550000);
+
+    // Single-sided deposit is filled up with zeros for missing assets
+    let deposit = helper.assets[&test_coins[1]].with_balance(1_000_000000u128);
+    helper.give_me_money(std::slice::from_ref(&deposit), &user);
+    helper
+        .provide_liquidity(&user, std::slice::from_ref(&deposit))
+        .unwrap();
+    let lp_amount = helper.token_balance(&helper.lp_token, &user);
+    assert!(lp_amount > 0);
+
+    helper.app.next_block(1000);
+
+    let simulated = helper
+        .query_simulate_withdraw(lp_amount, Some(helper.assets[&test_coins[2]].clone()))
+        .unwrap();
+    helper
+        .withdraw_liquidity_full(
+            &user,
+            lp_amount / 2,
+            None,
+            Some(helper.assets[&test_coins[2]].clone()),
+        )
+        .unwrap();
+    let foo_balance = helper.coin_balance(&test_coins[2], &user);
+    assert!(foo_balance > 0);
+    assert!(foo_balance < simulated[0].amount.u128());
+
+    let rest = lp_amount - lp_amount / 2;
+    let share = helper.query_share(rest).unwrap();
+    assert_eq!(share.len(), 3);
+    helper.withdraw_liquidity(&user, rest).unwrap();
+    assert_eq!(helper.token_balance(&helper.lp_token, &user), 0);
+    assert_eq!(
+        helper.coin_balance(&test_coins[2], &user),
+        foo_balance + share[2].amount.u128()
+    );
+
+    // Trading in one direction repegs the price scales over time
+    let scales_before = helper.query_config().unwrap().pool_state.price_state;
+    for _ in 0..10 {
+        let offer_asset = helper.assets[&test_coins[0]].with_balance(20_000_000000u128);
+        helper.give_me_money(std::slice::from_ref(&offer_asset), &user);
+        helper
+            .swap_to(
+                &user,
+                &offer_asset,
+                Some(helper.assets[&test_coins[2]].clone()),
+                Some(f64_to_dec(0.5)),
+                None,
+            )
+            .unwrap();
+        helper.app.next_block(3600);
+    }
+    let scales_after = helper.query_config().unwrap().pool_state.price_state;
+    assert!(scales_after.extra_assets[0].price_scale > scales_before.extra_assets[0].price_scale);
+    assert_eq!(helper.query_prices().unwrap().cumulative_prices.len(), 6);
+}
+
+#[test]
+fn check_migrate_legacy_fee_share() {
+    let mut deps = mock_dependencies();
+
+    let pair_info = PairInfo {
+        asset_infos: vec![
+            native_asset_info("uusd".to_string()),
+            native_asset_info("uluna".to_string()),
+        ],
+        contract_addr: Addr::unchecked(MOCK_CONTRACT_ADDR),
+        liquidity_token: "liquidity0000".to_string(),
+        pair_type: PairType::Custom("concentrated".to_string()),
+    };
+    let pool_state = PoolState {
+        initial: AmpGamma::default(),
+        future: AmpGamma::new(f64_to_dec(40f64), f64_to_dec(0.000145)).unwrap(),
+        future_time: 0,
+        initial_time: 0,
+        price_state: PriceState::new(&[Decimal256::one()], 0),
+    };
+    CONFIG_V410
+        .save(
+            deps.as_mut().storage,
+            &ConfigV410 {
+                pair_info: pair_info.clone(),
+                factory_addr: Addr::unchecked("factory"),
+                block_time_last: 100,
+                cumulative_prices: vec![],
+                pool_params: PoolParams::default(),
+                pool_state: pool_state.clone(),
+                owner: None,
+                fee_share: Some(LegacyFeeShareConfig {
+                    bps: 500,
+                    recipient: Addr::unchecked("recipient"),
+                }),
+            },
+        )
+        .unwrap();
+    cw2::set_contract_version(
+        deps.as_mut().storage,
+        "astroport-pair-concentrated",
+        "4.1.1",
+    )
+    .unwrap();
+
+    // The legacy fee share can't be read as the current config
+    CONFIG.load(deps.as_ref().storage).unwrap_err();
+
+    migrate(deps.as_mut(), mock_env(), Empty {}).unwrap();
+
+    let config = CONFIG.load(deps.as_ref().storage).unwrap();
+    assert_eq!(config.pair_info, pair_info);
+    assert_eq!(config.block_time_last, 100);
+    assert_eq!(config.pool_state, pool_state);
+    assert_eq!(
+        config.fee_share,
+        Some(FeeShareConfig::single(Addr::unchecked("recipient"), 500))
+    );
+    assert_eq!(
+        RESERVES.load(deps.as_ref().storage).unwrap(),
+        vec![Uint128::zero(); 2]
+    );
+    assert_eq!(
+        cw2::get_contract_version(deps.as_ref().storage)
+            .unwrap()
+            .version,
+        env!("CARGO_PKG_VERSION")
+    );
+}
+
+#[test]
+fn check_pair_hooks() {
+    let test_coins = vec![TestCoin::native("uluna"), TestCoin::cw20("USDC")];
+
+    let mut helper = Helper::new(test_coins.clone(), common_pcl_params()).unwrap();
+    let owner = helper.owner.clone();
+
+    let recorder = helper.init_hook(false);
+    let failing = helper.init_hook(true);
+    let hook = |contract_addr: &Addr, blocking: bool| PairHook {
+        contract_addr: contract_addr.clone(),
+        gas_limit: 500_000,
+        blocking,
+    };
+
+    // Only the owner can set hooks
+    let random_user = helper.app.api().addr_make("random");
+    let err = helper
+        .update_config(
+            &random_user,
+            &ConcentratedPoolUpdateParams::SetHooks {
+                hooks: vec![hook(&recorder, true)],
+            },
+        )
+        .unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+
+    for hooks in [
+        vec![hook(&recorder, true), hook(&recorder, false)],
+        vec![PairHook {
+            gas_limit: 10_000_000,
+            ..hook(&recorder, true)
+        }],
+    ] {
+        let err = helper
+            .update_config(&owner, &ConcentratedPoolUpdateParams::SetHooks { hooks })
+            .unwrap_err();
+        assert_eq!(ContractError::InvalidHooks {}, err.downcast().unwrap());
+    }
+
+    let hooks = vec![hook(&recorder, true), hook(&failing, false)];
+    helper
+        .update_config(
+            &owner,
+            &ConcentratedPoolUpdateParams::SetHooks {
+                hooks: hooks.clone(),
+            },
+        )
+        .unwrap();
+    assert_eq!(helper.query_config().unwrap().hooks, hooks);
+
+    // The failing hook is non-blocking so its error is ignored
+    let assets = vec![
+        helper.assets[&test_coins[0]].with_balance(100_000_000000u128),
+        helper.assets[&test_coins[1]].with_balance(100_000_000000u128),
+    ];
+    let resp = helper.provide_liquidity(&owner, &assets).unwrap();
+    assert!(resp
+        .events
+        .iter()
+        .any(|event| event.attributes.iter().any(|attr| attr.key == "hook_error")));
+    let PairHookMsg::AfterProvide {
+        assets: provided,
+        share,
+        receiver,
+    } = helper.query_hook_calls(&recorder).unwrap()[0].clone()
+    else {
+        panic!("Expected AfterProvide");
+    };
+    assert_eq!(provided, assets);
+    assert_eq!(share.u128(), helper.token_balance(&helper.lp_token, &owner));
+    assert_eq!(receiver, owner);
+
+    let user = helper.app.api().addr_make("user");
+    let offer_asset = helper.assets[&test_coins[0]].with_balance(1_000000u128);
+    helper.give_me_money(std::slice::from_ref(&offer_asset), &user);
+    helper.swap(&user, &offer_asset, None).unwrap();
+    let received = helper.coin_balance(&test_coins[1], &user);
+    assert_eq!(
+        helper.query_hook_calls(&recorder).unwrap()[1],
+        PairHookMsg::AfterSwap {
+            offer: offer_asset,
+            ask: helper.assets[&test_coins[1]].with_balance(received),
+            trader: user.clone(),
+        }
+    );
+
+    let ask_asset = helper.assets[&test_coins[1]].with_balance(1_000000u128);
+    let max_offer_asset = helper.assets[&test_coins[0]].with_balance(2_000000u128);
+    helper.give_me_money(std::slice::from_ref(&max_offer_asset), &user);
+    helper
+        .swap_exact_out(&user, &max_offer_asset, &ask_asset, None)
+        .unwrap();
+    let PairHookMsg::AfterSwap { offer, ask, trader } =
+        helper.query_hook_calls(&recorder).unwrap()[2].clone()
+    else {
+        panic!("Expected AfterSwap");
+    };
+    assert_eq!(
+        offer.amount.u128(),
+        2_000000 - helper.coin_balance(&test_coins[0], &user)
+    );
+    assert_eq!(ask, ask_asset);
+    assert_eq!(trader, user);
+
+    helper.withdraw_liquidity(&owner, 1_000000).unwrap();
+    let PairHookMsg::AfterWithdraw {
+        assets: withdrawn,
+        share,
+        receiver,
+    } = helper.query_hook_calls(&recorder).unwrap()[3].clone()
+    else {
+        panic!("Expected AfterWithdraw");
+    };
+    assert_eq!(withdrawn.len(), 2);
+    assert_eq!(share.u128(), 1_000000);
+    assert_eq!(receiver, owner);
+
+    // A failing blocking hook reverts the transaction
+    helper
+        .update_config(
+            &owner,
+            &ConcentratedPoolUpdateParams::SetHooks {
+                hooks: vec![hook(&failing, true)],
+            },
+        )
+        .unwrap();
+    let offer_asset = helper.assets[&test_coins[0]].with_balance(1_000000u128);
+    helper.give_me_money(std::slice::from_ref(&offer_asset), &user);
+    helper.swap(&user, &offer_asset, None).unwrap_err();
+}
+
+#[test]
+fn check_stats() {
+    let test_coins = vec![TestCoin::native("uluna"), TestCoin::cw20("USDC")];
+
+    let mut helper = Helper::new(test_coins.clone(), common_pcl_params()).unwrap();
+    let owner = helper.owner.clone();
+
+    let assets = vec![
+        helper.assets[&test_coins[0]].with_balance(100_000_000000u128),
+        helper.assets[&test_coins[1]].with_balance(100_000_000000u128),
+    ];
+    helper.provide_liquidity(&owner, &assets).unwrap();
+
+    let fee_share_address = helper.app.api().addr_make("fee_share");
+    helper
+        .update_config(
+            &owner,
+            &ConcentratedPoolUpdateParams::EnableFeeShare {
+                fee_share_bps: MAX_FEE_SHARE_BPS,
+                fee_share_address: fee_share_address.to_string(),
+            },
+        )
+        .unwrap();
+
+    let user = helper.app.api().addr_make("user");
+    let offer_asset = helper.assets[&test_coins[0]].with_balance(1_000_000000u128);
+    let sim = helper.simulate_swap(&offer_asset, None).unwrap();
+    helper.give_me_money(std::slice::from_ref(&offer_asset), &user);
+    helper.swap(&user, &offer_asset, None).unwrap();
+
+    let stats = helper.query_stats(None).unwrap();
+    assert_eq!(stats.epoch_stats, stats.lifetime_stats);
+    assert_eq!(stats.epoch_stats[0].volume_in, offer_asset.amount);
+    assert_eq!(stats.epoch_stats[1].volume_out, sim.return_amount);
+    assert_eq!(
+        stats.epoch_stats[1].fee_share.u128(),
+        helper.coin_balance(&test_coins[1], &fee_share_address)
+    );
+    assert_eq!(
+        stats.epoch_stats[1].maker_fee.u128(),
+        helper.coin_balance(&test_coins[1], &helper.fake_maker)
+    );
+    assert!(!stats.epoch_stats[1].lp_fee.is_zero());
+    assert_eq!(
+        stats.epoch_stats[1].lp_fee
+            + stats.epoch_stats[1].maker_fee
+            + stats.epoch_stats[1].fee_share,
+        sim.commission_amount
+    );
+
+    // The maker fee accrued in the pool is accounted as the maker fee as well
+    helper
+        .update_config(
+            &owner,
+            &ConcentratedPoolUpdateParams::EnableProtocolFeeAccrual,
+        )
+        .unwrap();
+    let ask_asset = helper.assets[&test_coins[0]].with_balance(500_000000u128);
+    let max_offer_asset = helper.assets[&test_coins[1]].with_balance(1_000_000000u128);
+    helper.give_me_money(std::slice::from_ref(&max_offer_asset), &user);
+    let balance_before = helper.coin_balance(&test_coins[1], &user);
+    helper
+        .swap_exact_out(&user, &max_offer_asset, &ask_asset, None)
+        .unwrap();
+
+    let prev = stats;
+    let stats = helper.query_stats(None).unwrap();
+    assert_eq!(
+        stats.epoch_stats[1].volume_in,
+        Uint128::new(balance_before - helper.coin_balance(&test_coins[1], &user))
+    );
+    assert_eq!(stats.epoch_stats[0].volume_out, ask_asset.amount);
+    assert!(!stats.epoch_stats[0].maker_fee.is_zero());
+    assert_eq!(helper.coin_balance(&test_coins[0], &helper.fake_maker), 0);
+    assert_eq!(
+        stats.epoch_stats[1].volume_out,
+        prev.epoch_stats[1].volume_out
+    );
+    assert_eq!(
+        stats.epoch_stats[1].maker_fee,
+        prev.epoch_stats[1].maker_fee
+    );
+
+    // The next epoch starts with empty stats
+    helper.app.next_block(STATS_EPOCH_LENGTH);
+    let next = helper.query_stats(None).unwrap();
+    assert_eq!(next.epoch, stats.epoch + 1);
+    assert!(next.epoch_stats[0].lp_fee.is_zero());
+    assert_eq!(next.lifetime_stats, stats.lifetime_stats);
+}
+
+#[test]
+fn check_price_guard() {
+    let test_coins = vec![TestCoin::native("uluna"), TestCoin::cw20("USDC")];
+
+    let mut helper = Helper::new(test_coins.clone(), common_pcl_params()).unwrap();
+    let owner = helper.owner.clone();
+
+    let assets = vec![
+        helper.assets[&test_coins[0]].with_balance(100_000_000000u128),
+        helper.assets[&test_coins[1]].with_balance(100_000_000000u128),
+    ];
+    helper.provide_liquidity(&owner, &assets).unwrap();
+
+    let err = helper
+        .update_config(
+            &owner,
+            &ConcentratedPoolUpdateParams::EnablePriceGuard {
+                max_deviation: Decimal::percent(51),
+            },
+        )
+        .unwrap_err();
+    assert_eq!(
+        ContractError::InvalidPriceDeviation {},
+        err.downcast().unwrap()
+    );
+
+    helper
+        .update_config(
+            &owner,
+            &ConcentratedPoolUpdateParams::EnablePriceGuard {
+                max_deviation: Decimal::permille(4),
+            },
+        )
+        .unwrap();
+    assert_eq!(
+        helper.query_config().unwrap().max_price_deviation,
+        Some(Decimal::permille(4))
+    );
+
+    let user = helper.app.api().addr_make("user");
+    let offer_asset = helper.assets[&test_coins[0]].with_balance(700_000000u128);
+    helper.give_me_money(
+        &[
+            offer_asset.clone(),
+            offer_asset.clone(),
+            offer_asset.clone(),
+        ],
+        &user,
+    );
+
+    helper.app.next_block(5);
+    helper.swap(&user, &offer_asset, None).unwrap();
+
+    // A second swap within the same block pushes the price past the limit
+    let err = helper.swap(&user, &offer_asset, None).unwrap_err();
+    assert!(matches!(
+        err.downcast().unwrap(),
+        ContractError::PriceDeviationExceeded { max_deviation, .. } if max_deviation == Decimal::permille(4)
+    ));
+
+    // The next block opens at the new price
+    helper.app.next_block(5);
+    helper.swap(&user, &offer_asset, None).unwrap();
+
+    // Flash swaps repaid in another asset are checked as well
+    let borrower = helper.init_borrower();
+    helper.give_me_money(
+        &[helper.assets[&test_coins[1]].with_balance(30_000_000000u128)],
+        &borrower,
+    );
+    let ask_asset = helper.assets[&test_coins[0]].with_balance(10_000_000000u128);
+    let repay_msg = BorrowerMsg::Repay {
+        pair: helper.pair_addr.to_string(),
+        assets: vec![helper.assets[&test_coins[1]].with_balance(15_000_000000u128)],
+    };
+    let err = helper
+        .flash_swap(&borrower, &ask_asset, &repay_msg)
+        .unwrap_err();
+    assert!(err
+        .root_cause()
+        .to_string()
+        .starts_with("Swap moves the price by"));
+
+    // Imbalanced deposits and single-asset withdrawals move the price too
+    helper.app.next_block(5);
+    let err = helper
+        .provide_liquidity_full(
+            &owner,
+            &[helper.assets[&test_coins[0]].with_balance(5_000_000000u128)],
+            Some(Decimal::percent(50)),
+            None,
+            None,
+            None,
+        )
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast().unwrap(),
+        ContractError::PriceDeviationExceeded { max_deviation, .. } if max_deviation == Decimal::permille(4)
+    ));
+    let err = helper
+        .withdraw_liquidity_full(
+            &owner,
+            5_000_000000,
+            None,
+            Some(helper.assets[&test_coins[1]].clone()),
+        )
+        .unwrap_err();
+    assert!(err
+        .root_cause()
+        .to_string()
+        .starts_with("Swap moves the price by"));
+
+    helper
+        .update_config(&owner, &ConcentratedPoolUpdateParams::DisablePriceGuard)
+        .unwrap();
+    assert_eq!(helper.query_config().unwrap().max_price_deviation, None);
+    helper.swap(&user, &offer_asset, None).unwrap();
+    helper
+        .flash_swap(&borrower, &ask_asset, &repay_msg)
+        .unwrap();
+}
+
+#[test]
+fn check_trader_allowlist() {
+    let test_coins = vec![TestCoin::native("uluna"), TestCoin::cw20("USDC")];
+
+    let mut helper = Helper::new(test_coins.clone(), common_pcl_params()).unwrap();
+    let owner = helper.owner.clone();
+
+    let assets = vec![
+        helper.assets[&test_coins[0]].with_balance(100_000_000000u128),
+        helper.assets[&test_coins[1]].with_balance(100_000_000000u128),
+    ];
+    helper.provide_liquidity(&owner, &assets).unwrap();
+
+    let alice = helper.app.api().addr_make("alice");
+    let bob = helper.app.api().addr_make("bob");
+    let offer_asset = helper.assets[&test_coins[0]].with_balance(1_000000u128);
+    let provide_assets = vec![
+        helper.assets[&test_coins[0]].with_balance(1_000000u128),
+        helper.assets[&test_coins[1]].with_balance(1_000000u128),
+    ];
+    for user in [&alice, &bob] {
+        helper.give_me_money(&[offer_asset.clone(), offer_asset.clone()], user);
+        helper.give_me_money(&provide_assets, user);
+    }
+
+    let err = helper
+        .update_config(
+            &owner,
+            &ConcentratedPoolUpdateParams::SetTraderAllowlist(TraderAllowlist::Addresses(vec![
+                alice.clone(),
+                alice.clone(),
+            ])),
+        )
+        .unwrap_err();
+    assert_eq!(
+        ContractError::InvalidTraderAllowlist {},
+        err.downcast().unwrap()
+    );
+
+    helper
+        .update_config(
+            &owner,
+            &ConcentratedPoolUpdateParams::SetTraderAllowlist(TraderAllowlist::Addresses(vec![
+                alice.clone(),
+            ])),
+        )
+        .unwrap();
+    assert_eq!(
+        helper.query_config().unwrap().trader_allowlist,
+        Some(TraderAllowlist::Addresses(vec![alice.clone()]))
+    );
+
+    helper.swap(&alice, &offer_asset, None).unwrap();
+    let err = helper.swap(&bob, &offer_asset, None).unwrap_err();
+    assert_eq!(
+        ContractError::TraderNotAllowed { addr: bob.clone() },
+        err.downcast().unwrap()
+    );
+
+    // The swap recipient must be allowed as well
+    let ask_asset = helper.assets[&test_coins[1]].with_balance(100000u128);
+    let err = helper
+        .swap_exact_out(&alice, &offer_asset, &ask_asset, Some(bob.to_string()))
+        .unwrap_err();
+    assert_eq!(
+        ContractError::TraderNotAllowed { addr: bob.clone() },
+        err.downcast().unwrap()
+    );
+    helper
+        .swap_exact_out(&alice, &offer_asset, &ask_asset, None)
+        .unwrap();
+
+    let err = helper.provide_liquidity(&bob, &provide_assets).unwrap_err();
+    assert_eq!(
+        ContractError::TraderNotAllowed { addr: bob.clone() },
+        err.downcast().unwrap()
+    );
+    let err = helper
+        .provide_liquidity_full(
+            &alice,
+            &provide_assets,
+            None,
+            None,
+            Some(bob.to_string()),
+            None,
+        )
+        .unwrap_err();
+    assert_eq!(
+        ContractError::TraderNotAllowed { addr: bob.clone() },
+        err.downcast().unwrap()
+    );
+    helper.provide_liquidity(&alice, &provide_assets).unwrap();
+
+    // Flash swap borrowers must be allowed
+    let borrower = helper.init_borrower();
+    let err = helper
+        .flash_swap(
+            &borrower,
+            &ask_asset,
+            &BorrowerMsg::Repay {
+                pair: helper.pair_addr.to_string(),
+                assets: vec![ask_asset.clone()],
+            },
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.root_cause().to_string(),
+        ContractError::TraderNotAllowed { addr: borrower }.to_string()
+    );
+
+    helper
+        .update_config(&owner, &ConcentratedPoolUpdateParams::RemoveTraderAllowlist)
+        .unwrap();
+    assert_eq!(helper.query_config().unwrap().trader_allowlist, None);
+    helper.swap(&bob, &offer_asset, None).unwrap();
+}
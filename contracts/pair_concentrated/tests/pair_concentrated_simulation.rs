@@ -1,3 +1,4 @@
+#![allow(clippy::inconsistent_digit_grouping)]
 extern crate core;
 
 use std::collections::HashMap;
@@ -41,7 +42,7 @@ fn simulate_case(case: Vec<(usize, u128, u64)>) {
         println!("i: {i}, {offer_ind} {dy} {shift_time}");
         let offer_asset = helper.assets[&test_coins[offer_ind]].with_balance(dy);
         // let balance_before = helper.coin_balance(&test_coins[ask_ind], &user);
-        helper.give_me_money(&[offer_asset.clone()], &user);
+        helper.give_me_money(std::slice::from_ref(&offer_asset), &user);
         if let Err(err) = helper.swap(&user, &offer_asset, None) {
             let err: ContractError = err.downcast().unwrap();
             match err {
@@ -93,7 +94,7 @@ fn simulate_fee_share_case(case: Vec<(usize, u128, u64)>) {
         println!("i: {i}, {offer_ind} {dy} {shift_time}");
         let offer_asset = helper.assets[&test_coins[offer_ind]].with_balance(dy);
         // let balance_before = helper.coin_balance(&test_coins[ask_ind], &user);
-        helper.give_me_money(&[offer_asset.clone()], &user);
+        helper.give_me_money(std::slice::from_ref(&offer_asset), &user);
         if let Err(err) = helper.swap(&user, &offer_asset, None) {
             let err: ContractError = err.downcast().unwrap();
             match err {
@@ -440,7 +441,7 @@ fn simulate_mixed_case(cases: Vec<(PclEvent, u64)>) {
             }
             PclEvent::Swap { offer_ind, dy } => {
                 let offer_asset = helper.assets[&test_coins[offer_ind]].with_balance(dy);
-                helper.give_me_money(&[offer_asset.clone()], &user);
+                helper.give_me_money(std::slice::from_ref(&offer_asset), &user);
 
                 if let Err(err) =
                     helper.swap(&user, &offer_asset, Some(Decimal::from_str("0.5").unwrap()))
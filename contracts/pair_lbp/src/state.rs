@@ -0,0 +1,108 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, Decimal256, DepsMut, StdResult, Storage, Uint128};
+use cw_storage_plus::{Deque, Item, Map, SnapshotMap, Strategy};
+
+use astroport::asset::{AssetInfo, PairInfo};
+use astroport::common::OwnershipProposal;
+use astroport::observation::Observation;
+
+/// This structure stores the main liquidity bootstrapping pair parameters.
+#[cw_serde]
+pub struct Config {
+    /// The contract owner. Only the owner can provide and withdraw liquidity
+    pub owner: Addr,
+    /// The pair information stored in a [`PairInfo`] struct
+    pub pair_info: PairInfo,
+    /// The factory contract address
+    pub factory_addr: Addr,
+    /// The last timestamp when the pair contract update the asset cumulative prices
+    pub block_time_last: u64,
+    /// Normalized asset weights at `start_time` in the same order as [`PairInfo::asset_infos`]
+    pub start_weights: Vec<Decimal256>,
+    /// Normalized asset weights at `end_time` in the same order as [`PairInfo::asset_infos`]
+    pub end_weights: Vec<Decimal256>,
+    /// The timestamp when swaps are enabled and the weights start changing
+    pub start_time: u64,
+    /// The timestamp when swaps are disabled and the weights stop changing
+    pub end_time: u64,
+    /// The vector contains cumulative prices for each pair of assets in the pool
+    pub cumulative_prices: Vec<(AssetInfo, AssetInfo, Uint128)>,
+    /// Whether LP shares are issued as a token factory denom instead of a CW20 token
+    pub native_lp_token: bool,
+}
+
+impl Config {
+    /// Returns the asset weights at the given timestamp. The weights move linearly from
+    /// `start_weights` to `end_weights` and stay constant outside of the schedule.
+    pub fn weights(&self, time: u64) -> Vec<Decimal256> {
+        let time = time.clamp(self.start_time, self.end_time);
+        let elapsed =
+            Decimal256::from_ratio(time - self.start_time, self.end_time - self.start_time);
+
+        let mut weights = self
+            .start_weights
+            .iter()
+            .zip(&self.end_weights)
+            .take(self.start_weights.len() - 1)
+            .map(|(start, end)| {
+                if end >= start {
+                    *start + (*end - *start) * elapsed
+                } else {
+                    *start - (*start - *end) * elapsed
+                }
+            })
+            .collect::<Vec<_>>();
+        // The last weight absorbs the rounding error
+        let rest = weights.iter().sum::<Decimal256>();
+        weights.push(Decimal256::one().saturating_sub(rest));
+
+        weights
+    }
+
+    /// Returns true if swaps are allowed at the given timestamp.
+    pub fn is_swap_window(&self, time: u64) -> bool {
+        (self.start_time..self.end_time).contains(&time)
+    }
+}
+
+/// Stores the config struct at the given key
+pub const CONFIG: Item<Config> = Item::new("config");
+
+/// Stores the pool reserves in the same order as [`PairInfo::asset_infos`]
+pub const RESERVES: Item<Vec<Uint128>> = Item::new("reserves");
+
+/// Stores a bounded ring buffer of cumulative price observations
+pub const OBSERVATIONS: Deque<Observation> = Deque::new("observations");
+
+/// Stores the pool balance of each asset at every block height it changed
+pub const BALANCES: SnapshotMap<&AssetInfo, Uint128> = SnapshotMap::new(
+    "balances",
+    "balances_check",
+    "balances_change",
+    Strategy::EveryBlock,
+);
+
+/// Stores the latest contract ownership transfer proposal
+pub const OWNERSHIP_PROPOSAL: Item<OwnershipProposal> = Item::new("ownership_proposal");
+
+/// Stores asset precisions keyed by the asset denom or contract address
+const PRECISIONS: Map<String, u8> = Map::new("precisions");
+
+/// Queries and stores the precision of every pool asset.
+pub fn store_precisions(
+    deps: DepsMut,
+    asset_infos: &[AssetInfo],
+    factory_addr: &Addr,
+) -> StdResult<()> {
+    for asset_info in asset_infos {
+        let precision = asset_info.decimals(&deps.querier, factory_addr)?;
+        PRECISIONS.save(deps.storage, asset_info.to_string(), &precision)?;
+    }
+
+    Ok(())
+}
+
+/// Loads the precision of the given pool asset.
+pub fn get_precision(storage: &dyn Storage, asset_info: &AssetInfo) -> StdResult<u8> {
+    PRECISIONS.load(storage, asset_info.to_string())
+}
@@ -0,0 +1,346 @@
+#![allow(clippy::inconsistent_digit_grouping)]
+
+use cosmwasm_std::testing::MockApi;
+use cosmwasm_std::{to_json_binary, Decimal, StdError, Uint128};
+use cw_multi_test::Executor;
+
+use astroport::asset::{native_asset_info, AssetInfoExt};
+use astroport::factory::PauseScope;
+use astroport::pair::ExecuteMsg;
+use astroport_pair_lbp::error::ContractError;
+use astroport_test::coins::TestCoin;
+
+use crate::helper::{lbp_params, AppExtension, Helper};
+
+mod helper;
+
+const START_DELAY: u64 = 100;
+const DURATION: u64 = 86400;
+
+/// Creates a LUNA/uusd LBP with 90/10 -> 10/90 weights starting in [`START_DELAY`] seconds.
+fn setup(native_lp: bool) -> (Helper, Vec<TestCoin>) {
+    let test_coins = vec![TestCoin::native("uluna"), TestCoin::native("uusd")];
+    let owner = MockApi::default().addr_make("owner");
+    let now = cw_multi_test::App::default().block_info().time.seconds();
+    let params = lbp_params(
+        &owner,
+        &[90, 10],
+        &[10, 90],
+        now + START_DELAY,
+        now + START_DELAY + DURATION,
+    );
+
+    let helper = if native_lp {
+        Helper::new_native_lp(test_coins.clone(), params).unwrap()
+    } else {
+        Helper::new(test_coins.clone(), params).unwrap()
+    };
+
+    (helper, test_coins)
+}
+
+#[test]
+fn check_wrong_initialization() {
+    let test_coins = vec![TestCoin::native("uluna"), TestCoin::native("uusd")];
+    let owner = MockApi::default().addr_make("owner");
+    let now = cw_multi_test::App::default().block_info().time.seconds();
+
+    let err = Helper::new(
+        test_coins.clone(),
+        lbp_params(&owner, &[90], &[10, 90], now, now + DURATION),
+    )
+    .unwrap_err();
+    assert_eq!(ContractError::InvalidWeights {}, err.downcast().unwrap());
+
+    let err = Helper::new(
+        test_coins.clone(),
+        lbp_params(&owner, &[90, 10], &[100, 0], now, now + DURATION),
+    )
+    .unwrap_err();
+    assert_eq!(ContractError::InvalidWeights {}, err.downcast().unwrap());
+
+    // End time must be later than the start time
+    let err = Helper::new(
+        test_coins.clone(),
+        lbp_params(&owner, &[90, 10], &[10, 90], now + 10, now + 10),
+    )
+    .unwrap_err();
+    assert_eq!(ContractError::InvalidSchedule {}, err.downcast().unwrap());
+
+    // End time must be in the future
+    let err = Helper::new(
+        test_coins.clone(),
+        lbp_params(&owner, &[90, 10], &[10, 90], now - 20, now),
+    )
+    .unwrap_err();
+    assert_eq!(ContractError::InvalidSchedule {}, err.downcast().unwrap());
+
+    // LBP pairs are permissioned so only the factory owner can create them
+    let (mut helper, test_coins) = setup(false);
+    let user = helper.app.api().addr_make("user");
+    let err = helper
+        .app
+        .execute_contract(
+            user,
+            helper.factory.clone(),
+            &astroport::factory::ExecuteMsg::CreatePair {
+                pair_type: astroport::factory::PairType::Lbp {},
+                asset_infos: vec![
+                    helper.assets[&test_coins[0]].clone(),
+                    native_asset_info("uusdc".to_string()),
+                ],
+                init_params: Some(
+                    to_json_binary(&lbp_params(
+                        &owner,
+                        &[90, 10],
+                        &[10, 90],
+                        now + 10,
+                        now + DURATION,
+                    ))
+                    .unwrap(),
+                ),
+            },
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(
+        astroport_factory::error::ContractError::Unauthorized {},
+        err.downcast().unwrap()
+    );
+}
+
+#[test]
+fn check_owner_only_liquidity() {
+    let (mut helper, test_coins) = setup(true);
+
+    let owner = helper.owner.clone();
+    let user = helper.app.api().addr_make("user");
+    let assets = vec![
+        helper.assets[&test_coins[0]].with_balance(9_000_000_000000u128),
+        helper.assets[&test_coins[1]].with_balance(1_000_000_000000u128),
+    ];
+
+    helper.give_me_money(&assets, &user);
+    let err = helper.provide_liquidity(&user, &assets).unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+
+    // The owner can provide liquidity before the sale starts
+    helper.provide_liquidity(&owner, &assets).unwrap();
+    let lp_amount = helper.native_balance(&helper.lp_token, &owner);
+    assert!(lp_amount > 0);
+
+    // LP tokens transferred to another address can't be redeemed
+    helper
+        .app
+        .send_tokens(
+            owner.clone(),
+            user.clone(),
+            &[cosmwasm_std::coin(1_000000, &helper.lp_token)],
+        )
+        .unwrap();
+    let err = helper.withdraw_liquidity(&user, 1_000000).unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+
+    // Single asset withdrawals are not supported
+    let err = helper
+        .app
+        .execute_contract(
+            owner.clone(),
+            helper.pair_addr.clone(),
+            &ExecuteMsg::WithdrawLiquidity {
+                min_assets_to_receive: None,
+                asset: Some(helper.assets[&test_coins[1]].clone()),
+                deadline: None,
+            },
+            &[cosmwasm_std::coin(1_000000, &helper.lp_token)],
+        )
+        .unwrap_err();
+    assert_eq!(ContractError::NonSupported {}, err.downcast().unwrap());
+
+    let err = helper
+        .simulate_withdraw(1_000000u128, Some(helper.assets[&test_coins[1]].clone()))
+        .unwrap_err();
+    assert_eq!(
+        StdError::generic_err("Querier contract error: Generic error: Operation non supported"),
+        err
+    );
+
+    // The owner withdraws everything after the sale ends
+    helper.app.next_block(START_DELAY + DURATION);
+    let lp_amount = lp_amount - 1_000000;
+    let simulated = helper.simulate_withdraw(lp_amount, None).unwrap();
+    let usd_before = helper.coin_balance(&test_coins[1], &owner);
+    helper.withdraw_liquidity(&owner, lp_amount).unwrap();
+    assert_eq!(helper.native_balance(&helper.lp_token, &owner), 0);
+    assert_eq!(
+        helper.coin_balance(&test_coins[1], &owner) - usd_before,
+        simulated[1].amount.u128()
+    );
+}
+
+#[test]
+fn check_swap_window() {
+    let (mut helper, test_coins) = setup(false);
+
+    let owner = helper.owner.clone();
+    let assets = vec![
+        helper.assets[&test_coins[0]].with_balance(9_000_000_000000u128),
+        helper.assets[&test_coins[1]].with_balance(1_000_000_000000u128),
+    ];
+    helper.provide_liquidity(&owner, &assets).unwrap();
+
+    let config = helper.query_config_params().unwrap().1;
+    let window_err = ContractError::OutsideSwapWindow {
+        start_time: config.start_time,
+        end_time: config.end_time,
+    };
+
+    let user = helper.app.api().addr_make("user");
+    let offer_asset = helper.assets[&test_coins[1]].with_balance(1_000_000000u128);
+    helper.give_me_money(std::slice::from_ref(&offer_asset), &user);
+
+    // Swaps are not allowed before the start
+    let err = helper.swap(&user, &offer_asset, None).unwrap_err();
+    assert_eq!(window_err, err.downcast().unwrap());
+    let err = helper.simulate_swap(&offer_asset, None).unwrap_err();
+    assert_eq!(
+        StdError::generic_err(format!(
+            "Querier contract error: Generic error: {window_err}"
+        )),
+        err
+    );
+
+    helper.app.next_block(START_DELAY);
+    helper.swap(&user, &offer_asset, None).unwrap();
+    assert!(helper.coin_balance(&test_coins[0], &user) > 0);
+
+    // The pair can be paused as any other pair
+    helper.pause(PauseScope::Swap).unwrap();
+    helper.give_me_money(std::slice::from_ref(&offer_asset), &user);
+    let err = helper.swap(&user, &offer_asset, None).unwrap_err();
+    assert_eq!(
+        ContractError::Paused(PauseScope::Swap),
+        err.downcast().unwrap()
+    );
+
+    // Swaps are not allowed after the end
+    helper.app.next_block(DURATION);
+    let err = helper.swap(&user, &offer_asset, None).unwrap_err();
+    assert_eq!(window_err, err.downcast().unwrap());
+}
+
+#[test]
+fn check_weights_shift() {
+    let (mut helper, test_coins) = setup(false);
+
+    let owner = helper.owner.clone();
+    let assets = vec![
+        helper.assets[&test_coins[0]].with_balance(9_000_000_000000u128),
+        helper.assets[&test_coins[1]].with_balance(1_000_000_000000u128),
+    ];
+    helper.provide_liquidity(&owner, &assets).unwrap();
+
+    let weights = |helper: &Helper| {
+        helper
+            .query_config_params()
+            .unwrap()
+            .1
+            .weights
+            .into_iter()
+            .map(|(_, weight)| weight)
+            .collect::<Vec<_>>()
+    };
+
+    // Weights stay at the start values until the sale starts
+    assert_eq!(
+        weights(&helper),
+        vec![Decimal::percent(90), Decimal::percent(10)]
+    );
+
+    helper.app.next_block(START_DELAY);
+    let ask_asset = helper.assets[&test_coins[0]].with_balance(1_000000u128);
+    let price_at_start = helper
+        .simulate_reverse_swap(&ask_asset, None)
+        .unwrap()
+        .offer_amount;
+    // 9M LUNA with 90% weight vs 1M USD with 10% weight: 1 LUNA = 1 USD
+    assert!(
+        price_at_start.u128().abs_diff(1_003009) < 10,
+        "{price_at_start}"
+    );
+
+    // Halfway through the sale the weights are 50/50
+    helper.app.next_block(DURATION / 2);
+    assert_eq!(
+        weights(&helper),
+        vec![Decimal::percent(50), Decimal::percent(50)]
+    );
+
+    // Without any trades the price of LUNA declines as its weight decreases
+    let price_at_middle = helper
+        .simulate_reverse_swap(&ask_asset, None)
+        .unwrap()
+        .offer_amount;
+    assert!(
+        price_at_middle.u128().abs_diff(111_445) < 10,
+        "{price_at_middle}"
+    );
+
+    // Weights stay at the end values after the sale ends
+    helper.app.next_block(DURATION);
+    assert_eq!(
+        weights(&helper),
+        vec![Decimal::percent(10), Decimal::percent(90)]
+    );
+}
+
+#[test]
+fn check_swap_event() {
+    let (mut helper, test_coins) = setup(false);
+
+    let owner = helper.owner.clone();
+    let assets = vec![
+        helper.assets[&test_coins[0]].with_balance(9_000_000_000000u128),
+        helper.assets[&test_coins[1]].with_balance(1_000_000_000000u128),
+    ];
+    helper.provide_liquidity(&owner, &assets).unwrap();
+    helper.app.next_block(START_DELAY);
+
+    let user = helper.app.api().addr_make("user");
+    let offer_asset = helper.assets[&test_coins[1]].with_balance(1_000000u128);
+    helper.give_me_money(std::slice::from_ref(&offer_asset), &user);
+    let sim = helper.simulate_swap(&offer_asset, None).unwrap();
+    let resp = helper.swap(&user, &offer_asset, None).unwrap();
+
+    // The swap event has the same attributes as the one emitted by the XYK pair
+    let event = resp
+        .events
+        .iter()
+        .find(|event| event.ty == "wasm-swap")
+        .unwrap();
+    let attrs = event
+        .attributes
+        .iter()
+        .skip(1)
+        .map(|attr| (attr.key.as_str(), attr.value.clone()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        attrs,
+        vec![
+            ("action", "swap".to_string()),
+            ("sender", user.to_string()),
+            ("receiver", user.to_string()),
+            ("offer_asset", "uusd".to_string()),
+            ("ask_asset", "uluna".to_string()),
+            ("offer_amount", "1000000".to_string()),
+            ("return_amount", sim.return_amount.to_string()),
+            ("spread_amount", sim.spread_amount.to_string()),
+            ("commission_amount", sim.commission_amount.to_string()),
+            (
+                "maker_fee_amount",
+                (sim.commission_amount / Uint128::new(2)).to_string()
+            ),
+            ("fee_share_amount", "0".to_string()),
+        ]
+    );
+}
@@ -0,0 +1,1538 @@
+use std::vec;
+
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+use cosmwasm_std::{
+    attr, coin, ensure, ensure_eq, from_json, to_json_binary, wasm_execute, wasm_instantiate, Addr,
+    Binary, CosmosMsg, Decimal, Decimal256, Deps, DepsMut, Empty, Env, Event, MessageInfo, Reply,
+    Response, StdError, StdResult, SubMsg, SubMsgResponse, SubMsgResult, Uint128, Uint256, Uint64,
+};
+use cw2::{get_contract_version, set_contract_version};
+use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg, MinterResponse};
+use cw_utils::{must_pay, parse_instantiate_response_data};
+use itertools::Itertools;
+
+use astroport::asset::{
+    addr_opt_validate, check_swap_parameters, format_lp_token_name, Asset, AssetInfo, AssetInfoExt,
+    CoinsExt, PairInfo, MINIMUM_LIQUIDITY_AMOUNT,
+};
+use astroport::common::{claim_ownership, drop_ownership_proposal, propose_new_owner};
+use astroport::cosmwasm_ext::{DecMul, DecimalToInteger, IntegerToDecimal};
+use astroport::factory::PauseScope;
+use astroport::observation::{query_observations, Observation};
+use astroport::pair::{
+    is_deadline_exceeded, ConfigResponse, CumulativePricesResponse, Cw20HookMsg, ExecuteMsg,
+    FeeShareConfig, InstantiateMsg, PairHookMsg, PoolResponse, QueryMsg, ReverseSimulationResponse,
+    SimulationResponse, StablePoolConfig, StablePoolParams, StablePoolUpdateParams,
+    TraderAllowlist, HOOK_REPLY_ID, INSTANTIATE_TOKEN_REPLY_ID, MAX_FEE_SHARE_BPS,
+    PRICE_DEVIATION_LIMITS,
+};
+use astroport::querier::{
+    ensure_not_paused, query_factory_config, query_fee_info, query_lp_supply,
+};
+use astroport::stats::{epoch_at, AssetStats, StatsResponse};
+use astroport::token_factory::{tf_burn_msg, tf_create_denom_msg, tf_denom, LP_SUBDENOM};
+
+use crate::error::ContractError;
+use crate::math::{
+    compute_current_amp, compute_d, AMP_PRECISION, MAX_AMP, MAX_AMP_CHANGE, MIN_AMP_CHANGING_TIME,
+};
+use crate::state::{
+    get_precision, store_precisions, Config, BALANCES, CONFIG, OBSERVATIONS, OWNERSHIP_PROPOSAL,
+    RESERVES, STATS,
+};
+use crate::utils::{
+    accumulate_prices, apply_rates, assert_max_spread, asset_position, check_asset_infos,
+    check_hooks, check_rate_sources, check_trader_allowlist, compute_offer_amount, compute_swap,
+    ensure_min_assets_to_receive, ensure_price_deviation, ensure_traders_allowed,
+    get_share_in_assets, mint_liquidity_token_message, pool_info, query_rates, query_reserves,
+    save_observation, save_reserves, select_pools, to_decimal_pools,
+};
+
+/// Contract name that is used for migration.
+const CONTRACT_NAME: &str = env!("CARGO_PKG_NAME");
+/// Contract version that is used for migration.
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+/// An LP token's precision.
+pub(crate) const LP_TOKEN_PRECISION: u8 = 6;
+
+/// Creates a new contract with the specified parameters in the [`InstantiateMsg`].
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn instantiate(
+    mut deps: DepsMut,
+    env: Env,
+    _info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
+    check_asset_infos(deps.api, &msg.asset_infos)?;
+
+    let params: StablePoolParams = from_json(
+        msg.init_params
+            .ok_or(ContractError::InitParamsNotFound {})?,
+    )?;
+
+    if params.amp == 0 || params.amp > MAX_AMP {
+        return Err(ContractError::IncorrectAmp {});
+    }
+
+    let rate_sources = check_rate_sources(deps.api, &msg.asset_infos, &params.rate_sources)?;
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    let factory_addr = deps.api.addr_validate(&msg.factory_addr)?;
+
+    store_precisions(deps.branch(), &msg.asset_infos, &factory_addr)?;
+
+    // Initializing cumulative prices for every ordered pair of assets
+    let cumulative_prices = msg
+        .asset_infos
+        .iter()
+        .cloned()
+        .permutations(2)
+        .map(|pair| (pair[0].clone(), pair[1].clone(), Uint128::zero()))
+        .collect();
+
+    let liquidity_token = if msg.native_lp_token {
+        tf_denom(&env.contract.address, LP_SUBDENOM)
+    } else {
+        "".to_owned()
+    };
+
+    let config = Config {
+        owner: addr_opt_validate(deps.api, &params.owner)?,
+        pair_info: PairInfo {
+            contract_addr: env.contract.address.clone(),
+            liquidity_token,
+            asset_infos: msg.asset_infos.clone(),
+            pair_type: msg.pair_type,
+        },
+        factory_addr,
+        block_time_last: 0,
+        init_amp: params.amp * AMP_PRECISION,
+        init_amp_time: env.block.time.seconds(),
+        next_amp: params.amp * AMP_PRECISION,
+        next_amp_time: env.block.time.seconds(),
+        cumulative_prices,
+        fee_share: None,
+        native_lp_token: msg.native_lp_token,
+        rate_sources,
+        hooks: vec![],
+        block_open_prices: vec![],
+        max_price_deviation: None,
+        trader_allowlist: None,
+    };
+
+    CONFIG.save(deps.storage, &config)?;
+    RESERVES.save(
+        deps.storage,
+        &vec![Uint128::zero(); config.pair_info.asset_infos.len()],
+    )?;
+
+    // The LP denom is known upfront so there is no need to wait for a reply
+    if msg.native_lp_token {
+        return Ok(Response::new()
+            .add_message(tf_create_denom_msg(&env.contract.address, LP_SUBDENOM))
+            .add_attribute("liquidity_token", config.pair_info.liquidity_token));
+    }
+
+    let sub_msg = SubMsg::reply_on_success(
+        wasm_instantiate(
+            msg.token_code_id,
+            &cw20_base::msg::InstantiateMsg {
+                name: format_lp_token_name(&msg.asset_infos, &deps.querier)?,
+                symbol: "uLP".to_string(),
+                decimals: LP_TOKEN_PRECISION,
+                initial_balances: vec![],
+                mint: Some(MinterResponse {
+                    minter: env.contract.address.to_string(),
+                    cap: None,
+                }),
+                marketing: None,
+            },
+            vec![],
+            "LP token".to_string(),
+        )?,
+        INSTANTIATE_TOKEN_REPLY_ID,
+    );
+
+    Ok(Response::new().add_submessage(sub_msg))
+}
+
+/// The entry point to the contract for processing replies from submessages.
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
+    match msg {
+        #[allow(deprecated)]
+        Reply {
+            id: INSTANTIATE_TOKEN_REPLY_ID,
+            result:
+                SubMsgResult::Ok(SubMsgResponse {
+                    data: Some(data), ..
+                }),
+            ..
+        } => {
+            let config = CONFIG.update::<_, ContractError>(deps.storage, |mut config| {
+                config.pair_info.liquidity_token =
+                    parse_instantiate_response_data(data.as_slice())?.contract_address;
+                Ok(config)
+            })?;
+
+            Ok(Response::new()
+                .add_attribute("liquidity_token_addr", config.pair_info.liquidity_token))
+        }
+        // Failures of non-blocking hooks are ignored
+        Reply {
+            id: HOOK_REPLY_ID,
+            result: SubMsgResult::Err(err),
+            ..
+        } => Ok(Response::new().add_attribute("hook_error", err)),
+        _ => Err(StdError::generic_err("Invalid reply".to_string()).into()),
+    }
+}
+
+/// Exposes all the execute functions available in the contract.
+///
+/// ## Variants
+/// * **ExecuteMsg::UpdateConfig { params: Binary }** Updates the contract configuration with the
+/// specified [`StablePoolUpdateParams`].
+///
+/// * **ExecuteMsg::Receive(msg)** Receives a message of type [`Cw20ReceiveMsg`] and processes
+/// it depending on the received template.
+///
+/// * **ExecuteMsg::ProvideLiquidity {
+///             assets,
+///             slippage_tolerance,
+///             auto_stake,
+///             receiver,
+///             min_lp_to_receive,
+///             deadline,
+///         }** Provides liquidity in the pair with the specified input parameters.
+///
+/// * **ExecuteMsg::Swap {
+///             offer_asset,
+///             ask_asset_info,
+///             belief_price,
+///             max_spread,
+///             to,
+///             deadline,
+///         }** Performs a swap operation with the specified parameters.
+///
+/// * **ExecuteMsg::WithdrawLiquidity { min_assets_to_receive }** Withdraws liquidity from the pool
+/// by burning the LP token denom sent along with the message.
+///
+/// Swap and liquidity messages are rejected with [`ContractError::DeadlineExceeded`]
+/// once the block time is past their optional deadline.
+///
+/// * **ExecuteMsg::Skim { to }** Sends the pair balances exceeding the tracked reserves to the recipient.
+///
+/// * **ExecuteMsg::Sync {}** Updates the tracked reserves to match the pair balances.
+///
+/// * **ExecuteMsg::ProposeNewOwner { owner, expires_in }** Creates a new request to change
+/// contract ownership.
+///
+/// * **ExecuteMsg::DropOwnershipProposal {}** Removes a request to change contract ownership.
+///
+/// * **ExecuteMsg::ClaimOwnership {}** Claims contract ownership.
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    if is_deadline_exceeded(&env.block, msg.deadline()) {
+        return Err(ContractError::DeadlineExceeded {});
+    }
+
+    match msg {
+        ExecuteMsg::Receive(msg) => receive_cw20(deps, env, info, msg),
+        ExecuteMsg::ProvideLiquidity {
+            assets,
+            auto_stake,
+            receiver,
+            min_lp_to_receive,
+            ..
+        } => provide_liquidity(
+            deps,
+            env,
+            info,
+            assets,
+            auto_stake,
+            receiver,
+            min_lp_to_receive,
+        ),
+        ExecuteMsg::Swap {
+            offer_asset,
+            ask_asset_info,
+            belief_price,
+            max_spread,
+            to,
+            ..
+        } => {
+            offer_asset.info.check(deps.api)?;
+            if !offer_asset.info.is_native_token() {
+                return Err(ContractError::Cw20DirectSwap {});
+            }
+
+            let to_addr = addr_opt_validate(deps.api, &to)?;
+
+            swap(
+                deps,
+                env,
+                info.clone(),
+                info.sender,
+                offer_asset,
+                ask_asset_info,
+                belief_price,
+                max_spread,
+                to_addr,
+            )
+        }
+        ExecuteMsg::WithdrawLiquidity {
+            min_assets_to_receive,
+            asset,
+            ..
+        } => {
+            let config = CONFIG.load(deps.storage)?;
+            if !config.native_lp_token || asset.is_some() {
+                return Err(ContractError::NonSupported {});
+            }
+
+            let amount = must_pay(&info, &config.pair_info.liquidity_token)?;
+            let sender = info.sender.clone();
+
+            withdraw_liquidity(deps, env, info, min_assets_to_receive, sender, amount)
+        }
+        ExecuteMsg::SwapExactOut { .. }
+        | ExecuteMsg::FlashSwap { .. }
+        | ExecuteMsg::CollectProtocolFees {}
+        | ExecuteMsg::SettleBatch {}
+        | ExecuteMsg::CancelBatchOrder { .. } => Err(ContractError::NonSupported {}),
+        ExecuteMsg::Skim { to } => {
+            let to_addr = addr_opt_validate(deps.api, &to)?.unwrap_or(info.sender);
+            skim(deps, env, to_addr)
+        }
+        ExecuteMsg::Sync {} => sync(deps, env),
+        ExecuteMsg::UpdateConfig { params } => update_config(deps, env, info, params),
+        ExecuteMsg::ProposeNewOwner { owner, expires_in } => {
+            let config = CONFIG.load(deps.storage)?;
+            let factory_config = query_factory_config(&deps.querier, config.factory_addr)?;
+
+            propose_new_owner(
+                deps,
+                info,
+                env,
+                owner,
+                expires_in,
+                config.owner.unwrap_or(factory_config.owner),
+                OWNERSHIP_PROPOSAL,
+            )
+            .map_err(Into::into)
+        }
+        ExecuteMsg::DropOwnershipProposal {} => {
+            let config = CONFIG.load(deps.storage)?;
+            let factory_config = query_factory_config(&deps.querier, config.factory_addr)?;
+
+            drop_ownership_proposal(
+                deps,
+                info,
+                config.owner.unwrap_or(factory_config.owner),
+                OWNERSHIP_PROPOSAL,
+            )
+            .map_err(Into::into)
+        }
+        ExecuteMsg::ClaimOwnership {} => {
+            claim_ownership(deps, info, env, OWNERSHIP_PROPOSAL, |deps, new_owner| {
+                CONFIG.update::<_, StdError>(deps.storage, |mut config| {
+                    config.owner = Some(new_owner);
+                    Ok(config)
+                })?;
+
+                Ok(())
+            })
+            .map_err(Into::into)
+        }
+    }
+}
+
+/// Receives a message of type [`Cw20ReceiveMsg`] and processes it depending on the received template.
+///
+/// * **cw20_msg** is the CW20 message that has to be processed.
+pub fn receive_cw20(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    cw20_msg: Cw20ReceiveMsg,
+) -> Result<Response, ContractError> {
+    let hook_msg: Cw20HookMsg = from_json(&cw20_msg.msg)?;
+    if is_deadline_exceeded(&env.block, hook_msg.deadline()) {
+        return Err(ContractError::DeadlineExceeded {});
+    }
+
+    match hook_msg {
+        Cw20HookMsg::Swap {
+            ask_asset_info,
+            belief_price,
+            max_spread,
+            to,
+            ..
+        } => {
+            // Only asset contract can execute this message
+            let config = CONFIG.load(deps.storage)?;
+            let offer_asset_info = AssetInfo::Token {
+                contract_addr: info.sender.clone(),
+            };
+            if !config.pair_info.asset_infos.contains(&offer_asset_info) {
+                return Err(ContractError::Unauthorized {});
+            }
+
+            let to_addr = addr_opt_validate(deps.api, &to)?;
+
+            swap(
+                deps,
+                env,
+                info,
+                Addr::unchecked(cw20_msg.sender),
+                offer_asset_info.with_balance(cw20_msg.amount),
+                ask_asset_info,
+                belief_price,
+                max_spread,
+                to_addr,
+            )
+        }
+        Cw20HookMsg::SwapExactOut { .. } => Err(ContractError::NonSupported {}),
+        Cw20HookMsg::WithdrawLiquidity {
+            min_assets_to_receive,
+            asset,
+            ..
+        } => {
+            let config = CONFIG.load(deps.storage)?;
+            ensure_eq!(
+                info.sender.to_string(),
+                config.pair_info.liquidity_token,
+                ContractError::Unauthorized {}
+            );
+            if asset.is_some() {
+                return Err(ContractError::NonSupported {});
+            }
+
+            withdraw_liquidity(
+                deps,
+                env,
+                info,
+                min_assets_to_receive,
+                Addr::unchecked(cw20_msg.sender),
+                cw20_msg.amount,
+            )
+        }
+    }
+}
+
+/// Provides liquidity in the pair with the specified input parameters.
+/// Any subset of pool assets can be deposited. Imbalanced deposits are charged
+/// an imbalance fee which stays in the pool. Slippage tolerance is not applicable to
+/// stableswap pools, **min_lp_to_receive** should be used instead.
+///
+/// * **assets** is an array with assets available in the pool.
+///
+/// * **auto_stake** is an optional parameter which determines whether the LP tokens minted after
+///   liquidity provision are automatically staked in the Incentives contract on behalf of the LP token receiver.
+///
+/// * **receiver** is an optional parameter which defines the receiver of the LP tokens.
+///   If no custom receiver is specified, the pair will mint LP tokens for the function caller.
+///
+/// * **min_lp_to_receive** is an optional parameter which specifies the minimum amount of LP tokens to receive.
+///   NOTE - the address that wants to provide liquidity should approve the pair contract to pull its relevant tokens.
+pub fn provide_liquidity(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    assets: Vec<Asset>,
+    auto_stake: Option<bool>,
+    receiver: Option<String>,
+    min_lp_to_receive: Option<Uint128>,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    ensure_not_paused(
+        &deps.querier,
+        &config.factory_addr,
+        &config.pair_info.contract_addr,
+        PauseScope::Provide,
+        ContractError::Paused,
+    )?;
+
+    let receiver = addr_opt_validate(deps.api, &receiver)?.unwrap_or_else(|| info.sender.clone());
+    ensure_traders_allowed(deps.querier, &config, &[&info.sender, &receiver])?;
+
+    let deposits = get_deposits_from_assets(&config, &assets)?;
+
+    info.funds
+        .assert_coins_properly_sent(&assets, &config.pair_info.asset_infos)?;
+
+    let pools = query_reserves(deps.storage, &config)?;
+
+    let mut messages = vec![];
+    for (i, pool) in pools.iter().enumerate() {
+        // If the asset is a token contract, then we need to execute a TransferFrom msg to receive assets
+        if let AssetInfo::Token { contract_addr } = &pool.info {
+            if !deposits[i].is_zero() {
+                messages.push(CosmosMsg::Wasm(wasm_execute(
+                    contract_addr,
+                    &Cw20ExecuteMsg::TransferFrom {
+                        owner: info.sender.to_string(),
+                        recipient: env.contract.address.to_string(),
+                        amount: deposits[i],
+                    },
+                    vec![],
+                )?));
+            }
+        }
+    }
+
+    let total_share = query_lp_supply(
+        &deps.querier,
+        &config.pair_info.liquidity_token,
+        config.native_lp_token,
+    )?;
+    let share = calculate_shares(deps.as_ref(), &env, &config, &pools, &deposits, total_share)?;
+
+    if total_share.is_zero() {
+        messages.extend(mint_liquidity_token_message(
+            deps.querier,
+            &config,
+            &env.contract.address,
+            &env.contract.address,
+            MINIMUM_LIQUIDITY_AMOUNT,
+            false,
+        )?);
+    }
+
+    let min_amount_lp = min_lp_to_receive.unwrap_or_default();
+    ensure!(
+        share >= min_amount_lp,
+        ContractError::ProvideSlippageViolation(share, min_amount_lp)
+    );
+
+    // Mint LP tokens for the sender or for the receiver (if set)
+    messages.extend(mint_liquidity_token_message(
+        deps.querier,
+        &config,
+        &env.contract.address,
+        &receiver,
+        share,
+        auto_stake.unwrap_or(false),
+    )?);
+
+    let new_pools = pools
+        .iter()
+        .zip(deposits.iter())
+        .map(|(pool, deposit)| Ok(pool.info.with_balance(pool.amount.checked_add(*deposit)?)))
+        .collect::<StdResult<Vec<_>>>()?;
+    save_reserves(deps.storage, &new_pools, env.block.height)?;
+
+    // Accumulate prices for the assets in the pool
+    let xp = to_decimal_pools(deps.storage, &pools)?;
+    let rates = query_rates(deps.as_ref(), &config)?;
+    accumulate_prices(&env, &mut config, &xp, &rates)?;
+    CONFIG.save(deps.storage, &config)?;
+    save_observation(deps.storage, &config)?;
+
+    // An imbalanced deposit moves the prices of the pool assets
+    if !total_share.is_zero() {
+        let new_xp = to_decimal_pools(deps.storage, &new_pools)?;
+        for (offer_ind, ask_ind) in (0..new_xp.len()).tuple_combinations() {
+            ensure_price_deviation(&env, &config, &new_xp, &rates, offer_ind, ask_ind)?;
+        }
+    }
+
+    let event = Event::new("provide_liquidity").add_attributes(vec![
+        attr("action", "provide_liquidity"),
+        attr("sender", info.sender),
+        attr("receiver", &receiver),
+        attr("assets", assets.iter().join(", ")),
+        attr("share", share),
+    ]);
+
+    let hook_msgs = PairHookMsg::AfterProvide {
+        assets,
+        share,
+        receiver,
+    }
+    .into_submsgs(&config.hooks)?;
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_submessages(hook_msgs)
+        .add_event(event))
+}
+
+/// Withdraw liquidity from the pool.
+///
+/// * **receiver** address that will receive assets back from the pair contract.
+///
+/// * **amount** amount of LP tokens to burn.
+pub fn withdraw_liquidity(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    min_assets_to_receive: Option<Vec<Asset>>,
+    receiver: Addr,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+
+    let (pools, total_share) = pool_info(deps.as_ref(), &config)?;
+
+    let refund_assets = get_share_in_assets(&pools, amount, total_share);
+
+    ensure_min_assets_to_receive(&refund_assets, min_assets_to_receive)?;
+
+    let new_pools = pools
+        .iter()
+        .zip(refund_assets.iter())
+        .map(|(pool, refund_asset)| {
+            Ok(pool
+                .info
+                .with_balance(pool.amount.checked_sub(refund_asset.amount)?))
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    save_reserves(deps.storage, &new_pools, env.block.height)?;
+
+    // Accumulate prices for the assets in the pool
+    let xp = to_decimal_pools(deps.storage, &pools)?;
+    let rates = query_rates(deps.as_ref(), &config)?;
+    accumulate_prices(&env, &mut config, &xp, &rates)?;
+    CONFIG.save(deps.storage, &config)?;
+    save_observation(deps.storage, &config)?;
+
+    let mut messages = refund_assets
+        .iter()
+        .filter(|asset| !asset.amount.is_zero())
+        .map(|asset| asset.clone().into_msg(&receiver))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    if config.native_lp_token {
+        messages.push(tf_burn_msg(
+            &env.contract.address,
+            coin(amount.into(), &config.pair_info.liquidity_token),
+        ));
+    } else {
+        messages.push(
+            wasm_execute(
+                &config.pair_info.liquidity_token,
+                &Cw20ExecuteMsg::Burn { amount },
+                vec![],
+            )?
+            .into(),
+        );
+    }
+
+    let event = Event::new("withdraw_liquidity").add_attributes(vec![
+        attr("action", "withdraw_liquidity"),
+        attr("sender", info.sender),
+        attr("withdrawn_share", amount),
+        attr("refund_assets", refund_assets.iter().join(", ")),
+        attr("receiver", &receiver),
+    ]);
+
+    let hook_msgs = PairHookMsg::AfterWithdraw {
+        assets: refund_assets,
+        share: amount,
+        receiver,
+    }
+    .into_submsgs(&config.hooks)?;
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_submessages(hook_msgs)
+        .add_event(event))
+}
+
+/// Performs an swap operation with the specified parameters. The trader must approve the
+/// pool contract to transfer offer assets from their wallet.
+///
+/// * **sender** is the sender of the swap operation.
+///
+/// * **offer_asset** proposed asset for swapping.
+///
+/// * **ask_asset_info** is the asset to swap to. Must be specified if the pool holds more than two assets.
+///
+/// * **belief_price** is used to calculate the maximum swap spread.
+///
+/// * **max_spread** sets the maximum spread of the swap operation.
+///
+/// * **to** sets the recipient of the swap operation.
+#[allow(clippy::too_many_arguments)]
+pub fn swap(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    sender: Addr,
+    offer_asset: Asset,
+    ask_asset_info: Option<AssetInfo>,
+    belief_price: Option<Decimal>,
+    max_spread: Option<Decimal>,
+    to: Option<Addr>,
+) -> Result<Response, ContractError> {
+    offer_asset.assert_sent_native_token_balance(&info)?;
+
+    let mut config = CONFIG.load(deps.storage)?;
+    ensure_not_paused(
+        &deps.querier,
+        &config.factory_addr,
+        &config.pair_info.contract_addr,
+        PauseScope::Swap,
+        ContractError::Paused,
+    )?;
+    ensure_traders_allowed(
+        deps.querier,
+        &config,
+        &[&sender, to.as_ref().unwrap_or(&sender)],
+    )?;
+
+    let (offer_ind, ask_ind) =
+        select_pools(&config, Some(&offer_asset.info), ask_asset_info.as_ref())?;
+
+    let pools = query_reserves(deps.storage, &config)?;
+
+    check_swap_parameters(
+        pools.iter().map(|pool| pool.amount).collect(),
+        offer_asset.amount,
+    )?;
+
+    let xp = to_decimal_pools(deps.storage, &pools)?;
+    let rates = query_rates(deps.as_ref(), &config)?;
+    let offer_precision = get_precision(deps.storage, &offer_asset.info)?;
+    let ask_precision = get_precision(deps.storage, &pools[ask_ind].info)?;
+
+    // Get fee info from the factory
+    let fee_info = query_fee_info(
+        &deps.querier,
+        &config.factory_addr,
+        config.pair_info.pair_type.clone(),
+        &config.pair_info.contract_addr,
+    )?;
+
+    let (return_amount, spread_amount, commission_amount) = compute_swap(
+        compute_current_amp(&config, &env)?,
+        &xp,
+        &rates,
+        offer_ind,
+        ask_ind,
+        offer_asset.amount.to_decimal256(offer_precision),
+        fee_info.total_fee_rate.into(),
+    )?;
+    let return_amount: Uint128 = return_amount.to_uint(ask_precision)?;
+    let spread_amount: Uint128 = spread_amount.to_uint(ask_precision)?;
+    let commission_amount: Uint128 = commission_amount.to_uint(ask_precision)?;
+
+    // Check the max spread limit (if it was specified)
+    assert_max_spread(
+        belief_price,
+        max_spread,
+        offer_asset.amount,
+        return_amount + commission_amount,
+        spread_amount,
+    )?;
+
+    let ask_asset_info = pools[ask_ind].info.clone();
+    let receiver = to.unwrap_or_else(|| sender.clone());
+    let mut messages = vec![];
+    if !return_amount.is_zero() {
+        messages.push(
+            ask_asset_info
+                .with_balance(return_amount)
+                .into_msg(&receiver)?,
+        );
+    }
+
+    // If this pool is configured to share fees, calculate the amount to send
+    // to the receiver and add the transfer message
+    // The calculation works as follows: We take the share percentage first,
+    // and the remainder is then split between LPs and maker
+    let mut fees_commission_amount = commission_amount;
+    let mut fee_share_amount = Uint128::zero();
+    if let Some(fee_share) = config.fee_share.clone() {
+        // Calculate the fee share amount from the full commission amount
+        let share_fee_rate = Decimal::from_ratio(fee_share.total_bps(), 10000u16);
+        fee_share_amount = fees_commission_amount.dec_mul(share_fee_rate);
+
+        if !fee_share_amount.is_zero() {
+            // Subtract the fee share amount from the commission
+            fees_commission_amount = fees_commission_amount.saturating_sub(fee_share_amount);
+
+            // Build send messages for the shared amount
+            messages.extend(fee_share.into_msgs(&ask_asset_info, fee_share_amount)?);
+        }
+    }
+
+    // Compute the Maker fee
+    let mut maker_fee_amount = Uint128::zero();
+    if let Some(fee_address) = fee_info.fee_address {
+        maker_fee_amount = fees_commission_amount.dec_mul(fee_info.maker_fee_rate);
+        if !maker_fee_amount.is_zero() {
+            messages.push(
+                ask_asset_info
+                    .with_balance(maker_fee_amount)
+                    .into_msg(fee_address)?,
+            );
+        }
+    }
+
+    let mut new_pools = pools.clone();
+    new_pools[offer_ind].amount = new_pools[offer_ind]
+        .amount
+        .checked_add(offer_asset.amount)?;
+    new_pools[ask_ind].amount = new_pools[ask_ind]
+        .amount
+        .checked_sub(return_amount + fee_share_amount + maker_fee_amount)?;
+    save_reserves(deps.storage, &new_pools, env.block.height)?;
+    STATS.record(
+        deps.storage,
+        &config.pair_info.asset_infos,
+        env.block.time.seconds(),
+        &AssetStats::swap(
+            &offer_asset,
+            &ask_asset_info.with_balance(return_amount),
+            fees_commission_amount.checked_sub(maker_fee_amount)?,
+            maker_fee_amount,
+            fee_share_amount,
+        ),
+    )?;
+
+    // Accumulate prices for the assets in the pool
+    accumulate_prices(&env, &mut config, &xp, &rates)?;
+    CONFIG.save(deps.storage, &config)?;
+    save_observation(deps.storage, &config)?;
+
+    let new_xp = to_decimal_pools(deps.storage, &new_pools)?;
+    ensure_price_deviation(&env, &config, &new_xp, &rates, offer_ind, ask_ind)?;
+
+    let event = Event::new("swap").add_attributes(vec![
+        attr("action", "swap"),
+        attr("sender", &sender),
+        attr("receiver", receiver),
+        attr("offer_asset", offer_asset.info.to_string()),
+        attr("ask_asset", ask_asset_info.to_string()),
+        attr("offer_amount", offer_asset.amount),
+        attr("return_amount", return_amount),
+        attr("spread_amount", spread_amount),
+        attr("commission_amount", commission_amount),
+        attr("maker_fee_amount", maker_fee_amount),
+        attr("fee_share_amount", fee_share_amount),
+    ]);
+
+    let hook_msgs = PairHookMsg::AfterSwap {
+        offer: offer_asset,
+        ask: ask_asset_info.with_balance(return_amount),
+        trader: sender,
+    }
+    .into_submsgs(&config.hooks)?;
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_submessages(hook_msgs)
+        .add_event(event))
+}
+
+/// Sends the pair balances exceeding the tracked reserves (e.g. direct token transfers)
+/// to the specified recipient.
+///
+/// * **to** is the recipient of the excess balances.
+pub fn skim(deps: DepsMut, env: Env, to: Addr) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    let reserves = query_reserves(deps.storage, &config)?;
+    let balances = config
+        .pair_info
+        .query_pools(&deps.querier, &env.contract.address)?;
+
+    let excess_assets = balances
+        .into_iter()
+        .zip(reserves)
+        .map(|(balance, reserve)| {
+            balance
+                .info
+                .with_balance(balance.amount.saturating_sub(reserve.amount))
+        })
+        .filter(|asset| !asset.amount.is_zero())
+        .collect_vec();
+
+    let messages = excess_assets
+        .iter()
+        .map(|asset| asset.clone().into_msg(&to))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let event = Event::new("skim").add_attributes(vec![
+        attr("action", "skim"),
+        attr("receiver", to),
+        attr("assets", excess_assets.iter().join(", ")),
+    ]);
+
+    Ok(Response::new().add_messages(messages).add_event(event))
+}
+
+/// Updates the tracked reserves to match the current pair balances.
+pub fn sync(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+
+    let reserves = query_reserves(deps.storage, &config)?;
+    let balances = config
+        .pair_info
+        .query_pools(&deps.querier, &env.contract.address)?;
+
+    save_reserves(deps.storage, &balances, env.block.height)?;
+
+    // Accumulate prices up to this moment using the previous reserves
+    let xp = to_decimal_pools(deps.storage, &reserves)?;
+    let rates = query_rates(deps.as_ref(), &config)?;
+    accumulate_prices(&env, &mut config, &xp, &rates)?;
+    CONFIG.save(deps.storage, &config)?;
+    save_observation(deps.storage, &config)?;
+
+    let event = Event::new("sync").add_attributes(vec![
+        attr("action", "sync"),
+        attr("reserves", balances.iter().join(", ")),
+    ]);
+
+    Ok(Response::new().add_event(event))
+}
+
+/// Updates the pool configuration with the specified parameters in the `params` variable.
+///
+/// * **params** new parameter values in [`Binary`] form.
+pub fn update_config(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    params: Binary,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    let factory_config = query_factory_config(&deps.querier, &config.factory_addr)?;
+
+    let owner = config.owner.as_ref().unwrap_or(&factory_config.owner);
+    if info.sender != *owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let mut event = Event::new("update_config");
+
+    match from_json::<StablePoolUpdateParams>(&params)? {
+        StablePoolUpdateParams::StartChangingAmp {
+            next_amp,
+            next_amp_time,
+        } => {
+            start_changing_amp(&mut config, &env, next_amp, next_amp_time)?;
+
+            event = event
+                .add_attribute("action", "start_changing_amp")
+                .add_attribute("next_amp", next_amp.to_string())
+                .add_attribute("next_amp_time", next_amp_time.to_string());
+        }
+        StablePoolUpdateParams::StopChangingAmp {} => {
+            stop_changing_amp(&mut config, &env)?;
+
+            event = event.add_attribute("action", "stop_changing_amp");
+        }
+        StablePoolUpdateParams::EnableFeeShare {
+            fee_share_bps,
+            fee_share_address,
+        } => {
+            // Enable fee sharing for this contract
+            // If fee sharing is already enabled, we should be able to overwrite
+            // the values currently set
+
+            // Ensure the fee share isn't 0 and doesn't exceed the maximum allowed value
+            if fee_share_bps == 0 || fee_share_bps > MAX_FEE_SHARE_BPS {
+                return Err(ContractError::FeeShareOutOfBounds {});
+            }
+
+            // Set sharing config
+            config.fee_share = Some(FeeShareConfig::single(
+                deps.api.addr_validate(&fee_share_address)?,
+                fee_share_bps,
+            ));
+
+            event = event
+                .add_attribute("action", "enable_fee_share")
+                .add_attribute("fee_share_bps", fee_share_bps.to_string())
+                .add_attribute("fee_share_address", fee_share_address);
+        }
+        StablePoolUpdateParams::DisableFeeShare => {
+            // Disable fee sharing for this contract by setting bps and
+            // address back to None
+            config.fee_share = None;
+
+            event = event.add_attribute("action", "disable_fee_share");
+        }
+        StablePoolUpdateParams::SetHooks { hooks } => {
+            config.hooks = check_hooks(deps.api, hooks)?;
+
+            event = event.add_attribute("action", "set_hooks").add_attribute(
+                "hooks",
+                config
+                    .hooks
+                    .iter()
+                    .map(|hook| hook.contract_addr.as_str())
+                    .join(", "),
+            );
+        }
+        StablePoolUpdateParams::EnablePriceGuard { max_deviation } => {
+            if !PRICE_DEVIATION_LIMITS.contains(&max_deviation) {
+                return Err(ContractError::InvalidPriceDeviation {});
+            }
+
+            config.max_price_deviation = Some(max_deviation);
+
+            event = event
+                .add_attribute("action", "enable_price_guard")
+                .add_attribute("max_deviation", max_deviation.to_string());
+        }
+        StablePoolUpdateParams::DisablePriceGuard => {
+            config.max_price_deviation = None;
+
+            event = event.add_attribute("action", "disable_price_guard");
+        }
+        StablePoolUpdateParams::SetTraderAllowlist(allowlist) => {
+            let allowlist = check_trader_allowlist(deps.api, allowlist)?;
+
+            event = event.add_attribute("action", "set_trader_allowlist");
+            event = match &allowlist {
+                TraderAllowlist::Addresses(addresses) => {
+                    event.add_attribute("addresses", addresses.iter().map(Addr::as_str).join(", "))
+                }
+                TraderAllowlist::Cw4Group(group_addr) => {
+                    event.add_attribute("cw4_group", group_addr)
+                }
+            };
+
+            config.trader_allowlist = Some(allowlist);
+        }
+        StablePoolUpdateParams::RemoveTraderAllowlist => {
+            config.trader_allowlist = None;
+
+            event = event.add_attribute("action", "remove_trader_allowlist");
+        }
+    }
+
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new().add_event(event))
+}
+
+/// Start changing the AMP value.
+///
+/// * **next_amp** new value for AMP.
+///
+/// * **next_amp_time** end time when the pool amplification will be equal to `next_amp`.
+fn start_changing_amp(
+    config: &mut Config,
+    env: &Env,
+    next_amp: u64,
+    next_amp_time: u64,
+) -> Result<(), ContractError> {
+    if next_amp == 0 || next_amp > MAX_AMP {
+        return Err(ContractError::IncorrectAmp {});
+    }
+
+    let current_amp = compute_current_amp(config, env)?.u64();
+    let next_amp_with_precision = next_amp * AMP_PRECISION;
+
+    if next_amp_with_precision * MAX_AMP_CHANGE < current_amp
+        || next_amp_with_precision > current_amp * MAX_AMP_CHANGE
+    {
+        return Err(ContractError::MaxAmpChangeAssertion {});
+    }
+
+    let block_time = env.block.time.seconds();
+
+    if block_time < config.init_amp_time + MIN_AMP_CHANGING_TIME
+        || next_amp_time < block_time + MIN_AMP_CHANGING_TIME
+    {
+        return Err(ContractError::MinAmpChangingTimeAssertion {});
+    }
+
+    config.init_amp = current_amp;
+    config.next_amp = next_amp_with_precision;
+    config.init_amp_time = block_time;
+    config.next_amp_time = next_amp_time;
+
+    Ok(())
+}
+
+/// Freezes the amplification at its current value.
+fn stop_changing_amp(config: &mut Config, env: &Env) -> StdResult<()> {
+    let current_amp = compute_current_amp(config, env)?;
+    let block_time = env.block.time.seconds();
+
+    config.init_amp = current_amp.u64();
+    config.next_amp = current_amp.u64();
+    config.init_amp_time = block_time;
+    config.next_amp_time = block_time;
+
+    Ok(())
+}
+
+/// Exposes all the queries available in the contract.
+///
+/// ## Queries
+/// * **QueryMsg::Pair {}** Returns information about the pair in an object of type [`PairInfo`].
+///
+/// * **QueryMsg::Pool {}** Returns information about the amount of assets in the pair contract as
+/// well as the amount of LP tokens issued using an object of type [`PoolResponse`].
+///
+/// * **QueryMsg::Config {}** Returns the configuration for the pair contract using a [`ConfigResponse`] object.
+///
+/// * **QueryMsg::Share { amount }** Returns the amount of assets that could be withdrawn from the pool
+/// using a specific amount of LP tokens. The result is returned in a vector that contains objects of type [`Asset`].
+///
+/// * **QueryMsg::Simulation { offer_asset, ask_asset_info }** Returns the result of a swap simulation using a [`SimulationResponse`] object.
+///
+/// * **QueryMsg::ReverseSimulation { offer_asset_info, ask_asset }** Returns the result of a reverse swap simulation using
+/// a [`ReverseSimulationResponse`] object.
+///
+/// * **QueryMsg::CumulativePrices {}** Returns information about cumulative prices for the assets in the
+/// pool using a [`CumulativePricesResponse`] object.
+///
+/// * **QueryMsg::QueryComputeD {}** Returns the current D invariant scaled to the LP token precision.
+///
+/// * **QueryMsg::SimulateWithdraw { lp_amount, .. }** Returns the amount of assets that could be withdrawn from the pool
+/// using a specific amount of LP tokens. The result is returned in a vector that contains objects of type [`Asset`].
+/// Single asset withdrawals are not supported.
+///
+/// * **QueryMsg::SimulateProvide { assets, .. }** Simulates the liquidity provision in the pair contract.
+///
+/// * **QueryMsg::AssetBalanceAt { asset_info, block_height }** Returns the balance of the specified
+/// asset that was in the pool just preceding the moment of the specified block height creation.
+///
+/// * **QueryMsg::Observe { seconds_ago }** Returns cumulative prices at each moment `seconds_ago`
+/// from the current block time using a vector of [`Observation`] objects.
+///
+/// * **QueryMsg::Stats { epoch }** Returns swap volumes and fees accumulated during the epoch
+/// and over the pair lifetime in a [`StatsResponse`] object.
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::Pair {} => to_json_binary(&CONFIG.load(deps.storage)?.pair_info),
+        QueryMsg::Pool {} => to_json_binary(&query_pool(deps)?),
+        QueryMsg::Config {} => to_json_binary(&query_config(deps, env)?),
+        QueryMsg::Share { amount } => to_json_binary(&query_share(deps, amount)?),
+        QueryMsg::Simulation {
+            offer_asset,
+            ask_asset_info,
+        } => to_json_binary(
+            &query_simulation(deps, env, offer_asset, ask_asset_info)
+                .map_err(|err| StdError::generic_err(err.to_string()))?,
+        ),
+        QueryMsg::ReverseSimulation {
+            offer_asset_info,
+            ask_asset,
+        } => to_json_binary(
+            &query_reverse_simulation(deps, env, ask_asset, offer_asset_info)
+                .map_err(|err| StdError::generic_err(err.to_string()))?,
+        ),
+        QueryMsg::CumulativePrices {} => to_json_binary(&query_cumulative_prices(deps, env)?),
+        QueryMsg::QueryComputeD {} => to_json_binary(&query_compute_d(deps, env)?),
+        QueryMsg::SimulateWithdraw { lp_amount, asset } => {
+            if asset.is_some() {
+                return Err(StdError::generic_err(
+                    ContractError::NonSupported {}.to_string(),
+                ));
+            }
+
+            to_json_binary(&query_share(deps, lp_amount)?)
+        }
+        QueryMsg::SimulateProvide { assets, .. } => to_json_binary(
+            &query_simulate_provide(deps, env, assets)
+                .map_err(|err| StdError::generic_err(err.to_string()))?,
+        ),
+        QueryMsg::AssetBalanceAt {
+            asset_info,
+            block_height,
+        } => to_json_binary(&query_asset_balance_at(deps, asset_info, block_height)?),
+        QueryMsg::Observe { seconds_ago } => {
+            to_json_binary(&query_observe(deps, env, seconds_ago)?)
+        }
+        QueryMsg::Stats { epoch } => to_json_binary(&query_stats(deps, env, epoch)?),
+        QueryMsg::BatchOrder { .. } | QueryMsg::BatchOrders { .. } => Err(StdError::generic_err(
+            ContractError::NonSupported {}.to_string(),
+        )),
+    }
+}
+
+/// Returns the amounts of assets in the pair contract as well as the amount of LP
+/// tokens currently minted in an object of type [`PoolResponse`].
+pub fn query_pool(deps: Deps) -> StdResult<PoolResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let (assets, total_share) = pool_info(deps, &config)?;
+
+    Ok(PoolResponse {
+        assets,
+        total_share,
+    })
+}
+
+/// Returns the amount of assets that could be withdrawn from the pool using a specific amount of LP tokens.
+/// The result is returned in a vector that contains objects of type [`Asset`].
+///
+/// * **amount** is the amount of LP tokens for which we calculate associated amounts of assets.
+pub fn query_share(deps: Deps, amount: Uint128) -> StdResult<Vec<Asset>> {
+    let config = CONFIG.load(deps.storage)?;
+    let (pools, total_share) = pool_info(deps, &config)?;
+
+    Ok(get_share_in_assets(&pools, amount, total_share))
+}
+
+/// Returns information about a swap simulation in a [`SimulationResponse`] object.
+///
+/// * **offer_asset** is the asset to swap as well as an amount of the said asset.
+///
+/// * **ask_asset_info** is the asset to swap to.
+pub fn query_simulation(
+    deps: Deps,
+    env: Env,
+    offer_asset: Asset,
+    ask_asset_info: Option<AssetInfo>,
+) -> Result<SimulationResponse, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let (offer_ind, ask_ind) =
+        select_pools(&config, Some(&offer_asset.info), ask_asset_info.as_ref())?;
+
+    let pools = query_reserves(deps.storage, &config)?;
+    check_swap_parameters(
+        pools.iter().map(|pool| pool.amount).collect(),
+        offer_asset.amount,
+    )?;
+
+    let xp = to_decimal_pools(deps.storage, &pools)?;
+    let rates = query_rates(deps, &config)?;
+    let offer_precision = get_precision(deps.storage, &offer_asset.info)?;
+    let ask_precision = get_precision(deps.storage, &pools[ask_ind].info)?;
+
+    // Get fee info from the factory contract
+    let fee_info = query_fee_info(
+        &deps.querier,
+        &config.factory_addr,
+        config.pair_info.pair_type.clone(),
+        &config.pair_info.contract_addr,
+    )?;
+
+    let (return_amount, spread_amount, commission_amount) = compute_swap(
+        compute_current_amp(&config, &env)?,
+        &xp,
+        &rates,
+        offer_ind,
+        ask_ind,
+        offer_asset.amount.to_decimal256(offer_precision),
+        fee_info.total_fee_rate.into(),
+    )?;
+
+    Ok(SimulationResponse {
+        return_amount: return_amount.to_uint(ask_precision)?,
+        spread_amount: spread_amount.to_uint(ask_precision)?,
+        commission_amount: commission_amount.to_uint(ask_precision)?,
+    })
+}
+
+/// Returns information about a reverse swap simulation in a [`ReverseSimulationResponse`] object.
+///
+/// * **ask_asset** is the asset to swap to as well as the desired amount of ask
+///   assets to receive from the swap.
+///
+/// * **offer_asset_info** is the asset to swap from.
+pub fn query_reverse_simulation(
+    deps: Deps,
+    env: Env,
+    ask_asset: Asset,
+    offer_asset_info: Option<AssetInfo>,
+) -> Result<ReverseSimulationResponse, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let (offer_ind, ask_ind) =
+        select_pools(&config, offer_asset_info.as_ref(), Some(&ask_asset.info))?;
+
+    let pools = query_reserves(deps.storage, &config)?;
+    check_swap_parameters(
+        pools.iter().map(|pool| pool.amount).collect(),
+        ask_asset.amount,
+    )?;
+
+    let xp = to_decimal_pools(deps.storage, &pools)?;
+    let rates = query_rates(deps, &config)?;
+    let offer_precision = get_precision(deps.storage, &pools[offer_ind].info)?;
+    let ask_precision = get_precision(deps.storage, &ask_asset.info)?;
+
+    // Get fee info from the factory contract
+    let fee_info = query_fee_info(
+        &deps.querier,
+        &config.factory_addr,
+        config.pair_info.pair_type.clone(),
+        &config.pair_info.contract_addr,
+    )?;
+
+    let (offer_amount, spread_amount, commission_amount) = compute_offer_amount(
+        compute_current_amp(&config, &env)?,
+        &xp,
+        &rates,
+        offer_ind,
+        ask_ind,
+        ask_asset.amount.to_decimal256(ask_precision),
+        fee_info.total_fee_rate.into(),
+    )?;
+
+    Ok(ReverseSimulationResponse {
+        offer_amount: offer_amount.to_uint(offer_precision)?,
+        spread_amount: spread_amount.to_uint(offer_precision)?,
+        commission_amount: commission_amount.to_uint(ask_precision)?,
+    })
+}
+
+/// Returns the balance of the specified asset that was in the pool just preceding the moment
+/// of the specified block height creation. Returns `None` if the pool balance had never been
+/// recorded before the given block height.
+pub fn query_asset_balance_at(
+    deps: Deps,
+    asset_info: AssetInfo,
+    block_height: Uint64,
+) -> StdResult<Option<Uint128>> {
+    BALANCES.may_load_at_height(deps.storage, &asset_info, block_height.u64())
+}
+
+/// Returns information about cumulative prices for the assets in the pool using a [`CumulativePricesResponse`] object.
+pub fn query_cumulative_prices(deps: Deps, env: Env) -> StdResult<CumulativePricesResponse> {
+    let mut config = CONFIG.load(deps.storage)?;
+    let (assets, total_share) = pool_info(deps, &config)?;
+
+    let xp = to_decimal_pools(deps.storage, &assets)?;
+    let rates = query_rates(deps, &config)?;
+    accumulate_prices(&env, &mut config, &xp, &rates)?;
+
+    Ok(CumulativePricesResponse {
+        assets,
+        total_share,
+        cumulative_prices: config.cumulative_prices,
+    })
+}
+
+/// Returns cumulative prices interpolated from the recorded observations at each moment
+/// `seconds_ago` from the current block time.
+pub fn query_observe(deps: Deps, env: Env, seconds_ago: Vec<u64>) -> StdResult<Vec<Observation>> {
+    let current = Observation {
+        timestamp: env.block.time.seconds(),
+        cumulative_prices: query_cumulative_prices(deps, env)?.cumulative_prices,
+    };
+
+    query_observations(deps.storage, &OBSERVATIONS, &current, seconds_ago)
+}
+
+/// Returns swap volumes and fees accumulated during the given **epoch** (the current one
+/// by default) and over the pair lifetime in a [`StatsResponse`] object.
+pub fn query_stats(deps: Deps, env: Env, epoch: Option<u64>) -> StdResult<StatsResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let epoch = epoch.unwrap_or_else(|| epoch_at(env.block.time.seconds()));
+
+    STATS.query(deps.storage, &config.pair_info.asset_infos, epoch)
+}
+
+/// Returns the pair contract configuration in a [`ConfigResponse`] object.
+pub fn query_config(deps: Deps, env: Env) -> StdResult<ConfigResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let factory_config = query_factory_config(&deps.querier, &config.factory_addr)?;
+    let rates = query_rates(deps, &config)?;
+
+    Ok(ConfigResponse {
+        block_time_last: config.block_time_last,
+        params: Some(to_json_binary(&StablePoolConfig {
+            amp: Decimal::from_ratio(compute_current_amp(&config, &env)?, AMP_PRECISION),
+            fee_share: config.fee_share,
+            rates,
+            rate_sources: config.rate_sources,
+            hooks: config.hooks,
+            max_price_deviation: config.max_price_deviation,
+            trader_allowlist: config.trader_allowlist,
+        })?),
+        owner: config.owner.unwrap_or(factory_config.owner),
+        factory_addr: config.factory_addr,
+    })
+}
+
+/// Returns the current D invariant scaled to the LP token precision.
+pub fn query_compute_d(deps: Deps, env: Env) -> StdResult<Uint128> {
+    let config = CONFIG.load(deps.storage)?;
+    let pools = query_reserves(deps.storage, &config)?;
+    let xp = apply_rates(
+        &to_decimal_pools(deps.storage, &pools)?,
+        &query_rates(deps, &config)?,
+    )?;
+
+    compute_d(compute_current_amp(&config, &env)?, &xp)?
+        .to_uint(LP_TOKEN_PRECISION)
+        .map_err(Into::into)
+}
+
+/// Returns the amount of LP tokens that will be minted for the given assets.
+///
+/// * **assets** is an array with assets to provide.
+fn query_simulate_provide(
+    deps: Deps,
+    env: Env,
+    assets: Vec<Asset>,
+) -> Result<Uint128, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let deposits = get_deposits_from_assets(&config, &assets)?;
+    let (pools, total_share) = pool_info(deps, &config)?;
+
+    calculate_shares(deps, &env, &config, &pools, &deposits, total_share)
+}
+
+/// Verifies provided assets and returns deposit amounts ordered the same way as the pool assets.
+///
+/// * **assets** is an array with assets to provide.
+fn get_deposits_from_assets(
+    config: &Config,
+    assets: &[Asset],
+) -> Result<Vec<Uint128>, ContractError> {
+    let n_assets = config.pair_info.asset_infos.len();
+    if assets.is_empty() || assets.len() > n_assets {
+        return Err(ContractError::WrongAssetLength {
+            expected: n_assets,
+            actual: assets.len(),
+        });
+    }
+
+    if !assets.iter().map(|asset| &asset.info).all_unique() {
+        return Err(ContractError::DoublingAssets {});
+    }
+
+    let mut deposits = vec![Uint128::zero(); n_assets];
+    for asset in assets {
+        deposits[asset_position(config, &asset.info)?] = asset.amount;
+    }
+
+    if deposits.iter().all(Uint128::is_zero) {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+
+    Ok(deposits)
+}
+
+/// Returns shares for the provided deposits. The initial provide must contain all pool assets
+/// and mints LP tokens equal to the invariant D. Subsequent provides mint LP tokens proportionally
+/// to the invariant growth after charging an imbalance fee.
+///
+/// * **pools** is an array with total amount of assets in the pool (without the deposits).
+///
+/// * **deposits** is an array with deposit amounts ordered the same way as the pool assets.
+///
+/// * **total_share** is the total amount of LP tokens currently minted.
+fn calculate_shares(
+    deps: Deps,
+    env: &Env,
+    config: &Config,
+    pools: &[Asset],
+    deposits: &[Uint128],
+    total_share: Uint128,
+) -> Result<Uint128, ContractError> {
+    let amp = compute_current_amp(config, env)?;
+    let rates = query_rates(deps, config)?;
+    let old_balances = apply_rates(&to_decimal_pools(deps.storage, pools)?, &rates)?;
+    let mut new_balances = pools
+        .iter()
+        .zip(deposits)
+        .zip(&old_balances)
+        .zip(&rates)
+        .map(|(((pool, deposit), balance), rate)| {
+            let precision = get_precision(deps.storage, &pool.info)?;
+            Ok(balance.checked_add(deposit.to_decimal256(precision).checked_mul(*rate)?)?)
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let deposit_d = compute_d(amp, &new_balances)?;
+
+    if total_share.is_zero() {
+        if deposits.iter().any(Uint128::is_zero) {
+            return Err(ContractError::InitialProvideAllAssets {});
+        }
+
+        // Initial share = invariant D
+        let share = deposit_d
+            .to_uint(LP_TOKEN_PRECISION)?
+            .checked_sub(MINIMUM_LIQUIDITY_AMOUNT)
+            .map_err(|_| ContractError::MinimumLiquidityAmountError {})?;
+
+        // share cannot become zero after minimum liquidity subtraction
+        if share.is_zero() {
+            return Err(ContractError::MinimumLiquidityAmountError {});
+        }
+
+        return Ok(share);
+    }
+
+    let init_d = compute_d(amp, &old_balances)?;
+    if init_d.is_zero() {
+        return Err(StdError::generic_err("One of the pools is empty").into());
+    }
+
+    // Get fee info from the factory
+    let fee_info = query_fee_info(
+        &deps.querier,
+        &config.factory_addr,
+        config.pair_info.pair_type.clone(),
+        &config.pair_info.contract_addr,
+    )?;
+
+    // The imbalance fee is the same as in Curve: fee * n / (4 * (n - 1))
+    let n_coins = pools.len() as u128;
+    let imbalance_fee = Decimal256::from(fee_info.total_fee_rate)
+        * Decimal256::from_ratio(n_coins, 4 * (n_coins - 1));
+
+    for (new_balance, old_balance) in new_balances.iter_mut().zip(&old_balances) {
+        let ideal_balance = Decimal256::new(
+            old_balance
+                .atomics()
+                .multiply_ratio(deposit_d.atomics(), init_d.atomics()),
+        );
+        let difference = ideal_balance.abs_diff(*new_balance);
+        *new_balance = new_balance.checked_sub(difference * imbalance_fee)?;
+    }
+
+    let after_fee_d = compute_d(amp, &new_balances)?;
+
+    let share = Uint256::from(total_share)
+        .multiply_ratio(
+            after_fee_d.saturating_sub(init_d).atomics(),
+            init_d.atomics(),
+        )
+        .try_into()?;
+
+    Ok(share)
+}
+
+/// Manages the contract migration.
+#[cfg(not(tarpaulin_include))]
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, env: Env, _msg: Empty) -> Result<Response, ContractError> {
+    let version = get_contract_version(deps.storage)?;
+
+    ensure_eq!(
+        version.contract,
+        CONTRACT_NAME,
+        ContractError::MigrationError {}
+    );
+
+    // Start tracking reserves from the current pair balances
+    if !RESERVES.exists(deps.storage) {
+        let config = CONFIG.load(deps.storage)?;
+        let balances = config
+            .pair_info
+            .query_pools(&deps.querier, &env.contract.address)?;
+        save_reserves(deps.storage, &balances, env.block.height)?;
+    }
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Response::new()
+        .add_attribute("previous_contract_name", &version.contract)
+        .add_attribute("previous_contract_version", &version.version)
+        .add_attribute("new_contract_name", CONTRACT_NAME)
+        .add_attribute("new_contract_version", CONTRACT_VERSION))
+}
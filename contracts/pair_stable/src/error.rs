@@ -0,0 +1,158 @@
+use cosmwasm_std::{
+    Addr, CheckedMultiplyRatioError, ConversionOverflowError, Decimal, Decimal256, OverflowError,
+    StdError, Uint128,
+};
+use cw_utils::{ParseReplyError, PaymentError};
+use thiserror::Error;
+
+use astroport::asset::MINIMUM_LIQUIDITY_AMOUNT;
+use astroport::factory::PauseScope;
+use astroport::pair::{
+    HOOK_GAS_LIMIT, MAX_ALLOWLIST_ADDRESSES, MAX_FEE_SHARE_BPS, MAX_HOOKS, PRICE_DEVIATION_LIMITS,
+};
+
+use crate::math::{MAX_AMP, MAX_AMP_CHANGE, MIN_AMP_CHANGING_TIME};
+
+/// This enum describes stableswap pair contract errors
+#[derive(Error, Debug, PartialEq)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("{0}")]
+    ConversionOverflowError(#[from] ConversionOverflowError),
+
+    #[error("{0}")]
+    CheckedMultiplyRatioError(#[from] CheckedMultiplyRatioError),
+
+    #[error("{0}")]
+    ParseReplyError(#[from] ParseReplyError),
+
+    #[error("{0}")]
+    OverflowError(#[from] OverflowError),
+
+    #[error("{0}")]
+    PaymentError(#[from] PaymentError),
+
+    #[error("Unauthorized")]
+    Unauthorized {},
+
+    #[error("{0:?} is paused")]
+    Paused(PauseScope),
+
+    #[error("Transaction deadline exceeded")]
+    DeadlineExceeded {},
+
+    #[error("CW20 tokens can be swapped via Cw20::Send message only")]
+    Cw20DirectSwap {},
+
+    #[error("Operation non supported")]
+    NonSupported {},
+
+    #[error("You need to provide init params")]
+    InitParamsNotFound {},
+
+    #[error("Invalid number of assets. This pair supports from {min} to {max} assets")]
+    InvalidNumberOfAssets { min: usize, max: usize },
+
+    #[error("Doubling assets in asset infos")]
+    DoublingAssets {},
+
+    #[error("The asset {0} does not belong to the pair")]
+    InvalidAsset(String),
+
+    #[error("Ask or offer asset is missed")]
+    VariableAssetMissed {},
+
+    #[error("Source and target assets are the same")]
+    SameAssets {},
+
+    #[error("Event of zero transfer")]
+    InvalidZeroAmount {},
+
+    #[error("Initial provide must contain all pool assets")]
+    InitialProvideAllAssets {},
+
+    #[error("Initial liquidity must be more than {}", MINIMUM_LIQUIDITY_AMOUNT)]
+    MinimumLiquidityAmountError {},
+
+    #[error(
+        "Amp coefficient must be greater than 0 and less than or equal to {}",
+        MAX_AMP
+    )]
+    IncorrectAmp {},
+
+    #[error(
+        "The difference between the old and new amp value must not exceed {} times",
+        MAX_AMP_CHANGE
+    )]
+    MaxAmpChangeAssertion {},
+
+    #[error(
+        "Amp coefficient cannot be changed more often than once per {} seconds",
+        MIN_AMP_CHANGING_TIME
+    )]
+    MinAmpChangingTimeAssertion {},
+
+    #[error("Operation exceeds max spread limit")]
+    MaxSpreadAssertion {},
+
+    #[error("Provided spread amount exceeds allowed limit")]
+    AllowedSpreadAssertion {},
+
+    #[error("Slippage is more than expected: received {0}, expected {1} LP tokens")]
+    ProvideSlippageViolation(Uint128, Uint128),
+
+    #[error("Received {received} {asset_name} but expected {expected}")]
+    WithdrawSlippageViolation {
+        asset_name: String,
+        received: Uint128,
+        expected: Uint128,
+    },
+
+    #[error("Wrong asset length: expected {expected}, actual {actual}")]
+    WrongAssetLength { expected: usize, actual: usize },
+
+    #[error("Asset mismatch between the requested and the stored asset in contract")]
+    AssetMismatch {},
+
+    #[error("Failed to migrate the contract")]
+    MigrationError {},
+
+    #[error("Failed to parse or process reply message")]
+    FailedToParseReply {},
+
+    #[error("Incentives address is not set in factory. Cannot auto-stake")]
+    AutoStakeError {},
+
+    #[error(
+        "Fee share is 0 or exceeds maximum allowed value of {} bps",
+        MAX_FEE_SHARE_BPS
+    )]
+    FeeShareOutOfBounds {},
+
+    #[error(
+        "Hooks must be unique, there can be at most {} hooks and their gas limits must be within {:?}",
+        MAX_HOOKS,
+        HOOK_GAS_LIMIT
+    )]
+    InvalidHooks {},
+
+    #[error("Max price deviation must be within {:?}", PRICE_DEVIATION_LIMITS)]
+    InvalidPriceDeviation {},
+
+    #[error("Swap moves the price by {deviation} from the block open price, max allowed {max_deviation}")]
+    PriceDeviationExceeded {
+        deviation: Decimal256,
+        max_deviation: Decimal,
+    },
+
+    #[error(
+        "Trader allowlist must contain from 1 to {} unique addresses",
+        MAX_ALLOWLIST_ADDRESSES
+    )]
+    InvalidTraderAllowlist {},
+
+    #[error("{addr} is not allowed to trade in this pair")]
+    TraderNotAllowed { addr: Addr },
+}
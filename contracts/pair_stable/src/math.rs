@@ -0,0 +1,220 @@
+use cosmwasm_std::{Decimal256, Env, StdError, StdResult, Uint256, Uint512, Uint64};
+
+use crate::state::Config;
+
+/// The maximum amount of assets a stableswap pool can hold
+pub const MAX_ASSETS: usize = 5;
+/// The minimum amount of assets a stableswap pool can hold
+pub const MIN_ASSETS: usize = 2;
+
+/// The maximum allowed amplification
+pub const MAX_AMP: u64 = 1_000_000;
+/// The maximum allowed amplification change ratio
+pub const MAX_AMP_CHANGE: u64 = 10;
+/// The minimum time it takes to ramp the amplification
+pub const MIN_AMP_CHANGING_TIME: u64 = 86400;
+/// Amplification is stored multiplied by this value to allow smooth ramping
+pub const AMP_PRECISION: u64 = 100;
+
+/// The maximum number of calculation steps for Newton's method.
+const ITERATIONS: u8 = 64;
+
+/// Returns the current amplification (multiplied by [`AMP_PRECISION`]), linearly interpolating
+/// between the initial and the target values while a ramp is in progress.
+pub fn compute_current_amp(config: &Config, env: &Env) -> StdResult<Uint64> {
+    let block_time = env.block.time.seconds();
+    if block_time < config.next_amp_time {
+        let elapsed_time = Uint64::from(block_time).checked_sub(config.init_amp_time.into())?;
+        let time_range =
+            Uint64::from(config.next_amp_time).checked_sub(config.init_amp_time.into())?;
+        let init_amp = Uint64::from(config.init_amp);
+        let next_amp = Uint64::from(config.next_amp);
+
+        if next_amp > init_amp {
+            let amp_range = next_amp - init_amp;
+            let res = init_amp + (amp_range * elapsed_time).checked_div(time_range)?;
+            Ok(res)
+        } else {
+            let amp_range = init_amp - next_amp;
+            let res = init_amp - (amp_range * elapsed_time).checked_div(time_range)?;
+            Ok(res)
+        }
+    } else {
+        Ok(Uint64::from(config.next_amp))
+    }
+}
+
+/// Computes the stableswap invariant (D).
+///
+/// * **Equation**
+///
+/// A * sum(x_i) * n**n + D = A * D * n**n + D**(n+1) / (n**n * prod(x_i))
+///
+/// * **amp** is the current amplification multiplied by [`AMP_PRECISION`].
+///
+/// * **pools** are the pool balances normalized to [`Decimal256`] precision.
+pub fn compute_d(amp: Uint64, pools: &[Decimal256]) -> StdResult<Decimal256> {
+    if pools.iter().any(|pool| pool.is_zero()) {
+        return Ok(Decimal256::zero());
+    }
+
+    let xp: Vec<Uint256> = pools.iter().map(|pool| pool.atomics()).collect();
+    let sum = xp
+        .iter()
+        .try_fold(Uint256::zero(), |acc, x| acc.checked_add(*x))?;
+
+    let n_coins = Uint256::from(xp.len() as u128);
+    let amp_precision = Uint256::from(AMP_PRECISION);
+    let ann = Uint256::from(amp.u64()).checked_mul(n_coins)?;
+    let ann_sum = ann.checked_mul(sum)? / amp_precision;
+
+    let mut d = sum;
+    for _ in 0..ITERATIONS {
+        // d_p = d**(n+1) / (n**n * prod(x_i))
+        let d_p = xp.iter().try_fold(d, |acc, x| {
+            acc.checked_multiply_ratio(d, x.checked_mul(n_coins)?)
+                .map_err(|err| StdError::generic_err(err.to_string()))
+        })?;
+        let d_prev = d;
+
+        let numerator = ann_sum.checked_add(d_p.checked_mul(n_coins)?)?;
+        let denominator = (ann - amp_precision).checked_mul(d)? / amp_precision
+            + (n_coins + Uint256::one()).checked_mul(d_p)?;
+        d = numerator
+            .checked_multiply_ratio(d, denominator)
+            .map_err(|err| StdError::generic_err(err.to_string()))?;
+
+        if d.abs_diff(d_prev) <= Uint256::one() {
+            return Ok(Decimal256::new(d));
+        }
+    }
+
+    Err(StdError::generic_err(
+        "Newton method for D failed to converge",
+    ))
+}
+
+/// Computes the new balance of the asset at position **target** which keeps the invariant **d**
+/// given all other pool balances.
+///
+/// * **amp** is the current amplification multiplied by [`AMP_PRECISION`].
+///
+/// * **d** is the invariant the new balances must satisfy.
+///
+/// * **pools** are the pool balances normalized to [`Decimal256`] precision. The value at
+///   position **target** is ignored.
+pub fn calc_y(
+    amp: Uint64,
+    d: Decimal256,
+    pools: &[Decimal256],
+    target: usize,
+) -> StdResult<Decimal256> {
+    let d = d.atomics();
+    let n_coins = Uint256::from(pools.len() as u128);
+    let amp_precision = Uint256::from(AMP_PRECISION);
+    let ann = Uint256::from(amp.u64()).checked_mul(n_coins)?;
+
+    let mut c = d;
+    let mut sum = Uint256::zero();
+    for (ind, pool) in pools.iter().enumerate() {
+        if ind == target {
+            continue;
+        }
+
+        let x = pool.atomics();
+        sum = sum.checked_add(x)?;
+        c = c
+            .checked_multiply_ratio(d, x.checked_mul(n_coins)?)
+            .map_err(|err| StdError::generic_err(err.to_string()))?;
+    }
+    let c = Uint512::from(c)
+        .checked_mul(Uint512::from(d.checked_mul(amp_precision)?))?
+        .checked_div(Uint512::from(ann.checked_mul(n_coins)?))?;
+    let b = sum.checked_add(d.checked_mul(amp_precision)? / ann)?;
+
+    let mut y = d;
+    for _ in 0..ITERATIONS {
+        let y_prev = y;
+        // y = (y**2 + c) / (2 * y + b - d)
+        let numerator = Uint512::from(y)
+            .checked_mul(Uint512::from(y))?
+            .checked_add(c)?;
+        let denominator = y.checked_add(y)?.checked_add(b)?.checked_sub(d)?;
+        y = numerator
+            .checked_div(Uint512::from(denominator))?
+            .try_into()
+            .map_err(|err: cosmwasm_std::ConversionOverflowError| {
+                StdError::generic_err(err.to_string())
+            })?;
+
+        if y.abs_diff(y_prev) <= Uint256::one() {
+            return Ok(Decimal256::new(y));
+        }
+    }
+
+    Err(StdError::generic_err(
+        "Newton method for y failed to converge",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dec(val: u128) -> Decimal256 {
+        Decimal256::from_ratio(val, 1u8)
+    }
+
+    #[test]
+    fn balanced_pool_invariant_equals_sum() {
+        let amp = Uint64::from(100 * AMP_PRECISION);
+        for n in MIN_ASSETS..=MAX_ASSETS {
+            let pools = vec![dec(1_000_000); n];
+            let d = compute_d(amp, &pools).unwrap();
+            assert_eq!(d, dec(1_000_000 * n as u128));
+        }
+    }
+
+    #[test]
+    fn empty_pool_invariant_is_zero() {
+        let amp = Uint64::from(100 * AMP_PRECISION);
+        let d = compute_d(amp, &[dec(1_000_000), Decimal256::zero()]).unwrap();
+        assert_eq!(d, Decimal256::zero());
+    }
+
+    #[test]
+    fn calc_y_keeps_invariant() {
+        let amp = Uint64::from(85 * AMP_PRECISION);
+        let pools = vec![dec(2_000_000), dec(1_500_000), dec(1_000_000)];
+        let d = compute_d(amp, &pools).unwrap();
+
+        let mut new_pools = pools.clone();
+        new_pools[0] += dec(100_000);
+        let y = calc_y(amp, d, &new_pools, 2).unwrap();
+        new_pools[2] = y;
+
+        // Swap output is close to 1:1 with a high amplification
+        let dy = pools[2] - y;
+        assert!(dy > dec(98_000) && dy < dec(100_000), "{dy}");
+
+        let new_d = compute_d(amp, &new_pools).unwrap();
+        let diff = new_d.abs_diff(d);
+        assert!(diff < Decimal256::raw(1000), "{diff}");
+    }
+
+    #[test]
+    fn lower_amp_gives_bigger_slippage() {
+        let pools = vec![dec(1_000_000), dec(1_000_000)];
+        let outputs: Vec<_> = [1u64, 10, 100, 1000]
+            .into_iter()
+            .map(|amp| {
+                let amp = Uint64::from(amp * AMP_PRECISION);
+                let d = compute_d(amp, &pools).unwrap();
+                let y = calc_y(amp, d, &[dec(1_500_000), pools[1]], 1).unwrap();
+                pools[1] - y
+            })
+            .collect();
+
+        assert!(outputs.windows(2).all(|w| w[0] < w[1]), "{outputs:?}");
+    }
+}
@@ -0,0 +1,98 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, Decimal, Decimal256, DepsMut, StdResult, Storage, Uint128};
+use cw_storage_plus::{Deque, Item, Map, SnapshotMap, Strategy};
+
+use astroport::asset::{AssetInfo, PairInfo};
+use astroport::common::OwnershipProposal;
+use astroport::observation::Observation;
+use astroport::pair::{AssetRateSource, FeeShareConfig, PairHook, TraderAllowlist};
+use astroport::stats::PairStats;
+
+/// This structure stores the main stableswap pair parameters.
+#[cw_serde]
+pub struct Config {
+    /// The contract owner. If not set, the factory owner is used
+    pub owner: Option<Addr>,
+    /// The pair information stored in a [`PairInfo`] struct
+    pub pair_info: PairInfo,
+    /// The factory contract address
+    pub factory_addr: Addr,
+    /// The last timestamp when the pair contract update the asset cumulative prices
+    pub block_time_last: u64,
+    /// This is the current amplification used in the pool (multiplied by AMP_PRECISION)
+    pub init_amp: u64,
+    /// This is the start time when amplification starts to scale up or down
+    pub init_amp_time: u64,
+    /// This is the target amplification to reach at `next_amp_time` (multiplied by AMP_PRECISION)
+    pub next_amp: u64,
+    /// This is the timestamp when the current pool amplification should be `next_amp`
+    pub next_amp_time: u64,
+    /// The vector contains cumulative prices for each pair of assets in the pool
+    pub cumulative_prices: Vec<(AssetInfo, AssetInfo, Uint128)>,
+    /// The config for swap fee sharing
+    pub fee_share: Option<FeeShareConfig>,
+    /// Whether LP shares are issued as a token factory denom instead of a CW20 token
+    #[serde(default)]
+    pub native_lp_token: bool,
+    /// Redemption rate sources of the pool assets
+    #[serde(default)]
+    pub rate_sources: Vec<AssetRateSource>,
+    /// Contracts notified after swaps and liquidity events
+    #[serde(default)]
+    pub hooks: Vec<PairHook>,
+    /// The price of one unit of the first asset in the second asset for each pair of assets
+    /// at the start of the block at `block_time_last`
+    #[serde(default)]
+    pub block_open_prices: Vec<(AssetInfo, AssetInfo, Decimal256)>,
+    /// The maximum relative deviation of the pool price from the block open price allowed after a swap
+    #[serde(default)]
+    pub max_price_deviation: Option<Decimal>,
+    /// The addresses allowed to swap and provide liquidity. Everyone is allowed if not set
+    #[serde(default)]
+    pub trader_allowlist: Option<TraderAllowlist>,
+}
+
+/// Stores the config struct at the given key
+pub const CONFIG: Item<Config> = Item::new("config");
+
+/// Stores the pool reserves in the same order as [`PairInfo::asset_infos`]
+pub const RESERVES: Item<Vec<Uint128>> = Item::new("reserves");
+
+/// Stores a bounded ring buffer of cumulative price observations
+pub const OBSERVATIONS: Deque<Observation> = Deque::new("observations");
+
+/// Stores the pool balance of each asset at every block height it changed
+pub const BALANCES: SnapshotMap<&AssetInfo, Uint128> = SnapshotMap::new(
+    "balances",
+    "balances_check",
+    "balances_change",
+    Strategy::EveryBlock,
+);
+
+/// Stores the lifetime and per-epoch swap volumes and fees
+pub const STATS: PairStats = PairStats::new("stats", "epoch_stats");
+
+/// Stores the latest contract ownership transfer proposal
+pub const OWNERSHIP_PROPOSAL: Item<OwnershipProposal> = Item::new("ownership_proposal");
+
+/// Stores asset precisions keyed by the asset denom or contract address
+const PRECISIONS: Map<String, u8> = Map::new("precisions");
+
+/// Queries and stores the precision of every pool asset.
+pub fn store_precisions(
+    deps: DepsMut,
+    asset_infos: &[AssetInfo],
+    factory_addr: &Addr,
+) -> StdResult<()> {
+    for asset_info in asset_infos {
+        let precision = asset_info.decimals(&deps.querier, factory_addr)?;
+        PRECISIONS.save(deps.storage, asset_info.to_string(), &precision)?;
+    }
+
+    Ok(())
+}
+
+/// Loads the precision of the given pool asset.
+pub fn get_precision(storage: &dyn Storage, asset_info: &AssetInfo) -> StdResult<u8> {
+    PRECISIONS.load(storage, asset_info.to_string())
+}
@@ -0,0 +1,725 @@
+use std::collections::HashSet;
+use std::str::FromStr;
+
+use cosmwasm_std::{
+    coin, to_json_binary, wasm_execute, Addr, Api, CosmosMsg, CustomMsg, CustomQuery, Decimal,
+    Decimal256, Deps, Env, Fraction, QuerierWrapper, StdError, StdResult, Storage, Uint128,
+    Uint256, Uint64,
+};
+use cw20::Cw20ExecuteMsg;
+use itertools::Itertools;
+
+use astroport::asset::{Asset, AssetInfo, AssetInfoExt};
+use astroport::cosmwasm_ext::{DecMul, DecimalToInteger, IntegerToDecimal};
+use astroport::observation::{store_observation, Observation};
+use astroport::pair::{
+    price_deviation, AssetRateSource, PairHook, RateSource, TraderAllowlist, DEFAULT_SLIPPAGE,
+    HOOK_GAS_LIMIT, MAX_ALLOWED_SLIPPAGE, MAX_ALLOWLIST_ADDRESSES, MAX_HOOKS, TWAP_PRECISION,
+};
+use astroport::querier::{query_factory_config, query_lp_supply};
+use astroport::rate_provider::ExchangeRateResponse;
+use astroport::token_factory::tf_mint_msg;
+use astroport::{incentives, oracle, rate_provider};
+
+use crate::error::ContractError;
+use crate::math::{calc_y, compute_current_amp, compute_d, MAX_ASSETS, MIN_ASSETS};
+use crate::state::{get_precision, Config, BALANCES, OBSERVATIONS, RESERVES};
+
+/// Validates pool assets: their amount must be within [`MIN_ASSETS`]..=[`MAX_ASSETS`],
+/// each asset must be valid and there must be no duplicates.
+pub fn check_asset_infos(api: &dyn Api, asset_infos: &[AssetInfo]) -> Result<(), ContractError> {
+    if !(MIN_ASSETS..=MAX_ASSETS).contains(&asset_infos.len()) {
+        return Err(ContractError::InvalidNumberOfAssets {
+            min: MIN_ASSETS,
+            max: MAX_ASSETS,
+        });
+    }
+
+    if !asset_infos.iter().all_unique() {
+        return Err(ContractError::DoublingAssets {});
+    }
+
+    asset_infos
+        .iter()
+        .try_for_each(|asset_info| asset_info.check(api))
+        .map_err(Into::into)
+}
+
+/// Returns the position of the given asset in the pool.
+pub fn asset_position(config: &Config, asset_info: &AssetInfo) -> Result<usize, ContractError> {
+    config
+        .pair_info
+        .asset_infos
+        .iter()
+        .position(|info| info.equal(asset_info))
+        .ok_or_else(|| ContractError::InvalidAsset(asset_info.to_string()))
+}
+
+/// Resolves positions of the offer and the ask assets in the pool.
+/// The ask asset may be omitted only in a pool with two assets.
+pub fn select_pools(
+    config: &Config,
+    offer_asset_info: Option<&AssetInfo>,
+    ask_asset_info: Option<&AssetInfo>,
+) -> Result<(usize, usize), ContractError> {
+    let asset_infos = &config.pair_info.asset_infos;
+
+    let (offer_ind, ask_ind) = match (offer_asset_info, ask_asset_info) {
+        (Some(offer), Some(ask)) => (asset_position(config, offer)?, asset_position(config, ask)?),
+        (Some(offer), None) if asset_infos.len() == 2 => {
+            let offer_ind = asset_position(config, offer)?;
+            (offer_ind, 1 - offer_ind)
+        }
+        (None, Some(ask)) if asset_infos.len() == 2 => {
+            let ask_ind = asset_position(config, ask)?;
+            (1 - ask_ind, ask_ind)
+        }
+        _ => return Err(ContractError::VariableAssetMissed {}),
+    };
+
+    if offer_ind == ask_ind {
+        return Err(ContractError::SameAssets {});
+    }
+
+    Ok((offer_ind, ask_ind))
+}
+
+/// Converts pool balances into [`Decimal256`] values using the stored asset precisions.
+pub fn to_decimal_pools(storage: &dyn Storage, pools: &[Asset]) -> StdResult<Vec<Decimal256>> {
+    pools
+        .iter()
+        .map(|pool| {
+            Ok(pool
+                .amount
+                .to_decimal256(get_precision(storage, &pool.info)?))
+        })
+        .collect()
+}
+
+/// Validates redemption rate sources: every asset must belong to the pool and have at most one source.
+pub fn check_rate_sources(
+    api: &dyn Api,
+    asset_infos: &[AssetInfo],
+    rate_sources: &[AssetRateSource],
+) -> Result<Vec<AssetRateSource>, ContractError> {
+    if !rate_sources
+        .iter()
+        .map(|rate_source| &rate_source.asset_info)
+        .all_unique()
+    {
+        return Err(ContractError::DoublingAssets {});
+    }
+
+    rate_sources
+        .iter()
+        .map(|rate_source| {
+            if !asset_infos.contains(&rate_source.asset_info) {
+                return Err(ContractError::InvalidAsset(
+                    rate_source.asset_info.to_string(),
+                ));
+            }
+
+            let source = match &rate_source.source {
+                RateSource::Provider { contract_addr } => RateSource::Provider {
+                    contract_addr: api.addr_validate(contract_addr)?.to_string(),
+                },
+                RateSource::Oracle { contract_addr } => RateSource::Oracle {
+                    contract_addr: api.addr_validate(contract_addr)?.to_string(),
+                },
+            };
+
+            Ok(AssetRateSource {
+                asset_info: rate_source.asset_info.clone(),
+                source,
+            })
+        })
+        .collect()
+}
+
+/// Validates hooks: they must be unique, their number must not exceed [`MAX_HOOKS`]
+/// and their gas limits must be within [`HOOK_GAS_LIMIT`].
+pub fn check_hooks(api: &dyn Api, hooks: Vec<PairHook>) -> Result<Vec<PairHook>, ContractError> {
+    if hooks.len() > MAX_HOOKS
+        || !hooks.iter().map(|hook| &hook.contract_addr).all_unique()
+        || hooks
+            .iter()
+            .any(|hook| !HOOK_GAS_LIMIT.contains(&hook.gas_limit))
+    {
+        return Err(ContractError::InvalidHooks {});
+    }
+
+    hooks
+        .into_iter()
+        .map(|hook| {
+            Ok(PairHook {
+                contract_addr: api.addr_validate(hook.contract_addr.as_str())?,
+                ..hook
+            })
+        })
+        .collect()
+}
+
+/// Validates the trader allowlist: it must contain from 1 to [`MAX_ALLOWLIST_ADDRESSES`]
+/// unique addresses.
+pub fn check_trader_allowlist(
+    api: &dyn Api,
+    allowlist: TraderAllowlist,
+) -> Result<TraderAllowlist, ContractError> {
+    match allowlist {
+        TraderAllowlist::Addresses(addresses) => {
+            if addresses.is_empty()
+                || addresses.len() > MAX_ALLOWLIST_ADDRESSES
+                || !addresses.iter().all_unique()
+            {
+                return Err(ContractError::InvalidTraderAllowlist {});
+            }
+
+            Ok(TraderAllowlist::Addresses(
+                addresses
+                    .iter()
+                    .map(|addr| api.addr_validate(addr.as_str()))
+                    .collect::<StdResult<_>>()?,
+            ))
+        }
+        TraderAllowlist::Cw4Group(group_addr) => Ok(TraderAllowlist::Cw4Group(
+            api.addr_validate(group_addr.as_str())?,
+        )),
+    }
+}
+
+/// Returns an error if the pair has a trader allowlist and any of the addresses isn't on it.
+pub fn ensure_traders_allowed(
+    querier: QuerierWrapper,
+    config: &Config,
+    addrs: &[&Addr],
+) -> Result<(), ContractError> {
+    if let Some(allowlist) = &config.trader_allowlist {
+        for addr in addrs {
+            if !allowlist.is_allowed(&querier, addr)? {
+                return Err(ContractError::TraderNotAllowed {
+                    addr: (*addr).clone(),
+                });
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Queries the current redemption rates of the pool assets.
+/// Assets without a rate source are valued 1:1.
+pub fn query_rates(deps: Deps, config: &Config) -> StdResult<Vec<Decimal256>> {
+    config
+        .pair_info
+        .asset_infos
+        .iter()
+        .map(|asset_info| {
+            let Some(rate_source) = config
+                .rate_sources
+                .iter()
+                .find(|rate_source| rate_source.asset_info.equal(asset_info))
+            else {
+                return Ok(Decimal256::one());
+            };
+
+            let rate = match &rate_source.source {
+                RateSource::Provider { contract_addr } => {
+                    deps.querier
+                        .query_wasm_smart::<ExchangeRateResponse>(
+                            contract_addr,
+                            &rate_provider::QueryMsg::ExchangeRate {},
+                        )?
+                        .rate
+                }
+                RateSource::Oracle { contract_addr } => {
+                    let unit =
+                        Uint128::new(10u128.pow(get_precision(deps.storage, asset_info)?.into()));
+                    let prices: Vec<(AssetInfo, Uint256)> = deps.querier.query_wasm_smart(
+                        contract_addr,
+                        &oracle::QueryMsg::Consult {
+                            token: asset_info.clone(),
+                            amount: unit,
+                        },
+                    )?;
+                    let (_, amount) = prices.first().ok_or_else(|| {
+                        StdError::generic_err(format!("Oracle returned no price for {asset_info}"))
+                    })?;
+
+                    Decimal256::from_ratio(*amount, unit)
+                }
+            };
+
+            if rate.is_zero() {
+                return Err(StdError::generic_err(format!(
+                    "Redemption rate of {asset_info} is zero"
+                )));
+            }
+
+            Ok(rate)
+        })
+        .collect()
+}
+
+/// Scales pool balances by the asset redemption rates.
+pub fn apply_rates(pools: &[Decimal256], rates: &[Decimal256]) -> StdResult<Vec<Decimal256>> {
+    pools
+        .iter()
+        .zip(rates)
+        .map(|(pool, rate)| Ok(pool.checked_mul(*rate)?))
+        .collect()
+}
+
+/// Converts an amount scaled by the redemption rate back to the asset units.
+fn remove_rate(amount: Decimal256, rate: Decimal256) -> StdResult<Decimal256> {
+    amount
+        .checked_div(rate)
+        .map_err(|err| StdError::generic_err(err.to_string()))
+}
+
+/// Returns the total amount of assets in the pool as well as the total amount of LP tokens currently minted.
+pub fn pool_info(deps: Deps, config: &Config) -> StdResult<(Vec<Asset>, Uint128)> {
+    let pools = query_reserves(deps.storage, config)?;
+    let total_share = query_lp_supply(
+        &deps.querier,
+        &config.pair_info.liquidity_token,
+        config.native_lp_token,
+    )?;
+
+    Ok((pools, total_share))
+}
+
+/// Returns the pool reserves tracked by the contract.
+/// Unlike the pair balances they can't be affected by direct token transfers.
+pub fn query_reserves(storage: &dyn Storage, config: &Config) -> StdResult<Vec<Asset>> {
+    let reserves = RESERVES.load(storage)?;
+
+    Ok(config
+        .pair_info
+        .asset_infos
+        .iter()
+        .zip(reserves)
+        .map(|(info, amount)| info.with_balance(amount))
+        .collect())
+}
+
+/// Saves the new pool reserves and records them in the asset balance snapshots.
+pub fn save_reserves(
+    storage: &mut dyn Storage,
+    pools: &[Asset],
+    block_height: u64,
+) -> StdResult<()> {
+    for pool in pools {
+        BALANCES.save(storage, &pool.info, &pool.amount, block_height)?;
+    }
+
+    RESERVES.save(storage, &pools.iter().map(|pool| pool.amount).collect())
+}
+
+/// Records the latest cumulative prices from the config in the observations buffer.
+pub fn save_observation(storage: &mut dyn Storage, config: &Config) -> StdResult<()> {
+    let observation = Observation {
+        timestamp: config.block_time_last,
+        cumulative_prices: config.cumulative_prices.clone(),
+    };
+
+    store_observation(storage, &OBSERVATIONS, &observation)
+}
+
+/// Returns the result of a swap in the form of (return amount, spread amount, commission amount).
+/// As the pool targets the redemption rates of the assets (1:1 for assets without a rate source),
+/// any difference between the offer and the return values (before fees) is considered spread.
+///
+/// * **amp** is the current amplification multiplied by AMP_PRECISION.
+///
+/// * **pools** are the pool balances normalized to [`Decimal256`] precision.
+///
+/// * **rates** are the redemption rates of the pool assets.
+///
+/// * **offer_amount** amount of offer assets to swap.
+///
+/// * **commission_rate** total amount of fees charged for the swap.
+pub fn compute_swap(
+    amp: Uint64,
+    pools: &[Decimal256],
+    rates: &[Decimal256],
+    offer_ind: usize,
+    ask_ind: usize,
+    offer_amount: Decimal256,
+    commission_rate: Decimal256,
+) -> StdResult<(Decimal256, Decimal256, Decimal256)> {
+    let xp = apply_rates(pools, rates)?;
+    let offer_amount = offer_amount.checked_mul(rates[offer_ind])?;
+    let d = compute_d(amp, &xp)?;
+
+    let mut new_pools = xp.clone();
+    new_pools[offer_ind] = new_pools[offer_ind].checked_add(offer_amount)?;
+    let new_ask_pool = calc_y(amp, d, &new_pools, ask_ind)?;
+
+    // Subtract one atom to make sure rounding errors go in favor of the pool
+    let return_amount = xp[ask_ind]
+        .saturating_sub(new_ask_pool)
+        .saturating_sub(Decimal256::raw(1));
+    let spread_amount = offer_amount.saturating_sub(return_amount);
+    let commission_amount = return_amount * commission_rate;
+
+    let ask_rate = rates[ask_ind];
+    Ok((
+        remove_rate(return_amount - commission_amount, ask_rate)?,
+        remove_rate(spread_amount, ask_rate)?,
+        remove_rate(commission_amount, ask_rate)?,
+    ))
+}
+
+/// Returns an amount of offer assets for a specified amount of ask assets in the form of
+/// (offer amount, spread amount, commission amount).
+///
+/// * **amp** is the current amplification multiplied by AMP_PRECISION.
+///
+/// * **pools** are the pool balances normalized to [`Decimal256`] precision.
+///
+/// * **rates** are the redemption rates of the pool assets.
+///
+/// * **ask_amount** amount of ask assets to swap to.
+///
+/// * **commission_rate** total amount of fees charged for the swap.
+pub fn compute_offer_amount(
+    amp: Uint64,
+    pools: &[Decimal256],
+    rates: &[Decimal256],
+    offer_ind: usize,
+    ask_ind: usize,
+    ask_amount: Decimal256,
+    commission_rate: Decimal256,
+) -> StdResult<(Decimal256, Decimal256, Decimal256)> {
+    let xp = apply_rates(pools, rates)?;
+    let ask_amount = ask_amount.checked_mul(rates[ask_ind])?;
+    let before_commission = ask_amount
+        .checked_div(Decimal256::one() - commission_rate)
+        .map_err(|err| StdError::generic_err(err.to_string()))?;
+
+    let d = compute_d(amp, &xp)?;
+
+    let mut new_pools = xp.clone();
+    new_pools[ask_ind] = new_pools[ask_ind]
+        .checked_sub(before_commission)
+        .map_err(|_| StdError::generic_err("Not enough assets in the pool"))?;
+    if new_pools[ask_ind].is_zero() {
+        return Err(StdError::generic_err("Not enough assets in the pool"));
+    }
+    let new_offer_pool = calc_y(amp, d, &new_pools, offer_ind)?;
+
+    // Add one atom to make sure rounding errors go in favor of the pool
+    let offer_amount = new_offer_pool.saturating_sub(xp[offer_ind]) + Decimal256::raw(1);
+    let spread_amount = offer_amount.saturating_sub(before_commission);
+    let commission_amount = before_commission - ask_amount;
+
+    let offer_rate = rates[offer_ind];
+    Ok((
+        remove_rate(offer_amount, offer_rate)?,
+        remove_rate(spread_amount, offer_rate)?,
+        remove_rate(commission_amount, rates[ask_ind])?,
+    ))
+}
+
+/// Accumulate token prices for every pair of assets in the pool.
+/// Note that this function shifts **block_time** when any of the pool balances is zero in order to not
+/// fill an accumulator with a null price for that period.
+///
+/// * **pools** are the pool balances normalized to [`Decimal256`] precision.
+///
+/// * **rates** are the redemption rates of the pool assets.
+pub fn accumulate_prices(
+    env: &Env,
+    config: &mut Config,
+    pools: &[Decimal256],
+    rates: &[Decimal256],
+) -> StdResult<()> {
+    let block_time = env.block.time.seconds();
+    if block_time <= config.block_time_last {
+        return Ok(());
+    }
+
+    let time_elapsed = Uint128::from(block_time - config.block_time_last);
+
+    let mut open_prices = vec![];
+    if pools.iter().all(|pool| !pool.is_zero()) {
+        let amp = compute_current_amp(config, env)?;
+        let asset_infos = config.pair_info.asset_infos.clone();
+        let position = |asset_info: &AssetInfo| {
+            asset_infos
+                .iter()
+                .position(|info| info.equal(asset_info))
+                .ok_or_else(|| StdError::generic_err(format!("Unknown asset {asset_info}")))
+        };
+
+        for (from, to, value) in config.cumulative_prices.iter_mut() {
+            // Price of one unit of the offer asset without fees
+            let (price, ..) = compute_swap(
+                amp,
+                pools,
+                rates,
+                position(from)?,
+                position(to)?,
+                Decimal256::one(),
+                Decimal256::zero(),
+            )?;
+
+            *value = value.wrapping_add(time_elapsed.checked_mul(price.to_uint(TWAP_PRECISION)?)?);
+            open_prices.push((from.clone(), to.clone(), price));
+        }
+    }
+
+    config.block_open_prices = open_prices;
+    config.block_time_last = block_time;
+
+    Ok(())
+}
+
+/// Ensures the price of the swapped assets stays within the allowed deviation
+/// from their price at the start of the block.
+///
+/// * **pools** are the pool balances after the swap normalized to [`Decimal256`] precision.
+///
+/// * **rates** are the redemption rates of the pool assets.
+pub fn ensure_price_deviation(
+    env: &Env,
+    config: &Config,
+    pools: &[Decimal256],
+    rates: &[Decimal256],
+    offer_ind: usize,
+    ask_ind: usize,
+) -> Result<(), ContractError> {
+    let Some(max_deviation) = config.max_price_deviation else {
+        return Ok(());
+    };
+
+    let (offer_info, ask_info) = (
+        &config.pair_info.asset_infos[offer_ind],
+        &config.pair_info.asset_infos[ask_ind],
+    );
+    let Some((.., open_price)) = config
+        .block_open_prices
+        .iter()
+        .find(|(from, to, _)| from.equal(offer_info) && to.equal(ask_info))
+    else {
+        return Ok(());
+    };
+
+    let (price, ..) = compute_swap(
+        compute_current_amp(config, env)?,
+        pools,
+        rates,
+        offer_ind,
+        ask_ind,
+        Decimal256::one(),
+        Decimal256::zero(),
+    )?;
+
+    let deviation = price_deviation(*open_price, price);
+    if deviation > Decimal256::from(max_deviation) {
+        return Err(ContractError::PriceDeviationExceeded {
+            deviation,
+            max_deviation,
+        });
+    }
+
+    Ok(())
+}
+
+/// Mint LP tokens for a beneficiary and auto stake the tokens in the Incentive contract (if auto staking is specified).
+///
+/// * **recipient** LP token recipient.
+///
+/// * **amount** amount of LP tokens that will be minted for the recipient.
+///
+/// * **auto_stake** determines whether the newly minted LP tokens will
+///   be automatically staked in the Incentives contract on behalf of the recipient.
+pub fn mint_liquidity_token_message<T, C>(
+    querier: QuerierWrapper<C>,
+    config: &Config,
+    contract_address: &Addr,
+    recipient: &Addr,
+    amount: Uint128,
+    auto_stake: bool,
+) -> Result<Vec<CosmosMsg<T>>, ContractError>
+where
+    C: CustomQuery,
+    T: CustomMsg,
+{
+    if config.native_lp_token {
+        let coin = coin(amount.into(), &config.pair_info.liquidity_token);
+
+        if !auto_stake {
+            return Ok(tf_mint_msg(contract_address, coin, recipient));
+        }
+
+        let incentives_addr = query_factory_config(&querier, &config.factory_addr)?
+            .incentives_address
+            .ok_or(ContractError::AutoStakeError {})?;
+
+        let mut messages = tf_mint_msg(contract_address, coin.clone(), contract_address);
+        messages.push(
+            wasm_execute(
+                incentives_addr,
+                &incentives::ExecuteMsg::Deposit {
+                    recipient: Some(recipient.to_string()),
+                },
+                vec![coin],
+            )?
+            .into(),
+        );
+
+        return Ok(messages);
+    }
+
+    // If no auto-stake - just mint to recipient
+    if !auto_stake {
+        return Ok(vec![wasm_execute(
+            &config.pair_info.liquidity_token,
+            &Cw20ExecuteMsg::Mint {
+                recipient: recipient.to_string(),
+                amount,
+            },
+            vec![],
+        )?
+        .into()]);
+    }
+
+    // Mint for the pair contract and stake into the Incentives contract
+    let incentives_addr = query_factory_config(&querier, &config.factory_addr)?.incentives_address;
+
+    if let Some(address) = incentives_addr {
+        Ok(vec![
+            wasm_execute(
+                &config.pair_info.liquidity_token,
+                &Cw20ExecuteMsg::Mint {
+                    recipient: contract_address.to_string(),
+                    amount,
+                },
+                vec![],
+            )?
+            .into(),
+            wasm_execute(
+                &config.pair_info.liquidity_token,
+                &Cw20ExecuteMsg::Send {
+                    contract: address.to_string(),
+                    amount,
+                    msg: to_json_binary(&incentives::ExecuteMsg::Deposit {
+                        recipient: Some(recipient.to_string()),
+                    })?,
+                },
+                vec![],
+            )?
+            .into(),
+        ])
+    } else {
+        Err(ContractError::AutoStakeError {})
+    }
+}
+
+/// Returns the amount of pool assets that correspond to an amount of LP tokens.
+///
+/// * **pools** is the array with assets in the pool.
+///
+/// * **amount** is amount of LP tokens to compute a corresponding amount of assets for.
+///
+/// * **total_share** is the total amount of LP tokens currently minted.
+pub fn get_share_in_assets(pools: &[Asset], amount: Uint128, total_share: Uint128) -> Vec<Asset> {
+    let mut share_ratio = Decimal::zero();
+    if !total_share.is_zero() {
+        share_ratio = Decimal::from_ratio(amount, total_share);
+    }
+
+    pools
+        .iter()
+        .map(|a| Asset {
+            info: a.info.clone(),
+            amount: a.amount.dec_mul(share_ratio),
+        })
+        .collect()
+}
+
+/// Checks that every asset in **min_assets_to_receive** belongs to the pool and that
+/// the refunded amount is not less than the specified minimum.
+pub fn ensure_min_assets_to_receive(
+    refund_assets: &[Asset],
+    min_assets_to_receive: Option<Vec<Asset>>,
+) -> Result<(), ContractError> {
+    if let Some(min_assets_to_receive) = min_assets_to_receive {
+        if refund_assets.len() != min_assets_to_receive.len() {
+            return Err(ContractError::WrongAssetLength {
+                expected: refund_assets.len(),
+                actual: min_assets_to_receive.len(),
+            });
+        }
+
+        let mut seen = HashSet::new();
+        for min_asset in &min_assets_to_receive {
+            if !seen.insert(min_asset.info.to_string()) {
+                return Err(ContractError::DoublingAssets {});
+            }
+
+            let refund_asset = refund_assets
+                .iter()
+                .find(|asset| asset.info.equal(&min_asset.info))
+                .ok_or(ContractError::AssetMismatch {})?;
+
+            if refund_asset.amount < min_asset.amount {
+                return Err(ContractError::WithdrawSlippageViolation {
+                    asset_name: refund_asset.info.to_string(),
+                    received: refund_asset.amount,
+                    expected: min_asset.amount,
+                });
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// If `belief_price` and `max_spread` are both specified, we compute a new spread,
+/// otherwise we just use the swap spread to check `max_spread`.
+///
+/// * **belief_price** belief price used in the swap.
+///
+/// * **max_spread** max spread allowed so that the swap can be executed successfully.
+///
+/// * **offer_amount** amount of assets to swap.
+///
+/// * **return_amount** amount of assets to receive from the swap.
+///
+/// * **spread_amount** spread used in the swap.
+pub fn assert_max_spread(
+    belief_price: Option<Decimal>,
+    max_spread: Option<Decimal>,
+    offer_amount: Uint128,
+    return_amount: Uint128,
+    spread_amount: Uint128,
+) -> Result<(), ContractError> {
+    let default_spread = Decimal::from_str(DEFAULT_SLIPPAGE)?;
+    let max_allowed_spread = Decimal::from_str(MAX_ALLOWED_SLIPPAGE)?;
+
+    let max_spread = max_spread.unwrap_or(default_spread);
+    if max_spread.gt(&max_allowed_spread) {
+        return Err(ContractError::AllowedSpreadAssertion {});
+    }
+
+    if let Some(belief_price) = belief_price {
+        let expected_return = offer_amount.dec_mul(
+            belief_price
+                .inv()
+                .ok_or_else(|| StdError::generic_err("Belief price must not be zero!"))?,
+        );
+        let spread_amount = expected_return.saturating_sub(return_amount);
+
+        if return_amount < expected_return
+            && Decimal::from_ratio(spread_amount, expected_return) > max_spread
+        {
+            return Err(ContractError::MaxSpreadAssertion {});
+        }
+    } else if Decimal::from_ratio(spread_amount, return_amount + spread_amount) > max_spread {
+        return Err(ContractError::MaxSpreadAssertion {});
+    }
+
+    Ok(())
+}
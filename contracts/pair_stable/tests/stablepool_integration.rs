@@ -0,0 +1,1126 @@
+#![allow(clippy::inconsistent_digit_grouping)]
+
+use cosmwasm_std::{Decimal, Decimal256, Uint128};
+use cw_multi_test::Executor;
+use itertools::Itertools;
+
+use astroport::asset::{native_asset_info, AssetInfoExt, MINIMUM_LIQUIDITY_AMOUNT};
+use astroport::factory::{ExecuteMsg as FactoryExecuteMsg, PauseScope};
+use astroport::pair::{
+    AssetRateSource, RateSource, StablePoolParams, StablePoolUpdateParams, TraderAllowlist,
+    MAX_FEE_SHARE_BPS,
+};
+use astroport::stats::STATS_EPOCH_LENGTH;
+use astroport_pair_stable::error::ContractError;
+use astroport_pair_stable::math::{MAX_AMP, MAX_AMP_CHANGE, MIN_AMP_CHANGING_TIME};
+use astroport_test::coins::TestCoin;
+
+use crate::helper::{common_stable_params, AppExtension, Helper, MockRateSource};
+
+mod helper;
+
+#[test]
+fn check_wrong_initialization() {
+    let err = Helper::new(vec![TestCoin::native("uusdc")], common_stable_params(100)).unwrap_err();
+    assert_eq!(
+        ContractError::InvalidNumberOfAssets { min: 2, max: 5 },
+        err.downcast().unwrap(),
+    );
+
+    let too_many_coins = (0..6u8)
+        .map(|i| TestCoin::cw20(&format!("TOKEN{}", (b'A' + i) as char)))
+        .collect_vec();
+    let err = Helper::new(too_many_coins, common_stable_params(100)).unwrap_err();
+    assert_eq!(
+        ContractError::InvalidNumberOfAssets { min: 2, max: 5 },
+        err.downcast().unwrap(),
+    );
+
+    let test_coins = vec![TestCoin::native("uusdc"), TestCoin::cw20("USDT")];
+
+    let err = Helper::new(test_coins.clone(), common_stable_params(0)).unwrap_err();
+    assert_eq!(ContractError::IncorrectAmp {}, err.downcast().unwrap());
+
+    let err = Helper::new(test_coins.clone(), common_stable_params(MAX_AMP + 1)).unwrap_err();
+    assert_eq!(ContractError::IncorrectAmp {}, err.downcast().unwrap());
+
+    // Valid pools with the minimum and maximum amount of assets
+    Helper::new(test_coins, common_stable_params(100)).unwrap();
+    let max_coins = (0..5u8)
+        .map(|i| TestCoin::cw20(&format!("TOKEN{}", (b'A' + i) as char)))
+        .collect_vec();
+    Helper::new(max_coins, common_stable_params(100)).unwrap();
+}
+
+#[test]
+fn provide_and_withdraw() {
+    let test_coins = vec![TestCoin::native("uusdc"), TestCoin::cw20("USDT")];
+    let mut helper = Helper::new(test_coins.clone(), common_stable_params(100)).unwrap();
+
+    let user1 = helper.app.api().addr_make("user1");
+
+    let random_coin = native_asset_info("random-coin".to_string()).with_balance(100u8);
+    let wrong_assets = vec![
+        helper.assets[&test_coins[0]].with_balance(100_000_000000u128),
+        random_coin.clone(),
+    ];
+    helper.give_me_money(&wrong_assets, &user1);
+    let err = helper.provide_liquidity(&user1, &wrong_assets).unwrap_err();
+    assert_eq!(
+        ContractError::InvalidAsset(random_coin.info.to_string()),
+        err.downcast().unwrap(),
+    );
+
+    // Initial provide must contain all pool assets
+    let assets = vec![helper.assets[&test_coins[0]].with_balance(100_000_000000u128)];
+    let err = helper.provide_liquidity(&user1, &assets).unwrap_err();
+    assert_eq!(
+        ContractError::InitialProvideAllAssets {},
+        err.downcast().unwrap(),
+    );
+
+    let assets = vec![
+        helper.assets[&test_coins[0]].with_balance(100_000_000000u128),
+        helper.assets[&test_coins[1]].with_balance(100_000_000000u128),
+    ];
+    helper.give_me_money(&assets, &user1);
+    helper.provide_liquidity(&user1, &assets).unwrap();
+
+    // Balanced pool mints LP tokens equal to the invariant D
+    assert_eq!(
+        200_000_000000 - MINIMUM_LIQUIDITY_AMOUNT.u128(),
+        helper.token_balance(&helper.lp_token, &user1)
+    );
+    assert_eq!(
+        MINIMUM_LIQUIDITY_AMOUNT.u128(),
+        helper.token_balance(&helper.lp_token, &helper.pair_addr)
+    );
+    assert_eq!(helper.query_d().unwrap().u128(), 200_000_000000);
+
+    // The second balanced provide mints LP tokens proportionally
+    let user2 = helper.app.api().addr_make("user2");
+    let assets = vec![
+        helper.assets[&test_coins[0]].with_balance(50_000_000000u128),
+        helper.assets[&test_coins[1]].with_balance(50_000_000000u128),
+    ];
+    helper.give_me_money(&assets, &user2);
+
+    // Check slippage protection
+    let err = helper
+        .provide_liquidity_full(
+            &user2,
+            &assets,
+            None,
+            None,
+            None,
+            Some(100_000_000001u128.into()),
+        )
+        .unwrap_err();
+    assert_eq!(
+        ContractError::ProvideSlippageViolation(
+            100_000_000000u128.into(),
+            100_000_000001u128.into()
+        ),
+        err.downcast().unwrap(),
+    );
+
+    helper.provide_liquidity(&user2, &assets).unwrap();
+    assert_eq!(
+        100_000_000000,
+        helper.token_balance(&helper.lp_token, &user2)
+    );
+
+    helper.withdraw_liquidity(&user2, 50_000_000000).unwrap();
+    assert_eq!(
+        50_000_000000,
+        helper.token_balance(&helper.lp_token, &user2)
+    );
+    // Withdrawn amounts are rounded down in favor of the pool
+    assert_eq!(24_999_999999, helper.coin_balance(&test_coins[0], &user2));
+    assert_eq!(24_999_999999, helper.coin_balance(&test_coins[1], &user2));
+
+    let pool = helper.query_pool().unwrap();
+    assert_eq!(
+        pool.assets
+            .iter()
+            .map(|asset| asset.amount.u128())
+            .collect_vec(),
+        vec![125_000_000001, 125_000_000001]
+    );
+    assert_eq!(pool.total_share.u128(), 250_000_000000);
+}
+
+#[test]
+fn check_imbalanced_provide() {
+    let test_coins = vec![TestCoin::native("uusdc"), TestCoin::native("uusdt")];
+    let mut helper = Helper::new(test_coins.clone(), common_stable_params(100)).unwrap();
+
+    let owner = helper.owner.clone();
+    let assets = vec![
+        helper.assets[&test_coins[0]].with_balance(1_000_000_000000u128),
+        helper.assets[&test_coins[1]].with_balance(1_000_000_000000u128),
+    ];
+    helper.provide_liquidity(&owner, &assets).unwrap();
+
+    let user1 = helper.app.api().addr_make("user1");
+    let single_sided = vec![helper.assets[&test_coins[0]].with_balance(100_000_000000u128)];
+    let simulated = helper.simulate_provide(&single_sided).unwrap();
+
+    helper.give_me_money(&single_sided, &user1);
+    helper.provide_liquidity(&user1, &single_sided).unwrap();
+    let received = helper.token_balance(&helper.lp_token, &user1);
+
+    assert_eq!(simulated.u128(), received);
+    // Imbalanced provide is charged an imbalance fee and faces price impact
+    assert!(received < 100_000_000000, "{received}");
+    assert!(received > 99_000_000000, "{received}");
+
+    // Withdrawing everything right away returns less than was deposited
+    helper.withdraw_liquidity(&user1, received).unwrap();
+    let usdc = helper.coin_balance(&test_coins[0], &user1);
+    let usdt = helper.coin_balance(&test_coins[1], &user1);
+    assert!(usdc + usdt < 100_000_000000, "{usdc} + {usdt}");
+}
+
+#[test]
+fn provide_and_withdraw_with_native_lp_token() {
+    let test_coins = vec![
+        TestCoin::native("uusdc"),
+        TestCoin::native("uusdt"),
+        TestCoin::native("uusd"),
+    ];
+    let mut helper = Helper::new_native_lp(test_coins.clone(), common_stable_params(100)).unwrap();
+    assert_eq!(
+        helper.lp_token,
+        format!("factory/{}/astroport/share", helper.pair_addr)
+    );
+
+    let owner = helper.owner.clone();
+    let assets = test_coins
+        .iter()
+        .map(|coin| helper.assets[coin].with_balance(1_000_000000u128))
+        .collect_vec();
+    helper.provide_liquidity(&owner, &assets).unwrap();
+
+    let lp_amount = helper.native_balance(&helper.lp_token, &owner);
+    assert_eq!(lp_amount, 3_000_000000 - MINIMUM_LIQUIDITY_AMOUNT.u128());
+    assert_eq!(
+        helper.query_pool().unwrap().total_share.u128(),
+        3_000_000000
+    );
+
+    helper.withdraw_liquidity(&owner, lp_amount).unwrap();
+
+    assert_eq!(helper.native_balance(&helper.lp_token, &owner), 0);
+    assert_eq!(
+        helper.query_pool().unwrap().total_share,
+        MINIMUM_LIQUIDITY_AMOUNT
+    );
+}
+
+#[test]
+fn check_swaps_with_three_assets() {
+    let test_coins = vec![
+        TestCoin::native("uusdc"),
+        TestCoin::native("uusdt"),
+        TestCoin::cw20("DAI"),
+    ];
+    let mut helper = Helper::new(test_coins.clone(), common_stable_params(100)).unwrap();
+
+    let owner = helper.owner.clone();
+    let assets = test_coins
+        .iter()
+        .map(|coin| helper.assets[coin].with_balance(1_000_000_000000u128))
+        .collect_vec();
+    helper.provide_liquidity(&owner, &assets).unwrap();
+    assert_eq!(helper.query_d().unwrap().u128(), 3_000_000_000000);
+
+    let user = helper.app.api().addr_make("user");
+    let offer_asset = helper.assets[&test_coins[0]].with_balance(100_000000u128);
+    helper.give_me_money(std::slice::from_ref(&offer_asset), &user);
+
+    // Ask asset must be specified in a pool with more than two assets
+    let err = helper.swap(&user, &offer_asset, None).unwrap_err();
+    assert_eq!(
+        ContractError::VariableAssetMissed {},
+        err.downcast().unwrap(),
+    );
+
+    // Offer and ask assets must differ
+    let err = helper
+        .swap_full_params(
+            &user,
+            &offer_asset,
+            Some(helper.assets[&test_coins[0]].clone()),
+            None,
+            None,
+        )
+        .unwrap_err();
+    assert_eq!(ContractError::SameAssets {}, err.downcast().unwrap());
+
+    let sim = helper
+        .simulate_swap(&offer_asset, Some(helper.assets[&test_coins[2]].clone()))
+        .unwrap();
+    helper
+        .swap_full_params(
+            &user,
+            &offer_asset,
+            Some(helper.assets[&test_coins[2]].clone()),
+            None,
+            None,
+        )
+        .unwrap();
+
+    let received = helper.coin_balance(&test_coins[2], &user);
+    assert_eq!(received, sim.return_amount.u128());
+    // 0.05% fee and a tiny spread in a balanced pool
+    assert_eq!(sim.commission_amount.u128(), 49999);
+    assert!(received > 99_940000 && received < 99_950001, "{received}");
+
+    // Half of the fee goes to the maker
+    assert_eq!(
+        helper.coin_balance(&test_coins[2], &helper.fake_maker),
+        24999
+    );
+
+    // Reverse simulation is consistent with the direct one
+    let ask_asset = helper.assets[&test_coins[1]].with_balance(100_000000u128);
+    let rev_sim = helper
+        .simulate_reverse_swap(&ask_asset, Some(helper.assets[&test_coins[2]].clone()))
+        .unwrap();
+    let offer_asset = helper.assets[&test_coins[2]].with_balance(rev_sim.offer_amount);
+    let sim = helper
+        .simulate_swap(&offer_asset, Some(helper.assets[&test_coins[1]].clone()))
+        .unwrap();
+    assert!(
+        sim.return_amount.u128().abs_diff(100_000000) <= 1,
+        "{}",
+        sim.return_amount
+    );
+}
+
+#[test]
+fn swap_different_precisions() {
+    let test_coins = vec![TestCoin::native("ubtc"), TestCoin::cw20precise("WBTC", 18)];
+    let mut helper = Helper::new(test_coins.clone(), common_stable_params(500)).unwrap();
+
+    let owner = helper.owner.clone();
+    let assets = vec![
+        helper.assets[&test_coins[0]].with_balance(100_00000000u128),
+        helper.assets[&test_coins[1]].with_balance(100_000000000000000000u128),
+    ];
+    helper.provide_liquidity(&owner, &assets).unwrap();
+    assert_eq!(helper.query_d().unwrap().u128(), 200_000000);
+
+    let user = helper.app.api().addr_make("user");
+    let offer_asset = helper.assets[&test_coins[0]].with_balance(1_00000000u128);
+    helper.give_me_money(std::slice::from_ref(&offer_asset), &user);
+    helper.swap(&user, &offer_asset, None).unwrap();
+
+    let received = helper.coin_balance(&test_coins[1], &user);
+    assert!(
+        received > 999_000_000_000_000_000 && received < 1_000_000_000_000_000_000,
+        "{received}"
+    );
+
+    let offer_asset = helper.assets[&test_coins[1]].with_balance(received);
+    helper.swap(&user, &offer_asset, None).unwrap();
+    let received_back = helper.coin_balance(&test_coins[0], &user);
+    assert!(
+        received_back > 99_800_000 && received_back < 100_000_000,
+        "{received_back}"
+    );
+}
+
+#[test]
+fn check_amp_changing() {
+    let test_coins = vec![TestCoin::native("uusdc"), TestCoin::native("uusdt")];
+    let mut helper = Helper::new(test_coins, common_stable_params(100)).unwrap();
+
+    let (_, params) = helper.query_config_params().unwrap();
+    assert_eq!(params.amp, Decimal::from_ratio(100u8, 1u8));
+
+    let user = helper.app.api().addr_make("user");
+    let owner = helper.owner.clone();
+
+    let action = StablePoolUpdateParams::StartChangingAmp {
+        next_amp: 200,
+        next_amp_time: helper.app.block_info().time.seconds() + MIN_AMP_CHANGING_TIME,
+    };
+    let err = helper.update_config(&user, &action).unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+
+    // Amp can not be changed right after the pool was created
+    let err = helper.update_config(&owner, &action).unwrap_err();
+    assert_eq!(
+        ContractError::MinAmpChangingTimeAssertion {},
+        err.downcast().unwrap()
+    );
+
+    helper.app.next_block(MIN_AMP_CHANGING_TIME);
+    let now = helper.app.block_info().time.seconds();
+
+    let err = helper
+        .update_config(
+            &owner,
+            &StablePoolUpdateParams::StartChangingAmp {
+                next_amp: 100 * MAX_AMP_CHANGE + 1,
+                next_amp_time: now + MIN_AMP_CHANGING_TIME,
+            },
+        )
+        .unwrap_err();
+    assert_eq!(
+        ContractError::MaxAmpChangeAssertion {},
+        err.downcast().unwrap()
+    );
+
+    let err = helper
+        .update_config(
+            &owner,
+            &StablePoolUpdateParams::StartChangingAmp {
+                next_amp: 200,
+                next_amp_time: now + MIN_AMP_CHANGING_TIME - 1,
+            },
+        )
+        .unwrap_err();
+    assert_eq!(
+        ContractError::MinAmpChangingTimeAssertion {},
+        err.downcast().unwrap()
+    );
+
+    helper
+        .update_config(
+            &owner,
+            &StablePoolUpdateParams::StartChangingAmp {
+                next_amp: 200,
+                next_amp_time: now + MIN_AMP_CHANGING_TIME,
+            },
+        )
+        .unwrap();
+
+    // Amp grows linearly
+    helper.app.next_block(MIN_AMP_CHANGING_TIME / 2);
+    let (_, params) = helper.query_config_params().unwrap();
+    assert_eq!(params.amp, Decimal::from_ratio(150u8, 1u8));
+
+    // Freeze the amp at its current value
+    helper
+        .update_config(&owner, &StablePoolUpdateParams::StopChangingAmp {})
+        .unwrap();
+    helper.app.next_block(MIN_AMP_CHANGING_TIME);
+    let (_, params) = helper.query_config_params().unwrap();
+    assert_eq!(params.amp, Decimal::from_ratio(150u8, 1u8));
+}
+
+#[test]
+fn check_prices() {
+    let test_coins = vec![
+        TestCoin::native("uusdc"),
+        TestCoin::native("uusdt"),
+        TestCoin::cw20("DAI"),
+    ];
+    let mut helper = Helper::new(test_coins.clone(), common_stable_params(100)).unwrap();
+
+    let prices = helper.query_prices().unwrap();
+    assert_eq!(prices.cumulative_prices.len(), 6);
+    assert!(prices
+        .cumulative_prices
+        .iter()
+        .all(|(_, _, price)| price.is_zero()));
+
+    let owner = helper.owner.clone();
+    let assets = test_coins
+        .iter()
+        .map(|coin| helper.assets[coin].with_balance(1_000_000_000000u128))
+        .collect_vec();
+    helper.provide_liquidity(&owner, &assets).unwrap();
+    helper.app.next_block(1000);
+
+    let offer_asset = helper.assets[&test_coins[0]].with_balance(100_000_000000u128);
+    helper
+        .swap_full_params(
+            &owner,
+            &offer_asset,
+            Some(helper.assets[&test_coins[1]].clone()),
+            Some(Decimal::percent(50)),
+            None,
+        )
+        .unwrap();
+    helper.app.next_block(1000);
+
+    let prices = helper.query_prices().unwrap();
+    for (from, to, price) in prices.cumulative_prices {
+        let price = price.u128();
+        if from == helper.assets[&test_coins[0]] && to == helper.assets[&test_coins[1]] {
+            // uusdc became cheaper than uusdt after the swap
+            assert!(price < 2000_000000, "{from} -> {to}: {price}");
+        } else if from == helper.assets[&test_coins[1]] && to == helper.assets[&test_coins[0]] {
+            assert!(price > 2000_000000, "{from} -> {to}: {price}");
+        } else {
+            assert!(
+                price.abs_diff(2000_000000) < 2_000000,
+                "{from} -> {to}: {price}"
+            );
+        }
+    }
+}
+
+#[test]
+fn check_fee_share() {
+    let test_coins = vec![TestCoin::native("uusdc"), TestCoin::native("uusdt")];
+    let mut helper = Helper::new(test_coins.clone(), common_stable_params(100)).unwrap();
+
+    let owner = helper.owner.clone();
+    let assets = vec![
+        helper.assets[&test_coins[0]].with_balance(1_000_000_000000u128),
+        helper.assets[&test_coins[1]].with_balance(1_000_000_000000u128),
+    ];
+    helper.provide_liquidity(&owner, &assets).unwrap();
+
+    let fee_share_address = helper.app.api().addr_make("fee_share");
+    let err = helper
+        .update_config(
+            &owner,
+            &StablePoolUpdateParams::EnableFeeShare {
+                fee_share_bps: MAX_FEE_SHARE_BPS + 1,
+                fee_share_address: fee_share_address.to_string(),
+            },
+        )
+        .unwrap_err();
+    assert_eq!(
+        ContractError::FeeShareOutOfBounds {},
+        err.downcast().unwrap()
+    );
+
+    helper
+        .update_config(
+            &owner,
+            &StablePoolUpdateParams::EnableFeeShare {
+                fee_share_bps: MAX_FEE_SHARE_BPS,
+                fee_share_address: fee_share_address.to_string(),
+            },
+        )
+        .unwrap();
+    let (_, params) = helper.query_config_params().unwrap();
+    assert_eq!(params.fee_share.unwrap().total_bps(), MAX_FEE_SHARE_BPS);
+
+    let user = helper.app.api().addr_make("user");
+    let offer_asset = helper.assets[&test_coins[0]].with_balance(100_000_000000u128);
+    helper.give_me_money(std::slice::from_ref(&offer_asset), &user);
+    helper.swap(&user, &offer_asset, None).unwrap();
+
+    // 10% of the 0.05% fee goes to the fee share address, the maker gets half of the remainder
+    let fee_share = helper.coin_balance(&test_coins[1], &fee_share_address);
+    let maker_fee = helper.coin_balance(&test_coins[1], &helper.fake_maker);
+    assert!(fee_share.abs_diff(5_000000) < 50000, "{fee_share}");
+    assert!(maker_fee.abs_diff(22_500000) < 50000, "{maker_fee}");
+
+    helper
+        .update_config(&owner, &StablePoolUpdateParams::DisableFeeShare)
+        .unwrap();
+    let (_, params) = helper.query_config_params().unwrap();
+    assert_eq!(params.fee_share, None);
+}
+
+#[test]
+fn check_stats() {
+    let test_coins = vec![TestCoin::native("uusdc"), TestCoin::cw20("USDT")];
+    let mut helper = Helper::new(test_coins.clone(), common_stable_params(100)).unwrap();
+
+    let owner = helper.owner.clone();
+    let assets = vec![
+        helper.assets[&test_coins[0]].with_balance(1_000_000_000000u128),
+        helper.assets[&test_coins[1]].with_balance(1_000_000_000000u128),
+    ];
+    helper.provide_liquidity(&owner, &assets).unwrap();
+
+    let fee_share_address = helper.app.api().addr_make("fee_share");
+    helper
+        .update_config(
+            &owner,
+            &StablePoolUpdateParams::EnableFeeShare {
+                fee_share_bps: MAX_FEE_SHARE_BPS,
+                fee_share_address: fee_share_address.to_string(),
+            },
+        )
+        .unwrap();
+
+    let user = helper.app.api().addr_make("user");
+    let offer_asset = helper.assets[&test_coins[0]].with_balance(100_000_000000u128);
+    let sim = helper.simulate_swap(&offer_asset, None).unwrap();
+    helper.give_me_money(std::slice::from_ref(&offer_asset), &user);
+    helper.swap(&user, &offer_asset, None).unwrap();
+
+    let stats = helper.query_stats(None).unwrap();
+    assert_eq!(stats.epoch_stats, stats.lifetime_stats);
+    assert_eq!(stats.epoch_stats[0].volume_in, offer_asset.amount);
+    assert_eq!(stats.epoch_stats[1].volume_out, sim.return_amount);
+    assert_eq!(
+        stats.epoch_stats[1].fee_share.u128(),
+        helper.coin_balance(&test_coins[1], &fee_share_address)
+    );
+    assert_eq!(
+        stats.epoch_stats[1].maker_fee.u128(),
+        helper.coin_balance(&test_coins[1], &helper.fake_maker)
+    );
+    assert_eq!(
+        stats.epoch_stats[1].lp_fee
+            + stats.epoch_stats[1].maker_fee
+            + stats.epoch_stats[1].fee_share,
+        sim.commission_amount
+    );
+
+    // The next epoch starts with empty stats
+    helper.app.next_block(STATS_EPOCH_LENGTH);
+    let next = helper.query_stats(None).unwrap();
+    assert_eq!(next.epoch, stats.epoch + 1);
+    assert!(next.epoch_stats[1].lp_fee.is_zero());
+    assert_eq!(next.lifetime_stats, stats.lifetime_stats);
+}
+
+#[test]
+fn check_price_guard() {
+    let test_coins = vec![TestCoin::native("uusdc"), TestCoin::cw20("USDT")];
+    let mut helper = Helper::new(test_coins.clone(), common_stable_params(100)).unwrap();
+
+    let owner = helper.owner.clone();
+    let assets = vec![
+        helper.assets[&test_coins[0]].with_balance(1_000_000_000000u128),
+        helper.assets[&test_coins[1]].with_balance(1_000_000_000000u128),
+    ];
+    helper.provide_liquidity(&owner, &assets).unwrap();
+
+    let err = helper
+        .update_config(
+            &owner,
+            &StablePoolUpdateParams::EnablePriceGuard {
+                max_deviation: Decimal::percent(51),
+            },
+        )
+        .unwrap_err();
+    assert_eq!(
+        ContractError::InvalidPriceDeviation {},
+        err.downcast().unwrap()
+    );
+
+    helper
+        .update_config(
+            &owner,
+            &StablePoolUpdateParams::EnablePriceGuard {
+                max_deviation: Decimal::permille(3),
+            },
+        )
+        .unwrap();
+    let (_, params) = helper.query_config_params().unwrap();
+    assert_eq!(params.max_price_deviation, Some(Decimal::permille(3)));
+
+    let user = helper.app.api().addr_make("user");
+    let offer_asset = helper.assets[&test_coins[0]].with_balance(100_000_000000u128);
+    helper.give_me_money(
+        &[
+            offer_asset.clone(),
+            offer_asset.clone(),
+            offer_asset.clone(),
+        ],
+        &user,
+    );
+
+    helper.app.next_block(5);
+    helper.swap(&user, &offer_asset, None).unwrap();
+
+    // A second swap within the same block pushes the price past the limit
+    let err = helper.swap(&user, &offer_asset, None).unwrap_err();
+    assert!(matches!(
+        err.downcast().unwrap(),
+        ContractError::PriceDeviationExceeded { max_deviation, .. } if max_deviation == Decimal::permille(3)
+    ));
+
+    // The next block opens at the new price
+    helper.app.next_block(5);
+    helper.swap(&user, &offer_asset, None).unwrap();
+
+    // An imbalanced deposit moves the price as well
+    helper.app.next_block(5);
+    let err = helper
+        .provide_liquidity(
+            &owner,
+            &[helper.assets[&test_coins[0]].with_balance(1_000_000_000000u128)],
+        )
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast().unwrap(),
+        ContractError::PriceDeviationExceeded { max_deviation, .. } if max_deviation == Decimal::permille(3)
+    ));
+
+    helper
+        .update_config(&owner, &StablePoolUpdateParams::DisablePriceGuard)
+        .unwrap();
+    // Same block, but the guard is disabled
+    helper
+        .swap(&user, &offer_asset, Some(Decimal::percent(5)))
+        .unwrap();
+}
+
+#[test]
+fn check_trader_allowlist() {
+    let test_coins = vec![TestCoin::native("uusdc"), TestCoin::native("uusdt")];
+    let mut helper = Helper::new(test_coins.clone(), common_stable_params(100)).unwrap();
+
+    let owner = helper.owner.clone();
+    let assets = vec![
+        helper.assets[&test_coins[0]].with_balance(100_000_000000u128),
+        helper.assets[&test_coins[1]].with_balance(100_000_000000u128),
+    ];
+    helper.provide_liquidity(&owner, &assets).unwrap();
+
+    let alice = helper.app.api().addr_make("alice");
+    let bob = helper.app.api().addr_make("bob");
+    let offer_asset = helper.assets[&test_coins[0]].with_balance(1_000000u128);
+    let provide_assets = vec![
+        helper.assets[&test_coins[0]].with_balance(1_000000u128),
+        helper.assets[&test_coins[1]].with_balance(1_000000u128),
+    ];
+    for user in [&alice, &bob] {
+        helper.give_me_money(&[offer_asset.clone(), offer_asset.clone()], user);
+        helper.give_me_money(&provide_assets, user);
+    }
+
+    let err = helper
+        .update_config(
+            &owner,
+            &StablePoolUpdateParams::SetTraderAllowlist(TraderAllowlist::Addresses(vec![
+                alice.clone(),
+                alice.clone(),
+            ])),
+        )
+        .unwrap_err();
+    assert_eq!(
+        ContractError::InvalidTraderAllowlist {},
+        err.downcast().unwrap()
+    );
+
+    helper
+        .update_config(
+            &owner,
+            &StablePoolUpdateParams::SetTraderAllowlist(TraderAllowlist::Addresses(vec![
+                alice.clone()
+            ])),
+        )
+        .unwrap();
+    let (_, params) = helper.query_config_params().unwrap();
+    assert_eq!(
+        params.trader_allowlist,
+        Some(TraderAllowlist::Addresses(vec![alice.clone()]))
+    );
+
+    helper.swap(&alice, &offer_asset, None).unwrap();
+    let err = helper.swap(&bob, &offer_asset, None).unwrap_err();
+    assert_eq!(
+        ContractError::TraderNotAllowed { addr: bob.clone() },
+        err.downcast().unwrap()
+    );
+    let err = helper.provide_liquidity(&bob, &provide_assets).unwrap_err();
+    assert_eq!(
+        ContractError::TraderNotAllowed { addr: bob.clone() },
+        err.downcast().unwrap()
+    );
+    helper.provide_liquidity(&alice, &provide_assets).unwrap();
+
+    helper
+        .update_config(&owner, &StablePoolUpdateParams::RemoveTraderAllowlist)
+        .unwrap();
+    let (_, params) = helper.query_config_params().unwrap();
+    assert_eq!(params.trader_allowlist, None);
+    helper.swap(&bob, &offer_asset, None).unwrap();
+}
+
+#[test]
+fn check_pool_owner() {
+    let test_coins = vec![TestCoin::native("uusdc"), TestCoin::native("uusdt")];
+    let api = cosmwasm_std::testing::MockApi::default();
+    let pool_owner = api.addr_make("pool_owner");
+    let params = StablePoolParams {
+        amp: 100,
+        owner: Some(pool_owner.to_string()),
+        rate_sources: vec![],
+    };
+    let mut helper = Helper::new(test_coins, params).unwrap();
+
+    let (config, _) = helper.query_config_params().unwrap();
+    assert_eq!(config.owner, pool_owner);
+
+    // The factory owner is not able to update the config anymore
+    let owner = helper.owner.clone();
+    let err = helper
+        .update_config(&owner, &StablePoolUpdateParams::StopChangingAmp {})
+        .unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+
+    helper
+        .update_config(&pool_owner, &StablePoolUpdateParams::StopChangingAmp {})
+        .unwrap();
+}
+
+#[test]
+fn check_asset_balances_tracking() {
+    let test_coins = vec![TestCoin::native("uusdc"), TestCoin::cw20("USDT")];
+    let mut helper = Helper::new(test_coins.clone(), common_stable_params(100)).unwrap();
+    let owner = helper.owner.clone();
+    let asset_infos = test_coins
+        .iter()
+        .map(|coin| helper.assets[coin].clone())
+        .collect_vec();
+
+    let query_balances_at = |helper: &Helper, height: u64| {
+        asset_infos
+            .iter()
+            .map(|info| helper.query_asset_balance_at(info, height).unwrap())
+            .collect_vec()
+    };
+    let query_pool_balances = |helper: &Helper| {
+        helper
+            .query_pool()
+            .unwrap()
+            .assets
+            .into_iter()
+            .map(|asset| Some(asset.amount))
+            .collect_vec()
+    };
+
+    let provide_height = helper.app.block_info().height;
+    let assets = asset_infos
+        .iter()
+        .map(|info| info.with_balance(100_000_000000u128))
+        .collect_vec();
+    helper.provide_liquidity(&owner, &assets).unwrap();
+    assert_eq!(query_balances_at(&helper, provide_height), vec![None, None]);
+    helper.app.next_block(1000);
+    assert_eq!(
+        query_balances_at(&helper, provide_height + 1),
+        vec![Some(Uint128::new(100_000_000000)); 2]
+    );
+
+    let swap_height = helper.app.block_info().height;
+    let offer_asset = asset_infos[1].with_balance(1_000_000000u128);
+    helper.give_me_money(std::slice::from_ref(&offer_asset), &owner);
+    helper.swap(&owner, &offer_asset, None).unwrap();
+    let balances_after_swap = query_pool_balances(&helper);
+    helper.app.next_block(1000);
+    assert_eq!(
+        query_balances_at(&helper, swap_height + 1),
+        balances_after_swap
+    );
+
+    let withdraw_height = helper.app.block_info().height;
+    helper.withdraw_liquidity(&owner, 10_000_000000).unwrap();
+    let balances_after_withdraw = query_pool_balances(&helper);
+    helper.app.next_block(1000);
+    assert_eq!(
+        query_balances_at(&helper, withdraw_height + 1),
+        balances_after_withdraw
+    );
+    assert_eq!(
+        query_balances_at(&helper, swap_height + 1),
+        balances_after_swap
+    );
+}
+
+#[test]
+fn check_skim_and_sync() {
+    let test_coins = vec![TestCoin::native("uusdc"), TestCoin::cw20("USDT")];
+    let mut helper = Helper::new(test_coins.clone(), common_stable_params(100)).unwrap();
+    let owner = helper.owner.clone();
+    let asset_infos = test_coins
+        .iter()
+        .map(|coin| helper.assets[coin].clone())
+        .collect_vec();
+    let query_pool_balances = |helper: &Helper| {
+        helper
+            .query_pool()
+            .unwrap()
+            .assets
+            .into_iter()
+            .map(|asset| asset.amount.u128())
+            .collect_vec()
+    };
+
+    let assets = asset_infos
+        .iter()
+        .map(|info| info.with_balance(100_000_000000u128))
+        .collect_vec();
+    helper.provide_liquidity(&owner, &assets).unwrap();
+
+    // Direct transfers to the pair are not accounted in the pool reserves
+    let donations = asset_infos
+        .iter()
+        .map(|info| info.with_balance(1_000000u128))
+        .collect_vec();
+    helper.give_me_money(&donations, &helper.pair_addr.clone());
+    assert_eq!(
+        query_pool_balances(&helper),
+        vec![100_000_000000, 100_000_000000]
+    );
+
+    let skimmer = helper.app.api().addr_make("skimmer");
+    helper.skim(&owner, Some(skimmer.to_string())).unwrap();
+    for coin in &test_coins {
+        assert_eq!(helper.coin_balance(coin, &skimmer), 1_000000);
+    }
+    assert_eq!(
+        query_pool_balances(&helper),
+        vec![100_000_000000, 100_000_000000]
+    );
+
+    // Nothing left to skim
+    helper.skim(&owner, Some(skimmer.to_string())).unwrap();
+    for coin in &test_coins {
+        assert_eq!(helper.coin_balance(coin, &skimmer), 1_000000);
+    }
+
+    helper.give_me_money(&donations, &helper.pair_addr.clone());
+    helper.sync(&owner).unwrap();
+    assert_eq!(
+        query_pool_balances(&helper),
+        vec![100_001_000000, 100_001_000000]
+    );
+}
+
+#[test]
+fn check_observe() {
+    let test_coins = vec![TestCoin::native("uusdc"), TestCoin::cw20("USDT")];
+    let mut helper = Helper::new(test_coins.clone(), common_stable_params(100)).unwrap();
+    let owner = helper.owner.clone();
+
+    let assets = test_coins
+        .iter()
+        .map(|coin| helper.assets[coin].with_balance(100_000_000000u128))
+        .collect_vec();
+    helper.provide_liquidity(&owner, &assets).unwrap();
+    let prices_after_provide = helper.query_prices().unwrap().cumulative_prices;
+
+    helper.app.next_block(1000);
+
+    let offer_asset = helper.assets[&test_coins[0]].with_balance(10_000_000000u128);
+    helper.give_me_money(std::slice::from_ref(&offer_asset), &owner);
+    helper.swap(&owner, &offer_asset, None).unwrap();
+    let prices_after_swap = helper.query_prices().unwrap().cumulative_prices;
+
+    helper.app.next_block(1000);
+
+    let observations = helper.query_observe(vec![0, 1000, 2000]).unwrap();
+    assert_eq!(
+        observations[0].cumulative_prices,
+        helper.query_prices().unwrap().cumulative_prices
+    );
+    assert_eq!(observations[1].cumulative_prices, prices_after_swap);
+    assert_eq!(observations[2].cumulative_prices, prices_after_provide);
+
+    let err = helper.query_observe(vec![2001]).unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("is older than the oldest recorded one"));
+}
+
+#[test]
+fn check_wrong_rate_sources() {
+    let test_coins = vec![TestCoin::native("uluna"), TestCoin::native("uusd")];
+    let rate_source = |denom: &str| AssetRateSource {
+        asset_info: native_asset_info(denom.to_string()),
+        source: RateSource::Provider {
+            contract_addr: cosmwasm_std::testing::MockApi::default()
+                .addr_make("provider")
+                .to_string(),
+        },
+    };
+
+    let mut params = common_stable_params(100);
+    params.rate_sources = vec![rate_source("uusdc")];
+    let err = Helper::new(test_coins.clone(), params).unwrap_err();
+    assert_eq!(
+        ContractError::InvalidAsset("uusdc".to_string()),
+        err.downcast().unwrap()
+    );
+
+    let mut params = common_stable_params(100);
+    params.rate_sources = vec![rate_source("uluna"), rate_source("uluna")];
+    let err = Helper::new(test_coins, params).unwrap_err();
+    assert_eq!(ContractError::DoublingAssets {}, err.downcast().unwrap());
+}
+
+#[test]
+fn check_rate_provider() {
+    // LUNA is a liquid staking token redeemable for 1.1 USD
+    let test_coins = vec![TestCoin::native("uluna"), TestCoin::native("uusd")];
+    let mut helper = Helper::new_with_rates(
+        test_coins.clone(),
+        common_stable_params(100),
+        vec![(
+            test_coins[0].clone(),
+            MockRateSource::Provider,
+            Decimal256::percent(110),
+        )],
+    )
+    .unwrap();
+
+    let owner = helper.owner.clone();
+    let assets = vec![
+        helper.assets[&test_coins[0]].with_balance(1_000_000_000000u128),
+        helper.assets[&test_coins[1]].with_balance(1_100_000_000000u128),
+    ];
+    helper.provide_liquidity(&owner, &assets).unwrap();
+    // The invariant is computed from the rate-adjusted balances
+    assert_eq!(helper.query_d().unwrap().u128(), 2_200_000_000000);
+
+    let (_, params) = helper.query_config_params().unwrap();
+    assert_eq!(
+        params.rates,
+        vec![Decimal256::percent(110), Decimal256::one()]
+    );
+    assert_eq!(params.rate_sources.len(), 1);
+
+    // The pool is balanced at the redemption rate so the swap has almost no spread
+    let offer_asset = helper.assets[&test_coins[0]].with_balance(100_000000u128);
+    let sim = helper.simulate_swap(&offer_asset, None).unwrap();
+    assert!(sim.commission_amount.u128().abs_diff(55000) <= 1);
+    assert!(sim.spread_amount.u128() < 200, "{}", sim.spread_amount);
+    assert!(
+        sim.return_amount.u128().abs_diff(109_945000) < 200,
+        "{}",
+        sim.return_amount
+    );
+
+    let ask_asset = helper.assets[&test_coins[1]].with_balance(sim.return_amount);
+    let reverse = helper.simulate_reverse_swap(&ask_asset, None).unwrap();
+    assert!(
+        reverse.offer_amount.u128().abs_diff(100_000000) < 200,
+        "{}",
+        reverse.offer_amount
+    );
+
+    let user = helper.app.api().addr_make("user");
+    helper.give_me_money(std::slice::from_ref(&offer_asset), &user);
+    helper.swap(&user, &offer_asset, None).unwrap();
+    assert_eq!(
+        helper.coin_balance(&test_coins[1], &user),
+        sim.return_amount.u128()
+    );
+
+    // When the rate grows the swap results follow it. The pool now holds more LUNA by value
+    // so buying LUNA is slightly cheaper than the redemption rate
+    helper
+        .set_rate(&test_coins[0], Decimal256::percent(120))
+        .unwrap();
+    let sim = helper.simulate_swap(&offer_asset, None).unwrap();
+    assert!(
+        sim.return_amount.u128() > 119_000000 && sim.return_amount.u128() < 120_000000,
+        "{}",
+        sim.return_amount
+    );
+    let offer_asset = helper.assets[&test_coins[1]].with_balance(120_000000u128);
+    let sim = helper.simulate_swap(&offer_asset, None).unwrap();
+    assert!(
+        sim.return_amount.u128() > 100_000000 && sim.return_amount.u128() < 100_100000,
+        "{}",
+        sim.return_amount
+    );
+}
+
+#[test]
+fn check_oracle_rate_source() {
+    let test_coins = vec![TestCoin::native("uluna"), TestCoin::native("uusd")];
+    let mut helper = Helper::new_with_rates(
+        test_coins.clone(),
+        common_stable_params(100),
+        vec![(
+            test_coins[0].clone(),
+            MockRateSource::Oracle,
+            Decimal256::percent(125),
+        )],
+    )
+    .unwrap();
+
+    let owner = helper.owner.clone();
+    let assets = vec![
+        helper.assets[&test_coins[0]].with_balance(1_000_000_000000u128),
+        helper.assets[&test_coins[1]].with_balance(1_250_000_000000u128),
+    ];
+    helper.provide_liquidity(&owner, &assets).unwrap();
+
+    let (_, params) = helper.query_config_params().unwrap();
+    assert_eq!(
+        params.rates,
+        vec![Decimal256::percent(125), Decimal256::one()]
+    );
+
+    let user = helper.app.api().addr_make("user");
+    let offer_asset = helper.assets[&test_coins[1]].with_balance(125_000000u128);
+    helper.give_me_money(std::slice::from_ref(&offer_asset), &user);
+    let sim = helper.simulate_swap(&offer_asset, None).unwrap();
+    helper.swap(&user, &offer_asset, None).unwrap();
+    let received = helper.coin_balance(&test_coins[0], &user);
+    assert_eq!(received, sim.return_amount.u128());
+    assert!(received.abs_diff(99_950000) < 200, "{received}");
+}
+
+#[test]
+fn check_pause() {
+    let test_coins = vec![TestCoin::native("uusdc"), TestCoin::cw20("USDT")];
+    let mut helper = Helper::new(test_coins.clone(), common_stable_params(100)).unwrap();
+
+    let owner = helper.owner.clone();
+    let assets = vec![
+        helper.assets[&test_coins[0]].with_balance(100_000_000000u128),
+        helper.assets[&test_coins[1]].with_balance(100_000_000000u128),
+    ];
+    helper.provide_liquidity(&owner, &assets).unwrap();
+
+    let user = helper.app.api().addr_make("user");
+    let offer_asset = helper.assets[&test_coins[0]].with_balance(100_000000u128);
+    helper.give_me_money(&[offer_asset.clone(), offer_asset.clone()], &user);
+
+    let pause = |helper: &mut Helper, scope: PauseScope| {
+        helper
+            .app
+            .execute_contract(
+                owner.clone(),
+                helper.factory.clone(),
+                &FactoryExecuteMsg::Pause {
+                    pair: Some(helper.pair_addr.to_string()),
+                    scope,
+                },
+                &[],
+            )
+            .unwrap();
+    };
+
+    pause(&mut helper, PauseScope::Provide);
+    let err = helper.provide_liquidity(&owner, &assets).unwrap_err();
+    assert_eq!(
+        ContractError::Paused(PauseScope::Provide),
+        err.downcast().unwrap()
+    );
+    helper.swap(&user, &offer_asset, None).unwrap();
+
+    pause(&mut helper, PauseScope::Swap);
+    let err = helper.swap(&user, &offer_asset, None).unwrap_err();
+    assert_eq!(
+        ContractError::Paused(PauseScope::Swap),
+        err.downcast().unwrap()
+    );
+
+    // Withdrawals stay open during a pause
+    helper.withdraw_liquidity(&owner, 1_000000).unwrap();
+
+    helper
+        .app
+        .execute_contract(
+            owner.clone(),
+            helper.factory.clone(),
+            &FactoryExecuteMsg::Unpause {
+                pair: Some(helper.pair_addr.to_string()),
+            },
+            &[],
+        )
+        .unwrap();
+    helper.provide_liquidity(&owner, &assets).unwrap();
+}
@@ -0,0 +1,116 @@
+use cosmwasm_std::{
+    CheckedMultiplyRatioError, ConversionOverflowError, OverflowError, StdError, Uint128,
+};
+use cw_utils::{ParseReplyError, PaymentError};
+use thiserror::Error;
+
+use astroport::asset::MINIMUM_LIQUIDITY_AMOUNT;
+use astroport::pair::MAX_FEE_SHARE_BPS;
+
+use astroport::factory::PauseScope;
+
+use crate::math::MIN_WEIGHT;
+
+/// This enum describes weighted pair contract errors
+#[derive(Error, Debug, PartialEq)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("{0}")]
+    ConversionOverflowError(#[from] ConversionOverflowError),
+
+    #[error("{0}")]
+    CheckedMultiplyRatioError(#[from] CheckedMultiplyRatioError),
+
+    #[error("{0}")]
+    ParseReplyError(#[from] ParseReplyError),
+
+    #[error("{0}")]
+    OverflowError(#[from] OverflowError),
+
+    #[error("{0}")]
+    PaymentError(#[from] PaymentError),
+
+    #[error("Unauthorized")]
+    Unauthorized {},
+
+    #[error("Transaction deadline exceeded")]
+    DeadlineExceeded {},
+
+    #[error("CW20 tokens can be swapped via Cw20::Send message only")]
+    Cw20DirectSwap {},
+
+    #[error("Operation non supported")]
+    NonSupported {},
+
+    #[error("You need to provide init params")]
+    InitParamsNotFound {},
+
+    #[error("Invalid number of assets. This pair supports from {min} to {max} assets")]
+    InvalidNumberOfAssets { min: usize, max: usize },
+
+    #[error("Doubling assets in asset infos")]
+    DoublingAssets {},
+
+    #[error("The asset {0} does not belong to the pair")]
+    InvalidAsset(String),
+
+    #[error("Ask or offer asset is missed")]
+    VariableAssetMissed {},
+
+    #[error("Source and target assets are the same")]
+    SameAssets {},
+
+    #[error("Event of zero transfer")]
+    InvalidZeroAmount {},
+
+    #[error("Initial provide must contain all pool assets")]
+    InitialProvideAllAssets {},
+
+    #[error("Initial liquidity must be more than {}", MINIMUM_LIQUIDITY_AMOUNT)]
+    MinimumLiquidityAmountError {},
+
+    #[error("Weights must be specified for every pool asset and each normalized weight must be at least {}", MIN_WEIGHT)]
+    InvalidWeights {},
+
+    #[error("{0:?} is paused")]
+    Paused(PauseScope),
+
+    #[error("Operation exceeds max spread limit")]
+    MaxSpreadAssertion {},
+
+    #[error("Provided spread amount exceeds allowed limit")]
+    AllowedSpreadAssertion {},
+
+    #[error("Slippage is more than expected: received {0}, expected {1} LP tokens")]
+    ProvideSlippageViolation(Uint128, Uint128),
+
+    #[error("Received {received} {asset_name} but expected {expected}")]
+    WithdrawSlippageViolation {
+        asset_name: String,
+        received: Uint128,
+        expected: Uint128,
+    },
+
+    #[error("Wrong asset length: expected {expected}, actual {actual}")]
+    WrongAssetLength { expected: usize, actual: usize },
+
+    #[error("Asset mismatch between the requested and the stored asset in contract")]
+    AssetMismatch {},
+
+    #[error("Failed to migrate the contract")]
+    MigrationError {},
+
+    #[error("Failed to parse or process reply message")]
+    FailedToParseReply {},
+
+    #[error("Incentives address is not set in factory. Cannot auto-stake")]
+    AutoStakeError {},
+
+    #[error(
+        "Fee share is 0 or exceeds maximum allowed value of {} bps",
+        MAX_FEE_SHARE_BPS
+    )]
+    FeeShareOutOfBounds {},
+}
@@ -0,0 +1,341 @@
+use cosmwasm_std::{Decimal256, StdError, StdResult, Uint128};
+
+/// The maximum amount of assets a weighted pool can hold
+pub const MAX_ASSETS: usize = 8;
+/// The minimum amount of assets a weighted pool can hold
+pub const MIN_ASSETS: usize = 2;
+
+/// The minimum normalized weight of a pool asset
+pub const MIN_WEIGHT: Decimal256 = Decimal256::percent(1);
+/// The maximum share of the pool balance which can be swapped in or out in a single swap
+pub const MAX_SWAP_RATIO: Decimal256 = Decimal256::percent(30);
+/// The minimum ratio between the invariant after and before a single asset withdrawal
+pub const MIN_INVARIANT_RATIO: Decimal256 = Decimal256::percent(70);
+
+/// The maximum number of binary digits of a fractional exponent processed by [`pow`].
+const POW_ITERATIONS: u8 = 60;
+/// The upper bound of the relative error of [`pow`] within the allowed swap and withdrawal ratios.
+const MAX_POW_RELATIVE_ERROR: Decimal256 = Decimal256::raw(10000);
+
+/// Raises **base** to the power of **exp**.
+/// The integer part of the exponent is applied directly while the fractional part is processed
+/// bit by bit using successive square roots of the base.
+pub fn pow(base: Decimal256, exp: Decimal256) -> StdResult<Decimal256> {
+    let int_exp = Uint128::try_from(exp.to_uint_floor())?;
+    let int_exp = u32::try_from(int_exp.u128())
+        .map_err(|_| StdError::generic_err(format!("Exponent {exp} is too large")))?;
+    let mut frac_exp = exp - exp.floor();
+
+    let mut result = base.checked_pow(int_exp)?;
+    let mut root = base;
+    for _ in 0..POW_ITERATIONS {
+        if frac_exp.is_zero() {
+            break;
+        }
+
+        root = root.sqrt();
+        frac_exp += frac_exp;
+        if frac_exp >= Decimal256::one() {
+            result = result.checked_mul(root)?;
+            frac_exp -= Decimal256::one();
+        }
+    }
+
+    Ok(result)
+}
+
+/// Returns [`pow`] rounded up by its maximum error.
+fn pow_up(base: Decimal256, exp: Decimal256) -> StdResult<Decimal256> {
+    let raw = pow(base, exp)?;
+
+    Ok(raw + raw * MAX_POW_RELATIVE_ERROR + Decimal256::raw(1))
+}
+
+/// Returns [`pow`] rounded down by its maximum error.
+fn pow_down(base: Decimal256, exp: Decimal256) -> StdResult<Decimal256> {
+    let raw = pow(base, exp)?;
+
+    Ok(raw.saturating_sub(raw * MAX_POW_RELATIVE_ERROR + Decimal256::raw(1)))
+}
+
+/// Returns `numerator / denominator` mapping the division error into [`StdError`].
+fn div(numerator: Decimal256, denominator: Decimal256) -> StdResult<Decimal256> {
+    numerator
+        .checked_div(denominator)
+        .map_err(|err| StdError::generic_err(err.to_string()))
+}
+
+/// Computes the weighted pool invariant.
+///
+/// * **Equation**
+///
+/// V = prod(x_i ** w_i)
+///
+/// * **pools** are the pool balances normalized to [`Decimal256`] precision.
+///
+/// * **weights** are the normalized asset weights.
+pub fn compute_invariant(pools: &[Decimal256], weights: &[Decimal256]) -> StdResult<Decimal256> {
+    pools
+        .iter()
+        .zip(weights)
+        .try_fold(Decimal256::one(), |acc, (pool, weight)| {
+            Ok(acc.checked_mul(pow_down(*pool, *weight)?)?)
+        })
+}
+
+/// Returns the spot price of one unit of the **from** asset quoted in the **to** asset without fees.
+///
+/// * **Equation**
+///
+/// p = (x_to / w_to) / (x_from / w_from)
+pub fn spot_price(
+    pools: &[Decimal256],
+    weights: &[Decimal256],
+    from: usize,
+    to: usize,
+) -> StdResult<Decimal256> {
+    div(pools[to] * weights[from], pools[from] * weights[to])
+}
+
+/// Returns the amount of ask assets received for the given amount of offer assets before fees.
+///
+/// * **Equation**
+///
+/// a_out = x_out * (1 - (x_in / (x_in + a_in)) ** (w_in / w_out))
+pub fn calc_out_given_in(
+    offer_pool: Decimal256,
+    offer_weight: Decimal256,
+    ask_pool: Decimal256,
+    ask_weight: Decimal256,
+    offer_amount: Decimal256,
+) -> StdResult<Decimal256> {
+    if offer_amount > offer_pool * MAX_SWAP_RATIO {
+        return Err(StdError::generic_err(format!(
+            "Offer amount exceeds {MAX_SWAP_RATIO} of the pool balance"
+        )));
+    }
+
+    // Round the base up to make sure rounding errors go in favor of the pool
+    let base = div(offer_pool, offer_pool.checked_add(offer_amount)?)? + Decimal256::raw(1);
+    let power = pow_up(base, div(offer_weight, ask_weight)?)?;
+
+    Ok(ask_pool * Decimal256::one().saturating_sub(power))
+}
+
+/// Returns the amount of offer assets required to receive the given amount of ask assets before fees.
+///
+/// * **Equation**
+///
+/// a_in = x_in * ((x_out / (x_out - a_out)) ** (w_out / w_in) - 1)
+pub fn calc_in_given_out(
+    offer_pool: Decimal256,
+    offer_weight: Decimal256,
+    ask_pool: Decimal256,
+    ask_weight: Decimal256,
+    ask_amount: Decimal256,
+) -> StdResult<Decimal256> {
+    if ask_amount > ask_pool * MAX_SWAP_RATIO {
+        return Err(StdError::generic_err(format!(
+            "Ask amount exceeds {MAX_SWAP_RATIO} of the pool balance"
+        )));
+    }
+
+    let base = div(ask_pool, ask_pool - ask_amount)? + Decimal256::raw(1);
+    let power = pow_up(base, div(ask_weight, offer_weight)?)?;
+
+    Ok(offer_pool * (power - Decimal256::one()))
+}
+
+/// Returns the ratio between the invariant after and before the deposit.
+/// The part of a deposit exceeding the proportional one is charged a swap fee.
+///
+/// * **pools** are the pool balances normalized to [`Decimal256`] precision.
+///
+/// * **weights** are the normalized asset weights.
+///
+/// * **deposits** are the deposit amounts ordered the same way as the pool assets.
+///
+/// * **fee_rate** is the fee charged from the imbalanced part of the deposit.
+pub fn calc_invariant_ratio_given_deposits(
+    pools: &[Decimal256],
+    weights: &[Decimal256],
+    deposits: &[Decimal256],
+    fee_rate: Decimal256,
+) -> StdResult<Decimal256> {
+    let balance_ratios = pools
+        .iter()
+        .zip(deposits)
+        .map(|(pool, deposit)| div(pool.checked_add(*deposit)?, *pool))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    // The invariant ratio as if the deposit were proportional
+    let weighted_ratio = balance_ratios
+        .iter()
+        .zip(weights)
+        .fold(Decimal256::zero(), |acc, (ratio, weight)| {
+            acc + *ratio * weight
+        });
+
+    let mut invariant_ratio = Decimal256::one();
+    for (((pool, weight), deposit), balance_ratio) in
+        pools.iter().zip(weights).zip(deposits).zip(&balance_ratios)
+    {
+        let amount_without_fee = if *balance_ratio > weighted_ratio {
+            let non_taxable = *pool * (weighted_ratio - Decimal256::one());
+            let taxable = *deposit - non_taxable;
+            non_taxable + taxable * (Decimal256::one() - fee_rate)
+        } else {
+            *deposit
+        };
+
+        let ratio = div(pool.checked_add(amount_without_fee)?, *pool)?;
+        invariant_ratio = invariant_ratio.checked_mul(pow_down(ratio, *weight)?)?;
+    }
+
+    Ok(invariant_ratio)
+}
+
+/// Returns the amount of the asset received for burning LP tokens as well as the fee charged
+/// in the form of (return amount, fee amount). Only the part of the withdrawal exceeding
+/// the proportional one is charged a swap fee.
+///
+/// * **pool** is the asset balance normalized to [`Decimal256`] precision.
+///
+/// * **weight** is the normalized asset weight.
+///
+/// * **invariant_ratio** is the ratio between the LP token supply after and before the withdrawal.
+///
+/// * **fee_rate** is the fee charged from the imbalanced part of the withdrawal.
+pub fn calc_out_given_invariant_ratio(
+    pool: Decimal256,
+    weight: Decimal256,
+    invariant_ratio: Decimal256,
+    fee_rate: Decimal256,
+) -> StdResult<(Decimal256, Decimal256)> {
+    if invariant_ratio < MIN_INVARIANT_RATIO {
+        return Err(StdError::generic_err(format!(
+            "Withdrawal in a single asset can't decrease the pool invariant below {MIN_INVARIANT_RATIO} of its value"
+        )));
+    }
+
+    let balance_ratio = pow_up(invariant_ratio, div(Decimal256::one(), weight)?)?;
+    let amount_without_fee = pool * Decimal256::one().saturating_sub(balance_ratio);
+
+    let taxable = amount_without_fee * (Decimal256::one() - weight);
+    let fee_amount = taxable * fee_rate;
+
+    Ok((amount_without_fee - fee_amount, fee_amount))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    fn dec(val: &str) -> Decimal256 {
+        Decimal256::from_str(val).unwrap()
+    }
+
+    fn assert_approx(actual: Decimal256, expected: Decimal256) {
+        let diff = actual.abs_diff(expected);
+        assert!(
+            diff <= expected * Decimal256::raw(1_000_000) + Decimal256::raw(100),
+            "{actual} != {expected}"
+        );
+    }
+
+    #[test]
+    fn test_pow() {
+        assert_eq!(pow(dec("2"), dec("10")).unwrap(), dec("1024"));
+        assert_approx(pow(dec("16"), dec("0.25")).unwrap(), dec("2"));
+        assert_approx(
+            pow(dec("8"), dec("1.5")).unwrap(),
+            dec("22.627416997969520780"),
+        );
+        assert_approx(
+            pow(dec("0.9"), dec("0.25")).unwrap(),
+            dec("0.974003746425296764"),
+        );
+        assert_approx(
+            pow(dec("0.5"), dec("3.333333333333333333")).unwrap(),
+            dec("0.099212565748012467"),
+        );
+        assert_eq!(
+            pow(dec("1.5"), Decimal256::zero()).unwrap(),
+            Decimal256::one()
+        );
+    }
+
+    #[test]
+    fn test_swap_matches_constant_product() {
+        // Equal weights work as x * y = k
+        let weight = dec("0.5");
+        let out = calc_out_given_in(dec("1000"), weight, dec("2000"), weight, dec("100")).unwrap();
+        assert_approx(out, dec("2000") - dec("2000000") / dec("1100"));
+
+        let offer = calc_in_given_out(dec("1000"), weight, dec("2000"), weight, out).unwrap();
+        assert_approx(offer, dec("100"));
+        assert!(offer >= dec("100"));
+
+        // 80/20 pool prices the heavy asset 4 times higher for the same balances
+        let heavy = dec("0.8");
+        let light = dec("0.2");
+        let price = spot_price(&[dec("1000"), dec("1000")], &[heavy, light], 0, 1).unwrap();
+        assert_eq!(price, dec("4"));
+        let out = calc_out_given_in(dec("1000"), heavy, dec("1000"), light, dec("1")).unwrap();
+        assert!(out < dec("4") && out > dec("3.98"));
+
+        let err =
+            calc_out_given_in(dec("1000"), heavy, dec("1000"), light, dec("301")).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Generic error: Offer amount exceeds 0.3 of the pool balance"
+        );
+    }
+
+    #[test]
+    fn test_invariant_ratio() {
+        let pools = [dec("1000"), dec("250")];
+        let weights = [dec("0.8"), dec("0.2")];
+
+        // Proportional deposits are not charged
+        let ratio = calc_invariant_ratio_given_deposits(
+            &pools,
+            &weights,
+            &[dec("100"), dec("25")],
+            dec("0.003"),
+        )
+        .unwrap();
+        assert_approx(ratio, dec("1.1"));
+
+        // A single-sided deposit is charged for the imbalanced part
+        let with_fee = calc_invariant_ratio_given_deposits(
+            &pools,
+            &weights,
+            &[dec("100"), Decimal256::zero()],
+            dec("0.003"),
+        )
+        .unwrap();
+        let no_fee = calc_invariant_ratio_given_deposits(
+            &pools,
+            &weights,
+            &[dec("100"), Decimal256::zero()],
+            Decimal256::zero(),
+        )
+        .unwrap();
+        assert_approx(no_fee, pow(dec("1.1"), dec("0.8")).unwrap());
+        assert!(with_fee < no_fee);
+
+        // Withdrawing the minted share in the same asset returns less than deposited
+        let (amount, fee) = calc_out_given_invariant_ratio(
+            dec("1100"),
+            dec("0.8"),
+            div(Decimal256::one(), with_fee).unwrap(),
+            dec("0.003"),
+        )
+        .unwrap();
+        assert!(amount < dec("99.886") && amount > dec("99.885"));
+        assert!(!fee.is_zero());
+    }
+}
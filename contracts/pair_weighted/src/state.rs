@@ -0,0 +1,71 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, Decimal256, DepsMut, StdResult, Storage, Uint128};
+use cw_storage_plus::{Deque, Item, Map, SnapshotMap, Strategy};
+
+use astroport::asset::{AssetInfo, PairInfo};
+use astroport::common::OwnershipProposal;
+use astroport::observation::Observation;
+use astroport::pair::FeeShareConfig;
+
+/// This structure stores the main weighted pair parameters.
+#[cw_serde]
+pub struct Config {
+    /// The contract owner. If not set, the factory owner is used
+    pub owner: Option<Addr>,
+    /// The pair information stored in a [`PairInfo`] struct
+    pub pair_info: PairInfo,
+    /// The factory contract address
+    pub factory_addr: Addr,
+    /// The last timestamp when the pair contract update the asset cumulative prices
+    pub block_time_last: u64,
+    /// Normalized asset weights in the same order as [`PairInfo::asset_infos`]
+    pub weights: Vec<Decimal256>,
+    /// The vector contains cumulative prices for each pair of assets in the pool
+    pub cumulative_prices: Vec<(AssetInfo, AssetInfo, Uint128)>,
+    /// The config for swap fee sharing
+    pub fee_share: Option<FeeShareConfig>,
+    /// Whether LP shares are issued as a token factory denom instead of a CW20 token
+    pub native_lp_token: bool,
+}
+
+/// Stores the config struct at the given key
+pub const CONFIG: Item<Config> = Item::new("config");
+
+/// Stores the pool reserves in the same order as [`PairInfo::asset_infos`]
+pub const RESERVES: Item<Vec<Uint128>> = Item::new("reserves");
+
+/// Stores a bounded ring buffer of cumulative price observations
+pub const OBSERVATIONS: Deque<Observation> = Deque::new("observations");
+
+/// Stores the pool balance of each asset at every block height it changed
+pub const BALANCES: SnapshotMap<&AssetInfo, Uint128> = SnapshotMap::new(
+    "balances",
+    "balances_check",
+    "balances_change",
+    Strategy::EveryBlock,
+);
+
+/// Stores the latest contract ownership transfer proposal
+pub const OWNERSHIP_PROPOSAL: Item<OwnershipProposal> = Item::new("ownership_proposal");
+
+/// Stores asset precisions keyed by the asset denom or contract address
+const PRECISIONS: Map<String, u8> = Map::new("precisions");
+
+/// Queries and stores the precision of every pool asset.
+pub fn store_precisions(
+    deps: DepsMut,
+    asset_infos: &[AssetInfo],
+    factory_addr: &Addr,
+) -> StdResult<()> {
+    for asset_info in asset_infos {
+        let precision = asset_info.decimals(&deps.querier, factory_addr)?;
+        PRECISIONS.save(deps.storage, asset_info.to_string(), &precision)?;
+    }
+
+    Ok(())
+}
+
+/// Loads the precision of the given pool asset.
+pub fn get_precision(storage: &dyn Storage, asset_info: &AssetInfo) -> StdResult<u8> {
+    PRECISIONS.load(storage, asset_info.to_string())
+}
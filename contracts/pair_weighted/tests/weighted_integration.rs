@@ -0,0 +1,586 @@
+#![allow(clippy::inconsistent_digit_grouping)]
+
+use cosmwasm_std::{Decimal, Decimal256, StdError, Uint128};
+use itertools::Itertools;
+
+use astroport::asset::{native_asset_info, AssetInfoExt, MINIMUM_LIQUIDITY_AMOUNT};
+use astroport::factory::PauseScope;
+use astroport::pair::{WeightedPoolParams, WeightedPoolUpdateParams, MAX_FEE_SHARE_BPS};
+use astroport_pair_weighted::error::ContractError;
+use astroport_test::coins::TestCoin;
+
+use crate::helper::{weighted_params, AppExtension, Helper};
+
+mod helper;
+
+#[test]
+fn check_wrong_initialization() {
+    let err = Helper::new(vec![TestCoin::native("uusdc")], weighted_params(&[100])).unwrap_err();
+    assert_eq!(
+        ContractError::InvalidNumberOfAssets { min: 2, max: 8 },
+        err.downcast().unwrap(),
+    );
+
+    let too_many_coins = (0..9u8)
+        .map(|i| TestCoin::cw20(&format!("TOKEN{}", (b'A' + i) as char)))
+        .collect_vec();
+    let err = Helper::new(too_many_coins, weighted_params(&[1; 9])).unwrap_err();
+    assert_eq!(
+        ContractError::InvalidNumberOfAssets { min: 2, max: 8 },
+        err.downcast().unwrap(),
+    );
+
+    let test_coins = vec![TestCoin::native("uluna"), TestCoin::cw20("ASTRO")];
+
+    // Every asset must have a weight
+    let err = Helper::new(test_coins.clone(), weighted_params(&[50])).unwrap_err();
+    assert_eq!(ContractError::InvalidWeights {}, err.downcast().unwrap());
+
+    let err = Helper::new(test_coins.clone(), weighted_params(&[50, 0])).unwrap_err();
+    assert_eq!(ContractError::InvalidWeights {}, err.downcast().unwrap());
+
+    // The normalized weight is below 1%
+    let err = Helper::new(test_coins.clone(), weighted_params(&[995, 5])).unwrap_err();
+    assert_eq!(ContractError::InvalidWeights {}, err.downcast().unwrap());
+
+    // Weights are normalized so they don't have to sum up to 1
+    let helper = Helper::new(test_coins.clone(), weighted_params(&[4, 1])).unwrap();
+    let (_, params) = helper.query_config_params().unwrap();
+    assert_eq!(
+        params.weights,
+        vec![
+            (helper.assets[&test_coins[0]].clone(), Decimal::percent(80)),
+            (helper.assets[&test_coins[1]].clone(), Decimal::percent(20)),
+        ]
+    );
+
+    let max_coins = (0..8u8)
+        .map(|i| TestCoin::cw20(&format!("TOKEN{}", (b'A' + i) as char)))
+        .collect_vec();
+    Helper::new(max_coins, weighted_params(&[1; 8])).unwrap();
+}
+
+#[test]
+fn provide_and_withdraw() {
+    let test_coins = vec![TestCoin::native("uluna"), TestCoin::cw20("ASTRO")];
+    let mut helper = Helper::new(test_coins.clone(), weighted_params(&[80, 20])).unwrap();
+
+    let user1 = helper.app.api().addr_make("user1");
+
+    let random_coin = native_asset_info("random-coin".to_string()).with_balance(100u8);
+    let wrong_assets = vec![
+        helper.assets[&test_coins[0]].with_balance(100_000_000000u128),
+        random_coin.clone(),
+    ];
+    helper.give_me_money(&wrong_assets, &user1);
+    let err = helper.provide_liquidity(&user1, &wrong_assets).unwrap_err();
+    assert_eq!(
+        ContractError::InvalidAsset(random_coin.info.to_string()),
+        err.downcast().unwrap(),
+    );
+
+    // Initial provide must contain all pool assets
+    let assets = vec![helper.assets[&test_coins[0]].with_balance(100_000_000000u128)];
+    let err = helper.provide_liquidity(&user1, &assets).unwrap_err();
+    assert_eq!(
+        ContractError::InitialProvideAllAssets {},
+        err.downcast().unwrap(),
+    );
+
+    // The initial provide defines the price: 4 LUNA per ASTRO with 80/20 weights
+    let assets = vec![
+        helper.assets[&test_coins[0]].with_balance(400_000_000000u128),
+        helper.assets[&test_coins[1]].with_balance(25_000_000000u128),
+    ];
+    helper.give_me_money(&assets, &user1);
+    helper.provide_liquidity(&user1, &assets).unwrap();
+
+    // LP supply equals the pool invariant 400^0.8 * 25^0.2 = 229.739...
+    let invariant = helper.query_invariant().unwrap().u128();
+    assert!(invariant.abs_diff(229_739_670999) < 10, "{invariant}");
+    assert_eq!(
+        invariant - MINIMUM_LIQUIDITY_AMOUNT.u128(),
+        helper.token_balance(&helper.lp_token, &user1)
+    );
+
+    // The second proportional provide mints LP tokens proportionally
+    let user2 = helper.app.api().addr_make("user2");
+    let assets = vec![
+        helper.assets[&test_coins[0]].with_balance(40_000_000000u128),
+        helper.assets[&test_coins[1]].with_balance(2_500_000000u128),
+    ];
+    helper.give_me_money(&assets, &user2);
+    let simulated = helper.simulate_provide(&assets).unwrap().u128();
+
+    let err = helper
+        .provide_liquidity_full(
+            &user2,
+            &assets,
+            None,
+            None,
+            None,
+            Some((simulated + 1).into()),
+        )
+        .unwrap_err();
+    assert_eq!(
+        ContractError::ProvideSlippageViolation(simulated.into(), (simulated + 1).into()),
+        err.downcast().unwrap(),
+    );
+
+    helper.provide_liquidity(&user2, &assets).unwrap();
+    let lp_amount = helper.token_balance(&helper.lp_token, &user2);
+    assert_eq!(lp_amount, simulated);
+    assert!(
+        lp_amount.abs_diff(invariant / 10) < 100,
+        "{lp_amount} vs {}",
+        invariant / 10
+    );
+
+    // Proportional withdrawal returns the assets in the pool ratio
+    helper.withdraw_liquidity(&user2, lp_amount).unwrap();
+    assert_eq!(0, helper.token_balance(&helper.lp_token, &user2));
+    let luna = helper.coin_balance(&test_coins[0], &user2);
+    let astro = helper.coin_balance(&test_coins[1], &user2);
+    assert!(luna <= 40_000_000000 && luna > 39_999_990000, "{luna}");
+    assert!(astro <= 2_500_000000 && astro > 2_499_990000, "{astro}");
+}
+
+#[test]
+fn check_single_sided_provide() {
+    let test_coins = vec![TestCoin::native("uluna"), TestCoin::native("uusd")];
+    let mut helper = Helper::new(test_coins.clone(), weighted_params(&[80, 20])).unwrap();
+
+    let owner = helper.owner.clone();
+    let assets = vec![
+        helper.assets[&test_coins[0]].with_balance(1_000_000_000000u128),
+        helper.assets[&test_coins[1]].with_balance(1_000_000_000000u128),
+    ];
+    helper.provide_liquidity(&owner, &assets).unwrap();
+
+    let user1 = helper.app.api().addr_make("user1");
+    let single_sided = vec![helper.assets[&test_coins[1]].with_balance(10_000_000000u128)];
+    let simulated = helper.simulate_provide(&single_sided).unwrap();
+
+    helper.give_me_money(&single_sided, &user1);
+    helper.provide_liquidity(&user1, &single_sided).unwrap();
+    let received = helper.token_balance(&helper.lp_token, &user1);
+    assert_eq!(simulated.u128(), received);
+
+    // Withdrawing everything right away returns less than was deposited because
+    // the part of the deposit exceeding the pool weight is charged a swap fee.
+    // 1 LUNA is worth 4 USD in the 80/20 pool with equal balances.
+    helper.withdraw_liquidity(&user1, received).unwrap();
+    let luna = helper.coin_balance(&test_coins[0], &user1);
+    let usd = helper.coin_balance(&test_coins[1], &user1);
+    let value = 4 * luna + usd;
+    assert!(value < 10_000_000000, "{value}");
+    assert!(value > 9_900_000000, "{value}");
+}
+
+#[test]
+fn check_single_asset_withdraw() {
+    let test_coins = vec![TestCoin::native("uluna"), TestCoin::native("uusd")];
+    let mut helper = Helper::new_native_lp(test_coins.clone(), weighted_params(&[80, 20])).unwrap();
+
+    let owner = helper.owner.clone();
+    let assets = vec![
+        helper.assets[&test_coins[0]].with_balance(1_000_000_000000u128),
+        helper.assets[&test_coins[1]].with_balance(1_000_000_000000u128),
+    ];
+    helper.provide_liquidity(&owner, &assets).unwrap();
+    let total_share = helper.query_pool().unwrap().total_share.u128();
+
+    // Withdrawing more than 30% of the supply into a single asset is not allowed
+    let err = helper
+        .simulate_withdraw(
+            total_share * 31 / 100,
+            Some(helper.assets[&test_coins[0]].clone()),
+        )
+        .unwrap_err();
+    assert_eq!(
+        StdError::generic_err(
+            "Querier contract error: Generic error: Generic error: Withdrawal in a single asset can't decrease the pool invariant below 0.7 of its value"
+        ),
+        err
+    );
+
+    let lp_amount = total_share / 100;
+    let proportional = helper.simulate_withdraw(lp_amount, None).unwrap();
+    assert_eq!(proportional, helper.query_share(lp_amount).unwrap());
+
+    let simulated = helper
+        .simulate_withdraw(lp_amount, Some(helper.assets[&test_coins[1]].clone()))
+        .unwrap();
+    assert_eq!(simulated.len(), 1);
+    assert_eq!(simulated[0].info, helper.assets[&test_coins[1]]);
+
+    // Slippage protection
+    let err = helper
+        .withdraw_single_asset(
+            &owner,
+            lp_amount,
+            helper.assets[&test_coins[1]].clone(),
+            Some(vec![
+                helper.assets[&test_coins[1]].with_balance(simulated[0].amount + Uint128::one())
+            ]),
+        )
+        .unwrap_err();
+    assert_eq!(
+        ContractError::WithdrawSlippageViolation {
+            asset_name: helper.assets[&test_coins[1]].to_string(),
+            received: simulated[0].amount,
+            expected: simulated[0].amount + Uint128::one(),
+        },
+        err.downcast().unwrap(),
+    );
+
+    let usd_before = helper.coin_balance(&test_coins[1], &owner);
+    helper
+        .withdraw_single_asset(
+            &owner,
+            lp_amount,
+            helper.assets[&test_coins[1]].clone(),
+            None,
+        )
+        .unwrap();
+    let received = helper.coin_balance(&test_coins[1], &owner) - usd_before;
+    assert_eq!(received, simulated[0].amount.u128());
+
+    // A proportional exit would return 1% of each pool. Exiting into a single 20% asset
+    // returns more of it but pays the swap fee on the part not matching the pool weights
+    let proportional_value = proportional[0].amount.u128() / 4 + proportional[1].amount.u128();
+    assert!(received > proportional[1].amount.u128(), "{received}");
+    assert!(received < 5 * proportional_value, "{received}");
+
+    let pool = helper.query_pool().unwrap();
+    assert_eq!(pool.total_share.u128(), total_share - lp_amount);
+    assert_eq!(pool.assets[0].amount.u128(), 1_000_000_000000);
+    assert_eq!(pool.assets[1].amount.u128(), 1_000_000_000000 - received);
+}
+
+#[test]
+fn check_swaps() {
+    let test_coins = vec![TestCoin::native("uluna"), TestCoin::cw20("ASTRO")];
+    let mut helper = Helper::new(test_coins.clone(), weighted_params(&[80, 20])).unwrap();
+
+    let owner = helper.owner.clone();
+    let assets = vec![
+        helper.assets[&test_coins[0]].with_balance(4_000_000_000000u128),
+        helper.assets[&test_coins[1]].with_balance(1_000_000_000000u128),
+    ];
+    helper.provide_liquidity(&owner, &assets).unwrap();
+
+    // 80% of the pool value is in LUNA so the spot price is (1000 / 0.2) / (4000 / 0.8) = 1
+    let user = helper.app.api().addr_make("user");
+    let offer_asset = helper.assets[&test_coins[0]].with_balance(100_000000u128);
+    helper.give_me_money(std::slice::from_ref(&offer_asset), &user);
+
+    let sim = helper.simulate_swap(&offer_asset, None).unwrap();
+    // 0.3% fee and a tiny spread
+    assert_eq!(sim.commission_amount.u128(), 299_981);
+    assert!(
+        sim.return_amount.u128() > 99_680000 && sim.return_amount.u128() < 99_700000,
+        "{}",
+        sim.return_amount
+    );
+
+    // Wrong belief price
+    let err = helper
+        .swap_full_params(&user, &offer_asset, None, None, Some(Decimal::percent(90)))
+        .unwrap_err();
+    assert_eq!(
+        ContractError::MaxSpreadAssertion {},
+        err.downcast().unwrap()
+    );
+
+    helper.swap(&user, &offer_asset, None).unwrap();
+    assert_eq!(
+        sim.return_amount.u128(),
+        helper.coin_balance(&test_coins[1], &user)
+    );
+    assert_eq!(
+        helper.coin_balance(&test_coins[1], &helper.fake_maker),
+        sim.commission_amount.u128() / 2
+    );
+
+    // Reverse simulation is consistent with the direct one
+    let ask_asset = helper.assets[&test_coins[0]].with_balance(100_000000u128);
+    let rev_sim = helper.simulate_reverse_swap(&ask_asset, None).unwrap();
+    let offer_asset = helper.assets[&test_coins[1]].with_balance(rev_sim.offer_amount);
+    let sim = helper.simulate_swap(&offer_asset, None).unwrap();
+    assert!(
+        sim.return_amount.u128().abs_diff(100_000000) <= 1,
+        "{}",
+        sim.return_amount
+    );
+
+    // Offer amount above 30% of the pool balance
+    let offer_asset = helper.assets[&test_coins[1]].with_balance(300_000_000001u128);
+    helper.give_me_money(std::slice::from_ref(&offer_asset), &user);
+    let err = helper.swap(&user, &offer_asset, None).unwrap_err();
+    assert_eq!(
+        ContractError::Std(StdError::generic_err(
+            "Offer amount exceeds 0.3 of the pool balance"
+        )),
+        err.downcast().unwrap(),
+    );
+
+    // Ask amount above 30% of the pool balance
+    let err = helper
+        .simulate_reverse_swap(
+            &helper.assets[&test_coins[0]].with_balance(2_000_000_000000u128),
+            None,
+        )
+        .unwrap_err();
+    assert_eq!(
+        StdError::generic_err(
+            "Querier contract error: Generic error: Generic error: Ask amount exceeds 0.3 of the pool balance"
+        ),
+        err
+    );
+}
+
+#[test]
+fn check_swaps_with_three_assets() {
+    let test_coins = vec![
+        TestCoin::native("uluna"),
+        TestCoin::native("uusd"),
+        TestCoin::cw20("ASTRO"),
+    ];
+    let mut helper = Helper::new(test_coins.clone(), weighted_params(&[50, 25, 25])).unwrap();
+
+    let owner = helper.owner.clone();
+    let assets = test_coins
+        .iter()
+        .map(|coin| helper.assets[coin].with_balance(1_000_000_000000u128))
+        .collect_vec();
+    helper.provide_liquidity(&owner, &assets).unwrap();
+
+    let user = helper.app.api().addr_make("user");
+    let offer_asset = helper.assets[&test_coins[0]].with_balance(100_000000u128);
+    helper.give_me_money(std::slice::from_ref(&offer_asset), &user);
+
+    // Ask asset must be specified in a pool with more than two assets
+    let err = helper.swap(&user, &offer_asset, None).unwrap_err();
+    assert_eq!(
+        ContractError::VariableAssetMissed {},
+        err.downcast().unwrap(),
+    );
+
+    // Offer and ask assets must differ
+    let err = helper
+        .swap_full_params(
+            &user,
+            &offer_asset,
+            Some(helper.assets[&test_coins[0]].clone()),
+            None,
+            None,
+        )
+        .unwrap_err();
+    assert_eq!(ContractError::SameAssets {}, err.downcast().unwrap());
+
+    let sim = helper
+        .simulate_swap(&offer_asset, Some(helper.assets[&test_coins[2]].clone()))
+        .unwrap();
+    helper
+        .swap_full_params(
+            &user,
+            &offer_asset,
+            Some(helper.assets[&test_coins[2]].clone()),
+            None,
+            None,
+        )
+        .unwrap();
+
+    // LUNA has twice the weight of ASTRO with equal balances, so 1 LUNA is worth 2 ASTRO
+    let received = helper.coin_balance(&test_coins[2], &user);
+    assert_eq!(received, sim.return_amount.u128());
+    assert!(received > 199_000000 && received < 199_400000, "{received}");
+
+    // The untouched asset stays the same
+    let pool = helper.query_pool().unwrap();
+    assert_eq!(pool.assets[1].amount.u128(), 1_000_000_000000);
+}
+
+#[test]
+fn check_prices() {
+    let test_coins = vec![TestCoin::native("uluna"), TestCoin::native("uusd")];
+    let mut helper = Helper::new(test_coins.clone(), weighted_params(&[80, 20])).unwrap();
+
+    let owner = helper.owner.clone();
+    let assets = vec![
+        helper.assets[&test_coins[0]].with_balance(1_000_000_000000u128),
+        helper.assets[&test_coins[1]].with_balance(1_000_000_000000u128),
+    ];
+    helper.provide_liquidity(&owner, &assets).unwrap();
+    helper.app.next_block(1000);
+
+    // 1 LUNA is worth 4 USD in the 80/20 pool with equal balances
+
+    let offer_asset = helper.assets[&test_coins[0]].with_balance(1_000000u128);
+    helper.give_me_money(std::slice::from_ref(&offer_asset), &owner);
+    helper.swap(&owner, &offer_asset, None).unwrap();
+
+    // Cumulative prices reflect the weighted spot price
+    let prices = helper.query_prices().unwrap();
+    let luna_usd = prices
+        .cumulative_prices
+        .iter()
+        .find(|(from, _, _)| *from == helper.assets[&test_coins[0]])
+        .unwrap()
+        .2;
+    let usd_luna = prices
+        .cumulative_prices
+        .iter()
+        .find(|(from, _, _)| *from == helper.assets[&test_coins[1]])
+        .unwrap()
+        .2;
+    // 1000 seconds at 4 and 0.25 accordingly
+    assert_eq!(luna_usd.u128(), 4_000_000000);
+    assert_eq!(usd_luna.u128(), 250_000000);
+}
+
+#[test]
+fn check_fee_share() {
+    let test_coins = vec![TestCoin::native("uluna"), TestCoin::native("uusd")];
+    let mut helper = Helper::new(test_coins.clone(), weighted_params(&[50, 50])).unwrap();
+
+    let owner = helper.owner.clone();
+    let assets = vec![
+        helper.assets[&test_coins[0]].with_balance(1_000_000_000000u128),
+        helper.assets[&test_coins[1]].with_balance(1_000_000_000000u128),
+    ];
+    helper.provide_liquidity(&owner, &assets).unwrap();
+
+    let fee_share_address = helper.app.api().addr_make("fee_share");
+    let err = helper
+        .update_config(
+            &owner,
+            &WeightedPoolUpdateParams::EnableFeeShare {
+                fee_share_bps: MAX_FEE_SHARE_BPS + 1,
+                fee_share_address: fee_share_address.to_string(),
+            },
+        )
+        .unwrap_err();
+    assert_eq!(
+        ContractError::FeeShareOutOfBounds {},
+        err.downcast().unwrap()
+    );
+
+    helper
+        .update_config(
+            &owner,
+            &WeightedPoolUpdateParams::EnableFeeShare {
+                fee_share_bps: MAX_FEE_SHARE_BPS,
+                fee_share_address: fee_share_address.to_string(),
+            },
+        )
+        .unwrap();
+    let (_, params) = helper.query_config_params().unwrap();
+    assert_eq!(params.fee_share.unwrap().total_bps(), MAX_FEE_SHARE_BPS);
+
+    let user = helper.app.api().addr_make("user");
+    let offer_asset = helper.assets[&test_coins[0]].with_balance(1_000_000000u128);
+    helper.give_me_money(std::slice::from_ref(&offer_asset), &user);
+    helper.swap(&user, &offer_asset, None).unwrap();
+
+    // 10% of the 0.3% fee goes to the fee share address, the maker gets half of the remainder
+    let fee_share = helper.coin_balance(&test_coins[1], &fee_share_address);
+    let maker_fee = helper.coin_balance(&test_coins[1], &helper.fake_maker);
+    assert!(fee_share.abs_diff(300000) < 1000, "{fee_share}");
+    assert!(maker_fee.abs_diff(1_350000) < 5000, "{maker_fee}");
+
+    helper
+        .update_config(&owner, &WeightedPoolUpdateParams::DisableFeeShare)
+        .unwrap();
+    let (_, params) = helper.query_config_params().unwrap();
+    assert_eq!(params.fee_share, None);
+}
+
+#[test]
+fn check_pool_owner() {
+    let test_coins = vec![TestCoin::native("uluna"), TestCoin::native("uusd")];
+    let api = cosmwasm_std::testing::MockApi::default();
+    let pool_owner = api.addr_make("pool_owner");
+    let params = WeightedPoolParams {
+        weights: vec![Decimal::percent(80), Decimal::percent(20)],
+        owner: Some(pool_owner.to_string()),
+    };
+    let mut helper = Helper::new(test_coins, params).unwrap();
+
+    let (config, _) = helper.query_config_params().unwrap();
+    assert_eq!(config.owner, pool_owner);
+
+    // The factory owner is not able to update the config anymore
+    let owner = helper.owner.clone();
+    let err = helper
+        .update_config(&owner, &WeightedPoolUpdateParams::DisableFeeShare)
+        .unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+
+    helper
+        .update_config(&pool_owner, &WeightedPoolUpdateParams::DisableFeeShare)
+        .unwrap();
+}
+
+#[test]
+fn check_pause() {
+    let test_coins = vec![TestCoin::native("uluna"), TestCoin::native("uusd")];
+    let mut helper = Helper::new(test_coins.clone(), weighted_params(&[80, 20])).unwrap();
+
+    let owner = helper.owner.clone();
+    let assets = vec![
+        helper.assets[&test_coins[0]].with_balance(1_000_000_000000u128),
+        helper.assets[&test_coins[1]].with_balance(1_000_000_000000u128),
+    ];
+    helper.provide_liquidity(&owner, &assets).unwrap();
+
+    helper.pause(PauseScope::All).unwrap();
+
+    let err = helper.provide_liquidity(&owner, &assets).unwrap_err();
+    assert_eq!(
+        ContractError::Paused(PauseScope::Provide),
+        err.downcast().unwrap()
+    );
+
+    let offer_asset = helper.assets[&test_coins[0]].with_balance(1_000000u128);
+    let err = helper.swap(&owner, &offer_asset, None).unwrap_err();
+    assert_eq!(
+        ContractError::Paused(PauseScope::Swap),
+        err.downcast().unwrap()
+    );
+
+    // Withdrawals are never paused
+    helper.withdraw_liquidity(&owner, 1_000000).unwrap();
+}
+
+#[test]
+fn check_swap_limits_keep_invariant() {
+    let test_coins = vec![TestCoin::native("uluna"), TestCoin::native("uusd")];
+    let mut helper = Helper::new(test_coins.clone(), weighted_params(&[80, 20])).unwrap();
+
+    let owner = helper.owner.clone();
+    let assets = vec![
+        helper.assets[&test_coins[0]].with_balance(1_000_000_000000u128),
+        helper.assets[&test_coins[1]].with_balance(1_000_000_000000u128),
+    ];
+    helper.provide_liquidity(&owner, &assets).unwrap();
+    let invariant_before = helper.query_invariant().unwrap();
+
+    let user = helper.app.api().addr_make("user");
+    for i in 0..10u128 {
+        let coin = &test_coins[(i % 2) as usize];
+        let offer_asset = helper.assets[coin].with_balance(50_000_000000u128 + i);
+        helper.give_me_money(std::slice::from_ref(&offer_asset), &user);
+        helper
+            .swap(&user, &offer_asset, Some(Decimal::percent(50)))
+            .unwrap();
+    }
+
+    // Fees stay in the pool so the invariant grows
+    let invariant_after = helper.query_invariant().unwrap();
+    assert!(
+        Decimal256::from_ratio(invariant_after, invariant_before) > Decimal256::one(),
+        "{invariant_before} -> {invariant_after}"
+    );
+}
@@ -7,7 +7,12 @@ use cw2::set_contract_version;
 use cw20::Cw20ReceiveMsg;
 
 use astroport::asset::{addr_opt_validate, AssetInfo, AssetInfoExt};
-use astroport::pair::{QueryMsg as PairQueryMsg, SimulationResponse};
+use astroport::factory::PauseScope;
+use astroport::pair::{
+    is_deadline_exceeded, ConfigResponse as PairConfigResponse, QueryMsg as PairQueryMsg,
+    SimulationResponse,
+};
+use astroport::querier::ensure_not_paused;
 use astroport::router::{
     Cw20HookMsg, ExecuteMsg, QueryMsg, SimulateSwapOperationsResponse, SwapOperation,
     SwapResponseData, MAX_SWAP_OPERATIONS,
@@ -45,7 +50,9 @@ pub fn instantiate(
 /// * **ExecuteMsg::ExecuteSwapOperations {
 ///             operations,
 ///             minimum_receive,
-///             to
+///             to,
+///             max_spread,
+///             deadline
 ///         }** Performs swap operations with the specified parameters.
 ///
 /// * **ExecuteMsg::ExecuteSwapOperation { operation, to }** Execute a single swap operation.
@@ -70,6 +77,7 @@ pub fn execute(
             minimum_receive,
             to,
             max_spread,
+            deadline,
         } => execute_swap_operations(
             deps,
             env,
@@ -78,6 +86,7 @@ pub fn execute(
             minimum_receive,
             to,
             max_spread,
+            deadline,
         ),
         ExecuteMsg::ExecuteSwapOperation {
             operation,
@@ -102,6 +111,7 @@ pub fn receive_cw20(
             minimum_receive,
             to,
             max_spread,
+            deadline,
         } => execute_swap_operations(
             deps,
             env,
@@ -110,6 +120,7 @@ pub fn receive_cw20(
             minimum_receive,
             to,
             max_spread,
+            deadline,
         ),
     }
 }
@@ -123,6 +134,8 @@ pub fn receive_cw20(
 /// * **minimum_receive** used to guarantee that the ask amount is above a minimum amount.
 ///
 /// * **to** recipient of the ask tokens.
+///
+/// * **deadline** block time (in seconds) after which the swap operations are rejected.
 #[allow(clippy::too_many_arguments)]
 pub fn execute_swap_operations(
     deps: DepsMut,
@@ -132,8 +145,16 @@ pub fn execute_swap_operations(
     minimum_receive: Option<Uint128>,
     to: Option<String>,
     max_spread: Option<Decimal>,
+    deadline: Option<u64>,
 ) -> Result<Response, ContractError> {
+    if is_deadline_exceeded(&env.block, deadline) {
+        return Err(ContractError::DeadlineExceeded {});
+    }
+
     assert_operations(&operations)?;
+    for operation in &operations {
+        ensure_swaps_not_paused(deps.as_ref(), &operation.pair_address)?;
+    }
 
     let to = addr_opt_validate(deps.api, &to)?.unwrap_or(sender);
     let target_asset_info = operations.last().unwrap().ask_asset_info.clone();
@@ -321,6 +342,20 @@ fn assert_operations(operations: &[SwapOperation]) -> Result<(), ContractError>
     Ok(())
 }
 
+/// Returns an error if swaps are paused for the pair in its factory.
+fn ensure_swaps_not_paused(deps: Deps, pair: &str) -> Result<(), ContractError> {
+    let pair_config: PairConfigResponse = deps
+        .querier
+        .query_wasm_smart(pair, &PairQueryMsg::Config {})?;
+    ensure_not_paused(
+        &deps.querier,
+        pair_config.factory_addr,
+        pair,
+        PauseScope::Swap,
+        |_| ContractError::PairPaused(pair.to_string()),
+    )
+}
+
 #[cfg(test)]
 mod testing {
     use super::*;
@@ -354,7 +389,7 @@ mod testing {
         .unwrap();
 
         // asset0002 output
-        assert_operations(&vec![
+        assert_operations(&[
             SwapOperation {
                 pair_address: "".to_string(),
                 offer_asset_info: AssetInfo::NativeToken {
@@ -386,7 +421,7 @@ mod testing {
         .unwrap();
 
         // Multiple output token type errors
-        assert_operations(&vec![
+        assert_operations(&[
             SwapOperation {
                 pair_address: "".to_string(),
                 offer_asset_info: AssetInfo::NativeToken {
@@ -37,4 +37,10 @@ pub enum ContractError {
 
     #[error("The swap operation limit was exceeded!")]
     SwapLimitExceeded {},
+
+    #[error("Transaction deadline exceeded")]
+    DeadlineExceeded {},
+
+    #[error("Swaps are paused in pair {0}")]
+    PairPaused(String),
 }
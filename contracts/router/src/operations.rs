@@ -82,6 +82,8 @@ pub fn asset_into_swap_msg(
                 belief_price,
                 max_spread,
                 to,
+                // The deadline is checked by the router before the swap operations are dispatched
+                deadline: None,
             },
             coins(offer_asset.amount.u128(), denom),
         ),
@@ -95,6 +97,7 @@ pub fn asset_into_swap_msg(
                     belief_price,
                     max_spread,
                     to,
+                    deadline: None,
                 })?,
             },
             vec![],
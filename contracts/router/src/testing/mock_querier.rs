@@ -9,8 +9,8 @@ use cosmwasm_std::{
 use cw20::{BalanceResponse, Cw20QueryMsg, TokenInfoResponse};
 
 use astroport::asset::{Asset, AssetInfo, PairInfo};
-use astroport::factory::PairType;
-use astroport::pair::SimulationResponse;
+use astroport::factory::{PairType, PauseStatusResponse};
+use astroport::pair::{ConfigResponse, SimulationResponse};
 use astroport_test::legacy_mock_api::LegacyMockApi;
 
 #[cw_serde]
@@ -22,6 +22,10 @@ pub enum QueryMsg {
         offer_asset: Asset,
         ask_asset_info: Option<AssetInfo>,
     },
+    Config {},
+    PauseStatus {
+        pair: Option<String>,
+    },
 }
 
 /// mock_dependencies is a drop-in replacement for cosmwasm_std::testing::mock_dependencies.
@@ -112,11 +116,6 @@ impl Querier for WasmMockQuerier {
     }
 }
 
-#[cw_serde]
-pub enum MockQueryMsg {
-    Price {},
-}
-
 impl WasmMockQuerier {
     pub fn handle_query(&self, request: &QueryRequest<Empty>) -> QuerierResult {
         match &request {
@@ -164,6 +163,17 @@ impl WasmMockQuerier {
                     spread_amount: Uint128::zero(),
                 })))
             }
+            QueryMsg::Config {} => {
+                SystemResult::Ok(ContractResult::from(to_json_binary(&ConfigResponse {
+                    block_time_last: 0,
+                    params: None,
+                    owner: Addr::unchecked("owner"),
+                    factory_addr: Addr::unchecked("factory"),
+                })))
+            }
+            QueryMsg::PauseStatus { .. } => SystemResult::Ok(ContractResult::from(to_json_binary(
+                &PauseStatusResponse::default(),
+            ))),
         }
     }
 
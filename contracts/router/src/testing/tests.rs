@@ -42,6 +42,7 @@ fn execute_swap_operations() {
         minimum_receive: None,
         to: None,
         max_spread: None,
+        deadline: None,
     };
 
     let env = mock_env();
@@ -82,6 +83,7 @@ fn execute_swap_operations() {
         minimum_receive: Some(Uint128::from(1000000u128)),
         to: None,
         max_spread: None,
+        deadline: None,
     };
 
     let env = mock_env();
@@ -207,6 +209,7 @@ fn execute_swap_operations() {
             minimum_receive: None,
             to: Some(String::from("addr0002")),
             max_spread: None,
+            deadline: None,
         })
         .unwrap(),
     });
@@ -299,6 +302,59 @@ fn execute_swap_operations() {
     );
 }
 
+#[test]
+fn execute_swap_operations_deadline() {
+    let mut deps = mock_dependencies(&[]);
+
+    let env = mock_env();
+    let info = mock_info("addr0000", &[]);
+
+    instantiate(deps.as_mut(), env, info, Empty {}).unwrap();
+
+    let operations = vec![SwapOperation {
+        pair_address: "pair0000".to_string(),
+        offer_asset_info: native_asset_info("ukrw".to_string()),
+        ask_asset_info: native_asset_info("uluna".to_string()),
+    }];
+    let now = mock_env().block.time.seconds();
+
+    let msg = ExecuteMsg::ExecuteSwapOperations {
+        operations: operations.clone(),
+        minimum_receive: None,
+        to: None,
+        max_spread: None,
+        deadline: Some(now - 1),
+    };
+    let err = execute(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap_err();
+    assert_eq!(err, ContractError::DeadlineExceeded {});
+
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: "addr0000".to_string(),
+        amount: Uint128::from(1000000u128),
+        msg: to_json_binary(&Cw20HookMsg::ExecuteSwapOperations {
+            operations: operations.clone(),
+            minimum_receive: None,
+            to: None,
+            max_spread: None,
+            deadline: Some(now - 1),
+        })
+        .unwrap(),
+    });
+    let err = execute(deps.as_mut(), mock_env(), mock_info("asset0000", &[]), msg).unwrap_err();
+    assert_eq!(err, ContractError::DeadlineExceeded {});
+
+    // The swap can still be executed at the deadline
+    let msg = ExecuteMsg::ExecuteSwapOperations {
+        operations,
+        minimum_receive: None,
+        to: None,
+        max_spread: None,
+        deadline: Some(now),
+    };
+    let res = execute(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
+    assert_eq!(res.messages.len(), 1);
+}
+
 #[test]
 fn execute_swap_operation() {
     let mut deps = mock_dependencies(&[]);
@@ -358,6 +414,7 @@ fn execute_swap_operation() {
                         belief_price: None,
                         max_spread: None,
                         to: Some(String::from("addr0000")),
+                        deadline: None,
                     })
                     .unwrap()
                 })
@@ -447,6 +504,7 @@ fn assert_maximum_receive_swap_operations() {
         minimum_receive: None,
         to: None,
         max_spread: None,
+        deadline: None,
     };
 
     let env = mock_env();
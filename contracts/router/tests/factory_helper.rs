@@ -56,6 +56,7 @@ impl FactoryHelper {
                     is_disabled: false,
                     is_generator_disabled: false,
                     permissioned: false,
+                    native_lp_token: false,
                 },
                 PairConfig {
                     code_id: pair_code_id,
@@ -65,6 +66,7 @@ impl FactoryHelper {
                     is_disabled: false,
                     is_generator_disabled: false,
                     permissioned: false,
+                    native_lp_token: false,
                 },
             ],
             token_code_id: cw20_token_code_id,
@@ -195,3 +197,12 @@ pub fn mint_native(
 
     app.send_tokens(denom_admin, receiver.clone(), &coins_vec)
 }
+
+pub fn sync_pair(app: &mut App, pair: &Addr) -> AnyResult<AppResponse> {
+    app.execute_contract(
+        Addr::unchecked("anyone"),
+        pair.clone(),
+        &astroport::pair::ExecuteMsg::Sync {},
+        &[],
+    )
+}
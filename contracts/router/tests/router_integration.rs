@@ -1,15 +1,16 @@
+#![allow(clippy::inconsistent_digit_grouping)]
 use cosmwasm_std::{coins, from_json, to_json_binary, Empty, StdError, Uint128};
 use cw20::{BalanceResponse, Cw20ExecuteMsg, Cw20QueryMsg};
 use cw_multi_test::{App, Contract, ContractWrapper, Executor};
 
 use astroport::asset::{native_asset_info, token_asset_info, AssetInfo};
-use astroport::factory::PairType;
+use astroport::factory::{ExecuteMsg as FactoryExecuteMsg, PairType, PauseScope};
 use astroport::router::{
     ExecuteMsg, QueryMsg, SimulateSwapOperationsResponse, SwapOperation, SwapResponseData,
 };
 use astroport_router::error::ContractError;
 
-use crate::factory_helper::{instantiate_token, mint, mint_native, FactoryHelper};
+use crate::factory_helper::{instantiate_token, mint, mint_native, sync_pair, FactoryHelper};
 
 mod factory_helper;
 
@@ -55,6 +56,7 @@ fn router_does_not_enforce_spread_assertion() {
             .unwrap();
         mint(&mut app, &owner, a, liq, &pair).unwrap();
         mint(&mut app, &owner, b, liq, &pair).unwrap();
+        sync_pair(&mut app, &pair).unwrap();
     }
 
     let router_code = app.store_code(router_contract());
@@ -66,7 +68,7 @@ fn router_does_not_enforce_spread_assertion() {
     mint(&mut app, &owner, &token_x, 50_000_000000, &owner).unwrap();
     let pair_1 = helper
         .query_pair_by_asset_infos(
-            &mut app,
+            &app,
             &[
                 AssetInfo::cw20(token_x.clone()),
                 AssetInfo::cw20(token_y.clone()),
@@ -75,7 +77,7 @@ fn router_does_not_enforce_spread_assertion() {
         .unwrap();
     let pair_2 = helper
         .query_pair_by_asset_infos(
-            &mut app,
+            &app,
             &[
                 AssetInfo::cw20(token_y.clone()),
                 AssetInfo::cw20(token_z.clone()),
@@ -105,6 +107,7 @@ fn router_does_not_enforce_spread_assertion() {
                     minimum_receive: None,
                     to: None,
                     max_spread: None,
+                    deadline: None,
                 })
                 .unwrap(),
             },
@@ -136,6 +139,7 @@ fn router_does_not_enforce_spread_assertion() {
                     minimum_receive: None,
                     to: None,
                     max_spread: None,
+                    deadline: None,
                 })
                 .unwrap(),
             },
@@ -182,6 +186,7 @@ fn route_through_pairs_with_natives() {
             .unwrap();
         mint_native(&mut app, a, liq, &pair).unwrap();
         mint_native(&mut app, b, liq, &pair).unwrap();
+        sync_pair(&mut app, &pair).unwrap();
     }
 
     let router_code = app.store_code(router_contract());
@@ -226,6 +231,7 @@ fn route_through_pairs_with_natives() {
                 to: None,
                 max_spread: None,
                 minimum_receive: None,
+                deadline: None,
             },
             &[],
         )
@@ -243,13 +249,13 @@ fn route_through_pairs_with_natives() {
     mint_native(&mut app, denom_x, 50_000_000000, &owner).unwrap();
     let pair_1 = helper
         .query_pair_by_asset_infos(
-            &mut app,
+            &app,
             &[AssetInfo::native(denom_x), AssetInfo::native(denom_y)],
         )
         .unwrap();
     let pair_2 = helper
         .query_pair_by_asset_infos(
-            &mut app,
+            &app,
             &[AssetInfo::native(denom_y), AssetInfo::native(denom_z)],
         )
         .unwrap();
@@ -273,6 +279,7 @@ fn route_through_pairs_with_natives() {
                 minimum_receive: None,
                 to: None,
                 max_spread: None,
+                deadline: None,
             },
             &coins(50_000_000000, denom_x),
         )
@@ -303,6 +310,7 @@ fn route_through_pairs_with_natives() {
                 minimum_receive: Some(50_000_000000u128.into()), // <--- enforcing minimum receive with 1:1 rate (which practically impossible)
                 to: None,
                 max_spread: None,
+                deadline: None,
             },
             &coins(50_000_000000, denom_x),
         )
@@ -344,6 +352,7 @@ fn test_swap_route() {
             .unwrap();
         mint(&mut app, &owner, a, liq, &pair).unwrap();
         mint(&mut app, &owner, b, liq, &pair).unwrap();
+        sync_pair(&mut app, &pair).unwrap();
     }
     let router_code = app.store_code(router_contract());
     let router = app
@@ -356,13 +365,13 @@ fn test_swap_route() {
     // route: astro -> inj, atom -> osmo
     let pair_1 = helper
         .query_pair_by_asset_infos(
-            &mut app,
+            &app,
             &[AssetInfo::cw20(astro.clone()), AssetInfo::cw20(inj.clone())],
         )
         .unwrap();
     let pair_2 = helper
         .query_pair_by_asset_infos(
-            &mut app,
+            &app,
             &[AssetInfo::cw20(atom.clone()), AssetInfo::cw20(osmo.clone())],
         )
         .unwrap();
@@ -408,13 +417,13 @@ fn test_swap_route() {
     // route: astro -> inj, inj -> osmo, osmo -> atom, atom -> osmo
     let pair_3 = helper
         .query_pair_by_asset_infos(
-            &mut app,
+            &app,
             &[AssetInfo::cw20(inj.clone()), AssetInfo::cw20(osmo.clone())],
         )
         .unwrap();
     let pair_4 = helper
         .query_pair_by_asset_infos(
-            &mut app,
+            &app,
             &[AssetInfo::cw20(osmo.clone()), AssetInfo::cw20(atom.clone())],
         )
         .unwrap();
@@ -502,6 +511,7 @@ fn test_swap_route() {
                 minimum_receive: None,
                 to: None,
                 max_spread: None,
+                deadline: None,
             })
             .unwrap(),
         },
@@ -589,6 +599,7 @@ fn test_swap_route() {
                 minimum_receive: None,
                 to: None,
                 max_spread: None,
+                deadline: None,
             })
             .unwrap(),
         },
@@ -682,6 +693,7 @@ fn test_swap_route() {
                 minimum_receive: Some(Uint128::new(9_997_000)),
                 to: None,
                 max_spread: None,
+                deadline: None,
             },
             &[],
         )
@@ -817,6 +829,7 @@ fn test_swap_route() {
                 minimum_receive: Some(donated_atom),
                 to: None,
                 max_spread: None,
+                deadline: None,
             })
             .unwrap(),
         },
@@ -876,6 +889,7 @@ fn test_swap_route() {
                 minimum_receive: None,
                 to: None,
                 max_spread: None,
+                deadline: None,
             },
             &[],
         )
@@ -912,3 +926,96 @@ fn test_swap_route() {
     let profit = balance_res.balance.saturating_sub(donated_atom);
     println!("Attacker2's profit: {:?}", profit);
 }
+
+#[test]
+fn router_checks_pauses() {
+    let mut app = App::default();
+
+    let mut helper = FactoryHelper::init(&mut app);
+    let owner = helper.owner.clone();
+
+    let denom_x = "denom_x";
+    let denom_y = "denom_y";
+    let denom_z = "denom_z";
+
+    let mut pairs = vec![];
+    for (a, b) in [(&denom_x, &denom_y), (&denom_y, &denom_z)] {
+        let pair = helper
+            .create_pair(
+                &mut app,
+                &owner,
+                PairType::Xyk {},
+                [
+                    native_asset_info(a.to_string()),
+                    native_asset_info(b.to_string()),
+                ],
+                None,
+            )
+            .unwrap();
+        mint_native(&mut app, a, 100_000_000000, &pair).unwrap();
+        mint_native(&mut app, b, 100_000_000000, &pair).unwrap();
+        sync_pair(&mut app, &pair).unwrap();
+        pairs.push(pair);
+    }
+
+    let router_code = app.store_code(router_contract());
+    let router = app
+        .instantiate_contract(router_code, owner.clone(), &Empty {}, &[], "router", None)
+        .unwrap();
+
+    app.execute_contract(
+        owner.clone(),
+        helper.factory.clone(),
+        &FactoryExecuteMsg::Pause {
+            pair: Some(pairs[1].to_string()),
+            scope: PauseScope::Swap,
+        },
+        &[],
+    )
+    .unwrap();
+
+    let swap_msg = ExecuteMsg::ExecuteSwapOperations {
+        operations: vec![
+            SwapOperation {
+                pair_address: pairs[0].to_string(),
+                offer_asset_info: native_asset_info(denom_x.to_string()),
+                ask_asset_info: native_asset_info(denom_y.to_string()),
+            },
+            SwapOperation {
+                pair_address: pairs[1].to_string(),
+                offer_asset_info: native_asset_info(denom_y.to_string()),
+                ask_asset_info: native_asset_info(denom_z.to_string()),
+            },
+        ],
+        minimum_receive: None,
+        to: None,
+        max_spread: None,
+        deadline: None,
+    };
+
+    mint_native(&mut app, denom_x, 1_000000, &owner).unwrap();
+    let err = app
+        .execute_contract(
+            owner.clone(),
+            router.clone(),
+            &swap_msg,
+            &coins(1_000000, denom_x),
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::PairPaused(pairs[1].to_string())
+    );
+
+    app.execute_contract(
+        owner.clone(),
+        helper.factory.clone(),
+        &FactoryExecuteMsg::Unpause {
+            pair: Some(pairs[1].to_string()),
+        },
+        &[],
+    )
+    .unwrap();
+    app.execute_contract(owner, router, &swap_msg, &coins(1_000000, denom_x))
+        .unwrap();
+}
@@ -58,6 +58,9 @@ pub enum ContractError {
     #[error("Pair type {pair_type} is blocked")]
     BlockedPairType { pair_type: PairType },
 
+    #[error("Deposits to pool {lp_token} are paused")]
+    PoolPaused { lp_token: String },
+
     #[error("Failed to parse or process reply message")]
     FailedToParseReply {},
 
@@ -26,8 +26,8 @@ use crate::state::{
 };
 use crate::utils::{
     asset_info_key, claim_orphaned_rewards, claim_rewards, deactivate_blocked_pools,
-    deactivate_pool, incentivize_many, is_pool_registered, query_pair_info,
-    remove_reward_from_pool,
+    deactivate_pool, ensure_deposits_not_paused, incentivize_many, is_pool_registered,
+    query_pair_info, remove_reward_from_pool,
 };
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -184,7 +184,8 @@ fn deposit(
     let staker = addr_opt_validate(deps.api, &recipient)?.unwrap_or(sender.clone());
 
     let config = CONFIG.load(deps.storage)?;
-    is_pool_registered(deps.querier, &config, &maybe_lp.info.to_string())?;
+    let pair_info = is_pool_registered(deps.querier, &config, &maybe_lp.info.to_string())?;
+    ensure_deposits_not_paused(deps.querier, &config, &pair_info)?;
 
     let mut pool_info = PoolInfo::may_load(deps.storage, &maybe_lp.info)?.unwrap_or_default();
     let mut user_info = UserInfo::may_load_position(deps.storage, &staker, &maybe_lp.info)?
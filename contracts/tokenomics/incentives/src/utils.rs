@@ -7,8 +7,9 @@ use itertools::Itertools;
 use astroport::asset::{
     determine_asset_info, pair_info_by_pool, AssetInfo, AssetInfoExt, PairInfo,
 };
-use astroport::factory::PairType;
+use astroport::factory::{PairType, PauseScope};
 use astroport::incentives::{Config, IncentivesSchedule, InputSchedule, MAX_ORPHANED_REWARD_LIMIT};
+use astroport::querier::ensure_not_paused;
 use astroport::{factory, pair, vesting};
 
 use crate::error::ContractError;
@@ -416,11 +417,12 @@ pub fn query_pair_info(deps: Deps, lp_asset: &AssetInfo) -> StdResult<PairInfo>
 }
 
 /// Checks if the pool with the following LP token is registered in the factory.
+/// Returns the pair info of the registered pool.
 pub fn is_pool_registered(
     querier: QuerierWrapper,
     config: &Config,
     lp_token_addr: &str,
-) -> StdResult<()> {
+) -> StdResult<PairInfo> {
     querier
         .query_wasm_smart::<PairInfo>(
             &config.factory,
@@ -433,7 +435,24 @@ pub fn is_pool_registered(
                 "The pair is not registered for token {lp_token_addr}"
             ))
         })
-        .map(|_| ())
+}
+
+/// Returns an error if liquidity provision is paused in the factory for the given pair.
+/// Depositing LP tokens of a paused pair is blocked while withdrawals and claims stay open.
+pub fn ensure_deposits_not_paused(
+    querier: QuerierWrapper,
+    config: &Config,
+    pair_info: &PairInfo,
+) -> Result<(), ContractError> {
+    ensure_not_paused(
+        &querier,
+        &config.factory,
+        &pair_info.contract_addr,
+        PauseScope::Provide,
+        |_| ContractError::PoolPaused {
+            lp_token: pair_info.liquidity_token.clone(),
+        },
+    )
 }
 
 pub fn claim_orphaned_rewards(
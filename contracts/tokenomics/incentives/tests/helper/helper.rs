@@ -131,6 +131,7 @@ impl Helper {
                             is_disabled: false,
                             is_generator_disabled: false,
                             permissioned: false,
+                            native_lp_token: false,
                         },
                         PairConfig {
                             code_id: pair_code, // yet another xyk
@@ -140,6 +141,7 @@ impl Helper {
                             is_disabled: false,
                             is_generator_disabled: false,
                             permissioned: false,
+                            native_lp_token: false,
                         },
                     ],
                     token_code_id,
@@ -615,7 +617,7 @@ impl Helper {
             .unwrap();
 
         self.app
-            .send_tokens(denom_admin, to.clone(), &[coin.clone()])
+            .send_tokens(denom_admin, to.clone(), std::slice::from_ref(coin))
             .unwrap();
     }
 
@@ -838,6 +840,7 @@ impl Helper {
             auto_stake: Some(auto_stake),
             receiver: None,
             min_lp_to_receive: None,
+            deadline: None,
         };
 
         self.app
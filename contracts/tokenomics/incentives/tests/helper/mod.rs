@@ -1,4 +1,5 @@
 pub mod broken_cw20;
+#[allow(clippy::module_inception)]
 mod helper;
 
 pub use helper::*;
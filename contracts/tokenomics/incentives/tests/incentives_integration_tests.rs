@@ -1,3 +1,4 @@
+#![allow(clippy::inconsistent_digit_grouping)]
 use std::str::FromStr;
 
 use cosmwasm_std::{coin, coins, Addr, Decimal256, Timestamp, Uint128};
@@ -5,6 +6,7 @@ use cw_multi_test::Executor;
 use itertools::Itertools;
 
 use astroport::asset::{native_asset_info, AssetInfo, AssetInfoExt};
+use astroport::factory::{self, PauseScope};
 use astroport::incentives::{
     ExecuteMsg, IncentivizationFeeInfo, InputSchedule, ScheduleResponse, EPOCHS_START,
     EPOCH_LENGTH, MAX_REWARD_TOKENS,
@@ -81,6 +83,65 @@ fn test_stake_unstake() {
     assert_eq!(lp_balance, initial_lp_balance);
 }
 
+#[test]
+fn test_stake_paused_pool() {
+    let astro = native_asset_info("astro".to_string());
+    let mut helper = Helper::new(&astro).unwrap();
+    let owner = helper.owner.clone();
+    let user = helper.app.api().addr_make("user");
+
+    let asset_infos = [AssetInfo::native("uusd"), AssetInfo::native("ueur")];
+    let pair_info = helper.create_pair(&asset_infos).unwrap();
+    let provide_assets = [
+        asset_infos[0].with_balance(100000u64),
+        asset_infos[1].with_balance(100000u64),
+    ];
+    helper
+        .provide_liquidity(&user, &provide_assets, &pair_info.contract_addr, false)
+        .unwrap();
+
+    let cw20_lp = AssetInfo::cw20(Addr::unchecked(&pair_info.liquidity_token));
+    let lp_balance = cw20_lp.query_pool(&helper.app.wrap(), &user).unwrap();
+    helper
+        .stake(&user, cw20_lp.with_balance(lp_balance.u128() / 2))
+        .unwrap();
+
+    helper
+        .app
+        .execute_contract(
+            owner,
+            helper.factory.clone(),
+            &factory::ExecuteMsg::Pause {
+                pair: Some(pair_info.contract_addr.to_string()),
+                scope: PauseScope::Provide,
+            },
+            &[],
+        )
+        .unwrap();
+
+    let err = helper
+        .stake(&user, cw20_lp.with_balance(lp_balance.u128() / 2))
+        .unwrap_err();
+    assert_eq!(
+        err.root_cause().to_string(),
+        ContractError::PoolPaused {
+            lp_token: pair_info.liquidity_token.clone()
+        }
+        .to_string()
+    );
+
+    // Unstaking is still possible
+    helper
+        .unstake(
+            &user,
+            pair_info.liquidity_token.as_str(),
+            lp_balance.u128() / 2,
+        )
+        .unwrap();
+    let new_balance = cw20_lp.query_pool(&helper.app.wrap(), &user).unwrap();
+    assert_eq!(new_balance, lp_balance);
+}
+
 #[test]
 fn test_incentives() {
     let astro = native_asset_info("astro".to_string());
@@ -115,7 +176,7 @@ fn test_incentives() {
     let bank = helper.app.api().addr_make("bank");
     let reward_asset_info = AssetInfo::native("reward");
     let reward = reward_asset_info.with_balance(1000_000000u128);
-    helper.mint_assets(&bank, &[reward.clone()]);
+    helper.mint_assets(&bank, std::slice::from_ref(&reward));
     let (schedule, internal_sch) = helper.create_schedule(&reward, 2).unwrap();
     helper.mint_coin(&bank, &incentivization_fee);
 
@@ -162,7 +223,7 @@ fn test_incentives() {
             &bank,
             &lp_token,
             schedule.clone(),
-            &[incentivization_fee.clone()],
+            std::slice::from_ref(&incentivization_fee),
         )
         .unwrap();
 
@@ -254,7 +315,7 @@ fn test_cw20_incentives() {
     let reward_cw20 = helper.init_cw20("reward", None);
     let reward_asset_info = AssetInfo::cw20(reward_cw20);
     let reward = reward_asset_info.with_balance(1000_000000u128);
-    helper.mint_assets(&bank, &[reward.clone()]);
+    helper.mint_assets(&bank, std::slice::from_ref(&reward));
 
     let (schedule, internal_sch) = helper.create_schedule(&reward, 2).unwrap();
     helper.mint_coin(&bank, &incentivization_fee);
@@ -263,7 +324,7 @@ fn test_cw20_incentives() {
             &bank,
             &lp_token,
             schedule.clone(),
-            &[incentivization_fee.clone()],
+            std::slice::from_ref(&incentivization_fee),
         )
         .unwrap();
 
@@ -345,7 +406,7 @@ fn test_large_incentives() {
     let bank = helper.app.api().addr_make("bank");
     let reward_asset_info = AssetInfo::native("reward");
     let reward = reward_asset_info.with_balance(2839081665193567584256u128);
-    helper.mint_assets(&bank, &[reward.clone()]);
+    helper.mint_assets(&bank, std::slice::from_ref(&reward));
 
     let (schedule, internal_sch) = helper.create_schedule(&reward, 1).unwrap();
     helper.mint_coin(&bank, &incentivization_fee);
@@ -354,7 +415,7 @@ fn test_large_incentives() {
             &bank,
             &lp_token,
             schedule.clone(),
-            &[incentivization_fee.clone()],
+            std::slice::from_ref(&incentivization_fee),
         )
         .unwrap();
 
@@ -412,7 +473,7 @@ fn test_multiple_schedules_same_reward() {
         .map(|i| helper.create_schedule(&reward, i).unwrap())
         .collect();
     for (ind, (schedule, _)) in schedules.iter().enumerate() {
-        helper.mint_assets(&bank, &[reward.clone()]);
+        helper.mint_assets(&bank, std::slice::from_ref(&reward));
         if ind == 0 {
             // attach incentivization fee on the first schedule
             helper.mint_coin(&bank, &incentivization_fee);
@@ -421,7 +482,7 @@ fn test_multiple_schedules_same_reward() {
                     &bank,
                     &lp_token,
                     schedule.clone(),
-                    &[incentivization_fee.clone()],
+                    std::slice::from_ref(&incentivization_fee),
                 )
                 .unwrap();
         } else {
@@ -489,21 +550,21 @@ fn test_astro_can_bypass_rewards_limit() {
         .collect();
     // Create multiple schedules with different rewards (starts on the next week)
     for (schedule, _) in &schedules {
-        helper.mint_assets(&bank, &[schedule.reward.clone()]);
+        helper.mint_assets(&bank, std::slice::from_ref(&schedule.reward));
         helper.mint_coin(&bank, &incentivization_fee);
         helper
             .incentivize(
                 &bank,
                 &lp_token,
                 schedule.clone(),
-                &[incentivization_fee.clone()],
+                std::slice::from_ref(&incentivization_fee),
             )
             .unwrap();
     }
 
     // ASTRO can always be added no matter what MAX_REWARD_TOKENS limit is
     let astro_reward = astro.with_balance(1000_000000u128);
-    helper.mint_assets(&bank, &[astro_reward.clone()]);
+    helper.mint_assets(&bank, std::slice::from_ref(&astro_reward));
     helper
         .incentivize(
             &bank,
@@ -559,14 +620,14 @@ fn test_multiple_schedules_different_reward() {
         .collect();
     // Create multiple schedules with different rewards (starts on the next week)
     for (schedule, _) in &schedules {
-        helper.mint_assets(&bank, &[schedule.reward.clone()]);
+        helper.mint_assets(&bank, std::slice::from_ref(&schedule.reward));
         helper.mint_coin(&bank, &incentivization_fee);
         helper
             .incentivize(
                 &bank,
                 &lp_token,
                 schedule.clone(),
-                &[incentivization_fee.clone()],
+                std::slice::from_ref(&incentivization_fee),
             )
             .unwrap();
     }
@@ -575,14 +636,14 @@ fn test_multiple_schedules_different_reward() {
     let reward_asset_info = AssetInfo::native(format!("reward{}", MAX_REWARD_TOKENS + 1));
     let reward = reward_asset_info.with_balance(1000_000000u128);
     let (schedule, _) = helper.create_schedule(&reward, 2).unwrap();
-    helper.mint_assets(&bank, &[schedule.reward.clone()]);
+    helper.mint_assets(&bank, std::slice::from_ref(&schedule.reward));
     helper.mint_coin(&bank, &incentivization_fee);
     let err = helper
         .incentivize(
             &bank,
             &lp_token,
             schedule.clone(),
-            &[incentivization_fee.clone()],
+            std::slice::from_ref(&incentivization_fee),
         )
         .unwrap_err();
     assert_eq!(
@@ -687,7 +748,7 @@ fn test_incentivize_many() {
     let incentives = schedules
         .iter()
         .map(|(schedule, _)| {
-            helper.mint_assets(&bank, &[schedule.reward.clone()]);
+            helper.mint_assets(&bank, std::slice::from_ref(&schedule.reward));
             helper.mint_coin(&bank, &incentivization_fee);
             (lp_token.as_str(), schedule.clone())
         })
@@ -742,14 +803,14 @@ fn test_incentivize_many() {
     let reward_asset_info = AssetInfo::native(format!("reward{}", MAX_REWARD_TOKENS + 1));
     let reward = reward_asset_info.with_balance(1000_000000u128);
     let (schedule, _) = helper.create_schedule(&reward, 2).unwrap();
-    helper.mint_assets(&bank, &[schedule.reward.clone()]);
+    helper.mint_assets(&bank, std::slice::from_ref(&schedule.reward));
     helper.mint_coin(&bank, &incentivization_fee);
     let err = helper
         .incentivize(
             &bank,
             &lp_token,
             schedule.clone(),
-            &[incentivization_fee.clone()],
+            std::slice::from_ref(&incentivization_fee),
         )
         .unwrap_err();
     assert_eq!(
@@ -850,7 +911,7 @@ fn test_claim_between_different_periods() {
         .map(|i| helper.create_schedule(&reward, i).unwrap())
         .collect();
     for (ind, (schedule, _)) in schedules.iter().enumerate() {
-        helper.mint_assets(&bank, &[reward.clone()]);
+        helper.mint_assets(&bank, std::slice::from_ref(&reward));
         if ind == 0 {
             // attach incentivization fee on the first schedule
             helper.mint_coin(&bank, &incentivization_fee);
@@ -859,7 +920,7 @@ fn test_claim_between_different_periods() {
                     &bank,
                     &lp_token,
                     schedule.clone(),
-                    &[incentivization_fee.clone()],
+                    std::slice::from_ref(&incentivization_fee),
                 )
                 .unwrap();
         } else {
@@ -907,7 +968,7 @@ fn test_blocked_tokens() {
 
     // Check general validation
     let err = helper
-        .block_tokens(&guardian, &[astro.clone()])
+        .block_tokens(&guardian, std::slice::from_ref(&astro))
         .unwrap_err();
     assert_eq!(
         err.root_cause().to_string(),
@@ -1151,10 +1212,10 @@ fn test_incentives_with_blocked() {
     let bank = helper.app.api().addr_make("bank");
     let blocked_token = AssetInfo::native("blocked_reward");
     helper
-        .block_tokens(&owner, &[blocked_token.clone()])
+        .block_tokens(&owner, std::slice::from_ref(&blocked_token))
         .unwrap();
     let reward = blocked_token.with_balance(1000_000000u128);
-    helper.mint_assets(&bank, &[reward.clone()]);
+    helper.mint_assets(&bank, std::slice::from_ref(&reward));
 
     let (schedule, _) = helper.create_schedule(&reward, 2).unwrap();
     helper.mint_coin(&bank, &incentivization_fee);
@@ -1163,7 +1224,7 @@ fn test_incentives_with_blocked() {
             &bank,
             pair_info.liquidity_token.as_str(),
             schedule.clone(),
-            &[incentivization_fee.clone()],
+            std::slice::from_ref(&incentivization_fee),
         )
         .unwrap_err();
     assert_eq!(
@@ -1215,7 +1276,7 @@ fn test_remove_rewards() {
     let reward = reward_asset_info.with_balance(1000_000000u128);
     let (schedule, _) = helper.create_schedule(&reward, 1).unwrap();
 
-    helper.mint_assets(&bank, &[reward.clone()]);
+    helper.mint_assets(&bank, std::slice::from_ref(&reward));
     helper.mint_coin(&bank, &incentivization_fee);
 
     helper
@@ -1223,7 +1284,7 @@ fn test_remove_rewards() {
             &bank,
             &lp_token,
             schedule.clone(),
-            &[incentivization_fee.clone()],
+            std::slice::from_ref(&incentivization_fee),
         )
         .unwrap();
 
@@ -1338,7 +1399,7 @@ fn test_long_unclaimed_rewards() {
     let max_end = schedules.iter().map(|(_, sch)| sch.end_ts).max().unwrap();
     // Create multiple schedules with different rewards (starts on the next week)
     for (ind, (schedule, _)) in schedules.iter().enumerate() {
-        helper.mint_assets(&bank, &[schedule.reward.clone()]);
+        helper.mint_assets(&bank, std::slice::from_ref(&schedule.reward));
         let mut attach_funds = vec![];
         if ind % 2 == 0 {
             helper.mint_coin(&bank, &incentivization_fee);
@@ -1482,7 +1543,7 @@ fn test_queries() {
         .map(|i| helper.create_schedule(&reward, i).unwrap())
         .collect();
     for (ind, (schedule, _)) in schedules.iter().enumerate() {
-        helper.mint_assets(&bank, &[reward.clone()]);
+        helper.mint_assets(&bank, std::slice::from_ref(&reward));
         if ind == 0 {
             // attach incentivization fee on the first schedule
             helper.mint_coin(&bank, &incentivization_fee);
@@ -1491,7 +1552,7 @@ fn test_queries() {
                     &bank,
                     &lp_token,
                     schedule.clone(),
-                    &[incentivization_fee.clone()],
+                    std::slice::from_ref(&incentivization_fee),
                 )
                 .unwrap();
         } else {
@@ -1731,7 +1792,7 @@ fn test_incentive_without_funds() {
     let bank = helper.app.api().addr_make("bank");
     let reward_asset_info = usdc.clone();
     let reward = reward_asset_info.with_balance(1000_000000u128);
-    helper.mint_assets(&bank, &[reward.clone()]);
+    helper.mint_assets(&bank, std::slice::from_ref(&reward));
     let (schedule, _) = helper.create_schedule(&reward, 2).unwrap();
     let incentivization_fee = helper.incentivization_fee.clone();
     helper.mint_coin(&bank, &incentivization_fee);
@@ -1794,7 +1855,7 @@ fn test_user_claim_less() {
     let reward = reward_asset_info.with_balance(1000_000000u128);
 
     // create reward schedule
-    helper.mint_assets(&bank, &[reward.clone()]);
+    helper.mint_assets(&bank, std::slice::from_ref(&reward));
     let (schedule, internal_sch) = helper.create_schedule(&reward, 2).unwrap();
     helper.mint_coin(&bank, &incentivization_fee);
     helper
@@ -1802,7 +1863,7 @@ fn test_user_claim_less() {
             &bank,
             &lp_token,
             schedule.clone(),
-            &[incentivization_fee.clone()],
+            std::slice::from_ref(&incentivization_fee),
         )
         .unwrap();
 
@@ -1819,7 +1880,7 @@ fn test_user_claim_less() {
         .update_block(|block| block.time = Timestamp::from_seconds(internal_sch.end_ts + 1));
 
     // create reward schedule again
-    helper.mint_assets(&bank, &[reward.clone()]);
+    helper.mint_assets(&bank, std::slice::from_ref(&reward));
     let (schedule, internal_sch) = helper.create_schedule(&reward, 2).unwrap();
     helper.mint_coin(&bank, &incentivization_fee);
     helper
@@ -1827,7 +1888,7 @@ fn test_user_claim_less() {
             &bank,
             &lp_token,
             schedule.clone(),
-            &[incentivization_fee.clone()],
+            std::slice::from_ref(&incentivization_fee),
         )
         .unwrap();
 
@@ -1907,14 +1968,14 @@ fn test_broken_cw20_incentives() {
 
     // Create multiple schedules with different rewards
     for (schedule, _) in &schedules {
-        helper.mint_assets(&bank, &[schedule.reward.clone()]);
+        helper.mint_assets(&bank, std::slice::from_ref(&schedule.reward));
         helper.mint_coin(&bank, &incentivization_fee);
         helper
             .incentivize(
                 &bank,
                 &lp_token,
                 schedule.clone(),
-                &[incentivization_fee.clone()],
+                std::slice::from_ref(&incentivization_fee),
             )
             .unwrap();
     }
@@ -1974,14 +2035,14 @@ fn test_orphaned_rewards() {
         .collect();
     // Create multiple schedules with different rewards
     for (schedule, _) in &schedules {
-        helper.mint_assets(&bank, &[schedule.reward.clone()]);
+        helper.mint_assets(&bank, std::slice::from_ref(&schedule.reward));
         helper.mint_coin(&bank, &incentivization_fee);
         helper
             .incentivize(
                 &bank,
                 &lp_token,
                 schedule.clone(),
-                &[incentivization_fee.clone()],
+                std::slice::from_ref(&incentivization_fee),
             )
             .unwrap();
     }
@@ -2013,7 +2074,7 @@ fn test_orphaned_rewards() {
             &bank,
             &lp_token,
             new_schedule,
-            &[incentivization_fee.clone()],
+            std::slice::from_ref(&incentivization_fee),
         )
         .unwrap();
 
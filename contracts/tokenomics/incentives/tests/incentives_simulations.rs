@@ -1,3 +1,4 @@
+#![allow(clippy::inconsistent_digit_grouping)]
 #![allow(dead_code)]
 extern crate core;
 
@@ -240,7 +241,7 @@ fn simulate_case(events: Vec<(Event, u64)>) {
                         helper.mint_coin(&bank, &incentivization_fee);
                         attach_funds.push(incentivization_fee.clone());
                     }
-                    helper.mint_assets(&bank, &[schedule.reward.clone()]);
+                    helper.mint_assets(&bank, std::slice::from_ref(&schedule.reward));
                     helper
                         .incentivize(&bank, lp_token, schedule, &attach_funds)
                         .unwrap();
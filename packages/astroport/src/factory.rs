@@ -21,14 +21,21 @@ pub struct Config {
     pub fee_address: Option<Addr>,
     /// The address of the contract that contains the coins with their precision
     pub coin_registry_address: Addr,
+    /// Address allowed to pause pairs alongside the owner
+    #[serde(default)]
+    pub guardian: Option<Addr>,
 }
 
 /// This enum describes available pair types.
 /// ## Available pool types
 /// ```
-/// # use astroport::factory::PairType::{Custom, Concentrated, Xyk};
+/// # use astroport::factory::PairType::{Clmm, Custom, Concentrated, Lbp, Stable, Weighted, Xyk};
 /// Xyk {};
+/// Stable {};
 /// Concentrated {};
+/// Weighted {};
+/// Lbp {};
+/// Clmm {};
 /// Custom(String::from("Custom"));
 /// ```
 #[derive(Eq)]
@@ -36,8 +43,16 @@ pub struct Config {
 pub enum PairType {
     /// XYK pair type
     Xyk {},
+    /// Stable pair type
+    Stable {},
     /// Concentrated liquidity type
     Concentrated {},
+    /// Weighted pair type
+    Weighted {},
+    /// Liquidity bootstrapping pair type
+    Lbp {},
+    /// Tick-based concentrated liquidity pair type
+    Clmm {},
     /// Custom pair type
     Custom(String),
 }
@@ -47,7 +62,11 @@ impl Display for PairType {
     fn fmt(&self, fmt: &mut Formatter) -> Result {
         match self {
             PairType::Xyk {} => fmt.write_str("xyk"),
+            PairType::Stable {} => fmt.write_str("stable"),
             PairType::Concentrated {} => fmt.write_str("concentrated"),
+            PairType::Weighted {} => fmt.write_str("weighted"),
+            PairType::Lbp {} => fmt.write_str("lbp"),
+            PairType::Clmm {} => fmt.write_str("clmm"),
             PairType::Custom(pair_type) => fmt.write_str(format!("custom-{}", pair_type).as_str()),
         }
     }
@@ -78,6 +97,30 @@ pub struct PairConfig {
     /// Default is false.
     #[serde(default)]
     pub permissioned: bool,
+    /// If true, pairs of this type issue LP shares as token factory denoms
+    /// (`factory/{pair}/astroport/share`) instead of instantiating a CW20 LP token.
+    /// Default is false.
+    #[serde(default)]
+    pub native_lp_token: bool,
+}
+
+/// This enum describes the pair actions which can be paused.
+#[cw_serde]
+#[derive(Copy, Eq)]
+pub enum PauseScope {
+    /// Only swaps are paused
+    Swap,
+    /// Only liquidity provision is paused
+    Provide,
+    /// Both swaps and liquidity provision are paused
+    All,
+}
+
+impl PauseScope {
+    /// Returns true if pausing this scope blocks the given action.
+    pub fn covers(&self, action: PauseScope) -> bool {
+        *self == PauseScope::All || *self == action
+    }
 }
 
 impl PairConfig {
@@ -87,6 +130,24 @@ impl PairConfig {
     }
 }
 
+/// This structure stores fee overrides of a specific pair.
+#[cw_serde]
+#[derive(Default)]
+pub struct PairFees {
+    /// Total amount of fees (in bps) charged on a swap
+    pub total_fee_bps: Option<u16>,
+    /// Amount of fees (in bps) sent to the Maker contract
+    pub maker_fee_bps: Option<u16>,
+}
+
+impl PairFees {
+    /// This method is used to check fee bps.
+    pub fn valid_fee_bps(&self) -> bool {
+        self.total_fee_bps.unwrap_or_default() <= MAX_TOTAL_FEE_BPS
+            && self.maker_fee_bps.unwrap_or_default() <= MAX_MAKER_FEE_BPS
+    }
+}
+
 /// This structure stores the basic settings for creating a new factory contract.
 #[cw_serde]
 pub struct InstantiateMsg {
@@ -144,6 +205,35 @@ pub enum ExecuteMsg {
     DropOwnershipProposal {},
     /// Used to claim contract ownership.
     ClaimOwnership {},
+    /// Sets or removes the guardian which is allowed to pause pairs. Only the owner can execute this.
+    UpdateGuardian {
+        /// New guardian address. `None` removes the guardian
+        guardian: Option<String>,
+    },
+    /// Pauses the given scope for a pair or for all pairs if `pair` is `None`.
+    /// Can be executed by the owner or the guardian. Withdrawals are never paused.
+    Pause {
+        /// The pair to pause. Pauses all pairs if `None`
+        pair: Option<String>,
+        /// The pair actions to pause
+        scope: PauseScope,
+    },
+    /// Lifts a pause from a pair or the global pause if `pair` is `None`.
+    /// Only the owner can execute this.
+    Unpause {
+        /// The pair to unpause. Lifts the global pause if `None`
+        pair: Option<String>,
+    },
+    /// Overrides the fees of a specific pair. Fees set to `None` fall back to the pair type config.
+    /// Only the owner can execute this.
+    UpdatePairFees {
+        /// The pair which fees are overridden
+        pair_addr: String,
+        /// Total amount of fees (in bps) charged on a swap
+        total_fee_bps: Option<u16>,
+        /// Amount of fees (in bps) sent to the Maker contract
+        maker_fee_bps: Option<u16>,
+    },
 }
 
 /// This structure describes the available query messages for the factory contract.
@@ -182,10 +272,18 @@ pub enum QueryMsg {
     FeeInfo {
         /// The pair type for which we return fee information. Pair type is a [`PairType`] struct
         pair_type: PairType,
+        /// The pair which fee overrides take precedence over the pair type fees. Optional
+        pair_addr: Option<String>,
     },
     /// Returns a vector that contains blacklisted pair types
     #[returns(Vec<PairType>)]
     BlacklistedPairTypes {},
+    /// Returns the pause status of a pair. Returns only the global pause if `pair` is `None`
+    #[returns(PauseStatusResponse)]
+    PauseStatus {
+        /// The pair for which we return the pause status
+        pair: Option<String>,
+    },
 }
 
 /// A custom struct for each query response that returns general contract settings/configs.
@@ -203,6 +301,8 @@ pub struct ConfigResponse {
     pub incentives_address: Option<Addr>,
     /// The address of the contract that contains the coins and their accuracy
     pub coin_registry_address: Addr,
+    /// Address allowed to pause pairs alongside the owner
+    pub guardian: Option<Addr>,
 }
 
 /// A custom struct for each query response that returns an object of type [`FeeInfoResponse`].
@@ -215,3 +315,23 @@ pub struct FeeInfoResponse {
     /// Amount of fees (in bps) sent to the Maker contract
     pub maker_fee_bps: u16,
 }
+
+/// This structure describes the pause status of a pair.
+#[cw_serde]
+#[derive(Default)]
+pub struct PauseStatusResponse {
+    /// The pause applied to all pairs
+    pub global: Option<PauseScope>,
+    /// The pause applied to the specific pair
+    pub pair: Option<PauseScope>,
+}
+
+impl PauseStatusResponse {
+    /// Returns true if the given action is paused either globally or for the pair.
+    pub fn is_paused(&self, action: PauseScope) -> bool {
+        self.global
+            .iter()
+            .chain(self.pair.iter())
+            .any(|scope| scope.covers(action))
+    }
+}
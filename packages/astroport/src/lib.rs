@@ -3,10 +3,16 @@ pub mod common;
 pub mod cosmwasm_ext;
 pub mod factory;
 pub mod native_coin_registry;
+pub mod observation;
+pub mod oracle;
 pub mod pair;
+pub mod pair_clmm;
 pub mod pair_concentrated;
 pub mod querier;
+pub mod rate_provider;
 pub mod router;
+pub mod stats;
+pub mod token_factory;
 pub mod vesting;
 
 pub mod incentives;
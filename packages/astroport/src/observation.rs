@@ -0,0 +1,239 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{StdError, StdResult, Storage, Uint128};
+use cw_storage_plus::Deque;
+
+use crate::asset::AssetInfo;
+
+/// The maximum number of observations kept by a pair.
+/// The oldest observation is dropped once the limit is reached.
+pub const OBSERVATIONS_LIMIT: u32 = 1000;
+
+/// Cumulative prices of the pool assets at the given timestamp
+#[cw_serde]
+pub struct Observation {
+    /// Timestamp in seconds
+    pub timestamp: u64,
+    /// The vector contains cumulative prices for each pair of assets in the pool
+    pub cumulative_prices: Vec<(AssetInfo, AssetInfo, Uint128)>,
+}
+
+/// Records a new observation in the ring buffer dropping the oldest one if the buffer is full.
+/// The observation is skipped if the buffer already has one with the same or later timestamp.
+pub fn store_observation(
+    storage: &mut dyn Storage,
+    observations: &Deque<Observation>,
+    observation: &Observation,
+) -> StdResult<()> {
+    if let Some(last) = observations.back(storage)? {
+        if last.timestamp >= observation.timestamp {
+            return Ok(());
+        }
+    }
+
+    observations.push_back(storage, observation)?;
+    if observations.len(storage)? > OBSERVATIONS_LIMIT {
+        observations.pop_front(storage)?;
+    }
+
+    Ok(())
+}
+
+/// Returns cumulative prices at each moment `seconds_ago` from the **current** observation.
+/// Cumulative prices between two recorded observations are linearly interpolated as the pool
+/// price is constant between them.
+///
+/// * **current** contains cumulative prices at the current block time.
+pub fn query_observations(
+    storage: &dyn Storage,
+    observations: &Deque<Observation>,
+    current: &Observation,
+    seconds_ago: Vec<u64>,
+) -> StdResult<Vec<Observation>> {
+    seconds_ago
+        .into_iter()
+        .map(|seconds_ago| {
+            let timestamp = current.timestamp.checked_sub(seconds_ago).ok_or_else(|| {
+                StdError::generic_err(format!("Invalid seconds_ago value: {seconds_ago}"))
+            })?;
+            observe_at(storage, observations, current, timestamp)
+        })
+        .collect()
+}
+
+/// Returns cumulative prices at the given **timestamp** which must not be older than
+/// the oldest recorded observation.
+fn observe_at(
+    storage: &dyn Storage,
+    observations: &Deque<Observation>,
+    current: &Observation,
+    timestamp: u64,
+) -> StdResult<Observation> {
+    if timestamp == current.timestamp {
+        return Ok(current.clone());
+    }
+
+    // Find the latest observation recorded at or before the timestamp
+    let mut low = 0;
+    let mut high = observations.len(storage)?;
+    while low < high {
+        let mid = low + (high - low) / 2;
+        if load_observation(storage, observations, mid)?.timestamp <= timestamp {
+            low = mid + 1;
+        } else {
+            high = mid;
+        }
+    }
+
+    if low == 0 {
+        return Err(StdError::generic_err(format!(
+            "Observation at {timestamp} is older than the oldest recorded one"
+        )));
+    }
+
+    let before = load_observation(storage, observations, low - 1)?;
+    if before.timestamp == timestamp {
+        return Ok(before);
+    }
+    let after = match observations.get(storage, low)? {
+        Some(observation) => observation,
+        None => current.clone(),
+    };
+
+    let cumulative_prices = before
+        .cumulative_prices
+        .into_iter()
+        .zip(after.cumulative_prices)
+        .map(|((from, to, before_value), (.., after_value))| {
+            // Cumulative prices wrap on overflow thus we use wrapping arithmetic here
+            let delta = after_value.wrapping_sub(before_value).multiply_ratio(
+                timestamp - before.timestamp,
+                after.timestamp - before.timestamp,
+            );
+            (from, to, before_value.wrapping_add(delta))
+        })
+        .collect();
+
+    Ok(Observation {
+        timestamp,
+        cumulative_prices,
+    })
+}
+
+fn load_observation(
+    storage: &dyn Storage,
+    observations: &Deque<Observation>,
+    index: u32,
+) -> StdResult<Observation> {
+    observations
+        .get(storage, index)?
+        .ok_or_else(|| StdError::generic_err(format!("Observation {index} not found")))
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::testing::MockStorage;
+
+    use crate::asset::native_asset_info;
+
+    use super::*;
+
+    const OBSERVATIONS: Deque<Observation> = Deque::new("observations");
+
+    fn observation(timestamp: u64, value: u128) -> Observation {
+        Observation {
+            timestamp,
+            cumulative_prices: vec![(
+                native_asset_info("uusd".to_string()),
+                native_asset_info("uluna".to_string()),
+                Uint128::new(value),
+            )],
+        }
+    }
+
+    fn values(observations: Vec<Observation>) -> Vec<(u64, u128)> {
+        observations
+            .into_iter()
+            .map(|obs| (obs.timestamp, obs.cumulative_prices[0].2.u128()))
+            .collect()
+    }
+
+    #[test]
+    fn test_store_observation() {
+        let mut storage = MockStorage::new();
+
+        for i in 0..OBSERVATIONS_LIMIT + 10 {
+            store_observation(
+                &mut storage,
+                &OBSERVATIONS,
+                &observation(i as u64 + 1, i as u128),
+            )
+            .unwrap();
+        }
+        // Observations with the same timestamp are skipped
+        store_observation(&mut storage, &OBSERVATIONS, &observation(10, 0)).unwrap();
+
+        assert_eq!(OBSERVATIONS.len(&storage).unwrap(), OBSERVATIONS_LIMIT);
+        assert_eq!(
+            OBSERVATIONS.front(&storage).unwrap().unwrap(),
+            observation(11, 10)
+        );
+        assert_eq!(
+            OBSERVATIONS.back(&storage).unwrap().unwrap(),
+            observation(
+                OBSERVATIONS_LIMIT as u64 + 10,
+                OBSERVATIONS_LIMIT as u128 + 9
+            )
+        );
+    }
+
+    #[test]
+    fn test_query_observations() {
+        let mut storage = MockStorage::new();
+        store_observation(&mut storage, &OBSERVATIONS, &observation(100, 0)).unwrap();
+        store_observation(&mut storage, &OBSERVATIONS, &observation(110, 1000)).unwrap();
+        store_observation(&mut storage, &OBSERVATIONS, &observation(130, 1400)).unwrap();
+
+        let current = observation(150, 3400);
+        let res = query_observations(
+            &storage,
+            &OBSERVATIONS,
+            &current,
+            vec![0, 10, 20, 25, 40, 45, 50],
+        )
+        .unwrap();
+        assert_eq!(
+            values(res),
+            vec![
+                (150, 3400),
+                (140, 2400),
+                (130, 1400),
+                (125, 1300),
+                (110, 1000),
+                (105, 500),
+                (100, 0)
+            ]
+        );
+
+        let err = query_observations(&storage, &OBSERVATIONS, &current, vec![51]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Generic error: Observation at 99 is older than the oldest recorded one"
+        );
+        query_observations(&storage, &OBSERVATIONS, &current, vec![151]).unwrap_err();
+    }
+
+    #[test]
+    fn test_interpolation_with_overflow() {
+        let mut storage = MockStorage::new();
+        store_observation(
+            &mut storage,
+            &OBSERVATIONS,
+            &observation(100, u128::MAX - 99),
+        )
+        .unwrap();
+
+        let current = observation(110, 100);
+        let res = query_observations(&storage, &OBSERVATIONS, &current, vec![5]).unwrap();
+        assert_eq!(values(res), vec![(105, 0)]);
+    }
+}
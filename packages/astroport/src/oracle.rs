@@ -0,0 +1,19 @@
+use cosmwasm_schema::{cw_serde, QueryResponses};
+use cosmwasm_std::{Uint128, Uint256};
+
+use crate::asset::AssetInfo;
+
+/// This structure describes the query messages of the Astroport oracle contract
+/// which are used by other contracts.
+#[cw_serde]
+#[derive(QueryResponses)]
+pub enum QueryMsg {
+    /// Calculates a new TWAP with updated precision
+    #[returns(Vec<(AssetInfo, Uint256)>)]
+    Consult {
+        /// The asset for which to compute a new TWAP value
+        token: AssetInfo,
+        /// The amount of tokens for which to compute the token price
+        amount: Uint128,
+    },
+}
@@ -1,9 +1,17 @@
+use std::ops::RangeInclusive;
+
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use cosmwasm_std::{Addr, Binary, Decimal, Decimal256, Uint128};
+use cosmwasm_std::{
+    wasm_execute, Addr, Binary, BlockInfo, CosmosMsg, CustomMsg, CustomQuery, Decimal, Decimal256,
+    QuerierWrapper, ReplyOn, StdResult, SubMsg, Uint128, Uint256, Uint64,
+};
 use cw20::Cw20ReceiveMsg;
 
-use crate::asset::{Asset, AssetInfo, PairInfo};
+use crate::asset::{Asset, AssetInfo, AssetInfoExt, PairInfo};
 use crate::factory::PairType;
+use crate::observation::Observation;
+use crate::querier::query_cw4_membership;
+use crate::stats::StatsResponse;
 
 /// The default swap slippage
 pub const DEFAULT_SLIPPAGE: &str = "0.005";
@@ -12,6 +20,28 @@ pub const MAX_ALLOWED_SLIPPAGE: &str = "0.5";
 /// The maximum fee share allowed, 10%
 pub const MAX_FEE_SHARE_BPS: u16 = 1000;
 
+/// The maximum number of hooks a pair can notify
+pub const MAX_HOOKS: usize = 5;
+/// Validation constraints for the gas limit of a single hook call.
+/// If a non-blocking hook hits this gas limit, its failure is ignored.
+pub const HOOK_GAS_LIMIT: RangeInclusive<u64> = 100_000..=1_500_000u64;
+
+/// The maximum dynamic fee allowed, 10%
+pub const MAX_DYNAMIC_FEE_BPS: u16 = 1000;
+/// Validation constraints for the TWAP window (in seconds) of the dynamic fee
+pub const DYNAMIC_FEE_WINDOW_LIMITS: RangeInclusive<u64> = 60..=86_400u64;
+
+/// Validation constraints for the maximum intra-block price deviation
+pub const PRICE_DEVIATION_LIMITS: RangeInclusive<Decimal> =
+    Decimal::permille(1)..=Decimal::percent(50);
+
+/// The maximum number of swap orders queued in a batch auction pair
+pub const MAX_BATCH_ORDERS: usize = 50;
+
+/// The maximum number of addresses in a trader allowlist.
+/// Larger sets of traders should be managed with a cw4 group
+pub const MAX_ALLOWLIST_ADDRESSES: usize = 100;
+
 /// Decimal precision for TWAP results
 pub const TWAP_PRECISION: u8 = 6;
 
@@ -32,6 +62,9 @@ pub struct InstantiateMsg {
     pub factory_addr: String,
     /// Optional binary serialised parameters for custom pool types
     pub init_params: Option<Binary>,
+    /// Whether LP shares are issued as a token factory denom instead of a CW20 token
+    #[serde(default)]
+    pub native_lp_token: bool,
 }
 
 /// This structure describes the execute messages available in the contract.
@@ -50,6 +83,8 @@ pub enum ExecuteMsg {
         /// The receiver of LP tokens
         receiver: Option<String>,
         min_lp_to_receive: Option<Uint128>,
+        /// The block time (in seconds) after which the transaction is rejected
+        deadline: Option<u64>,
     },
     /// Swap performs a swap in the pool
     Swap {
@@ -58,7 +93,53 @@ pub enum ExecuteMsg {
         belief_price: Option<Decimal>,
         max_spread: Option<Decimal>,
         to: Option<String>,
+        /// The block time (in seconds) after which the transaction is rejected
+        deadline: Option<u64>,
+    },
+    /// Swaps the offer asset sent along with the message for the exact amount of the ask asset.
+    /// Unused offer funds are refunded to the sender
+    SwapExactOut {
+        /// The asset and the exact amount to receive
+        ask_asset: Asset,
+        /// The maximum amount of the offer asset to spend
+        max_offer_amount: Uint128,
+        to: Option<String>,
+        /// The block time (in seconds) after which the transaction is rejected
+        deadline: Option<u64>,
     },
+    /// Sends the ask asset to the sender and executes `callback_msg` on the sender.
+    /// By the end of the callback the borrowed amount plus swap fees must be transferred back
+    /// to the pair in any of the pool assets, otherwise the whole transaction is reverted
+    FlashSwap {
+        ask_asset: Asset,
+        callback_msg: Binary,
+        /// The block time (in seconds) after which the transaction is rejected
+        deadline: Option<u64>,
+    },
+    /// Withdraw liquidity from the pool by sending the LP token denom as funds.
+    /// Only available for pairs issuing LP shares as a token factory denom
+    WithdrawLiquidity {
+        min_assets_to_receive: Option<Vec<Asset>>,
+        /// Withdraw all liquidity in the specified asset.
+        /// Supported only by the concentrated and the weighted pairs
+        asset: Option<AssetInfo>,
+        /// The block time (in seconds) after which the transaction is rejected
+        deadline: Option<u64>,
+    },
+    /// Sends the pair balances exceeding the tracked reserves to the recipient (the sender by default)
+    Skim { to: Option<String> },
+    /// Updates the tracked reserves to match the pair balances
+    Sync {},
+    /// Mints the protocol share of the swap fees accrued since the last liquidity event
+    /// as LP tokens to the fee address. Supported only by the XYK and the concentrated pairs
+    /// with the protocol fee accrual enabled
+    CollectProtocolFees {},
+    /// Executes the swap orders queued before the current block at a single uniform price.
+    /// Supported only by the XYK pair
+    SettleBatch {},
+    /// Cancels a queued swap order and refunds the offer asset to the trader.
+    /// Supported only by the XYK pair
+    CancelBatchOrder { order_id: u64 },
     /// Update the pair configuration
     UpdateConfig { params: Binary },
     /// ProposeNewOwner creates a proposal to change contract ownership.
@@ -75,6 +156,20 @@ pub enum ExecuteMsg {
     ClaimOwnership {},
 }
 
+impl ExecuteMsg {
+    /// Returns the deadline of swap and liquidity messages.
+    pub fn deadline(&self) -> Option<u64> {
+        match self {
+            ExecuteMsg::ProvideLiquidity { deadline, .. }
+            | ExecuteMsg::Swap { deadline, .. }
+            | ExecuteMsg::SwapExactOut { deadline, .. }
+            | ExecuteMsg::FlashSwap { deadline, .. }
+            | ExecuteMsg::WithdrawLiquidity { deadline, .. } => *deadline,
+            _ => None,
+        }
+    }
+}
+
 /// This structure describes a CW20 hook message.
 #[cw_serde]
 pub enum Cw20HookMsg {
@@ -84,13 +179,45 @@ pub enum Cw20HookMsg {
         belief_price: Option<Decimal>,
         max_spread: Option<Decimal>,
         to: Option<String>,
+        /// The block time (in seconds) after which the transaction is rejected
+        deadline: Option<u64>,
+    },
+    /// Swap the sent tokens for the exact amount of the ask asset.
+    /// The sent amount is the maximum offer amount, unused tokens are refunded to the sender
+    SwapExactOut {
+        ask_asset: Asset,
+        to: Option<String>,
+        /// The block time (in seconds) after which the transaction is rejected
+        deadline: Option<u64>,
     },
     /// Withdraw liquidity from the pool
     WithdrawLiquidity {
         min_assets_to_receive: Option<Vec<Asset>>,
+        /// Withdraw all liquidity in the specified asset.
+        /// Supported only by the concentrated and the weighted pairs
+        asset: Option<AssetInfo>,
+        /// The block time (in seconds) after which the transaction is rejected
+        deadline: Option<u64>,
     },
 }
 
+impl Cw20HookMsg {
+    /// Returns the deadline of swap and liquidity messages.
+    pub fn deadline(&self) -> Option<u64> {
+        match self {
+            Cw20HookMsg::Swap { deadline, .. }
+            | Cw20HookMsg::SwapExactOut { deadline, .. }
+            | Cw20HookMsg::WithdrawLiquidity { deadline, .. } => *deadline,
+        }
+    }
+}
+
+/// Returns true if the block time is past the **deadline** (in seconds).
+/// Messages without a deadline never expire.
+pub fn is_deadline_exceeded(block: &BlockInfo, deadline: Option<u64>) -> bool {
+    deadline.is_some_and(|deadline| block.time.seconds() > deadline)
+}
+
 /// This structure describes the query messages available in the contract.
 #[cw_serde]
 #[derive(QueryResponses)]
@@ -125,15 +252,65 @@ pub enum QueryMsg {
     /// Returns current D invariant in as a [`Uint128`] value
     #[returns(Uint128)]
     QueryComputeD {},
-    /// Returns an estimation of assets received for the given amount of LP tokens
+    /// Returns an estimation of assets received for the given amount of LP tokens.
+    /// If **asset** is specified, returns the amount of the asset received for withdrawing
+    /// all liquidity in it. Supported only by the weighted pair
     #[returns(Vec<Asset>)]
-    SimulateWithdraw { lp_amount: Uint128 },
+    SimulateWithdraw {
+        lp_amount: Uint128,
+        asset: Option<AssetInfo>,
+    },
     /// Returns an estimation of shares received for the given amount of assets
     #[returns(Uint128)]
     SimulateProvide {
         assets: Vec<Asset>,
         slippage_tolerance: Option<Decimal>,
     },
+    /// Returns the balance of the specified asset that was in the pool just preceding the moment
+    /// of the specified block height creation. Returns `None` if the balance was not recorded yet.
+    #[returns(Option<Uint128>)]
+    AssetBalanceAt {
+        asset_info: AssetInfo,
+        block_height: Uint64,
+    },
+    /// Returns cumulative prices at each moment `seconds_ago` from the current block time.
+    /// Cumulative prices between recorded observations are linearly interpolated.
+    #[returns(Vec<Observation>)]
+    Observe { seconds_ago: Vec<u64> },
+    /// Returns swap volumes and fees accumulated during the given epoch (the current one
+    /// by default) and over the pair lifetime. Supported only by the XYK, stable and PCL pairs
+    #[returns(StatsResponse)]
+    Stats { epoch: Option<u64> },
+    /// Returns a queued swap order in a [`BatchOrder`] object. Supported only by the XYK pair
+    #[returns(BatchOrder)]
+    BatchOrder { order_id: u64 },
+    /// Returns queued swap orders, optionally only the ones of the given trader.
+    /// Supported only by the XYK pair
+    #[returns(Vec<BatchOrder>)]
+    BatchOrders {
+        trader: Option<String>,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+}
+
+/// A swap order queued in a batch auction pair until the batch is settled
+#[cw_serde]
+pub struct BatchOrder {
+    /// The order identifier
+    pub order_id: u64,
+    /// The address which placed the order. Refunds are sent to this address
+    pub trader: Addr,
+    /// The recipient of the swap
+    pub receiver: Addr,
+    /// The asset offered to the pool
+    pub offer_asset: Asset,
+    /// The asset to receive
+    pub ask_asset_info: AssetInfo,
+    pub belief_price: Option<Decimal>,
+    pub max_spread: Option<Decimal>,
+    /// The block height at which the order was placed
+    pub block_height: u64,
 }
 
 /// This struct is used to return a query result with the total amount of LP tokens and assets in a specific pool.
@@ -161,12 +338,115 @@ pub struct ConfigResponse {
 /// Holds the configuration for fee sharing
 #[cw_serde]
 pub struct FeeShareConfig {
+    /// Parties receiving a share of swap fees
+    pub recipients: Vec<FeeShareRecipient>,
+}
+
+/// The fee share config stored by pairs before multiple recipients were supported.
+/// It is converted to [`FeeShareConfig`] when pairs are migrated
+#[cw_serde]
+pub struct LegacyFeeShareConfig {
     /// The fee shared with the address
     pub bps: u16,
     /// The share is sent to this address on every swap
     pub recipient: Addr,
 }
 
+impl From<LegacyFeeShareConfig> for FeeShareConfig {
+    fn from(legacy: LegacyFeeShareConfig) -> Self {
+        Self::single(legacy.recipient, legacy.bps)
+    }
+}
+
+/// A single party receiving a share of swap fees
+#[cw_serde]
+pub struct FeeShareRecipient {
+    /// The share is sent to this address on every swap
+    pub recipient: Addr,
+    /// The fee shared with the address
+    pub bps: u16,
+}
+
+impl FeeShareConfig {
+    /// Returns the fee share config with a single recipient.
+    pub fn single(recipient: Addr, bps: u16) -> Self {
+        Self {
+            recipients: vec![FeeShareRecipient { recipient, bps }],
+        }
+    }
+
+    /// Returns the total fee shared with all recipients.
+    pub fn total_bps(&self) -> u16 {
+        self.recipients.iter().map(|recipient| recipient.bps).sum()
+    }
+
+    /// Splits the shared amount between recipients proportionally to their bps.
+    /// Rounding leftovers are sent to the last recipient.
+    pub fn split(&self, amount: Uint128) -> Vec<(Addr, Uint128)> {
+        let total_bps = self.total_bps();
+        let mut remaining = amount;
+
+        self.recipients
+            .iter()
+            .enumerate()
+            .map(|(ind, recipient)| {
+                let share = if ind == self.recipients.len() - 1 {
+                    remaining
+                } else {
+                    amount.multiply_ratio(recipient.bps, total_bps)
+                };
+                remaining -= share;
+
+                (recipient.recipient.clone(), share)
+            })
+            .collect()
+    }
+
+    /// Builds transfer messages sending the shared amount of the given asset to all recipients.
+    pub fn into_msgs<T: CustomMsg>(
+        &self,
+        asset_info: &AssetInfo,
+        amount: Uint128,
+    ) -> StdResult<Vec<CosmosMsg<T>>> {
+        self.split(amount)
+            .into_iter()
+            .filter(|(_, share)| !share.is_zero())
+            .map(|(recipient, share)| asset_info.with_balance(share).into_msg(recipient))
+            .collect()
+    }
+}
+
+/// Returns the amount of LP tokens to mint to the protocol so that it owns **fee_rate**
+/// of the pool growth since the last liquidity event (the Uniswap V2 `kLast` approach):
+/// `total_share * fee_rate * growth / (liquidity - fee_rate * growth)`.
+///
+/// * **liquidity** and **liquidity_last** are the current and the recorded measure of the pool
+///   value per LP token (e.g. `sqrt(k)` while the LP supply is unchanged). It grows only with swap fees.
+pub fn calc_protocol_fee_share(
+    total_share: Uint128,
+    liquidity: Decimal256,
+    liquidity_last: Decimal256,
+    fee_rate: Decimal,
+) -> StdResult<Uint128> {
+    if total_share.is_zero() || liquidity_last.is_zero() || liquidity <= liquidity_last {
+        return Ok(Uint128::zero());
+    }
+
+    let protocol_growth = (liquidity - liquidity_last) * Decimal256::from(fee_rate);
+    let share = protocol_growth / (liquidity - protocol_growth);
+
+    Ok(Uint256::from(total_share).mul_floor(share).try_into()?)
+}
+
+/// Returns the relative deviation of **price** from **open_price**.
+/// The deviation is unbounded if the open price is zero.
+pub fn price_deviation(open_price: Decimal256, price: Decimal256) -> Decimal256 {
+    price
+        .abs_diff(open_price)
+        .checked_div(open_price)
+        .unwrap_or(Decimal256::MAX)
+}
+
 /// This structure holds the parameters that are returned from a swap simulation response
 #[cw_serde]
 pub struct SimulationResponse {
@@ -200,11 +480,147 @@ pub struct CumulativePricesResponse {
     pub cumulative_prices: Vec<(AssetInfo, AssetInfo, Uint128)>,
 }
 
+/// This structure describes a contract notified by the pair after swaps and liquidity events.
+#[cw_serde]
+pub struct PairHook {
+    /// The hook contract address
+    pub contract_addr: Addr,
+    /// The gas limit of the hook call, must be within [`HOOK_GAS_LIMIT`]
+    pub gas_limit: u64,
+    /// Whether a failing hook reverts the whole transaction
+    pub blocking: bool,
+}
+
+/// This enum describes the notifications sent to pair hooks.
+#[cw_serde]
+pub enum PairHookMsg {
+    /// Sent after a swap
+    AfterSwap {
+        /// The asset sent by the trader
+        offer: Asset,
+        /// The asset received by the trader (or the receiver) after fees
+        ask: Asset,
+        /// The swap initiator
+        trader: Addr,
+    },
+    /// Sent after liquidity provision
+    AfterProvide {
+        /// The deposited assets
+        assets: Vec<Asset>,
+        /// The amount of minted LP tokens
+        share: Uint128,
+        /// The receiver of LP tokens
+        receiver: Addr,
+    },
+    /// Sent after liquidity withdrawal
+    AfterWithdraw {
+        /// The withdrawn assets
+        assets: Vec<Asset>,
+        /// The amount of burned LP tokens
+        share: Uint128,
+        /// The receiver of the withdrawn assets
+        receiver: Addr,
+    },
+}
+
+/// This enum describes the execute message every hook contract must handle.
+#[cw_serde]
+pub enum PairHookExecuteMsg {
+    PairHook(PairHookMsg),
+}
+
+impl PairHookMsg {
+    /// Builds a submessage for every hook. Non-blocking hooks reply with [`HOOK_REPLY_ID`]
+    /// on error so the pair can ignore their failures.
+    pub fn into_submsgs<T>(self, hooks: &[PairHook]) -> StdResult<Vec<SubMsg<T>>>
+    where
+        T: CustomMsg,
+    {
+        let msg = PairHookExecuteMsg::PairHook(self);
+
+        hooks
+            .iter()
+            .map(|hook| {
+                let (id, reply_on) = if hook.blocking {
+                    (0, ReplyOn::Never)
+                } else {
+                    (HOOK_REPLY_ID, ReplyOn::Error)
+                };
+
+                Ok(SubMsg {
+                    id,
+                    payload: Default::default(),
+                    msg: wasm_execute(&hook.contract_addr, &msg, vec![])?.into(),
+                    gas_limit: Some(hook.gas_limit),
+                    reply_on,
+                })
+            })
+            .collect()
+    }
+}
+
 /// This structure stores a XYK pool's configuration.
 #[cw_serde]
 pub struct XYKPoolConfig {
     /// The config for swap fee sharing
     pub fee_share: Option<FeeShareConfig>,
+    /// Contracts notified after swaps and liquidity events
+    #[serde(default)]
+    pub hooks: Vec<PairHook>,
+    /// The config for the volatility-based swap fee
+    #[serde(default)]
+    pub dynamic_fee: Option<DynamicFeeParams>,
+    /// Whether the maker fee accrues in the pool and is minted as LP tokens to the fee address
+    #[serde(default)]
+    pub protocol_fee_accrual: bool,
+    /// The maximum relative deviation of the pool price from the block open price allowed after a swap
+    #[serde(default)]
+    pub max_price_deviation: Option<Decimal>,
+    /// Whether swaps are queued and settled in batches at a uniform price
+    #[serde(default)]
+    pub batch_auction: bool,
+    /// The addresses allowed to swap and provide liquidity. Everyone is allowed if not set
+    #[serde(default)]
+    pub trader_allowlist: Option<TraderAllowlist>,
+}
+
+/// This enum describes who can swap and provide liquidity in a permissioned pair.
+#[cw_serde]
+pub enum TraderAllowlist {
+    /// Only the listed addresses. At most [`MAX_ALLOWLIST_ADDRESSES`] addresses can be listed
+    Addresses(Vec<Addr>),
+    /// Only the members of the cw4 group contract
+    Cw4Group(Addr),
+}
+
+impl TraderAllowlist {
+    /// Returns whether the address is allowed to trade.
+    pub fn is_allowed<C: CustomQuery>(
+        &self,
+        querier: &QuerierWrapper<C>,
+        addr: &Addr,
+    ) -> StdResult<bool> {
+        match self {
+            TraderAllowlist::Addresses(addresses) => Ok(addresses.contains(addr)),
+            TraderAllowlist::Cw4Group(group_addr) => {
+                query_cw4_membership(querier, group_addr, addr)
+            }
+        }
+    }
+}
+
+/// This structure describes the parameters of the volatility-based swap fee.
+/// The fee grows with the deviation of the spot price from the TWAP over the last `window` seconds.
+#[cw_serde]
+pub struct DynamicFeeParams {
+    /// The minimum fee (in bps) charged on a swap
+    pub min_fee_bps: u16,
+    /// The maximum fee (in bps) charged on a swap
+    pub max_fee_bps: u16,
+    /// The TWAP period (in seconds) the spot price is compared against
+    pub window: u64,
+    /// The fee added to the base fee per unit of the relative price deviation from the TWAP
+    pub volatility_multiplier: Decimal,
 }
 
 #[cw_serde]
@@ -216,7 +632,201 @@ pub enum XYKPoolUpdateParams {
         /// The fee_share_bps is sent to this address on every swap
         fee_share_address: String,
     },
+    /// Enables the sharing of swap fees with several parties.
+    /// Replaces the current fee share config.
+    EnableFeeShareRecipients {
+        /// Recipient addresses along with their fee shares in bps.
+        /// The total share must not exceed [`MAX_FEE_SHARE_BPS`]
+        recipients: Vec<(String, u16)>,
+    },
     DisableFeeShare,
+    /// Replaces the list of hooks notified after swaps and liquidity events.
+    SetHooks {
+        hooks: Vec<PairHook>,
+    },
+    /// Enables the volatility-based swap fee which replaces the factory fee rate.
+    EnableDynamicFee(DynamicFeeParams),
+    /// Returns to the factory fee rate.
+    DisableDynamicFee,
+    /// Keeps the maker fee in the pool instead of sending it on every swap.
+    /// The accrued fee is minted as LP tokens to the fee address on the next liquidity event
+    /// or on [`ExecuteMsg::CollectProtocolFees`].
+    EnableProtocolFeeAccrual,
+    /// Collects the accrued protocol fee and returns to sending the maker fee on every swap.
+    DisableProtocolFeeAccrual,
+    /// Rejects swaps moving the pool price by more than `max_deviation` relative to
+    /// the price at the start of the block.
+    /// The deviation must be within [`PRICE_DEVIATION_LIMITS`]
+    EnablePriceGuard {
+        max_deviation: Decimal,
+    },
+    /// Removes the intra-block price deviation limit.
+    DisablePriceGuard,
+    /// Queues swaps instead of executing them immediately. Orders placed within a block
+    /// are settled together at a uniform price with [`ExecuteMsg::SettleBatch`]
+    EnableBatchAuction,
+    /// Returns to executing swaps immediately. Queued orders can still be settled or cancelled
+    DisableBatchAuction,
+    /// Restricts swaps and liquidity provision to the allowlisted traders.
+    /// Replaces the current allowlist
+    SetTraderAllowlist(TraderAllowlist),
+    /// Allows everyone to swap and provide liquidity.
+    RemoveTraderAllowlist,
+}
+
+/// This structure holds stableswap pool parameters.
+#[cw_serde]
+pub struct StablePoolParams {
+    /// The current stableswap pool amplification
+    pub amp: u64,
+    /// The contract owner
+    pub owner: Option<String>,
+    /// Redemption rate sources for assets accruing value against their underlying (e.g. liquid staking tokens).
+    /// Assets without a rate source are valued 1:1
+    #[serde(default)]
+    pub rate_sources: Vec<AssetRateSource>,
+}
+
+/// This structure binds a pool asset to the source of its redemption rate.
+#[cw_serde]
+pub struct AssetRateSource {
+    /// The pool asset
+    pub asset_info: AssetInfo,
+    /// Where the asset redemption rate is queried from
+    pub source: RateSource,
+}
+
+/// This enum describes the supported redemption rate sources.
+#[cw_serde]
+pub enum RateSource {
+    /// A contract implementing [`crate::rate_provider::QueryMsg`]
+    Provider { contract_addr: String },
+    /// An Astroport oracle contract. The rate is the amount of the quote asset returned for one whole
+    /// unit of the pool asset, so the quote asset must have the same precision as the pool asset
+    Oracle { contract_addr: String },
+}
+
+/// This structure stores a stableswap pool's configuration.
+#[cw_serde]
+pub struct StablePoolConfig {
+    /// The stableswap pool amplification
+    pub amp: Decimal,
+    /// The config for swap fee sharing
+    pub fee_share: Option<FeeShareConfig>,
+    /// Redemption rate sources of the pool assets
+    #[serde(default)]
+    pub rate_sources: Vec<AssetRateSource>,
+    /// The current redemption rates in the same order as the pool assets
+    #[serde(default)]
+    pub rates: Vec<Decimal256>,
+    /// Contracts notified after swaps and liquidity events
+    #[serde(default)]
+    pub hooks: Vec<PairHook>,
+    /// The maximum relative deviation of the pool price from the block open price allowed after a swap
+    #[serde(default)]
+    pub max_price_deviation: Option<Decimal>,
+    /// The addresses allowed to swap and provide liquidity. Everyone is allowed if not set
+    #[serde(default)]
+    pub trader_allowlist: Option<TraderAllowlist>,
+}
+
+/// This enum stores the options available to start and stop changing a stableswap pool's amplification.
+#[cw_serde]
+pub enum StablePoolUpdateParams {
+    /// Starts ramping the amplification towards `next_amp` which is reached at `next_amp_time`
+    StartChangingAmp {
+        next_amp: u64,
+        next_amp_time: u64,
+    },
+    /// Freezes the amplification at its current value
+    StopChangingAmp {},
+    /// Enables the sharing of swap fees with an external party.
+    EnableFeeShare {
+        /// The fee shared with the fee_share_address
+        fee_share_bps: u16,
+        /// The fee_share_bps is sent to this address on every swap
+        fee_share_address: String,
+    },
+    DisableFeeShare,
+    /// Replaces the list of hooks notified after swaps and liquidity events.
+    SetHooks {
+        hooks: Vec<PairHook>,
+    },
+    /// Rejects swaps moving the pool price by more than `max_deviation` relative to
+    /// the price at the start of the block.
+    /// The deviation must be within [`PRICE_DEVIATION_LIMITS`]
+    EnablePriceGuard {
+        max_deviation: Decimal,
+    },
+    /// Removes the intra-block price deviation limit.
+    DisablePriceGuard,
+    /// Restricts swaps and liquidity provision to the allowlisted traders.
+    /// Replaces the current allowlist
+    SetTraderAllowlist(TraderAllowlist),
+    /// Allows everyone to swap and provide liquidity.
+    RemoveTraderAllowlist,
+}
+
+/// This structure holds weighted pool parameters.
+#[cw_serde]
+pub struct WeightedPoolParams {
+    /// Asset weights in the same order as the pool assets. They are normalized so that their sum is 1
+    pub weights: Vec<Decimal>,
+    /// The contract owner
+    pub owner: Option<String>,
+}
+
+/// This structure stores a weighted pool's configuration.
+#[cw_serde]
+pub struct WeightedPoolConfig {
+    /// Normalized weights of the pool assets
+    pub weights: Vec<(AssetInfo, Decimal)>,
+    /// The config for swap fee sharing
+    pub fee_share: Option<FeeShareConfig>,
+}
+
+#[cw_serde]
+pub enum WeightedPoolUpdateParams {
+    /// Enables the sharing of swap fees with an external party.
+    EnableFeeShare {
+        /// The fee shared with the fee_share_address
+        fee_share_bps: u16,
+        /// The fee_share_bps is sent to this address on every swap
+        fee_share_address: String,
+    },
+    DisableFeeShare,
+}
+
+/// This structure holds liquidity bootstrapping pool parameters.
+#[cw_serde]
+pub struct LbpParams {
+    /// The only address allowed to provide and withdraw liquidity
+    pub owner: String,
+    /// Asset weights at `start_time` in the same order as the pool assets
+    pub start_weights: Vec<Decimal>,
+    /// Asset weights at `end_time` in the same order as the pool assets
+    pub end_weights: Vec<Decimal>,
+    /// The timestamp (in seconds) when swaps are enabled and the weights start changing
+    pub start_time: u64,
+    /// The timestamp (in seconds) when swaps are disabled and the weights stop changing
+    pub end_time: u64,
+}
+
+/// This structure stores a liquidity bootstrapping pool's configuration.
+#[cw_serde]
+pub struct LbpConfig {
+    /// Normalized weights of the pool assets at the current block time
+    pub weights: Vec<(AssetInfo, Decimal)>,
+    /// Normalized weights of the pool assets at `start_time`
+    pub start_weights: Vec<(AssetInfo, Decimal)>,
+    /// Normalized weights of the pool assets at `end_time`
+    pub end_weights: Vec<(AssetInfo, Decimal)>,
+    /// The timestamp (in seconds) when swaps are enabled
+    pub start_time: u64,
+    /// The timestamp (in seconds) when swaps are disabled
+    pub end_time: u64,
 }
 
 pub const INSTANTIATE_TOKEN_REPLY_ID: u64 = 1;
+pub const FLASH_SWAP_REPLY_ID: u64 = 2;
+pub const HOOK_REPLY_ID: u64 = 3;
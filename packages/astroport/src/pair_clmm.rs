@@ -0,0 +1,237 @@
+use cosmwasm_schema::{cw_serde, QueryResponses};
+use cosmwasm_std::{
+    wasm_execute, Addr, Binary, CosmosMsg, Decimal, Decimal256, Int128, StdResult, Uint128,
+};
+use cw20::Cw20ReceiveMsg;
+
+use crate::asset::{Asset, AssetInfo, PairInfo};
+use crate::pair::{ConfigResponse, PoolResponse, ReverseSimulationResponse, SimulationResponse};
+
+/// This structure holds tick-based concentrated liquidity pool parameters.
+#[cw_serde]
+pub struct ClmmParams {
+    /// Only ticks divisible by the tick spacing can be used as position bounds
+    pub tick_spacing: u32,
+    /// The initial price of the first asset denominated in the second one.
+    /// Prices are expressed in the smallest units of the assets
+    pub initial_price: Decimal,
+}
+
+/// This structure stores a tick-based concentrated liquidity pool's configuration.
+#[cw_serde]
+pub struct ClmmConfig {
+    /// Only ticks divisible by the tick spacing can be used as position bounds
+    pub tick_spacing: u32,
+}
+
+/// This structure describes the execute messages available in the contract.
+/// Position NFTs follow the CW721 interface for transfers.
+#[cw_serde]
+pub enum ExecuteMsg {
+    /// Receives a message of type [`Cw20ReceiveMsg`]
+    Receive(Cw20ReceiveMsg),
+    /// Swap performs a swap in the pool
+    Swap {
+        offer_asset: Asset,
+        ask_asset_info: Option<AssetInfo>,
+        belief_price: Option<Decimal>,
+        max_spread: Option<Decimal>,
+        to: Option<String>,
+        /// The block time (in seconds) after which the transaction is rejected
+        deadline: Option<u64>,
+    },
+    /// Adds liquidity to the price range between `tick_lower` and `tick_upper`. Mints a new position
+    /// NFT for the receiver or tops up the receiver's existing position in the same range
+    AddLiquidity {
+        tick_lower: i32,
+        tick_upper: i32,
+        /// The maximum amounts of assets to deposit
+        assets: Vec<Asset>,
+        /// The minimum amount of liquidity to add
+        min_liquidity: Option<Uint128>,
+        /// The receiver of the position. Defaults to the sender
+        receiver: Option<String>,
+        /// The block time (in seconds) after which the transaction is rejected
+        deadline: Option<u64>,
+    },
+    /// Removes liquidity from the position and sends the withdrawn assets together with
+    /// the accrued fees. The position NFT is burned once its liquidity drops to zero
+    RemoveLiquidity {
+        token_id: String,
+        /// The amount of liquidity to remove. Defaults to the whole position
+        liquidity: Option<Uint128>,
+        min_assets_to_receive: Option<Vec<Asset>>,
+        /// The receiver of the assets. Defaults to the sender
+        receiver: Option<String>,
+        /// The block time (in seconds) after which the transaction is rejected
+        deadline: Option<u64>,
+    },
+    /// Sends the fees accrued by the position
+    CollectFees {
+        token_id: String,
+        /// The receiver of the fees. Defaults to the sender
+        receiver: Option<String>,
+    },
+    /// Transfers the position NFT to another address
+    TransferNft { recipient: String, token_id: String },
+    /// Transfers the position NFT to a contract and triggers an action on the receiving contract
+    SendNft {
+        contract: String,
+        token_id: String,
+        msg: Binary,
+    },
+}
+
+impl ExecuteMsg {
+    /// Returns the deadline of swap and liquidity messages.
+    pub fn deadline(&self) -> Option<u64> {
+        match self {
+            ExecuteMsg::Swap { deadline, .. }
+            | ExecuteMsg::AddLiquidity { deadline, .. }
+            | ExecuteMsg::RemoveLiquidity { deadline, .. } => *deadline,
+            _ => None,
+        }
+    }
+}
+
+/// This structure describes the query messages available in the contract.
+#[cw_serde]
+#[derive(QueryResponses)]
+pub enum QueryMsg {
+    /// Returns information about a pair
+    #[returns(PairInfo)]
+    Pair {},
+    /// Returns the pair balances and the liquidity in the current price range
+    #[returns(PoolResponse)]
+    Pool {},
+    /// Returns contract configuration
+    #[returns(ConfigResponse)]
+    Config {},
+    /// Returns information about a swap simulation
+    #[returns(SimulationResponse)]
+    Simulation {
+        offer_asset: Asset,
+        ask_asset_info: Option<AssetInfo>,
+    },
+    /// Returns information about a reverse swap simulation
+    #[returns(ReverseSimulationResponse)]
+    ReverseSimulation {
+        offer_asset_info: Option<AssetInfo>,
+        ask_asset: Asset,
+    },
+    /// Returns the current price, tick and liquidity
+    #[returns(PoolStateResponse)]
+    PoolState {},
+    /// Returns initialized ticks in ascending order
+    #[returns(Vec<TickResponse>)]
+    Ticks {
+        start_after: Option<i32>,
+        limit: Option<u32>,
+    },
+    /// Returns the position represented by the NFT
+    #[returns(PositionResponse)]
+    Position { token_id: String },
+    /// Returns the owner of the position NFT
+    #[returns(OwnerOfResponse)]
+    OwnerOf { token_id: String },
+    /// Returns position NFTs owned by the address
+    #[returns(TokensResponse)]
+    Tokens {
+        owner: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Returns all position NFTs
+    #[returns(TokensResponse)]
+    AllTokens {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Returns the number of position NFTs
+    #[returns(NumTokensResponse)]
+    NumTokens {},
+}
+
+/// This structure holds the current state of the pool.
+#[cw_serde]
+pub struct PoolStateResponse {
+    /// The square root of the current price
+    pub sqrt_price: Decimal256,
+    /// The current price of the first asset denominated in the second one
+    pub price: Decimal256,
+    /// The current tick
+    pub tick: i32,
+    /// The liquidity in the current price range
+    pub liquidity: Uint128,
+}
+
+/// This structure describes an initialized tick.
+#[cw_serde]
+pub struct TickResponse {
+    pub tick: i32,
+    /// The total liquidity of positions using the tick as a bound
+    pub liquidity_gross: Uint128,
+    /// The liquidity added when the price crosses the tick from left to right
+    pub liquidity_net: Int128,
+}
+
+/// This structure describes a liquidity position.
+#[cw_serde]
+pub struct PositionResponse {
+    pub token_id: String,
+    pub owner: Addr,
+    pub tick_lower: i32,
+    pub tick_upper: i32,
+    pub liquidity: Uint128,
+    /// The assets the position liquidity can be withdrawn for at the current price
+    pub assets: Vec<Asset>,
+    /// The fees accrued by the position so far
+    pub fees: Vec<Asset>,
+}
+
+/// This structure is used to return the owner of a position NFT.
+#[cw_serde]
+pub struct OwnerOfResponse {
+    pub owner: String,
+    /// CW721 approvals. Always empty as approvals are not supported
+    pub approvals: Vec<Approval>,
+}
+
+/// This structure describes a CW721 approval.
+#[cw_serde]
+pub struct Approval {
+    pub spender: String,
+}
+
+/// This structure is used to return a list of position NFTs.
+#[cw_serde]
+pub struct TokensResponse {
+    pub tokens: Vec<String>,
+}
+
+/// This structure is used to return the number of position NFTs.
+#[cw_serde]
+pub struct NumTokensResponse {
+    pub count: u64,
+}
+
+/// The message sent to the recipient contract of [`ExecuteMsg::SendNft`].
+#[cw_serde]
+pub struct Cw721ReceiveMsg {
+    pub sender: String,
+    pub token_id: String,
+    pub msg: Binary,
+}
+
+impl Cw721ReceiveMsg {
+    /// Wraps the message in the `receive_nft` variant and builds a [`CosmosMsg`] for the recipient contract.
+    pub fn into_cosmos_msg(self, contract_addr: impl Into<String>) -> StdResult<CosmosMsg> {
+        Ok(wasm_execute(contract_addr, &ReceiverExecuteMsg::ReceiveNft(self), vec![])?.into())
+    }
+}
+
+/// This enum describes the message the recipient contract of [`ExecuteMsg::SendNft`] must handle.
+#[cw_serde]
+pub enum ReceiverExecuteMsg {
+    ReceiveNft(Cw721ReceiveMsg),
+}
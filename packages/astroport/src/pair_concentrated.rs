@@ -1,12 +1,14 @@
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use cosmwasm_std::{Decimal, Decimal256, Uint128};
+use cosmwasm_std::{Decimal, Decimal256, Uint128, Uint64};
 
 use crate::asset::PairInfo;
 use crate::asset::{Asset, AssetInfo};
+use crate::observation::Observation;
 use crate::pair::{
-    ConfigResponse, CumulativePricesResponse, FeeShareConfig, PoolResponse,
-    ReverseSimulationResponse, SimulationResponse,
+    ConfigResponse, CumulativePricesResponse, FeeShareConfig, PairHook, PoolResponse,
+    ReverseSimulationResponse, SimulationResponse, TraderAllowlist,
 };
+use crate::stats::StatsResponse;
 
 /// This structure holds concentrated pool parameters.
 #[cw_serde]
@@ -33,6 +35,10 @@ pub struct ConcentratedPoolParams {
     pub ma_half_time: u64,
     /// The config for swap fee sharing
     pub fee_share: Option<FeeShareConfig>,
+    /// Initial price scales of the 3rd and subsequent assets: 1 x\[0] = price_scale * x\[i].
+    /// Must contain a value for each asset after the second one
+    #[serde(default)]
+    pub extra_price_scales: Vec<Decimal>,
 }
 
 /// This structure holds concentrated pool parameters which can be changed immediately.
@@ -70,7 +76,37 @@ pub enum ConcentratedPoolUpdateParams {
         /// The fee_share_bps is sent to this address on every swap
         fee_share_address: String,
     },
+    /// Enables the sharing of swap fees with several parties.
+    /// Replaces the current fee share config.
+    EnableFeeShareRecipients {
+        /// Recipient addresses along with their fee shares in bps.
+        /// The total share must not exceed [`MAX_FEE_SHARE_BPS`](crate::pair::MAX_FEE_SHARE_BPS)
+        recipients: Vec<(String, u16)>,
+    },
     DisableFeeShare,
+    /// Keeps the maker fee in the pool instead of sending it on every swap.
+    /// The accrued fee is minted as LP tokens to the fee address on the next liquidity event
+    /// or on [`CollectProtocolFees`](crate::pair::ExecuteMsg::CollectProtocolFees).
+    EnableProtocolFeeAccrual,
+    /// Collects the accrued protocol fee and returns to sending the maker fee on every swap.
+    DisableProtocolFeeAccrual,
+    /// Replaces the list of hooks notified after swaps and liquidity events.
+    SetHooks {
+        hooks: Vec<PairHook>,
+    },
+    /// Rejects swaps moving the pool price by more than `max_deviation` relative to
+    /// the price at the start of the block.
+    /// The deviation must be within [`PRICE_DEVIATION_LIMITS`](crate::pair::PRICE_DEVIATION_LIMITS)
+    EnablePriceGuard {
+        max_deviation: Decimal,
+    },
+    /// Removes the intra-block price deviation limit.
+    DisablePriceGuard,
+    /// Restricts swaps and liquidity provision to the allowlisted traders.
+    /// Replaces the current allowlist
+    SetTraderAllowlist(TraderAllowlist),
+    /// Allows everyone to swap and provide liquidity.
+    RemoveTraderAllowlist,
 }
 
 /// This structure stores a CL pool's configuration.
@@ -98,6 +134,22 @@ pub struct ConcentratedPoolConfig {
     pub ma_half_time: u64,
     /// The config for swap fee sharing
     pub fee_share: Option<FeeShareConfig>,
+    /// Price scales of the 3rd and subsequent assets: 1 x\[0] = price_scale * x\[i].
+    /// Empty for 2-asset pools
+    #[serde(default)]
+    pub extra_price_scales: Vec<Decimal>,
+    /// Whether the maker fee accrues in the pool and is minted as LP tokens to the fee address
+    #[serde(default)]
+    pub protocol_fee_accrual: bool,
+    /// Contracts notified after swaps and liquidity events
+    #[serde(default)]
+    pub hooks: Vec<PairHook>,
+    /// The maximum relative deviation of the pool price from the block open price allowed after a swap
+    #[serde(default)]
+    pub max_price_deviation: Option<Decimal>,
+    /// The addresses allowed to swap and provide liquidity. Everyone is allowed if not set
+    #[serde(default)]
+    pub trader_allowlist: Option<TraderAllowlist>,
 }
 
 /// This structure describes the query messages available in the contract.
@@ -143,7 +195,27 @@ pub enum QueryMsg {
         assets: Vec<Asset>,
         slippage_tolerance: Option<Decimal>,
     },
-    /// Returns an estimation of assets received for the given amount of LP tokens
+    /// Returns an estimation of assets received for the given amount of LP tokens.
+    /// If **asset** is specified, returns the amount of the asset received for withdrawing
+    /// all liquidity in it
     #[returns(Vec<Asset>)]
-    SimulateWithdraw { lp_amount: Uint128 },
+    SimulateWithdraw {
+        lp_amount: Uint128,
+        asset: Option<AssetInfo>,
+    },
+    /// Returns the balance of the specified asset that was in the pool just preceding the moment
+    /// of the specified block height creation. Returns `None` if the balance was not recorded yet.
+    #[returns(Option<Uint128>)]
+    AssetBalanceAt {
+        asset_info: AssetInfo,
+        block_height: Uint64,
+    },
+    /// Returns cumulative prices at each moment `seconds_ago` from the current block time.
+    /// Cumulative prices between recorded observations are linearly interpolated.
+    #[returns(Vec<Observation>)]
+    Observe { seconds_ago: Vec<u64> },
+    /// Returns swap volumes and fees accumulated during the given epoch (the current one
+    /// by default) and over the pair lifetime
+    #[returns(StatsResponse)]
+    Stats { epoch: Option<u64> },
 }
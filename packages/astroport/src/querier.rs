@@ -1,3 +1,4 @@
+use cosmwasm_schema::cw_serde;
 use cosmwasm_std::{
     from_json, Addr, CustomQuery, Decimal, QuerierWrapper, StdError, StdResult, Uint128,
 };
@@ -5,7 +6,8 @@ use cw20::{BalanceResponse as Cw20BalanceResponse, Cw20QueryMsg, TokenInfoRespon
 
 use crate::asset::AssetInfo;
 use crate::factory::{
-    Config as FactoryConfig, FeeInfoResponse, PairType, QueryMsg as FactoryQueryMsg,
+    Config as FactoryConfig, FeeInfoResponse, PairType, PauseScope, PauseStatusResponse,
+    QueryMsg as FactoryQueryMsg,
 };
 
 /// Returns a native token's balance for a specific account.
@@ -84,6 +86,26 @@ where
     Ok(res.total_supply)
 }
 
+/// Returns the total supply of a pair's LP token.
+///
+/// * **lp_token** CW20 LP token address or token factory LP denom.
+///
+/// * **native_lp_token** whether the LP token is a token factory denom.
+pub fn query_lp_supply<C>(
+    querier: &QuerierWrapper<C>,
+    lp_token: impl Into<String>,
+    native_lp_token: bool,
+) -> StdResult<Uint128>
+where
+    C: CustomQuery,
+{
+    if native_lp_token {
+        querier.query_supply(lp_token).map(|coin| coin.amount)
+    } else {
+        query_supply(querier, lp_token)
+    }
+}
+
 /// Returns the number of decimals that a token has.
 ///
 /// * **asset_info** is an object of type [`AssetInfo`] and contains the asset details for a specific token.
@@ -147,19 +169,27 @@ pub struct FeeInfo {
     pub maker_fee_rate: Decimal,
 }
 
-/// Returns the fee information for a specific pair type.
+/// Returns the fee information for a specific pair.
 ///
 /// * **pair_type** pair type we query information for.
+///
+/// * **pair_addr** pair address. Fee overrides of the pair take precedence over the pair type fees.
 pub fn query_fee_info<C>(
     querier: &QuerierWrapper<C>,
     factory_contract: impl Into<String>,
     pair_type: PairType,
+    pair_addr: impl Into<String>,
 ) -> StdResult<FeeInfo>
 where
     C: CustomQuery,
 {
-    let res: FeeInfoResponse =
-        querier.query_wasm_smart(factory_contract, &FactoryQueryMsg::FeeInfo { pair_type })?;
+    let res: FeeInfoResponse = querier.query_wasm_smart(
+        factory_contract,
+        &FactoryQueryMsg::FeeInfo {
+            pair_type,
+            pair_addr: Some(pair_addr.into()),
+        },
+    )?;
 
     Ok(FeeInfo {
         fee_address: res.fee_address,
@@ -167,3 +197,74 @@ where
         maker_fee_rate: Decimal::from_ratio(res.maker_fee_bps, 10000u16),
     })
 }
+
+/// Returns the pause status of a pair from the factory contract.
+///
+/// * **pair** is the pair address. Only the global pause is returned if `None`.
+pub fn query_pause_status<C>(
+    querier: &QuerierWrapper<C>,
+    factory_contract: impl Into<String>,
+    pair: Option<String>,
+) -> StdResult<PauseStatusResponse>
+where
+    C: CustomQuery,
+{
+    querier.query_wasm_smart(factory_contract, &FactoryQueryMsg::PauseStatus { pair })
+}
+
+/// Returns an error built by **paused_err** if the `action` is paused in the factory
+/// either globally or for the pair.
+pub fn ensure_not_paused<C, E>(
+    querier: &QuerierWrapper<C>,
+    factory_contract: impl Into<String>,
+    pair: impl Into<String>,
+    action: PauseScope,
+    paused_err: impl FnOnce(PauseScope) -> E,
+) -> Result<(), E>
+where
+    C: CustomQuery,
+    E: From<StdError>,
+{
+    let status = query_pause_status(querier, factory_contract, Some(pair.into()))?;
+    if status.is_paused(action) {
+        return Err(paused_err(action));
+    }
+
+    Ok(())
+}
+
+/// The cw4 group query used to check membership
+#[cw_serde]
+enum Cw4QueryMsg {
+    Member {
+        addr: String,
+        at_height: Option<u64>,
+    },
+}
+
+/// The cw4 group response to [`Cw4QueryMsg::Member`]
+#[cw_serde]
+struct Cw4MemberResponse {
+    weight: Option<u64>,
+}
+
+/// Returns whether the address is a member of the cw4 group contract.
+/// Members with a zero weight are considered members as well.
+pub fn query_cw4_membership<C>(
+    querier: &QuerierWrapper<C>,
+    group_contract: impl Into<String>,
+    addr: impl Into<String>,
+) -> StdResult<bool>
+where
+    C: CustomQuery,
+{
+    let res: Cw4MemberResponse = querier.query_wasm_smart(
+        group_contract,
+        &Cw4QueryMsg::Member {
+            addr: addr.into(),
+            at_height: None,
+        },
+    )?;
+
+    Ok(res.weight.is_some())
+}
@@ -0,0 +1,18 @@
+use cosmwasm_schema::{cw_serde, QueryResponses};
+use cosmwasm_std::Decimal256;
+
+/// This structure describes the query messages a redemption rate provider must implement.
+#[cw_serde]
+#[derive(QueryResponses)]
+pub enum QueryMsg {
+    /// Returns the amount of the underlying asset one unit of the asset can be redeemed for
+    #[returns(ExchangeRateResponse)]
+    ExchangeRate {},
+}
+
+/// This structure holds the redemption rate returned by a rate provider.
+#[cw_serde]
+pub struct ExchangeRateResponse {
+    /// The amount of the underlying asset one unit of the asset can be redeemed for
+    pub rate: Decimal256,
+}
@@ -27,6 +27,8 @@ pub enum ExecuteMsg {
         minimum_receive: Option<Uint128>,
         to: Option<String>,
         max_spread: Option<Decimal>,
+        /// The block time (in seconds) after which the transaction is rejected
+        deadline: Option<u64>,
     },
     /// Internal use
     /// ExecuteSwapOperation executes a single swap operation
@@ -54,6 +56,8 @@ pub enum Cw20HookMsg {
         to: Option<String>,
         /// Max spread
         max_spread: Option<Decimal>,
+        /// The block time (in seconds) after which the transaction is rejected
+        deadline: Option<u64>,
     },
 }
 
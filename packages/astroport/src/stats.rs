@@ -0,0 +1,229 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{StdResult, Storage, Uint128};
+use cw_storage_plus::{Item, Map};
+
+use crate::asset::{Asset, AssetInfo};
+
+/// The length of a statistics epoch in seconds
+pub const STATS_EPOCH_LENGTH: u64 = 86_400;
+
+/// Returns the statistics epoch containing the given timestamp (in seconds).
+pub fn epoch_at(timestamp: u64) -> u64 {
+    timestamp / STATS_EPOCH_LENGTH
+}
+
+/// Swap volume and fees accumulated by a pair for a single asset
+#[cw_serde]
+pub struct AssetStats {
+    /// The asset the amounts below are denominated in
+    pub info: AssetInfo,
+    /// Amount of the asset offered to the pool by traders
+    pub volume_in: Uint128,
+    /// Amount of the asset returned from the pool to traders
+    pub volume_out: Uint128,
+    /// Swap fees left in the pool for liquidity providers
+    pub lp_fee: Uint128,
+    /// Swap fees sent to the maker
+    pub maker_fee: Uint128,
+    /// Swap fees sent to the fee share recipients
+    pub fee_share: Uint128,
+}
+
+impl AssetStats {
+    /// Returns empty statistics for the given asset.
+    pub fn new(info: AssetInfo) -> Self {
+        Self {
+            info,
+            volume_in: Uint128::zero(),
+            volume_out: Uint128::zero(),
+            lp_fee: Uint128::zero(),
+            maker_fee: Uint128::zero(),
+            fee_share: Uint128::zero(),
+        }
+    }
+
+    /// Returns the statistics deltas of a single swap.
+    /// All fees are charged in the ask asset.
+    ///
+    /// * **offer_asset** is the asset offered to the pool.
+    ///
+    /// * **return_asset** is the asset returned to the trader.
+    pub fn swap(
+        offer_asset: &Asset,
+        return_asset: &Asset,
+        lp_fee: Uint128,
+        maker_fee: Uint128,
+        fee_share: Uint128,
+    ) -> [AssetStats; 2] {
+        [
+            AssetStats {
+                volume_in: offer_asset.amount,
+                ..AssetStats::new(offer_asset.info.clone())
+            },
+            AssetStats {
+                volume_out: return_asset.amount,
+                lp_fee,
+                maker_fee,
+                fee_share,
+                ..AssetStats::new(return_asset.info.clone())
+            },
+        ]
+    }
+
+    /// Adds the amounts of **other** to these statistics.
+    pub fn add(&mut self, other: &AssetStats) -> StdResult<()> {
+        self.volume_in = self.volume_in.checked_add(other.volume_in)?;
+        self.volume_out = self.volume_out.checked_add(other.volume_out)?;
+        self.lp_fee = self.lp_fee.checked_add(other.lp_fee)?;
+        self.maker_fee = self.maker_fee.checked_add(other.maker_fee)?;
+        self.fee_share = self.fee_share.checked_add(other.fee_share)?;
+        Ok(())
+    }
+}
+
+/// This structure describes the response of a stats query
+#[cw_serde]
+pub struct StatsResponse {
+    /// The queried epoch
+    pub epoch: u64,
+    /// The timestamp (in seconds) when the epoch starts
+    pub epoch_start: u64,
+    /// The epoch length in seconds
+    pub epoch_length: u64,
+    /// Amounts accumulated during the epoch, one entry per pool asset
+    pub epoch_stats: Vec<AssetStats>,
+    /// Amounts accumulated since the stats were introduced, one entry per pool asset
+    pub lifetime_stats: Vec<AssetStats>,
+}
+
+/// Storage of the lifetime and per-epoch swap statistics of a pair.
+/// Statistics are stored in the same order as [`crate::asset::PairInfo::asset_infos`].
+pub struct PairStats {
+    lifetime: Item<Vec<AssetStats>>,
+    epochs: Map<u64, Vec<AssetStats>>,
+}
+
+impl PairStats {
+    pub const fn new(lifetime_key: &'static str, epochs_key: &'static str) -> Self {
+        Self {
+            lifetime: Item::new(lifetime_key),
+            epochs: Map::new(epochs_key),
+        }
+    }
+
+    /// Adds **deltas** to the lifetime statistics and to the statistics of the epoch
+    /// containing **block_time**. Deltas of assets which are not in the pool are ignored.
+    pub fn record(
+        &self,
+        storage: &mut dyn Storage,
+        asset_infos: &[AssetInfo],
+        block_time: u64,
+        deltas: &[AssetStats],
+    ) -> StdResult<()> {
+        let epoch = epoch_at(block_time);
+
+        let mut lifetime = stats_or_default(self.lifetime.may_load(storage)?, asset_infos);
+        let mut epoch_stats = stats_or_default(self.epochs.may_load(storage, epoch)?, asset_infos);
+
+        for delta in deltas {
+            for stats in lifetime.iter_mut().chain(epoch_stats.iter_mut()) {
+                if stats.info.equal(&delta.info) {
+                    stats.add(delta)?;
+                }
+            }
+        }
+
+        self.lifetime.save(storage, &lifetime)?;
+        self.epochs.save(storage, epoch, &epoch_stats)
+    }
+
+    /// Returns the statistics of the given epoch together with the lifetime statistics.
+    pub fn query(
+        &self,
+        storage: &dyn Storage,
+        asset_infos: &[AssetInfo],
+        epoch: u64,
+    ) -> StdResult<StatsResponse> {
+        Ok(StatsResponse {
+            epoch,
+            epoch_start: epoch.saturating_mul(STATS_EPOCH_LENGTH),
+            epoch_length: STATS_EPOCH_LENGTH,
+            epoch_stats: stats_or_default(self.epochs.may_load(storage, epoch)?, asset_infos),
+            lifetime_stats: stats_or_default(self.lifetime.may_load(storage)?, asset_infos),
+        })
+    }
+}
+
+fn stats_or_default(stats: Option<Vec<AssetStats>>, asset_infos: &[AssetInfo]) -> Vec<AssetStats> {
+    stats.unwrap_or_else(|| asset_infos.iter().cloned().map(AssetStats::new).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::testing::MockStorage;
+
+    use crate::asset::{native_asset_info, AssetInfoExt};
+
+    use super::*;
+
+    const STATS: PairStats = PairStats::new("stats", "epoch_stats");
+
+    #[test]
+    fn record_and_query_stats() {
+        let mut storage = MockStorage::new();
+        let asset_infos = vec![
+            native_asset_info("uusd".to_string()),
+            native_asset_info("uluna".to_string()),
+        ];
+
+        let swap = |offer: u128, ask: u128, lp_fee: u128| {
+            AssetStats::swap(
+                &asset_infos[0].with_balance(offer),
+                &asset_infos[1].with_balance(ask),
+                lp_fee.into(),
+                Uint128::one(),
+                Uint128::zero(),
+            )
+        };
+
+        STATS
+            .record(&mut storage, &asset_infos, 100, &swap(1000, 900, 3))
+            .unwrap();
+        STATS
+            .record(&mut storage, &asset_infos, 200, &swap(500, 450, 2))
+            .unwrap();
+        STATS
+            .record(
+                &mut storage,
+                &asset_infos,
+                STATS_EPOCH_LENGTH + 1,
+                &swap(100, 90, 1),
+            )
+            .unwrap();
+
+        let first = STATS.query(&storage, &asset_infos, 0).unwrap();
+        assert_eq!(first.epoch_start, 0);
+        assert_eq!(first.epoch_stats[0].volume_in.u128(), 1500);
+        assert_eq!(first.epoch_stats[1].volume_out.u128(), 1350);
+        assert_eq!(first.epoch_stats[1].lp_fee.u128(), 5);
+        assert_eq!(first.epoch_stats[1].maker_fee.u128(), 2);
+        assert_eq!(first.lifetime_stats[0].volume_in.u128(), 1600);
+        assert_eq!(first.lifetime_stats[1].lp_fee.u128(), 6);
+
+        let second = STATS.query(&storage, &asset_infos, 1).unwrap();
+        assert_eq!(second.epoch_start, STATS_EPOCH_LENGTH);
+        assert_eq!(second.epoch_stats[0].volume_in.u128(), 100);
+        assert_eq!(second.epoch_stats[1].lp_fee.u128(), 1);
+
+        // Epochs without swaps return zeroed statistics
+        let empty = STATS.query(&storage, &asset_infos, 5).unwrap();
+        assert_eq!(
+            empty.epoch_stats,
+            asset_infos
+                .iter()
+                .cloned()
+                .map(AssetStats::new)
+                .collect::<Vec<_>>()
+        );
+    }
+}
@@ -0,0 +1,176 @@
+use cosmwasm_std::{AnyMsg, BankMsg, Binary, Coin, CosmosMsg};
+use prost::Message;
+
+/// The subdenom used by pairs for their LP token
+pub const LP_SUBDENOM: &str = "astroport/share";
+
+pub const MSG_CREATE_DENOM_TYPE_URL: &str = "/osmosis.tokenfactory.v1beta1.MsgCreateDenom";
+pub const MSG_MINT_TYPE_URL: &str = "/osmosis.tokenfactory.v1beta1.MsgMint";
+pub const MSG_BURN_TYPE_URL: &str = "/osmosis.tokenfactory.v1beta1.MsgBurn";
+
+/// Protobuf representation of a [`Coin`] used in token factory messages.
+#[derive(Clone, PartialEq, Message)]
+pub struct ProtoCoin {
+    #[prost(string, tag = "1")]
+    pub denom: String,
+    #[prost(string, tag = "2")]
+    pub amount: String,
+}
+
+impl From<Coin> for ProtoCoin {
+    fn from(coin: Coin) -> Self {
+        Self {
+            denom: coin.denom,
+            amount: coin.amount.to_string(),
+        }
+    }
+}
+
+/// Creates a new denom `factory/{sender}/{subdenom}` administrated by the sender.
+#[derive(Clone, PartialEq, Message)]
+pub struct MsgCreateDenom {
+    #[prost(string, tag = "1")]
+    pub sender: String,
+    #[prost(string, tag = "2")]
+    pub subdenom: String,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct MsgCreateDenomResponse {
+    #[prost(string, tag = "1")]
+    pub new_token_denom: String,
+}
+
+/// Mints tokens of a denom administrated by the sender.
+#[derive(Clone, PartialEq, Message)]
+pub struct MsgMint {
+    #[prost(string, tag = "1")]
+    pub sender: String,
+    #[prost(message, optional, tag = "2")]
+    pub amount: Option<ProtoCoin>,
+    #[prost(string, tag = "3")]
+    pub mint_to_address: String,
+}
+
+/// Burns tokens of a denom administrated by the sender.
+#[derive(Clone, PartialEq, Message)]
+pub struct MsgBurn {
+    #[prost(string, tag = "1")]
+    pub sender: String,
+    #[prost(message, optional, tag = "2")]
+    pub amount: Option<ProtoCoin>,
+    #[prost(string, tag = "3")]
+    pub burn_from_address: String,
+}
+
+fn any_msg<T>(type_url: &str, msg: impl Message) -> CosmosMsg<T> {
+    CosmosMsg::Any(AnyMsg {
+        type_url: type_url.to_string(),
+        value: Binary::new(msg.encode_to_vec()),
+    })
+}
+
+/// Returns the full token factory denom created by **creator** for the given **subdenom**.
+pub fn tf_denom(creator: impl Into<String>, subdenom: &str) -> String {
+    format!("factory/{}/{subdenom}", creator.into())
+}
+
+/// Returns a message creating the `factory/{sender}/{subdenom}` denom.
+pub fn tf_create_denom_msg<T>(
+    sender: impl Into<String>,
+    subdenom: impl Into<String>,
+) -> CosmosMsg<T> {
+    any_msg(
+        MSG_CREATE_DENOM_TYPE_URL,
+        MsgCreateDenom {
+            sender: sender.into(),
+            subdenom: subdenom.into(),
+        },
+    )
+}
+
+/// Returns messages minting **coin** to **sender** and transferring it to **receiver**
+/// if they differ. Minting directly to a third party is not supported by every token factory
+/// implementation thus minted tokens always go through the denom admin.
+pub fn tf_mint_msg<T>(
+    sender: impl Into<String>,
+    coin: Coin,
+    receiver: impl Into<String>,
+) -> Vec<CosmosMsg<T>> {
+    let sender = sender.into();
+    let receiver = receiver.into();
+
+    let mut messages = vec![any_msg(
+        MSG_MINT_TYPE_URL,
+        MsgMint {
+            sender: sender.clone(),
+            amount: Some(coin.clone().into()),
+            mint_to_address: sender.clone(),
+        },
+    )];
+
+    if sender != receiver {
+        messages.push(
+            BankMsg::Send {
+                to_address: receiver,
+                amount: vec![coin],
+            }
+            .into(),
+        );
+    }
+
+    messages
+}
+
+/// Returns a message burning **coin** from the **sender** balance.
+pub fn tf_burn_msg<T>(sender: impl Into<String>, coin: Coin) -> CosmosMsg<T> {
+    let sender = sender.into();
+    any_msg(
+        MSG_BURN_TYPE_URL,
+        MsgBurn {
+            sender: sender.clone(),
+            amount: Some(coin.into()),
+            burn_from_address: sender,
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::{coin, Empty};
+
+    use super::*;
+
+    #[test]
+    fn test_mint_msg() {
+        let messages = tf_mint_msg::<Empty>("pair", coin(100, "factory/pair/lp"), "pair");
+        assert_eq!(messages.len(), 1);
+
+        let messages = tf_mint_msg::<Empty>("pair", coin(100, "factory/pair/lp"), "user");
+        assert_eq!(messages.len(), 2);
+        match &messages[0] {
+            CosmosMsg::Any(AnyMsg { type_url, value }) => {
+                assert_eq!(type_url, MSG_MINT_TYPE_URL);
+                let msg = MsgMint::decode(value.as_slice()).unwrap();
+                assert_eq!(msg.mint_to_address, "pair");
+                assert_eq!(msg.amount.unwrap().amount, "100");
+            }
+            _ => panic!("Unexpected message"),
+        }
+        assert_eq!(
+            messages[1],
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "user".to_string(),
+                amount: vec![coin(100, "factory/pair/lp")]
+            })
+        );
+    }
+
+    #[test]
+    fn test_tf_denom() {
+        assert_eq!(
+            tf_denom("pair", LP_SUBDENOM),
+            "factory/pair/astroport/share"
+        );
+    }
+}
@@ -9,8 +9,13 @@ pub const MAX_ALLOWED_SLIPPAGE: Decimal256 = Decimal256::raw(500000000000000000)
 /// Percentage of 1st pool volume used as offer amount to forecast last price (0.01% or 0.0001).
 pub const OFFER_PERCENT: Decimal256 = Decimal256::raw(100000000000000);
 
+/// The minimum amount of assets a concentrated pool can hold
+pub const MIN_ASSETS: usize = 2;
+/// The maximum amount of assets a concentrated pool can hold
+pub const MAX_ASSETS: usize = 3;
+
 /// ## Internal constants
-/// Number of coins. (2.0)
+/// Number of coins in a 2-asset pool. (2.0)
 pub const N: Decimal256 = Decimal256::raw(2000000000000000000);
 /// Defines fee tolerance. If k coefficient is small enough then k = 0. (0.001)
 pub const FEE_TOL: Decimal256 = Decimal256::raw(1000000000000000);
@@ -32,6 +32,9 @@ pub enum PclError {
     #[error("{0} failed to converge")]
     ConvergenceFailure(String),
 
+    #[error("Invalid number of assets. This pair supports from {min} to {max} assets")]
+    InvalidNumberOfAssets { min: usize, max: usize },
+
     #[error("Doubling assets in asset infos")]
     DoublingAssets {},
 
@@ -7,7 +7,28 @@ use crate::error::PclError;
 const PADDING: SignedDecimal256 = SignedDecimal256::raw(1e36 as i128);
 
 pub fn geometric_mean(x: &[Decimal256]) -> Decimal256 {
-    (x[0] * x[1]).sqrt()
+    if x.len() == 2 {
+        return (x[0] * x[1]).sqrt();
+    }
+
+    // N-th root of the product using Newton's method.
+    // The arithmetic mean is not less than the geometric one thus the iterations converge from above.
+    let n = x.len() as u32;
+    let n_dec = Decimal256::from_ratio(n, 1u8);
+    let prod = x.iter().fold(Decimal256::one(), |acc, val| acc * val);
+    let mut root = x.iter().sum::<Decimal256>() / n_dec;
+    for _ in 0..MAX_ITER {
+        if root.is_zero() {
+            break;
+        }
+        let next = (root * (n_dec - Decimal256::one()) + prod / root.pow(n - 1)) / n_dec;
+        if next >= root {
+            break;
+        }
+        root = next;
+    }
+
+    root
 }
 
 pub(crate) fn f(
@@ -61,7 +82,8 @@ pub(crate) fn newton_d(
     a: Decimal256,
     gamma: Decimal256,
 ) -> Result<Decimal256, PclError> {
-    let mut d_prev: SignedDecimal256 = (N * geometric_mean(x)).try_into()?;
+    let n = Decimal256::from_ratio(x.len() as u128, 1u8);
+    let mut d_prev: SignedDecimal256 = (n * geometric_mean(x)).try_into()?;
 
     let x = x
         .iter()
@@ -70,6 +92,12 @@ pub(crate) fn newton_d(
     let a = SignedDecimal256::try_from(a)?;
     let gamma = SignedDecimal256::try_from(gamma)?;
 
+    let (f, df_dd) = if x.len() == 2 {
+        (f as InvariantFn, df_dd as InvariantFn)
+    } else {
+        (f_multi as InvariantFn, df_dd_multi as InvariantFn)
+    };
+
     for _ in 0..MAX_ITER {
         let d = d_prev - f(d_prev, &x, a, gamma) / df_dd(d_prev, &x, a, gamma);
         if d.abs_diff(d_prev) <= TOL {
@@ -120,7 +148,24 @@ pub(crate) fn newton_y(
         .iter()
         .map(|val| Ok(SignedDecimal256::try_from(*val)?))
         .collect::<Result<Vec<_>, PclError>>()?;
-    let x0 = d.pow(2) / (SignedDecimal256::try_from(N_POW2).unwrap() * x[1 - j]);
+    let (x0, f, df_dx) = if x.len() == 2 {
+        let x0 = d.pow(2) / (SignedDecimal256::try_from(N_POW2).unwrap() * x[1 - j]);
+        (x0, f as InvariantFn, df_dx as DerivativeFn)
+    } else {
+        // y = D^N / (N^N * prod(x_others))
+        let n = x.len() as u32;
+        let n_pow_n = SignedDecimal256::from_ratio(n.pow(n), 1u8);
+        let prod_others = x
+            .iter()
+            .enumerate()
+            .filter(|(ind, _)| *ind != j)
+            .fold(SignedDecimal256::one(), |acc, (_, val)| acc * *val);
+        if prod_others.is_zero() {
+            return Err(PclError::ConvergenceFailure("newton_y".to_string()));
+        }
+        let x0 = d.pow(n) / (n_pow_n * prod_others);
+        (x0, f_multi as InvariantFn, df_dx_multi as DerivativeFn)
+    };
     let mut xi_1 = x0;
     x[j] = x0;
 
@@ -136,6 +181,121 @@ pub(crate) fn newton_y(
     Err(PclError::ConvergenceFailure("newton_y".to_string()))
 }
 
+type InvariantFn = fn(
+    SignedDecimal256,
+    &[SignedDecimal256],
+    SignedDecimal256,
+    SignedDecimal256,
+) -> SignedDecimal256;
+type DerivativeFn = fn(
+    SignedDecimal256,
+    &[SignedDecimal256],
+    SignedDecimal256,
+    SignedDecimal256,
+    usize,
+) -> SignedDecimal256;
+
+/// Intermediate values of the invariant shared by the function and its derivatives
+/// in pools with more than two assets.
+struct MultiInvariant {
+    n: u32,
+    n_dec: SignedDecimal256,
+    sum: SignedDecimal256,
+    prod: SignedDecimal256,
+    /// D^(N-1)
+    d_pow_n_1: SignedDecimal256,
+    /// K0 = prod * N^N / D^N
+    k0: SignedDecimal256,
+    /// K = A * gamma^2 * K0 / (gamma + 1 - K0)^2
+    k: SignedDecimal256,
+    /// dK/dK0 = A * gamma^2 * (gamma + 1 + K0) / (gamma + 1 - K0)^3
+    dk_dk0: SignedDecimal256,
+}
+
+impl MultiInvariant {
+    fn new(
+        d: SignedDecimal256,
+        x: &[SignedDecimal256],
+        a: SignedDecimal256,
+        gamma: SignedDecimal256,
+    ) -> Self {
+        let n = x.len() as u32;
+        let n_dec = SignedDecimal256::from_ratio(n, 1u8);
+        let sum = x
+            .iter()
+            .fold(SignedDecimal256::zero(), |acc, val| acc + *val);
+        let prod = x
+            .iter()
+            .fold(SignedDecimal256::one(), |acc, val| acc * *val);
+        let d_pow_n_1 = d.pow(n - 1);
+
+        let k0 = prod * SignedDecimal256::from_ratio(n.pow(n), 1u8) / (d_pow_n_1 * d);
+        let gamma_one_k0 = gamma + SignedDecimal256::one() - k0;
+        // gamma / (gamma + 1 - K0) is close to 1 which keeps the precision of small gamma values
+        let gamma_ratio = gamma / gamma_one_k0;
+        let k = a * k0 * gamma_ratio * gamma_ratio;
+        let dk_dk0 =
+            a * gamma_ratio * gamma_ratio * (gamma + SignedDecimal256::one() + k0) / gamma_one_k0;
+
+        Self {
+            n,
+            n_dec,
+            sum,
+            prod,
+            d_pow_n_1,
+            k0,
+            k,
+            dk_dk0,
+        }
+    }
+}
+
+/// The invariant for pools with more than two assets:
+/// F = K * D^(N-1) * sum + prod - K * D^N - (D/N)^N
+pub(crate) fn f_multi(
+    d: SignedDecimal256,
+    x: &[SignedDecimal256],
+    a: SignedDecimal256,
+    gamma: SignedDecimal256,
+) -> SignedDecimal256 {
+    let inv = MultiInvariant::new(d, x, a, gamma);
+
+    inv.k * inv.d_pow_n_1 * inv.sum + inv.prod
+        - inv.k * inv.d_pow_n_1 * d
+        - (d / inv.n_dec).pow(inv.n)
+}
+
+/// df/dD for pools with more than two assets
+pub(crate) fn df_dd_multi(
+    d: SignedDecimal256,
+    x: &[SignedDecimal256],
+    a: SignedDecimal256,
+    gamma: SignedDecimal256,
+) -> SignedDecimal256 {
+    let inv = MultiInvariant::new(d, x, a, gamma);
+    let n_1 = inv.n_dec - SignedDecimal256::one();
+    let d_pow_n_2 = d.pow(inv.n - 2);
+
+    // dK/dD = -N * K0 / D * dK/dK0
+    inv.dk_dk0 * inv.k0 * inv.n_dec * d_pow_n_2 * (d - inv.sum) + inv.k * n_1 * d_pow_n_2 * inv.sum
+        - inv.k * inv.n_dec * inv.d_pow_n_1
+        - (d / inv.n_dec).pow(inv.n - 1)
+}
+
+/// df/dx for pools with more than two assets
+pub(crate) fn df_dx_multi(
+    d: SignedDecimal256,
+    x: &[SignedDecimal256],
+    a: SignedDecimal256,
+    gamma: SignedDecimal256,
+    i: usize,
+) -> SignedDecimal256 {
+    let inv = MultiInvariant::new(d, x, a, gamma);
+
+    // dK/dx_i = K0 / x_i * dK/dK0
+    (inv.dk_dk0 * inv.k0 * inv.d_pow_n_1 * (inv.sum - d) + inv.prod) / x[i] + inv.k * inv.d_pow_n_1
+}
+
 /// Calculates 0.5^power.
 pub fn half_float_pow(power: Decimal256) -> StdResult<Decimal256> {
     let intpow = power.floor();
@@ -167,6 +327,7 @@ pub fn half_float_pow(power: Decimal256) -> StdResult<Decimal256> {
 }
 
 #[cfg(test)]
+#[allow(clippy::inconsistent_digit_grouping)]
 mod tests {
     use std::fmt::Display;
     use std::str::FromStr;
@@ -291,6 +452,35 @@ mod tests {
         assert_eq!(d.to_string(), "33532826223.999399077170285763")
     }
 
+    #[test]
+    fn test_three_assets() {
+        let xs = [f64_to_dec(9.0), f64_to_dec(3.0), f64_to_dec(1.0)];
+        assert_values(geometric_mean(&xs), 3.0);
+
+        let amp = f64_to_dec(100.0);
+        let gamma = f64_to_dec(0.000145);
+
+        // The invariant of a balanced pool equals the sum of its reserves
+        let xs = [f64_to_dec(1000.0); 3];
+        let d = newton_d(&xs, amp, gamma).unwrap();
+        assert_values(d, 3000.0);
+
+        let y = newton_y(&[xs[0], Decimal256::zero(), xs[2]], amp, gamma, d, 1).unwrap();
+        assert_values(y, 1000.0);
+
+        // Offering one asset returns slightly less of another one
+        let y = newton_y(
+            &[xs[0] + f64_to_dec(10.0), xs[1], Decimal256::zero()],
+            amp,
+            gamma,
+            d,
+            2,
+        )
+        .unwrap();
+        let dy = dec_to_f64(xs[2] - y);
+        assert!(dy < 10.0 && dy > 9.9, "dy {dy}");
+    }
+
     #[test]
     fn test_derivatives() {
         let a_f64 = 3500f64;
@@ -307,7 +497,7 @@ mod tests {
         ];
 
         let der_f64 = crate::math::math_f64::df_dd(d_f64, &[x1, x2], a_f64, gamma_f64);
-        let der = df_dd(d.try_into().unwrap(), &x, a, gamma);
+        let der = df_dd(d, &x, a, gamma);
         assert_values(der, der_f64);
 
         let dx_f64 = crate::math::math_f64::df_dx(d_f64, &[x1, x2], a_f64, gamma_f64, 0);
@@ -2,7 +2,6 @@ use cosmwasm_std::{Decimal256, StdError, StdResult};
 
 pub use math_decimal::half_float_pow;
 
-use crate::consts::N;
 use crate::math::math_decimal::{geometric_mean, newton_d, newton_y};
 use crate::state::AmpGamma;
 
@@ -37,8 +36,12 @@ pub fn calc_y(
 
 /// Get current XCP.
 /// * **d** - internal D invariant.
-/// * **price_scale** - x_0/x_1 exchange rate.
-pub fn get_xcp(d: Decimal256, price_scale: Decimal256) -> Decimal256 {
-    let xs = [d / N, d / (N * price_scale)];
+/// * **price_scales** - x_0/x_i exchange rates of all assets. The 1st one is always 1.
+pub fn get_xcp(d: Decimal256, price_scales: &[Decimal256]) -> Decimal256 {
+    let n = Decimal256::from_ratio(price_scales.len() as u128, 1u8);
+    let xs = price_scales
+        .iter()
+        .map(|price_scale| d / (n * price_scale))
+        .collect::<Vec<_>>();
     geometric_mean(&xs)
 }
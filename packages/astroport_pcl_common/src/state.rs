@@ -9,12 +9,12 @@ use cw_storage_plus::Map;
 
 use astroport::asset::{AssetInfo, PairInfo};
 use astroport::cosmwasm_ext::IntegerToDecimal;
-use astroport::pair::FeeShareConfig;
+use astroport::pair::{FeeShareConfig, PairHook, TraderAllowlist};
 use astroport::pair_concentrated::{PromoteParams, UpdatePoolParams};
 
 use crate::consts::{
     AMP_MAX, AMP_MIN, FEE_GAMMA_MAX, FEE_GAMMA_MIN, FEE_TOL, GAMMA_MAX, GAMMA_MIN, MAX_CHANGE,
-    MAX_FEE, MA_HALF_TIME_LIMITS, MIN_AMP_CHANGING_TIME, MIN_FEE, N_POW2, PRICE_SCALE_DELTA_MAX,
+    MAX_FEE, MA_HALF_TIME_LIMITS, MIN_AMP_CHANGING_TIME, MIN_FEE, PRICE_SCALE_DELTA_MAX,
     PRICE_SCALE_DELTA_MIN, REPEG_PROFIT_THRESHOLD_MAX, REPEG_PROFIT_THRESHOLD_MIN, TWO,
 };
 use crate::error::PclError;
@@ -39,6 +39,24 @@ pub struct Config {
     pub owner: Option<Addr>,
     /// The config for swap fee sharing
     pub fee_share: Option<FeeShareConfig>,
+    /// Whether LP shares are issued as a token factory denom instead of a CW20 token
+    #[serde(default)]
+    pub native_lp_token: bool,
+    /// Whether the maker fee accrues in the pool and is minted as LP tokens to the fee address
+    #[serde(default)]
+    pub protocol_fee_accrual: bool,
+    /// Contracts notified after swaps and liquidity events
+    #[serde(default)]
+    pub hooks: Vec<PairHook>,
+    /// Prices of all assets quoted in the 1st asset at the start of the block at `block_time_last`
+    #[serde(default)]
+    pub block_open_prices: Vec<Decimal256>,
+    /// The maximum relative deviation of the pool price from the block open price allowed after a swap
+    #[serde(default)]
+    pub max_price_deviation: Option<Decimal>,
+    /// The addresses allowed to swap and provide liquidity. Everyone is allowed if not set
+    #[serde(default)]
+    pub trader_allowlist: Option<TraderAllowlist>,
 }
 
 /// This structure stores the pool parameters which may be adjusted via the `update_pool_params`.
@@ -155,8 +173,10 @@ impl PoolParams {
 
     pub fn fee(&self, xp: &[Decimal256]) -> Decimal256 {
         let fee_gamma: Decimal256 = self.fee_gamma.into();
-        let sum = xp[0] + xp[1];
-        let mut k = xp[0] * xp[1] * N_POW2 / sum.pow(2);
+        let n = xp.len() as u32;
+        let sum = xp.iter().sum::<Decimal256>();
+        let prod = xp[1..].iter().fold(xp[0], |acc, x| acc * x);
+        let mut k = prod * Decimal256::from_ratio(n.pow(n), 1u8) / sum.pow(n);
         k = fee_gamma / (fee_gamma + Decimal256::one() - k);
 
         if k <= FEE_TOL {
@@ -204,6 +224,84 @@ pub struct PriceState {
     pub xcp_profit: Decimal256,
     /// Profits due to fees inclusive of realized losses from rebalancing
     pub xcp_profit_real: Decimal256,
+    /// Prices of the 3rd and subsequent assets quoted in the 1st asset. Empty in 2-asset pools.
+    #[serde(default)]
+    pub extra_assets: Vec<AssetPriceState>,
+}
+
+/// Oracle price, last price and price scale of a single asset quoted in the 1st asset.
+#[cw_serde]
+#[derive(Default, Copy)]
+pub struct AssetPriceState {
+    /// Internal oracle price
+    pub oracle_price: Decimal256,
+    /// The last saved price
+    pub last_price: Decimal256,
+    /// Such C that x = C * y where x - 1st asset, y - this asset.
+    pub price_scale: Decimal256,
+}
+
+impl PriceState {
+    /// Creates a new price state with the given initial price scales of the 2nd and subsequent assets.
+    pub fn new(price_scales: &[Decimal256], block_time: u64) -> Self {
+        let mut price_state = Self {
+            last_price_update: block_time,
+            ..Default::default()
+        };
+        let assets = price_scales
+            .iter()
+            .map(|&price_scale| AssetPriceState {
+                oracle_price: price_scale,
+                last_price: price_scale,
+                price_scale,
+            })
+            .collect::<Vec<_>>();
+        price_state.set_assets(&assets);
+
+        price_state
+    }
+
+    /// Returns price states of the 2nd and subsequent assets.
+    pub fn assets(&self) -> Vec<AssetPriceState> {
+        let second = AssetPriceState {
+            oracle_price: self.oracle_price,
+            last_price: self.last_price,
+            price_scale: self.price_scale,
+        };
+
+        std::iter::once(second)
+            .chain(self.extra_assets.iter().copied())
+            .collect()
+    }
+
+    /// Saves price states of the 2nd and subsequent assets.
+    pub fn set_assets(&mut self, assets: &[AssetPriceState]) {
+        self.oracle_price = assets[0].oracle_price;
+        self.last_price = assets[0].last_price;
+        self.price_scale = assets[0].price_scale;
+        self.extra_assets = assets[1..].to_vec();
+    }
+
+    /// Returns price scales of all assets. The 1st asset is the quote one thus its price scale is 1.
+    pub fn price_scales(&self) -> Vec<Decimal256> {
+        self.quoted(|asset| asset.price_scale)
+    }
+
+    /// Returns oracle prices of all assets quoted in the 1st asset.
+    pub fn oracle_prices(&self) -> Vec<Decimal256> {
+        self.quoted(|asset| asset.oracle_price)
+    }
+
+    /// Returns last prices of all assets quoted in the 1st asset.
+    pub fn last_prices(&self) -> Vec<Decimal256> {
+        self.quoted(|asset| asset.last_price)
+    }
+
+    fn quoted(&self, price: impl Fn(&AssetPriceState) -> Decimal256) -> Vec<Decimal256> {
+        std::iter::once(Decimal256::one())
+            .chain(self.assets().iter().map(price))
+            .collect()
+    }
 }
 
 /// Internal structure which stores the pool's state.
@@ -298,22 +396,24 @@ impl PoolState {
     }
 
     /// The function is responsible for repegging mechanism.
-    /// It updates internal oracle price and adjusts price scale.
+    /// It updates internal oracle prices and adjusts price scales.
     ///
     /// * **total_lp** total LP tokens were minted
     /// * **cur_xs** - internal representation of pool volumes
-    /// * **cur_price** - last price happened in the previous action (swap, provide or withdraw)
+    /// * **cur_prices** - last prices of all assets quoted in the 1st asset
+    ///   happened in the previous action (swap, provide or withdraw)
     pub fn update_price(
         &mut self,
         pool_params: &PoolParams,
         env: &Env,
         total_lp: Decimal256,
         cur_xs: &[Decimal256],
-        cur_price: Decimal256,
+        cur_prices: &[Decimal256],
     ) -> StdResult<()> {
         let amp_gamma = self.get_amp_gamma(env);
         let block_time = env.block.time.seconds();
         let price_state = &mut self.price_state;
+        let mut assets = price_state.assets();
 
         if price_state.last_price_update < block_time {
             let arg = Decimal256::from_ratio(
@@ -321,14 +421,19 @@ impl PoolState {
                 pool_params.ma_half_time,
             );
             let alpha = half_float_pow(arg)?;
-            price_state.oracle_price = price_state.last_price * (Decimal256::one() - alpha)
-                + price_state.oracle_price * alpha;
+            for asset in assets.iter_mut() {
+                asset.oracle_price =
+                    asset.last_price * (Decimal256::one() - alpha) + asset.oracle_price * alpha;
+            }
             price_state.last_price_update = block_time;
         }
-        price_state.last_price = cur_price;
+        for (asset, cur_price) in assets.iter_mut().zip(&cur_prices[1..]) {
+            asset.last_price = *cur_price;
+        }
+        price_state.set_assets(&assets);
 
         let cur_d = calc_d(cur_xs, &amp_gamma)?;
-        let xcp = get_xcp(cur_d, price_state.price_scale);
+        let xcp = get_xcp(cur_d, &price_state.price_scales());
 
         if !price_state.xcp_profit_real.is_zero() {
             let xcp_profit_real = xcp / total_lp;
@@ -347,7 +452,20 @@ impl PoolState {
 
         let xcp_profit = price_state.xcp_profit;
 
-        let norm = (price_state.oracle_price / price_state.price_scale).abs_diff(Decimal256::one());
+        // Distance between oracle prices and price scales
+        let norm = if let [asset] = assets.as_slice() {
+            (asset.oracle_price / asset.price_scale).abs_diff(Decimal256::one())
+        } else {
+            assets
+                .iter()
+                .map(|asset| {
+                    (asset.oracle_price / asset.price_scale)
+                        .abs_diff(Decimal256::one())
+                        .pow(2)
+                })
+                .sum::<Decimal256>()
+                .sqrt()
+        };
         let scale_delta = Decimal256::from(pool_params.min_price_scale_delta)
             .max(norm * Decimal256::from_ratio(1u8, 10u8));
 
@@ -356,21 +474,35 @@ impl PoolState {
                 > (xcp_profit - Decimal256::one()) / TWO
                     + Decimal256::from(pool_params.repeg_profit_threshold)
         {
-            let numerator = price_state.price_scale * (norm - scale_delta)
-                + scale_delta * price_state.oracle_price;
-            let price_scale_new = numerator / norm;
-
-            let xs = [
-                cur_xs[0],
-                cur_xs[1] * price_scale_new / price_state.price_scale,
-            ];
+            let new_assets = assets
+                .iter()
+                .map(|asset| {
+                    let numerator =
+                        asset.price_scale * (norm - scale_delta) + scale_delta * asset.oracle_price;
+                    AssetPriceState {
+                        price_scale: numerator / norm,
+                        ..*asset
+                    }
+                })
+                .collect::<Vec<_>>();
+
+            let xs = std::iter::once(cur_xs[0])
+                .chain(
+                    cur_xs[1..]
+                        .iter()
+                        .zip(assets.iter().zip(&new_assets))
+                        .map(|(x, (old, new))| *x * new.price_scale / old.price_scale),
+                )
+                .collect::<Vec<_>>();
             let new_d = calc_d(&xs, &amp_gamma)?;
 
-            let new_xcp = get_xcp(new_d, price_scale_new);
+            let mut new_price_state = price_state.clone();
+            new_price_state.set_assets(&new_assets);
+            let new_xcp = get_xcp(new_d, &new_price_state.price_scales());
             let new_xcp_profit_real = new_xcp / total_lp;
 
             if TWO * new_xcp_profit_real > xcp_profit + Decimal256::one() {
-                price_state.price_scale = price_scale_new;
+                price_state.set_assets(&new_assets);
                 price_state.xcp_profit_real = new_xcp_profit_real;
             };
         }
@@ -381,9 +513,9 @@ impl PoolState {
 
 pub struct Precisions(Vec<(String, u8)>);
 
-impl<'a> Precisions {
+impl Precisions {
     /// Stores map of AssetInfo (as String) -> precision
-    pub const PRECISIONS: Map<&'a str, u8> = Map::new("precisions");
+    pub const PRECISIONS: Map<&str, u8> = Map::new("precisions");
     pub fn new(storage: &dyn Storage) -> StdResult<Self> {
         let items = Self::PRECISIONS
             .range(storage, None, None, Order::Ascending)
@@ -809,6 +941,7 @@ mod test {
                 last_price_update: env.block.time.seconds(),
                 xcp_profit: Decimal256::one(),
                 xcp_profit_real: Decimal256::one(),
+                extra_assets: vec![],
             },
         };
 
@@ -819,7 +952,7 @@ mod test {
         let mut xs = ext_xs.to_vec();
         xs[1] *= pool_state.price_state.price_scale;
         let cur_d = calc_d(&xs, &amp_gamma).unwrap();
-        let total_lp = get_xcp(cur_d, pool_state.price_state.price_scale);
+        let total_lp = get_xcp(cur_d, &pool_state.price_state.price_scales());
 
         let offer_amount = f64_to_dec256(1000_f64);
         let price = swap(
@@ -836,7 +969,7 @@ mod test {
                 &env,
                 total_lp,
                 &to_internal_repr(&ext_xs, pool_state.price_state.price_scale),
-                price,
+                &[Decimal256::one(), price],
             )
             .unwrap();
 
@@ -857,7 +990,7 @@ mod test {
                 &env,
                 total_lp,
                 &to_internal_repr(&ext_xs, pool_state.price_state.price_scale),
-                price,
+                &[Decimal256::one(), price],
             )
             .unwrap();
 
@@ -878,7 +1011,7 @@ mod test {
                 &env,
                 total_lp,
                 &to_internal_repr(&ext_xs, pool_state.price_state.price_scale),
-                price,
+                &[Decimal256::one(), price],
             )
             .unwrap();
 
@@ -900,7 +1033,7 @@ mod test {
                 &env,
                 total_lp,
                 &to_internal_repr(&ext_xs, pool_state.price_state.price_scale),
-                price,
+                &[Decimal256::one(), price],
             )
             .unwrap();
 
@@ -922,7 +1055,7 @@ mod test {
                 &env,
                 total_lp,
                 &to_internal_repr(&ext_xs, pool_state.price_state.price_scale),
-                price,
+                &[Decimal256::one(), price],
             )
             .unwrap();
 
@@ -944,7 +1077,7 @@ mod test {
                 &env,
                 total_lp,
                 &to_internal_repr(&ext_xs, pool_state.price_state.price_scale),
-                price,
+                &[Decimal256::one(), price],
             )
             .unwrap();
     }
@@ -1,5 +1,5 @@
 use cosmwasm_std::{
-    to_json_binary, wasm_execute, Addr, Api, CosmosMsg, CustomMsg, CustomQuery, Decimal,
+    coin, to_json_binary, wasm_execute, Addr, Api, CosmosMsg, CustomMsg, CustomQuery, Decimal,
     Decimal256, Env, Fraction, QuerierWrapper, StdError, StdResult, Uint128,
 };
 use cw20::Cw20ExecuteMsg;
@@ -9,16 +9,26 @@ use astroport::asset::{Asset, AssetInfo, DecimalAsset};
 use astroport::cosmwasm_ext::{DecMul, DecimalToInteger};
 use astroport::incentives;
 use astroport::querier::query_factory_config;
+use astroport::token_factory::tf_mint_msg;
 
 use crate::consts::{
-    DEFAULT_SLIPPAGE, MAX_ALLOWED_SLIPPAGE, N, OFFER_PERCENT, TWAP_PRECISION_DEC, TWO,
+    DEFAULT_SLIPPAGE, MAX_ALLOWED_SLIPPAGE, MAX_ASSETS, MIN_ASSETS, OFFER_PERCENT,
+    TWAP_PRECISION_DEC,
 };
 use crate::error::PclError;
 use crate::state::{Config, PoolParams, PriceState};
-use crate::{calc_d, calc_y};
+use crate::{calc_d, calc_y, get_xcp};
 
 /// Helper function to check the given asset infos are valid.
+/// The amount of assets must be within [`MIN_ASSETS`]..=[`MAX_ASSETS`].
 pub fn check_asset_infos(api: &dyn Api, asset_infos: &[AssetInfo]) -> Result<(), PclError> {
+    if !(MIN_ASSETS..=MAX_ASSETS).contains(&asset_infos.len()) {
+        return Err(PclError::InvalidNumberOfAssets {
+            min: MIN_ASSETS,
+            max: MAX_ASSETS,
+        });
+    }
+
     if !asset_infos.iter().all_unique() {
         return Err(PclError::DoublingAssets {});
     }
@@ -69,6 +79,32 @@ where
     C: CustomQuery,
     T: CustomMsg,
 {
+    if config.native_lp_token {
+        let coin = coin(amount.into(), &config.pair_info.liquidity_token);
+
+        if !auto_stake {
+            return Ok(tf_mint_msg(contract_address, coin, recipient));
+        }
+
+        let incentives_addr = query_factory_config(&querier, &config.factory_addr)?
+            .incentives_address
+            .ok_or(PclError::AutoStakeError {})?;
+
+        let mut messages = tf_mint_msg(contract_address, coin.clone(), contract_address);
+        messages.push(
+            wasm_execute(
+                incentives_addr,
+                &incentives::ExecuteMsg::Deposit {
+                    recipient: Some(recipient.to_string()),
+                },
+                vec![coin],
+            )?
+            .into(),
+        );
+
+        return Ok(messages);
+    }
+
     // If no auto-stake - just mint to recipient
     if !auto_stake {
         return Ok(vec![wasm_execute(
@@ -206,39 +242,71 @@ pub struct SwapResult {
 }
 
 impl SwapResult {
-    /// Calculates **last price** for PCL repeg algo
-    pub fn calc_last_price(&self, offer_amount: Decimal256, offer_ind: usize) -> Decimal256 {
+    /// Calculates **last prices** for PCL repeg algo.
+    /// Prices are quoted in the 1st asset thus a swap between the other assets
+    /// is priced via the last price of the offer asset.
+    ///
+    /// * **last_prices** - last prices of all assets before the swap.
+    pub fn calc_last_prices(
+        &self,
+        offer_amount: Decimal256,
+        offer_ind: usize,
+        ask_ind: usize,
+        last_prices: &[Decimal256],
+    ) -> Vec<Decimal256> {
+        let ask_amount = self.dy + self.maker_fee + self.share_fee;
+        let mut last_prices = last_prices.to_vec();
         if offer_ind == 0 {
-            offer_amount / (self.dy + self.maker_fee + self.share_fee)
+            last_prices[ask_ind] = offer_amount / ask_amount;
+        } else if ask_ind == 0 {
+            last_prices[offer_ind] = ask_amount / offer_amount;
         } else {
-            (self.dy + self.maker_fee + self.share_fee) / offer_amount
+            last_prices[ask_ind] = last_prices[offer_ind] * offer_amount / ask_amount;
         }
+
+        last_prices
     }
 }
 
-/// Performs swap simulation to calculate a price.
-pub fn calc_last_prices(xs: &[Decimal256], config: &Config, env: &Env) -> StdResult<Decimal256> {
+/// Performs swap simulations to calculate prices of all assets quoted in the 1st asset.
+pub fn calc_last_prices(
+    xs: &[Decimal256],
+    config: &Config,
+    env: &Env,
+) -> StdResult<Vec<Decimal256>> {
+    // Swaps can't be simulated in an empty pool thus the last known prices are used
+    if xs.len() > 2 && xs.iter().any(Decimal256::is_zero) {
+        return Ok(config.pool_state.price_state.last_prices());
+    }
+
     let mut offer_amount = Decimal256::one().min(xs[0] * OFFER_PERCENT);
     if offer_amount.is_zero() {
         offer_amount = Decimal256::raw(1u128);
     }
 
-    let last_price = compute_swap(
-        xs,
-        offer_amount,
-        1,
-        config,
-        env,
-        Decimal256::zero(),
-        Decimal256::zero(),
-    )?
-    .calc_last_price(offer_amount, 0);
-
-    Ok(last_price)
+    let mut last_prices = vec![Decimal256::one(); xs.len()];
+    for ask_ind in 1..xs.len() {
+        let swap_result = compute_swap(
+            xs,
+            offer_amount,
+            0,
+            ask_ind,
+            config,
+            env,
+            Decimal256::zero(),
+            Decimal256::zero(),
+        )?;
+        last_prices = swap_result.calc_last_prices(offer_amount, 0, ask_ind, &last_prices);
+    }
+
+    Ok(last_prices)
 }
 
 /// Accumulate token prices for the assets in the pool.
-pub fn accumulate_prices(env: &Env, config: &mut Config, last_real_price: Decimal256) {
+/// The prices are stored as the block open prices once the block time advances.
+///
+/// * **last_real_prices** - prices of all assets quoted in the 1st asset.
+pub fn accumulate_prices(env: &Env, config: &mut Config, last_real_prices: &[Decimal256]) {
     let block_time = env.block.time.seconds();
     if block_time <= config.block_time_last {
         return;
@@ -246,12 +314,16 @@ pub fn accumulate_prices(env: &Env, config: &mut Config, last_real_price: Decima
 
     let time_elapsed = Uint128::from(block_time - config.block_time_last);
 
-    for (from, _, value) in config.cumulative_prices.iter_mut() {
-        let price = if &config.pair_info.asset_infos[0] == from {
-            last_real_price.inv().unwrap()
-        } else {
-            last_real_price
+    let asset_infos = &config.pair_info.asset_infos;
+    for (from, to, value) in config.cumulative_prices.iter_mut() {
+        let position = |asset_info: &AssetInfo| {
+            asset_infos
+                .iter()
+                .position(|info| info == asset_info)
+                .unwrap()
         };
+        // Amount of the "to" asset for one "from" asset
+        let price = last_real_prices[position(from)] / last_real_prices[position(to)];
         // Price max value = 1e18 bc smallest value in Decimal is 1e-18.
         // Thus highest inverted price is 1/1e-18.
         // (price * twap) max value = 1e24 which fits into Uint128 thus we use unwrap here
@@ -262,43 +334,47 @@ pub fn accumulate_prices(env: &Env, config: &mut Config, last_real_price: Decima
         *value = value.wrapping_add(time_elapsed * price);
     }
 
+    config.block_open_prices = last_real_prices.to_vec();
     config.block_time_last = block_time;
 }
 
 /// Calculate swap result.
+#[allow(clippy::too_many_arguments)]
 pub fn compute_swap(
     xs: &[Decimal256],
     offer_amount: Decimal256,
+    offer_ind: usize,
     ask_ind: usize,
     config: &Config,
     env: &Env,
     maker_fee_share: Decimal256,
     share_fee_share: Decimal256,
 ) -> StdResult<SwapResult> {
-    let offer_ind = 1 ^ ask_ind;
+    let price_state = &config.pool_state.price_state;
+    let price_scales = price_state.price_scales();
 
-    let mut ixs = xs.to_vec();
-    ixs[1] *= config.pool_state.price_state.price_scale;
+    let mut ixs = xs
+        .iter()
+        .zip(&price_scales)
+        .map(|(x, price_scale)| *x * price_scale)
+        .collect_vec();
 
     let amp_gamma = config.pool_state.get_amp_gamma(env);
     let d = calc_d(&ixs, &amp_gamma)?;
 
-    if offer_ind == 1 {
-        ixs[offer_ind] += offer_amount * config.pool_state.price_state.price_scale;
-    } else {
-        ixs[offer_ind] += offer_amount;
-    }
+    ixs[offer_ind] += offer_amount * price_scales[offer_ind];
 
     let new_y = calc_y(&ixs, d, &amp_gamma, ask_ind)?;
     let mut dy = ixs[ask_ind] - new_y;
     ixs[ask_ind] = new_y;
 
-    // Derive spread using oracle price
-    let spread_fee = if ask_ind == 1 {
-        dy /= config.pool_state.price_state.price_scale;
-        (offer_amount / config.pool_state.price_state.oracle_price).saturating_sub(dy)
+    // Derive spread using oracle prices
+    let oracle_prices = price_state.oracle_prices();
+    let spread_fee = if ask_ind == 0 {
+        offer_amount.saturating_sub(dy / oracle_prices[offer_ind])
     } else {
-        offer_amount.saturating_sub(dy / config.pool_state.price_state.oracle_price)
+        dy /= price_scales[ask_ind];
+        (offer_amount * oracle_prices[offer_ind] / oracle_prices[ask_ind]).saturating_sub(dy)
     };
 
     let fee_rate = config.pool_params.fee(&ixs);
@@ -319,19 +395,21 @@ pub fn compute_swap(
 /// Returns an amount of offer assets for a specified amount of ask assets.
 pub fn compute_offer_amount(
     xs: &[Decimal256],
-    mut want_amount: Decimal256,
+    want_amount: Decimal256,
+    offer_ind: usize,
     ask_ind: usize,
     config: &Config,
     env: &Env,
 ) -> StdResult<(Decimal256, Decimal256, Decimal256)> {
-    let offer_ind = 1 ^ ask_ind;
+    let price_scales = config.pool_state.price_state.price_scales();
 
-    if ask_ind == 1 {
-        want_amount *= config.pool_state.price_state.price_scale
-    }
+    let want_amount = want_amount * price_scales[ask_ind];
 
-    let mut ixs = xs.to_vec();
-    ixs[1] *= config.pool_state.price_state.price_scale;
+    let mut ixs = xs
+        .iter()
+        .zip(&price_scales)
+        .map(|(x, price_scale)| *x * price_scale)
+        .collect_vec();
 
     let amp_gamma = config.pool_state.get_amp_gamma(env);
     let d = calc_d(&ixs, &amp_gamma)?;
@@ -341,21 +419,20 @@ pub fn compute_offer_amount(
         * (Decimal256::one() - Decimal256::from(config.pool_params.out_fee))
             .inv()
             .unwrap();
-    let mut fee = before_fee - want_amount;
+    let fee = before_fee - want_amount;
 
     ixs[ask_ind] -= before_fee;
 
     let new_y = calc_y(&ixs, d, &amp_gamma, offer_ind)?;
-    let mut dy = new_y - ixs[offer_ind];
+    let dy = new_y - ixs[offer_ind];
 
-    let mut spread_fee = dy.saturating_sub(before_fee);
-    if offer_ind == 1 {
-        dy /= config.pool_state.price_state.price_scale;
-        spread_fee /= config.pool_state.price_state.price_scale;
-        fee /= config.pool_state.price_state.price_scale;
-    }
+    let spread_fee = dy.saturating_sub(before_fee);
 
-    Ok((dy, spread_fee, fee))
+    Ok((
+        dy / price_scales[offer_ind],
+        spread_fee / price_scales[offer_ind],
+        fee / price_scales[offer_ind],
+    ))
 }
 
 /// Calculate provide fee applied on the amount of LP tokens. Only charged for imbalanced provide.
@@ -366,10 +443,16 @@ pub fn calc_provide_fee(
     xp: &[Decimal256],
     params: &PoolParams,
 ) -> Decimal256 {
-    let sum = deposits[0] + deposits[1];
-    let avg = sum / N;
+    let n = deposits.len() as u128;
+    let sum = deposits.iter().sum::<Decimal256>();
+    let avg = sum / Decimal256::from_ratio(n, 1u8);
+    let diff = deposits
+        .iter()
+        .map(|deposit| deposit.abs_diff(avg))
+        .sum::<Decimal256>();
 
-    deposits[0].abs_diff(avg) * params.fee(xp) / sum
+    // fee * N / (4 * (N - 1)) * sum(|deposit - avg|) / sum
+    diff * params.fee(xp) * Decimal256::from_ratio(n, 4 * (n - 1)) / sum
 }
 
 /// This is an internal function that enforces slippage tolerance for provides. Returns actual slippage.
@@ -386,10 +469,13 @@ pub fn assert_slippage_tolerance(
         return Err(PclError::AllowedSpreadAssertion {});
     }
 
-    let deposit_value = deposits[0] + deposits[1] * price_state.price_scale;
-    let lp_expected = (deposit_value / TWO * deposit_value / (TWO * price_state.price_scale))
-        .sqrt()
-        / price_state.xcp_profit_real;
+    let price_scales = price_state.price_scales();
+    let deposit_value = deposits
+        .iter()
+        .zip(&price_scales)
+        .map(|(deposit, price_scale)| *deposit * price_scale)
+        .sum::<Decimal256>();
+    let lp_expected = get_xcp(deposit_value, &price_scales) / price_state.xcp_profit_real;
     let slippage = lp_expected.saturating_sub(actual_share) / lp_expected;
 
     if slippage > slippage_tolerance {
@@ -1,3 +1,4 @@
 pub mod coins;
 pub mod convert;
 pub mod legacy_mock_api;
+pub mod modules;
@@ -0,0 +1,122 @@
+use anyhow::{bail, ensure};
+use cosmwasm_std::testing::{MockApi, MockStorage};
+use cosmwasm_std::{
+    coins, Addr, AnyMsg, Api, BankMsg, Binary, BlockInfo, CustomMsg, CustomQuery, Empty, Storage,
+};
+use cw_multi_test::{
+    App, AppResponse, BankKeeper, BankSudo, CosmosRouter, DistributionKeeper, FailingModule,
+    GovFailingModule, IbcFailingModule, StakeKeeper, Stargate, WasmKeeper,
+};
+use prost::Message;
+use serde::de::DeserializeOwned;
+
+use astroport::token_factory::{
+    tf_denom, MsgBurn, MsgCreateDenom, MsgCreateDenomResponse, MsgMint, ProtoCoin,
+    MSG_BURN_TYPE_URL, MSG_CREATE_DENOM_TYPE_URL, MSG_MINT_TYPE_URL,
+};
+
+pub type StargateApp = App<
+    BankKeeper,
+    MockApi,
+    MockStorage,
+    FailingModule<Empty, Empty, Empty>,
+    WasmKeeper<Empty, Empty>,
+    StakeKeeper,
+    DistributionKeeper,
+    IbcFailingModule,
+    GovFailingModule,
+    MockStargate,
+>;
+
+/// Stands in for the token factory module. Denoms can be minted and burned only by their creator.
+#[derive(Default)]
+pub struct MockStargate;
+
+impl Stargate for MockStargate {
+    fn execute_any<ExecC, QueryC>(
+        &self,
+        api: &dyn Api,
+        storage: &mut dyn Storage,
+        router: &dyn CosmosRouter<ExecC = ExecC, QueryC = QueryC>,
+        block: &BlockInfo,
+        sender: Addr,
+        msg: AnyMsg,
+    ) -> anyhow::Result<AppResponse>
+    where
+        ExecC: CustomMsg + DeserializeOwned + 'static,
+        QueryC: CustomQuery + DeserializeOwned + 'static,
+    {
+        match msg.type_url.as_str() {
+            MSG_CREATE_DENOM_TYPE_URL => {
+                let MsgCreateDenom {
+                    sender: creator,
+                    subdenom,
+                } = MsgCreateDenom::decode(msg.value.as_slice())?;
+                ensure!(creator == sender.as_str(), "Sender mismatch");
+
+                let new_token_denom = tf_denom(creator, &subdenom);
+                Ok(AppResponse {
+                    events: vec![],
+                    data: Some(Binary::new(
+                        MsgCreateDenomResponse { new_token_denom }.encode_to_vec(),
+                    )),
+                })
+            }
+            MSG_MINT_TYPE_URL => {
+                let MsgMint {
+                    sender: minter,
+                    amount,
+                    mint_to_address,
+                } = MsgMint::decode(msg.value.as_slice())?;
+                ensure!(minter == sender.as_str(), "Sender mismatch");
+
+                let (denom, amount) = parse_coin(&sender, amount)?;
+                router.sudo(
+                    api,
+                    storage,
+                    block,
+                    BankSudo::Mint {
+                        to_address: mint_to_address,
+                        amount: coins(amount, denom),
+                    }
+                    .into(),
+                )
+            }
+            MSG_BURN_TYPE_URL => {
+                let MsgBurn {
+                    sender: burner,
+                    amount,
+                    burn_from_address,
+                } = MsgBurn::decode(msg.value.as_slice())?;
+                ensure!(
+                    burner == sender.as_str() && burn_from_address == sender.as_str(),
+                    "Burning from other accounts is not supported"
+                );
+
+                let (denom, amount) = parse_coin(&sender, amount)?;
+                router.execute(
+                    api,
+                    storage,
+                    block,
+                    sender,
+                    BankMsg::Burn {
+                        amount: coins(amount, denom),
+                    }
+                    .into(),
+                )
+            }
+            _ => bail!("Unexpected any execute: msg={:?} from {}", msg, sender),
+        }
+    }
+}
+
+/// Parses a token factory coin and checks the denom is administrated by the sender.
+fn parse_coin(sender: &Addr, coin: Option<ProtoCoin>) -> anyhow::Result<(String, u128)> {
+    let ProtoCoin { denom, amount } = coin.ok_or_else(|| anyhow::anyhow!("Empty amount"))?;
+    ensure!(
+        denom.starts_with(&format!("factory/{sender}/")),
+        "Denom {denom} is not administrated by {sender}"
+    );
+
+    Ok((denom, amount.parse()?))
+}